@@ -1,21 +1,27 @@
 use std::{
     cell::RefCell,
     ffi::OsString,
+    fmt::Display,
     io::Write,
     net::SocketAddr,
-    sync::{Arc, Mutex, RwLock},
+    sync::{Arc, Mutex, OnceLock, RwLock},
+    time::Instant,
 };
 
 use axum::{
-    Router, middleware,
-    response::IntoResponse,
+    Router,
+    extract::{OriginalUri, Request},
+    middleware,
+    response::{IntoResponse, Response},
     routing::{MethodRouter, Route, get},
 };
+use chrono::Utc;
 use fosk::Db;
-use http::{HeaderMap, HeaderValue, StatusCode, header::CONTENT_TYPE};
+use http::{HeaderMap, HeaderName, HeaderValue, header::CONTENT_TYPE};
+use serde_json::Value;
 use terminal_link::Link;
 use tower::{
-    Layer, ServiceBuilder,
+    Layer, ServiceBuilder, ServiceExt,
     layer::util::{Identity, Stack},
 };
 use tower_http::{
@@ -24,14 +30,39 @@ use tower_http::{
 
 use crate::{
     DEFAULT_FOLDER, DEFAULT_PORT,
-    handlers::{create_collections_routes, create_schema_routes, make_auth_middleware},
+    error_bodies::{ErrorBodies, make_error_body_middleware, not_found_response},
+    handlers::{
+        AllowedMethods, ChangeEvents, CollectionDefaults, CollectionOrder, CsrfGuard, GoldenDiff,
+        HalLinks, LazyFiles, NetworkPartitions, Partition, Partitions, PathMatching,
+        PersistedQueries, ProxyRules, PublicOptions, QuotaTracker, ReadOnlyCollections,
+        ReferenceConstraints, RequestJournal, ResponseCache, ResponseTransforms, Sequences,
+        StateSnapshots, Transactions, UploadRegistry, build_value_router, create_api_docs_routes,
+        create_audit_routes, create_batch_routes, create_cache_routes, create_change_event_routes,
+        create_collections_routes, create_csrf_routes, create_golden_diff_routes,
+        create_network_partition_routes, create_quota_routes, create_request_journal_routes,
+        create_saml_routes, create_schema_routes, create_sequence_routes, create_state_routes,
+        create_upload_cleanup_route, dashboard_collections, id_string, make_auth_middleware,
+        make_csrf_middleware, make_golden_diff_middleware, make_method_middleware,
+        make_network_partition_middleware, make_quota_middleware, make_request_journal_middleware,
+        public_router, with_path,
+    },
+    mqtt::start_mqtt_broker,
     pages::Pages,
+    random::{self, RandomSource, make_random_middleware},
+    response_validation::{
+        ResponseValidator, ValidationMode, load_openapi_contract,
+        make_response_validation_middleware, read_contract, resolve_contract_path,
+    },
     route_builder::{
-        RouteGenerator, RouteRegistrator,
-        config::{Config, ServerConfig},
-        route_manager::RouteManager,
+        RouteGenerator, RouteRegistrator, RouteVersion,
+        config::{Config, PublicConfig, ServerConfig},
+        route_manager::{RouteManager, route_shape},
+    },
+    schedule::{Schedule, make_schedule_middleware},
+    templates::render_value,
+    tls::{
+        ClientCertAcceptor, TlsMode, is_https, resolve_client_auth, resolve_tls_mode, rustls_config,
     },
-    tls::{TlsMode, is_https, resolve_tls_mode, rustls_config},
     upload_configuration::UploadConfiguration,
 };
 
@@ -48,6 +79,8 @@ pub struct GlobalSharedInfo {
 
 /// Prefix reserved for mock-server internal endpoints.
 pub const MOCK_SERVER_ROUTE: &str = "/mock-server";
+/// Page sending requests to one route, opened from the home page.
+pub const PLAYGROUND_ROUTE: &str = "/__playground";
 /// Global authentication metadata populated when auth routes are registered.
 pub static GLOBAL_SHARED_INFO: RwLock<GlobalSharedInfo> = RwLock::new(GlobalSharedInfo {
     jwt_secret: String::new(),
@@ -55,6 +88,53 @@ pub static GLOBAL_SHARED_INFO: RwLock<GlobalSharedInfo> = RwLock::new(GlobalShar
     auth_cookie_name: String::new(),
 });
 
+/// An HTTP route registered on the application router.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredRoute {
+    /// HTTP method, as passed at registration.
+    pub method: String,
+    /// Route path.
+    pub path: String,
+    /// Whether the route requires auth middleware.
+    pub is_protected: bool,
+}
+
+/// A route refused because the router already serves an overlapping one.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RouteOverlap {
+    /// Route that was refused.
+    pub route: RegisteredRoute,
+    /// Route registered earlier that it overlaps.
+    pub earlier: RegisteredRoute,
+}
+
+impl Display for RouteOverlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} overlaps {} {}, registered earlier",
+            self.route.method, self.route.path, self.earlier.method, self.earlier.path
+        )
+    }
+}
+
+/// Joins the descriptions of overlapping routes.
+pub(crate) fn describe_overlaps(overlaps: &[RouteOverlap]) -> String {
+    overlaps
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// A route answering with a fixed JSON body, added in code instead of by a mock file.
+#[derive(Clone)]
+struct CodeRoute {
+    method: String,
+    path: String,
+    router: MethodRouter,
+}
+
 /// Runtime application state and Axum router builder.
 pub struct App {
     /// Router under construction.
@@ -64,8 +144,68 @@ pub struct App {
     uploads_configurations: Vec<UploadConfiguration>,
     /// In-memory Fosk database used by REST, auth, collections, and GraphQL routes.
     pub db: Arc<Db>,
+    /// Reference constraints declared by REST collection configs.
+    pub references: Arc<ReferenceConstraints>,
+    /// Default and computed fields declared by REST collection configs.
+    pub collection_defaults: Arc<CollectionDefaults>,
+    /// Routes and references of REST collections, used to build HAL links.
+    pub hal_links: Arc<HalLinks>,
+    /// REST collections declared with `read_only = true`.
+    pub read_only_collections: Arc<ReadOnlyCollections>,
+    /// Renames, mappings, and masking applied to REST and GraphQL response items.
+    pub response_transforms: Arc<ResponseTransforms>,
+    /// Insertion order of REST collection items.
+    pub collection_order: Arc<CollectionOrder>,
+    /// Response contracts loaded from OpenAPI and per-route JSON Schemas.
+    pub response_validator: Arc<ResponseValidator>,
+    /// Templates for the bodies of unmatched routes and error responses.
+    pub error_bodies: Arc<ErrorBodies>,
+    /// Call counters of the response sequences.
+    pub sequences: Arc<Sequences>,
+    /// Rendered responses of routes with a `cache_ttl`.
+    pub response_cache: Arc<ResponseCache>,
+    /// Basic route files whose changes are served without a restart.
+    pub lazy_files: Arc<LazyFiles>,
+    /// Per-tenant and per-session copies of the database, when enabled.
+    pub partitions: Arc<Partitions>,
+    /// Named snapshots of the collections and sequences.
+    pub snapshots: Arc<StateSnapshots>,
+    /// Serializes transactions over the collections.
+    pub transactions: Arc<Transactions>,
+    /// Requests counted per client against the `[quota]` table.
+    pub quota: Arc<QuotaTracker>,
+    /// Writes to collection items, published as they happen.
+    pub change_events: Arc<ChangeEvents>,
+    /// Requests answered by the server, followed by the live tail page.
+    pub request_journal: Arc<RequestJournal>,
+    /// Golden requests recorded by the proxy and the differences from them.
+    pub golden_diff: Arc<GoldenDiff>,
+    /// Outage windows and delay ramps of the `[schedule]` table.
+    pub schedule: Arc<Schedule>,
+    /// Clients cut off from the server through `/mock-server/network`.
+    pub network_partitions: Arc<NetworkPartitions>,
+    /// Queries registered for GraphQL persisted query hashes.
+    pub persisted_queries: Arc<PersistedQueries>,
+    /// Folders requiring CSRF tokens and the tokens issued.
+    pub csrf: Arc<CsrfGuard>,
+    /// Metadata of the files uploaded to each upload folder.
+    pub uploads: Arc<UploadRegistry>,
+    /// Random source of this server, seeded by `[server].seed`.
+    pub random: Arc<RandomSource>,
+    /// The finished router, set once it is built, which `after` calls reach.
+    pub routes: Arc<OnceLock<Router>>,
     /// Effective server configuration.
     pub server_config: Config,
+    /// Suppresses startup messages printed while routes are built.
+    pub quiet: bool,
+    registered_routes: Vec<RegisteredRoute>,
+    code_routes: Vec<CodeRoute>,
+    code_seeds: Vec<(String, Value)>,
+    route_versions: Vec<RouteVersion>,
+    background_tasks: Vec<tokio::task::AbortHandle>,
+    bound_port: Option<u16>,
+    /// Set while `try_make_routes` runs, holding the overlaps it refused.
+    route_overlaps: Option<Vec<RouteOverlap>>,
 }
 
 impl Default for App {
@@ -87,7 +227,40 @@ impl Default for App {
             pages,
             uploads_configurations,
             db,
+            references: Arc::default(),
+            collection_defaults: Arc::default(),
+            hal_links: Arc::default(),
+            read_only_collections: Arc::default(),
+            response_transforms: Arc::default(),
+            collection_order: Arc::default(),
+            response_validator: Arc::default(),
+            error_bodies: Arc::default(),
+            sequences: Arc::default(),
+            response_cache: Arc::default(),
+            lazy_files: Arc::default(),
+            partitions: Arc::default(),
+            snapshots: Arc::default(),
+            transactions: Arc::default(),
+            quota: Arc::default(),
+            change_events: Arc::default(),
+            request_journal: Arc::default(),
+            golden_diff: Arc::default(),
+            schedule: Arc::default(),
+            network_partitions: Arc::default(),
+            persisted_queries: Arc::default(),
+            csrf: Arc::default(),
+            uploads: Arc::default(),
+            random: Arc::default(),
+            routes: Arc::default(),
             server_config,
+            quiet: false,
+            registered_routes: vec![],
+            code_routes: vec![],
+            code_seeds: vec![],
+            route_versions: vec![],
+            background_tasks: vec![],
+            bound_port: None,
+            route_overlaps: None,
         }
     }
 }
@@ -99,24 +272,95 @@ impl App {
         let pages = Arc::new(Mutex::new(Pages::new()));
         let uploads_configurations = vec![];
         let db = Db::new_arc();
+        let validation_mode = ValidationMode::from_config(
+            server_config
+                .server
+                .as_ref()
+                .and_then(|server| server.response_validation.as_deref()),
+        );
         App {
             router,
             pages,
             uploads_configurations,
             db,
+            references: Arc::default(),
+            collection_defaults: Arc::default(),
+            hal_links: Arc::default(),
+            read_only_collections: Arc::default(),
+            response_transforms: Arc::default(),
+            collection_order: Arc::default(),
+            response_validator: Arc::new(ResponseValidator::new(validation_mode)),
+            error_bodies: Arc::default(),
+            sequences: Arc::default(),
+            response_cache: Arc::default(),
+            lazy_files: Arc::default(),
+            partitions: Arc::new(Partitions::from_config(
+                &server_config.server.clone().unwrap_or_default(),
+            )),
+            snapshots: Arc::default(),
+            transactions: Arc::default(),
+            quota: Arc::new(QuotaTracker::from_config(&server_config)),
+            change_events: Arc::new(ChangeEvents::new(
+                server_config
+                    .server
+                    .as_ref()
+                    .and_then(|server| server.audit_log)
+                    .unwrap_or(true),
+            )),
+            request_journal: Arc::default(),
+            golden_diff: Arc::new(GoldenDiff::from_config(&server_config)),
+            schedule: Arc::default(),
+            network_partitions: Arc::default(),
+            persisted_queries: Arc::default(),
+            csrf: Arc::default(),
+            uploads: Arc::default(),
+            random: Arc::default(),
+            routes: Arc::default(),
             server_config,
+            quiet: false,
+            registered_routes: vec![],
+            code_routes: vec![],
+            code_seeds: vec![],
+            route_versions: vec![],
+            background_tasks: vec![],
+            bound_port: None,
+            route_overlaps: None,
+        }
+    }
+
+    /// Returns the database and item order shared by requests without a tenant or session.
+    pub fn shared_partition(&self) -> Partition {
+        Partition {
+            db: Arc::clone(&self.db),
+            collection_order: Arc::clone(&self.collection_order),
         }
     }
 
     /// Returns the configured mock folder, or the default folder when unset.
     pub fn get_folder(&self) -> String {
+        self.get_folders()
+            .into_iter()
+            .next()
+            .unwrap_or(DEFAULT_FOLDER.to_string())
+    }
+
+    /// Returns every mounted mock folder, starting with the main one.
+    pub fn get_folders(&self) -> Vec<String> {
+        self.server_config
+            .mount_configs()
+            .into_iter()
+            .filter_map(|config| config.server.and_then(|server| server.folder))
+            .collect()
+    }
+
+    /// Returns true when basic route files are read on each request instead of
+    /// restarting the server when they change.
+    pub fn reads_files_lazily(&self) -> bool {
         self.server_config
             .server
             .as_ref()
-            .unwrap_or(&ServerConfig::default())
-            .folder
-            .clone()
-            .unwrap_or(DEFAULT_FOLDER.to_string())
+            .and_then(|server| server.lazy_files)
+            .unwrap_or(false)
     }
 
     /// Returns the configured server port, or the default port when unset.
@@ -129,6 +373,31 @@ impl App {
             .unwrap_or(DEFAULT_PORT)
     }
 
+    /// Returns the port the server is listening on, which differs from
+    /// [`App::get_port`] when port `0` was requested.
+    pub fn bound_port(&self) -> Option<u16> {
+        self.bound_port
+    }
+
+    /// Records the port the server listens on and writes it to the configured port file.
+    pub(crate) fn set_bound_port(&mut self, port: u16) {
+        self.bound_port = Some(port);
+
+        let port_file = self
+            .server_config
+            .server
+            .as_ref()
+            .and_then(|server| server.port_file.clone());
+        if let Some(port_file) = port_file
+            && let Err(err) = std::fs::write(&port_file, port.to_string())
+        {
+            eprintln!(
+                "Unable to write the port file {}. Details: {}",
+                port_file, err
+            );
+        }
+    }
+
     /// Stores upload cleanup behavior for a registered upload route.
     pub fn push_uploads_config(&mut self, uploads_path: String, clean_uploads: bool) {
         self.uploads_configurations
@@ -149,6 +418,62 @@ impl App {
         let _old_route = self.router.replace(new_router);
     }
 
+    /// Prints a startup message unless the app is quiet.
+    pub fn log(&self, message: impl Display) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+
+    /// Returns the routes registered with a method so far, in registration order.
+    pub fn registered_routes(&self) -> &[RegisteredRoute] {
+        &self.registered_routes
+    }
+
+    /// Registers the routes of `route`, restoring the router and the registered
+    /// routes when some of them overlap routes registered earlier. The
+    /// overlapping routes are refused before they reach the router.
+    pub(crate) fn try_make_routes(
+        &mut self,
+        route: &impl RouteGenerator,
+    ) -> Result<(), Vec<RouteOverlap>> {
+        let router = self.router.borrow().clone();
+        let registered = self.registered_routes.len();
+        let outer = self.route_overlaps.replace(vec![]);
+        route.make_routes(self);
+        let overlaps = std::mem::replace(&mut self.route_overlaps, outer).unwrap_or_default();
+        if overlaps.is_empty() {
+            return Ok(());
+        }
+        let _ = self.router.replace(router);
+        self.registered_routes.truncate(registered);
+        Err(overlaps)
+    }
+
+    /// Returns the registered route the router would refuse `method path`
+    /// beside: one with the same path and method, or one whose path only
+    /// differs by the names of its parameters.
+    pub(crate) fn overlapping_route(&self, method: &str, path: &str) -> Option<&RegisteredRoute> {
+        let shape = route_shape(path);
+        self.registered_routes.iter().find(|route| {
+            route_shape(&route.path) == shape
+                && (route.path != path || route.method.eq_ignore_ascii_case(method))
+        })
+    }
+
+    /// Runs `task` on the Tokio runtime until the app finishes. Without a
+    /// runtime, as when routes are only listed, the task is not started.
+    pub(crate) fn spawn_background(
+        &mut self,
+        task: impl std::future::Future<Output = ()> + Send + 'static,
+    ) {
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let task = random::scope(Arc::clone(&self.random), task);
+            self.background_tasks
+                .push(runtime.spawn(task).abort_handle());
+        }
+    }
+
     /// Registers an Axum method router and optionally exposes it on the home page.
     pub fn route(
         &mut self,
@@ -157,17 +482,174 @@ impl App {
         method: Option<&str>,
         options: Option<&[String]>,
     ) {
-        let new_router = self.get_router().route(path, router);
+        self.register_route(path, router, method, options, false);
+    }
+
+    fn register_route(
+        &mut self,
+        path: &str,
+        router: MethodRouter<()>,
+        method: Option<&str>,
+        options: Option<&[String]>,
+        is_protected: bool,
+    ) {
+        if let Some(method) = method
+            && self.is_replaced_by_code_route(method, path)
+        {
+            self.log(format!("↪ {} {} is replaced by a code route", method, path));
+            return;
+        }
+
+        let version = self
+            .route_versions
+            .iter()
+            .find(|version| version.contains(path))
+            .cloned();
+        let router = match &version {
+            Some(version) => add_version_headers_layer(router, version.headers()),
+            None => router,
+        };
+
+        if let Some(method) = method
+            && let Some(earlier) = self.overlapping_route(method, path)
+        {
+            let overlap = RouteOverlap {
+                route: RegisteredRoute {
+                    method: method.to_string(),
+                    path: path.to_string(),
+                    is_protected,
+                },
+                earlier: earlier.clone(),
+            };
+            match &mut self.route_overlaps {
+                Some(overlaps) => {
+                    overlaps.push(overlap);
+                    return;
+                }
+                None => panic!("{}", overlap),
+            }
+        }
+
+        let new_router = self.get_router().route(path, router.clone());
 
         self.replace_router(new_router);
 
         if let Some(method) = method {
+            self.registered_routes.push(RegisteredRoute {
+                method: method.to_string(),
+                path: path.to_string(),
+                is_protected,
+            });
+
             self.pages.lock().unwrap().push_link(
                 method.to_string(),
                 path.to_string(),
                 options.unwrap_or(&Vec::<String>::new()),
+                is_protected,
             );
         }
+
+        if let Some(alias_path) = version.and_then(|version| version.alias_path(path)) {
+            self.register_route(&alias_path, router, method, options, is_protected);
+        }
+    }
+
+    /// Records the `vN` folder prefixes whose routes send deprecation headers or
+    /// are also served under the `latest` alias.
+    pub fn push_route_versions(&mut self, versions: &[RouteVersion]) {
+        self.route_versions.extend_from_slice(versions);
+    }
+
+    /// Adds a route answering `method` requests on `path` with a JSON body.
+    ///
+    /// Code routes are registered before the mock folder routes and replace a
+    /// mock folder route with the same method and path. Placeholders in `body`
+    /// are rendered on every request.
+    pub fn json_route(&mut self, method: &str, path: &str, body: Value) -> Result<(), String> {
+        let method = method.to_uppercase();
+        let router =
+            build_value_router(body, &method).ok_or(format!("unknown method `{}`", method))?;
+        if self
+            .code_routes
+            .iter()
+            .any(|code_route| code_route.method == method && code_route.path == path)
+        {
+            return Err(format!(
+                "route {} {} is already defined in code",
+                method, path
+            ));
+        }
+
+        self.code_routes.push(CodeRoute {
+            method,
+            path: path.to_string(),
+            router,
+        });
+        Ok(())
+    }
+
+    /// Replaces the items of a collection once the mock folder has been loaded.
+    ///
+    /// The collection is created when no mock file defines it. `items` must be
+    /// a JSON array; its placeholders are rendered when it is loaded.
+    pub fn seed_collection(&mut self, name: &str, items: Value) -> Result<(), String> {
+        if !items.is_array() {
+            return Err(format!("seed items of `{}` must be a JSON array", name));
+        }
+        self.code_seeds.push((name.to_string(), items));
+        Ok(())
+    }
+
+    fn is_replaced_by_code_route(&self, method: &str, path: &str) -> bool {
+        let method = method.to_uppercase();
+        self.code_routes
+            .iter()
+            .any(|code_route| code_route.method == method && code_route.path == path)
+            && self
+                .registered_routes
+                .iter()
+                .any(|registered| registered.method == method && registered.path == path)
+    }
+
+    fn build_code_routes(&mut self) {
+        for code_route in self.code_routes.clone() {
+            self.register_route(
+                &code_route.path,
+                code_route.router,
+                Some(&code_route.method),
+                None,
+                false,
+            );
+        }
+    }
+
+    fn load_code_seeds(&mut self) {
+        for (name, items) in self.code_seeds.clone() {
+            let collection = self.db.get(&name).unwrap_or_else(|| self.db.create(&name));
+            let id_key = collection
+                .get_config()
+                .map(|config| config.id_key)
+                .unwrap_or_default();
+            let items = self
+                .random
+                .fill_ids(&collection, render_value(&items, Utc::now()));
+            match collection.load_from_json(items, false) {
+                Ok(items) => {
+                    self.collection_order.reset(
+                        &name,
+                        items
+                            .iter()
+                            .filter_map(|item| item.get(&id_key).and_then(id_string)),
+                    );
+                    self.log(format!(
+                        "✔️ Seeded collection {} with {} items from code",
+                        name,
+                        items.len()
+                    ));
+                }
+                Err(err) => eprintln!("Unable to seed collection {}. Details: {}", name, err),
+            }
+        }
     }
 
     /// Wraps a method router with authentication middleware when the route is protected.
@@ -192,33 +674,118 @@ impl App {
     }
 
     fn build_dyn_routes(&mut self) {
-        let dir = self.get_folder();
-        RouteManager::from_dir(&dir, Some(self.server_config.clone())).make_routes(self);
+        for config in self.server_config.mount_configs() {
+            let dir = config
+                .server
+                .as_ref()
+                .and_then(|server| server.folder.clone())
+                .unwrap_or(DEFAULT_FOLDER.to_string());
+            let mut manager = self.stage(&format!("Walked {}", dir), |_| {
+                RouteManager::discover(&dir, Some(config))
+            });
+            self.stage("Parsed collection seeds", |_| manager.load_seeds());
+            self.push_route_versions(&manager.versions);
+            self.csrf.push_scopes(&manager.csrf_scopes);
+            self.stage("Registered routes", |app| manager.make_routes(app));
+        }
+    }
+
+    /// Runs a startup stage and logs how long it took.
+    fn stage<T>(&mut self, name: &str, run: impl FnOnce(&mut Self) -> T) -> T {
+        let started = Instant::now();
+        let value = run(self);
+        self.log(format!("⏱️ {} in {:?}", name, started.elapsed()));
+        value
     }
 
     fn load_schema_files(&mut self) {
-        match crate::schema_files::load_schema_files(&self.db, &self.server_config) {
-            Ok(loaded) => {
+        for config in self.server_config.mount_configs() {
+            match crate::schema_files::load_schema_files(&self.db, &config) {
+                Ok(loaded) => {
+                    for message in loaded {
+                        self.log(message);
+                    }
+                }
+                Err(err) => self.log(format!("Unable to load schema files. Details: {}", err)),
+            }
+        }
+    }
+
+    fn load_error_bodies(&mut self) {
+        match ErrorBodies::load(&self.server_config) {
+            Ok((error_bodies, loaded)) => {
                 for message in loaded {
-                    println!("{}", message);
+                    self.log(message);
                 }
+                self.error_bodies = Arc::new(error_bodies);
             }
-            Err(err) => println!("Unable to load schema files. Details: {}", err),
+            Err(err) => self.log(format!(
+                "Unable to load the error body templates. Details: {}",
+                err
+            )),
+        }
+    }
+
+    fn load_schedule(&mut self) {
+        match Schedule::from_config(&self.server_config) {
+            Ok(schedule) if schedule.is_empty() => {}
+            Ok(schedule) => {
+                self.log(format!("🗓️ Scheduled {} behavior windows", schedule.len()));
+                self.schedule = Arc::new(schedule);
+            }
+            Err(err) => self.log(format!("⚠️ {}", err)),
+        }
+    }
+
+    fn load_openapi_contract(&mut self) {
+        match load_openapi_contract(&self.response_validator, &self.server_config) {
+            Ok(Some(message)) => self.log(message),
+            Ok(None) => {}
+            Err(err) => self.log(format!(
+                "Unable to load the OpenAPI document. Details: {}",
+                err
+            )),
+        }
+    }
+
+    /// Registers a JSON Schema file that responses of a route must match.
+    pub fn push_response_schema(&mut self, method: &str, route: &str, schema_path: &str) {
+        let path = resolve_contract_path(&self.server_config, schema_path);
+        match read_contract(&path) {
+            Ok(schema) => self
+                .response_validator
+                .add_route_schema(method, route, schema),
+            Err(err) => self.log(format!(
+                "Unable to load the response schema. Details: {}",
+                err
+            )),
         }
     }
 
     fn load_collection_files(&mut self) {
-        match crate::collection_files::load_collection_files(&self.db, &self.server_config) {
-            Ok(loaded) => {
-                for message in loaded {
-                    println!("{}", message);
+        let quiet = self.quiet;
+        let log = move |message: String| {
+            if !quiet {
+                println!("{}", message);
+            }
+        };
+        for config in self.server_config.mount_configs() {
+            match crate::collection_files::load_collection_files(&self.db, &config, &log) {
+                Ok(loaded) => {
+                    for message in loaded {
+                        self.log(message);
+                    }
                 }
+                Err(err) => self.log(format!("Unable to load collection files. Details: {}", err)),
             }
-            Err(err) => println!("Unable to load collection files. Details: {}", err),
         }
     }
 
     fn build_home_route(&mut self, route: &str) {
+        let server = self.server_config.server.clone().unwrap_or_default();
+        if server.home_page == Some(false) {
+            return;
+        }
         let pages = Arc::clone(&self.pages);
 
         self.route(
@@ -241,6 +808,25 @@ impl App {
         );
     }
 
+    fn build_playground_route(&mut self) {
+        let pages = Arc::clone(&self.pages);
+        let db = Arc::clone(&self.db);
+        let hal_links = Arc::clone(&self.hal_links);
+
+        self.route(
+            PLAYGROUND_ROUTE,
+            get(|| async move {
+                let collections = dashboard_collections(&db, &hal_links);
+                let body = pages.lock().unwrap().render_playground(&collections);
+                let mut headers = HeaderMap::new();
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+                (headers, body).into_response()
+            }),
+            None,
+            None,
+        );
+    }
+
     fn build_cors_layer<L>(
         &self,
         service_builder: ServiceBuilder<L>,
@@ -270,18 +856,100 @@ impl App {
 
         let service_builder = service_builder.layer(NormalizePathLayer::trim_trailing_slash());
 
-        let new_router = self.get_router().layer(service_builder);
+        let mut new_router = self.get_router();
+        if self.csrf.is_active() {
+            new_router = new_router.layer(middleware::from_fn(make_csrf_middleware(&self.csrf)));
+        }
+        if self.error_bodies.is_active() {
+            new_router = new_router.layer(middleware::from_fn(make_error_body_middleware(
+                &self.error_bodies,
+            )));
+        }
+        if self.response_validator.is_active() {
+            new_router = new_router.layer(middleware::from_fn(
+                make_response_validation_middleware(&self.response_validator),
+            ));
+        }
+        if self.quota.is_active() {
+            new_router = new_router.layer(middleware::from_fn(make_quota_middleware(&self.quota)));
+        }
+        new_router = new_router.layer(middleware::from_fn(make_network_partition_middleware(
+            &self.network_partitions,
+        )));
+        if !self.schedule.is_empty() {
+            new_router = new_router.layer(middleware::from_fn(make_schedule_middleware(
+                &self.schedule,
+            )));
+        }
+        if self.golden_diff.is_active() {
+            new_router = new_router.layer(middleware::from_fn(make_golden_diff_middleware(
+                &self.golden_diff,
+            )));
+        }
+        // Outermost of the route layers, so latency covers the others.
+        new_router = new_router.layer(middleware::from_fn(make_request_journal_middleware(
+            &self.request_journal,
+        )));
+        // Outside the CORS layer, which answers every OPTIONS request itself.
+        let allowed = Arc::new(AllowedMethods::from_routes(&self.registered_routes));
+        let new_router = new_router
+            .layer(service_builder)
+            .layer(middleware::from_fn(make_method_middleware(&allowed)))
+            .layer(middleware::from_fn(make_random_middleware(&self.random)));
 
         self.replace_router(new_router);
     }
 
-    fn build_fallback(&mut self) {
-        let new_router = self.get_router().fallback(Self::handler_404);
-        self.replace_router(new_router);
-    }
+    pub(crate) fn build_fallback(&mut self) {
+        let allowed = Arc::new(AllowedMethods::from_routes(&self.registered_routes));
+        let error_bodies = Arc::clone(&self.error_bodies);
+        let matching =
+            PathMatching::from_config(&self.server_config.server.clone().unwrap_or_default());
+        let proxy = Arc::new(
+            ProxyRules::from_config(&self.server_config).unwrap_or_else(|err| {
+                self.log(format!("⚠️ {}", err));
+                ProxyRules::default()
+            }),
+        );
+        let routes = self.get_router();
+        let new_router = routes.clone().fallback(move |request: Request| {
+            let allowed = Arc::clone(&allowed);
+            let error_bodies = Arc::clone(&error_bodies);
+            let proxy = Arc::clone(&proxy);
+            let routes = routes.clone();
+            async move {
+                // The path layer has already trimmed a trailing slash from `uri`.
+                let method = request.method().clone();
+                let uri = request
+                    .extensions()
+                    .get::<OriginalUri>()
+                    .map(|original| original.0.clone())
+                    .unwrap_or_else(|| request.uri().clone());
+                // Requests reaching a route once normalized are routed again.
+                if let Some(uri) = allowed
+                    .normalized_path(uri.path(), matching)
+                    .and_then(|path| with_path(&uri, &path))
+                {
+                    let (mut parts, body) = request.into_parts();
+                    parts.uri = uri;
+                    return routes
+                        .oneshot(Request::from_parts(parts, body))
+                        .await
+                        .unwrap_or_else(|err| match err {});
+                }
+
+                // Requests under a proxy prefix that no mock route answers
+                // are forwarded to the backend.
+                if let Ok(response) = proxy.forward(&uri, request).await {
+                    return response;
+                }
 
-    async fn handler_404() -> impl IntoResponse {
-        (StatusCode::NOT_FOUND, "nothing to see here")
+                allowed
+                    .method_not_allowed(&method, uri.path())
+                    .unwrap_or_else(|| not_found_response(&error_bodies, &method, uri.path()))
+            }
+        });
+        self.replace_router(new_router);
     }
 
     /// Registers a public static directory using the legacy filename convention.
@@ -301,10 +969,18 @@ impl App {
     }
 
     /// Registers a public static directory at an explicit route prefix.
-    pub fn build_public_router_v2(&mut self, path: &OsString, route: &str) {
-        let static_files = ServeDir::new(path);
+    pub fn build_public_router_v2(&mut self, path: &OsString, route: &str, options: &PublicConfig) {
+        let static_files = public_router(
+            std::path::PathBuf::from(path),
+            PublicOptions::from_config(options),
+        );
         let new_router = self.router.take().nest_service(route, static_files);
         self.replace_router(new_router);
+        self.registered_routes.push(RegisteredRoute {
+            method: "GET".to_string(),
+            path: format!("{}/{{*path}}", route.trim_end_matches('/')),
+            is_protected: false,
+        });
     }
 
     /// Registers internal collection inspection routes.
@@ -317,8 +993,104 @@ impl App {
         create_schema_routes(self);
     }
 
-    /// Infers references between loaded Fosk collections.
+    /// Registers the sequence reset route when a response sequence exists.
+    pub fn build_sequences_route(&mut self) {
+        if !self.sequences.is_empty() {
+            create_sequence_routes(self);
+        }
+    }
+
+    /// Registers the upload cleanup route when a temporary upload folder exists.
+    pub fn build_uploads_route(&mut self) {
+        let temporary = self
+            .uploads_configurations
+            .iter()
+            .filter(|upload_config| upload_config.clean_uploads)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !temporary.is_empty() {
+            create_upload_cleanup_route(self, temporary);
+        }
+    }
+
+    /// Registers the cache flush route when a route caches its responses.
+    pub fn build_cache_route(&mut self) {
+        if !self.response_cache.is_empty() {
+            create_cache_routes(self);
+        }
+    }
+
+    /// Registers the state snapshot and restore routes.
+    pub fn build_state_route(&mut self) {
+        create_state_routes(self);
+    }
+
+    /// Registers the batch route, which applies writes in one transaction.
+    pub fn build_batch_route(&mut self) {
+        create_batch_routes(self);
+    }
+
+    /// Registers the change event stream.
+    pub fn build_change_events_route(&mut self) {
+        create_change_event_routes(self);
+    }
+
+    /// Registers the request journal, its event stream, and its live tail page.
+    pub fn build_request_journal_route(&mut self) {
+        create_request_journal_routes(self);
+    }
+
+    /// Registers the network partition rules of `/mock-server/network`.
+    pub fn build_network_partition_route(&mut self) {
+        create_network_partition_routes(self);
+    }
+
+    /// Registers the golden traffic report, when `[proxy] diff` is set.
+    pub fn build_golden_diff_route(&mut self) {
+        create_golden_diff_routes(self);
+    }
+
+    /// Registers the audit log routes, unless the log is disabled.
+    pub fn build_audit_route(&mut self) {
+        create_audit_routes(self);
+    }
+
+    /// Registers the quota consumption report, when `[quota]` is set.
+    pub fn build_quota_route(&mut self) {
+        create_quota_routes(self);
+    }
+
+    /// Registers the CSRF token route when a folder requires CSRF tokens.
+    pub fn build_csrf_route(&mut self) {
+        create_csrf_routes(self);
+    }
+
+    /// Registers the mock SAML identity provider routes, when configured.
+    pub fn build_saml_route(&mut self) {
+        create_saml_routes(self);
+    }
+
+    /// Registers the OpenAPI document and Swagger UI routes, unless disabled.
+    pub fn build_api_docs_route(&mut self) {
+        create_api_docs_routes(self);
+    }
+
+    /// Registers declared reference constraints and infers references between
+    /// loaded Fosk collections.
     pub fn build_collections_references(&mut self) {
+        for constraint in self.references.list() {
+            if let Some(ref_collection) = self.db.get(&constraint.ref_collection)
+                && let Ok(ref_config) = ref_collection.get_config()
+            {
+                self.db.create_reference(
+                    &constraint.collection,
+                    &constraint.field,
+                    &constraint.ref_collection,
+                    &ref_config.id_key,
+                );
+            }
+        }
+
         let collections = self.db.list_collections();
 
         if collections.len() > 1 {
@@ -356,18 +1128,55 @@ impl App {
     }
 
     fn build_router(&mut self, include_fallback: bool, home_route: &str) -> Router {
+        // Reseeded on every build so hot reloads regenerate the same data.
+        self.random.set_seed(
+            self.server_config
+                .server
+                .as_ref()
+                .and_then(|server| server.seed),
+        );
+        random::sync_scope(Arc::clone(&self.random), || {
+            self.build_seeded_router(include_fallback, home_route)
+        })
+    }
+
+    fn build_seeded_router(&mut self, include_fallback: bool, home_route: &str) -> Router {
+        let started = Instant::now();
+        self.build_code_routes();
         self.build_dyn_routes();
-        self.load_schema_files();
-        self.load_collection_files();
+        self.load_openapi_contract();
+        self.load_error_bodies();
+        self.load_schedule();
+        self.stage("Loaded schemas", App::load_schema_files);
+        self.stage("Loaded collection files", App::load_collection_files);
+        self.load_code_seeds();
         self.build_home_route(home_route);
+        self.build_playground_route();
         self.build_collections_route();
         self.build_schemas_route();
+        self.build_sequences_route();
+        self.build_uploads_route();
+        self.build_cache_route();
+        self.build_state_route();
+        self.build_batch_route();
+        self.build_change_events_route();
+        self.build_audit_route();
+        self.build_request_journal_route();
+        self.build_golden_diff_route();
+        self.build_network_partition_route();
+        self.build_quota_route();
+        self.build_csrf_route();
+        self.build_saml_route();
+        self.build_api_docs_route();
         if include_fallback {
             self.build_fallback();
         }
         self.build_middlewares();
         self.build_collections_references();
-        self.get_router()
+        let router = self.get_router();
+        let _ = self.routes.set(router.clone());
+        self.log(format!("⏱️ Server built in {:?}", started.elapsed()));
+        router
     }
 
     /// Builds the mock server routes as an Axum router without starting a server.
@@ -379,62 +1188,137 @@ impl App {
         self.build_router(false, MOCK_SERVER_ROUTE)
     }
 
-    async fn start_server(&self, router: Router) {
-        let address = format!("0.0.0.0:{}", self.get_port());
+    /// Builds the standalone server router, with the home page at `/` and the
+    /// 404 fallback, and starts the MQTT broker of the `[mqtt]` table.
+    pub(crate) fn build_server_router(&mut self) -> Router {
+        let router = self.build_router(true, "/");
+        start_mqtt_broker(self);
+        router
+    }
+
+    async fn start_server(&mut self, router: Router) {
         let server_config = self.server_config.server.clone().unwrap_or_default();
         let tls_mode = resolve_tls_mode(&server_config).unwrap_or_else(|err| panic!("{}", err));
+        let client_auth =
+            resolve_client_auth(&server_config).unwrap_or_else(|err| panic!("{}", err));
+
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", self.get_port()))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        self.set_bound_port(port);
 
         App::show_greetings();
-        self.print_listening_link(&tls_mode);
+        self.print_listening_link(&tls_mode, port);
 
         match tls_mode {
             TlsMode::Disabled => {
-                let listener = tokio::net::TcpListener::bind(address.clone())
-                    .await
-                    .unwrap();
-                axum::serve(listener, router).await.unwrap();
+                axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .await
+                .unwrap();
             }
             mode => {
-                let config = rustls_config(&mode)
+                let config = rustls_config(&mode, client_auth.as_ref())
                     .await
                     .unwrap_or_else(|err| panic!("{}", err));
-                let address: SocketAddr = address.parse().unwrap();
-                axum_server::bind_rustls(address, config)
-                    .serve(router.into_make_service())
+                axum_server::from_tcp(listener.into_std().unwrap())
+                    .unwrap()
+                    .acceptor(ClientCertAcceptor::new(config))
+                    .serve(router.into_make_service_with_connect_info::<SocketAddr>())
                     .await
                     .unwrap();
             }
         }
     }
 
-    fn print_listening_link(&self, tls_mode: &TlsMode) {
+    fn print_listening_link(&self, tls_mode: &TlsMode, port: u16) {
         let scheme = if is_https(tls_mode) { "https" } else { "http" };
-        let link = format!("{}://localhost:{}", scheme, self.get_port());
+        let link = format!("{}://localhost:{}", scheme, port);
         let link = Link::new(&link, &link);
         println!("🚀 Listening on {}", link);
     }
 
     /// Builds routes, middleware, and collection references, then starts the HTTP server.
     pub async fn initialize(&mut self) {
-        let router = self.build_router(true, "/");
+        let router = self.build_server_router();
         self.start_server(router).await;
     }
 
     /// Cleans upload folders and resets runtime state after shutdown.
     pub fn finish(&mut self) {
-        println!("\n");
+        self.shut_down(true);
+    }
+
+    /// Resets runtime state before a hot reload, keeping the files of
+    /// temporary upload folders when `[server].preserve_uploads` is set.
+    pub fn finish_for_reload(&mut self) {
+        self.shut_down(!self.preserves_uploads());
+    }
+
+    fn preserves_uploads(&self) -> bool {
+        self.server_config
+            .server
+            .as_ref()
+            .and_then(|server| server.preserve_uploads)
+            .unwrap_or(false)
+    }
+
+    fn shut_down(&mut self, clean_uploads: bool) {
+        self.log("\n");
 
-        for upload_config in self.uploads_configurations.iter() {
-            upload_config.clean_upload_folder();
+        for task in self.background_tasks.drain(..) {
+            task.abort();
+        }
+        if clean_uploads {
+            for upload_config in self.uploads_configurations.iter() {
+                upload_config.clean_upload_folder();
+            }
         }
 
         self.router = RefCell::new(Router::new());
         self.pages = Arc::new(Mutex::new(Pages::new()));
         self.uploads_configurations = vec![];
         self.db.clear();
+        self.references.clear();
+        self.collection_defaults.clear();
+        self.hal_links.clear();
+        self.read_only_collections.clear();
+        self.response_transforms.clear();
+        self.collection_order.clear();
+        self.response_validator.clear();
+        self.error_bodies = Arc::default();
+        self.sequences = Arc::default();
+        self.response_cache = Arc::default();
+        self.lazy_files.clear();
+        self.partitions.clear();
+        self.snapshots.clear();
+        self.registered_routes.clear();
+        self.route_versions.clear();
+        self.csrf.clear();
+
+        self.log("\n👋👋👋👋👋 Goodbye! 👋👋👋👋👋👋");
+    }
+}
 
-        println!("\n👋👋👋👋👋 Goodbye! 👋👋👋👋👋👋");
+/// Adds the `Deprecation` and `Sunset` headers of a version to every response.
+fn add_version_headers_layer(
+    router: MethodRouter,
+    headers: Vec<(HeaderName, HeaderValue)>,
+) -> MethodRouter {
+    if headers.is_empty() {
+        return router;
     }
+
+    router.layer(middleware::map_response(move |mut response: Response| {
+        let headers = headers.clone();
+        async move {
+            response.headers_mut().extend(headers);
+            response
+        }
+    }))
 }
 
 impl RouteRegistrator for App {
@@ -448,7 +1332,7 @@ impl RouteRegistrator for App {
     ) {
         let router = self.try_add_auth_middleware_layer(router, is_protected);
 
-        self.route(path, router, method, options);
+        self.register_route(path, router, method, options, is_protected);
     }
 }
 
@@ -457,8 +1341,11 @@ mod tests {
     use super::*;
     use axum::{
         body::{Body, to_bytes},
-        http::{Method, Request},
-        routing::get,
+        http::{
+            Method, Request, StatusCode,
+            header::{ALLOW, CONTENT_LENGTH, CONTENT_TYPE},
+        },
+        routing::{get, post, put},
     };
     use tower::ServiceExt;
 
@@ -484,6 +1371,71 @@ mod tests {
         assert_eq!(custom_app.get_port(), 9876);
     }
 
+    #[test]
+    fn overlapping_routes_are_refused_without_registering_the_file() {
+        struct Routes(&'static [(&'static str, &'static str)]);
+        impl RouteGenerator for Routes {
+            fn make_routes(&self, app: &mut App) {
+                for (method, path) in self.0 {
+                    let filter = Method::from_bytes(method.as_bytes()).unwrap();
+                    let router = axum::routing::on(filter.try_into().unwrap(), async || "ok");
+                    app.route(path, router, Some(method), None);
+                }
+            }
+        }
+        let listed = |app: &App| {
+            app.registered_routes()
+                .iter()
+                .map(|route| format!("{} {}", route.method, route.path))
+                .collect::<Vec<_>>()
+        };
+
+        let mut app = App {
+            quiet: true,
+            ..Default::default()
+        };
+        app.try_make_routes(&Routes(&[("GET", "/users/{id}")]))
+            .unwrap();
+        let err = app
+            .try_make_routes(&Routes(&[("GET", "/health"), ("PUT", "/users/{name}")]))
+            .unwrap_err();
+        assert_eq!(
+            describe_overlaps(&err),
+            "PUT /users/{name} overlaps GET /users/{id}, registered earlier"
+        );
+        assert_eq!(listed(&app), ["GET /users/{id}"]);
+
+        app.try_make_routes(&Routes(&[("DELETE", "/users/{id}")]))
+            .unwrap();
+        let err = app
+            .try_make_routes(&Routes(&[("GET", "/users/{id}")]))
+            .unwrap_err();
+        assert_eq!(err[0].earlier.path, "/users/{id}");
+        assert_eq!(err[0].earlier.method, "GET");
+        assert_eq!(listed(&app), ["GET /users/{id}", "DELETE /users/{id}"]);
+        let _ = app.take_router_for_test();
+    }
+
+    #[test]
+    fn bound_port_is_written_to_the_port_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let port_file = temp_dir.path().join("port");
+        let mut app = App::new(Config {
+            server: Some(ServerConfig {
+                port: Some(0),
+                port_file: Some(port_file.to_string_lossy().into_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(app.bound_port(), None);
+
+        app.set_bound_port(43121);
+
+        assert_eq!(app.bound_port(), Some(43121));
+        assert_eq!(std::fs::read_to_string(port_file).unwrap(), "43121");
+    }
+
     #[tokio::test]
     async fn route_registers_page_link_and_serves_handler() {
         let mut app = App::default();
@@ -510,6 +1462,90 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn home_page_can_be_disabled() {
+        for (home_page, expected) in [(None, StatusCode::OK), (Some(false), StatusCode::NOT_FOUND)]
+        {
+            let mut app = App::new(Config {
+                server: Some(ServerConfig {
+                    home_page,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            app.build_home_route("/");
+
+            let response = app
+                .take_router_for_test()
+                .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn versioned_routes_send_deprecation_headers_and_serve_the_latest_alias() {
+        let mut app = App::default();
+        app.push_route_versions(&[
+            RouteVersion {
+                version: "v1".to_string(),
+                route: "/v1/users".to_string(),
+                alias: None,
+                deprecation: Some("@1735689600".to_string()),
+                sunset: Some("Tue, 30 Jun 2026 00:00:00 GMT".to_string()),
+            },
+            RouteVersion {
+                version: "v2".to_string(),
+                route: "/v2/users".to_string(),
+                alias: Some("/latest/users".to_string()),
+                deprecation: None,
+                sunset: None,
+            },
+        ]);
+        app.route("/v1/users", get(|| async { "v1" }), Some("GET"), None);
+        app.route("/v2/users/{id}", get(|| async { "v2" }), Some("GET"), None);
+
+        let paths = app
+            .registered_routes()
+            .iter()
+            .map(|route| route.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["/v1/users", "/v2/users/{id}", "/latest/users/{id}"]);
+
+        let router = app.take_router_for_test();
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["deprecation"], "@1735689600");
+        assert_eq!(
+            response.headers()["sunset"],
+            "Tue, 30 Jun 2026 00:00:00 GMT"
+        );
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/latest/users/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.headers().get("deprecation").is_none());
+        assert_eq!(
+            to_bytes(response.into_body(), usize::MAX).await.unwrap(),
+            "v2"
+        );
+    }
+
     #[tokio::test]
     async fn unprotected_auth_layer_returns_original_router() {
         let mut app = App::default();
@@ -553,6 +1589,435 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn head_and_options_are_answered_for_registered_paths() {
+        let mut app = App::default();
+        app.route("/users", get(|| async { "users" }), Some("GET"), None);
+        app.route("/users", post(|| async { "created" }), Some("POST"), None);
+        app.route("/reports", put(|| async { "saved" }), Some("PUT"), None);
+        app.build_middlewares();
+        let router = app.take_router_for_test();
+
+        let request = |method: &str, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(request("HEAD", "/users"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_LENGTH], "5");
+        assert!(
+            to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let response = router
+            .clone()
+            .oneshot(request("OPTIONS", "/users"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()[ALLOW], "GET, HEAD, OPTIONS, POST");
+
+        let response = router
+            .clone()
+            .oneshot(request("OPTIONS", "/reports"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[ALLOW], "OPTIONS, PUT");
+
+        let preflight = Request::builder()
+            .method("OPTIONS")
+            .uri("/users")
+            .header("origin", "http://example.com")
+            .header("access-control-request-method", "POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(preflight).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response
+                .headers()
+                .contains_key("access-control-allow-origin")
+        );
+        assert_eq!(response.headers()[ALLOW], "GET, HEAD, OPTIONS, POST");
+    }
+
+    #[tokio::test]
+    async fn known_paths_answer_405_with_allow_instead_of_404() {
+        let mut app = App::default();
+        app.route("/users", get(|| async { "users" }), Some("GET"), None);
+        app.route(
+            "/search",
+            crate::handlers::query(|| async { "found" }),
+            Some("QUERY"),
+            None,
+        );
+        app.build_fallback();
+        app.build_middlewares();
+        let router = app.take_router_for_test();
+
+        let request = |method: &str, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(request("DELETE", "/users"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "GET, HEAD, OPTIONS");
+
+        let response = router
+            .clone()
+            .oneshot(request("DELETE", "/users/"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "GET, HEAD, OPTIONS");
+
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/search"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "OPTIONS, QUERY");
+
+        let response = router.oneshot(request("DELETE", "/missing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn cached_routes_reuse_rendered_bodies_until_flushed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("tokens")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("tokens/get.json"),
+            r#"{"token":"{{uuid}}"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("tokens/config.toml"),
+            "[route]\ncache_ttl = 60\n",
+        )
+        .unwrap();
+
+        let mut app = App::new(config(Some(&temp_dir.path().to_string_lossy()), None));
+        app.quiet = true;
+        let router = app.build_server_router();
+        let call = |method: &str, uri: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<Value>(&bytes).unwrap()
+            }
+        };
+
+        let first = call("GET", "/tokens").await;
+        assert_eq!(call("GET", "/tokens").await, first);
+        assert_eq!(
+            call("DELETE", "/mock-server/cache").await,
+            serde_json::json!({ "flushed": 1 })
+        );
+        assert_ne!(call("GET", "/tokens").await, first);
+    }
+
+    #[tokio::test]
+    async fn seeded_servers_keep_their_own_sequences() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("tokens")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("tokens/get.json"),
+            r#"{"token":"{{uuid}}"}"#,
+        )
+        .unwrap();
+        let seeded = |seed: u64| {
+            let mut config = config(Some(&temp_dir.path().to_string_lossy()), None);
+            config.server.as_mut().unwrap().seed = Some(seed);
+            let mut app = App::new(config);
+            app.quiet = true;
+            app.build_server_router()
+        };
+        let token = |router: &Router| {
+            let router = router.clone();
+            async move {
+                let request = Request::builder()
+                    .uri("/tokens")
+                    .body(Body::empty())
+                    .unwrap();
+                let response = router.oneshot(request).await.unwrap();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<Value>(&bytes).unwrap()["token"].clone()
+            }
+        };
+
+        let first = seeded(7);
+        let first_tokens = [token(&first).await, token(&first).await];
+        let second = seeded(7);
+        let _other = seeded(8);
+        assert_eq!([token(&second).await, token(&second).await], first_tokens);
+        assert_ne!(token(&first).await, first_tokens[1]);
+    }
+
+    #[tokio::test]
+    async fn csrf_folders_reject_mutations_without_the_issued_token() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("admin/open")).unwrap();
+        std::fs::write(temp_dir.path().join("admin/post.json"), r#"{"ok":true}"#).unwrap();
+        std::fs::write(
+            temp_dir.path().join("admin/config.toml"),
+            "[route]\ncsrf = true\n",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("admin/open/post.json"), "{}").unwrap();
+        std::fs::write(
+            temp_dir.path().join("admin/open/config.toml"),
+            "[route]\ncsrf = false\n",
+        )
+        .unwrap();
+        std::fs::create_dir(temp_dir.path().join("contact")).unwrap();
+        std::fs::write(temp_dir.path().join("contact/post.json"), "{}").unwrap();
+
+        let mut app = App::new(config(Some(&temp_dir.path().to_string_lossy()), None));
+        app.quiet = true;
+        let router = app.build_server_router();
+        let post = |uri: &str, token: Option<&str>| {
+            let mut builder = Request::builder().method("POST").uri(uri);
+            if let Some(token) = token {
+                builder = builder
+                    .header("cookie", format!("XSRF-TOKEN={}", token))
+                    .header("x-xsrf-token", token);
+            }
+            let request = builder.body(Body::empty()).unwrap();
+            let router = router.clone();
+            async move { router.oneshot(request).await.unwrap() }
+        };
+
+        let response = post("/admin", None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "csrf_token_invalid");
+        assert_eq!(
+            post("/admin", Some("forged")).await.status(),
+            StatusCode::FORBIDDEN
+        );
+
+        let response = router
+            .clone()
+            .oneshot(Request::get("/csrf").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let cookie = response.headers()["set-cookie"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let token = serde_json::from_slice::<Value>(&bytes).unwrap()["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(cookie.starts_with(&format!("XSRF-TOKEN={};", token)));
+
+        assert_eq!(post("/admin", Some(&token)).await.status(), StatusCode::OK);
+        assert_eq!(post("/admin/open", None).await.status(), StatusCode::OK);
+        assert_eq!(post("/contact", None).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn round_robin_strategy_cycles_through_json_and_script_steps() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("payments")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("payments/get.1.json"),
+            r#"{"ok":true}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("payments/get.2.rhai"),
+            r#"response(500, #{ error: "unavailable" })"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("payments/config.toml"),
+            "[route]\nstrategy = \"round-robin\"\n",
+        )
+        .unwrap();
+
+        let mut app = App::new(config(Some(&temp_dir.path().to_string_lossy()), None));
+        app.quiet = true;
+        let router = app.build_server_router();
+        let mut statuses = vec![];
+        for _ in 0..4 {
+            let response = router
+                .clone()
+                .oneshot(Request::get("/payments").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            statuses.push(response.status().as_u16());
+        }
+        assert_eq!(statuses, vec![200, 500, 200, 500]);
+    }
+
+    #[tokio::test]
+    async fn lazy_files_serve_edits_on_the_next_request() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("status.json");
+        std::fs::write(&file, r#"{"status":"up"}"#).unwrap();
+
+        let mut config = config(Some(&temp_dir.path().to_string_lossy()), None);
+        config.server.as_mut().unwrap().lazy_files = Some(true);
+        let mut app = App::new(config);
+        app.quiet = true;
+        let router = app.build_server_router();
+        assert!(app.lazy_files.contains(&file));
+
+        let call = || {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(Request::get("/status").body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                to_bytes(response.into_body(), usize::MAX).await.unwrap()
+            }
+        };
+        assert_eq!(call().await, r#"{"status":"up"}"#);
+        std::fs::write(&file, r#"{"status":"degraded"}"#).unwrap();
+        assert_eq!(call().await, r#"{"status":"degraded"}"#);
+    }
+
+    #[tokio::test]
+    async fn error_body_templates_replace_404_and_error_bodies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("users")).unwrap();
+        std::fs::write(temp_dir.path().join("users/get.json"), "[]").unwrap();
+        std::fs::write(
+            temp_dir.path().join("__404.json"),
+            r#"{"code":"{{status}}","message":"{{path}} does not exist"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("__error.json"),
+            r#"{"code":"{{status}}","message":"{{message}}"}"#,
+        )
+        .unwrap();
+
+        let mut app = App::new(config(Some(&temp_dir.path().to_string_lossy()), None));
+        app.quiet = true;
+        let router = app.build_server_router();
+        assert!(
+            app.registered_routes()
+                .iter()
+                .all(|route| !route.path.starts_with("/__404")
+                    && !route.path.starts_with("/__error"))
+        );
+
+        let request = |method: &str, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let body = |response: axum::response::Response| async move {
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&bytes).unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/missing"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(
+            body(response).await,
+            serde_json::json!({ "code": 404, "message": "/missing does not exist" })
+        );
+
+        let response = router
+            .clone()
+            .oneshot(request("DELETE", "/users"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(response.headers().contains_key(ALLOW));
+        assert_eq!(
+            body(response).await,
+            serde_json::json!({ "code": 405, "message": "Method Not Allowed" })
+        );
+
+        let response = router.oneshot(request("GET", "/users")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            to_bytes(response.into_body(), usize::MAX).await.unwrap(),
+            "[]"
+        );
+    }
+
+    #[tokio::test]
+    async fn lenient_path_matching_reaches_routes_from_the_fallback() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("users")).unwrap();
+        std::fs::write(temp_dir.path().join("users/get.json"), "[]").unwrap();
+        let folder = temp_dir.path().to_string_lossy().to_string();
+        let request = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let mut strict = App::new(config(Some(&folder), None));
+        strict.quiet = true;
+        let response = strict
+            .build_server_router()
+            .oneshot(request("/Users/"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut lenient = App::new(Config {
+            server: Some(ServerConfig {
+                folder: Some(folder),
+                normalize_trailing_slash: Some(true),
+                case_insensitive_paths: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        lenient.quiet = true;
+        let router = lenient.build_server_router();
+        for uri in ["/users/", "/Users", "/USERS/?page=2"] {
+            let response = router.clone().oneshot(request(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(
+                to_bytes(response.into_body(), usize::MAX).await.unwrap(),
+                "[]"
+            );
+        }
+        let response = router.oneshot(request("/orders/")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn home_fallback_public_and_middlewares_are_built() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -569,7 +2034,7 @@ mod tests {
         app.pages
             .lock()
             .unwrap()
-            .push_link("GET".to_string(), "/x".to_string(), &[]);
+            .push_link("GET".to_string(), "/x".to_string(), &[], false);
         app.build_home_route("/");
         app.build_public_router(
             "public-assets".to_string(),
@@ -611,6 +2076,69 @@ mod tests {
         assert_eq!(fallback.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn into_router_validates_responses_against_contracts() {
+        let mocks_dir = tempfile::TempDir::new().unwrap();
+        let contracts_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(mocks_dir.path().join("health.json"), r#"{"status":"ok"}"#).unwrap();
+        std::fs::create_dir(mocks_dir.path().join("users")).unwrap();
+        std::fs::write(mocks_dir.path().join("users/get.json"), r#"{"id":1}"#).unwrap();
+
+        let user_schema = contracts_dir.path().join("user.json");
+        std::fs::write(&user_schema, r#"{"type":"object","required":["name"]}"#).unwrap();
+        std::fs::write(
+            mocks_dir.path().join("users/get.toml"),
+            format!(
+                "[route]\nresponse_schema = {:?}\n",
+                user_schema.to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let openapi = contracts_dir.path().join("openapi.json");
+        std::fs::write(
+            &openapi,
+            r#"{"paths":{"/health":{"get":{"responses":{"200":{"content":{"application/json":{"schema":{"properties":{"status":{"enum":["ok"]}}}}}}}}}}}"#,
+        )
+        .unwrap();
+
+        let router = App::new(Config {
+            server: Some(ServerConfig {
+                folder: Some(mocks_dir.path().to_string_lossy().to_string()),
+                openapi: Some(openapi.to_string_lossy().to_string()),
+                response_validation: Some("fail".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .into_router();
+
+        let health = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+
+        let users = router
+            .oneshot(
+                Request::builder()
+                    .uri("/users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(users.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(users.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("$: missing required property 'name'"));
+    }
+
     #[tokio::test]
     async fn into_router_embeds_mock_routes_without_owning_host_fallback() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -709,7 +2237,11 @@ mod tests {
             }),
             ..Default::default()
         });
-        app.build_public_router_v2(&temp_dir.path().as_os_str().to_os_string(), "/static");
+        app.build_public_router_v2(
+            &temp_dir.path().as_os_str().to_os_string(),
+            "/static",
+            &PublicConfig::default(),
+        );
         app.build_middlewares();
 
         let response = app
@@ -746,4 +2278,95 @@ mod tests {
                 .contains("mock_routes = []")
         );
     }
+
+    #[tokio::test]
+    async fn preserved_uploads_survive_reloads_until_cleaned() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let uploads = temp_dir.path().join("{upload}{temp}");
+        std::fs::create_dir(&uploads).unwrap();
+        let uploaded = uploads.join("report.pdf");
+
+        let mut config = config(Some(&temp_dir.path().to_string_lossy()), None);
+        config.server.as_mut().unwrap().preserve_uploads = Some(true);
+        let mut app = App::new(config.clone());
+        app.quiet = true;
+        let _ = app.build_server_router();
+        std::fs::write(&uploaded, "pdf").unwrap();
+        app.finish_for_reload();
+        assert!(uploaded.exists());
+
+        let mut app = App::new(config);
+        app.quiet = true;
+        let router = app.build_server_router();
+        let response = router
+            .oneshot(
+                Request::delete("/mock-server/uploads")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&bytes).unwrap(),
+            serde_json::json!({ "deleted": 1 })
+        );
+        assert!(!uploaded.exists());
+
+        std::fs::write(&uploaded, "pdf").unwrap();
+        app.finish();
+        assert!(!uploaded.exists());
+    }
+
+    #[tokio::test]
+    async fn proxied_errors_keep_the_backend_body_with_problem_details() {
+        use crate::{
+            error_bodies::PROBLEM_JSON,
+            route_builder::config::{ProxyConfig, ProxyRule},
+        };
+
+        let backend = Router::new().fallback(|| async {
+            (
+                StatusCode::NOT_FOUND,
+                axum::Json(serde_json::json!({ "reason": "upstream" })),
+            )
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, backend).await });
+
+        let mut app = App::new(Config {
+            server: Some(ServerConfig {
+                problem_details: Some(true),
+                ..Default::default()
+            }),
+            proxy: Some(ProxyConfig {
+                rules: Some(vec![ProxyRule {
+                    prefix: "/payments".to_string(),
+                    target: format!("http://{}", address),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        app.error_bodies = Arc::new(ErrorBodies::load(&app.server_config).unwrap().0);
+        app.build_fallback();
+        app.build_middlewares();
+        let router = app.take_router_for_test();
+        let call = |uri: &str| {
+            router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = call("/payments/missing").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, r#"{"reason":"upstream"}"#);
+
+        let response = call("/orders").await.unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+    }
 }
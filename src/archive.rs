@@ -0,0 +1,331 @@
+//! Mock folders packed into a single `.zip` or `.tar.gz` archive.
+//!
+//! Route discovery, static file serving, lazily reloaded files, and uploads
+//! read the mock folder from disk while the server runs, so an archive is
+//! served by extracting it into a temporary folder that is removed again when
+//! the server stops.
+
+use std::{
+    fs, io,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use tempfile::TempDir;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::route_builder::config::Config;
+
+/// Returns true when `path` names a `.zip`, `.tar.gz`, or `.tgz` archive.
+pub fn is_archive(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    path.ends_with(".zip") || path.ends_with(".tar.gz") || path.ends_with(".tgz")
+}
+
+/// Mock folder extracted from an archive, removed when dropped.
+#[derive(Debug)]
+pub struct UnpackedArchive {
+    temp_dir: TempDir,
+    folder: PathBuf,
+}
+
+impl UnpackedArchive {
+    /// Extracts a `.zip` or `.tar.gz` archive into a new temporary folder.
+    ///
+    /// When the archive holds a single top-level folder named like the
+    /// archive, as with `tar czf mocks.tar.gz mocks`, that folder is the mock root.
+    pub fn extract(path: &Path) -> Result<Self, String> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("rs-mock-server-")
+            .tempdir()
+            .map_err(|err| format!("Unable to create a folder for {}: {}", path.display(), err))?;
+        let file = fs::File::open(path)
+            .map_err(|err| format!("Unable to read the archive {}: {}", path.display(), err))?;
+        let name = path.to_string_lossy().to_ascii_lowercase();
+        let extracted = if name.ends_with(".zip") {
+            extract_zip(file, temp_dir.path())
+        } else {
+            extract_tar_gz(file, temp_dir.path())
+        };
+        extracted.map_err(|err| format!("Invalid archive {}: {}", path.display(), err))?;
+
+        let root = temp_dir.path().join(archive_stem(&name));
+        let folder =
+            if only_entry(temp_dir.path()).is_some_and(|entry| entry == root) && root.is_dir() {
+                root
+            } else {
+                temp_dir.path().to_path_buf()
+            };
+        Ok(Self { temp_dir, folder })
+    }
+
+    /// Returns the folder holding the mock root.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Returns the temporary folder removed on drop.
+    pub fn temp_dir(&self) -> &Path {
+        self.temp_dir.path()
+    }
+}
+
+fn extract_zip(file: fs::File, into: &Path) -> Result<(), String> {
+    let mut zip = ZipArchive::new(file).map_err(|err| err.to_string())?;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|err| err.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let path = safe_path(Path::new(entry.name()))?;
+        write_entry(&mut entry, &into.join(path))?;
+    }
+    Ok(())
+}
+
+fn extract_tar_gz(file: fs::File, into: &Path) -> Result<(), String> {
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    for entry in tar.entries().map_err(|err| err.to_string())? {
+        let mut entry = entry.map_err(|err| err.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = safe_path(&entry.path().map_err(|err| err.to_string())?)?;
+        write_entry(&mut entry, &into.join(path))?;
+    }
+    Ok(())
+}
+
+fn write_entry(content: &mut impl io::Read, target: &Path) -> Result<(), String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let mut file = fs::File::create(target).map_err(|err| err.to_string())?;
+    io::copy(content, &mut file)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Returns the single entry of a folder, if it holds exactly one.
+fn only_entry(folder: &Path) -> Option<PathBuf> {
+    let mut entries = fs::read_dir(folder).ok()?;
+    let entry = entries.next()?.ok()?.path();
+    entries.next().is_none().then_some(entry)
+}
+
+/// Returns the file name of an archive without its directory and extension.
+fn archive_stem(path: &str) -> String {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    [".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Extracts every archive configured as the main folder or a mount, pointing
+/// the configuration at the extracted folders.
+///
+/// The folders are removed when the returned values are dropped.
+pub fn unpack_config_archives(config: &mut Config) -> Result<Vec<UnpackedArchive>, String> {
+    let Some(server) = config.server.as_mut() else {
+        return Ok(vec![]);
+    };
+
+    let mounts = server
+        .mounts
+        .iter_mut()
+        .flatten()
+        .map(|mount| &mut mount.folder);
+    let mut unpacked = vec![];
+    for folder in server.folder.iter_mut().chain(mounts) {
+        if !is_archive(folder) {
+            continue;
+        }
+        let archive = UnpackedArchive::extract(Path::new(folder))?;
+        *folder = archive.folder().to_string_lossy().into_owned();
+        unpacked.push(archive);
+    }
+    Ok(unpacked)
+}
+
+/// Packs a mock folder into a `.zip` or `.tar.gz` archive, chosen by the
+/// output extension, and returns the number of files packed.
+pub fn pack(folder: &Path, output: &Path) -> Result<usize, String> {
+    let mut files = vec![];
+    collect_files(folder, Path::new(""), &mut files)
+        .map_err(|err| format!("Unable to read {}: {}", folder.display(), err))?;
+    let file = fs::File::create(output)
+        .map_err(|err| format!("Unable to create {}: {}", output.display(), err))?;
+
+    let written = if output
+        .to_string_lossy()
+        .to_ascii_lowercase()
+        .ends_with(".zip")
+    {
+        write_zip(file, folder, &files)
+    } else {
+        write_tar_gz(file, folder, &files)
+    };
+    written
+        .map(|_| files.len())
+        .map_err(|err| format!("Unable to write {}: {}", output.display(), err))
+}
+
+fn collect_files(folder: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = fs::read_dir(folder.join(relative))?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = relative.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(folder, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn write_zip(file: fs::File, folder: &Path, files: &[PathBuf]) -> Result<(), String> {
+    let mut zip = ZipWriter::new(file);
+    for path in files {
+        let name = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(name, SimpleFileOptions::default())
+            .map_err(|err| err.to_string())?;
+        let content = fs::read(folder.join(path)).map_err(|err| err.to_string())?;
+        zip.write_all(&content).map_err(|err| err.to_string())?;
+    }
+    zip.finish().map(|_| ()).map_err(|err| err.to_string())
+}
+
+fn write_tar_gz(file: fs::File, folder: &Path, files: &[PathBuf]) -> Result<(), String> {
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for path in files {
+        tar.append_path_with_name(folder.join(path), path)
+            .map_err(|err| err.to_string())?;
+    }
+    tar.into_inner()
+        .and_then(|gzip| gzip.finish())
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Keeps the normal components of an entry path, rejecting paths that leave
+/// the archive root.
+fn safe_path(path: &Path) -> Result<PathBuf, String> {
+    let mut safe = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => safe.push(name),
+            Component::CurDir => {}
+            _ => return Err(format!("entry {} leaves the archive root", path.display())),
+        }
+    }
+    Ok(safe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::{FolderMount, ServerConfig};
+
+    fn mock_folder() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("users")).unwrap();
+        fs::write(temp_dir.path().join("users").join("get.json"), "[]").unwrap();
+        fs::write(temp_dir.path().join("health.json"), r#"{"ok":true}"#).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn packed_archives_extract_back_into_the_folder() {
+        let folder = mock_folder();
+        let output = TempDir::new().unwrap();
+
+        for name in ["mocks.zip", "mocks.tar.gz"] {
+            let archive_path = output.path().join(name);
+            assert_eq!(pack(folder.path(), &archive_path).unwrap(), 2);
+
+            let archive = UnpackedArchive::extract(&archive_path).unwrap();
+            assert_eq!(archive.folder(), archive.temp_dir());
+            assert_eq!(
+                fs::read_to_string(archive.folder().join("users").join("get.json")).unwrap(),
+                "[]"
+            );
+            assert!(archive.folder().join("health.json").is_file());
+        }
+    }
+
+    #[test]
+    fn a_top_level_folder_named_like_the_archive_is_the_mock_root() {
+        let folder = mock_folder();
+        let parent = TempDir::new().unwrap();
+        let nested = parent.path().join("mocks");
+        fs::rename(folder.path(), &nested).unwrap();
+        let output = TempDir::new().unwrap();
+
+        let archive_path = output.path().join("mocks.tgz");
+        pack(parent.path(), &archive_path).unwrap();
+        let archive = UnpackedArchive::extract(&archive_path).unwrap();
+        assert!(archive.folder().join("users").join("get.json").is_file());
+
+        let archive_path = output.path().join("fixtures.zip");
+        pack(parent.path(), &archive_path).unwrap();
+        let archive = UnpackedArchive::extract(&archive_path).unwrap();
+        assert!(
+            archive
+                .folder()
+                .join("mocks")
+                .join("users")
+                .join("get.json")
+                .is_file()
+        );
+    }
+
+    #[test]
+    fn entries_leaving_the_root_are_rejected() {
+        assert!(safe_path(Path::new("../etc/passwd")).is_err());
+        assert!(safe_path(Path::new("/etc/passwd")).is_err());
+        assert_eq!(
+            safe_path(Path::new("./users/get.json")).unwrap(),
+            PathBuf::from("users/get.json")
+        );
+    }
+
+    #[test]
+    fn config_archives_are_extracted_and_removed_on_drop() {
+        let folder = mock_folder();
+        let output = TempDir::new().unwrap();
+        let archive_path = output.path().join("billing.tar.gz");
+        pack(folder.path(), &archive_path).unwrap();
+
+        let mut config = Config {
+            server: Some(ServerConfig {
+                folder: Some("./mocks".to_string()),
+                mounts: Some(vec![FolderMount {
+                    prefix: Some("/billing".to_string()),
+                    folder: archive_path.to_string_lossy().into_owned(),
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let unpacked = unpack_config_archives(&mut config).unwrap();
+        let server = config.server.unwrap();
+        assert_eq!(server.folder.as_deref(), Some("./mocks"));
+        assert_eq!(unpacked.len(), 1);
+        let extracted = PathBuf::from(&server.mounts.unwrap()[0].folder);
+        assert!(extracted.join("users").join("get.json").is_file());
+
+        drop(unpacked);
+        assert!(!extracted.exists());
+    }
+}
@@ -0,0 +1,444 @@
+//! Load generator used by the `bench` command.
+//!
+//! Requests are sent by concurrent workers, either to a router built in
+//! process or over HTTP to a running server, and their latencies are reported
+//! per route kind so regressions in handlers and collections stand out.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+};
+use http::{Method, Request, Uri, header::CONTENT_TYPE};
+use hyper::client::conn::http1::{self, SendRequest};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+use tower::ServiceExt;
+
+use crate::{response_validation::route_matches, route_inventory::RouteEntry};
+
+/// Route kind of targets that match no route of the mock folder.
+pub const OTHER_KIND: &str = "other";
+
+/// One request sent repeatedly by the benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchTarget {
+    /// HTTP method.
+    pub method: Method,
+    /// Request path, with its query string.
+    pub path: String,
+    /// JSON request body.
+    pub body: Option<String>,
+    /// Kind of the route answering the request, such as `basic` or `rest`.
+    pub kind: String,
+}
+
+impl BenchTarget {
+    fn request(&self, uri: &str, host: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder().method(self.method.clone()).uri(uri);
+        if let Some(host) = host {
+            request = request.header(http::header::HOST, host);
+        }
+        match &self.body {
+            Some(body) => request
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.clone())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap()
+    }
+}
+
+/// Parses a routes file, with one `[METHOD] PATH [JSON BODY]` target per line.
+///
+/// The method defaults to GET. Blank lines and lines starting with `#` are
+/// skipped.
+pub fn parse_targets(text: &str) -> Result<Vec<BenchTarget>, String> {
+    let mut targets = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (method, rest) = match line.split_once(char::is_whitespace) {
+            Some((method, rest)) if !method.starts_with('/') => (method, rest.trim_start()),
+            _ => ("GET", line),
+        };
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .map_err(|_| format!("line {}: invalid method `{}`", number + 1, method))?;
+        let (path, body) = match rest.split_once(char::is_whitespace) {
+            Some((path, body)) => (path, Some(body.trim().to_string())),
+            None => (rest, None),
+        };
+        if !path.starts_with('/') || path.parse::<Uri>().is_err() {
+            return Err(format!("line {}: invalid path `{}`", number + 1, path));
+        }
+
+        targets.push(BenchTarget {
+            method,
+            path: path.to_string(),
+            body: body.filter(|body| !body.is_empty()),
+            kind: OTHER_KIND.to_string(),
+        });
+    }
+
+    if targets.is_empty() {
+        return Err("no routes to benchmark".to_string());
+    }
+    Ok(targets)
+}
+
+/// Builds a GET target for every route of the mock folder without path parameters.
+pub fn default_targets(routes: &[RouteEntry]) -> Vec<BenchTarget> {
+    routes
+        .iter()
+        .filter(|route| route.method == "GET" && !route.path.contains('{'))
+        .map(|route| BenchTarget {
+            method: Method::GET,
+            path: route.path.clone(),
+            body: None,
+            kind: route.kind.clone(),
+        })
+        .collect()
+}
+
+/// Sets the kind of every target to the kind of the first route answering it.
+pub fn classify_targets(targets: &mut [BenchTarget], routes: &[RouteEntry]) {
+    for target in targets {
+        let path = target.path.split('?').next().unwrap_or_default();
+        if let Some(route) = routes.iter().find(|route| {
+            route.method == target.method.as_str() && route_matches(&route.path, path)
+        }) {
+            target.kind = route.kind.clone();
+        }
+    }
+}
+
+/// Where benchmark requests are sent.
+#[derive(Clone)]
+pub enum BenchTransport {
+    /// A router built in process, called without a network round trip.
+    Router(Router),
+    /// A running server, reached over HTTP/1.1 at `host:port`.
+    Http(String),
+}
+
+impl BenchTransport {
+    /// Parses an `http://host:port` server URL.
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let uri = url
+            .parse::<Uri>()
+            .map_err(|err| format!("invalid URL `{}`: {}", url, err))?;
+        if uri.scheme_str() != Some("http") {
+            return Err(format!("`{}` is not an http:// URL", url));
+        }
+        let authority = uri
+            .authority()
+            .ok_or_else(|| format!("`{}` has no host", url))?;
+        let port = authority.port_u16().unwrap_or(80);
+        Ok(Self::Http(format!("{}:{}", authority.host(), port)))
+    }
+}
+
+/// How much load the benchmark generates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchOptions {
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Requests sent in total, spread over the targets in turn.
+    pub requests: usize,
+}
+
+/// Latency and throughput of the requests sent to one route kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchStats {
+    /// Route kind, or `total` for every request.
+    pub kind: String,
+    /// Requests sent.
+    pub requests: usize,
+    /// Requests that failed or were answered with a 5xx status.
+    pub errors: usize,
+    /// Requests per second over the whole run.
+    pub throughput: f64,
+    /// Median latency.
+    pub p50: Duration,
+    /// 90th percentile latency.
+    pub p90: Duration,
+    /// 99th percentile latency.
+    pub p99: Duration,
+    /// Slowest request.
+    pub max: Duration,
+}
+
+/// Sends `options.requests` requests to the targets and reports them per route kind.
+pub async fn run_bench(
+    transport: BenchTransport,
+    targets: Vec<BenchTarget>,
+    options: BenchOptions,
+) -> Vec<BenchStats> {
+    let targets = Arc::new(targets);
+    let next = Arc::new(AtomicUsize::new(0));
+    let started = Instant::now();
+
+    let workers = (0..options.concurrency.max(1))
+        .map(|_| {
+            let transport = transport.clone();
+            let targets = Arc::clone(&targets);
+            let next = Arc::clone(&next);
+            tokio::spawn(async move {
+                let mut samples = vec![];
+                let mut connection = None;
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    if index >= options.requests || targets.is_empty() {
+                        return samples;
+                    }
+                    let target = &targets[index % targets.len()];
+                    let sent = Instant::now();
+                    let ok = send(&transport, &mut connection, target).await;
+                    samples.push((target.kind.clone(), sent.elapsed(), ok));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut samples = vec![];
+    for worker in workers {
+        samples.extend(worker.await.unwrap_or_default());
+    }
+    summarize(samples, started.elapsed())
+}
+
+/// Sends one request and returns whether it was answered without a 5xx status.
+async fn send(
+    transport: &BenchTransport,
+    connection: &mut Option<SendRequest<Body>>,
+    target: &BenchTarget,
+) -> bool {
+    let response = match transport {
+        BenchTransport::Router(router) => router
+            .clone()
+            .oneshot(target.request(&target.path, None))
+            .await
+            .ok(),
+        BenchTransport::Http(address) => {
+            if connection.as_ref().is_none_or(SendRequest::is_closed) {
+                *connection = connect(address).await;
+            }
+            let Some(sender) = connection.as_mut() else {
+                return false;
+            };
+            match sender
+                .send_request(target.request(&target.path, Some(address)))
+                .await
+            {
+                Ok(response) => Some(response.map(Body::new)),
+                Err(_) => {
+                    *connection = None;
+                    None
+                }
+            }
+        }
+    };
+
+    let Some(response) = response else {
+        return false;
+    };
+    let status = response.status();
+    to_bytes(response.into_body(), usize::MAX).await.is_ok() && !status.is_server_error()
+}
+
+/// Opens a keep-alive HTTP/1.1 connection to `address`.
+async fn connect(address: &str) -> Option<SendRequest<Body>> {
+    let stream = TcpStream::connect(address).await.ok()?;
+    let (sender, connection) = http1::handshake(TokioIo::new(stream)).await.ok()?;
+    tokio::spawn(connection);
+    Some(sender)
+}
+
+fn summarize(samples: Vec<(String, Duration, bool)>, elapsed: Duration) -> Vec<BenchStats> {
+    let mut kinds = BTreeMap::<String, Vec<(Duration, bool)>>::new();
+    for (kind, latency, ok) in &samples {
+        kinds.entry(kind.clone()).or_default().push((*latency, *ok));
+    }
+
+    let total = samples
+        .into_iter()
+        .map(|(_, latency, ok)| (latency, ok))
+        .collect();
+    kinds
+        .into_iter()
+        .chain(std::iter::once(("total".to_string(), total)))
+        .map(|(kind, samples)| stats(kind, samples, elapsed))
+        .collect()
+}
+
+fn stats(kind: String, samples: Vec<(Duration, bool)>, elapsed: Duration) -> BenchStats {
+    let mut latencies = samples
+        .iter()
+        .map(|(latency, _)| *latency)
+        .collect::<Vec<_>>();
+    latencies.sort();
+    let percentile = |p: usize| {
+        latencies
+            .get((latencies.len() * p).div_ceil(100).saturating_sub(1))
+            .copied()
+            .unwrap_or_default()
+    };
+
+    BenchStats {
+        requests: samples.len(),
+        errors: samples.iter().filter(|(_, ok)| !ok).count(),
+        throughput: samples.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        p50: percentile(50),
+        p90: percentile(90),
+        p99: percentile(99),
+        max: latencies.last().copied().unwrap_or_default(),
+        kind,
+    }
+}
+
+/// Formats benchmark results as an aligned text table with a header row.
+pub fn format_report(stats: &[BenchStats]) -> String {
+    let headers = [
+        "KIND", "REQUESTS", "ERRORS", "REQ/S", "P50", "P90", "P99", "MAX",
+    ];
+    let millis = |latency: Duration| format!("{:.2}ms", latency.as_secs_f64() * 1000.0);
+    let rows = stats
+        .iter()
+        .map(|stats| {
+            [
+                stats.kind.clone(),
+                stats.requests.to_string(),
+                stats.errors.to_string(),
+                format!("{:.1}", stats.throughput),
+                millis(stats.p50),
+                millis(stats.p90),
+                millis(stats.p99),
+                millis(stats.max),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(headers.map(str::to_string))
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get};
+
+    fn route(method: &str, path: &str, kind: &str) -> RouteEntry {
+        RouteEntry {
+            method: method.to_string(),
+            path: path.to_string(),
+            source: String::new(),
+            kind: kind.to_string(),
+            protected: false,
+            delay: None,
+        }
+    }
+
+    #[test]
+    fn routes_files_list_methods_paths_and_bodies() {
+        let targets = parse_targets(
+            "# smoke\n/health\nget /users?page=2\n\nPOST /users {\"name\": \"Ada\"}\n",
+        )
+        .unwrap();
+
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[0].method, Method::GET);
+        assert_eq!(targets[0].path, "/health");
+        assert_eq!(targets[1].path, "/users?page=2");
+        assert_eq!(targets[2].method, Method::POST);
+        assert_eq!(targets[2].body.as_deref(), Some("{\"name\": \"Ada\"}"));
+
+        assert!(parse_targets("GET users").unwrap_err().contains("line 1"));
+        assert!(parse_targets("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn targets_take_the_kind_of_the_matching_route() {
+        let routes = [
+            route("GET", "/health", "basic"),
+            route("GET", "/users", "rest"),
+            route("GET", "/users/{id}", "rest"),
+        ];
+        let mut targets = parse_targets("/users/7\n/unknown\nDELETE /health").unwrap();
+        classify_targets(&mut targets, &routes);
+
+        let kinds = targets
+            .iter()
+            .map(|target| target.kind.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, ["rest", OTHER_KIND, OTHER_KIND]);
+        assert_eq!(default_targets(&routes).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn runs_report_every_kind_and_the_total() {
+        let router = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }));
+        let mut targets = parse_targets("/ok\n/fail").unwrap();
+        targets[0].kind = "basic".to_string();
+
+        let stats = run_bench(
+            BenchTransport::Router(router),
+            targets,
+            BenchOptions {
+                concurrency: 4,
+                requests: 10,
+            },
+        )
+        .await;
+
+        let kinds = stats
+            .iter()
+            .map(|stats| (stats.kind.as_str(), stats.requests, stats.errors))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [("basic", 5, 0), (OTHER_KIND, 5, 5), ("total", 10, 5)]
+        );
+        assert!(stats[2].max >= stats[2].p50);
+        assert!(format_report(&stats).starts_with("KIND "));
+    }
+
+    #[test]
+    fn server_urls_need_the_http_scheme() {
+        assert!(matches!(
+            BenchTransport::from_url("http://localhost:4520"),
+            Ok(BenchTransport::Http(address)) if address == "localhost:4520"
+        ));
+        assert!(BenchTransport::from_url("https://localhost").is_err());
+    }
+}
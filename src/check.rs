@@ -0,0 +1,360 @@
+//! Mock folder validation without starting the server.
+//!
+//! Backs the `check` command. Files are parsed, routes are registered against a
+//! throwaway [`App`] one at a time so conflicts are reported instead of
+//! aborting, and declared collection references are resolved after every
+//! collection has been loaded.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{
+    app::{App, describe_overlaps},
+    collection_files::load_collection_files,
+    handlers::{SCHEMA_FILE, compile_script, is_jgd, is_json, is_rhai, is_toml, load_schema_file},
+    route_builder::{Route, config::Config, route_manager::RouteManager},
+    schema_files::load_schema_files,
+};
+
+/// Category of a problem found in a mock folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// A `.json` or `.jgd` file is not valid JSON.
+    InvalidJson,
+    /// A `.toml` config file cannot be parsed.
+    InvalidToml,
+    /// A `.rhai` script does not compile.
+    InvalidScript,
+    /// A `schema.graphql` file is not a valid GraphQL schema.
+    InvalidGraphQLSchema,
+    /// A filename looks like a pattern but is not understood.
+    UnknownPattern,
+    /// A route cannot be registered, usually because it overlaps an earlier route.
+    RouteConflict,
+    /// A collection reference points to a collection that does not exist.
+    BrokenReference,
+}
+
+impl Display for ProblemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ProblemKind::InvalidJson => "invalid JSON",
+            ProblemKind::InvalidToml => "invalid TOML",
+            ProblemKind::InvalidScript => "invalid script",
+            ProblemKind::InvalidGraphQLSchema => "invalid GraphQL schema",
+            ProblemKind::UnknownPattern => "unknown pattern",
+            ProblemKind::RouteConflict => "route conflict",
+            ProblemKind::BrokenReference => "broken reference",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One problem found in a mock folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Problem category.
+    pub kind: ProblemKind,
+    /// File or directory the problem was found in.
+    pub path: String,
+    /// Human-readable description.
+    pub message: String,
+}
+
+impl Problem {
+    fn new(kind: ProblemKind, path: &Path, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            path: path.to_string_lossy().into_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: {}", self.kind, self.path, self.message)
+    }
+}
+
+/// Checks the mock folder of `config` and returns every problem found.
+pub fn check_folder(config: &Config) -> Vec<Problem> {
+    let mut app = App::new(config.clone());
+    app.quiet = true;
+
+    let mut problems = vec![];
+    for folder in app.get_folders() {
+        check_files(Path::new(&folder), &mut problems);
+    }
+    if !problems.is_empty() {
+        // Route discovery reads the same files and would stop at the first parse error.
+        return problems;
+    }
+
+    check_routes(app, config, &mut problems);
+    problems
+}
+
+fn check_files(dir: &Path, problems: &mut Vec<Problem>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            check_files(&path, problems);
+            continue;
+        }
+
+        let file_name = entry.file_name();
+        if is_json(&file_name) || is_jgd(&file_name) {
+            let parsed = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| {
+                    serde_json::from_str::<Value>(&text).map_err(|err| err.to_string())
+                });
+            if let Err(message) = parsed {
+                problems.push(Problem::new(ProblemKind::InvalidJson, &path, message));
+            }
+        } else if is_toml(&file_name) {
+            let parsed = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| {
+                    Config::try_from(text.as_str()).map_err(|err| err.message().to_string())
+                });
+            if let Err(message) = parsed {
+                problems.push(Problem::new(ProblemKind::InvalidToml, &path, message));
+            }
+        } else if is_rhai(&file_name)
+            && let Err(message) = compile_script(&path.clone().into_os_string())
+        {
+            problems.push(Problem::new(ProblemKind::InvalidScript, &path, message));
+        } else if file_name == SCHEMA_FILE
+            && let Some(Err(message)) = path
+                .parent()
+                .and_then(|folder| load_schema_file(&folder.as_os_str().to_os_string()))
+        {
+            problems.push(Problem::new(
+                ProblemKind::InvalidGraphQLSchema,
+                &path,
+                message,
+            ));
+        }
+    }
+}
+
+fn check_routes(mut app: App, config: &Config, problems: &mut Vec<Problem>) {
+    let found = problems.len();
+    let mut managers = vec![];
+    for (folder, mount_config) in app.get_folders().into_iter().zip(config.mount_configs()) {
+        match quietly(|| RouteManager::discover(&folder, Some(mount_config))) {
+            Ok(manager) => {
+                app.push_route_versions(&manager.versions);
+                managers.push(manager);
+            }
+            Err(message) => problems.push(Problem::new(
+                ProblemKind::RouteConflict,
+                Path::new(&folder),
+                message,
+            )),
+        }
+    }
+    if problems.len() > found {
+        return;
+    }
+
+    let routes = managers
+        .iter()
+        .flat_map(|manager| std::iter::once(&manager.auth_route).chain(&manager.routes))
+        .collect::<Vec<_>>();
+    for &route in &routes {
+        let Some(source) = route.source_path() else {
+            continue;
+        };
+        let source = Path::new(source);
+
+        let pattern_problem = match route {
+            Route::Basic(route_basic) => route_basic.pattern_problem(),
+            Route::Rest(route_rest) => route_rest.descriptor_problem(),
+            _ => None,
+        };
+        if let Some(message) = pattern_problem {
+            problems.push(Problem::new(ProblemKind::UnknownPattern, source, message));
+        }
+
+        // Overlaps are refused by `try_make_routes`. Other registration
+        // failures still panic, which also discards the router being built;
+        // restore it so later routes are checked against it.
+        let snapshot = app.router.borrow().clone();
+        match quietly(|| app.try_make_routes(route)) {
+            Ok(Ok(())) => {}
+            Ok(Err(overlaps)) => problems.push(Problem::new(
+                ProblemKind::RouteConflict,
+                source,
+                describe_overlaps(&overlaps),
+            )),
+            Err(message) => {
+                let _ = app.router.replace(snapshot);
+                problems.push(Problem::new(ProblemKind::RouteConflict, source, message));
+            }
+        }
+    }
+
+    for mount_config in config.mount_configs() {
+        let _ = load_schema_files(&app.db, &mount_config);
+        let _ = load_collection_files(&app.db, &mount_config, &|_| {});
+    }
+
+    for route in routes {
+        let Route::Rest(route_rest) = route else {
+            continue;
+        };
+        let refs = route_rest.refs.iter().collect::<BTreeMap<_, _>>();
+        for (field, ref_collection) in refs {
+            if app.db.get(ref_collection).is_none() {
+                problems.push(Problem::new(
+                    ProblemKind::BrokenReference,
+                    Path::new(&route_rest.path),
+                    format!(
+                        "field `{}` of `{}` references unknown collection `{}`",
+                        field, route_rest.collection_name, ref_collection
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Runs `f`, turning a panic into its message without printing it.
+pub(crate) fn quietly<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+
+    result.map_err(|payload| {
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|text| text.to_string()))
+            .unwrap_or_else(|| "route registration failed".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::ServerConfig;
+
+    fn check(files: &[(&str, &str)]) -> Vec<Problem> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (name, content) in files {
+            let path = temp_dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        check_folder(&Config {
+            server: Some(ServerConfig {
+                folder: Some(temp_dir.path().to_string_lossy().into_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    fn kinds(problems: &[Problem]) -> Vec<ProblemKind> {
+        problems.iter().map(|problem| problem.kind).collect()
+    }
+
+    #[test]
+    fn valid_folder_has_no_problems() {
+        let problems = check(&[
+            ("users/get.json", r#"{"id":1}"#),
+            ("users/get{id}.json", r#"{"id":1}"#),
+            ("posts/rest{_id-int}.json", "[]"),
+            ("posts/config.toml", "[route]\ndelay = 10\n"),
+        ]);
+
+        assert_eq!(problems, []);
+    }
+
+    #[test]
+    fn reports_invalid_json_and_toml() {
+        let problems = check(&[
+            ("users/get.json", r#"{"id":"#),
+            ("{collections}/tags.jgd", "not json"),
+            ("users/config.toml", "[route\n"),
+            ("users/post.rhai", "let x = ;"),
+            ("graphql/schema.graphql", "type Query { users: [User] }"),
+        ]);
+
+        assert_eq!(
+            kinds(&problems),
+            [
+                ProblemKind::InvalidGraphQLSchema,
+                ProblemKind::InvalidToml,
+                ProblemKind::InvalidJson,
+                ProblemKind::InvalidScript,
+                ProblemKind::InvalidJson
+            ]
+        );
+        assert!(problems[2].path.ends_with("get.json"));
+        assert!(problems[2].message.contains("line 1"));
+    }
+
+    #[test]
+    fn reports_unknown_patterns() {
+        let problems = check(&[
+            ("users/$list.json", "{}"),
+            ("users/get{5-1}.json", "{}"),
+            ("posts/rest{id-uid}.json", "[]"),
+        ]);
+
+        assert_eq!(kinds(&problems), [ProblemKind::UnknownPattern; 3]);
+        assert!(problems[2].message.contains("unknown id type `uid`"));
+    }
+
+    #[test]
+    fn reports_conflicting_routes_and_keeps_checking() {
+        let problems = check(&[
+            ("users/get.json", "{}"),
+            ("users.json", "{}"),
+            ("users/rest.json", "[]"),
+        ]);
+
+        assert_eq!(
+            kinds(&problems),
+            [ProblemKind::RouteConflict, ProblemKind::RouteConflict]
+        );
+        assert!(problems[0].message.contains("/users"));
+    }
+
+    #[test]
+    fn reports_references_to_unknown_collections() {
+        let problems = check(&[
+            ("posts/rest.json", "[]"),
+            (
+                "posts/rest.toml",
+                "[collection.refs]\nuserId = \"users\"\ntagId = \"tags\"\n",
+            ),
+            ("{collections}/tags.json", "[]"),
+        ]);
+
+        assert_eq!(kinds(&problems), [ProblemKind::BrokenReference]);
+        assert!(problems[0].message.contains("`userId`"));
+    }
+}
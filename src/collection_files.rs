@@ -1,4 +1,4 @@
-//! Startup loading helpers for Fosk collection seed files.
+//! Startup loading and generation helpers for Fosk collection seed files.
 
 use std::{
     ffi::OsString,
@@ -7,13 +7,19 @@ use std::{
     sync::Arc,
 };
 
+use chrono::Utc;
 use fosk::Db;
-use jgd_rs::generate_jgd_from_file;
+use jgd_rs::generate_jgd_from_str;
+use rayon::prelude::*;
+use serde_json::Value;
 
 use crate::{
     DEFAULT_COLLECTIONS_FOLDER,
-    handlers::{is_jgd, is_json},
+    handlers::{get_file_extension, is_jgd, is_json},
+    random,
     route_builder::config::Config,
+    seed_stream::load_seed_file,
+    templates::render_value,
 };
 
 /// Effective collection loading configuration with defaults applied.
@@ -44,7 +50,14 @@ pub fn resolve_collections_config(config: &Config) -> ResolvedCollectionsConfig
 }
 
 /// Loads collection seed files from the configured collection folder, if it exists.
-pub fn load_collection_files(db: &Arc<Db>, config: &Config) -> Result<Vec<String>, String> {
+///
+/// JSON files hold an array or one item per line, and `.ndjson` files one item
+/// per line. They are streamed, with progress on large files sent to `log`.
+pub fn load_collection_files(
+    db: &Arc<Db>,
+    config: &Config,
+    log: &(dyn Fn(String) + Sync),
+) -> Result<Vec<String>, String> {
     let resolved = resolve_collections_config(config);
     if !resolved.folder.exists() {
         return Ok(vec![]);
@@ -62,33 +75,47 @@ pub fn load_collection_files(db: &Arc<Db>, config: &Config) -> Result<Vec<String
         .collect::<Vec<_>>();
     entries.sort_by_key(|entry| entry.file_name());
 
-    let mut loaded = Vec::new();
-    for entry in entries {
-        let path = entry.path();
-        if !path.is_file() || !(is_jgd(&entry.file_name()) || is_json(&entry.file_name())) {
-            continue;
-        }
+    let paths = entries
+        .iter()
+        .filter(|entry| is_seed_file(&entry.file_name()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
 
-        loaded.push(load_collection_file(db, &path)?);
+    // Seeded runs load in order, so the generated data does not depend on
+    // which file a thread picks up first.
+    if random::current().is_seeded() {
+        paths
+            .iter()
+            .map(|path| load_collection_file(db, path, log))
+            .collect()
+    } else {
+        paths
+            .par_iter()
+            .map(|path| load_collection_file(db, path, log))
+            .collect()
     }
-
-    Ok(loaded)
 }
 
-fn load_collection_file(db: &Arc<Db>, path: &Path) -> Result<String, String> {
+fn load_collection_file(
+    db: &Arc<Db>,
+    path: &Path,
+    log: &(dyn Fn(String) + Sync),
+) -> Result<String, String> {
     let collection_name = collection_name_from_path(path)?;
     let collection = db.create(&collection_name);
 
     if is_jgd(&path_to_os_string(path)) {
-        let jgd_json = generate_jgd_from_file(&path.to_path_buf()).map_err(|error| {
+        let jgd_json = random::current().generate_jgd(path).map_err(|error| {
             format!(
                 "Error to generate JGD JSON for file {}. Details: {}",
                 path.to_string_lossy(),
                 error
             )
         })?;
+        let jgd_json = render_value(&jgd_json, Utc::now());
         let items = collection
-            .load_from_json(jgd_json, false)
+            .load_from_json(random::current().fill_ids(&collection, jgd_json), false)
             .map_err(|error| {
                 format!(
                     "Error to load JSON for file {}. Details: {}",
@@ -104,9 +131,102 @@ fn load_collection_file(db: &Arc<Db>, path: &Path) -> Result<String, String> {
         ));
     }
 
-    collection
-        .load_from_file(&path_to_os_string(path))
-        .map_err(|error| error.to_string())
+    let progress = |loaded: usize| {
+        log(format!(
+            "… {} items loaded into {} from {}",
+            loaded,
+            collection_name,
+            path.to_string_lossy()
+        ))
+    };
+    let loaded = load_seed_file(&collection, None, path, &mut |_| {}, &progress)
+        .map_err(|error| error.to_string())?;
+    Ok(format!(
+        "✔️ Loaded collection {} with {} initial items from {}",
+        collection_name,
+        loaded,
+        path.to_string_lossy()
+    ))
+}
+
+/// Options for generating a collection seed file from a JGD template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedGeneration {
+    /// Collection name, also used as the seed file name.
+    pub collection: String,
+    /// JGD template file.
+    pub template: PathBuf,
+    /// Number of items to generate, overriding the template count.
+    pub count: Option<u64>,
+    /// Random seed for reproducible output, overriding the template seed.
+    pub seed: Option<u64>,
+}
+
+/// Generates items from a JGD template and writes them to `<collection>.json`
+/// in the configured collection folder, returning the file path and item count.
+///
+/// Templates with a `root` entity generate the items from it. Templates with
+/// `entities` generate the entity named after the collection.
+pub fn generate_collection_file(
+    config: &Config,
+    generation: &SeedGeneration,
+) -> Result<(PathBuf, usize), String> {
+    let template_path = generation.template.to_string_lossy();
+    let content = fs::read_to_string(&generation.template)
+        .map_err(|err| format!("Could not read template {}: {}", template_path, err))?;
+    let mut template = serde_json::from_str::<Value>(&content)
+        .ok()
+        .filter(Value::is_object)
+        .ok_or_else(|| format!("Template {} is not a JGD document", template_path))?;
+
+    if let Some(seed) = generation.seed {
+        template["seed"] = Value::from(seed);
+    }
+    let entity_pointer = if template.get("root").is_some() {
+        "/root".to_string()
+    } else {
+        format!("/entities/{}", generation.collection)
+    };
+    let Some(entity) = template.pointer_mut(&entity_pointer) else {
+        return Err(format!(
+            "Template {} has no root or '{}' entity",
+            template_path, generation.collection
+        ));
+    };
+    if let Some(count) = generation.count {
+        entity["count"] = Value::from(count);
+    }
+
+    let generated = generate_jgd_from_str(&template.to_string()).map_err(|err| {
+        format!(
+            "Error to generate JGD JSON for file {}. Details: {}",
+            template_path, err
+        )
+    })?;
+    let items = match entity_pointer.as_str() {
+        "/root" => generated,
+        _ => generated
+            .get(&generation.collection)
+            .cloned()
+            .unwrap_or_default(),
+    };
+    let Value::Array(items) = items else {
+        return Err(format!(
+            "Template {} does not generate a list; set a count",
+            template_path
+        ));
+    };
+
+    let folder = resolve_collections_config(config).folder;
+    fs::create_dir_all(&folder)
+        .map_err(|err| format!("Could not create collection folder: {err}"))?;
+    let path = folder.join(format!("{}.json", generation.collection));
+    let count = items.len();
+    let json = serde_json::to_string_pretty(&Value::Array(items)).unwrap();
+    fs::write(&path, json)
+        .map_err(|err| format!("Could not write {}: {}", path.to_string_lossy(), err))?;
+
+    Ok((path, count))
 }
 
 fn collection_name_from_path(path: &Path) -> Result<String, String> {
@@ -117,6 +237,10 @@ fn collection_name_from_path(path: &Path) -> Result<String, String> {
         .ok_or_else(|| format!("Invalid collection file name: {}", path.to_string_lossy()))
 }
 
+fn is_seed_file(file_name: &OsString) -> bool {
+    is_jgd(file_name) || is_json(file_name) || get_file_extension(file_name) == "ndjson"
+}
+
 fn path_to_os_string(path: &Path) -> OsString {
     OsString::from(path.to_string_lossy().into_owned())
 }
@@ -182,6 +306,12 @@ mod tests {
             .to_string(),
         )
         .unwrap();
+        fs::write(
+            collections.join("warehouse_operators.json"),
+            json!([{ "id": "op-1", "name": "{{faker.name}}", "shift": "{{faker.int 2 2}}" }])
+                .to_string(),
+        )
+        .unwrap();
         fs::write(
             collections.join("warehouse_assets.jgd"),
             json!({
@@ -208,10 +338,13 @@ mod tests {
             }),
             ..Default::default()
         };
-        let loaded = load_collection_files(&db, &config).unwrap();
+        let loaded = load_collection_files(&db, &config, &|_| {}).unwrap();
 
-        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.len(), 3);
         assert_eq!(db.get("warehouse_locations").unwrap().count().unwrap(), 2);
+        let operators = db.get("warehouse_operators").unwrap().get_all().unwrap();
+        assert!(operators[0]["name"].as_str().unwrap() != "{{faker.name}}");
+        assert_eq!(operators[0]["shift"], 2);
         assert_eq!(db.get("warehouse_assets").unwrap().count().unwrap(), 3);
     }
 
@@ -230,7 +363,7 @@ mod tests {
             }),
             ..Default::default()
         };
-        let loaded = load_collection_files(&db, &config).unwrap();
+        let loaded = load_collection_files(&db, &config, &|_| {}).unwrap();
 
         assert!(loaded.is_empty());
         assert!(db.list_collections().is_empty());
@@ -251,8 +384,87 @@ mod tests {
             }),
             ..Default::default()
         };
-        let error = load_collection_files(&db, &config).unwrap_err();
+        let error = load_collection_files(&db, &config, &|_| {}).unwrap_err();
 
         assert!(error.contains("is not a directory"));
     }
+
+    fn mocks_config(temp_dir: &TempDir) -> Config {
+        Config {
+            server: Some(ServerConfig {
+                folder: Some(temp_dir.path().join("mocks").to_string_lossy().into_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn generates_seed_file_from_root_template_reproducibly() {
+        let temp_dir = TempDir::new().unwrap();
+        let template = temp_dir.path().join("users.jgd");
+        fs::write(
+            &template,
+            json!({
+                "$format": "jgd/v1",
+                "version": "1.0",
+                "root": {
+                    "count": 2,
+                    "fields": { "name": "${name.firstName}", "city": "${address.cityName}" }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let config = mocks_config(&temp_dir);
+        let generation = SeedGeneration {
+            collection: "users".to_string(),
+            template,
+            count: Some(25),
+            seed: Some(7),
+        };
+
+        let (path, count) = generate_collection_file(&config, &generation).unwrap();
+        assert_eq!(path, temp_dir.path().join("mocks/{collections}/users.json"));
+        assert_eq!(count, 25);
+        let first = fs::read_to_string(&path).unwrap();
+
+        generate_collection_file(&config, &generation).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), first);
+
+        let db = Db::new_arc();
+        load_collection_files(&db, &config, &|_| {}).unwrap();
+        assert_eq!(db.get("users").unwrap().count().unwrap(), 25);
+    }
+
+    #[test]
+    fn generates_seed_file_from_named_entity() {
+        let temp_dir = TempDir::new().unwrap();
+        let template = temp_dir.path().join("shop.jgd");
+        fs::write(
+            &template,
+            json!({
+                "$format": "jgd/v1",
+                "version": "1.0",
+                "entities": {
+                    "products": { "count": 3, "fields": { "id": "${uuid.v4}" } }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let config = mocks_config(&temp_dir);
+        let mut generation = SeedGeneration {
+            collection: "products".to_string(),
+            template,
+            count: None,
+            seed: None,
+        };
+
+        assert_eq!(generate_collection_file(&config, &generation).unwrap().1, 3);
+
+        generation.collection = "orders".to_string();
+        let error = generate_collection_file(&config, &generation).unwrap_err();
+        assert!(error.contains("has no root or 'orders' entity"));
+    }
 }
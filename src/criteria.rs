@@ -0,0 +1,763 @@
+//! Criteria language used to filter REST collection listings with `?where=`.
+//!
+//! A criteria expression combines constraints with `AND`, `OR`, `NOT`, and
+//! parentheses, for example:
+//!
+//! ```text
+//! status IN ("active", "pending") AND NOT (age BETWEEN 18 AND 30 OR vip = true)
+//! ```
+//!
+//! Values are JSON-like literals: quoted strings, numbers, `true`, `false`,
+//! and `null`. Fields may be dot paths into nested documents, such as
+//! `address.city` or `items[0].sku`, and a missing field compares as `null`.
+
+use std::{cmp::Ordering, fmt::Display};
+
+use serde_json::{Number, Value};
+
+/// Error returned when a criteria expression cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CriteriaError {
+    /// The expression ended where more input was expected.
+    UnexpectedEnd {
+        /// Description of the expected input.
+        expected: String,
+    },
+    /// A token did not fit the grammar at this position.
+    UnexpectedToken {
+        /// The offending token.
+        token: String,
+        /// Description of the expected input.
+        expected: String,
+    },
+    /// A quoted string was not closed.
+    UnterminatedString,
+    /// A character that cannot start a token was found.
+    InvalidCharacter {
+        /// The offending character.
+        character: char,
+    },
+}
+
+impl Display for CriteriaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CriteriaError::UnexpectedEnd { expected } => {
+                write!(f, "unexpected end of criteria, expected {}", expected)
+            }
+            CriteriaError::UnexpectedToken { token, expected } => {
+                write!(f, "unexpected '{}', expected {}", token, expected)
+            }
+            CriteriaError::UnterminatedString => write!(f, "unterminated string literal"),
+            CriteriaError::InvalidCharacter { character } => {
+                write!(f, "invalid character '{}'", character)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CriteriaError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(Number),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Text(text) => write!(f, "\"{}\"", text),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 10] = ["<=", ">=", "!=", "<>", "=", "<", ">", "(", ")", ","];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, CriteriaError> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        let character = chars[index];
+        if character.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        if character == '"' || character == '\'' {
+            let mut value = String::new();
+            index += 1;
+            loop {
+                match chars.get(index) {
+                    None => return Err(CriteriaError::UnterminatedString),
+                    Some('\\') if index + 1 < chars.len() => {
+                        value.push(chars[index + 1]);
+                        index += 2;
+                    }
+                    Some(&quote) if quote == character => {
+                        index += 1;
+                        break;
+                    }
+                    Some(&other) => {
+                        value.push(other);
+                        index += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Text(value));
+            continue;
+        }
+
+        if character.is_ascii_digit()
+            || (character == '-' && chars.get(index + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = index;
+            index += 1;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            let literal = chars[start..index].iter().collect::<String>();
+            let number = literal
+                .parse::<i64>()
+                .map(Number::from)
+                .ok()
+                .or_else(|| literal.parse::<f64>().ok().and_then(Number::from_f64))
+                .ok_or(CriteriaError::InvalidCharacter { character })?;
+            tokens.push(Token::Number(number));
+            continue;
+        }
+
+        if character.is_alphabetic() || character == '_' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_alphanumeric() || matches!(chars[index], '_' | '.' | '[' | ']'))
+            {
+                index += 1;
+            }
+            tokens.push(Token::Word(chars[start..index].iter().collect()));
+            continue;
+        }
+
+        let rest = chars[index..].iter().take(2).collect::<String>();
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+            Some(symbol) => {
+                tokens.push(Token::Symbol(symbol));
+                index += symbol.len();
+            }
+            None => return Err(CriteriaError::InvalidCharacter { character }),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Comparison applied by a [`Constraint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparer {
+    /// `=`
+    Equal,
+    /// `!=` or `<>`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+    /// `LIKE`, with `%` matching any run of characters and `_` one character.
+    Like,
+    /// `ILIKE`, a `LIKE` that ignores case.
+    ILike,
+    /// `IN (a, b, ...)`
+    In,
+    /// `NOT IN (a, b, ...)`
+    NotIn,
+    /// `BETWEEN low AND high`, inclusive.
+    Between,
+}
+
+impl Comparer {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "=" => Some(Comparer::Equal),
+            "!=" | "<>" => Some(Comparer::NotEqual),
+            "<" => Some(Comparer::Less),
+            "<=" => Some(Comparer::LessOrEqual),
+            ">" => Some(Comparer::Greater),
+            ">=" => Some(Comparer::GreaterOrEqual),
+            _ => None,
+        }
+    }
+
+    /// Applies this comparison to a field value and the constraint operands.
+    ///
+    /// With `case_insensitive`, string equality, `IN`, and `LIKE` ignore case;
+    /// ordering comparisons are unaffected.
+    pub fn compare(&self, value: &Value, operands: &[Value], case_insensitive: bool) -> bool {
+        let first = operands.first().unwrap_or(&Value::Null);
+        let equal = |operand: &Value| values_equal(value, operand, case_insensitive);
+        match self {
+            Comparer::Equal => equal(first),
+            Comparer::NotEqual => !equal(first),
+            Comparer::Less => order(value, first) == Some(Ordering::Less),
+            Comparer::LessOrEqual => {
+                matches!(order(value, first), Some(Ordering::Less | Ordering::Equal))
+            }
+            Comparer::Greater => order(value, first) == Some(Ordering::Greater),
+            Comparer::GreaterOrEqual => {
+                matches!(
+                    order(value, first),
+                    Some(Ordering::Greater | Ordering::Equal)
+                )
+            }
+            Comparer::Like | Comparer::ILike => match (value, first) {
+                (Value::String(text), Value::String(pattern))
+                    if case_insensitive || *self == Comparer::ILike =>
+                {
+                    wildcard_matches(&text.to_lowercase(), &pattern.to_lowercase(), '%', '_')
+                }
+                (Value::String(text), Value::String(pattern)) => {
+                    wildcard_matches(text, pattern, '%', '_')
+                }
+                _ => false,
+            },
+            Comparer::In => operands.iter().any(equal),
+            Comparer::NotIn => !operands.iter().any(equal),
+            Comparer::Between => {
+                let high = operands.get(1).unwrap_or(&Value::Null);
+                matches!(
+                    order(value, first),
+                    Some(Ordering::Greater | Ordering::Equal)
+                ) && matches!(order(value, high), Some(Ordering::Less | Ordering::Equal))
+            }
+        }
+    }
+}
+
+fn values_equal(left: &Value, right: &Value, case_insensitive: bool) -> bool {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64() == right.as_f64(),
+        (Value::String(left), Value::String(right)) if case_insensitive => {
+            left.to_lowercase() == right.to_lowercase()
+        }
+        _ => left == right,
+    }
+}
+
+/// Orders two numbers, strings, or booleans; other values are unordered.
+pub fn order(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+/// Matches wildcards where `any` stands for any characters and `one` for a
+/// single character, backtracking to the last `any` on a mismatch.
+///
+/// Queries use the SQL `LIKE` pair `%` and `_`; file and path globs use `*`
+/// and `?`.
+pub(crate) fn wildcard_matches(text: &str, pattern: &str, any: char, one: char) -> bool {
+    let text = text.chars().collect::<Vec<_>>();
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == one || pattern[p] == text[t]) {
+            t += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == any {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|character| *character == any)
+}
+
+/// Resolves a field path such as `address.city` or `items[0].sku` in an item.
+///
+/// A key that literally contains the whole path wins over traversal. Array
+/// elements are addressed with `[index]` or a numeric `.index` segment.
+pub fn field_value<'a>(item: &'a Value, path: &str) -> Option<&'a Value> {
+    if let Some(value) = item.get(path) {
+        return Some(value);
+    }
+
+    let mut current = item;
+    for segment in path.split('.') {
+        let (key, indexes) = match segment.find('[') {
+            Some(start) => (&segment[..start], &segment[start..]),
+            None => (segment, ""),
+        };
+
+        if !key.is_empty() {
+            current = match current {
+                Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => current.get(key)?,
+            };
+        }
+
+        for index in indexes.split('[').skip(1) {
+            let index = index.strip_suffix(']')?.parse::<usize>().ok()?;
+            current = current.get(index)?;
+        }
+    }
+
+    Some(current)
+}
+
+/// A single comparison between an item field and literal operands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    /// Compared field name or dot path.
+    pub field: String,
+    /// Comparison to apply.
+    pub comparer: Comparer,
+    /// Literal operands: one for binary comparisons, two for `BETWEEN`,
+    /// and any number for `IN`.
+    pub values: Vec<Value>,
+}
+
+impl Constraint {
+    /// Returns true when the item satisfies this constraint.
+    pub fn matches(&self, item: &Value) -> bool {
+        self.compare_item(item, false)
+    }
+
+    /// Returns true when the item satisfies this constraint, optionally ignoring string case.
+    pub fn compare_item(&self, item: &Value, case_insensitive: bool) -> bool {
+        let value = field_value(item, &self.field).unwrap_or(&Value::Null);
+        self.comparer.compare(value, &self.values, case_insensitive)
+    }
+}
+
+impl TryFrom<&str> for Constraint {
+    type Error = CriteriaError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut builder = CriteriaBuilder::new(tokenize(value)?);
+        let constraint = builder.constraint()?;
+        builder.expect_end()?;
+        Ok(constraint)
+    }
+}
+
+/// Parsed criteria expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Criteria {
+    /// A single constraint.
+    Constraint(Constraint),
+    /// All nested criteria must match.
+    And(Vec<Criteria>),
+    /// At least one nested criteria must match.
+    Or(Vec<Criteria>),
+    /// The nested criteria must not match.
+    Not(Box<Criteria>),
+}
+
+impl Criteria {
+    /// Returns true when the item satisfies this criteria.
+    pub fn matches(&self, item: &Value) -> bool {
+        self.matches_with(item, false)
+    }
+
+    /// Returns true when the item satisfies this criteria, optionally ignoring string case.
+    pub fn matches_with(&self, item: &Value, case_insensitive: bool) -> bool {
+        let matches = |criteria: &Criteria| criteria.matches_with(item, case_insensitive);
+        match self {
+            Criteria::Constraint(constraint) => constraint.compare_item(item, case_insensitive),
+            Criteria::And(criteria) => criteria.iter().all(matches),
+            Criteria::Or(criteria) => criteria.iter().any(matches),
+            Criteria::Not(criteria) => !matches(criteria),
+        }
+    }
+}
+
+impl TryFrom<&str> for Criteria {
+    type Error = CriteriaError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        CriteriaBuilder::parse(value)
+    }
+}
+
+/// Recursive-descent parser that builds [`Criteria`] from an expression.
+///
+/// `NOT` binds tighter than `AND`, which binds tighter than `OR`; parentheses
+/// group sub-expressions.
+pub struct CriteriaBuilder {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl CriteriaBuilder {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    /// Parses a complete criteria expression.
+    pub fn parse(text: &str) -> Result<Criteria, CriteriaError> {
+        let mut builder = Self::new(tokenize(text)?);
+        let criteria = builder.or_expression()?;
+        builder.expect_end()?;
+        Ok(criteria)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, CriteriaError> {
+        let token = self.tokens.get(self.position).cloned().ok_or_else(|| {
+            CriteriaError::UnexpectedEnd {
+                expected: expected.to_string(),
+            }
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().is_some_and(|token| token.is_keyword(keyword)) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), CriteriaError> {
+        let token = self.next(&expected.to_string())?;
+        let matches = match (&token, expected) {
+            (Token::Word(word), Token::Word(keyword)) => word.eq_ignore_ascii_case(keyword),
+            _ => &token == expected,
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected: expected.to_string(),
+            })
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), CriteriaError> {
+        match self.peek() {
+            Some(token) => Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected: "AND, OR, or the end of the criteria".to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn or_expression(&mut self) -> Result<Criteria, CriteriaError> {
+        let mut criteria = vec![self.and_expression()?];
+        while self.eat_keyword("OR") {
+            criteria.push(self.and_expression()?);
+        }
+        Ok(if criteria.len() == 1 {
+            criteria.remove(0)
+        } else {
+            Criteria::Or(criteria)
+        })
+    }
+
+    fn and_expression(&mut self) -> Result<Criteria, CriteriaError> {
+        let mut criteria = vec![self.primary()?];
+        while self.eat_keyword("AND") {
+            criteria.push(self.primary()?);
+        }
+        Ok(if criteria.len() == 1 {
+            criteria.remove(0)
+        } else {
+            Criteria::And(criteria)
+        })
+    }
+
+    fn primary(&mut self) -> Result<Criteria, CriteriaError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Criteria::Not(Box::new(self.primary()?)));
+        }
+        if self.peek() == Some(&Token::Symbol("(")) {
+            self.position += 1;
+            let criteria = self.or_expression()?;
+            self.expect(&Token::Symbol(")"))?;
+            return Ok(criteria);
+        }
+        Ok(Criteria::Constraint(self.constraint()?))
+    }
+
+    fn field(&mut self) -> Result<String, CriteriaError> {
+        match self.next("a field name")? {
+            Token::Word(word) => Ok(word),
+            token => Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected: "a field name".to_string(),
+            }),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, CriteriaError> {
+        match self.next("a value")? {
+            Token::Text(text) => Ok(Value::String(text)),
+            Token::Number(number) => Ok(Value::Number(number)),
+            token if token.is_keyword("true") => Ok(Value::Bool(true)),
+            token if token.is_keyword("false") => Ok(Value::Bool(false)),
+            token if token.is_keyword("null") => Ok(Value::Null),
+            token => Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected: "a value".to_string(),
+            }),
+        }
+    }
+
+    fn value_list(&mut self) -> Result<Vec<Value>, CriteriaError> {
+        self.expect(&Token::Symbol("("))?;
+        let mut values = vec![self.value()?];
+        while self.peek() == Some(&Token::Symbol(",")) {
+            self.position += 1;
+            values.push(self.value()?);
+        }
+        self.expect(&Token::Symbol(")"))?;
+        Ok(values)
+    }
+
+    fn constraint(&mut self) -> Result<Constraint, CriteriaError> {
+        let field = self.field()?;
+        let expected = "a comparison operator";
+        let token = self.next(expected)?;
+
+        let (comparer, values) = match &token {
+            Token::Symbol(symbol) if Comparer::from_symbol(symbol).is_some() => {
+                (Comparer::from_symbol(symbol).unwrap(), vec![self.value()?])
+            }
+            token if token.is_keyword("LIKE") => (Comparer::Like, vec![self.value()?]),
+            token if token.is_keyword("ILIKE") => (Comparer::ILike, vec![self.value()?]),
+            token if token.is_keyword("IN") => (Comparer::In, self.value_list()?),
+            token if token.is_keyword("NOT") => {
+                self.expect(&Token::Word("IN".to_string()))?;
+                (Comparer::NotIn, self.value_list()?)
+            }
+            token if token.is_keyword("BETWEEN") => {
+                let low = self.value()?;
+                self.expect(&Token::Word("AND".to_string()))?;
+                (Comparer::Between, vec![low, self.value()?])
+            }
+            token if token.is_keyword("IS") => {
+                let comparer = if self.eat_keyword("NOT") {
+                    Comparer::NotEqual
+                } else {
+                    Comparer::Equal
+                };
+                self.expect(&Token::Word("NULL".to_string()))?;
+                (comparer, vec![Value::Null])
+            }
+            token => {
+                return Err(CriteriaError::UnexpectedToken {
+                    token: token.to_string(),
+                    expected: expected.to_string(),
+                });
+            }
+        };
+
+        Ok(Constraint {
+            field,
+            comparer,
+            values,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn matches(criteria: &str, item: Value) -> bool {
+        CriteriaBuilder::parse(criteria).unwrap().matches(&item)
+    }
+
+    #[test]
+    fn constraint_parses_in_not_in_and_between() {
+        assert_eq!(
+            Constraint::try_from(r#"status IN ("a", 'b')"#).unwrap(),
+            Constraint {
+                field: "status".to_string(),
+                comparer: Comparer::In,
+                values: vec![json!("a"), json!("b")],
+            }
+        );
+        assert_eq!(
+            Constraint::try_from("id not in (1, 2.5)").unwrap().comparer,
+            Comparer::NotIn
+        );
+        assert_eq!(
+            Constraint::try_from("age BETWEEN 18 AND 30")
+                .unwrap()
+                .values,
+            vec![json!(18), json!(30)]
+        );
+        assert!(Constraint::try_from("age BETWEEN 18").is_err());
+        assert!(Constraint::try_from("a = 1 AND b = 2").is_err());
+    }
+
+    #[test]
+    fn comparers_match_items() {
+        let item = json!({ "status": "active", "age": 21, "name": "Ada Lovelace", "vip": false });
+
+        assert!(matches(r#"status IN ("active", "pending")"#, item.clone()));
+        assert!(!matches(r#"status NOT IN ("active")"#, item.clone()));
+        assert!(matches("age BETWEEN 18 AND 21.0", item.clone()));
+        assert!(!matches("age BETWEEN 22 AND 30", item.clone()));
+        assert!(matches(r#"name LIKE "Ada%ce""#, item.clone()));
+        assert!(matches(r#"name LIKE "_da %""#, item.clone()));
+        assert!(!matches(r#"name LIKE "ada%""#, item.clone()));
+        assert!(matches("missing = null AND vip != true", item.clone()));
+        assert!(matches("age >= 21 AND age < 22", item));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or_and_parentheses_group() {
+        let item = json!({ "a": 1, "b": 2, "c": 3 });
+
+        assert!(matches("a = 0 AND b = 0 OR c = 3", item.clone()));
+        assert!(!matches("a = 0 AND (b = 0 OR c = 3)", item.clone()));
+        assert!(matches("(a = 1 OR a = 2) AND c IN (3)", item));
+    }
+
+    #[test]
+    fn wildcards_match_with_either_pair_of_characters() {
+        assert!(wildcard_matches(
+            "mocks/users/get.json",
+            "mocks/*/get.js?n",
+            '*',
+            '?'
+        ));
+        assert!(wildcard_matches("report.csv", "*.csv", '*', '?'));
+        assert!(!wildcard_matches("report.csv.bak", "*.csv", '*', '?'));
+        assert!(wildcard_matches("Ada Lovelace", "_da%ce", '%', '_'));
+        assert!(!wildcard_matches("Ada*", "Ada%", '*', '?'));
+    }
+
+    #[test]
+    fn ilike_and_case_insensitive_matching_ignore_string_case() {
+        let item = json!({ "name": "Ada Lovelace", "tags": "Admin", "age": 21 });
+        let criteria = CriteriaBuilder::parse(r#"name = "ada lovelace""#).unwrap();
+
+        assert!(matches(r#"name ILIKE "ada%""#, item.clone()));
+        assert!(!matches(r#"name LIKE "ada%""#, item.clone()));
+        assert!(!criteria.matches(&item));
+        assert!(criteria.matches_with(&item, true));
+        assert!(
+            CriteriaBuilder::parse(r#"name LIKE "%LOVE%" AND tags IN ("admin") AND age = 21"#)
+                .unwrap()
+                .matches_with(&item, true)
+        );
+        assert!(
+            !CriteriaBuilder::parse(r#"tags NOT IN ("ADMIN")"#)
+                .unwrap()
+                .matches_with(&item, true)
+        );
+    }
+
+    #[test]
+    fn dot_paths_reach_nested_objects_and_arrays() {
+        let item = json!({
+            "address": { "city": "Paris", "geo": { "lat": 48.8 } },
+            "items": [{ "sku": "AB-1" }, { "sku": "CD-2", "tags": ["x", "y"] }],
+            "plain.key": 1
+        });
+
+        assert!(matches(r#"address.city = "Paris""#, item.clone()));
+        assert!(matches("address.geo.lat > 48", item.clone()));
+        assert!(matches(r#"items[0].sku LIKE "AB%""#, item.clone()));
+        assert!(matches(r#"items.1.tags[1] = "y""#, item.clone()));
+        assert!(matches("plain.key = 1", item.clone()));
+        assert!(matches(
+            "address.zip IS NULL AND items[5].sku IS NULL",
+            item.clone()
+        ));
+        assert!(field_value(&item, "items[x]").is_none());
+        assert_eq!(
+            Constraint::try_from("items[0].sku = 1").unwrap().field,
+            "items[0].sku"
+        );
+    }
+
+    #[test]
+    fn not_negates_constraints_and_groups() {
+        let item = json!({ "a": 1, "b": 2, "c": null });
+
+        assert_eq!(
+            CriteriaBuilder::parse("NOT a = 1").unwrap(),
+            Criteria::Not(Box::new(Criteria::Constraint(Constraint {
+                field: "a".to_string(),
+                comparer: Comparer::Equal,
+                values: vec![json!(1)],
+            })))
+        );
+        assert!(!matches("NOT (a = 1 AND b = 2)", item.clone()));
+        assert!(matches("NOT (a = 1 AND b = 3)", item.clone()));
+        assert!(matches("NOT a = 2 AND b = 2", item.clone()));
+        assert!(!matches("NOT a = 1 OR b = 3", item.clone()));
+        assert!(matches("NOT NOT a = 1", item.clone()));
+        assert!(matches("NOT (NOT (a = 1) OR b = 3)", item.clone()));
+    }
+
+    #[test]
+    fn null_comparisons_cover_missing_and_null_fields() {
+        let item = json!({ "a": 1, "c": null });
+
+        assert!(matches("a != NULL AND a IS NOT NULL", item.clone()));
+        assert!(matches("c = NULL AND c IS NULL", item.clone()));
+        assert!(matches("missing IS NULL", item.clone()));
+        assert!(!matches("missing <> null", item.clone()));
+        assert!(CriteriaBuilder::parse("a IS 1").is_err());
+    }
+
+    #[test]
+    fn parse_errors_describe_the_problem() {
+        assert_eq!(
+            CriteriaBuilder::parse("status = ").unwrap_err().to_string(),
+            "unexpected end of criteria, expected a value"
+        );
+        assert_eq!(
+            CriteriaBuilder::parse(r#"status = "open"#).unwrap_err(),
+            CriteriaError::UnterminatedString
+        );
+        assert_eq!(
+            CriteriaBuilder::parse("status ~ 1").unwrap_err(),
+            CriteriaError::InvalidCharacter { character: '~' }
+        );
+        assert!(CriteriaBuilder::parse("(a = 1").is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Custom bodies for unmatched routes and error responses.
+//!
+//! A `__404.json` file in the mock root, or the file set with
+//! `[server] not_found_body`, is returned for requests no route matches. A
+//! `__error.json` file, or `[server] error_body`, replaces the body of every
+//! other `4xx` and `5xx` response. Both are JSON templates: besides the usual
+//! placeholders, `{{status}}`, `{{error}}`, `{{message}}`, `{{method}}` and
+//! `{{path}}` describe the failed request.
+//!
+//! With `[server] problem_details = true`, errors without a template are sent
+//! as RFC 7807 `application/problem+json` documents instead.
+
+use std::{collections::HashMap, path::Path, pin::Pin, sync::Arc};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use http::{
+    HeaderValue, Method, StatusCode,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+};
+use serde_json::{Value, json};
+
+use crate::{
+    DEFAULT_ERROR_BODY_FILE, DEFAULT_NOT_FOUND_BODY_FILE,
+    response_validation::{read_contract, resolve_contract_path},
+    route_builder::config::Config,
+    templates::render_value_with,
+};
+
+/// Marks a response whose body was already rendered from a template.
+#[derive(Debug, Clone, Copy)]
+struct RenderedErrorBody;
+
+/// Marks an error response whose body is sent as is, such as a proxied
+/// backend response or an S3 XML error that clients parse.
+#[derive(Debug, Clone, Copy)]
+pub struct VerbatimErrorBody;
+
+/// Content type of RFC 7807 problem documents.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// How RFC 7807 problem documents name their `type`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProblemDetails {
+    /// Prefix completed with the error code, such as `https://errors.example.com/`.
+    pub type_base: Option<String>,
+    /// `type` URIs by error code or status.
+    pub types: HashMap<String, String>,
+}
+
+impl ProblemDetails {
+    /// Reads the `problem_*` settings, or returns `None` when problem
+    /// documents are not enabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let server = config.server.clone().unwrap_or_default();
+        server.problem_details.unwrap_or(false).then(|| Self {
+            type_base: server.problem_type_base,
+            types: server.problem_types.unwrap_or_default(),
+        })
+    }
+
+    /// Returns the `type` URI of an error, by code, then status, then base,
+    /// falling back to `about:blank`.
+    fn type_uri(&self, error: &str, status: StatusCode) -> String {
+        self.types
+            .get(error)
+            .or_else(|| self.types.get(status.as_str()))
+            .cloned()
+            .or_else(|| {
+                self.type_base
+                    .as_ref()
+                    .map(|base| format!("{}{}", base, error))
+            })
+            .unwrap_or_else(|| "about:blank".to_string())
+    }
+
+    fn render(&self, details: ErrorDetails, mut parts: http::response::Parts) -> Response {
+        let body = json!({
+            "type": self.type_uri(&details.error, details.status),
+            "title": details.status.canonical_reason().unwrap_or("Error"),
+            "status": details.status.as_u16(),
+            "detail": details.message,
+            "instance": details.path,
+            "code": details.error,
+        });
+
+        parts.headers.remove(CONTENT_LENGTH);
+        parts
+            .headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        parts.extensions.insert(RenderedErrorBody);
+        Response::from_parts(parts, Body::from(body.to_string()))
+    }
+}
+
+/// Error body templates loaded from the mock root or the server config.
+#[derive(Debug, Default)]
+pub struct ErrorBodies {
+    not_found: Option<Value>,
+    error: Option<Value>,
+    problem: Option<ProblemDetails>,
+}
+
+impl ErrorBodies {
+    /// Loads the configured templates, falling back to `__404.json` and
+    /// `__error.json` in the mock root.
+    ///
+    /// Returns a message for each loaded template, or an error when a file
+    /// cannot be read or is not valid JSON.
+    pub fn load(config: &Config) -> Result<(Self, Vec<String>), String> {
+        let server = config.server.clone().unwrap_or_default();
+        let mut messages = vec![];
+        let mut load = |configured: Option<String>, default_file: &str| {
+            let path = resolve_contract_path(config, configured.as_deref().unwrap_or(default_file));
+            if configured.is_none() && !path.is_file() {
+                return Ok(None);
+            }
+            let template = read_contract(&path)?;
+            messages.push(loaded_message(&path));
+            Ok::<_, String>(Some(template))
+        };
+
+        let bodies = Self {
+            not_found: load(server.not_found_body, DEFAULT_NOT_FOUND_BODY_FILE)?,
+            error: load(server.error_body, DEFAULT_ERROR_BODY_FILE)?,
+            problem: ProblemDetails::from_config(config),
+        };
+        Ok((bodies, messages))
+    }
+
+    /// Returns true when an error body template is loaded or problem
+    /// documents are enabled.
+    pub fn is_active(&self) -> bool {
+        self.error.is_some() || self.problem.is_some()
+    }
+
+    /// Returns the `404 Not Found` response rendered from the not-found
+    /// template or as a problem document, if either is set up.
+    pub fn not_found(&self, method: &Method, path: &str) -> Option<Response> {
+        let status = StatusCode::NOT_FOUND;
+        let details = ErrorDetails {
+            status,
+            error: default_error_code(status),
+            message: "No route matches the request".to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+        };
+        let parts = status.into_response().into_parts().0;
+        match (&self.not_found, &self.problem) {
+            (Some(template), _) => Some(render(template, details, parts)),
+            (None, Some(problem)) => Some(problem.render(details, parts)),
+            (None, None) => None,
+        }
+    }
+
+    async fn replace_body(&self, method: Method, path: String, response: Response) -> Response {
+        if !self.is_active() {
+            return response;
+        }
+        let status = response.status();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+        let (error, message) = error_and_message(status, &bytes);
+        let details = ErrorDetails {
+            status,
+            error,
+            message,
+            method: method.to_string(),
+            path,
+        };
+
+        match (&self.error, &self.problem) {
+            (Some(template), _) => render(template, details, parts),
+            (None, Some(problem)) => problem.render(details, parts),
+            (None, None) => unreachable!("inactive error bodies return early"),
+        }
+    }
+}
+
+struct ErrorDetails {
+    status: StatusCode,
+    error: String,
+    message: String,
+    method: String,
+    path: String,
+}
+
+fn render(template: &Value, details: ErrorDetails, mut parts: http::response::Parts) -> Response {
+    let body = render_value_with(template, Utc::now(), &|name| match name {
+        "status" => Some(Value::from(details.status.as_u16())),
+        "error" => Some(Value::String(details.error.clone())),
+        "message" => Some(Value::String(details.message.clone())),
+        "method" => Some(Value::String(details.method.clone())),
+        "path" => Some(Value::String(details.path.clone())),
+        _ => None,
+    });
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    parts.extensions.insert(RenderedErrorBody);
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
+
+/// Reads the error code and message of a response body, using the
+/// `{"error": ..., "message": ...}` shape of built-in errors when present.
+fn error_and_message(status: StatusCode, body: &[u8]) -> (String, String) {
+    let json = serde_json::from_slice::<Value>(body).ok();
+    let field = |name: &str| {
+        json.as_ref()
+            .and_then(|json| json.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    let error = field("error").unwrap_or_else(|| default_error_code(status));
+    let message = field("message").unwrap_or_else(|| {
+        let text = String::from_utf8_lossy(body).trim().to_string();
+        if text.is_empty() || json.is_some() {
+            status.canonical_reason().unwrap_or("Error").to_string()
+        } else {
+            text
+        }
+    });
+    (error, message)
+}
+
+fn default_error_code(status: StatusCode) -> String {
+    status
+        .canonical_reason()
+        .unwrap_or("error")
+        .to_lowercase()
+        .replace([' ', '-'], "_")
+}
+
+fn loaded_message(path: &Path) -> String {
+    format!("✔️ Loaded error body template {}", path.to_string_lossy())
+}
+
+type ErrorBodyReturn = Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware that renders the error template for `4xx` and `5xx` responses.
+pub fn make_error_body_middleware(
+    bodies: &Arc<ErrorBodies>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> ErrorBodyReturn {
+    let bodies = Arc::clone(bodies);
+    move |req: Request, next: Next| {
+        let bodies = Arc::clone(&bodies);
+        Box::pin(async move {
+            let method = req.method().clone();
+            let path = req.uri().path().to_string();
+            let response = next.run(req).await;
+
+            let status = response.status();
+            if !(status.is_client_error() || status.is_server_error())
+                || response.extensions().get::<RenderedErrorBody>().is_some()
+                || response.extensions().get::<VerbatimErrorBody>().is_some()
+            {
+                return response;
+            }
+            bodies.replace_body(method, path, response).await
+        })
+    }
+}
+
+/// Returns the response for a request no route matches.
+pub fn not_found_response(bodies: &ErrorBodies, method: &Method, path: &str) -> Response {
+    bodies
+        .not_found(method, path)
+        .unwrap_or_else(|| (StatusCode::NOT_FOUND, "nothing to see here").into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::ServerConfig;
+    use serde_json::json;
+
+    fn config(folder: &Path, server: ServerConfig) -> Config {
+        Config {
+            server: Some(ServerConfig {
+                folder: Some(folder.to_string_lossy().to_string()),
+                ..server
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn body_json(response: Response) -> Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn load_reads_default_files_and_configured_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (bodies, messages) =
+            ErrorBodies::load(&config(temp_dir.path(), ServerConfig::default())).unwrap();
+        assert!(bodies.not_found.is_none());
+        assert!(!bodies.is_active());
+        assert!(messages.is_empty());
+
+        std::fs::write(temp_dir.path().join("__404.json"), r#"{"code":404}"#).unwrap();
+        std::fs::write(temp_dir.path().join("envelope.json"), r#"{"code":0}"#).unwrap();
+        let (bodies, messages) = ErrorBodies::load(&config(
+            temp_dir.path(),
+            ServerConfig {
+                error_body: Some("envelope.json".to_string()),
+                ..Default::default()
+            },
+        ))
+        .unwrap();
+        assert_eq!(bodies.not_found, Some(json!({ "code": 404 })));
+        assert_eq!(bodies.error, Some(json!({ "code": 0 })));
+        assert_eq!(messages.len(), 2);
+
+        let err = ErrorBodies::load(&config(
+            temp_dir.path(),
+            ServerConfig {
+                not_found_body: Some("missing.json".to_string()),
+                ..Default::default()
+            },
+        ))
+        .unwrap_err();
+        assert!(err.contains("missing.json"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn templates_receive_the_error_details() {
+        let bodies = ErrorBodies {
+            not_found: Some(json!({ "code": "{{status}}", "detail": "{{method}} {{path}}" })),
+            error: Some(json!({
+                "code": "{{status}}",
+                "type": "{{error}}",
+                "detail": "{{message}}"
+            })),
+            problem: None,
+        };
+
+        let response = not_found_response(&bodies, &Method::GET, "/missing");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            body_json(response).await,
+            json!({ "code": 404, "detail": "GET /missing" })
+        );
+
+        let conflict = crate::handlers::error_response(
+            StatusCode::CONFLICT,
+            "duplicate_id",
+            "An item with id '1' already exists",
+        );
+        let response = bodies
+            .replace_body(Method::POST, "/users".to_string(), conflict)
+            .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            body_json(response).await,
+            json!({
+                "code": 409,
+                "type": "duplicate_id",
+                "detail": "An item with id '1' already exists"
+            })
+        );
+
+        let response = bodies
+            .replace_body(
+                Method::GET,
+                "/private".to_string(),
+                StatusCode::UNAUTHORIZED.into_response(),
+            )
+            .await;
+        assert_eq!(
+            body_json(response).await,
+            json!({ "code": 401, "type": "unauthorized", "detail": "Unauthorized" })
+        );
+    }
+
+    #[tokio::test]
+    async fn problem_documents_describe_errors_without_templates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (bodies, _) = ErrorBodies::load(&config(
+            temp_dir.path(),
+            ServerConfig {
+                problem_details: Some(true),
+                problem_type_base: Some("https://errors.example.com/".to_string()),
+                problem_types: Some(HashMap::from([(
+                    "401".to_string(),
+                    "https://errors.example.com/auth".to_string(),
+                )])),
+                ..Default::default()
+            },
+        ))
+        .unwrap();
+        assert!(bodies.is_active());
+
+        let response = not_found_response(&bodies, &Method::GET, "/missing");
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+        assert_eq!(
+            body_json(response).await,
+            json!({
+                "type": "https://errors.example.com/not_found",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "No route matches the request",
+                "instance": "/missing",
+                "code": "not_found"
+            })
+        );
+
+        let conflict = crate::handlers::error_response(
+            StatusCode::CONFLICT,
+            "duplicate_id",
+            "An item with id '1' already exists",
+        );
+        let response = bodies
+            .replace_body(Method::POST, "/users".to_string(), conflict)
+            .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = body_json(response).await;
+        assert_eq!(body["type"], "https://errors.example.com/duplicate_id");
+        assert_eq!(body["detail"], "An item with id '1' already exists");
+
+        let response = bodies
+            .replace_body(
+                Method::GET,
+                "/private".to_string(),
+                StatusCode::UNAUTHORIZED.into_response(),
+            )
+            .await;
+        assert_eq!(
+            body_json(response).await["type"],
+            "https://errors.example.com/auth"
+        );
+
+        let blank = ProblemDetails::default();
+        assert_eq!(
+            blank.type_uri("not_found", StatusCode::NOT_FOUND),
+            "about:blank"
+        );
+    }
+}
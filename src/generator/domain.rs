@@ -147,7 +147,7 @@ pub struct MainConfigSelection {
     /// Include global route defaults.
     pub include_route_defaults: bool,
     /// Optional global delay in milliseconds.
-    pub delay: Option<u16>,
+    pub delay: Option<u32>,
     /// Optional global route remap/prefix.
     pub remap: Option<String>,
     /// Optional global protection default.
@@ -54,7 +54,7 @@ fn insert_string(table: &mut Map<String, Value>, key: &str, value: &Option<Strin
     }
 }
 
-fn insert_integer(table: &mut Map<String, Value>, key: &str, value: Option<u16>) {
+fn insert_integer(table: &mut Map<String, Value>, key: &str, value: Option<u32>) {
     if let Some(value) = value {
         table.insert(key.to_string(), Value::Integer(value.into()));
     }
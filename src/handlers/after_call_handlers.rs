@@ -0,0 +1,261 @@
+//! Internal calls to other routes made after a route answers, to simulate
+//! service-to-service side effects such as audit entries.
+
+use std::sync::{Arc, OnceLock};
+
+use axum::{
+    Router,
+    body::{Body, Bytes, to_bytes},
+    extract::Request,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use chrono::Utc;
+use http::{
+    HeaderValue, Method, StatusCode, Uri,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::{
+    handlers::is_dropped_connection, route_builder::config::AfterCall, templates::render_value_with,
+};
+
+/// Most nested `after` calls made for one request, so routes calling each
+/// other stop instead of looping.
+pub const MAX_AFTER_CALL_DEPTH: u8 = 5;
+
+/// Number of `after` calls that led to a request.
+#[derive(Debug, Clone, Copy)]
+struct AfterCallDepth(u8);
+
+/// Parses the `METHOD /path` of an `after` call.
+pub fn parse_after_call(call: &str) -> Result<(Method, String), String> {
+    let (method, path) = call
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("`{}` is not a `METHOD /path` call", call))?;
+    let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| format!("`{}` is not an HTTP method", method))?;
+    let path = path.trim();
+    if !path.starts_with('/') {
+        return Err(format!("`{}` is not an absolute path", path));
+    }
+    Ok((method, path.to_string()))
+}
+
+/// Calls another route through `routes` after every successful response.
+///
+/// The call path and body may use `{{request}}` and `{{response}}`, or a field
+/// such as `{{response.id}}`, and the call carries the headers of the original
+/// request. The original response is returned once the call completes, so its
+/// side effects are visible to the client.
+pub fn after_call_router(
+    router: MethodRouter,
+    after: &AfterCall,
+    routes: Arc<OnceLock<Router>>,
+) -> Result<MethodRouter, String> {
+    let (method, path) = parse_after_call(&after.call)?;
+    let body = after
+        .body
+        .clone()
+        .unwrap_or_else(|| Value::String("{{response}}".to_string()));
+    let call = after.call.clone();
+
+    Ok(
+        router.layer(middleware::from_fn(move |req: Request, next: Next| {
+            let (method, path, body, call) =
+                (method.clone(), path.clone(), body.clone(), call.clone());
+            let routes = Arc::clone(&routes);
+            async move {
+                let depth = req
+                    .extensions()
+                    .get::<AfterCallDepth>()
+                    .map_or(0, |depth| depth.0);
+                let (parts, request_body) = req.into_parts();
+                let Ok(request_bytes) = to_bytes(request_body, usize::MAX).await else {
+                    return StatusCode::BAD_REQUEST.into_response();
+                };
+                let mut headers = parts.headers.clone();
+                let response = next
+                    .run(Request::from_parts(
+                        parts,
+                        Body::from(request_bytes.clone()),
+                    ))
+                    .await;
+                if !response.status().is_success()
+                    || is_dropped_connection(&response)
+                    || depth >= MAX_AFTER_CALL_DEPTH
+                {
+                    return response;
+                }
+
+                let (response_parts, response_body) = response.into_parts();
+                let Ok(response_bytes) = to_bytes(response_body, usize::MAX).await else {
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                };
+                let variables = |name: &str| {
+                    let mut keys = name.split('.');
+                    let root = match keys.next()? {
+                        "request" => body_value(&request_bytes),
+                        "response" => body_value(&response_bytes),
+                        _ => return None,
+                    };
+                    keys.try_fold(root?, |value, key| value.get(key).cloned())
+                };
+                let now = Utc::now();
+                let path = match render_value_with(&Value::String(path), now, &variables) {
+                    Value::String(path) => path,
+                    other => other.to_string(),
+                };
+                let body = render_value_with(&body, now, &variables);
+
+                let response = Response::from_parts(response_parts, Body::from(response_bytes));
+                let Ok(uri) = path.parse::<Uri>() else {
+                    eprintln!("⚠️ after call {} has an invalid path {}", call, path);
+                    return response;
+                };
+
+                headers.remove(CONTENT_LENGTH);
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                let mut request = Request::new(Body::from(body.to_string()));
+                *request.method_mut() = method;
+                *request.uri_mut() = uri;
+                *request.headers_mut() = headers;
+                request.extensions_mut().insert(AfterCallDepth(depth + 1));
+
+                if let Some(routes) = routes.get() {
+                    let status = routes
+                        .clone()
+                        .oneshot(request)
+                        .await
+                        .unwrap_or_else(|err| match err {})
+                        .status();
+                    if !status.is_success() {
+                        eprintln!("⚠️ after call {} answered {}", call, status);
+                    }
+                }
+
+                response
+            }
+        })),
+    )
+}
+
+/// Reads a body as JSON, or as text when it is not JSON.
+fn body_value(bytes: &Bytes) -> Option<Value> {
+    if bytes.is_empty() {
+        return Some(Value::Null);
+    }
+    Some(
+        serde_json::from_slice(bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).to_string())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[test]
+    fn after_calls_are_parsed() {
+        assert_eq!(
+            parse_after_call(" post  /internal/audit ").unwrap(),
+            (Method::POST, "/internal/audit".to_string())
+        );
+        assert!(parse_after_call("/internal/audit").is_err());
+        assert!(parse_after_call("POST internal").is_err());
+    }
+
+    #[tokio::test]
+    async fn after_calls_reach_other_routes_with_the_response() {
+        let audits = Arc::new(Mutex::new(vec![]));
+        let routes = Arc::new(OnceLock::new());
+        let after = AfterCall {
+            call: "POST /audit/{{response.id}}".to_string(),
+            body: Some(json!({ "order": "{{response}}", "note": "{{request.note}}" })),
+        };
+        let orders = after_call_router(
+            post(|| async { axum::Json(json!({ "id": 7 })) }),
+            &after,
+            Arc::clone(&routes),
+        )
+        .unwrap();
+        let failing = after_call_router(
+            post(|| async { StatusCode::CONFLICT }),
+            &after,
+            Arc::clone(&routes),
+        )
+        .unwrap();
+        let audit_log = Arc::clone(&audits);
+        let audit = post(
+            move |axum::extract::Path(id): axum::extract::Path<String>,
+                  axum::Json(body): axum::Json<Value>| async move {
+                audit_log.lock().unwrap().push((id, body));
+                StatusCode::CREATED
+            },
+        );
+        let router = Router::new()
+            .route("/orders", orders)
+            .route("/failing", failing)
+            .route("/audit/{id}", audit);
+        routes.set(router.clone()).unwrap();
+
+        for uri in ["/orders", "/failing"] {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::post(uri)
+                        .header(CONTENT_TYPE, "application/json")
+                        .body(Body::from(r#"{"note":"rush"}"#))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            if uri == "/orders" {
+                assert_eq!(bytes, r#"{"id":7}"#);
+            }
+        }
+
+        assert_eq!(
+            *audits.lock().unwrap(),
+            [(
+                "7".to_string(),
+                json!({ "order": { "id": 7 }, "note": "rush" })
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn after_calls_stop_at_the_maximum_depth() {
+        let calls = Arc::new(Mutex::new(0));
+        let routes = Arc::new(OnceLock::new());
+        let counter = Arc::clone(&calls);
+        let looping = after_call_router(
+            post(move || async move {
+                *counter.lock().unwrap() += 1;
+                StatusCode::OK
+            }),
+            &AfterCall {
+                call: "POST /loop".to_string(),
+                body: None,
+            },
+            Arc::clone(&routes),
+        )
+        .unwrap();
+        let router = Router::new().route("/loop", looping);
+        routes.set(router.clone()).unwrap();
+
+        router
+            .oneshot(Request::post("/loop").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), MAX_AFTER_CALL_DEPTH as usize + 1);
+    }
+}
@@ -0,0 +1,225 @@
+//! Aggregations over REST collection items.
+//!
+//! Backs `GET /resource/_aggregate`, which counts items and computes numeric
+//! `sum`, `avg`, `min`, and `max` per field, optionally grouped by one or more
+//! fields. Fosk collections do not aggregate, so items are summarized here after
+//! the listing filters are applied.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::criteria::field_value;
+
+/// Aggregations requested through query parameters.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AggregateQuery {
+    /// Fields whose values identify a group.
+    pub group_by: Vec<String>,
+    /// Fields summed per group.
+    pub sum: Vec<String>,
+    /// Fields averaged per group.
+    pub avg: Vec<String>,
+    /// Fields whose smallest value is reported per group.
+    pub min: Vec<String>,
+    /// Fields whose largest value is reported per group.
+    pub max: Vec<String>,
+}
+
+/// Computes one aggregate from the numeric values of a field.
+type Aggregation = fn(&[f64]) -> Option<f64>;
+
+fn field_list(params: &HashMap<String, String>, name: &str) -> Vec<String> {
+    params
+        .get(name)
+        .map(|fields| {
+            fields
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl AggregateQuery {
+    /// Reads comma-separated field lists from `group_by`, `sum`, `avg`, `min`, and `max`.
+    pub fn from_params(params: &HashMap<String, String>) -> Self {
+        Self {
+            group_by: field_list(params, "group_by"),
+            sum: field_list(params, "sum"),
+            avg: field_list(params, "avg"),
+            min: field_list(params, "min"),
+            max: field_list(params, "max"),
+        }
+    }
+
+    /// Summarizes items into one row per group, in order of first appearance.
+    ///
+    /// Each row holds the group fields, `count`, and one object per requested
+    /// aggregation keyed by field. Non-numeric values are ignored, and a field
+    /// without numbers aggregates to `null` (`0` for `sum`). Without `group_by`
+    /// a single row summarizes every item.
+    pub fn aggregate(&self, items: &[Value]) -> Vec<Value> {
+        let mut groups: Vec<(Vec<Value>, Vec<&Value>)> = vec![];
+        if self.group_by.is_empty() {
+            groups.push((vec![], items.iter().collect()));
+        } else {
+            for item in items {
+                let key = self
+                    .group_by
+                    .iter()
+                    .map(|field| field_value(item, field).cloned().unwrap_or(Value::Null))
+                    .collect::<Vec<_>>();
+                match groups.iter_mut().find(|(group, _)| *group == key) {
+                    Some((_, members)) => members.push(item),
+                    None => groups.push((key, vec![item])),
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(key, members)| self.row(key, &members))
+            .collect()
+    }
+
+    fn row(&self, key: Vec<Value>, members: &[&Value]) -> Value {
+        let mut row = Map::new();
+        for (field, value) in self.group_by.iter().zip(key) {
+            row.insert(field.clone(), value);
+        }
+        row.insert("count".to_string(), Value::from(members.len()));
+
+        let numbers = |field: &str| {
+            members
+                .iter()
+                .filter_map(|item| field_value(item, field).and_then(Value::as_f64))
+                .collect::<Vec<_>>()
+        };
+        let aggregations: [(&str, &Vec<String>, Aggregation); 4] = [
+            ("sum", &self.sum, |values| Some(values.iter().sum())),
+            ("avg", &self.avg, |values| {
+                (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+            }),
+            ("min", &self.min, |values| {
+                values.iter().copied().reduce(f64::min)
+            }),
+            ("max", &self.max, |values| {
+                values.iter().copied().reduce(f64::max)
+            }),
+        ];
+        for (name, fields, compute) in aggregations {
+            if fields.is_empty() {
+                continue;
+            }
+            let results = fields
+                .iter()
+                .map(|field| {
+                    let result = compute(&numbers(field)).map_or(Value::Null, number_value);
+                    (field.clone(), result)
+                })
+                .collect::<Map<_, _>>();
+            row.insert(name.to_string(), Value::Object(results));
+        }
+
+        Value::Object(row)
+    }
+}
+
+/// Converts an aggregate to JSON, keeping whole numbers as integers.
+fn number_value(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn items() -> Vec<Value> {
+        vec![
+            json!({ "status": "open", "amount": 10, "customer": { "country": "FR" } }),
+            json!({ "status": "paid", "amount": 2.5, "customer": { "country": "FR" } }),
+            json!({ "status": "open", "amount": 5, "customer": { "country": "BR" } }),
+            json!({ "status": "open", "amount": "n/a" }),
+        ]
+    }
+
+    #[test]
+    fn from_params_splits_field_lists() {
+        let params = HashMap::from([
+            (
+                "group_by".to_string(),
+                "status, customer.country".to_string(),
+            ),
+            ("sum".to_string(), "amount,,".to_string()),
+        ]);
+        let query = AggregateQuery::from_params(&params);
+
+        assert_eq!(query.group_by, ["status", "customer.country"]);
+        assert_eq!(query.sum, ["amount"]);
+        assert!(query.avg.is_empty());
+    }
+
+    #[test]
+    fn aggregate_without_group_summarizes_all_items() {
+        let query = AggregateQuery {
+            sum: vec!["amount".to_string()],
+            avg: vec!["amount".to_string()],
+            max: vec!["amount".to_string(), "missing".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.aggregate(&items()),
+            [json!({
+                "count": 4,
+                "sum": { "amount": 17.5 },
+                "avg": { "amount": 17.5 / 3.0 },
+                "max": { "amount": 10, "missing": null }
+            })]
+        );
+        assert_eq!(
+            query.aggregate(&[])[0]["sum"],
+            json!({ "amount": 0 }),
+            "sum of no values is zero"
+        );
+    }
+
+    #[test]
+    fn aggregate_groups_in_first_appearance_order() {
+        let query = AggregateQuery {
+            group_by: vec!["status".to_string()],
+            sum: vec!["amount".to_string()],
+            min: vec!["amount".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.aggregate(&items()),
+            [
+                json!({ "status": "open", "count": 3, "sum": { "amount": 15 }, "min": { "amount": 5 } }),
+                json!({ "status": "paid", "count": 1, "sum": { "amount": 2.5 }, "min": { "amount": 2.5 } }),
+            ]
+        );
+
+        let nested = AggregateQuery {
+            group_by: vec!["customer.country".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            nested.aggregate(&items()),
+            [
+                json!({ "customer.country": "FR", "count": 2 }),
+                json!({ "customer.country": "BR", "count": 1 }),
+                json!({ "customer.country": null, "count": 1 }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Deterministic anonymization of recorded responses, so production captures
+//! can safely become mock fixtures.
+//!
+//! Names, emails, phone numbers, and card numbers are replaced by fake values
+//! derived from a keyed hash of the original, so the same person gets the same
+//! replacement in every recording made with the key and relations between
+//! fixtures survive, while the originals cannot be recovered by hashing
+//! candidate values without the key.
+
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use sha2::Sha256;
+
+static RE_EMAIL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+});
+/// Card numbers written in digit groups, such as `4111 1111 1111 1111`, so
+/// plain numeric ids are left alone.
+static RE_GROUPED_CARD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b\d{4}(?:[ -]\d{3,6}){2,4}\b").unwrap());
+/// Card numbers in any layout, looked for only in card fields.
+static RE_CARD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").unwrap());
+static RE_PHONE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\+\d[\d ().-]{6,}\d|\(?\b\d{3}\)?[ .-]\d{3}[ .-]\d{4}\b").unwrap());
+
+const FIRST_NAMES: [&str; 16] = [
+    "Alex", "Blake", "Casey", "Dana", "Eden", "Finley", "Gray", "Harper", "Indy", "Jordan", "Kai",
+    "Logan", "Morgan", "Noel", "Parker", "Quinn",
+];
+const LAST_NAMES: [&str; 16] = [
+    "Archer", "Brooks", "Carter", "Dalton", "Ellis", "Fischer", "Garcia", "Hayes", "Ibarra",
+    "Jensen", "Keller", "Lopez", "Moreau", "Novak", "Okafor", "Price",
+];
+/// Fields holding a first name, compared without case, `_`, or `-`.
+const FIRST_NAME_FIELDS: [&str; 3] = ["firstname", "givenname", "forename"];
+/// Fields holding a last name, compared without case, `_`, or `-`.
+const LAST_NAME_FIELDS: [&str; 3] = ["lastname", "familyname", "surname"];
+/// Fields holding a full name, compared without case, `_`, or `-`.
+const FULL_NAME_FIELDS: [&str; 4] = ["fullname", "displayname", "middlename", "contactname"];
+/// Fields whose digits are replaced even when they don't look like a phone.
+const PHONE_FIELDS: [&str; 5] = ["phone", "mobile", "telephone", "tel", "fax"];
+/// Fields whose card numbers are replaced even when not written in groups.
+const CARD_FIELDS: [&str; 5] = [
+    "card",
+    "creditcard",
+    "ccnumber",
+    "pan",
+    "primaryaccountnumber",
+];
+/// Domain of the replacement emails.
+const EMAIL_DOMAIN: &str = "example.com";
+
+/// Replaces personal data in recorded bodies with deterministic fake values.
+#[derive(Clone, PartialEq)]
+pub struct Anonymizer {
+    /// Extra fields holding full names, such as `name` or `owner`.
+    pub name_fields: Vec<String>,
+    /// Key of the hash the replacements are derived from.
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for Anonymizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Anonymizer")
+            .field("name_fields", &self.name_fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new(vec![], None)
+    }
+}
+
+impl Anonymizer {
+    /// Creates an anonymizer that also treats `name_fields` as full names.
+    ///
+    /// Replacements are keyed by `secret`, or by a random key when it is
+    /// `None`, which keeps them stable only within one run.
+    pub fn new(name_fields: Vec<String>, secret: Option<&str>) -> Self {
+        let key = match secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => rand::rng().random::<[u8; 32]>().to_vec(),
+        };
+        Self {
+            name_fields: name_fields.iter().map(|field| normalize(field)).collect(),
+            key,
+        }
+    }
+
+    /// Anonymizes a recorded body: JSON field by field, other text by pattern.
+    pub fn anonymize_body(&self, body: &[u8]) -> Vec<u8> {
+        if let Ok(value) = serde_json::from_slice::<Value>(body) {
+            return serde_json::to_vec(&self.anonymize_value(value))
+                .unwrap_or_else(|_| body.to_vec());
+        }
+        match std::str::from_utf8(body) {
+            Ok(text) => self.anonymize_text(text).into_bytes(),
+            Err(_) => body.to_vec(),
+        }
+    }
+
+    /// Anonymizes a JSON value, recognizing names and phones by field name and
+    /// emails, cards, and phones in any text.
+    pub fn anonymize_value(&self, value: Value) -> Value {
+        match value {
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(field, value)| {
+                        let value = self.anonymize_field(&field, value);
+                        (field, value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| self.anonymize_value(item))
+                    .collect(),
+            ),
+            Value::String(text) => Value::String(self.anonymize_text(&text)),
+            other => other,
+        }
+    }
+
+    fn anonymize_field(&self, field: &str, value: Value) -> Value {
+        let Value::String(text) = &value else {
+            return self.anonymize_value(value);
+        };
+        let field = normalize(field);
+        let field = field.as_str();
+        if FIRST_NAME_FIELDS.contains(&field) {
+            Value::String(self.first_name(text).to_string())
+        } else if LAST_NAME_FIELDS.contains(&field) {
+            Value::String(self.last_name(text).to_string())
+        } else if FULL_NAME_FIELDS.contains(&field) || self.name_fields.iter().any(|f| f == field) {
+            Value::String(format!(
+                "{} {}",
+                self.first_name(text),
+                self.last_name(text)
+            ))
+        } else if PHONE_FIELDS.contains(&field) || field.ends_with("phone") {
+            Value::String(self.replace_digits(text))
+        } else if CARD_FIELDS.contains(&field) || field.ends_with("cardnumber") {
+            let text = self.replace_cards(&RE_CARD, text);
+            Value::String(self.anonymize_text(&text))
+        } else {
+            self.anonymize_value(value)
+        }
+    }
+
+    /// Replaces the emails, grouped card numbers, and phone numbers found in text.
+    pub fn anonymize_text(&self, text: &str) -> String {
+        let text = RE_EMAIL.replace_all(text, |captures: &Captures| self.email(&captures[0]));
+        let text = self.replace_cards(&RE_GROUPED_CARD, &text);
+        RE_PHONE
+            .replace_all(&text, |captures: &Captures| {
+                self.replace_digits(&captures[0])
+            })
+            .into_owned()
+    }
+
+    /// Replaces the numbers `pattern` finds that pass the Luhn check.
+    fn replace_cards(&self, pattern: &Regex, text: &str) -> String {
+        pattern
+            .replace_all(text, |captures: &Captures| {
+                let number = &captures[0];
+                if luhn_valid(number) {
+                    self.card(number)
+                } else {
+                    number.to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    fn digest(&self, text: &str) -> [u8; 32] {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key");
+        mac.update(text.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    fn first_name(&self, text: &str) -> &'static str {
+        FIRST_NAMES[self.digest(text)[0] as usize % FIRST_NAMES.len()]
+    }
+
+    fn last_name(&self, text: &str) -> &'static str {
+        // Full names hash the same text twice; another byte keeps the parts independent.
+        LAST_NAMES[self.digest(text)[1] as usize % LAST_NAMES.len()]
+    }
+
+    fn email(&self, address: &str) -> String {
+        let hash = self.digest(&address.to_lowercase());
+        let local = hash[..4]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        format!("user-{}@{}", local, EMAIL_DOMAIN)
+    }
+
+    /// Replaces every digit with one derived from the text, keeping the layout.
+    fn replace_digits(&self, text: &str) -> String {
+        let hash = self.digest(text);
+        let mut index = 0;
+        text.chars()
+            .map(|c| {
+                if c.is_ascii_digit() {
+                    let digit = hash[index % hash.len()] % 10;
+                    index += 1;
+                    char::from(b'0' + digit)
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+
+    /// Returns a card number with the same length, separators, and first digit,
+    /// whose check digit is valid.
+    fn card(&self, number: &str) -> String {
+        let hash = self.digest(number);
+        let length = number.chars().filter(char::is_ascii_digit).count();
+        let first = number.chars().find(char::is_ascii_digit).unwrap_or('4');
+        let mut digits = vec![first.to_digit(10).unwrap_or(4)];
+        digits.extend((1..length - 1).map(|index| u32::from(hash[index % hash.len()] % 10)));
+        digits.push(luhn_check_digit(&digits));
+
+        let mut digits = digits.into_iter();
+        number
+            .chars()
+            .map(|c| match c.is_ascii_digit() {
+                true => char::from_digit(digits.next().unwrap_or(0), 10).unwrap_or('0'),
+                false => c,
+            })
+            .collect()
+    }
+}
+
+/// Lowercases a field name and drops `_` and `-`, so `first_name` and
+/// `firstName` compare equal.
+fn normalize(field: &str) -> String {
+    field
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn luhn_valid(number: &str) -> bool {
+    let digits = number
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .collect::<Vec<_>>();
+    match digits.split_last() {
+        Some((check, payload)) => luhn_check_digit(payload) == *check,
+        None => false,
+    }
+}
+
+/// Returns the Luhn check digit completing `payload`.
+fn luhn_check_digit(payload: &[u32]) -> u32 {
+    let sum: u32 = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| match index % 2 {
+            0 if digit * 2 > 9 => digit * 2 - 9,
+            0 => digit * 2,
+            _ => *digit,
+        })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json_fields_are_replaced_deterministically() {
+        let anonymizer = Anonymizer::new(vec!["name".to_string()], Some("fixtures"));
+        let recorded = json!({
+            "name": "Ada Lovelace",
+            "first_name": "Ada",
+            "contact": { "email": "ada@lovelace.org", "phone": "0201 555 1234" },
+            "card": "4111 1111 1111 1111",
+            "note": "Call +44 20 7946 0958 or write to ada@lovelace.org",
+            "createdAt": "2024-01-01",
+            "amount": 4111111111111111u64,
+        });
+
+        let first = anonymizer.anonymize_value(recorded.clone());
+        assert_eq!(first, anonymizer.anonymize_value(recorded));
+
+        let email = first["contact"]["email"].as_str().unwrap();
+        assert!(email.starts_with("user-") && email.ends_with("@example.com"));
+        assert!(first["note"].as_str().unwrap().contains(email));
+        assert!(!first["note"].as_str().unwrap().contains("7946 0958"));
+        assert_ne!(first["name"], "Ada Lovelace");
+        assert!(FIRST_NAMES.contains(&first["first_name"].as_str().unwrap()));
+        assert_ne!(first["contact"]["phone"], "0201 555 1234");
+        assert_eq!(first["contact"]["phone"].as_str().unwrap().len(), 13);
+        assert_eq!(first["createdAt"], "2024-01-01");
+        assert_eq!(first["amount"], 4111111111111111u64);
+
+        let card = first["card"].as_str().unwrap();
+        assert_ne!(card, "4111 1111 1111 1111");
+        assert!(card.starts_with('4') && luhn_valid(card));
+        assert_eq!(card.len(), 19);
+    }
+
+    #[test]
+    fn text_bodies_are_anonymized_by_pattern() {
+        let body = Anonymizer::default()
+            .anonymize_body(b"<user><email>ada@lovelace.org</email><tel>555-123-4567</tel></user>");
+        let body = String::from_utf8(body).unwrap();
+
+        assert!(!body.contains("ada@lovelace.org"));
+        assert!(!body.contains("555-123-4567"));
+        assert!(body.starts_with("<user><email>user-"));
+    }
+
+    #[test]
+    fn replacements_depend_on_the_secret() {
+        let body = json!({ "email": "ada@lovelace.org", "name": "Ada Lovelace" });
+        let anonymize = |secret| {
+            Anonymizer::new(vec!["name".to_string()], secret).anonymize_value(body.clone())
+        };
+
+        assert_eq!(anonymize(Some("a")), anonymize(Some("a")));
+        assert_ne!(anonymize(Some("a"))["email"], anonymize(Some("b"))["email"]);
+        assert_ne!(anonymize(None)["email"], anonymize(None)["email"]);
+    }
+
+    #[test]
+    fn numbers_failing_the_luhn_check_are_kept() {
+        let anonymizer = Anonymizer::default();
+        assert_eq!(
+            anonymizer.anonymize_text("order 4111 1111 1111 1112"),
+            "order 4111 1111 1111 1112"
+        );
+        assert!(luhn_valid("4111111111111111"));
+    }
+
+    #[test]
+    fn ungrouped_numbers_are_only_cards_in_card_fields() {
+        let anonymizer = Anonymizer::default();
+        let recorded = json!({
+            "id": "4111111111111111",
+            "message": "snowflake 4111111111111111 created",
+            "cardNumber": "4111111111111111",
+            "payment": { "pan": "4111111111111111" },
+        });
+
+        let anonymized = anonymizer.anonymize_value(recorded);
+
+        assert_eq!(anonymized["id"], "4111111111111111");
+        assert_eq!(anonymized["message"], "snowflake 4111111111111111 created");
+        for card in [&anonymized["cardNumber"], &anonymized["payment"]["pan"]] {
+            let card = card.as_str().unwrap();
+            assert_ne!(card, "4111111111111111");
+            assert!(card.starts_with('4') && luhn_valid(card));
+        }
+    }
+}
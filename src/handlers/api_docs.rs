@@ -0,0 +1,268 @@
+//! The OpenAPI document of the mocked routes at `/__openapi.json` and an
+//! embedded Swagger UI at `/__docs`, to explore and try the routes from a
+//! browser.
+
+use std::collections::HashMap;
+
+use axum::{
+    Json,
+    extract::Path,
+    response::{Html, IntoResponse, Response},
+    routing::{MethodRouter, get},
+};
+use http::{HeaderValue, StatusCode, header::CONTENT_TYPE};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value, json};
+use zip::ZipArchive;
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE, RegisteredRoute},
+    handlers::error_response,
+    response_validation::{read_contract, resolve_contract_path},
+    route_builder::config::Config,
+};
+
+/// Route serving the OpenAPI document.
+pub const OPENAPI_ROUTE: &str = "/__openapi.json";
+/// Route serving the Swagger UI page.
+pub const DOCS_ROUTE: &str = "/__docs";
+
+const SWAGGER_UI_DIST: &str = "swagger-ui-5.17.14/dist/";
+const SWAGGER_UI_ASSETS: [&str; 3] = [
+    "swagger-ui.css",
+    "swagger-ui-bundle.js",
+    "swagger-ui-standalone-preset.js",
+];
+
+/// Swagger UI assets, unpacked from the vendored archive on first use.
+static SWAGGER_UI: Lazy<HashMap<&'static str, Vec<u8>>> = Lazy::new(|| {
+    let reader = std::io::Cursor::new(utoipa_swagger_ui_vendored::SWAGGER_UI_VENDORED);
+    let Ok(mut archive) = ZipArchive::new(reader) else {
+        return HashMap::new();
+    };
+    SWAGGER_UI_ASSETS
+        .into_iter()
+        .filter_map(|name| {
+            let mut file = archive
+                .by_name(&format!("{}{}", SWAGGER_UI_DIST, name))
+                .ok()?;
+            let mut content = vec![];
+            std::io::Read::read_to_end(&mut file, &mut content).ok()?;
+            Some((name, content))
+        })
+        .collect()
+});
+
+const DOCS_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8" />
+    <title>rs-mock-server API docs</title>
+    <link rel="stylesheet" href="/__docs/swagger-ui.css" />
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="/__docs/swagger-ui-bundle.js"></script>
+    <script src="/__docs/swagger-ui-standalone-preset.js"></script>
+    <script>
+        window.ui = SwaggerUIBundle({
+            url: "/__openapi.json",
+            dom_id: "#swagger-ui",
+            presets: [SwaggerUIBundle.presets.apis, SwaggerUIStandalonePreset],
+            layout: "StandaloneLayout",
+        });
+    </script>
+</body>
+</html>
+"##;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Returns the OpenAPI document of the mock server: the one configured with
+/// `[server] openapi` when it can be read, otherwise one listing `routes`.
+pub fn openapi_document(config: &Config, routes: &[RegisteredRoute]) -> Value {
+    if let Some(openapi) = config.server.clone().unwrap_or_default().openapi
+        && let Ok(document) = read_contract(&resolve_contract_path(config, &openapi))
+    {
+        return document;
+    }
+
+    let mut paths = Map::new();
+    let mut protected = false;
+    for route in routes {
+        let method = route.method.to_ascii_lowercase();
+        if !METHODS.contains(&method.as_str())
+            || route.path.starts_with("/__")
+            || route.path.starts_with(MOCK_SERVER_ROUTE)
+        {
+            continue;
+        }
+
+        let path = route.path.replace("{*", "{");
+        let mut operation = json!({
+            "summary": format!("{} {}", route.method.to_ascii_uppercase(), route.path),
+            "responses": { "200": { "description": "Mocked response" } },
+        });
+        let parameters = path_parameters(&path);
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if route.is_protected {
+            protected = true;
+            operation["security"] = json!([{ "bearerAuth": [] }]);
+        }
+
+        let item = paths.entry(path).or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+
+    let mut document = json!({
+        "openapi": "3.0.3",
+        "info": { "title": "rs-mock-server", "version": env!("CARGO_PKG_VERSION") },
+        "paths": paths,
+    });
+    if protected {
+        document["components"] = json!({
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" }
+            }
+        });
+    }
+    document
+}
+
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect()
+}
+
+fn serve_asset() -> MethodRouter {
+    get(async move |Path(name): Path<String>| -> Response {
+        let Some(content) = SWAGGER_UI.get(name.as_str()) else {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                format!("No Swagger UI asset named {}", name),
+            );
+        };
+        let content_type = match name.ends_with(".css") {
+            true => "text/css",
+            false => "text/javascript",
+        };
+        (
+            [(CONTENT_TYPE, HeaderValue::from_static(content_type))],
+            content.clone(),
+        )
+            .into_response()
+    })
+}
+
+/// Registers `GET /__openapi.json` and the Swagger UI at `GET /__docs`, unless
+/// `[server] docs` is disabled. Registered last, so the document lists every
+/// route registered before it.
+pub fn create_api_docs_routes(app: &mut App) {
+    let server = app.server_config.server.clone().unwrap_or_default();
+    if server.docs == Some(false) {
+        return;
+    }
+
+    let document = openapi_document(&app.server_config, app.registered_routes());
+    app.route(
+        OPENAPI_ROUTE,
+        get(async move || Json(document.clone())),
+        Some("GET"),
+        None,
+    );
+    app.route(DOCS_ROUTE, get(async || Html(DOCS_PAGE)), Some("GET"), None);
+    app.route(
+        &format!("{}/{{asset}}", DOCS_ROUTE),
+        serve_asset(),
+        None,
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use http::Request;
+    use tower::ServiceExt;
+
+    fn registered(method: &str, path: &str, is_protected: bool) -> RegisteredRoute {
+        RegisteredRoute {
+            method: method.to_string(),
+            path: path.to_string(),
+            is_protected,
+        }
+    }
+
+    #[test]
+    fn document_lists_mocked_routes_with_path_parameters() {
+        let routes = [
+            registered("GET", "/api/users", false),
+            registered("DELETE", "/api/users/{id}", true),
+            registered("GET", "/__audit", false),
+            registered("GET", "/mock-server/collections", false),
+        ];
+
+        let document = openapi_document(&Config::default(), &routes);
+
+        let paths = document["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 2);
+        let delete = &document["paths"]["/api/users/{id}"]["delete"];
+        assert_eq!(delete["parameters"][0]["name"], "id");
+        assert_eq!(delete["security"][0]["bearerAuth"], json!([]));
+        assert_eq!(
+            document["components"]["securitySchemes"]["bearerAuth"]["scheme"],
+            "bearer"
+        );
+    }
+
+    #[tokio::test]
+    async fn docs_page_loads_the_embedded_swagger_ui() {
+        let mut app = App::default();
+        app.route("/api/users", get(async || "[]"), Some("GET"), None);
+        create_api_docs_routes(&mut app);
+        let router = app.take_router_for_test();
+
+        let call = |uri: &str| {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let content_type = response.headers().get(CONTENT_TYPE).cloned();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, content_type, body)
+            }
+        };
+
+        let (status, _, body) = call(OPENAPI_ROUTE).await;
+        assert_eq!(status, StatusCode::OK);
+        let document: Value = serde_json::from_slice(&body).unwrap();
+        assert!(document["paths"]["/api/users"]["get"].is_object());
+
+        let (_, _, body) = call(DOCS_ROUTE).await;
+        assert!(String::from_utf8_lossy(&body).contains(OPENAPI_ROUTE));
+
+        let (status, content_type, body) = call("/__docs/swagger-ui-bundle.js").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.unwrap(), "text/javascript");
+        assert!(!body.is_empty());
+
+        let (status, _, _) = call("/__docs/missing.js").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
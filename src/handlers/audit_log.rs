@@ -0,0 +1,162 @@
+//! A log of every published change event, so a test can assert the exact
+//! sequence of writes a client performed, not only the final state.
+
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    Json,
+    extract::Query,
+    response::{IntoResponse, Response},
+    routing::{MethodRouter, delete, get},
+};
+use fosk::{Db, DbCollection, DbConfig};
+use http::StatusCode;
+use serde_json::{Value, json};
+
+use crate::{
+    app::App,
+    criteria::CriteriaBuilder,
+    handlers::{ChangeEvent, error_response, read_error_response, write_error_response},
+};
+
+/// Route that lists and clears the audit log.
+pub const AUDIT_ROUTE: &str = "/__audit";
+/// Collection holding the audit log, keyed by event `sequence`.
+pub const AUDIT_COLLECTION: &str = "__audit";
+
+/// Change events kept in an `__audit` collection of their own, so snapshots,
+/// restores, and the collections endpoints leave the history untouched.
+pub struct AuditLog {
+    collection: Arc<DbCollection>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        let db = Db::new_arc();
+        let collection = db.create_with_config(AUDIT_COLLECTION, DbConfig::none("sequence"));
+        Self { collection }
+    }
+}
+
+impl AuditLog {
+    /// Appends an event to the log.
+    pub fn record(&self, event: &ChangeEvent) {
+        if let Ok(entry) = serde_json::to_value(event) {
+            let _ = self.collection.add(entry);
+        }
+    }
+
+    /// Returns the logged events, oldest first.
+    pub fn entries(&self) -> Result<Vec<Value>, fosk::CollectionReadError> {
+        let mut entries = self.collection.get_all()?;
+        entries.sort_by_key(|entry| entry["sequence"].as_u64());
+        Ok(entries)
+    }
+
+    /// Empties the log and returns how many events it held.
+    pub fn clear(&self) -> Result<usize, fosk::CollectionWriteError> {
+        self.collection.clear()
+    }
+}
+
+fn list_audit(audit: Arc<AuditLog>) -> MethodRouter {
+    get(
+        async move |Query(params): Query<HashMap<String, String>>| -> Response {
+            let criteria = match params.get("where").map(|text| CriteriaBuilder::parse(text)) {
+                Some(Ok(criteria)) => Some(criteria),
+                Some(Err(err)) => {
+                    return error_response(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_where",
+                        err.to_string(),
+                    );
+                }
+                None => None,
+            };
+
+            match audit.entries() {
+                Ok(mut entries) => {
+                    if let Some(criteria) = criteria {
+                        entries.retain(|entry| criteria.matches(entry));
+                    }
+                    Json(json!({ "data": entries })).into_response()
+                }
+                Err(err) => read_error_response(err),
+            }
+        },
+    )
+}
+
+fn clear_audit(audit: Arc<AuditLog>) -> MethodRouter {
+    delete(async move || match audit.clear() {
+        Ok(cleared) => Json(json!({ "cleared": cleared })).into_response(),
+        Err(err) => write_error_response(err),
+    })
+}
+
+/// Registers `GET /__audit`, which lists the logged change events filtered by
+/// `?where=`, and `DELETE /__audit`, which empties the log.
+pub fn create_audit_routes(app: &mut App) {
+    let Some(audit) = app.change_events.audit() else {
+        return;
+    };
+    app.route(
+        AUDIT_ROUTE,
+        list_audit(Arc::clone(&audit)),
+        Some("GET"),
+        None,
+    );
+    app.route(AUDIT_ROUTE, clear_audit(audit), Some("DELETE"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::Change;
+    use axum::body::{Body, to_bytes};
+    use http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn audit_lists_events_in_order_and_filters_them() {
+        let mut app = App::default();
+        app.change_events.publish_all([
+            Change::created("users", "1", &json!({ "id": 1, "name": "Ada" })),
+            Change::created("orders", "1", &json!({ "id": 1 })),
+            Change::deleted("users", "1", &json!({ "id": 1, "name": "Ada" })),
+        ]);
+        app.build_audit_route();
+        let router = app.take_router_for_test();
+
+        let call = |method: &str, uri: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+            }
+        };
+
+        let (_, body) = call("GET", "/__audit?where=collection+%3D+%22users%22").await;
+        let entries = body["data"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["kind"], "created");
+        assert_eq!(entries[1]["kind"], "deleted");
+        assert_eq!(entries[1]["before"]["name"], "Ada");
+
+        let (status, body) = call("GET", "/__audit?where=kind+%3D").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_where");
+
+        let (_, body) = call("DELETE", "/__audit").await;
+        assert_eq!(body["cleared"], 3);
+        let (_, body) = call("GET", "/__audit").await;
+        assert_eq!(body["data"], json!([]));
+    }
+}
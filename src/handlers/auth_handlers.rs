@@ -1,23 +1,35 @@
-use std::{pin::Pin, sync::Arc};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 
 use axum::{
     Json,
-    body::Body,
+    body::{Body, to_bytes},
     extract::Request,
     middleware::Next,
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{get, patch, post},
+};
+use chrono::{DateTime, Duration, Utc};
+use fosk::{Db, DbCollection, DbConfig};
+use http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{CONTENT_LENGTH, RETRY_AFTER},
 };
-use chrono::{Duration, Utc};
-use fosk::{DbCollection, DbConfig};
-use http::{HeaderValue, StatusCode};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, TokenData, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
 use crate::{
     app::{App, GLOBAL_SHARED_INFO},
-    handlers::{SleepThread, build_rest_routes, error_response, write_error_response},
+    handlers::{
+        Change, ChangeEvents, MfaChallenges, SleepDelay, add_error_response, build_rest_routes,
+        create_mfa_route, error_response, id_string, mfa_challenge_response, request_cookies,
+        requires_mfa, write_error_response,
+    },
+    random,
     route_builder::{RouteAuth, RouteRest},
 };
 
@@ -30,6 +42,9 @@ struct Claims {
     iat: i64, // Issued at
 }
 
+/// Field of the password change payload holding the new password.
+const NEW_PASSWORD_FIELD: &str = "new_password";
+
 #[derive(Serialize)]
 struct AuthResponse {
     token: String,
@@ -128,7 +143,8 @@ fn generate_token(
         user: user_data.clone(),
     };
 
-    {
+    // Logins within the same second get the same token, which is already stored
+    if !token_collection.exists(&token).unwrap_or(false) {
         let mut user_data = user_data.clone();
         if let Some(obj) = user_data.as_object_mut() {
             obj.insert(
@@ -172,64 +188,426 @@ fn generate_token(
     response
 }
 
+/// Field of a user record that makes login answer `403 password_expired`.
+pub const PASSWORD_EXPIRED_FIELD: &str = "password_expired";
+/// Field of a user record that flags the login response with `must_change_password`.
+pub const MUST_CHANGE_PASSWORD_FIELD: &str = "must_change_password";
+
+/// Consecutive failed logins per username, locking it once `max_failed_attempts`
+/// is reached.
+#[derive(Default)]
+struct LoginAttempts {
+    entries: Mutex<HashMap<String, FailedLogins>>,
+}
+
+/// Failures counted since the last lockout, and the end of the current one.
+type FailedLogins = (u32, Option<DateTime<Utc>>);
+
+impl LoginAttempts {
+    /// Returns how long `username` stays locked, if it is.
+    fn locked_for(&self, username: &str) -> Option<Duration> {
+        let entries = self.entries.lock().unwrap();
+        let (_, locked_until) = entries.get(username)?;
+        locked_until
+            .map(|until| until - Utc::now())
+            .filter(|remaining| *remaining > Duration::zero())
+    }
+
+    /// Counts a failed login, and locks `username` for `lockout` when it
+    /// reaches `max_failed_attempts`. Returns true when it got locked.
+    fn fail(&self, username: &str, max_failed_attempts: u32, lockout: Duration) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        let (failures, locked_until) = entries.entry(username.to_string()).or_default();
+        *failures += 1;
+        if *failures < max_failed_attempts {
+            return false;
+        }
+        *failures = 0;
+        *locked_until = Some(
+            Utc::now()
+                .checked_add_signed(lockout)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        );
+        true
+    }
+
+    fn reset(&self, username: &str) {
+        self.entries.lock().unwrap().remove(username);
+    }
+}
+
+/// Converts `lockout_minutes` to a duration, clamping values too large for
+/// chrono to the longest duration it represents.
+fn lockout_duration(minutes: u64) -> Duration {
+    i64::try_from(minutes)
+        .ok()
+        .and_then(Duration::try_minutes)
+        .unwrap_or(Duration::MAX)
+}
+
+fn locked_response(remaining: Duration) -> Response {
+    let seconds = remaining.num_seconds().max(1);
+    let mut response = error_response(
+        StatusCode::LOCKED,
+        "account_locked",
+        format!(
+            "Too many failed login attempts, try again in {} seconds",
+            seconds
+        ),
+    );
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+/// Finds the user matching the credentials.
+fn find_user(db: &Db, auth_def: &RouteAuth, username: &str, password: &str) -> Option<Value> {
+    let sql = format!(
+        r#"
+            SELECT * FROM {}
+            WHERE {} = ? AND {} = ?
+        "#,
+        auth_def.user_collection.name, auth_def.username_field, auth_def.password_field
+    );
+    let users = db.query_with_args(&sql, json!([username, password])).ok()?;
+    users
+        .into_iter()
+        .find(|item| check_password(item, password.to_string(), &auth_def.password_field))
+}
+
+fn is_flagged(user: &Value, field: &str) -> bool {
+    user.get(field).and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// Issues the session token of a logged-in user, flagging the response when
+/// the user must change their password.
+pub(crate) async fn issue_session(db: &Db, user: &Value, auth_def: &RouteAuth) -> Response {
+    let Some(token_collection) = db.get(&auth_def.token_collection.name) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mut response = generate_token(token_collection, user, auth_def);
+    if is_flagged(user, MUST_CHANGE_PASSWORD_FIELD) && response.status().is_success() {
+        response = flag_must_change_password(response).await;
+    }
+    response
+}
+
 /// Registers the login route and token issuing behavior for an auth definition.
-pub fn create_login_route(app: &mut App, auth_def: &RouteAuth) {
+///
+/// With `max_failed_attempts`, consecutive failed logins lock the username,
+/// which answers `423 Locked` until the lockout ends. Users flagged with
+/// `password_expired` must change their password before logging in, and
+/// when MFA applies, the login answers with a challenge instead of a token.
+pub fn create_login_route(app: &mut App, auth_def: &RouteAuth, challenges: Arc<MfaChallenges>) {
     let login_route = format!("{}{}", auth_def.route, auth_def.login_endpoint);
-    let token_collection = auth_def.token_collection.name.clone();
-    let user_collection = auth_def.user_collection.name.clone();
     let username_field = auth_def.username_field.clone();
     let password_field = auth_def.password_field.clone();
     let delay = auth_def.delay;
+    let attempts = Arc::new(LoginAttempts::default());
+    let lockout = lockout_duration(auth_def.lockout_minutes);
 
     // POST /resource/login - auth
     let db = app.db.clone();
 
     let auth_def_clone = auth_def.clone();
     let create_router = post(move |Json(payload): Json<Value>| async move {
-        delay.sleep_thread();
+        delay.sleep().await;
 
-        if let Some((username, password)) =
+        let Some((username, password)) =
             try_get_auth_info(payload, &username_field, &password_field)
-        {
-            let sql = format!(
-                r#"
-                    SELECT * FROM {user_collection}
-                    WHERE {username_field} = ? AND {password_field} = ?
-                "#
-            );
+        else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
 
-            let users = db.query_with_args(&sql, json!([username, password]));
-            if users.is_err() {
-                return StatusCode::UNAUTHORIZED.into_response();
-            }
+        if let Some(remaining) = attempts.locked_for(&username) {
+            return locked_response(remaining);
+        }
 
-            let users = users.unwrap();
-            if users.is_empty() {
-                return StatusCode::UNAUTHORIZED.into_response();
+        let Some(item) = find_user(&db, &auth_def_clone, &username, &password) else {
+            if let Some(max_failed_attempts) = auth_def_clone.max_failed_attempts
+                && attempts.fail(&username, max_failed_attempts, lockout)
+            {
+                return locked_response(lockout);
             }
+            return StatusCode::UNAUTHORIZED.into_response();
+        };
+        attempts.reset(&username);
 
-            return match users.first() {
-                Some(item) => {
-                    if check_password(item, password, &auth_def_clone.password_field) {
-                        let token_collection = db.get(&token_collection).unwrap();
-                        (
-                            StatusCode::OK,
-                            generate_token(token_collection, item, &auth_def_clone),
-                        )
-                            .into_response()
-                    } else {
-                        StatusCode::UNAUTHORIZED.into_response()
-                    }
-                }
-                None => StatusCode::UNAUTHORIZED.into_response(),
-            };
+        if is_flagged(&item, PASSWORD_EXPIRED_FIELD) {
+            return error_response(
+                StatusCode::FORBIDDEN,
+                "password_expired",
+                format!(
+                    "The password has expired, change it with POST {}{}",
+                    auth_def_clone.route, auth_def_clone.password_endpoint
+                ),
+            );
         }
 
-        StatusCode::BAD_REQUEST.into_response()
+        if requires_mfa(&auth_def_clone, &item) {
+            return mfa_challenge_response(&challenges, &auth_def_clone, item);
+        }
+        issue_session(&db, &item, &auth_def_clone).await
     });
     app.route(&login_route, create_router, Some("POST"), None);
 }
 
+/// Adds `"must_change_password": true` to a login response body.
+async fn flag_must_change_password(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mut body: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    body[MUST_CHANGE_PASSWORD_FIELD] = Value::Bool(true);
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
+
+/// Registers the password change route, which checks the current password,
+/// stores the new one, and clears the `password_expired` and
+/// `must_change_password` flags.
+///
+/// The user is named by the payload, or by the session token when the
+/// payload has no username.
+pub fn create_password_route(app: &mut App, auth_def: &RouteAuth) {
+    let password_route = format!("{}{}", auth_def.route, auth_def.password_endpoint);
+    let db = app.db.clone();
+    let change_events = Arc::clone(&app.change_events);
+    let auth_def = auth_def.clone();
+    let delay = auth_def.delay;
+
+    let password_router = post(
+        move |headers: HeaderMap, Json(payload): Json<Value>| async move {
+            delay.sleep().await;
+
+            let username = match payload.get(&auth_def.username_field) {
+                Some(Value::String(username)) => Some(username.clone()),
+                _ => authenticated_user(&db, &auth_def, &headers)
+                    .and_then(|user| user.get(&auth_def.username_field).cloned())
+                    .and_then(|username| username.as_str().map(String::from)),
+            };
+            let password = payload
+                .get(&auth_def.password_field)
+                .and_then(Value::as_str);
+            let new_password = payload.get(NEW_PASSWORD_FIELD).and_then(Value::as_str);
+            let (Some(username), Some(password), Some(new_password)) =
+                (username, password, new_password)
+            else {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_payload",
+                    format!(
+                        "Expected {}, {}, and {}",
+                        auth_def.username_field, auth_def.password_field, NEW_PASSWORD_FIELD
+                    ),
+                );
+            };
+
+            let Some(user) = find_user(&db, &auth_def, &username, password) else {
+                return StatusCode::UNAUTHORIZED.into_response();
+            };
+            let changes = json!({
+                auth_def.password_field.clone(): new_password,
+                PASSWORD_EXPIRED_FIELD: false,
+                MUST_CHANGE_PASSWORD_FIELD: false,
+            });
+            update_user(&db, &change_events, &auth_def, user, changes, |_| {
+                Json(json!({ "message": "Password changed" })).into_response()
+            })
+        },
+    );
+
+    app.route(&password_route, password_router, Some("POST"), None);
+}
+
+/// Applies `changes` to a stored user, publishes the update, and answers
+/// with `respond` applied to the updated user.
+fn update_user(
+    db: &Db,
+    change_events: &ChangeEvents,
+    auth_def: &RouteAuth,
+    user: Value,
+    changes: Value,
+    respond: impl FnOnce(Value) -> Response,
+) -> Response {
+    let collection = &auth_def.user_collection.name;
+    let Some(users) = db.get(collection) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let id = user
+        .get(&auth_def.user_collection.id_key)
+        .and_then(id_string)
+        .unwrap_or_default();
+    match users.update_partial(&id, changes) {
+        Ok(Some(updated)) => {
+            change_events.publish(Change::updated(collection, &id, Some(user), &updated));
+            respond(updated)
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => write_error_response(err),
+    }
+}
+
+/// Returns the user of a valid, unrevoked session token.
+fn authenticated_user(db: &Db, auth_def: &RouteAuth, headers: &HeaderMap) -> Option<Value> {
+    let token = token_from_headers(headers, &auth_def.cookie_name)?;
+    let claims = decode_jwt(&token, &auth_def.jwt_secret).ok()?.claims;
+    let tokens = db.get(&auth_def.token_collection.name)?;
+    if !tokens.exists(&token).unwrap_or(false) {
+        return None;
+    }
+
+    let sql = format!(
+        "SELECT * FROM {} WHERE {} = ?",
+        auth_def.user_collection.name, auth_def.username_field
+    );
+    db.query_with_args(&sql, json!([claims.username]))
+        .ok()?
+        .into_iter()
+        .next()
+}
+
+fn unauthorized_session() -> Response {
+    error_response(
+        StatusCode::UNAUTHORIZED,
+        "unauthorized",
+        "A valid session token is required",
+    )
+}
+
+fn without_password(mut user: Value, auth_def: &RouteAuth) -> Value {
+    if let Some(fields) = user.as_object_mut() {
+        fields.remove(&auth_def.password_field);
+    }
+    user
+}
+
+/// Registers the sign-up route, which stores a new user and logs it in.
+///
+/// Answers `409 Conflict` when the username is taken.
+pub fn create_register_route(app: &mut App, auth_def: &RouteAuth) {
+    let register_route = format!("{}{}", auth_def.route, auth_def.register_endpoint);
+    let partition = app.shared_partition();
+    let change_events = Arc::clone(&app.change_events);
+    let auth_def = auth_def.clone();
+    let delay = auth_def.delay;
+
+    let register_router = post(move |Json(payload): Json<Value>| async move {
+        delay.sleep().await;
+
+        let Some((username, _)) = try_get_auth_info(
+            payload.clone(),
+            &auth_def.username_field,
+            &auth_def.password_field,
+        ) else {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_payload",
+                format!(
+                    "Expected {} and {}",
+                    auth_def.username_field, auth_def.password_field
+                ),
+            );
+        };
+        let db = &partition.db;
+        let collection_name = &auth_def.user_collection.name;
+        let Some(users) = db.get(collection_name) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let sql = format!(
+            "SELECT * FROM {} WHERE {} = ?",
+            collection_name, auth_def.username_field
+        );
+        if db
+            .query_with_args(&sql, json!([username]))
+            .is_ok_and(|existing| !existing.is_empty())
+        {
+            return error_response(
+                StatusCode::CONFLICT,
+                "username_taken",
+                format!("The username {} is already registered", username),
+            );
+        }
+
+        let user = match random::current().add_item(&users, payload) {
+            Ok(user) => user,
+            Err(err) => return add_error_response(err),
+        };
+        if let Some(id) = user
+            .get(&auth_def.user_collection.id_key)
+            .and_then(id_string)
+        {
+            change_events.publish(Change::created(collection_name, &id, &user));
+            partition.collection_order.push(collection_name, id);
+        }
+
+        let Some(token_collection) = db.get(&auth_def.token_collection.name) else {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+        let mut response = generate_token(token_collection, &user, &auth_def);
+        if response.status().is_success() {
+            *response.status_mut() = StatusCode::CREATED;
+        }
+        response
+    });
+
+    app.route(&register_route, register_router, Some("POST"), None);
+}
+
+/// Registers `GET` and `PATCH` on the profile route, which read and update
+/// the user of the session token. The password, id, and roles fields cannot
+/// be changed through it.
+pub fn create_me_route(app: &mut App, auth_def: &RouteAuth) {
+    let me_route = format!("{}{}", auth_def.route, auth_def.me_endpoint);
+    let db = app.db.clone();
+    let change_events = Arc::clone(&app.change_events);
+    let auth_def = Arc::new(auth_def.clone());
+    let delay = auth_def.delay;
+
+    let read_db = Arc::clone(&db);
+    let read_auth_def = Arc::clone(&auth_def);
+    let me_router = get(move |headers: HeaderMap| async move {
+        delay.sleep().await;
+
+        match authenticated_user(&read_db, &read_auth_def, &headers) {
+            Some(user) => Json(without_password(user, &read_auth_def)).into_response(),
+            None => unauthorized_session(),
+        }
+    });
+    app.route(&me_route, me_router, Some("GET"), None);
+
+    let update_router = patch(
+        move |headers: HeaderMap, Json(payload): Json<Value>| async move {
+            delay.sleep().await;
+
+            let Some(user) = authenticated_user(&db, &auth_def, &headers) else {
+                return unauthorized_session();
+            };
+            let Value::Object(mut changes) = payload else {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_payload",
+                    "Expected a JSON object",
+                );
+            };
+            for field in [
+                &auth_def.password_field,
+                &auth_def.roles_field,
+                &auth_def.user_collection.id_key,
+            ] {
+                changes.remove(field);
+            }
+            let changes = Value::Object(changes);
+            update_user(&db, &change_events, &auth_def, user, changes, |updated| {
+                Json(without_password(updated, &auth_def)).into_response()
+            })
+        },
+    );
+    app.route(&me_route, update_router, Some("PATCH"), None);
+}
+
 fn decode_jwt(jwt_token: &str, jwt_secret: &str) -> Result<TokenData<Claims>, StatusCode> {
     let result: Result<TokenData<Claims>, StatusCode> = decode(
         jwt_token,
@@ -241,8 +619,12 @@ fn decode_jwt(jwt_token: &str, jwt_secret: &str) -> Result<TokenData<Claims>, St
 }
 
 fn extract_token_from_request(req: &Request, cookie_name: &str) -> Option<String> {
+    token_from_headers(req.headers(), cookie_name)
+}
+
+fn token_from_headers(headers: &HeaderMap, cookie_name: &str) -> Option<String> {
     // Try to get token from Authorization header first
-    if let Some(auth_header) = req.headers().get("Authorization")
+    if let Some(auth_header) = headers.get("Authorization")
         && let Ok(auth_str) = auth_header.to_str()
         && let Some(token) = auth_str.strip_prefix("Bearer ")
     {
@@ -250,20 +632,10 @@ fn extract_token_from_request(req: &Request, cookie_name: &str) -> Option<String
     }
 
     // Try to get token from cookies if not found in header
-    if let Some(cookie_header) = req.headers().get("Cookie")
-        && let Ok(cookie_str) = cookie_header.to_str()
-    {
-        for cookie in cookie_str.split(';') {
-            let cookie = cookie.trim();
-            if let Some((name, value)) = cookie.split_once('=')
-                && name.trim() == cookie_name
-            {
-                return Some(value.trim().to_string());
-            }
-        }
-    }
-
-    None
+    request_cookies(headers)
+        .into_iter()
+        .find(|(name, _)| name == cookie_name)
+        .map(|(_, value)| value)
 }
 
 type AuthMiddlewareReturn =
@@ -315,7 +687,7 @@ pub fn create_logout_route(app: &mut App, auth_def: &RouteAuth) {
 
     let logout_router = post(move |req: Request| {
         async move {
-            delay.sleep_thread();
+            delay.sleep().await;
 
             // Extract token from request
             let token = match extract_token_from_request(&req, &cookie_name) {
@@ -340,7 +712,7 @@ pub fn create_logout_route(app: &mut App, auth_def: &RouteAuth) {
 
 /// Creates auth storage, user REST routes, login, and logout routes.
 pub fn build_auth_routes(app: &mut App, auth_def: &RouteAuth) {
-    println!("Starting loading Auth route");
+    app.log("Starting loading Auth route");
 
     let mut shared_info = GLOBAL_SHARED_INFO.write().unwrap();
     shared_info.jwt_secret = auth_def.jwt_secret.clone();
@@ -371,19 +743,25 @@ pub fn build_auth_routes(app: &mut App, auth_def: &RouteAuth) {
 
     let users_collection = build_rest_routes(app, &rest_config);
 
-    println!("✔️ Built REST routes for {}", users_routes);
+    app.log(format!("✔️ Built REST routes for {}", users_routes));
 
     if users_collection.count().unwrap_or(0) == 0 {
         return eprintln!("⚠️ Authentication routes were not created");
     }
 
-    create_login_route(app, auth_def);
+    let challenges = Arc::new(MfaChallenges::default());
+    create_login_route(app, auth_def, Arc::clone(&challenges));
+    create_mfa_route(app, auth_def, challenges);
     create_logout_route(app, auth_def);
+    create_password_route(app, auth_def);
+    create_register_route(app, auth_def);
+    create_me_route(app, auth_def);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::totp_code;
     use axum::{
         body::{Body, to_bytes},
         http::{
@@ -402,6 +780,9 @@ mod tests {
             delay: None,
             login_endpoint: "/login".to_string(),
             logout_endpoint: "/logout".to_string(),
+            password_endpoint: "/password".to_string(),
+            register_endpoint: "/register".to_string(),
+            me_endpoint: "/me".to_string(),
             users_route: "/auth/users".to_string(),
             token_collection: crate::route_builder::CollectionConfig {
                 name: "tokens".to_string(),
@@ -419,6 +800,12 @@ mod tests {
             jwt_secret: "test-secret".to_string(),
             cookie_name: "auth_token".to_string(),
             encrypt_password: false,
+            max_failed_attempts: None,
+            lockout_minutes: 15,
+            mfa_code: None,
+            mfa_secret: None,
+            mfa_endpoint: "/mfa/verify".to_string(),
+            mfa_challenge_minutes: 5,
         }
     }
 
@@ -507,6 +894,204 @@ mod tests {
         assert_eq!(missing_logout_token.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[tokio::test]
+    async fn failed_logins_lock_the_account_and_expired_passwords_must_change() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let users_file = temp_dir.path().join("{auth}.json");
+        std::fs::write(
+            &users_file,
+            json!([
+                { "id": "1", "username": "ada", "password": "secret", "roles": "admin" },
+                { "id": "2", "username": "bob", "password": "old", "password_expired": true },
+                { "id": "3", "username": "eve", "password": "temp", "must_change_password": true },
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let mut auth_def = auth_def(users_file.into_os_string());
+        auth_def.max_failed_attempts = Some(2);
+        build_auth_routes(&mut app, &auth_def);
+        let router = app.take_router_for_test();
+        let post = |uri: &str, body: Value| {
+            let router = router.clone();
+            let request = json_request(uri, body);
+            async move { router.oneshot(request).await.unwrap() }
+        };
+
+        let wrong = json!({ "username": "ada", "password": "bad" });
+        assert_eq!(
+            post("/auth/login", wrong.clone()).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        let locked = post("/auth/login", wrong).await;
+        assert_eq!(locked.status(), StatusCode::LOCKED);
+        assert_eq!(locked.headers()[RETRY_AFTER], "900");
+        let correct = json!({ "username": "ada", "password": "secret" });
+        assert_eq!(
+            post("/auth/login", correct).await.status(),
+            StatusCode::LOCKED
+        );
+
+        let expired = json!({ "username": "bob", "password": "old" });
+        assert_eq!(
+            post("/auth/login", expired).await.status(),
+            StatusCode::FORBIDDEN
+        );
+        let change = json!({ "username": "bob", "password": "old", "new_password": "new" });
+        assert_eq!(
+            post("/auth/password", change).await.status(),
+            StatusCode::OK
+        );
+        let renewed = json!({ "username": "bob", "password": "new" });
+        assert_eq!(post("/auth/login", renewed).await.status(), StatusCode::OK);
+
+        let forced = post(
+            "/auth/login",
+            json!({ "username": "eve", "password": "temp" }),
+        )
+        .await;
+        assert_eq!(forced.status(), StatusCode::OK);
+        let body: Value =
+            serde_json::from_slice(&to_bytes(forced.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body["must_change_password"], true);
+        assert!(body["token"].is_string());
+    }
+
+    #[test]
+    fn huge_lockouts_are_clamped_instead_of_overflowing() {
+        assert_eq!(lockout_duration(15), Duration::minutes(15));
+        let lockout = lockout_duration(u64::MAX);
+        assert_eq!(lockout, Duration::MAX);
+        assert_eq!(lockout_duration(i64::MAX as u64), Duration::MAX);
+
+        let attempts = LoginAttempts::default();
+        assert!(attempts.fail("ada", 1, lockout));
+        assert!(attempts.locked_for("ada").is_some());
+        assert_eq!(locked_response(lockout).status(), StatusCode::LOCKED);
+    }
+
+    #[tokio::test]
+    async fn users_register_read_their_profile_and_change_their_password() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let users_file = temp_dir.path().join("{auth}.json");
+        let existing = json!([{ "id": "1", "username": "bob", "password": "pw" }]);
+        std::fs::write(&users_file, existing.to_string()).unwrap();
+
+        let mut app = App::default();
+        let mut auth_def = auth_def(users_file.into_os_string());
+        auth_def.user_collection.id_type = IdType::Uuid;
+        build_auth_routes(&mut app, &auth_def);
+        let router = app.take_router_for_test();
+        let call = |method: Method, uri: &str, token: Option<&str>, body: Value| {
+            let mut request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, "application/json");
+            if let Some(token) = token {
+                request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+            }
+            let request = request.body(Body::from(body.to_string())).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let sign_up = json!({ "username": "ada", "password": "secret", "name": "Ada" });
+        let (status, body) = call(Method::POST, "/auth/register", None, sign_up.clone()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(body["user"]["id"].is_string());
+        assert!(body["user"].get("password").is_none());
+        let token = body["token"].as_str().unwrap().to_string();
+        let (status, _) = call(Method::POST, "/auth/register", None, sign_up).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, me) = call(Method::GET, "/auth/me", Some(&token), Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(me["name"], "Ada");
+        assert!(me.get("password").is_none());
+        let (status, _) = call(Method::GET, "/auth/me", None, Value::Null).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let profile = json!({ "name": "Ada L.", "roles": "admin", "password": "x" });
+        let (status, me) = call(Method::PATCH, "/auth/me", Some(&token), profile).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(me["name"], "Ada L.");
+        assert!(me.get("roles").is_none());
+
+        let change = json!({ "password": "secret", "new_password": "better" });
+        let (status, _) = call(Method::POST, "/auth/password", Some(&token), change).await;
+        assert_eq!(status, StatusCode::OK);
+        let login = json!({ "username": "ada", "password": "better" });
+        let (status, _) = call(Method::POST, "/auth/login", None, login).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn mfa_logins_exchange_a_challenge_and_code_for_the_token() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let users_file = temp_dir.path().join("{auth}.json");
+        let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        std::fs::write(
+            &users_file,
+            json!([
+                { "id": "1", "username": "ada", "password": "secret" },
+                { "id": "2", "username": "bob", "password": "pw", "mfa_secret": secret },
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let mut auth_def = auth_def(users_file.into_os_string());
+        auth_def.mfa_code = Some("123456".to_string());
+        build_auth_routes(&mut app, &auth_def);
+        let router = app.take_router_for_test();
+        let post = |uri: &str, body: Value| {
+            let router = router.clone();
+            let request = json_request(uri, body);
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+
+        let login = json!({ "username": "ada", "password": "secret" });
+        let (status, body) = post("/auth/login", login).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["mfa_required"], true);
+        assert!(body.get("token").is_none());
+        let challenge = body["challenge"].as_str().unwrap().to_string();
+
+        let wrong = json!({ "challenge": challenge, "code": "000000" });
+        let (status, body) = post("/auth/mfa/verify", wrong).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_mfa_code");
+        let right = json!({ "challenge": challenge, "code": 123456 });
+        let (status, body) = post("/auth/mfa/verify", right.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["token"].is_string());
+        let (status, body) = post("/auth/mfa/verify", right).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_challenge");
+
+        let login = json!({ "username": "bob", "password": "pw" });
+        let (_, body) = post("/auth/login", login).await;
+        let code = totp_code(secret, Utc::now().timestamp()).unwrap();
+        let verify = json!({ "challenge": body["challenge"], "code": code });
+        let (status, body) = post("/auth/mfa/verify", verify).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["user"]["username"], "bob");
+    }
+
     #[test]
     fn token_extraction_supports_authorization_cookie_and_missing_values() {
         let bearer = Request::builder()
@@ -0,0 +1,210 @@
+//! The optional second login step: a login with valid credentials answers with
+//! a short-lived challenge instead of a token, and the token is issued once the
+//! challenge is verified with a static or TOTP code.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use serde_json::{Value, json};
+use sha1::Sha1;
+
+use crate::{
+    app::App,
+    handlers::{SleepDelay, error_response, issue_session},
+    route_builder::RouteAuth,
+};
+
+/// Field of a user record holding its own base32 TOTP secret, which enables
+/// the MFA step for that user.
+pub const MFA_SECRET_FIELD: &str = "mfa_secret";
+
+/// Seconds each TOTP code is valid.
+const TOTP_STEP_SECONDS: i64 = 30;
+/// Digits of a TOTP code.
+const TOTP_DIGITS: u32 = 6;
+
+/// Returns the RFC 6238 code of a base32 `secret` at `unix_seconds`, using
+/// HMAC-SHA1, 30-second steps, and 6 digits like authenticator apps.
+pub fn totp_code(secret: &str, unix_seconds: i64) -> Option<String> {
+    let normalized: String = secret
+        .chars()
+        .filter(|character| !character.is_whitespace() && *character != '=')
+        .map(|character| character.to_ascii_uppercase())
+        .collect();
+    let key = data_encoding::BASE32_NOPAD
+        .decode(normalized.as_bytes())
+        .ok()?;
+    let counter = unix_seconds.div_euclid(TOTP_STEP_SECONDS);
+
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).ok()?;
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = usize::from(hash[hash.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    Some(format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    ))
+}
+
+/// The TOTP secret that applies to `user`: its own, then the configured one.
+fn totp_secret<'a>(auth_def: &'a RouteAuth, user: &'a Value) -> Option<&'a str> {
+    user.get(MFA_SECRET_FIELD)
+        .and_then(Value::as_str)
+        .or(auth_def.mfa_secret.as_deref())
+}
+
+/// Whether logging in as `user` takes the MFA step.
+pub fn requires_mfa(auth_def: &RouteAuth, user: &Value) -> bool {
+    auth_def.mfa_code.is_some() || totp_secret(auth_def, user).is_some()
+}
+
+/// Checks `code` against the TOTP of the current, previous, and next step, or
+/// against the static code when no TOTP secret applies.
+fn accepts_code(auth_def: &RouteAuth, user: &Value, code: &str) -> bool {
+    let Some(secret) = totp_secret(auth_def, user) else {
+        return auth_def.mfa_code.as_deref() == Some(code);
+    };
+    let now = Utc::now().timestamp();
+    [-1, 0, 1].into_iter().any(|step| {
+        totp_code(secret, now + step * TOTP_STEP_SECONDS).is_some_and(|expected| expected == code)
+    })
+}
+
+/// Pending MFA challenges, by challenge token, with the user that passed the
+/// first step and the end of their validity.
+#[derive(Default)]
+pub struct MfaChallenges {
+    entries: Mutex<HashMap<String, (Value, DateTime<Utc>)>>,
+}
+
+impl MfaChallenges {
+    /// Stores a challenge for `user`, valid for `ttl`, and returns its token.
+    fn issue(&self, user: Value, ttl: Duration) -> String {
+        let challenge = uuid::Uuid::new_v4().to_string();
+        let mut entries = self.entries.lock().unwrap();
+        let now = Utc::now();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        entries.insert(challenge.clone(), (user, now + ttl));
+        challenge
+    }
+
+    /// Returns the user of a pending, unexpired challenge.
+    fn user(&self, challenge: &str) -> Option<Value> {
+        let entries = self.entries.lock().unwrap();
+        let (user, expires_at) = entries.get(challenge)?;
+        (*expires_at > Utc::now()).then(|| user.clone())
+    }
+
+    fn remove(&self, challenge: &str) {
+        self.entries.lock().unwrap().remove(challenge);
+    }
+}
+
+/// Answers a login that passed the first step with a new challenge.
+pub fn mfa_challenge_response(
+    challenges: &MfaChallenges,
+    auth_def: &RouteAuth,
+    user: Value,
+) -> Response {
+    let ttl = Duration::minutes(i64::try_from(auth_def.mfa_challenge_minutes).unwrap_or(i64::MAX));
+    let challenge = challenges.issue(user, ttl);
+    Json(json!({
+        "mfa_required": true,
+        "challenge": challenge,
+        "expires_in": ttl.num_seconds(),
+    }))
+    .into_response()
+}
+
+/// Registers the MFA verification route, which exchanges a challenge and a
+/// valid code for the session token a login without MFA returns.
+pub fn create_mfa_route(app: &mut App, auth_def: &RouteAuth, challenges: Arc<MfaChallenges>) {
+    let mfa_route = format!("{}{}", auth_def.route, auth_def.mfa_endpoint);
+    let db = app.db.clone();
+    let auth_def = auth_def.clone();
+    let delay = auth_def.delay;
+
+    let mfa_router = post(move |Json(payload): Json<Value>| async move {
+        delay.sleep().await;
+
+        let challenge = payload.get("challenge").and_then(Value::as_str);
+        let code = payload.get("code").and_then(|code| match code {
+            Value::String(code) => Some(code.trim().to_string()),
+            Value::Number(code) => Some(code.to_string()),
+            _ => None,
+        });
+        let (Some(challenge), Some(code)) = (challenge, code) else {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_payload",
+                "Expected challenge and code",
+            );
+        };
+
+        let Some(user) = challenges.user(challenge) else {
+            return error_response(
+                StatusCode::UNAUTHORIZED,
+                "invalid_challenge",
+                "The MFA challenge is unknown or expired, log in again",
+            );
+        };
+        if !accepts_code(&auth_def, &user, &code) {
+            return error_response(
+                StatusCode::UNAUTHORIZED,
+                "invalid_mfa_code",
+                "The MFA code is not valid",
+            );
+        }
+        challenges.remove(challenge);
+        issue_session(&db, &user, &auth_def).await
+    });
+
+    app.route(&mfa_route, mfa_router, Some("POST"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totp_matches_the_rfc_6238_vectors() {
+        // Base32 of the RFC test key "12345678901234567890"
+        let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        assert_eq!(totp_code(secret, 59).unwrap(), "287082");
+        assert_eq!(totp_code(secret, 1111111109).unwrap(), "081804");
+        assert_eq!(
+            totp_code("gezd gnbv gy3t qojq gezd gnbv gy3t qojq", 59).unwrap(),
+            "287082"
+        );
+        assert!(totp_code("not base32!", 59).is_none());
+    }
+
+    #[test]
+    fn challenges_expire() {
+        let challenges = MfaChallenges::default();
+        let live = challenges.issue(json!({ "id": "1" }), Duration::minutes(5));
+        let expired = challenges.issue(json!({ "id": "2" }), Duration::seconds(-1));
+
+        assert_eq!(challenges.user(&live).unwrap()["id"], "1");
+        assert!(challenges.user(&expired).is_none());
+        challenges.remove(&live);
+        assert!(challenges.user(&live).is_none());
+    }
+}
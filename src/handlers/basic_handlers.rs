@@ -3,33 +3,68 @@
 use std::{ffi::OsString, fs, sync::Arc};
 
 use axum::{
+    Json,
     body::Body,
-    extract::{FromRequestParts, Path as AxumPath, Request},
+    extract::{FromRequestParts, Path as AxumPath, Query, RawPathParams, Request},
     http::StatusCode,
+    middleware::{self, Next},
     response::IntoResponse,
     routing::{MethodRouter, delete, get, options, patch, post, put},
 };
+use chrono::Utc;
 use http::{HeaderMap, HeaderValue, header::CONTENT_TYPE};
-use jgd_rs::generate_jgd_from_file;
 use mime_guess::from_path;
+use regex::Regex;
 use serde_json::{Map, Value, json};
 use tokio::fs::File;
 use tokio_util::io::ReaderStream;
+use tower::ServiceExt;
 
 use crate::{
     app::App,
-    handlers::{is_jgd, is_sql, is_text_file, query},
+    handlers::{
+        build_script_router, is_jgd, is_json, is_rhai, is_sql, is_text_file, query,
+        request_cookies, sniff_content_type,
+    },
+    random,
+    route_builder::QueryVariant,
+    templates::{render_json_text, render_value},
 };
 
 fn get_file_content(file_path: &OsString) -> String {
-    fs::read_to_string(file_path).unwrap()
+    let content = fs::read_to_string(file_path).unwrap();
+    render_file_content(file_path, content)
+}
+
+fn render_file_content(file_path: &OsString, content: String) -> String {
+    if is_json(file_path) {
+        return render_json_text(&content);
+    }
+    content
 }
 
 /// Builds a router that streams a non-text file with an inferred content type.
+///
+/// Files without a known extension are read whole and their content type is
+/// sniffed from their first bytes.
 pub fn build_stream_handler(file_path: OsString, method: &str) -> MethodRouter {
     let handler = move || {
         let file_path = file_path.clone();
         async move {
+            if from_path(&file_path).first().is_none() {
+                return match tokio::fs::read(&file_path).await {
+                    Ok(bytes) => {
+                        let content_type = sniff_content_type(&bytes);
+                        ([(CONTENT_TYPE, content_type)], bytes).into_response()
+                    }
+                    Err(_) => (
+                        StatusCode::NOT_FOUND,
+                        format!("File not found: {}", file_path.display()),
+                    )
+                        .into_response(),
+                };
+            }
+
             // Open the file
             let file = File::open(&file_path).await;
 
@@ -72,15 +107,26 @@ pub fn build_stream_handler(file_path: OsString, method: &str) -> MethodRouter {
 }
 
 /// Builds a router that serves text, JGD-generated JSON, or SQL query results.
+///
+/// With `lazy_files`, text files are cached by modification time, so an edited
+/// file is served on the next request.
 pub fn content_handler(app: &mut App, file_path: OsString, method: &str) -> MethodRouter {
     let file_path = file_path.clone();
-    let db = Arc::clone(&app.db);
+    let shared = app.shared_partition();
+    let partitions = Arc::clone(&app.partitions);
+    let lazy_files = app
+        .reads_files_lazily()
+        .then(|| Arc::clone(&app.lazy_files));
 
     let handler = move |req: Request| {
         let file_path = file_path.clone();
+        let db = partitions.resolve(&shared, req.headers()).db;
+        let lazy_files = lazy_files.clone();
         async move {
             if is_jgd(&file_path) {
-                let json = generate_jgd_from_file(&file_path.into());
+                let json = random::current()
+                    .generate_jgd(file_path.as_ref())
+                    .map(|json| render_value(&json, Utc::now()));
                 match json {
                     Ok(Value::Array(items)) => {
                         let mut data: Map<String, Value> = Map::new();
@@ -104,6 +150,11 @@ pub fn content_handler(app: &mut App, file_path: OsString, method: &str) -> Meth
                         .into_response(),
                     Err(_) => StatusCode::BAD_REQUEST.into_response(),
                 }
+            } else if let Some(lazy_files) = lazy_files {
+                match lazy_files.read(file_path.as_ref()) {
+                    Ok(content) => render_file_content(&file_path, content).into_response(),
+                    Err(_) => StatusCode::NOT_FOUND.into_response(),
+                }
             } else {
                 get_file_content(&file_path).into_response()
             }
@@ -123,10 +174,114 @@ pub fn content_handler(app: &mut App, file_path: OsString, method: &str) -> Meth
     }
 }
 
+/// Builds a router that answers with a JSON value, rendering its placeholders on every request.
+///
+/// Returns `None` for an unknown method.
+pub fn build_value_router(body: Value, method: &str) -> Option<MethodRouter> {
+    let handler = move || {
+        let body = render_value(&body, Utc::now());
+        async move { Json(body) }
+    };
+
+    let router = match method.to_uppercase().as_str() {
+        "GET" => get(handler),
+        "POST" => post(handler),
+        "PUT" => put(handler),
+        "PATCH" => patch(handler),
+        "DELETE" => delete(handler),
+        "OPTIONS" => options(handler),
+        "QUERY" => query(handler),
+        _ => return None,
+    };
+    Some(router)
+}
+
+/// Builds a router that serves the most specific variant whose query
+/// parameters and cookies the request carries, preferring earlier variants on ties.
+///
+/// Answers `404 Not Found` when no variant matches.
+pub fn build_query_variant_router(
+    variants: Vec<(QueryVariant, MethodRouter)>,
+    method: &str,
+) -> MethodRouter {
+    let variants = Arc::new(variants);
+    let handler = move |req: Request| {
+        let variants = Arc::clone(&variants);
+        async move {
+            let query = Query::<Vec<(String, String)>>::try_from_uri(req.uri())
+                .map(|Query(query)| query)
+                .unwrap_or_default();
+            let cookies = request_cookies(req.headers());
+            let mut selected: Option<&(QueryVariant, MethodRouter)> = None;
+            for variant in variants
+                .iter()
+                .filter(|(variant, _)| variant.matches(&query, &cookies))
+            {
+                if selected.is_none_or(|(best, _)| variant.0.query.len() > best.query.len()) {
+                    selected = Some(variant);
+                }
+            }
+
+            match selected {
+                Some((_, router)) => router
+                    .clone()
+                    .oneshot(req)
+                    .await
+                    .unwrap_or_else(|err| match err {}),
+                None => StatusCode::NOT_FOUND.into_response(),
+            }
+        }
+    };
+
+    match method.to_uppercase().as_str() {
+        "GET" => get(handler),
+        "POST" => post(handler),
+        "PUT" => put(handler),
+        "PATCH" => patch(handler),
+        "DELETE" => delete(handler),
+        "OPTIONS" => options(handler),
+        "QUERY" => query(handler),
+        // Fallback for an unknown method string
+        _ => get(|| async { "Unknown method in filename" }),
+    }
+}
+
+/// Answers `404 Not Found` unless the `name` path parameter matches `regex`.
+pub fn constrain_param(router: MethodRouter, name: &str, regex: &Regex) -> MethodRouter {
+    let name = name.to_string();
+    let regex = regex.clone();
+    router.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let name = name.clone();
+        let regex = regex.clone();
+        async move {
+            let (mut parts, body) = req.into_parts();
+            let matches = RawPathParams::from_request_parts(&mut parts, &())
+                .await
+                .is_ok_and(|params| {
+                    params
+                        .iter()
+                        .any(|(param, value)| param == name && regex.is_match(value))
+                });
+            if !matches {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+            next.run(Request::from_parts(parts, body)).await
+        }
+    }))
+}
+
 /// Builds the correct method router for a mock file based on its extension.
+///
+/// With `lazy_files`, every file except scripts is declared in
+/// [`App::lazy_files`], since its handler reads it on each request.
 pub fn build_method_router(app: &mut App, file_path: &OsString, method: &str) -> MethodRouter {
     let file_path = file_path.clone();
-    if is_text_file(&file_path) {
+    if app.reads_files_lazily() && !is_rhai(&file_path) {
+        app.lazy_files.register(file_path.as_ref());
+    }
+    if is_rhai(&file_path) {
+        build_script_router(app, file_path, method)
+    } else if is_text_file(&file_path) {
         content_handler(app, file_path, method)
     } else {
         build_stream_handler(file_path, method)
@@ -222,6 +377,37 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn content_handler_renders_json_placeholders_per_request() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("user.json");
+        std::fs::write(
+            &file_path,
+            r#"{"id":"{{faker.uuid}}","age":"{{faker.int 30 30}}"}"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let router = build_method_router(&mut app, &file_path.into_os_string(), "GET");
+        app.route("/user", router, Some("GET"), None);
+        let router = app.take_router_for_test();
+
+        let mut ids = vec![];
+        for _ in 0..2 {
+            let response = router
+                .clone()
+                .oneshot(Request::builder().uri("/user").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let body: Value =
+                serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                    .unwrap();
+            assert_eq!(body["age"], 30);
+            ids.push(body["id"].as_str().unwrap().to_string());
+        }
+        assert_ne!(ids[0], ids[1]);
+    }
+
     #[tokio::test]
     async fn stream_handler_serves_binary_and_sets_content_type() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -253,6 +439,32 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn stream_handler_sniffs_extensionless_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("get");
+        std::fs::write(&file_path, r#"{"id": 1}"#).unwrap();
+
+        let mut app = App::default();
+        let router = build_method_router(&mut app, &file_path.into_os_string(), "GET");
+        app.route("/users", router, Some("GET"), None);
+
+        let response = app
+            .take_router_for_test()
+            .oneshot(
+                Request::builder()
+                    .uri("/users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+
     #[tokio::test]
     async fn stream_handler_reports_missing_file() {
         let mut app = App::default();
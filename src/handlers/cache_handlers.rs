@@ -0,0 +1,187 @@
+//! Memoized bodies of basic routes, so JGD files and templates with large
+//! payloads are not rendered on every request.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json,
+    body::{Body, Bytes, to_bytes},
+    extract::Request,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{MethodRouter, delete},
+};
+use http::{HeaderMap, StatusCode, header::CONTENT_LENGTH};
+use serde_json::json;
+
+use crate::app::{App, MOCK_SERVER_ROUTE};
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    expires_at: Instant,
+}
+
+/// Rendered responses of routes with a `cache_ttl`, keyed by route and variant.
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    entries: RwLock<HashMap<String, CachedResponse>>,
+    routes: AtomicUsize,
+}
+
+impl ResponseCache {
+    /// Counts a route whose responses are cached.
+    pub fn register(&self) {
+        self.routes.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns true when no route caches its responses.
+    pub fn is_empty(&self) -> bool {
+        self.routes.load(Ordering::SeqCst) == 0
+    }
+
+    /// Returns the cached response of a key, unless it has expired.
+    fn get(&self, key: &str) -> Option<Response> {
+        let entries = self.entries.read().unwrap();
+        let cached = entries
+            .get(key)
+            .filter(|cached| cached.expires_at > Instant::now())?;
+        let mut response = Response::new(Body::from(cached.body.clone()));
+        *response.status_mut() = cached.status;
+        *response.headers_mut() = cached.headers.clone();
+        Some(response)
+    }
+
+    fn insert(&self, key: String, cached: CachedResponse) {
+        let mut entries = self.entries.write().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, cached| cached.expires_at > now);
+        entries.insert(key, cached);
+    }
+
+    /// Drops every cached response and returns how many there were.
+    pub fn flush(&self) -> usize {
+        let mut entries = self.entries.write().unwrap();
+        let flushed = entries.len();
+        entries.clear();
+        flushed
+    }
+}
+
+/// Serves the successful responses of `router` from `cache` for `ttl`.
+///
+/// Entries are keyed by `variant`, the file answering the request, and by the
+/// request path, so `/users/1` and `/users/2` are cached apart.
+pub fn cache_router(
+    router: MethodRouter,
+    variant: &str,
+    ttl: Duration,
+    cache: &Arc<ResponseCache>,
+) -> MethodRouter {
+    cache.register();
+    let variant = variant.to_string();
+    let cache = Arc::clone(cache);
+    router.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let key = format!("{} {} {}", variant, req.method(), req.uri().path());
+        let cache = Arc::clone(&cache);
+        async move {
+            if let Some(response) = cache.get(&key) {
+                return response;
+            }
+
+            let response = next.run(req).await;
+            if !response.status().is_success() {
+                return response;
+            }
+            let (mut parts, body) = response.into_parts();
+            let Ok(body) = to_bytes(body, usize::MAX).await else {
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            };
+            parts.headers.remove(CONTENT_LENGTH);
+            cache.insert(
+                key,
+                CachedResponse {
+                    status: parts.status,
+                    headers: parts.headers.clone(),
+                    body: body.clone(),
+                    expires_at: Instant::now() + ttl,
+                },
+            );
+            Response::from_parts(parts, Body::from(body))
+        }
+    }))
+}
+
+/// Registers `DELETE /mock-server/cache`, which drops every cached response.
+pub fn create_cache_routes(app: &mut App) {
+    let cache_route = format!("{}/cache", MOCK_SERVER_ROUTE);
+    let cache = Arc::clone(&app.response_cache);
+
+    let flush_router = delete(async move || {
+        let flushed = cache.flush();
+        Json(json!({ "flushed": flushed })).into_response()
+    });
+    app.route(&cache_route, flush_router, Some("DELETE"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn responses_are_cached_per_path_until_flushed_or_expired() {
+        let renders = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(ResponseCache::default());
+        let counter = Arc::clone(&renders);
+        let rendered =
+            get(
+                move || async move { format!("render {}", counter.fetch_add(1, Ordering::SeqCst)) },
+            );
+        let router = Router::new()
+            .route(
+                "/reports/{id}",
+                cache_router(
+                    rendered.clone(),
+                    "get{id}.jgd",
+                    Duration::from_secs(60),
+                    &cache,
+                ),
+            )
+            .route(
+                "/expired",
+                cache_router(rendered, "get.jgd", Duration::ZERO, &cache),
+            );
+        assert!(!cache.is_empty());
+
+        let call = |uri: &'static str| {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                to_bytes(response.into_body(), usize::MAX).await.unwrap()
+            }
+        };
+
+        assert_eq!(call("/reports/1").await, "render 0");
+        assert_eq!(call("/reports/1").await, "render 0");
+        assert_eq!(call("/reports/2").await, "render 1");
+        assert_eq!(call("/expired").await, "render 2");
+        assert_eq!(call("/expired").await, "render 3");
+
+        assert_eq!(cache.flush(), 3);
+        assert_eq!(call("/reports/1").await, "render 4");
+    }
+}
@@ -0,0 +1,300 @@
+//! Events emitted for every item created, updated, or deleted in a collection,
+//! so live views, webhooks, and logs can follow writes as they happen.
+//!
+//! REST routes, scripts, GraphQL mutations, batches, and the collections
+//! endpoints publish onto one broadcast channel. `GET /mock-server/events`
+//! streams the events as server-sent events, and the [`AuditLog`] keeps them.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use axum::{
+    extract::Query,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio_stream::{StreamExt, wrappers::BroadcastStream};
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE},
+    handlers::AuditLog,
+};
+
+/// Events kept for subscribers that fall behind; older events are dropped.
+pub const CHANGE_EVENTS_CAPACITY: usize = 1024;
+
+/// What happened to an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeKind {
+    /// Name of the kind, as serialized.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Updated => "updated",
+            ChangeKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// A write to a collection item, before it is numbered and published.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub collection: String,
+    pub id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl Change {
+    /// An item added to a collection.
+    pub fn created(collection: &str, id: &str, after: &Value) -> Self {
+        Self::new(
+            ChangeKind::Created,
+            collection,
+            id,
+            None,
+            Some(after.clone()),
+        )
+    }
+
+    /// An item replaced or patched in a collection.
+    pub fn updated(collection: &str, id: &str, before: Option<Value>, after: &Value) -> Self {
+        Self::new(
+            ChangeKind::Updated,
+            collection,
+            id,
+            before,
+            Some(after.clone()),
+        )
+    }
+
+    /// An item removed from a collection.
+    pub fn deleted(collection: &str, id: &str, before: &Value) -> Self {
+        Self::new(
+            ChangeKind::Deleted,
+            collection,
+            id,
+            Some(before.clone()),
+            None,
+        )
+    }
+
+    /// The dependents removed by a cascading delete, as returned by
+    /// [`ReferenceConstraints::apply_delete`](crate::handlers::ReferenceConstraints::apply_delete).
+    pub fn cascaded(deleted: Vec<(String, String, Value)>) -> Vec<Self> {
+        deleted
+            .into_iter()
+            .map(|(collection, id, before)| {
+                Self::new(ChangeKind::Deleted, &collection, &id, Some(before), None)
+            })
+            .collect()
+    }
+
+    fn new(
+        kind: ChangeKind,
+        collection: &str,
+        id: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Self {
+        Self {
+            kind,
+            collection: collection.to_string(),
+            id: id.to_string(),
+            before,
+            after,
+        }
+    }
+}
+
+/// One published write to a collection item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Position of the event since the server started, starting at 1.
+    pub sequence: u64,
+    /// When the write was published, as an RFC 3339 timestamp.
+    pub at: String,
+    pub kind: ChangeKind,
+    pub collection: String,
+    pub id: String,
+    /// The item before the write; `None` for created items.
+    pub before: Option<Value>,
+    /// The item after the write; `None` for deleted items.
+    pub after: Option<Value>,
+}
+
+/// Broadcast channel of the writes to collection items.
+pub struct ChangeEvents {
+    sender: broadcast::Sender<ChangeEvent>,
+    sequence: AtomicU64,
+    audit: Option<Arc<AuditLog>>,
+}
+
+impl Default for ChangeEvents {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl ChangeEvents {
+    /// Creates the channel, logging every event when `audit` is set.
+    pub fn new(audit: bool) -> Self {
+        Self {
+            sender: broadcast::Sender::new(CHANGE_EVENTS_CAPACITY),
+            sequence: AtomicU64::new(0),
+            audit: audit.then(Arc::default),
+        }
+    }
+
+    /// Returns the log of published events, unless it is disabled.
+    pub fn audit(&self) -> Option<Arc<AuditLog>> {
+        self.audit.clone()
+    }
+
+    /// Receives every event published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<ChangeEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Numbers and publishes a write; events without subscribers are dropped.
+    pub fn publish(&self, change: Change) {
+        let event = ChangeEvent {
+            sequence: self.sequence.fetch_add(1, Ordering::SeqCst) + 1,
+            at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            kind: change.kind,
+            collection: change.collection,
+            id: change.id,
+            before: change.before,
+            after: change.after,
+        };
+        if let Some(audit) = &self.audit {
+            audit.record(&event);
+        }
+        let _ = self.sender.send(event);
+    }
+
+    /// Publishes writes in order.
+    pub fn publish_all(&self, changes: impl IntoIterator<Item = Change>) {
+        for change in changes {
+            self.publish(change);
+        }
+    }
+}
+
+/// Registers `GET /mock-server/events`, which streams change events as
+/// server-sent events, optionally only those of `?collection=`.
+pub fn create_change_event_routes(app: &mut App) {
+    let events_route = format!("{}/events", MOCK_SERVER_ROUTE);
+    let change_events = Arc::clone(&app.change_events);
+
+    let events_router = get(async move |Query(params): Query<HashMap<String, String>>| {
+        let collection = params.get("collection").cloned();
+        let stream = BroadcastStream::new(change_events.subscribe()).filter_map(move |event| {
+            let event = event.ok()?;
+            if collection
+                .as_ref()
+                .is_some_and(|collection| *collection != event.collection)
+            {
+                return None;
+            }
+            let sse = Event::default()
+                .event(event.kind.as_str())
+                .id(event.sequence.to_string())
+                .json_data(&event)
+                .ok()?;
+            Some(Ok::<_, Infallible>(sse))
+        });
+        Sse::new(stream)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    });
+    app.route(&events_route, events_router, Some("GET"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn subscribers_receive_numbered_events() {
+        let events = ChangeEvents::default();
+        events.publish(Change::created("users", "0", &json!({ "id": 0 })));
+        let mut receiver = events.subscribe();
+
+        events.publish_all([
+            Change::updated(
+                "users",
+                "1",
+                Some(json!({ "id": 1, "name": "Ada" })),
+                &json!({ "id": 1, "name": "Grace" }),
+            ),
+            Change::deleted("users", "1", &json!({ "id": 1, "name": "Grace" })),
+        ]);
+
+        let updated = receiver.try_recv().unwrap();
+        assert_eq!(updated.sequence, 2);
+        assert_eq!(updated.kind, ChangeKind::Updated);
+        assert_eq!(updated.before.unwrap()["name"], "Ada");
+        assert_eq!(updated.after.unwrap()["name"], "Grace");
+
+        let deleted = receiver.try_recv().unwrap();
+        assert_eq!(
+            (deleted.kind, deleted.id.as_str()),
+            (ChangeKind::Deleted, "1")
+        );
+        assert_eq!(deleted.after, None);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn event_streams_send_the_writes_of_a_collection() {
+        use axum::body::Body;
+        use http::Request;
+        use tower::ServiceExt;
+
+        let mut app = App::default();
+        app.build_change_events_route();
+        let router = app.take_router_for_test();
+        let response = router
+            .oneshot(
+                Request::get("/mock-server/events?collection=orders")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let mut body = response.into_body().into_data_stream();
+
+        app.change_events.publish_all([
+            Change::created("users", "1", &json!({ "id": 1 })),
+            Change::created("orders", "7", &json!({ "id": 7 })),
+        ]);
+
+        let frame = body.next().await.unwrap().unwrap();
+        let frame = String::from_utf8(frame.to_vec()).unwrap();
+        assert!(frame.starts_with("event: created\nid: 2\n"), "{}", frame);
+        assert!(frame.contains(r#""collection":"orders""#), "{}", frame);
+    }
+}
@@ -0,0 +1,253 @@
+//! Collection items imported over HTTP from JSON or CSV, replacing or merging
+//! with the current items.
+
+use fosk::DbCollection;
+use serde_json::{Map, Number, Value};
+
+use crate::{handlers::id_string, random};
+
+/// How imported items combine with the items already in a collection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// Removes the current items first.
+    Replace,
+    /// Replaces items with the same id and adds the others.
+    Merge,
+}
+
+impl ImportMode {
+    /// Parses the `?mode=` value, defaulting to [`ImportMode::Replace`].
+    pub fn from_param(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("replace") => Some(Self::Replace),
+            Some("merge") => Some(Self::Merge),
+            Some(_) => None,
+        }
+    }
+
+    /// Returns the `?mode=` value of this mode.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Replace => "replace",
+            Self::Merge => "merge",
+        }
+    }
+}
+
+/// Parses CSV text with a header row into one object per record.
+///
+/// Fields may be quoted with `"`, doubling quotes inside them. Unquoted fields
+/// that read as numbers or booleans become JSON numbers and booleans, empty
+/// unquoted fields become `null`, and every other field is a string.
+pub fn parse_csv(text: &str) -> Result<Vec<Value>, String> {
+    let mut records = parse_records(text)?.into_iter();
+    let Some(header) = records.next() else {
+        return Ok(vec![]);
+    };
+    let header = header
+        .into_iter()
+        .map(|field| field.text)
+        .collect::<Vec<_>>();
+
+    records
+        .enumerate()
+        .map(|(index, record)| {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "record {} has {} fields, but the header has {}",
+                    index + 1,
+                    record.len(),
+                    header.len()
+                ));
+            }
+            let item = header
+                .iter()
+                .cloned()
+                .zip(record.into_iter().map(CsvField::into_value))
+                .collect::<Map<_, _>>();
+            Ok(Value::Object(item))
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct CsvField {
+    text: String,
+    quoted: bool,
+}
+
+impl CsvField {
+    fn into_value(self) -> Value {
+        if self.quoted {
+            return Value::String(self.text);
+        }
+        match self.text.as_str() {
+            "" => Value::Null,
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            text => text
+                .parse::<i64>()
+                .map(Number::from)
+                .ok()
+                .or_else(|| text.parse::<f64>().ok().and_then(Number::from_f64))
+                .map(Value::Number)
+                .unwrap_or(Value::String(self.text)),
+        }
+    }
+}
+
+fn parse_records(text: &str) -> Result<Vec<Vec<CsvField>>, String> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = CsvField::default();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.text.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.text.is_empty() && !field.quoted => {
+                in_quotes = true;
+                field.quoted = true;
+            }
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            char => field.text.push(char),
+        }
+    }
+
+    if in_quotes {
+        return Err("a quoted field is not closed".to_string());
+    }
+    if !record.is_empty() || !field.text.is_empty() || field.quoted {
+        record.push(field);
+        records.push(record);
+    }
+    // Blank lines hold a single empty field.
+    records.retain(|record| match record.as_slice() {
+        [field] => !field.text.is_empty() || field.quoted,
+        _ => true,
+    });
+    Ok(records)
+}
+
+/// Error of an import into a collection.
+#[derive(Debug)]
+pub enum ImportError {
+    /// Loading the items failed.
+    Load(fosk::LoadCollectionError),
+    /// Reading the current items failed.
+    Read(fosk::CollectionReadError),
+    /// Replacing an existing item failed.
+    Write(fosk::CollectionWriteError),
+}
+
+/// Imports items into a collection and returns the number of items imported.
+pub fn import_items(
+    collection: &DbCollection,
+    items: Vec<Value>,
+    mode: ImportMode,
+) -> Result<usize, ImportError> {
+    let Value::Array(items) = random::current().fill_ids(collection, Value::Array(items)) else {
+        return Ok(0);
+    };
+    let count = items.len();
+    if mode == ImportMode::Replace {
+        collection
+            .load_from_json(Value::Array(items), false)
+            .map_err(ImportError::Load)?;
+        return Ok(count);
+    }
+
+    let id_key = collection.get_config().map_err(ImportError::Read)?.id_key;
+    let mut new_items = vec![];
+    for item in items {
+        let existing = match item.get(&id_key).and_then(id_string) {
+            Some(id) if collection.exists(&id).map_err(ImportError::Read)? => Some(id),
+            _ => None,
+        };
+        match existing {
+            Some(id) => {
+                collection.update(&id, item).map_err(ImportError::Write)?;
+            }
+            None => new_items.push(item),
+        }
+    }
+    collection
+        .load_from_json(Value::Array(new_items), true)
+        .map_err(ImportError::Load)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fosk::DbConfig;
+    use serde_json::json;
+
+    #[test]
+    fn csv_records_become_typed_objects() {
+        let items = parse_csv(
+            "id,name,active,score,note\r\n1,Ada,true,9.5,\n2,\"Hopper, Grace\",false,7,\"say \"\"hi\"\"\"\n\"3\",Lin,,x,\"\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                json!({ "id": 1, "name": "Ada", "active": true, "score": 9.5, "note": null }),
+                json!({ "id": 2, "name": "Hopper, Grace", "active": false, "score": 7, "note": "say \"hi\"" }),
+                json!({ "id": "3", "name": "Lin", "active": null, "score": "x", "note": "" }),
+            ]
+        );
+        assert!(parse_csv("id,name\n1\n").is_err());
+        assert!(parse_csv("id\n\"1\n").is_err());
+        assert_eq!(parse_csv("").unwrap(), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn import_replaces_or_merges_items() {
+        let collection = DbCollection::new_coll("users", DbConfig::int("id"));
+        collection
+            .load_from_json(
+                json!([{ "id": 1, "name": "Ada" }, { "id": 2, "name": "Grace" }]),
+                false,
+            )
+            .unwrap();
+
+        let merged = import_items(
+            &collection,
+            vec![
+                json!({ "id": 2, "name": "Hopper" }),
+                json!({ "id": 3, "name": "Lin" }),
+            ],
+            ImportMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(merged, 2);
+        assert_eq!(collection.count().unwrap(), 3);
+        assert_eq!(collection.get("2").unwrap().unwrap()["name"], "Hopper");
+
+        import_items(&collection, vec![json!({ "id": 7 })], ImportMode::Replace).unwrap();
+        assert_eq!(collection.count().unwrap(), 1);
+        assert!(collection.exists("7").unwrap());
+    }
+
+    #[test]
+    fn import_mode_parses_the_query_value() {
+        assert_eq!(ImportMode::from_param(None), Some(ImportMode::Replace));
+        assert_eq!(
+            ImportMode::from_param(Some("MERGE")),
+            Some(ImportMode::Merge)
+        );
+        assert_eq!(ImportMode::from_param(Some("append")), None);
+    }
+}
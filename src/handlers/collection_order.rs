@@ -0,0 +1,149 @@
+//! Stable item ordering for REST collections.
+//!
+//! Fosk collections are hash maps, so `get_all` returns items in arbitrary
+//! order. This registry remembers the order in which ids were loaded or
+//! inserted and sorts listed items by it. Items written by other means are
+//! appended the first time they are listed, so their position is stable too.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::RwLock,
+};
+
+use serde_json::Value;
+
+use crate::handlers::id_string;
+
+#[derive(Debug, Default)]
+struct Order {
+    next: u64,
+    positions: HashMap<String, u64>,
+}
+
+impl Order {
+    fn append(&mut self, id: String) {
+        self.positions.insert(id, self.next);
+        self.next += 1;
+    }
+}
+
+/// Registry of item insertion order keyed by collection name.
+#[derive(Debug, Default)]
+pub struct CollectionOrder {
+    orders: RwLock<HashMap<String, Order>>,
+}
+
+impl CollectionOrder {
+    /// Replaces the order of a collection with the given ids, e.g. after loading seed data.
+    pub fn reset(&self, collection: &str, ids: impl IntoIterator<Item = String>) {
+        let mut order = Order::default();
+        for id in ids {
+            if !order.positions.contains_key(&id) {
+                order.append(id);
+            }
+        }
+        self.orders
+            .write()
+            .unwrap()
+            .insert(collection.to_string(), order);
+    }
+
+    /// Moves an id to the end of a collection, e.g. after inserting an item.
+    pub fn push(&self, collection: &str, id: String) {
+        self.orders
+            .write()
+            .unwrap()
+            .entry(collection.to_string())
+            .or_default()
+            .append(id);
+    }
+
+    /// Forgets the position of a removed item.
+    pub fn remove(&self, collection: &str, id: &str) {
+        if let Some(order) = self.orders.write().unwrap().get_mut(collection) {
+            order.positions.remove(id);
+        }
+    }
+
+    /// Removes every tracked collection.
+    pub fn clear(&self) {
+        self.orders.write().unwrap().clear();
+    }
+
+    /// Sorts items by insertion order.
+    ///
+    /// Ids seen for the first time are appended in id order, comparing numeric
+    /// ids numerically, and positions of ids that are no longer present are dropped.
+    pub fn sort(&self, collection: &str, id_key: &str, items: &mut [Value]) {
+        let mut orders = self.orders.write().unwrap();
+        let order = orders.entry(collection.to_string()).or_default();
+
+        let ids = items
+            .iter()
+            .map(|item| item.get(id_key).and_then(id_string).unwrap_or_default())
+            .collect::<HashSet<_>>();
+        order.positions.retain(|id, _| ids.contains(id));
+
+        let mut unseen = ids
+            .into_iter()
+            .filter(|id| !order.positions.contains_key(id))
+            .collect::<Vec<_>>();
+        unseen.sort_by_cached_key(|id| {
+            let numeric = id.parse::<u64>().ok();
+            (numeric.is_none(), numeric, id.clone())
+        });
+        for id in unseen {
+            order.append(id);
+        }
+
+        items.sort_by_cached_key(|item| {
+            item.get(id_key)
+                .and_then(id_string)
+                .and_then(|id| order.positions.get(&id).copied())
+                .unwrap_or(u64::MAX)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(items: &[Value]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| id_string(&item["id"]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sort_follows_loaded_and_pushed_order() {
+        let order = CollectionOrder::default();
+        order.reset("users", ["b".to_string(), "a".to_string()]);
+        order.push("users", "c".to_string());
+        order.push("users", "b".to_string());
+
+        let mut items = vec![json!({"id":"a"}), json!({"id":"b"}), json!({"id":"c"})];
+        order.sort("users", "id", &mut items);
+        assert_eq!(ids(&items), ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn sort_appends_unseen_ids_and_keeps_them_stable() {
+        let order = CollectionOrder::default();
+        order.reset("users", [1.to_string()]);
+
+        let mut items = vec![json!({"id":3}), json!({"id":2}), json!({"id":1})];
+        order.sort("users", "id", &mut items);
+        assert_eq!(ids(&items), ["1", "2", "3"]);
+
+        order.remove("users", "1");
+        let mut items = vec![json!({"id":3}), json!({"id":4}), json!({"id":2})];
+        order.sort("users", "id", &mut items);
+        assert_eq!(ids(&items), ["2", "3", "4"]);
+
+        order.clear();
+        assert!(order.orders.read().unwrap().is_empty());
+    }
+}
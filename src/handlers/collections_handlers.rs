@@ -1,21 +1,30 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use axum::{
-    extract::{Json, Multipart, Path as AxumPath},
+    body::Bytes,
+    extract::{Json, Multipart, Path as AxumPath, Query},
     response::IntoResponse,
     routing::{get, post},
 };
-use fosk::{DbCollection, FieldInfo, JsonPrimitive, SchemaWithRefs};
+use fosk::{
+    Db, DbCollection, FieldInfo, JsonPrimitive, SchemaWithRefs, parser::analyzer::AnalyzerError,
+};
 use http::{
     HeaderMap, HeaderValue, StatusCode,
     header::{CONTENT_DISPOSITION, CONTENT_TYPE},
 };
 use mime_guess::from_ext;
-use serde_json::{Map, Value};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
 
 use crate::{
     app::{App, MOCK_SERVER_ROUTE},
-    handlers::{load_collection_error_response, read_error_response},
+    handlers::{
+        Change, HalLinks, ImportError, ImportMode, error_response, id_string, import_items,
+        is_soft_deleted, load_collection_error_response, parse_csv, read_error_response,
+        reference_error_response, write_error_response,
+    },
+    random,
 };
 
 fn field_info_to_json(field_info: &FieldInfo) -> Value {
@@ -137,7 +146,9 @@ fn create_collection_load_from_file(app: &mut App) {
                     None => db.create(&name),
                 };
 
-                return match collection.load_from_json(json, false) {
+                return match collection
+                    .load_from_json(random::current().fill_ids(&collection, json), false)
+                {
                     Ok(_) => StatusCode::OK.into_response(),
                     Err(err) => load_collection_error_response(err),
                 };
@@ -154,6 +165,64 @@ fn create_collection_load_from_file(app: &mut App) {
     );
 }
 
+fn create_collection_import(app: &mut App) {
+    let collection_route = format!("{}/collections/{{name}}/import", MOCK_SERVER_ROUTE);
+
+    let db = app.db.clone();
+
+    let create_router = post(
+        async move |AxumPath(name): AxumPath<String>,
+                    Query(params): Query<HashMap<String, String>>,
+                    headers: HeaderMap,
+                    body: Bytes| {
+            let Some(mode) = ImportMode::from_param(params.get("mode").map(String::as_str)) else {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_mode",
+                    "mode must be replace or merge",
+                );
+            };
+
+            let is_csv = headers
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.starts_with("text/csv"));
+            let items = if is_csv {
+                let text = String::from_utf8_lossy(&body);
+                match parse_csv(&text) {
+                    Ok(items) => items,
+                    Err(err) => return error_response(StatusCode::BAD_REQUEST, "invalid_csv", err),
+                }
+            } else {
+                match serde_json::from_slice::<Vec<Value>>(&body) {
+                    Ok(items) => items,
+                    Err(err) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            "invalid_json",
+                            format!("The body must be a JSON array of items: {}", err),
+                        );
+                    }
+                }
+            };
+
+            let collection = match db.get(&name) {
+                Some(collection) => collection,
+                None => db.create(&name),
+            };
+            match import_items(&collection, items, mode) {
+                Ok(imported) => {
+                    Json(json!({ "imported": imported, "mode": mode.as_str() })).into_response()
+                }
+                Err(ImportError::Load(err)) => load_collection_error_response(err),
+                Err(ImportError::Read(err)) => read_error_response(err),
+                Err(ImportError::Write(err)) => write_error_response(err),
+            }
+        },
+    );
+    app.route(&collection_route, create_router, Some("POST"), None);
+}
+
 fn create_db_load_from_file(app: &mut App) {
     let collection_route = format!("{}/collections", MOCK_SERVER_ROUTE);
 
@@ -270,19 +339,212 @@ fn create_db_download(app: &mut App) {
     );
 }
 
-/// Registers internal collection metadata, upload, and download routes.
+fn create_collection_purge(app: &mut App) {
+    let collection_route = format!("{}/collections/{{name}}/purge", MOCK_SERVER_ROUTE);
+
+    let db = app.db.clone();
+    let references = Arc::clone(&app.references);
+    let change_events = Arc::clone(&app.change_events);
+
+    let create_router = post(async move |AxumPath(name): AxumPath<String>| {
+        let Some(collection) = db.get(&name) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+
+        let id_key = match collection.get_config() {
+            Ok(config) => config.id_key,
+            Err(err) => return read_error_response(err),
+        };
+        let deleted_ids = match collection.get_all() {
+            Ok(items) => items
+                .iter()
+                .filter(|item| is_soft_deleted(item))
+                .filter_map(|item| item.get(&id_key).and_then(id_string))
+                .collect::<Vec<_>>(),
+            Err(err) => return read_error_response(err),
+        };
+
+        let mut purged = 0;
+        for id in deleted_ids {
+            match references.apply_delete(&db, &name, &id) {
+                Ok(cascaded) => change_events.publish_all(Change::cascaded(cascaded)),
+                Err(err) => return reference_error_response(err),
+            }
+            match collection.delete(&id) {
+                Ok(Some(item)) => {
+                    change_events.publish(Change::deleted(&name, &id, &item));
+                    purged += 1;
+                }
+                Ok(None) => {}
+                Err(err) => return write_error_response(err),
+            }
+        }
+
+        Json(json!({ "purged": purged })).into_response()
+    });
+    app.route(&collection_route, create_router, Some("POST"), None);
+}
+
+/// Route that runs SQL `SELECT` queries over the in-memory collections.
+pub const QUERY_ROUTE: &str = "/__query";
+
+/// Body of a `POST /__query` request.
+#[derive(Debug, Deserialize)]
+pub struct QueryRequest {
+    /// SQL query; `?` placeholders are bound to `args` in order.
+    pub sql: String,
+    /// Values bound to the query placeholders.
+    #[serde(default)]
+    pub args: Vec<Value>,
+}
+
+fn create_query_route(app: &mut App) {
+    let db = app.db.clone();
+
+    let create_router = post(async move |Json(request): Json<QueryRequest>| {
+        match db.query_with_args(&request.sql, Value::Array(request.args)) {
+            Ok(rows) => Json(json!({ "data": rows })).into_response(),
+            Err(err) => error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_query",
+                query_error_message(&err),
+            ),
+        }
+    });
+    app.route(QUERY_ROUTE, create_router, Some("POST"), None);
+}
+
+/// Describes why Fosk refused a query, for the `invalid_query` error body.
+fn query_error_message(err: &AnalyzerError) -> String {
+    match err {
+        AnalyzerError::UnknownCollection(name) => format!("Unknown collection {}", name),
+        AnalyzerError::UnknownColumn { name, candidates } if candidates.is_empty() => {
+            format!("Unknown column {}", name)
+        }
+        AnalyzerError::UnknownColumn { name, candidates } => {
+            let mut candidates = candidates.clone();
+            candidates.sort();
+            format!(
+                "Unknown column {}; known columns are {}",
+                name,
+                candidates.join(", ")
+            )
+        }
+        AnalyzerError::AmbiguousColumn { name, matches } => format!(
+            "Column {} is ambiguous between {}",
+            name,
+            matches
+                .iter()
+                .map(|(collection, column)| format!("{}.{}", collection, column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        AnalyzerError::NotACollection(name) => format!("{} is not a collection", name),
+        AnalyzerError::FunctionNotFound(name) => format!("Unknown function {}", name),
+        AnalyzerError::FunctionArgMismatch {
+            name,
+            expected,
+            got,
+        } => format!(
+            "{} expects {}, got ({})",
+            name,
+            expected,
+            got.iter()
+                .map(|ty| primitive_name(*ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        AnalyzerError::NonConstInConstFold => "The expression must be constant".to_string(),
+        AnalyzerError::InvalidLikePattern => "Invalid LIKE pattern".to_string(),
+        AnalyzerError::InvalidParameterValue => {
+            "Invalid value bound to a ? placeholder".to_string()
+        }
+        // Parse errors span lines, with the position of the offending text.
+        AnalyzerError::Other(message) => message.split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+fn primitive_name(ty: JsonPrimitive) -> &'static str {
+    match ty {
+        JsonPrimitive::Null => "null",
+        JsonPrimitive::Bool => "bool",
+        JsonPrimitive::Int => "int",
+        JsonPrimitive::Float => "float",
+        JsonPrimitive::String => "string",
+        JsonPrimitive::Object => "object",
+        JsonPrimitive::Array => "array",
+    }
+}
+
+/// Route of the collections dashboard page.
+pub const COLLECTIONS_PAGE_ROUTE: &str = "/__collections";
+
+/// Describes every collection for the dashboard and the playground: its REST
+/// route and id field, when it has them, and the fields of its inferred schema.
+pub fn dashboard_collections(db: &Db, hal_links: &HalLinks) -> Value {
+    let routes: HashMap<_, _> = hal_links.collections().into_iter().collect();
+    let mut names = db.list_collections();
+    names.sort();
+
+    let collections = names
+        .into_iter()
+        .map(|name| {
+            let fields = db
+                .schema_with_refs_of(&name)
+                .map(|schema| schema_to_json(&schema)["fields"].take())
+                .unwrap_or_else(|| json!({}));
+            let links = routes.get(&name);
+            json!({
+                "name": name,
+                "route": links.map(|links| &links.route),
+                "id_key": links.map(|links| &links.id_key),
+                "fields": fields,
+            })
+        })
+        .collect();
+    Value::Array(collections)
+}
+
+/// Registers the collections dashboard, which lists the collections with
+/// their schemas and edits items through their REST routes.
+fn create_collections_page_route(app: &mut App) {
+    let db = app.db.clone();
+    let hal_links = Arc::clone(&app.hal_links);
+    let pages = Arc::clone(&app.pages);
+
+    let page_router = get(move || async move {
+        let collections = dashboard_collections(&db, &hal_links);
+        let body = pages.lock().unwrap().render_collections(&collections);
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        headers.insert(
+            "Cache-Control",
+            HeaderValue::from_static("no-cache, no-store, must-revalidate"),
+        );
+        (headers, body).into_response()
+    });
+    app.route(COLLECTIONS_PAGE_ROUTE, page_router, None, None);
+}
+
+/// Registers internal collection metadata, upload, import, download, purge,
+/// and query routes, and the collections dashboard.
 pub fn create_collections_routes(app: &mut App) {
+    create_collections_page_route(app);
     create_all_collections_info_route(app);
     create_collection_info_route(app);
     create_collection_load_from_file(app);
+    create_collection_import(app);
     create_db_load_from_file(app);
     create_collection_download(app);
     create_db_download(app);
+    create_collection_purge(app);
+    create_query_route(app);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::CollectionLinks;
     use axum::{
         body::{Body, to_bytes},
         http::{Method, Request, header::CONTENT_TYPE},
@@ -306,6 +568,48 @@ mod tests {
             .unwrap()
     }
 
+    #[tokio::test]
+    async fn collections_page_lists_routes_and_schemas() {
+        let mut app = App::default();
+        app.db
+            .create("users")
+            .load_from_json(json!([{"id":"1","name":"Ada"}]), false)
+            .unwrap();
+        app.db.create("audit");
+        app.hal_links.register(
+            "users",
+            CollectionLinks {
+                route: "/api/users".to_string(),
+                id_key: "id".to_string(),
+                ..Default::default()
+            },
+        );
+        create_collections_routes(&mut app);
+        let router = app.take_router_for_test();
+
+        let page = router
+            .oneshot(
+                Request::builder()
+                    .uri(COLLECTIONS_PAGE_ROUTE)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.status(), StatusCode::OK);
+        assert_eq!(page.headers()[CONTENT_TYPE], "text/html");
+        let html = String::from_utf8(
+            to_bytes(page.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .to_vec(),
+        )
+        .unwrap();
+        assert!(html.contains(r#"{"name":"audit","route":null,"id_key":null,"#));
+        assert!(html.contains(r#"{"name":"users","route":"/api/users","id_key":"id","#));
+        assert!(html.contains(r#""name":{"type":"String","nullable":false}"#));
+    }
+
     #[tokio::test]
     async fn collection_routes_expose_schema_load_and_download() {
         let mut app = App::default();
@@ -539,8 +843,10 @@ mod tests {
                     .uri("/mock-server/collections/projects")
                     .header(CONTENT_TYPE, "multipart/form-data; boundary=BOUNDARY")
                     .body(
-                        multipart_json(r#"[{"id":"p1","name":"First"},{"id":"p1","name":"Second"}]"#)
-                            .into_body(),
+                        multipart_json(
+                            r#"[{"id":"p1","name":"First"},{"id":"p1","name":"Second"}]"#,
+                        )
+                        .into_body(),
                     )
                     .unwrap(),
             )
@@ -556,4 +862,138 @@ mod tests {
             "Item at index 1 duplicates existing id 'p1'"
         );
     }
+
+    #[tokio::test]
+    async fn collection_import_merges_csv_and_replaces_json() {
+        let mut app = App::default();
+        let users = app.db.create_with_config("users", DbConfig::int("id"));
+        users
+            .load_from_json(
+                json!([{"id":1,"name":"Ada"},{"id":2,"name":"Grace"}]),
+                false,
+            )
+            .unwrap();
+        create_collections_routes(&mut app);
+        let router = app.take_router_for_test();
+        let import = |uri: &str, content_type: &str, body: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(CONTENT_TYPE, content_type)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(import(
+                "/mock-server/collections/users/import?mode=merge",
+                "text/csv",
+                "id,name\n2,Hopper\n3,Lin\n",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body, json!({ "imported": 2, "mode": "merge" }));
+        assert_eq!(users.count().unwrap(), 3);
+        assert_eq!(users.get("2").unwrap().unwrap()["name"], "Hopper");
+
+        let response = router
+            .clone()
+            .oneshot(import(
+                "/mock-server/collections/users/import",
+                "application/json",
+                r#"[{"id":9,"name":"Linus"}]"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(users.count().unwrap(), 1);
+
+        let response = router
+            .oneshot(import(
+                "/mock-server/collections/users/import?mode=append",
+                "application/json",
+                "[]",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn query_route_runs_sql_with_args() {
+        let mut app = App::default();
+        let users = app.db.create("users");
+        users
+            .load_from_json(
+                json!([
+                    {"id":"1","name":"Ada","age":36},
+                    {"id":"2","name":"Grace","age":85},
+                    {"id":"3","name":"Linus","age":28}
+                ]),
+                false,
+            )
+            .unwrap();
+        create_collections_routes(&mut app);
+        let router = app.take_router_for_test();
+        let query = |body: Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(QUERY_ROUTE)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(query(json!({
+                "sql": "SELECT name FROM users WHERE age > ? ORDER BY name",
+                "args": [30]
+            })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body["data"], json!([{"name":"Ada"},{"name":"Grace"}]));
+
+        let response = router
+            .clone()
+            .oneshot(query(json!({ "sql": "SELECT * FROM missing" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body["error"], "invalid_query");
+        assert_eq!(body["message"], "Unknown collection missing");
+
+        for (sql, message) in [
+            (
+                "SELECT users.nickname FROM users",
+                "Unknown column users.nickname; known columns are age, id, name",
+            ),
+            ("SELEC name FROM users", "parse error"),
+        ] {
+            let response = router
+                .clone()
+                .oneshot(query(json!({ "sql": sql })))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body: Value =
+                serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                    .unwrap();
+            let text = body["message"].as_str().unwrap();
+            assert!(text.starts_with(message), "{}", text);
+            assert!(!text.contains('\n') && !text.contains("{"), "{}", text);
+        }
+    }
 }
@@ -0,0 +1,192 @@
+//! Content types of mock files without a known extension, and the per-route
+//! `content_type` and `charset` overrides.
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    middleware::{self, Next},
+    routing::MethodRouter,
+};
+use http::{HeaderValue, header::CONTENT_TYPE};
+
+/// Character set a route's text responses are declared and encoded in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// UTF-8, the encoding of the mock files.
+    Utf8,
+    /// ISO-8859-1; characters beyond it are sent as `?`.
+    Latin1,
+}
+
+impl Charset {
+    /// Parses a charset label such as `utf-8` or `iso-8859-1`, ignoring case.
+    pub fn parse(label: &str) -> Result<Self, String> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "iso-8859-1" | "latin1" | "latin-1" => Ok(Self::Latin1),
+            _ => Err(format!(
+                "unsupported charset `{}`, expected utf-8 or iso-8859-1",
+                label
+            )),
+        }
+    }
+
+    /// Label sent in the `charset` parameter of the `Content-Type` header.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Latin1 => "iso-8859-1",
+        }
+    }
+
+    /// Encodes UTF-8 text in this charset.
+    fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Latin1 => text
+                .chars()
+                .map(|character| u8::try_from(u32::from(character)).unwrap_or(b'?'))
+                .collect(),
+        }
+    }
+}
+
+/// Guesses the content type of a file without a known extension from its
+/// first bytes: common binary signatures, then JSON, XML, HTML, and text.
+pub fn sniff_content_type(bytes: &[u8]) -> &'static str {
+    const SIGNATURES: [(&[u8], &str); 6] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+    ];
+    if let Some((_, content_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return content_type;
+    }
+
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return "application/octet-stream";
+    };
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let lowercase = text
+        .get(..text.len().min(64))
+        .unwrap_or(text)
+        .to_ascii_lowercase();
+    if text.starts_with(['{', '[']) && serde_json::from_str::<serde_json::Value>(text).is_ok() {
+        "application/json"
+    } else if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") {
+        "text/html"
+    } else if lowercase.starts_with("<?xml") {
+        "application/xml"
+    } else {
+        "text/plain"
+    }
+}
+
+/// Replaces the `Content-Type` of every response with `content_type`, and
+/// declares `charset` on it, encoding text bodies in that charset.
+///
+/// Without `content_type`, the media type the handler chose is kept.
+pub fn content_type_router(
+    router: MethodRouter,
+    content_type: Option<String>,
+    charset: Option<Charset>,
+) -> MethodRouter {
+    router.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let content_type = content_type.clone();
+        async move {
+            let response = next.run(req).await;
+            let (mut parts, body) = response.into_parts();
+            let media_type = content_type.or_else(|| {
+                parts
+                    .headers
+                    .get(CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| {
+                        value
+                            .split(';')
+                            .next()
+                            .unwrap_or_default()
+                            .trim()
+                            .to_string()
+                    })
+            });
+            let Some(media_type) = media_type else {
+                return axum::response::Response::from_parts(parts, body);
+            };
+
+            let body = match charset {
+                Some(Charset::Latin1) => match to_bytes(body, usize::MAX).await {
+                    Ok(bytes) => match std::str::from_utf8(&bytes) {
+                        Ok(text) => Body::from(Charset::Latin1.encode(text)),
+                        Err(_) => Body::from(bytes),
+                    },
+                    Err(_) => Body::empty(),
+                },
+                _ => body,
+            };
+            let header = match charset {
+                Some(charset) => format!("{}; charset={}", media_type, charset.label()),
+                None => media_type,
+            };
+            if let Ok(value) = HeaderValue::from_str(&header) {
+                parts.headers.insert(CONTENT_TYPE, value);
+            }
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            axum::response::Response::from_parts(parts, body)
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use tower::ServiceExt;
+
+    #[test]
+    fn extensionless_files_are_sniffed() {
+        assert_eq!(sniff_content_type(b"  {\"id\": 1}"), "application/json");
+        assert_eq!(
+            sniff_content_type(b"<?xml version=\"1.0\"?><a/>"),
+            "application/xml"
+        );
+        assert_eq!(
+            sniff_content_type(b"<!DOCTYPE html><html></html>"),
+            "text/html"
+        );
+        assert_eq!(sniff_content_type(b"{not json"), "text/plain");
+        assert_eq!(sniff_content_type(b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(
+            sniff_content_type(&[0xc3, 0x28, 0x00]),
+            "application/octet-stream"
+        );
+    }
+
+    #[tokio::test]
+    async fn latin1_charset_encodes_the_body_and_declares_it() {
+        let router = content_type_router(
+            get(async || "café"),
+            Some("application/json".to_string()),
+            Some(Charset::parse("ISO-8859-1").unwrap()),
+        );
+        let response = Router::new()
+            .route("/", router)
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json; charset=iso-8859-1"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"caf\xe9");
+        assert!(Charset::parse("utf-16").is_err());
+    }
+}
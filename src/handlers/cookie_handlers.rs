@@ -0,0 +1,141 @@
+//! Cookies read from requests and set on route responses.
+
+use axum::{
+    extract::Request,
+    middleware::{self, Next},
+    routing::MethodRouter,
+};
+use http::{
+    HeaderMap, HeaderValue,
+    header::{COOKIE, SET_COOKIE},
+};
+
+use crate::route_builder::config::CookieConfig;
+
+/// Returns the name and value pairs of every `Cookie` header of a request.
+pub fn request_cookies(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .filter_map(|cookie| cookie.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Formats the `Set-Cookie` header value of a configured cookie.
+pub fn set_cookie_header(cookie: &CookieConfig) -> String {
+    let mut header = format!("{}={}", cookie.name, cookie.value);
+    if let Some(path) = &cookie.path {
+        header.push_str(&format!("; Path={}", path));
+    }
+    if let Some(domain) = &cookie.domain {
+        header.push_str(&format!("; Domain={}", domain));
+    }
+    if let Some(max_age) = cookie.max_age {
+        header.push_str(&format!("; Max-Age={}", max_age));
+    }
+    if cookie.http_only == Some(true) {
+        header.push_str("; HttpOnly");
+    }
+    if cookie.secure == Some(true) {
+        header.push_str("; Secure");
+    }
+    if let Some(same_site) = &cookie.same_site {
+        header.push_str(&format!("; SameSite={}", same_site));
+    }
+    header
+}
+
+/// Appends a `Set-Cookie` header for every cookie to each response.
+///
+/// Cookies whose header value is not valid are skipped.
+pub fn set_cookies_router(router: MethodRouter, cookies: &[CookieConfig]) -> MethodRouter {
+    let headers = cookies
+        .iter()
+        .filter_map(|cookie| HeaderValue::from_str(&set_cookie_header(cookie)).ok())
+        .collect::<Vec<_>>();
+    router.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let headers = headers.clone();
+        async move {
+            let mut response = next.run(req).await;
+            for header in headers {
+                response.headers_mut().append(SET_COOKIE, header);
+            }
+            response
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, routing::get};
+    use tower::ServiceExt;
+
+    #[test]
+    fn request_cookies_are_split_into_pairs() {
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("ab=b; session = 42"));
+        headers.append(COOKIE, HeaderValue::from_static("theme=dark;flag"));
+
+        assert_eq!(
+            request_cookies(&headers),
+            vec![
+                ("ab".to_string(), "b".to_string()),
+                ("session".to_string(), "42".to_string()),
+                ("theme".to_string(), "dark".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_cookies_are_set_on_every_response() {
+        let cookies = [
+            CookieConfig {
+                name: "ab".to_string(),
+                value: "b".to_string(),
+                path: Some("/".to_string()),
+                max_age: Some(3600),
+                http_only: Some(true),
+                same_site: Some("Lax".to_string()),
+                ..Default::default()
+            },
+            CookieConfig {
+                name: "node".to_string(),
+                value: "2".to_string(),
+                secure: Some(true),
+                ..Default::default()
+            },
+        ];
+        let router = Router::new().route(
+            "/flags",
+            set_cookies_router(get(|| async { "{}" }), &cookies),
+        );
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/flags")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let set_cookies = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            set_cookies,
+            vec![
+                "ab=b; Path=/; Max-Age=3600; HttpOnly; SameSite=Lax",
+                "node=2; Secure",
+            ]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! CSRF protection emulation: `GET /csrf` issues a token as a cookie and in the
+//! body, and mutating requests to folders with `csrf = true` must echo it in a
+//! header, the double-submit cookie pattern most frontends implement.
+
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json,
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use http::{HeaderMap, HeaderValue, Method, StatusCode, header::SET_COOKIE};
+use serde_json::json;
+
+use crate::{
+    app::App,
+    handlers::{error_response, request_cookies},
+};
+
+/// Route issuing CSRF tokens.
+pub const CSRF_ROUTE: &str = "/csrf";
+/// Cookie holding the CSRF token, the name Angular and axios read.
+pub const CSRF_COOKIE: &str = "XSRF-TOKEN";
+/// Headers a client may echo the CSRF token in.
+pub const CSRF_HEADERS: [&str; 2] = ["x-xsrf-token", "x-csrf-token"];
+
+/// Whether the routes under a folder route require CSRF tokens, from the
+/// `csrf` key of the folder's `[route]` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrfScope {
+    /// Route of the folder, such as `/admin`.
+    pub route: String,
+    /// Whether mutating requests under the route must carry a token.
+    pub enabled: bool,
+}
+
+/// Folder scopes and the tokens issued by `GET /csrf`.
+#[derive(Debug, Default)]
+pub struct CsrfGuard {
+    scopes: Mutex<Vec<CsrfScope>>,
+    tokens: Mutex<HashSet<String>>,
+}
+
+impl CsrfGuard {
+    /// Adds the scopes found while walking a mock folder.
+    pub fn push_scopes(&self, scopes: &[CsrfScope]) {
+        self.scopes.lock().unwrap().extend_from_slice(scopes);
+    }
+
+    /// Whether any folder requires CSRF tokens.
+    pub fn is_active(&self) -> bool {
+        self.scopes
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|scope| scope.enabled)
+    }
+
+    /// Forgets the folder scopes, keeping the issued tokens valid.
+    pub fn clear(&self) {
+        self.scopes.lock().unwrap().clear();
+    }
+
+    /// Whether `path` is under a folder requiring CSRF tokens; the deepest
+    /// folder with a `csrf` key decides.
+    fn protects(&self, path: &str) -> bool {
+        self.scopes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|scope| {
+                let route = scope.route.trim_end_matches('/');
+                route.is_empty()
+                    || path == route
+                    || path
+                        .strip_prefix(route)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|scope| scope.route.trim_end_matches('/').len())
+            .is_some_and(|scope| scope.enabled)
+    }
+
+    fn issue(&self) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        self.tokens.lock().unwrap().insert(token.clone());
+        token
+    }
+
+    /// Checks that the request echoes the token of its CSRF cookie in a
+    /// header, and that the token was issued by `GET /csrf`.
+    fn accepts(&self, headers: &HeaderMap) -> bool {
+        let Some((_, cookie)) = request_cookies(headers)
+            .into_iter()
+            .find(|(name, _)| name == CSRF_COOKIE)
+        else {
+            return false;
+        };
+        let echoed = CSRF_HEADERS
+            .iter()
+            .find_map(|name| headers.get(*name))
+            .and_then(|value| value.to_str().ok());
+        echoed == Some(cookie.as_str()) && self.tokens.lock().unwrap().contains(&cookie)
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    !matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Registers `GET /csrf`, which issues a token in the `XSRF-TOKEN` cookie and
+/// in the body, when a folder requires CSRF tokens.
+pub fn create_csrf_routes(app: &mut App) {
+    if !app.csrf.is_active() {
+        return;
+    }
+
+    let guard = Arc::clone(&app.csrf);
+    let router = get(async move || {
+        let token = guard.issue();
+        let cookie = format!("{}={}; Path=/; SameSite=Strict", CSRF_COOKIE, token);
+        let mut response = Json(json!({ "token": token })).into_response();
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().insert(SET_COOKIE, cookie);
+        }
+        response
+    });
+    app.route(CSRF_ROUTE, router, Some("GET"), None);
+}
+
+type CsrfMiddlewareReturn = Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware answering `403 Forbidden` to mutating requests under a
+/// CSRF folder that do not echo a valid token.
+pub fn make_csrf_middleware(
+    guard: &Arc<CsrfGuard>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> CsrfMiddlewareReturn {
+    let guard = Arc::clone(guard);
+    move |req: Request, next: Next| {
+        let guard = Arc::clone(&guard);
+        Box::pin(async move {
+            if is_mutating(req.method())
+                && guard.protects(req.uri().path())
+                && !guard.accepts(req.headers())
+            {
+                return error_response(
+                    StatusCode::FORBIDDEN,
+                    "csrf_token_invalid",
+                    format!(
+                        "Send the token of GET {} in the {} cookie and the X-XSRF-TOKEN header",
+                        CSRF_ROUTE, CSRF_COOKIE
+                    ),
+                );
+            }
+            next.run(req).await
+        })
+    }
+}
@@ -0,0 +1,176 @@
+//! Handler that answers with the request it received, for debugging client
+//! serialization.
+
+use axum::{
+    Json,
+    body::to_bytes,
+    extract::{Query, Request},
+    response::{IntoResponse, Response},
+    routing::{MethodRouter, delete, get, options, patch, post, put},
+};
+use http::{Extensions, HeaderMap, StatusCode, header::CONTENT_TYPE};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    app::App,
+    handlers::{SleepDelay, error_response},
+    route_builder::{RouteEcho, RouteRegistrator},
+    tls::ClientCertificate,
+};
+
+/// Methods answered by echo routes.
+const ECHO_METHODS: [&str; 6] = ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+/// Registers an echo route for every standard method.
+pub fn build_echo_routes(app: &mut App, route_echo: &RouteEcho) {
+    for method in ECHO_METHODS {
+        let delay = route_echo.delay;
+        let handler = move |req: Request| async move {
+            delay.sleep().await;
+            echo_response(req).await
+        };
+        let router: MethodRouter = match method {
+            "GET" => get(handler),
+            "POST" => post(handler),
+            "PUT" => put(handler),
+            "PATCH" => patch(handler),
+            "DELETE" => delete(handler),
+            _ => options(handler),
+        };
+        app.push_route(
+            &route_echo.route,
+            router,
+            Some(method),
+            route_echo.is_protected,
+            None,
+        );
+    }
+}
+
+/// Describes the method, path, query, headers, body, and client certificate of
+/// a request as JSON.
+async fn echo_response(req: Request) -> Response {
+    let (parts, body) = req.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_body",
+            "The request body could not be read",
+        );
+    };
+
+    let query = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+        .map(|Query(query)| query)
+        .unwrap_or_default();
+    let body = if bytes.is_empty() {
+        Value::Null
+    } else if is_json_content_type(&parts.headers) {
+        serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).to_string()))
+    } else {
+        Value::String(String::from_utf8_lossy(&bytes).to_string())
+    };
+
+    Json(json!({
+        "method": parts.method.as_str(),
+        "path": parts.uri.path(),
+        "query": grouped(query),
+        "headers": headers_json(&parts.headers),
+        "body": body,
+        "client_cert": client_cert_json(&parts.extensions),
+    }))
+    .into_response()
+}
+
+/// Describes the client certificate of a mutual TLS request, or `null`.
+pub(crate) fn client_cert_json(extensions: &Extensions) -> Value {
+    extensions
+        .get::<ClientCertificate>()
+        .and_then(|certificate| serde_json::to_value(certificate).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Groups name and value pairs into an object, collecting repeated names into arrays.
+pub(crate) fn grouped(pairs: impl IntoIterator<Item = (String, String)>) -> Value {
+    let mut object = Map::new();
+    for (name, value) in pairs {
+        match object.get_mut(&name) {
+            Some(Value::Array(values)) => values.push(Value::String(value)),
+            Some(previous) => {
+                let first = previous.take();
+                *previous = Value::Array(vec![first, Value::String(value)]);
+            }
+            None => {
+                object.insert(name, Value::String(value));
+            }
+        }
+    }
+    Value::Object(object)
+}
+
+/// Returns true when the request declares a JSON body.
+pub(crate) fn is_json_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| {
+            let mime = mime.trim();
+            mime.eq_ignore_ascii_case("application/json") || mime.ends_with("+json")
+        })
+}
+
+/// Describes request headers as an object, collecting repeated names into arrays.
+pub(crate) fn headers_json(headers: &HeaderMap) -> Value {
+    grouped(headers.iter().map(|(name, value)| {
+        (
+            name.to_string(),
+            String::from_utf8_lossy(value.as_bytes()).to_string(),
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn echo_routes_describe_the_received_request() {
+        let mut app = App::default();
+        build_echo_routes(
+            &mut app,
+            &RouteEcho {
+                path: "echo.json".into(),
+                route: "/debug/echo".to_string(),
+                is_protected: false,
+                delay: None,
+            },
+        );
+
+        let response = app
+            .take_router_for_test()
+            .oneshot(
+                Request::builder()
+                    .method("PATCH")
+                    .uri("/debug/echo?tag=a&tag=b&page=2")
+                    .header(CONTENT_TYPE, "application/json")
+                    .header("x-trace", "1")
+                    .body(Body::from(r#"{"name":"Ada"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let echo = serde_json::from_slice::<Value>(&bytes).unwrap();
+        assert_eq!(echo["method"], "PATCH");
+        assert_eq!(echo["path"], "/debug/echo");
+        assert_eq!(echo["query"], json!({ "tag": ["a", "b"], "page": "2" }));
+        assert_eq!(echo["headers"]["x-trace"], "1");
+        assert_eq!(echo["body"], json!({ "name": "Ada" }));
+        assert_eq!(echo["client_cert"], Value::Null);
+    }
+}
@@ -0,0 +1,387 @@
+//! Routes that hang, time out, break, or cap the size of their responses, so
+//! client timeout, retry, parsing, and payload limit handling can be exercised.
+//!
+//! Closing a connection without answering is done with a response whose body
+//! fails before its first byte, which makes the server drop the socket before
+//! anything is written.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing::MethodRouter,
+};
+use http::{
+    HeaderValue, StatusCode,
+    header::{CONNECTION, CONTENT_LENGTH},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    handlers::error_response,
+    route_builder::config::{Fault, Hang, Oversize, TimeoutMode},
+};
+
+/// Header carrying the full length of a body cut at `max_response_bytes`.
+pub const TRUNCATED_HEADER: &str = "x-mock-truncated";
+
+/// Holds every request open without answering, as `hang` describes.
+pub fn hang_router(router: MethodRouter, hang: Hang) -> MethodRouter {
+    router.layer(middleware::from_fn(
+        move |_req: Request, _next: Next| async move {
+            match hang {
+                Hang::Infinite => std::future::pending::<Response>().await,
+                Hang::Seconds(seconds) => {
+                    tokio::time::sleep(Duration::from_secs(seconds)).await;
+                    drop_connection()
+                }
+            }
+        },
+    ))
+}
+
+/// Times out requests that take longer than `timeout`: with `408` and a closed
+/// connection when the client has not sent its body, or with `504` when the
+/// route has not answered, like a gateway in front of a slow upstream.
+pub fn timeout_router(router: MethodRouter, timeout: Duration, mode: TimeoutMode) -> MethodRouter {
+    router.layer(middleware::from_fn(
+        move |req: Request, next: Next| async move {
+            match mode {
+                TimeoutMode::Client => {
+                    let (parts, body) = req.into_parts();
+                    match tokio::time::timeout(timeout, to_bytes(body, usize::MAX)).await {
+                        Ok(Ok(bytes)) => {
+                            next.run(Request::from_parts(parts, Body::from(bytes)))
+                                .await
+                        }
+                        Ok(Err(err)) => {
+                            error_response(StatusCode::BAD_REQUEST, "invalid_body", err.to_string())
+                        }
+                        Err(_) => {
+                            let mut response = error_response(
+                                StatusCode::REQUEST_TIMEOUT,
+                                "request_timeout",
+                                format!(
+                                    "The request body was not received within {} seconds",
+                                    timeout.as_secs_f64()
+                                ),
+                            );
+                            response
+                                .headers_mut()
+                                .insert(CONNECTION, HeaderValue::from_static("close"));
+                            response
+                        }
+                    }
+                }
+                TimeoutMode::Gateway => tokio::time::timeout(timeout, next.run(req))
+                    .await
+                    .unwrap_or_else(|_| {
+                        error_response(
+                            StatusCode::GATEWAY_TIMEOUT,
+                            "gateway_timeout",
+                            format!(
+                                "The upstream did not answer within {} seconds",
+                                timeout.as_secs_f64()
+                            ),
+                        )
+                    }),
+            }
+        },
+    ))
+}
+
+/// Answers every request with the transport failure `fault` describes.
+pub fn fault_router(router: MethodRouter, fault: Fault) -> MethodRouter {
+    router.layer(middleware::from_fn(
+        move |req: Request, next: Next| async move {
+            if fault == Fault::Reset {
+                return drop_connection();
+            }
+
+            let (mut parts, body) = next.run(req).await.into_parts();
+            let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+            let half = bytes.slice(..bytes.len() / 2);
+            parts.headers.remove(CONTENT_LENGTH);
+            let body = match fault {
+                Fault::InvalidJson => Body::from(half),
+                Fault::Truncated => {
+                    parts
+                        .headers
+                        .insert(CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+                    broken_body(half)
+                }
+                Fault::Reset | Fault::BrokenChunked => broken_body(half),
+            };
+            Response::from_parts(parts, body)
+        },
+    ))
+}
+
+/// Cuts or rejects response bodies larger than `max_bytes`, as `oversize` describes.
+pub fn size_limit_router(router: MethodRouter, max_bytes: u64, oversize: Oversize) -> MethodRouter {
+    router.layer(middleware::from_fn(
+        move |req: Request, next: Next| async move {
+            let (mut parts, body) = next.run(req).await.into_parts();
+            let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+            let length = bytes.len() as u64;
+            if length <= max_bytes {
+                return Response::from_parts(parts, Body::from(bytes));
+            }
+
+            match oversize {
+                Oversize::Truncate => {
+                    parts.headers.remove(CONTENT_LENGTH);
+                    parts
+                        .headers
+                        .insert(TRUNCATED_HEADER, HeaderValue::from(length));
+                    Response::from_parts(parts, Body::from(bytes.slice(..max_bytes as usize)))
+                }
+                Oversize::Reject(status) => error_response(
+                    StatusCode::from_u16(status).unwrap_or(StatusCode::PAYLOAD_TOO_LARGE),
+                    "response_too_large",
+                    format!(
+                        "The response of {} bytes exceeds the limit of {} bytes",
+                        length, max_bytes
+                    ),
+                ),
+            }
+        },
+    ))
+}
+
+/// Marks a response made by [`drop_connection`], so middleware that buffers
+/// bodies passes it on instead of answering for it.
+#[derive(Debug, Clone, Copy)]
+pub struct DroppedConnection;
+
+/// Returns true when `response` stands for a dropped connection.
+pub fn is_dropped_connection(response: &Response) -> bool {
+    response.extensions().get::<DroppedConnection>().is_some()
+}
+
+/// Ends the request without an answer: the body fails before its first byte,
+/// so the server closes the connection, while callers running the route in
+/// process read a body error.
+pub(crate) fn drop_connection() -> Response {
+    let failure = io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "connection dropped by fault injection",
+    );
+    let mut response = Response::new(Body::from_stream(tokio_stream::once(Err::<Bytes, _>(
+        failure,
+    ))));
+    response.extensions_mut().insert(DroppedConnection);
+    response
+}
+
+/// Returns a streamed body that sends `bytes` and then fails, which makes the
+/// server close the connection mid-body.
+///
+/// The failure waits a moment so the head and the first bytes are flushed to
+/// the client before the connection is closed.
+fn broken_body(bytes: Bytes) -> Body {
+    let failing = FailingReader {
+        delay: Box::pin(tokio::time::sleep(Duration::from_millis(100))),
+    };
+    let reader = io::Cursor::new(bytes).chain(failing);
+    Body::from_stream(ReaderStream::new(reader))
+}
+
+struct FailingReader {
+    delay: Pin<Box<tokio::time::Sleep>>,
+}
+
+impl AsyncRead for FailingReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.delay.as_mut().poll(cx).map(|()| {
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "response body broken by fault injection",
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handlers::SleepDelay, route_builder::config::Delay};
+    use axum::{
+        Router,
+        routing::{get, post},
+    };
+    use std::time::Instant;
+    use tokio::io::AsyncWriteExt;
+    use tower::ServiceExt;
+
+    fn faulty(fault: Fault) -> Router {
+        Router::new().route(
+            "/users",
+            fault_router(get(|| async { r#"{"name":"Ada"}"# }), fault),
+        )
+    }
+
+    fn request() -> Request {
+        Request::builder()
+            .uri("/users")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_json_answers_half_of_the_body() {
+        let response = faulty(Fault::InvalidJson).oneshot(request()).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        assert_eq!(body, r#"{"name""#);
+        assert!(serde_json::from_slice::<serde_json::Value>(&body).is_err());
+    }
+
+    #[tokio::test]
+    async fn truncated_and_broken_chunked_bodies_fail_midway() {
+        let response = faulty(Fault::Truncated).oneshot(request()).await.unwrap();
+        assert_eq!(response.headers()[CONTENT_LENGTH], "14");
+        assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+
+        let response = faulty(Fault::BrokenChunked)
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(CONTENT_LENGTH));
+        assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_truncated_or_rejected() {
+        let limited = |oversize: Oversize| {
+            Router::new().route(
+                "/users",
+                size_limit_router(get(|| async { r#"{"name":"Ada"}"# }), 8, oversize),
+            )
+        };
+
+        let response = limited(Oversize::Truncate)
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[TRUNCATED_HEADER], "14");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, r#"{"name":"#);
+
+        let response = limited(Oversize::Reject(500))
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "response_too_large");
+
+        let response = Router::new()
+            .route(
+                "/users",
+                size_limit_router(get(|| async { "small" }), 8, Oversize::Reject(413)),
+            )
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn slow_bodies_answer_408_and_slow_routes_answer_504() {
+        let timeout = Duration::from_millis(50);
+        let echo = || post(|body: String| async move { body });
+
+        let client = Router::new().route(
+            "/users",
+            timeout_router(echo(), timeout, TimeoutMode::Client),
+        );
+        let response = client
+            .clone()
+            .oneshot(Request::post("/users").body(Body::from("Ada")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            to_bytes(response.into_body(), usize::MAX).await.unwrap(),
+            "Ada"
+        );
+
+        // The writer stays open without sending, like a stalled upload.
+        let (_writer, reader) = tokio::io::duplex(8);
+        let stalled = Body::from_stream(ReaderStream::new(reader));
+        let response = client
+            .oneshot(Request::post("/users").body(stalled).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(response.headers()[CONNECTION], "close");
+
+        let gateway = Router::new().route(
+            "/users",
+            timeout_router(
+                hang_router(echo(), Hang::Infinite),
+                timeout,
+                TimeoutMode::Gateway,
+            ),
+        );
+        let response = gateway
+            .oneshot(Request::post("/users").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn gateway_timeouts_interrupt_delayed_routes() {
+        let delayed = get(|| async {
+            Some(Delay::Fixed(2_000)).sleep().await;
+            "late"
+        });
+        let gateway = Router::new().route(
+            "/users",
+            timeout_router(delayed, Duration::from_millis(50), TimeoutMode::Gateway),
+        );
+
+        let start = Instant::now();
+        let response = gateway.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn reset_drops_the_connection_without_a_response() {
+        let response = faulty(Fault::Reset).oneshot(request()).await.unwrap();
+        assert!(is_dropped_connection(&response));
+        assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, faulty(Fault::Reset)).await });
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET /users HTTP/1.1\r\nhost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut received = vec![];
+        let _ = stream.read_to_end(&mut received).await;
+        assert!(
+            received.is_empty(),
+            "{:?}",
+            String::from_utf8_lossy(&received)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Server-generated fields for REST collections.
+//!
+//! A collection declares `[collection.defaults]` to fill missing fields on insert
+//! and `[collection.computed]` to overwrite fields on every insert and update.
+//! String values may use placeholders such as `{{now}}`, `{{uuid}}`, or
+//! `{{faker.name}}`, which are rendered when the item is written. A collection
+//! with an `id_strategy` also gets its ids generated when items are created.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use chrono::Utc;
+use serde_json::Value;
+
+use crate::{handlers::IdGenerator, templates::render_value};
+
+/// Default and computed field values declared for one collection.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FieldDefaults {
+    /// Values filled into inserted or replaced items when the field is missing.
+    pub defaults: HashMap<String, Value>,
+    /// Values written into items on every insert and update.
+    pub computed: HashMap<String, Value>,
+    /// Generator filling the id of created items that have none.
+    pub id: Option<IdGenerator>,
+}
+
+impl FieldDefaults {
+    /// Returns true when no default, computed field, or id generator is declared.
+    pub fn is_empty(&self) -> bool {
+        self.defaults.is_empty() && self.computed.is_empty() && self.id.is_none()
+    }
+
+    /// Generates a missing id, then applies defaults and computed fields to a created item.
+    pub fn apply_create(&self, payload: Value) -> Value {
+        let payload = match &self.id {
+            Some(id) => id.assign(payload),
+            None => payload,
+        };
+        self.apply_insert(payload)
+    }
+
+    /// Applies defaults and computed fields to an item being inserted or replaced.
+    pub fn apply_insert(&self, payload: Value) -> Value {
+        let now = Utc::now();
+        let Value::Object(mut item) = payload else {
+            return payload;
+        };
+
+        for (field, value) in &self.defaults {
+            if !item.contains_key(field) {
+                item.insert(field.clone(), render_value(value, now));
+            }
+        }
+        for (field, value) in &self.computed {
+            item.insert(field.clone(), render_value(value, now));
+        }
+
+        Value::Object(item)
+    }
+
+    /// Applies computed fields to a partial update.
+    pub fn apply_update(&self, payload: Value) -> Value {
+        let now = Utc::now();
+        let Value::Object(mut item) = payload else {
+            return payload;
+        };
+
+        for (field, value) in &self.computed {
+            item.insert(field.clone(), render_value(value, now));
+        }
+
+        Value::Object(item)
+    }
+}
+
+/// Registry of field defaults keyed by collection name.
+#[derive(Debug, Default)]
+pub struct CollectionDefaults {
+    collections: RwLock<HashMap<String, FieldDefaults>>,
+}
+
+impl CollectionDefaults {
+    /// Declares the default and computed fields of a collection.
+    pub fn register(&self, collection: &str, defaults: FieldDefaults) {
+        let mut collections = self.collections.write().unwrap();
+        if defaults.is_empty() {
+            collections.remove(collection);
+        } else {
+            collections.insert(collection.to_string(), defaults);
+        }
+    }
+
+    /// Returns the fields declared for a collection.
+    pub fn get(&self, collection: &str) -> Option<FieldDefaults> {
+        self.collections.read().unwrap().get(collection).cloned()
+    }
+
+    /// Removes every declared collection.
+    pub fn clear(&self) {
+        self.collections.write().unwrap().clear();
+    }
+
+    /// Applies a collection's id generator, defaults, and computed fields to a created item.
+    pub fn apply_create(&self, collection: &str, payload: Value) -> Value {
+        match self.collections.read().unwrap().get(collection) {
+            Some(defaults) => defaults.apply_create(payload),
+            None => payload,
+        }
+    }
+
+    /// Applies a collection's defaults and computed fields to an inserted or replaced item.
+    pub fn apply_insert(&self, collection: &str, payload: Value) -> Value {
+        match self.collections.read().unwrap().get(collection) {
+            Some(defaults) => defaults.apply_insert(payload),
+            None => payload,
+        }
+    }
+
+    /// Applies a collection's computed fields to a partial update.
+    pub fn apply_update(&self, collection: &str, payload: Value) -> Value {
+        match self.collections.read().unwrap().get(collection) {
+            Some(defaults) => defaults.apply_update(payload),
+            None => payload,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::IdStrategy;
+    use chrono::DateTime;
+    use serde_json::json;
+
+    fn field_defaults() -> FieldDefaults {
+        FieldDefaults {
+            defaults: HashMap::from([
+                ("status".to_string(), json!("pending")),
+                ("createdAt".to_string(), json!("{{now}}")),
+            ]),
+            computed: HashMap::from([("updatedAt".to_string(), json!("{{timestamp}}"))]),
+            id: None,
+        }
+    }
+
+    #[test]
+    fn insert_fills_missing_defaults_and_computed_fields() {
+        let item = field_defaults().apply_insert(json!({ "name": "Ada", "status": "active" }));
+
+        assert_eq!(item["status"], "active");
+        assert!(DateTime::parse_from_rfc3339(item["createdAt"].as_str().unwrap()).is_ok());
+        assert!(item["updatedAt"].is_i64());
+    }
+
+    #[test]
+    fn update_only_writes_computed_fields() {
+        let item = field_defaults().apply_update(json!({ "name": "Ada", "updatedAt": 1 }));
+
+        assert!(item.get("status").is_none());
+        assert_ne!(item["updatedAt"], 1);
+    }
+
+    #[test]
+    fn only_created_items_get_generated_ids() {
+        let defaults = FieldDefaults {
+            id: Some(IdGenerator::new(
+                "id",
+                IdStrategy::Template("ord_{{seq}}".to_string()),
+            )),
+            ..field_defaults()
+        };
+
+        assert_eq!(defaults.apply_create(json!({}))["id"], "ord_1");
+        assert!(defaults.apply_insert(json!({})).get("id").is_none());
+    }
+
+    #[test]
+    fn registry_ignores_unknown_collections_and_non_objects() {
+        let registry = CollectionDefaults::default();
+        registry.register("orders", field_defaults());
+        registry.register("empty", FieldDefaults::default());
+
+        assert!(registry.get("empty").is_none());
+        assert_eq!(registry.apply_insert("users", json!({})), json!({}));
+        assert_eq!(registry.apply_insert("orders", json!([1])), json!([1]));
+        assert_eq!(
+            registry.apply_insert("orders", json!({}))["status"],
+            "pending"
+        );
+
+        registry.clear();
+        assert!(registry.get("orders").is_none());
+    }
+}
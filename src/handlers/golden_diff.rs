@@ -0,0 +1,652 @@
+//! Comparison of incoming requests with the golden requests recorded in proxy
+//! record mode, to detect client behavioral drift between versions.
+//!
+//! Rules with `record = true` append the shape of every forwarded request to
+//! the `.golden.jsonl` file of the record folder: its header names, query
+//! parameter names, and the JSON paths and types of its body. With
+//! `[proxy] diff = true`, requests are compared with the golden request of
+//! their method and path, and the differences are reported at `/__diff`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json,
+    body::{Body, to_bytes},
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use chrono::{SecondsFormat, Utc};
+use http::{HeaderMap, Method, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    app::App,
+    handlers::{DEFAULT_RECORD_FOLDER, error_response},
+    route_builder::config::Config,
+};
+
+/// Route reporting the differences from the golden requests.
+pub const DIFF_ROUTE: &str = "/__diff";
+/// File of the record folder the golden requests are appended to.
+pub const GOLDEN_FILE: &str = ".golden.jsonl";
+/// Drifted requests kept for `GET /__diff`.
+pub const DIFF_CAPACITY: usize = 500;
+
+/// Headers describing the connection or the transport, never compared.
+const IGNORED_HEADERS: [&str; 10] = [
+    "connection",
+    "content-length",
+    "date",
+    "host",
+    "keep-alive",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers whose values are recorded and compared; only the names of the
+/// others are, so credentials never reach the golden file.
+const COMPARED_HEADER_VALUES: [&str; 2] = ["accept", "content-type"];
+
+/// The shape of a request, as recorded in the golden file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenRequest {
+    pub method: String,
+    pub path: String,
+    /// Names of the query parameters.
+    #[serde(default)]
+    pub query: BTreeSet<String>,
+    /// Header names, with the value of the compared headers.
+    #[serde(default)]
+    pub headers: BTreeMap<String, Option<String>>,
+    /// Type of each JSON path of the body, such as `"$.user.name": "string"`;
+    /// `"$": "text"` for a body that is not JSON.
+    #[serde(default)]
+    pub body: BTreeMap<String, String>,
+}
+
+impl GoldenRequest {
+    /// Reads the shape of a request.
+    pub fn from_parts(method: &Method, uri: &Uri, headers: &HeaderMap, body: &[u8]) -> Self {
+        let query = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                pair.split_once('=')
+                    .map_or(pair, |(name, _)| name)
+                    .to_string()
+            })
+            .collect();
+        let headers = headers
+            .iter()
+            .filter(|(name, _)| !IGNORED_HEADERS.contains(&name.as_str()))
+            .map(|(name, value)| {
+                let value = COMPARED_HEADER_VALUES
+                    .contains(&name.as_str())
+                    .then(|| header_value(name.as_str(), value.to_str().unwrap_or_default()));
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+
+        let mut shape = BTreeMap::new();
+        if !body.is_empty() {
+            match serde_json::from_slice::<Value>(body) {
+                Ok(value) => body_shape(&value, "$".to_string(), &mut shape),
+                Err(_) => {
+                    shape.insert("$".to_string(), "text".to_string());
+                }
+            }
+        }
+
+        Self {
+            method: method.as_str().to_string(),
+            path: uri.path().to_string(),
+            query,
+            headers,
+            body: shape,
+        }
+    }
+
+    /// Appends the request to the golden file of `folder`.
+    pub fn append_to(&self, folder: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(folder)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(folder.join(GOLDEN_FILE))?;
+        let line = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        writeln!(file, "{}", line)
+    }
+
+    fn key(&self) -> (String, String) {
+        (self.method.clone(), self.path.clone())
+    }
+}
+
+/// Content types are compared without their parameters, such as a multipart
+/// boundary.
+fn header_value(name: &str, value: &str) -> String {
+    if name == "content-type" {
+        value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+    } else {
+        value.to_string()
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Records the type of `value` at `path` and of everything under it, the
+/// items of an array sharing the `path[]` paths.
+fn body_shape(value: &Value, path: String, shape: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                body_shape(field, format!("{}.{}", path, name), shape);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                body_shape(item, format!("{}[]", path), shape);
+            }
+        }
+        _ => {}
+    }
+    shape
+        .entry(path)
+        .or_insert_with(|| type_name(value).to_string());
+}
+
+/// A difference between a request and its golden request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Difference {
+    /// `header`, `query`, or `body`.
+    pub part: &'static str,
+    /// Header name, query parameter, or JSON path of the body.
+    pub name: String,
+    /// `missing`, `added`, or `changed`.
+    pub change: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+}
+
+fn compare_sets<'a>(
+    part: &'static str,
+    expected: impl Iterator<Item = &'a String>,
+    actual: impl Iterator<Item = &'a String>,
+    differences: &mut Vec<Difference>,
+) {
+    let expected: BTreeSet<_> = expected.collect();
+    let actual: BTreeSet<_> = actual.collect();
+    for (names, others, change) in [
+        (&expected, &actual, "missing"),
+        (&actual, &expected, "added"),
+    ] {
+        for name in names.difference(others) {
+            differences.push(Difference {
+                part,
+                name: name.to_string(),
+                change,
+                expected: None,
+                actual: None,
+            });
+        }
+    }
+}
+
+/// Lists how `actual` differs from `golden`. Body paths under a missing or
+/// added path are left out, as the path itself already reports them.
+pub fn compare(golden: &GoldenRequest, actual: &GoldenRequest) -> Vec<Difference> {
+    let mut differences = vec![];
+
+    compare_sets(
+        "header",
+        golden.headers.keys(),
+        actual.headers.keys(),
+        &mut differences,
+    );
+    for (name, expected) in &golden.headers {
+        if let (Some(expected), Some(Some(value))) = (expected, actual.headers.get(name))
+            && expected != value
+        {
+            differences.push(Difference {
+                part: "header",
+                name: name.clone(),
+                change: "changed",
+                expected: Some(expected.clone()),
+                actual: Some(value.clone()),
+            });
+        }
+    }
+
+    compare_sets(
+        "query",
+        golden.query.iter(),
+        actual.query.iter(),
+        &mut differences,
+    );
+
+    let mut body = vec![];
+    compare_sets("body", golden.body.keys(), actual.body.keys(), &mut body);
+    let reported: Vec<String> = body
+        .iter()
+        .map(|difference| difference.name.clone())
+        .collect();
+    body.retain(|difference| {
+        !reported
+            .iter()
+            .any(|parent| is_under(&difference.name, parent))
+    });
+    for (path, expected) in &golden.body {
+        if let Some(value) = actual.body.get(path)
+            && expected != value
+        {
+            body.push(Difference {
+                part: "body",
+                name: path.clone(),
+                change: "changed",
+                expected: Some(expected.clone()),
+                actual: Some(value.clone()),
+            });
+        }
+    }
+    differences.extend(body);
+
+    differences
+}
+
+fn is_under(path: &str, parent: &str) -> bool {
+    path.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+}
+
+/// A request that differed from its golden request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Drift {
+    /// When the request was received, as RFC 3339.
+    pub at: String,
+    pub method: String,
+    pub path: String,
+    pub differences: Vec<Difference>,
+}
+
+#[derive(Default)]
+struct DiffReport {
+    compared: u64,
+    matching: u64,
+    unknown: BTreeSet<String>,
+    drifts: VecDeque<Drift>,
+}
+
+/// The golden requests, when diffing is enabled, and the differences found.
+#[derive(Default)]
+pub struct GoldenDiff {
+    golden: Option<HashMap<(String, String), GoldenRequest>>,
+    report: Mutex<DiffReport>,
+}
+
+impl GoldenDiff {
+    /// Loads the golden file of the record folder when `[proxy] diff` is set.
+    ///
+    /// A missing file is an empty golden set, which reports every request as
+    /// unknown.
+    pub fn from_config(config: &Config) -> Self {
+        let Some(proxy) = config
+            .proxy
+            .as_ref()
+            .filter(|proxy| proxy.diff == Some(true))
+        else {
+            return Self::default();
+        };
+        let folder = PathBuf::from(
+            proxy
+                .record_folder
+                .as_deref()
+                .unwrap_or(DEFAULT_RECORD_FOLDER),
+        );
+        Self::with_golden(load_golden(&folder.join(GOLDEN_FILE)))
+    }
+
+    fn with_golden(requests: Vec<GoldenRequest>) -> Self {
+        Self {
+            golden: Some(
+                requests
+                    .into_iter()
+                    .map(|request| (request.key(), request))
+                    .collect(),
+            ),
+            report: Mutex::default(),
+        }
+    }
+
+    /// Whether requests are compared with golden requests.
+    pub fn is_active(&self) -> bool {
+        self.golden.is_some()
+    }
+
+    /// Compares a request with the golden request of its method and path.
+    pub fn check(&self, request: &GoldenRequest) {
+        let Some(golden) = &self.golden else {
+            return;
+        };
+        let mut report = self.report.lock().unwrap();
+        report.compared += 1;
+        let Some(expected) = golden.get(&request.key()) else {
+            report
+                .unknown
+                .insert(format!("{} {}", request.method, request.path));
+            return;
+        };
+
+        let differences = compare(expected, request);
+        if differences.is_empty() {
+            report.matching += 1;
+            return;
+        }
+        if report.drifts.len() == DIFF_CAPACITY {
+            report.drifts.pop_front();
+        }
+        report.drifts.push_back(Drift {
+            at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            method: request.method.clone(),
+            path: request.path.clone(),
+            differences,
+        });
+    }
+
+    /// Returns the counters, the requests without a golden request, and the
+    /// latest drifted requests, oldest first.
+    pub fn report(&self) -> Value {
+        let report = self.report.lock().unwrap();
+        json!({
+            "golden": self.golden.as_ref().map_or(0, HashMap::len),
+            "compared": report.compared,
+            "matching": report.matching,
+            "unknown": report.unknown,
+            "drifts": report.drifts,
+        })
+    }
+
+    /// Forgets the comparisons made so far.
+    pub fn clear(&self) {
+        *self.report.lock().unwrap() = DiffReport::default();
+    }
+}
+
+/// Reads the golden requests of a file, the last recorded one of a method
+/// and path winning. Unreadable lines are skipped.
+fn load_golden(path: &Path) -> Vec<GoldenRequest> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+type GoldenDiffMiddlewareReturn =
+    Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware comparing every request with its golden request, except
+/// those of the internal `/__` routes.
+pub fn make_golden_diff_middleware(
+    diff: &Arc<GoldenDiff>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> GoldenDiffMiddlewareReturn {
+    let diff = Arc::clone(diff);
+    move |req: Request, next: Next| {
+        let diff = Arc::clone(&diff);
+        Box::pin(async move {
+            if req.uri().path().starts_with("/__") {
+                return next.run(req).await;
+            }
+
+            let (parts, body) = req.into_parts();
+            let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_body",
+                    "The request body could not be read",
+                );
+            };
+            diff.check(&GoldenRequest::from_parts(
+                &parts.method,
+                &parts.uri,
+                &parts.headers,
+                &bytes,
+            ));
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        })
+    }
+}
+
+/// Registers `GET /__diff`, which reports the differences from the golden
+/// requests, and `DELETE /__diff`, which clears them, when diffing is enabled.
+pub fn create_golden_diff_routes(app: &mut App) {
+    if !app.golden_diff.is_active() {
+        return;
+    }
+    let diff = Arc::clone(&app.golden_diff);
+    let report_router = get(async move || Json(diff.report()).into_response());
+    app.route(DIFF_ROUTE, report_router, Some("GET"), None);
+
+    let diff = Arc::clone(&app.golden_diff);
+    let clear_router = delete(async move || {
+        diff.clear();
+        StatusCode::NO_CONTENT.into_response()
+    });
+    app.route(DIFF_ROUTE, clear_router, Some("DELETE"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::middleware;
+    use http::header::{AUTHORIZATION, CONTENT_TYPE};
+    use tower::ServiceExt;
+
+    fn request(uri: &str, headers: &[(&str, &str)], body: &str) -> GoldenRequest {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(
+                http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        GoldenRequest::from_parts(&Method::POST, &uri.parse().unwrap(), &map, body.as_bytes())
+    }
+
+    #[test]
+    fn shapes_keep_names_and_types_but_not_values() {
+        let shape = request(
+            "/users?page=2&sort",
+            &[
+                ("content-type", "application/json; charset=utf-8"),
+                ("authorization", "Bearer secret"),
+                ("host", "localhost"),
+            ],
+            r#"{"name":"Ada","tags":["a"],"address":{"zip":1}}"#,
+        );
+
+        assert_eq!(shape.path, "/users");
+        assert_eq!(
+            shape.query,
+            BTreeSet::from(["page".to_string(), "sort".to_string()])
+        );
+        assert_eq!(
+            shape.headers,
+            BTreeMap::from([
+                ("authorization".to_string(), None),
+                (
+                    "content-type".to_string(),
+                    Some("application/json".to_string())
+                ),
+            ])
+        );
+        assert_eq!(
+            serde_json::to_value(&shape.body).unwrap(),
+            json!({
+                "$": "object",
+                "$.address": "object",
+                "$.address.zip": "number",
+                "$.name": "string",
+                "$.tags": "array",
+                "$.tags[]": "string",
+            })
+        );
+        assert_eq!(request("/", &[], "plain").body["$"], "text");
+    }
+
+    #[test]
+    fn differences_cover_headers_query_and_body_structure() {
+        let golden = request(
+            "/users?page=1",
+            &[("content-type", "application/json"), ("x-client", "1")],
+            r#"{"name":"Ada","age":36,"address":{"zip":"1"}}"#,
+        );
+        let actual = request(
+            "/users?cursor=a",
+            &[("content-type", "text/plain"), ("x-trace", "2")],
+            r#"{"name":"Ada","age":"36","email":"a@b.c"}"#,
+        );
+
+        let differences: Vec<_> = compare(&golden, &actual)
+            .into_iter()
+            .map(|difference| {
+                format!(
+                    "{} {} {} {:?} {:?}",
+                    difference.part,
+                    difference.name,
+                    difference.change,
+                    difference.expected,
+                    difference.actual
+                )
+            })
+            .collect();
+        assert_eq!(
+            differences,
+            [
+                "header x-client missing None None",
+                "header x-trace added None None",
+                r#"header content-type changed Some("application/json") Some("text/plain")"#,
+                "query page missing None None",
+                "query cursor added None None",
+                "body $.address missing None None",
+                "body $.email added None None",
+                r#"body $.age changed Some("number") Some("string")"#,
+            ]
+        );
+        assert!(compare(&golden, &golden).is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_are_compared_and_reported_at_diff() {
+        let record_folder = tempfile::tempdir().unwrap();
+        request(
+            "/users",
+            &[("content-type", "application/json")],
+            r#"{"name":"Ada"}"#,
+        )
+        .append_to(record_folder.path())
+        .unwrap();
+        let mut app = App::new(Config {
+            proxy: Some(crate::route_builder::config::ProxyConfig {
+                record_folder: Some(record_folder.path().to_string_lossy().to_string()),
+                diff: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        app.route(
+            "/users",
+            axum::routing::post(|| async { "ok" }),
+            Some("POST"),
+            None,
+        );
+        app.route(
+            "/orders",
+            axum::routing::post(|| async { "ok" }),
+            Some("POST"),
+            None,
+        );
+        create_golden_diff_routes(&mut app);
+        let diff = Arc::clone(&app.golden_diff);
+        let router = app
+            .take_router_for_test()
+            .layer(middleware::from_fn(make_golden_diff_middleware(&diff)));
+
+        for (uri, body) in [
+            ("/users", r#"{"name":"Ada"}"#),
+            ("/users", r#"{"name":1}"#),
+            ("/orders", "{}"),
+        ] {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::post(uri)
+                        .header(CONTENT_TYPE, "application/json")
+                        .header(AUTHORIZATION, "Bearer token")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let report = router
+            .clone()
+            .oneshot(Request::get(DIFF_ROUTE).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let report: Value =
+            serde_json::from_slice(&to_bytes(report.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(report["golden"], 1);
+        assert_eq!(report["compared"], 3);
+        assert_eq!(report["matching"], 0);
+        assert_eq!(report["unknown"], json!(["POST /orders"]));
+        let drifts = report["drifts"].as_array().unwrap();
+        assert_eq!(drifts.len(), 2);
+        assert_eq!(
+            drifts[0]["differences"],
+            json!([{ "part": "header", "name": "authorization", "change": "added" }])
+        );
+        assert_eq!(drifts[1]["differences"].as_array().unwrap().len(), 2);
+
+        let cleared = router
+            .oneshot(Request::delete(DIFF_ROUTE).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(cleared.status(), StatusCode::NO_CONTENT);
+        assert_eq!(diff.report()["compared"], 0);
+    }
+}
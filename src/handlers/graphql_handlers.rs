@@ -1,36 +1,86 @@
-use std::{ffi::OsString, fs, io::Error, path::PathBuf, str::FromStr};
+use std::{ffi::OsString, fs, io::Error, path::PathBuf};
 
 use async_graphql::{
-    Error as GQLError, Request as GQLRequest, Response as GQLResponse, ServerError,
-    Value as GValue,
+    Error as GQLError, ErrorExtensionValues, Request as GQLRequest, Response as GQLResponse,
+    ServerError, Value as GValue,
     dynamic::{Field, FieldFuture, Object, Scalar, Schema, TypeRef},
-    http::GraphiQLSource,
+    http::{GraphiQLSource, parse_query_string},
 };
 use axum::{
-    extract::Json,
+    extract::{Json, RawQuery},
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use fosk::{CollectionReadError, Db, IdType, JsonPrimitive};
 use graphql_parser::query::{
     Definition, Document, OperationDefinition, Selection, Value as GqlValue, parse_query,
 };
-use serde_json;
-use std::sync::Arc;
-
-use jgd_rs::generate_jgd_from_file;
+use http::StatusCode;
+use serde::Deserialize;
+use serde_json::{self, json};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
 
 use crate::{
-    app::App,
-    handlers::{SleepThread, is_jgd, is_json},
-    route_builder::{RouteRegistrator, route_graphql::RouteGraphQL},
+    app::{App, MOCK_SERVER_ROUTE},
+    criteria::CriteriaBuilder,
+    handlers::{
+        Change, ChangeEvents, CollectionOrder, ResponseTransforms, SCHEMA_FILE, SleepDelay,
+        error_response, id_string, is_jgd, is_json, load_schema_file,
+    },
+    random,
+    route_builder::{RouteRegistrator, config::Delay, route_graphql::RouteGraphQL},
 };
 use std::collections::{HashMap, HashSet};
 
 /// Folder under a GraphQL route that seeds Fosk collections.
 pub const COLLECTIONS_FOLDER: &str = "/collections";
 
+/// Queries registered for clients that send persisted query hashes instead of
+/// query text.
+#[derive(Debug, Default)]
+pub struct PersistedQueries {
+    queries: RwLock<BTreeMap<String, String>>,
+}
+
+impl PersistedQueries {
+    /// Registers a query under `hash`, or under its SHA-256 hash when `hash`
+    /// is `None`, and returns the hash.
+    pub fn register(&self, query: &str, hash: Option<&str>) -> String {
+        let hash = hash.map_or_else(|| query_hash(query), str::to_string);
+        self.queries
+            .write()
+            .unwrap()
+            .insert(hash.clone(), query.to_string());
+        hash
+    }
+
+    /// Returns the query registered under `hash`.
+    pub fn get(&self, hash: &str) -> Option<String> {
+        self.queries.read().unwrap().get(hash).cloned()
+    }
+
+    /// Returns every registered query by hash.
+    pub fn all(&self) -> BTreeMap<String, String> {
+        self.queries.read().unwrap().clone()
+    }
+}
+
+/// Returns the hex-encoded SHA-256 hash of a query, as persisted query clients
+/// compute it.
+pub fn query_hash(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
 /// Builds a dynamic Async-GraphQL schema from loaded Fosk collections.
-pub fn build_dynamic_schema(db: &Db) -> Schema {
+///
+/// With `relay`, query fields return Relay connections of the collection
+/// items instead of plain lists.
+pub fn build_dynamic_schema(db: &Db, relay: bool) -> Schema {
     struct CollectionMeta {
         raw: String,
         field: String,
@@ -131,7 +181,83 @@ pub fn build_dynamic_schema(db: &Db) -> Schema {
         obj
     }
 
-    fn build_query(collections: &[CollectionMeta]) -> Object {
+    fn value_field(name: &str, ty: TypeRef) -> Field {
+        let key = name.to_string();
+        Field::new(name, ty, move |ctx| {
+            let key = key.clone();
+            FieldFuture::new(async move {
+                Ok(match ctx.parent_value.as_value() {
+                    Some(GValue::Object(parent)) => parent.get(key.as_str()).cloned(),
+                    _ => None,
+                })
+            })
+        })
+    }
+
+    fn build_page_info() -> Object {
+        Object::new("PageInfo")
+            .field(value_field("hasNextPage", TypeRef::named_nn("Boolean")))
+            .field(value_field("hasPreviousPage", TypeRef::named_nn("Boolean")))
+            .field(value_field("startCursor", TypeRef::named("String")))
+            .field(value_field("endCursor", TypeRef::named("String")))
+    }
+
+    fn build_connection(meta: &CollectionMeta) -> [Object; 2] {
+        let edge = format!("{}Edge", meta.type_name);
+        [
+            Object::new(&edge)
+                .field(value_field("cursor", TypeRef::named_nn("String")))
+                .field(value_field("node", TypeRef::named_nn(&meta.type_name))),
+            Object::new(format!("{}Connection", meta.type_name))
+                .field(value_field("edges", TypeRef::named_nn_list_nn(&edge)))
+                .field(value_field(
+                    "nodes",
+                    TypeRef::named_nn_list_nn(&meta.type_name),
+                ))
+                .field(value_field("pageInfo", TypeRef::named_nn("PageInfo")))
+                .field(value_field("totalCount", TypeRef::named_nn("Int"))),
+        ]
+    }
+
+    fn build_connection_field(meta: &CollectionMeta) -> Field {
+        let coll_name = meta.raw.clone();
+        Field::new(
+            meta.field.clone(),
+            TypeRef::named_nn(format!("{}Connection", meta.type_name)),
+            move |ctx| {
+                let db = ctx.data::<Arc<Db>>().unwrap().clone();
+                let coll_name = coll_name.clone();
+                FieldFuture::new(async move {
+                    let items = db
+                        .get(&coll_name)
+                        .unwrap()
+                        .get_all()
+                        .map_err(|err| GQLError::new(err.to_string()))?;
+                    let total = items.len();
+                    let connection = connection_json(items, 0, total);
+                    Ok(Some(GValue::from_json(connection).unwrap_or(GValue::Null)))
+                })
+            },
+        )
+        .argument(async_graphql::dynamic::InputValue::new(
+            "first",
+            TypeRef::named("Int"),
+        ))
+        .argument(async_graphql::dynamic::InputValue::new(
+            "after",
+            TypeRef::named("String"),
+        ))
+        .argument(async_graphql::dynamic::InputValue::new(
+            "last",
+            TypeRef::named("Int"),
+        ))
+        .argument(async_graphql::dynamic::InputValue::new(
+            "before",
+            TypeRef::named("String"),
+        ))
+    }
+
+    fn build_query(collections: &[CollectionMeta], relay: bool) -> Object {
         let mut query = Object::new("Query");
         let mut seen = HashSet::new();
 
@@ -139,6 +265,10 @@ pub fn build_dynamic_schema(db: &Db) -> Schema {
             if !seen.insert(meta.field.clone()) {
                 continue;
             }
+            if relay {
+                query = query.field(build_connection_field(meta));
+                continue;
+            }
 
             let field_name = meta.field.clone();
             let coll_name = meta.raw.clone();
@@ -256,11 +386,19 @@ pub fn build_dynamic_schema(db: &Db) -> Schema {
             };
             let object = build_object(&def, &meta);
             schema = schema.register(object);
+            if relay {
+                for object in build_connection(&meta) {
+                    schema = schema.register(object);
+                }
+            }
             collections.push(meta);
         }
     }
+    if relay {
+        schema = schema.register(build_page_info());
+    }
 
-    schema = schema.register(build_query(&collections));
+    schema = schema.register(build_query(&collections, relay));
 
     let mut mutation = Object::new("Mutation");
     for meta in &collections {
@@ -303,7 +441,9 @@ fn load_static_data(base_path: &OsString, op_name: &str) -> Option<serde_json::V
     }
     let jgd_file = file_path.join(format!("{}.jgd", op_name));
     if jgd_file.exists() {
-        let data_json = generate_jgd_from_file(&jgd_file).unwrap_or(serde_json::Value::Null);
+        let data_json = random::current()
+            .generate_jgd(&jgd_file)
+            .unwrap_or(serde_json::Value::Null);
         return Some(data_json);
     }
     None
@@ -317,6 +457,55 @@ fn response_from_json(data_json: serde_json::Value) -> Json<GQLResponse> {
     Json(response)
 }
 
+/// Applies the `persistedQuery` extension of a request, as Apollo clients send
+/// it.
+///
+/// A request without query text takes the query registered under the hash, and
+/// a request with both registers its query, so a client can retry after a
+/// `PersistedQueryNotFound` error.
+fn resolve_persisted_query(
+    req: &mut GQLRequest,
+    queries: &PersistedQueries,
+) -> Result<(), ServerError> {
+    let Some(extension) = req.extensions.remove("persistedQuery") else {
+        return Ok(());
+    };
+    let extension = extension.into_json().unwrap_or_default();
+    if extension["version"].as_i64() != Some(1) {
+        return Err(ServerError::new(
+            "Only version 1 of the persistedQuery extension is supported",
+            None,
+        ));
+    }
+    let Some(hash) = extension["sha256Hash"].as_str() else {
+        return Err(ServerError::new(
+            "The persistedQuery extension needs a sha256Hash",
+            None,
+        ));
+    };
+
+    if !req.query.is_empty() {
+        if query_hash(&req.query) != hash {
+            return Err(ServerError::new("provided sha does not match query", None));
+        }
+        queries.register(&req.query, Some(hash));
+        return Ok(());
+    }
+    match queries.get(hash) {
+        Some(query) => {
+            req.query = query;
+            Ok(())
+        }
+        None => {
+            let mut error = ServerError::new("PersistedQueryNotFound", None);
+            let mut extensions = ErrorExtensionValues::default();
+            extensions.set("code", "PERSISTED_QUERY_NOT_FOUND");
+            error.extensions = Some(extensions);
+            Err(error)
+        }
+    }
+}
+
 /// Parse the raw GraphQL request into an AST document
 fn parse_request_ast(req: &GQLRequest) -> Result<Document<'_, String>, GQLError> {
     parse_query::<String>(&req.query)
@@ -403,16 +592,21 @@ fn expansion_paths(selection_set: &graphql_parser::query::SelectionSet<String>)
     paths
 }
 
+/// Expands the referenced items a selection asks for, applies the
+/// collection's response transforms, then keeps the selected fields.
 fn expand_list_with_selection(
     collection: &Arc<fosk::DbCollection>,
     items: Vec<serde_json::Value>,
     selection_set: &graphql_parser::query::SelectionSet<String>,
     db: &Db,
+    transforms: &ResponseTransforms,
 ) -> Result<Vec<serde_json::Value>, CollectionReadError> {
     let mut expanded_items = items;
     for path in expansion_paths(selection_set) {
         expanded_items = collection.expand_list(expanded_items, &path, db)?;
     }
+    let collection_name = collection.get_name().unwrap_or_default();
+    let expanded_items = transforms.apply_items(&collection_name, expanded_items);
 
     Ok(expanded_items
         .into_iter()
@@ -425,11 +619,14 @@ fn expand_row_with_selection(
     item: serde_json::Value,
     selection_set: &graphql_parser::query::SelectionSet<String>,
     db: &Db,
+    transforms: &ResponseTransforms,
 ) -> Result<serde_json::Value, CollectionReadError> {
     let mut expanded_item = item;
     for path in expansion_paths(selection_set) {
         expanded_item = collection.expand_row(&expanded_item, &path, db)?;
     }
+    let collection_name = collection.get_name().unwrap_or_default();
+    let expanded_item = transforms.apply_item(&collection_name, expanded_item);
 
     Ok(filter_value(expanded_item, selection_set))
 }
@@ -472,12 +669,153 @@ fn graphql_value_to_json(val: &GqlValue<String>) -> serde_json::Value {
     serde_json::from_str(&s).unwrap_or_else(|_| serde_json::Value::String(s))
 }
 
-// Updated execute_query to respect GraphQL arguments for filtering
+/// Arguments that page through a Relay connection instead of filtering it.
+const PAGINATION_ARGUMENTS: [&str; 4] = ["first", "after", "last", "before"];
+
+/// Prefix of the offsets encoded in connection cursors, as in graphql-relay.
+const CURSOR_PREFIX: &str = "arrayconnection:";
+
+fn cursor(offset: usize) -> String {
+    BASE64.encode(format!("{}{}", CURSOR_PREFIX, offset))
+}
+
+fn cursor_offset(cursor: &str) -> Result<usize, String> {
+    BASE64
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|text| text.strip_prefix(CURSOR_PREFIX)?.parse().ok())
+        .ok_or_else(|| format!("invalid cursor '{}'", cursor))
+}
+
+/// Builds a Relay connection of `page`, the items of a list of `total` items
+/// starting at offset `start`.
+fn connection_json(page: Vec<serde_json::Value>, start: usize, total: usize) -> serde_json::Value {
+    let end = start + page.len();
+    let edges = page
+        .iter()
+        .enumerate()
+        .map(|(index, node)| serde_json::json!({ "cursor": cursor(start + index), "node": node }))
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "edges": edges,
+        "nodes": page,
+        "pageInfo": {
+            "hasPreviousPage": start > 0,
+            "hasNextPage": end < total,
+            "startCursor": (start < end).then(|| cursor(start)),
+            "endCursor": (start < end).then(|| cursor(end - 1)),
+        },
+        "totalCount": total,
+    })
+}
+
+/// Returns the `start..end` range of the items a connection's `first`,
+/// `after`, `last`, and `before` arguments select, as the Relay spec
+/// describes.
+fn page_bounds(
+    total: usize,
+    pagination: &[&(String, GqlValue<'_, String>)],
+    variables: &serde_json::Value,
+) -> Result<(usize, usize), String> {
+    let (mut start, mut end) = (0, total);
+    let (mut first, mut last) = (None, None);
+    for (name, value) in pagination {
+        let value = match value {
+            GqlValue::Variable(variable) => variables[variable.as_str()].clone(),
+            value => graphql_value_to_json(value),
+        };
+        match (name.as_str(), &value) {
+            (_, serde_json::Value::Null) => {}
+            ("after", serde_json::Value::String(after)) => start = cursor_offset(after)? + 1,
+            ("before", serde_json::Value::String(before)) => end = cursor_offset(before)?,
+            ("first", count) if count.is_u64() => first = count.as_u64(),
+            ("last", count) if count.is_u64() => last = count.as_u64(),
+            (name, _) => return Err(format!("invalid {} argument {}", name, value)),
+        }
+    }
+
+    end = end.min(total);
+    start = start.min(end);
+    if let Some(first) = first {
+        end = end.min(start.saturating_add(first as usize));
+    }
+    if let Some(last) = last {
+        start = start.max(end.saturating_sub(last as usize));
+    }
+    Ok((start, end))
+}
+
+/// What Relay connections need besides the collections.
+struct RelayContext<'a> {
+    /// Item order, so pages stay stable between requests.
+    order: &'a CollectionOrder,
+    /// Variables of the request, which pagination arguments may refer to.
+    variables: &'a serde_json::Value,
+    /// Renames, mappings, and masking applied to the page's nodes.
+    transforms: &'a ResponseTransforms,
+}
+
+/// Answers a query field as a Relay connection of the matching items.
+fn execute_connection(
+    db: &Db,
+    collection: &Arc<fosk::DbCollection>,
+    mut items: Vec<serde_json::Value>,
+    pagination: &[&(String, GqlValue<'_, String>)],
+    field: &graphql_parser::query::Field<'_, String>,
+    relay: &RelayContext<'_>,
+) -> Result<serde_json::Value, String> {
+    let id_key = collection
+        .get_config()
+        .map_err(|err| err.to_string())?
+        .id_key;
+    relay.order.sort(&field.name, &id_key, &mut items);
+    let total = items.len();
+    let (start, end) = page_bounds(total, pagination, relay.variables)?;
+    let mut page = items[start..end].to_vec();
+
+    let node_selections = field.selection_set.items.iter().filter_map(|selection| {
+        let Selection::Field(child) = selection else {
+            return None;
+        };
+        match child.name.as_str() {
+            "nodes" => Some(&child.selection_set),
+            "edges" => child
+                .selection_set
+                .items
+                .iter()
+                .find_map(|edge| match edge {
+                    Selection::Field(node) if node.name == "node" => Some(&node.selection_set),
+                    _ => None,
+                }),
+            _ => None,
+        }
+    });
+    for selection_set in node_selections {
+        for path in expansion_paths(selection_set) {
+            page = collection
+                .expand_list(page, &path, db)
+                .map_err(|err| err.to_string())?;
+        }
+    }
+    let page = relay.transforms.apply_items(&field.name, page);
+
+    Ok(filter_value(
+        connection_json(page, start, total),
+        &field.selection_set,
+    ))
+}
+
+// Updated execute_query to respect GraphQL arguments for filtering.
+// A `where` argument holds a criteria expression, such as `address.city = "Paris"`,
+// applied after the other arguments.
 fn execute_query(
     db: &Db,
+    transforms: &ResponseTransforms,
     result: &mut serde_json::Map<String, serde_json::Value>,
     query: &graphql_parser::query::Query<'_, String>,
-) -> Result<(), CollectionReadError> {
+    relay: Option<&RelayContext<'_>>,
+) -> Result<(), String> {
     fn should_skip_field(name: &str) -> bool {
         name.starts_with("__")
     }
@@ -486,15 +824,39 @@ fn execute_query(
         db: &Db,
         collection: &Arc<fosk::DbCollection>,
         field_name: &str,
-        field: &graphql_parser::query::Field<'_, String>,
+        mut arguments: Vec<&(String, GqlValue<'_, String>)>,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        let criteria = match arguments.iter().position(|(name, _)| name == "where") {
+            Some(position) => match &arguments.remove(position).1 {
+                GqlValue::String(text) => {
+                    Some(CriteriaBuilder::parse(text).map_err(|err| err.to_string())?)
+                }
+                _ => return Err("where argument must be a string".to_string()),
+            },
+            None => None,
+        };
+
+        let mut items = fetch_matching_items(db, collection, field_name, &arguments)
+            .map_err(|err| err.to_string())?;
+        if let Some(criteria) = criteria {
+            items.retain(|item| criteria.matches(item));
+        }
+        Ok(items)
+    }
+
+    fn fetch_matching_items(
+        db: &Db,
+        collection: &Arc<fosk::DbCollection>,
+        field_name: &str,
+        arguments: &[&(String, GqlValue<'_, String>)],
     ) -> Result<Vec<serde_json::Value>, CollectionReadError> {
-        if field.arguments.is_empty() {
+        if arguments.is_empty() {
             return collection.get_all();
         }
 
         let id_key = collection.get_config()?.id_key;
-        if field.arguments.len() == 1 && field.arguments[0].0 == id_key {
-            let arg_val = graphql_value_to_json(&field.arguments[0].1);
+        if arguments.len() == 1 && arguments[0].0 == id_key {
+            let arg_val = graphql_value_to_json(&arguments[0].1);
             if let Some(item) = collection.get(arg_val.as_str().unwrap_or(""))? {
                 return Ok(vec![item]);
             }
@@ -503,7 +865,7 @@ fn execute_query(
 
         let mut clauses = Vec::new();
         let mut args_json = Vec::new();
-        for (name, val) in &field.arguments {
+        for (name, val) in arguments {
             clauses.push(format!("{} = ?", name));
             args_json.push(graphql_value_to_json(val));
         }
@@ -525,11 +887,25 @@ fn execute_query(
             }
 
             let field_name = field.name.as_str();
+            let (pagination, arguments): (Vec<_>, Vec<_>) =
+                field.arguments.iter().partition(|(name, _)| {
+                    relay.is_some() && PAGINATION_ARGUMENTS.contains(&name.as_str())
+                });
             let value = match db.get(field_name) {
+                Some(collection) if let Some(relay) = relay => {
+                    let items = fetch_collection_items(db, &collection, field_name, arguments)?;
+                    execute_connection(db, &collection, items, &pagination, field, relay)?
+                }
                 Some(collection) => {
-                    let items = fetch_collection_items(db, &collection, field_name, field)?;
-                    let filtered =
-                        expand_list_with_selection(&collection, items, &field.selection_set, db)?;
+                    let items = fetch_collection_items(db, &collection, field_name, arguments)?;
+                    let filtered = expand_list_with_selection(
+                        &collection,
+                        items,
+                        &field.selection_set,
+                        db,
+                        transforms,
+                    )
+                    .map_err(|err| err.to_string())?;
                     serde_json::Value::Array(filtered)
                 }
                 None => serde_json::Value::Null,
@@ -544,6 +920,8 @@ fn execute_query(
 
 fn execute_operation(
     db: &Db,
+    events: &ChangeEvents,
+    transforms: &ResponseTransforms,
     result: &mut serde_json::Map<String, serde_json::Value>,
     errors: &mut Vec<String>,
     mutation: &graphql_parser::query::Mutation<'_, String>,
@@ -558,6 +936,8 @@ fn execute_operation(
 
     fn handle_create(
         db: &Db,
+        events: &ChangeEvents,
+        transforms: &ResponseTransforms,
         collection_name: &str,
         field: &graphql_parser::query::Field<'_, String>,
     ) -> Result<serde_json::Value, String> {
@@ -567,8 +947,17 @@ fn execute_operation(
                 new_map.insert(arg_name.clone(), graphql_value_to_json(arg_val));
             }
             let new_item = serde_json::Value::Object(new_map);
-            let created = collection.add(new_item).map_err(|err| err.to_string())?;
-            expand_row_with_selection(&collection, created, &field.selection_set, db)
+            let created = random::current()
+                .add_item(&collection, new_item)
+                .map_err(|err| err.to_string())?;
+            let id_key = collection
+                .get_config()
+                .map_err(|err| err.to_string())?
+                .id_key;
+            if let Some(id) = created.get(&id_key).and_then(id_string) {
+                events.publish(Change::created(collection_name, &id, &created));
+            }
+            expand_row_with_selection(&collection, created, &field.selection_set, db, transforms)
                 .map_err(|err| err.to_string())
         } else {
             Ok(serde_json::Value::Null)
@@ -577,6 +966,8 @@ fn execute_operation(
 
     fn handle_update(
         db: &Db,
+        events: &ChangeEvents,
+        transforms: &ResponseTransforms,
         collection_name: &str,
         field: &graphql_parser::query::Field<'_, String>,
     ) -> Result<serde_json::Value, String> {
@@ -598,9 +989,15 @@ fn execute_operation(
 
             if let Some(id) = id_value {
                 let partial = serde_json::Value::Object(update_map);
-                Ok(collection
+                let before = collection.get(&id).map_err(|err| err.to_string())?;
+                let updated = collection
                     .update_partial(&id, partial)
-                    .map_err(|err| err.to_string())?
+                    .map_err(|err| err.to_string())?;
+                if let Some(updated) = &updated {
+                    events.publish(Change::updated(collection_name, &id, before, updated));
+                }
+                Ok(updated
+                    .map(|updated| transforms.apply_item(collection_name, updated))
                     .unwrap_or(serde_json::Value::Null))
             } else {
                 Ok(serde_json::Value::Null)
@@ -612,6 +1009,8 @@ fn execute_operation(
 
     fn handle_delete(
         db: &Db,
+        events: &ChangeEvents,
+        transforms: &ResponseTransforms,
         collection_name: &str,
         field: &graphql_parser::query::Field<'_, String>,
     ) -> Result<serde_json::Value, String> {
@@ -629,8 +1028,15 @@ fn execute_operation(
             if let Some(id) = id_value {
                 match collection.delete(&id).map_err(|err| err.to_string())? {
                     Some(deleted) => {
-                        expand_row_with_selection(&collection, deleted, &field.selection_set, db)
-                            .map_err(|err| err.to_string())
+                        events.publish(Change::deleted(collection_name, &id, &deleted));
+                        expand_row_with_selection(
+                            &collection,
+                            deleted,
+                            &field.selection_set,
+                            db,
+                            transforms,
+                        )
+                        .map_err(|err| err.to_string())
                     }
                     None => Ok(serde_json::Value::Null),
                 }
@@ -646,11 +1052,11 @@ fn execute_operation(
         if let Selection::Field(field) = sel {
             let field_name = field.name.as_str();
             let outcome = if let Some(collection_name) = field_name.strip_prefix("create") {
-                handle_create(db, collection_name, field)
+                handle_create(db, events, transforms, collection_name, field)
             } else if let Some(collection_name) = field_name.strip_prefix("update") {
-                handle_update(db, collection_name, field)
+                handle_update(db, events, transforms, collection_name, field)
             } else if let Some(collection_name) = field_name.strip_prefix("delete") {
-                handle_delete(db, collection_name, field)
+                handle_delete(db, events, transforms, collection_name, field)
             } else {
                 Ok(serde_json::Value::Null)
             };
@@ -672,6 +1078,9 @@ fn execute_operation(
 async fn execute_graphql_operations(
     doc: &Document<'_, String>,
     db: &Db,
+    events: &ChangeEvents,
+    transforms: &ResponseTransforms,
+    relay: Option<&RelayContext<'_>>,
 ) -> Result<(serde_json::Value, Vec<String>), String> {
     let mut result = serde_json::Map::new();
     let mut errors = Vec::new();
@@ -679,10 +1088,10 @@ async fn execute_graphql_operations(
     for def in &doc.definitions {
         match def {
             Definition::Operation(OperationDefinition::Query(q)) => {
-                execute_query(db, &mut result, q).map_err(|err| err.to_string())?;
+                execute_query(db, transforms, &mut result, q, relay)?;
             }
             Definition::Operation(OperationDefinition::Mutation(m)) => {
-                execute_operation(db, &mut result, &mut errors, m);
+                execute_operation(db, events, transforms, &mut result, &mut errors, m);
             }
             _ => {}
         }
@@ -693,92 +1102,243 @@ async fn execute_graphql_operations(
 
 // -------------------------------------------------------------------------------
 
+/// Settings shared by the requests of one GraphQL endpoint.
+#[derive(Clone)]
+struct GraphQLEndpoint {
+    db: Arc<Db>,
+    folder: OsString,
+    schema: Option<Schema>,
+    delay: Option<Delay>,
+    queries: Arc<PersistedQueries>,
+    order: Arc<CollectionOrder>,
+    events: Arc<ChangeEvents>,
+    transforms: Arc<ResponseTransforms>,
+    relay: bool,
+}
+
+/// Executes one GraphQL request, resolving persisted queries first.
+///
+/// Introspection answers from the endpoint schema, or from a schema inferred
+/// from the collections when the folder has no `schema.graphql`. `read_only`
+/// requests, sent over GET, may not run mutations.
+async fn execute_request(
+    mut req: GQLRequest,
+    endpoint: GraphQLEndpoint,
+    read_only: bool,
+) -> Response {
+    let GraphQLEndpoint {
+        db,
+        folder,
+        schema,
+        delay,
+        queries,
+        order,
+        events,
+        transforms,
+        relay,
+    } = endpoint;
+    if let Err(err) = resolve_persisted_query(&mut req, &queries) {
+        let mut response = GQLResponse::default();
+        response.errors = vec![err];
+        return Json(response).into_response();
+    }
+
+    // Introspection queries (__schema or __type)
+    let query_str = req.query.clone();
+    if query_str.contains("__schema") || query_str.contains("__type") {
+        // Build a fresh request for introspection and attach DB
+        let int_req = async_graphql::Request::new(query_str).data(db.clone());
+        let dyn_schema = schema.unwrap_or_else(|| build_dynamic_schema(&db, relay));
+        let resp = dyn_schema.execute(int_req).await;
+        return Json(resp).into_response();
+    }
+
+    delay.sleep().await;
+
+    // 1) Parse request into AST
+    let doc = match parse_request_ast(&req) {
+        Err(err) => {
+            let mut response = GQLResponse::default();
+            response.errors = vec![ServerError::new(err.message, None)];
+            return Json(response).into_response();
+        }
+        Ok(d) => d,
+    };
+    if read_only && has_mutation(&doc) {
+        let mut response = GQLResponse::default();
+        response.errors = vec![ServerError::new(
+            "Mutations are only allowed over POST",
+            None,
+        )];
+        return (StatusCode::METHOD_NOT_ALLOWED, Json(response)).into_response();
+    }
+    // 2) Static operation override: return matching .json or .jgd file if present
+    if let Some(op_name) = doc
+        .definitions
+        .iter()
+        .filter_map(|def| {
+            if let Definition::Operation(OperationDefinition::Query(q)) = def {
+                q.name.clone()
+            } else if let Definition::Operation(OperationDefinition::Mutation(m)) = def {
+                m.name.clone()
+            } else {
+                None
+            }
+        })
+        .next()
+        && let Some(data_json) = load_static_data(&folder, &op_name)
+    {
+        return response_from_json(data_json).into_response();
+    }
+
+    // 3) Validate referenced collections exist in Fosk database
+    if let Err(err) = validate_request_ast(&doc, &db) {
+        let mut response = GQLResponse::default();
+        response.errors = vec![ServerError::new(err.message, None)];
+        return Json(response).into_response();
+    }
+
+    // Execute GraphQL operations directly on Fosk database
+    let variables = req
+        .variables
+        .clone()
+        .into_value()
+        .into_json()
+        .unwrap_or_default();
+    let relay = relay.then_some(RelayContext {
+        order: &order,
+        variables: &variables,
+        transforms: &transforms,
+    });
+    let result = execute_graphql_operations(&doc, &db, &events, &transforms, relay.as_ref()).await;
+
+    // Return GraphQL response
+    let mut response = GQLResponse::default();
+    match result {
+        Ok((data, mutation_errors)) => {
+            response.data =
+                async_graphql::Value::from_json(data).unwrap_or(async_graphql::Value::Null);
+            if !mutation_errors.is_empty() {
+                response.errors = mutation_errors
+                    .into_iter()
+                    .map(|message| ServerError::new(message, None))
+                    .collect();
+            }
+        }
+        Err(err) => {
+            response.errors = vec![ServerError::new(err, None)];
+        }
+    }
+    Json(response).into_response()
+}
+
+/// Returns whether a document defines a mutation.
+fn has_mutation(doc: &Document<String>) -> bool {
+    doc.definitions
+        .iter()
+        .any(|def| matches!(def, Definition::Operation(OperationDefinition::Mutation(_))))
+}
+
 /// Registers the GraphQL endpoint for dynamic collection queries and mutations.
+///
+/// Queries are also accepted over GET, as `query`, `operationName`,
+/// `variables`, and `extensions` URL parameters.
 pub fn create_graphql_route(
     app: &mut App,
     route: &str,
     path: OsString,
+    schema: Option<Schema>,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
+    relay: bool,
 ) {
-    // Prepare dynamic schema for introspection
-    let db = app.db.clone();
-    // Build and store dynamic schema for GraphiQL introspection
-    // build_dynamic_schema already returns a finished Schema
-    let router = post(move |Json(req): Json<GQLRequest>| {
-        let db = db.clone();
-        async move {
-            // Introspection queries (__schema or __type)
-            let query_str = req.query.clone();
-            if query_str.contains("__schema") || query_str.contains("__type") {
-                // Build a fresh request for introspection and attach DB
-                let int_req = async_graphql::Request::new(query_str).data(db.clone());
-                let dyn_schema = build_dynamic_schema(&db);
-                let resp = dyn_schema.execute(int_req).await;
-                return Json(resp);
-            }
+    let endpoint = GraphQLEndpoint {
+        db: app.db.clone(),
+        folder: path,
+        schema,
+        delay,
+        queries: Arc::clone(&app.persisted_queries),
+        order: Arc::clone(&app.collection_order),
+        events: Arc::clone(&app.change_events),
+        transforms: Arc::clone(&app.response_transforms),
+        relay,
+    };
 
-            delay.sleep_thread();
+    let post_endpoint = endpoint.clone();
+    let router = post(move |Json(req): Json<GQLRequest>| {
+        let endpoint = post_endpoint.clone();
+        async move { execute_request(req, endpoint, false).await }
+    });
+    app.push_route(route, router, Some("POST"), is_protected, None);
 
-            // 1) Parse request into AST
-            let doc = match parse_request_ast(&req) {
+    let router = get(move |RawQuery(query): RawQuery| {
+        let endpoint = endpoint.clone();
+        async move {
+            match parse_query_string(&query.unwrap_or_default()) {
+                Ok(req) => execute_request(req, endpoint, true).await,
                 Err(err) => {
                     let mut response = GQLResponse::default();
-                    response.errors = vec![ServerError::new(err.message, None)];
-                    return Json(response);
+                    response.errors = vec![ServerError::new(err.to_string(), None)];
+                    (StatusCode::BAD_REQUEST, Json(response)).into_response()
                 }
-                Ok(d) => d,
-            };
-            // 2) Static operation override: return matching .json or .jgd file if present
-            if let Some(op_name) = doc
-                .definitions
-                .iter()
-                .filter_map(|def| {
-                    if let Definition::Operation(OperationDefinition::Query(q)) = def {
-                        q.name.clone()
-                    } else if let Definition::Operation(OperationDefinition::Mutation(m)) = def {
-                        m.name.clone()
-                    } else {
-                        None
-                    }
-                })
-                .next()
-                && let Some(data_json) = load_static_data(&path, &op_name)
-            {
-                return response_from_json(data_json);
-            }
-
-            // 3) Validate referenced collections exist in Fosk database
-            if let Err(err) = validate_request_ast(&doc, &db) {
-                let mut response = GQLResponse::default();
-                response.errors = vec![ServerError::new(err.message, None)];
-                return Json(response);
             }
+        }
+    });
+    app.push_route(route, router, Some("GET"), is_protected, None);
+}
 
-            // Execute GraphQL operations directly on Fosk database
-            let result = execute_graphql_operations(&doc, &db).await;
+/// A query registered through the persisted queries endpoint.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedQueryRegistration {
+    query: String,
+    sha256_hash: Option<String>,
+}
 
-            // Return GraphQL response
-            let mut response = GQLResponse::default();
-            match result {
-                Ok((data, mutation_errors)) => {
-                    response.data =
-                        async_graphql::Value::from_json(data).unwrap_or(async_graphql::Value::Null);
-                    if !mutation_errors.is_empty() {
-                        response.errors = mutation_errors
-                            .into_iter()
-                            .map(|message| ServerError::new(message, None))
-                            .collect();
+/// Registers the endpoints that list and register persisted queries.
+pub fn create_persisted_query_routes(app: &mut App) {
+    let route = format!("{}/graphql/persisted-queries", MOCK_SERVER_ROUTE);
+
+    let queries = Arc::clone(&app.persisted_queries);
+    let list_router = get(async move || Json(queries.all()).into_response());
+    app.route(&route, list_router, Some("GET"), None);
+
+    let queries = Arc::clone(&app.persisted_queries);
+    let register_router = post(async move |Json(body): Json<serde_json::Value>| {
+        let registrations = match body {
+            serde_json::Value::Array(items) => items,
+            item => vec![item],
+        };
+        let mut hashes = vec![];
+        for registration in registrations {
+            let registration =
+                match serde_json::from_value::<PersistedQueryRegistration>(registration) {
+                    Ok(registration) => registration,
+                    Err(err) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            "invalid_persisted_query",
+                            err.to_string(),
+                        );
                     }
-                }
-                Err(err) => {
-                    response.errors = vec![ServerError::new(err, None)];
-                }
+                };
+            if let Err(err) = parse_query::<String>(&registration.query) {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_persisted_query",
+                    format!("GraphQL syntax error: {}", err),
+                );
             }
-            Json(response)
+            hashes.push((registration.query, registration.sha256_hash));
         }
+
+        let hashes = hashes
+            .into_iter()
+            .map(|(query, hash)| queries.register(&query, hash.as_deref()))
+            .collect::<Vec<_>>();
+        (StatusCode::CREATED, Json(json!({ "sha256Hashes": hashes }))).into_response()
     });
-    app.push_route(route, router, Some("POST"), is_protected, None);
+    app.route(&route, register_router, Some("POST"), None);
 }
 
 /// Loads JSON and JGD collection seed files from a GraphQL `collections` folder.
@@ -787,7 +1347,7 @@ pub fn load_folder_collections(app: &mut App, path: OsString) -> Result<(), Erro
     path.push(COLLECTIONS_FOLDER);
 
     if !fs::exists(&path)? {
-        println!("Folder Collections doesn't exist for GraphQL routes");
+        app.log("Folder Collections doesn't exist for GraphQL routes");
         return Ok(());
     }
 
@@ -800,38 +1360,39 @@ pub fn load_folder_collections(app: &mut App, path: OsString) -> Result<(), Erro
             let collection = app.db.create(&name);
 
             if is_jgd(&file.file_name()) {
-                match generate_jgd_from_file(
-                    &PathBuf::from_str(file.path().to_str().unwrap()).unwrap(),
-                ) {
+                match random::current().generate_jgd(&file.path()) {
                     Ok(jgd_json) => {
-                        let value = collection.load_from_json(jgd_json, false);
+                        let value = collection.load_from_json(
+                            random::current().fill_ids(&collection, jgd_json),
+                            false,
+                        );
                         match value {
                             Ok(items) => {
-                                println!(
+                                app.log(format!(
                                     "✔️ Loaded collection {} with {} initial items from {}",
                                     name,
                                     items.len(),
                                     binding.to_string_lossy()
-                                );
+                                ));
                             }
-                            Err(error) => println!(
+                            Err(error) => app.log(format!(
                                 "Error to load JSON for file {}. Details: {}",
                                 binding.to_string_lossy(),
                                 error
-                            ),
+                            )),
                         }
                     }
-                    Err(error) => println!(
+                    Err(error) => app.log(format!(
                         "Error to generate JGD JSON for file {}. Details: {}",
                         binding.to_string_lossy(),
                         error
-                    ),
+                    )),
                 }
             } else {
                 let result = collection.load_from_file(&binding.as_os_str().to_os_string());
                 match result {
-                    Ok(value) => println!("{}", value),
-                    Err(error) => println!("{}", error),
+                    Ok(value) => app.log(value),
+                    Err(error) => app.log(error),
                 }
             }
         });
@@ -843,16 +1404,35 @@ pub fn load_folder_collections(app: &mut App, path: OsString) -> Result<(), Erro
 pub fn build_graphql_routes(app: &mut App, config: &RouteGraphQL) {
     let result = load_folder_collections(app, config.path.clone());
     if let Err(error) = result {
-        println!("Error to load GraphQL collections. Details: {}", error);
+        app.log(format!(
+            "Error to load GraphQL collections. Details: {}",
+            error
+        ));
     }
 
+    let schema = match load_schema_file(&config.path) {
+        Some(Ok(schema)) => {
+            app.log(format!("✔️ Loaded GraphQL schema from {}", SCHEMA_FILE));
+            Some(schema)
+        }
+        Some(Err(error)) => {
+            app.log(format!(
+                "⚠️ Ignoring {}, the schema is inferred from collections instead. Details: {}",
+                SCHEMA_FILE, error
+            ));
+            None
+        }
+        None => None,
+    };
+
     let route = &config.route;
     let is_protected = config.is_protected;
     let delay = config.delay;
     let path = config.path.clone();
 
     create_graphiql_route(app);
-    create_graphql_route(app, route, path, is_protected, delay);
+    create_graphql_route(app, route, path, schema, is_protected, delay, config.relay);
+    create_persisted_query_routes(app);
 }
 
 // Unit tests for GraphQL helper functions
@@ -1003,6 +1583,100 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn graphql_where_argument_filters_nested_fields() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let collections = temp_dir.path().join("collections");
+        std::fs::create_dir(&collections).unwrap();
+        std::fs::write(
+            collections.join("Orders.json"),
+            r#"[
+                {"id":"1","status":"open","address":{"city":"Paris"},"items":[{"sku":"AB-1"}]},
+                {"id":"2","status":"open","address":{"city":"Lyon"},"items":[{"sku":"CD-2"}]},
+                {"id":"3","status":"closed","address":{"city":"Paris"},"items":[{"sku":"AB-3"}]}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let config = RouteGraphQL::new(
+            temp_dir.path().as_os_str().to_os_string(),
+            "/graphql".to_string(),
+            false,
+            None,
+        );
+        build_graphql_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+
+        let filtered = router
+            .clone()
+            .oneshot(graphql_request(
+                r#"query { Orders(status: "open", where: "address.city = \"Paris\" OR items[0].sku LIKE \"CD%\"") { id } }"#,
+            ))
+            .await
+            .unwrap();
+        let mut ids = response_json(filtered).await["data"]["Orders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, ["1", "2"]);
+
+        let invalid = router
+            .oneshot(graphql_request(
+                r#"query { Orders(where: "status =") { id } }"#,
+            ))
+            .await
+            .unwrap();
+        assert!(
+            response_json(invalid).await["errors"][0]["message"]
+                .as_str()
+                .unwrap()
+                .contains("expected a value")
+        );
+    }
+
+    #[tokio::test]
+    async fn graphql_queries_select_transformed_fields() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let collections = temp_dir.path().join("collections");
+        std::fs::create_dir(&collections).unwrap();
+        std::fs::write(
+            collections.join("Users.json"),
+            r#"[{"id":"1","firstName":"Ada","card":"4111111111111111"}]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        app.response_transforms.register(
+            "Users",
+            crate::handlers::ResponseTransform {
+                mask: vec!["card".to_string()],
+                rename: HashMap::from([("firstName".to_string(), "first_name".to_string())]),
+                ..Default::default()
+            },
+        );
+        let config = RouteGraphQL::new(
+            temp_dir.path().as_os_str().to_os_string(),
+            "/graphql".to_string(),
+            false,
+            None,
+        );
+        build_graphql_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+
+        let response = router
+            .oneshot(graphql_request("query { Users { first_name card } }"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response_json(response).await["data"]["Users"],
+            json!([{"first_name":"Ada","card":"************1111"}])
+        );
+    }
+
     #[tokio::test]
     async fn graphql_static_operations_and_mutations_are_supported() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -1109,8 +1783,10 @@ mod tests {
             &mut app,
             "/graphql",
             temp_dir.path().as_os_str().to_os_string(),
+            None,
             false,
             None,
+            false,
         );
         let router = app.take_router_for_test();
 
@@ -1168,4 +1844,341 @@ mod tests {
             Value::String("text".to_string())
         );
     }
+
+    fn graphql_get(params: &[(&str, &str)]) -> Request<Body> {
+        let query = params
+            .iter()
+            .map(|(name, value)| {
+                let value = value
+                    .bytes()
+                    .map(|byte| match byte {
+                        b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => (byte as char).to_string(),
+                        _ => format!("%{:02X}", byte),
+                    })
+                    .collect::<String>();
+                format!("{}={}", name, value)
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        Request::builder()
+            .uri(format!("/graphql?{}", query))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn graphql_app() -> (tempfile::TempDir, axum::Router) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let collections = temp_dir.path().join("collections");
+        std::fs::create_dir(&collections).unwrap();
+        std::fs::write(
+            collections.join("Users.json"),
+            r#"[{"id":"1","name":"Ada"}]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let config = RouteGraphQL::new(
+            temp_dir.path().as_os_str().to_os_string(),
+            "/graphql".to_string(),
+            false,
+            None,
+        );
+        build_graphql_routes(&mut app, &config);
+        (temp_dir, app.take_router_for_test())
+    }
+
+    #[tokio::test]
+    async fn graphql_queries_are_accepted_over_get() {
+        let (_temp_dir, router) = graphql_app().await;
+
+        let query = router
+            .clone()
+            .oneshot(graphql_get(&[
+                ("query", "query Named { Users { name } }"),
+                ("operationName", "Named"),
+                ("variables", "{}"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(query.status(), http::StatusCode::OK);
+        assert_eq!(
+            response_json(query).await["data"]["Users"][0]["name"],
+            "Ada"
+        );
+
+        let mutation = router
+            .clone()
+            .oneshot(graphql_get(&[(
+                "query",
+                r#"mutation { createUsers(name: "Bob") { id } }"#,
+            )]))
+            .await
+            .unwrap();
+        assert_eq!(mutation.status(), http::StatusCode::METHOD_NOT_ALLOWED);
+
+        let invalid = router
+            .oneshot(graphql_get(&[
+                ("query", "query { Users { id } }"),
+                ("variables", "{"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn persisted_queries_are_registered_and_resolved_by_hash() {
+        let (_temp_dir, router) = graphql_app().await;
+        let query = "query { Users { name } }";
+        let hash = query_hash(query);
+        let extensions =
+            json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } }).to_string();
+
+        let missing = router
+            .clone()
+            .oneshot(graphql_get(&[("extensions", &extensions)]))
+            .await
+            .unwrap();
+        let missing = response_json(missing).await;
+        assert_eq!(missing["errors"][0]["message"], "PersistedQueryNotFound");
+        assert_eq!(
+            missing["errors"][0]["extensions"]["code"],
+            "PERSISTED_QUERY_NOT_FOUND"
+        );
+
+        let mismatch = router
+            .clone()
+            .oneshot(graphql_get(&[
+                ("query", "query { Users { id } }"),
+                ("extensions", &extensions),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            response_json(mismatch).await["errors"][0]["message"],
+            "provided sha does not match query"
+        );
+
+        for params in [
+            vec![("query", query), ("extensions", extensions.as_str())],
+            vec![("extensions", extensions.as_str())],
+        ] {
+            let response = router.clone().oneshot(graphql_get(&params)).await.unwrap();
+            assert_eq!(
+                response_json(response).await["data"]["Users"][0]["name"],
+                "Ada"
+            );
+        }
+
+        let registered = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/mock-server/graphql/persisted-queries")
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        json!([{ "query": "query { Users { id } }", "sha256Hash": "users-ids" }])
+                            .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(registered.status(), http::StatusCode::CREATED);
+        assert_eq!(
+            response_json(registered).await,
+            json!({ "sha256Hashes": ["users-ids"] })
+        );
+
+        let listed = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/mock-server/graphql/persisted-queries")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response_json(listed).await,
+            json!({ hash.clone(): query, "users-ids": "query { Users { id } }" })
+        );
+
+        let extensions =
+            json!({ "persistedQuery": { "version": 1, "sha256Hash": "users-ids" } }).to_string();
+        let by_id = router
+            .clone()
+            .oneshot(graphql_get(&[("extensions", &extensions)]))
+            .await
+            .unwrap();
+        assert_eq!(response_json(by_id).await["data"]["Users"][0]["id"], "1");
+
+        let invalid = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/mock-server/graphql/persisted-queries")
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(json!({ "query": "{ Users {" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn schema_files_replace_the_inferred_schema() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let collections = temp_dir.path().join("collections");
+        std::fs::create_dir(&collections).unwrap();
+        std::fs::write(
+            collections.join("Users.json"),
+            r#"[{"id":"1","name":"Ada"}]"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join(SCHEMA_FILE),
+            "type Order { id: ID!, status: Status! }\nenum Status { OPEN CLOSED }\n\
+             type User { id: ID!, name: String }\n\
+             type Query { Users: [User!]!, orders: [Order!]! }",
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let config = RouteGraphQL::new(
+            temp_dir.path().as_os_str().to_os_string(),
+            "/graphql".to_string(),
+            false,
+            None,
+        );
+        build_graphql_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+
+        let order = router
+            .clone()
+            .oneshot(graphql_request(
+                r#"{ __type(name: "Order") { fields { name type { kind ofType { name } } } } }"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            response_json(order).await["data"]["__type"]["fields"],
+            json!([
+                { "name": "id", "type": { "kind": "NON_NULL", "ofType": { "name": "ID" } } },
+                { "name": "status", "type": { "kind": "NON_NULL", "ofType": { "name": "Status" } } }
+            ])
+        );
+
+        let users = router
+            .oneshot(graphql_request("query { Users { name } }"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response_json(users).await["data"]["Users"][0]["name"],
+            "Ada"
+        );
+    }
+
+    #[tokio::test]
+    async fn relay_connections_page_through_collections() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let collections = temp_dir.path().join("collections");
+        std::fs::create_dir(&collections).unwrap();
+        std::fs::write(
+            collections.join("Users.json"),
+            r#"[{"id":"1","name":"Ada"},{"id":"2","name":"Bob"},{"id":"3","name":"Cy"},{"id":"4","name":"Di"}]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let mut config = RouteGraphQL::new(
+            temp_dir.path().as_os_str().to_os_string(),
+            "/graphql".to_string(),
+            false,
+            None,
+        );
+        config.relay = true;
+        build_graphql_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+
+        let connection = router
+            .clone()
+            .oneshot(graphql_request(
+                r#"{ __type(name: "UsersConnection") { fields { name } } }"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            response_json(connection).await["data"]["__type"]["fields"],
+            json!([
+                { "name": "edges" },
+                { "name": "nodes" },
+                { "name": "pageInfo" },
+                { "name": "totalCount" }
+            ])
+        );
+
+        let page = |body: Value| {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(
+                        Request::builder()
+                            .method(Method::POST)
+                            .uri("/graphql")
+                            .header(CONTENT_TYPE, "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                response_json(response).await
+            }
+        };
+
+        let first = page(json!({
+            "query": "query Users($count: Int) { Users(first: $count) { totalCount edges { cursor node { name } } pageInfo { hasNextPage endCursor } } }",
+            "variables": { "count": 2 }
+        }))
+        .await;
+        let users = &first["data"]["Users"];
+        assert_eq!(users["totalCount"], 4);
+        assert_eq!(users["edges"][1]["node"], json!({ "name": "Bob" }));
+        assert_eq!(users["edges"][1]["cursor"], cursor(1));
+        assert_eq!(
+            users["pageInfo"],
+            json!({ "hasNextPage": true, "endCursor": cursor(1) })
+        );
+
+        let next = page(json!({
+            "query": format!(
+                r#"query {{ Users(after: "{}", first: 5) {{ nodes {{ name }} pageInfo {{ hasNextPage hasPreviousPage }} }} }}"#,
+                cursor(1)
+            )
+        }))
+        .await;
+        assert_eq!(
+            next["data"]["Users"],
+            json!({
+                "nodes": [{ "name": "Cy" }, { "name": "Di" }],
+                "pageInfo": { "hasNextPage": false, "hasPreviousPage": true }
+            })
+        );
+
+        let last = page(json!({
+            "query": format!(
+                r#"query {{ Users(before: "{}", last: 1, name: "Bob") {{ nodes {{ id }} }} }}"#,
+                cursor(3)
+            )
+        }))
+        .await;
+        assert_eq!(last["data"]["Users"]["nodes"], json!([{ "id": "2" }]));
+
+        let invalid =
+            page(json!({ "query": r#"query { Users(after: "nope") { totalCount } }"# })).await;
+        assert_eq!(invalid["errors"][0]["message"], "invalid cursor 'nope'");
+    }
 }
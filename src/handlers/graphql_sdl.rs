@@ -0,0 +1,434 @@
+//! GraphQL schemas read from a `schema.graphql` file, so introspection shows
+//! the real contract instead of the types inferred from collections.
+
+use std::{ffi::OsString, fs, path::PathBuf};
+
+use async_graphql::{
+    Name, Number, Value as GValue,
+    dynamic::{
+        Enum, EnumItem, Field, FieldFuture, InputObject, InputValue, Interface, InterfaceField,
+        Object, Scalar, Schema, Type, TypeRef, Union,
+    },
+    indexmap::IndexMap,
+};
+use graphql_parser::schema::{
+    Definition, Directive, InputValue as SdlInputValue, Type as SdlType, TypeDefinition,
+    TypeExtension, Value as SdlValue, parse_schema,
+};
+
+/// File of a GraphQL folder whose schema replaces the one inferred from
+/// collections.
+pub const SCHEMA_FILE: &str = "schema.graphql";
+
+const BUILT_IN_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+/// Reads the `schema.graphql` file of a GraphQL folder, when there is one.
+pub fn load_schema_file(folder: &OsString) -> Option<Result<Schema, String>> {
+    let path = PathBuf::from(folder).join(SCHEMA_FILE);
+    if !path.exists() {
+        return None;
+    }
+    Some(
+        fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| build_sdl_schema(&text)),
+    )
+}
+
+/// Builds a schema from GraphQL SDL, keeping its types, nullability, enums,
+/// descriptions, default values, and deprecations.
+///
+/// `extend` definitions are merged into the types they extend. Fields resolve
+/// to `null`, since operations run against the collections and the schema only
+/// answers introspection.
+pub fn build_sdl_schema(text: &str) -> Result<Schema, String> {
+    let document = parse_schema::<String>(text).map_err(|err| err.to_string())?;
+
+    let mut query = None;
+    let mut mutation = None;
+    let mut types = vec![];
+    let mut extensions = vec![];
+    for definition in document.definitions {
+        match definition {
+            Definition::SchemaDefinition(schema) => {
+                query = schema.query;
+                mutation = schema.mutation;
+            }
+            Definition::TypeDefinition(definition) => types.push(definition),
+            Definition::TypeExtension(extension) => extensions.push(extension),
+            Definition::DirectiveDefinition(_) => {}
+        }
+    }
+    for extension in extensions {
+        extend_type(&mut types, extension)?;
+    }
+
+    let type_name = |definition: &TypeDefinition<String>| match definition {
+        TypeDefinition::Scalar(scalar) => scalar.name.clone(),
+        TypeDefinition::Object(object) => object.name.clone(),
+        TypeDefinition::Interface(interface) => interface.name.clone(),
+        TypeDefinition::Union(union) => union.name.clone(),
+        TypeDefinition::Enum(enumeration) => enumeration.name.clone(),
+        TypeDefinition::InputObject(input) => input.name.clone(),
+    };
+    let has_type = |name: &str| types.iter().any(|definition| type_name(definition) == name);
+    let query = query.unwrap_or_else(|| "Query".to_string());
+    let mutation = mutation.or_else(|| has_type("Mutation").then(|| "Mutation".to_string()));
+
+    let mut schema = Schema::build(&query, mutation.as_deref(), None);
+    for definition in types {
+        schema = schema.register(build_type(definition));
+    }
+    schema.finish().map_err(|err| err.to_string())
+}
+
+fn build_type(definition: TypeDefinition<String>) -> Type {
+    match definition {
+        TypeDefinition::Scalar(scalar) if BUILT_IN_SCALARS.contains(&scalar.name.as_str()) => {
+            Type::Scalar(Scalar::new(scalar.name))
+        }
+        TypeDefinition::Scalar(scalar) => {
+            let mut built = Scalar::new(&scalar.name);
+            if let Some(description) = scalar.description {
+                built = built.description(description);
+            }
+            if let Some(url) = directive_argument(&scalar.directives, "specifiedBy", "url") {
+                built = built.specified_by_url(url);
+            }
+            Type::Scalar(built)
+        }
+        TypeDefinition::Object(object) => {
+            let mut built = Object::new(&object.name);
+            if let Some(description) = object.description {
+                built = built.description(description);
+            }
+            for interface in object.implements_interfaces {
+                built = built.implement(interface);
+            }
+            for field in object.fields {
+                let mut output = Field::new(&field.name, type_ref(&field.field_type), |_| {
+                    FieldFuture::from_value(None)
+                });
+                if let Some(reason) = deprecation(&field.directives) {
+                    output = output.deprecation(Some(&reason));
+                }
+                if let Some(description) = field.description {
+                    output = output.description(description);
+                }
+                for argument in &field.arguments {
+                    output = output.argument(input_value(argument));
+                }
+                built = built.field(output);
+            }
+            Type::Object(built)
+        }
+        TypeDefinition::Interface(interface) => {
+            let mut built = Interface::new(&interface.name);
+            if let Some(description) = interface.description {
+                built = built.description(description);
+            }
+            for parent in interface.implements_interfaces {
+                built = built.implement(parent);
+            }
+            for field in interface.fields {
+                let mut output = InterfaceField::new(&field.name, type_ref(&field.field_type));
+                if let Some(reason) = deprecation(&field.directives) {
+                    output = output.deprecation(Some(&reason));
+                }
+                if let Some(description) = field.description {
+                    output = output.description(description);
+                }
+                for argument in &field.arguments {
+                    output = output.argument(input_value(argument));
+                }
+                built = built.field(output);
+            }
+            Type::Interface(built)
+        }
+        TypeDefinition::Union(union) => {
+            let mut built = Union::new(&union.name);
+            if let Some(description) = union.description {
+                built = built.description(description);
+            }
+            for member in union.types {
+                built = built.possible_type(member);
+            }
+            Type::Union(built)
+        }
+        TypeDefinition::Enum(enumeration) => {
+            let mut built = Enum::new(&enumeration.name);
+            if let Some(description) = enumeration.description {
+                built = built.description(description);
+            }
+            for value in enumeration.values {
+                let mut item = EnumItem::new(&value.name);
+                if let Some(reason) = deprecation(&value.directives) {
+                    item = item.deprecation(Some(&reason));
+                }
+                if let Some(description) = value.description {
+                    item = item.description(description);
+                }
+                built = built.item(item);
+            }
+            Type::Enum(built)
+        }
+        TypeDefinition::InputObject(input) => {
+            let mut built = InputObject::new(&input.name);
+            if let Some(description) = input.description {
+                built = built.description(description);
+            }
+            for field in &input.fields {
+                built = built.field(input_value(field));
+            }
+            Type::InputObject(built)
+        }
+    }
+}
+
+/// Adds the fields, values, or members of an `extend` definition to the type
+/// it extends.
+fn extend_type<'a>(
+    types: &mut [TypeDefinition<'a, String>],
+    extension: TypeExtension<'a, String>,
+) -> Result<(), String> {
+    let extended = match extension {
+        TypeExtension::Object(extension) => types.iter_mut().find_map(|definition| {
+            let TypeDefinition::Object(object) = definition else {
+                return None;
+            };
+            (object.name == extension.name).then(|| {
+                object
+                    .implements_interfaces
+                    .extend(extension.implements_interfaces.clone());
+                object.fields.extend(extension.fields.clone());
+            })
+        }),
+        TypeExtension::Interface(extension) => types.iter_mut().find_map(|definition| {
+            let TypeDefinition::Interface(interface) = definition else {
+                return None;
+            };
+            (interface.name == extension.name)
+                .then(|| interface.fields.extend(extension.fields.clone()))
+        }),
+        TypeExtension::Union(extension) => types.iter_mut().find_map(|definition| {
+            let TypeDefinition::Union(union) = definition else {
+                return None;
+            };
+            (union.name == extension.name).then(|| union.types.extend(extension.types.clone()))
+        }),
+        TypeExtension::Enum(extension) => types.iter_mut().find_map(|definition| {
+            let TypeDefinition::Enum(enumeration) = definition else {
+                return None;
+            };
+            (enumeration.name == extension.name)
+                .then(|| enumeration.values.extend(extension.values.clone()))
+        }),
+        TypeExtension::InputObject(extension) => types.iter_mut().find_map(|definition| {
+            let TypeDefinition::InputObject(input) = definition else {
+                return None;
+            };
+            (input.name == extension.name).then(|| input.fields.extend(extension.fields.clone()))
+        }),
+        TypeExtension::Scalar(_) => Some(()),
+    };
+    extended.ok_or_else(|| "`extend` refers to a type that is not defined".to_string())
+}
+
+fn input_value(value: &SdlInputValue<String>) -> InputValue {
+    let mut built = InputValue::new(&value.name, type_ref(&value.value_type));
+    if let Some(description) = &value.description {
+        built = built.description(description);
+    }
+    if let Some(default) = &value.default_value {
+        built = built.default_value(const_value(default));
+    }
+    built
+}
+
+fn type_ref(ty: &SdlType<String>) -> TypeRef {
+    match ty {
+        SdlType::NamedType(name) => TypeRef::Named(name.clone().into()),
+        SdlType::ListType(inner) => TypeRef::List(Box::new(type_ref(inner))),
+        SdlType::NonNullType(inner) => TypeRef::NonNull(Box::new(type_ref(inner))),
+    }
+}
+
+fn const_value(value: &SdlValue<String>) -> GValue {
+    match value {
+        SdlValue::Variable(_) | SdlValue::Null => GValue::Null,
+        SdlValue::Int(number) => number.as_i64().map_or(GValue::Null, GValue::from),
+        SdlValue::Float(number) => Number::from_f64(*number).map_or(GValue::Null, GValue::Number),
+        SdlValue::String(text) => GValue::String(text.clone()),
+        SdlValue::Boolean(flag) => GValue::Boolean(*flag),
+        SdlValue::Enum(name) => GValue::Enum(Name::new(name)),
+        SdlValue::List(items) => GValue::List(items.iter().map(const_value).collect()),
+        SdlValue::Object(fields) => GValue::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (Name::new(name), const_value(value)))
+                .collect::<IndexMap<_, _>>(),
+        ),
+    }
+}
+
+/// Returns the reason of a `@deprecated` directive, which defaults to the
+/// reason the GraphQL spec gives.
+fn deprecation(directives: &[Directive<String>]) -> Option<String> {
+    directives
+        .iter()
+        .any(|directive| directive.name == "deprecated")
+        .then(|| {
+            directive_argument(directives, "deprecated", "reason")
+                .unwrap_or_else(|| "No longer supported".to_string())
+        })
+}
+
+fn directive_argument(
+    directives: &[Directive<String>],
+    name: &str,
+    argument: &str,
+) -> Option<String> {
+    directives
+        .iter()
+        .filter(|directive| directive.name == name)
+        .flat_map(|directive| &directive.arguments)
+        .find_map(|(key, value)| match value {
+            SdlValue::String(text) if key == argument => Some(text.clone()),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    async fn introspect(schema: &Schema, query: &str) -> Value {
+        let response = schema.execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    async fn sdl_schemas_keep_types_nullability_and_enums() {
+        let schema = build_sdl_schema(
+            r#"
+            "A person with an account"
+            type User implements Node {
+                id: ID!
+                name: String
+                role: Role!
+                tags: [String!]!
+                legacy: String @deprecated(reason: "Use name")
+            }
+
+            interface Node { id: ID! }
+
+            enum Role { ADMIN USER GUEST @deprecated }
+
+            input UserFilter { role: Role = USER, limit: Int = 10 }
+
+            scalar DateTime @specifiedBy(url: "https://tools.ietf.org/html/rfc3339")
+
+            type Query {
+                users(filter: UserFilter): [User!]!
+                node(id: ID!): Node
+            }
+
+            extend type Query { me: User }
+            "#,
+        )
+        .unwrap();
+
+        let user = introspect(
+            &schema,
+            r#"{ __type(name: "User") {
+                description
+                interfaces { name }
+                fields(includeDeprecated: true) {
+                    name isDeprecated deprecationReason
+                    type { kind name ofType { kind name ofType { kind name } } }
+                }
+            } }"#,
+        )
+        .await;
+        assert_eq!(user["__type"]["description"], "A person with an account");
+        assert_eq!(user["__type"]["interfaces"], json!([{ "name": "Node" }]));
+        let fields = user["__type"]["fields"].as_array().unwrap();
+        assert_eq!(
+            fields
+                .iter()
+                .map(|field| &field["name"])
+                .collect::<Vec<_>>(),
+            ["id", "name", "role", "tags", "legacy"]
+        );
+        assert_eq!(
+            fields[0]["type"],
+            json!({ "kind": "NON_NULL", "name": null, "ofType": { "kind": "SCALAR", "name": "ID", "ofType": null } })
+        );
+        assert_eq!(fields[1]["type"]["kind"], "SCALAR");
+        assert_eq!(fields[3]["type"]["ofType"]["kind"], "LIST");
+        assert_eq!(fields[4]["deprecationReason"], "Use name");
+
+        let role = introspect(
+            &schema,
+            r#"{ __type(name: "Role") { kind enumValues(includeDeprecated: true) { name isDeprecated } } }"#,
+        )
+        .await;
+        assert_eq!(role["__type"]["kind"], "ENUM");
+        assert_eq!(
+            role["__type"]["enumValues"],
+            json!([
+                { "name": "ADMIN", "isDeprecated": false },
+                { "name": "USER", "isDeprecated": false },
+                { "name": "GUEST", "isDeprecated": true }
+            ])
+        );
+
+        let filter = introspect(
+            &schema,
+            r#"{ __type(name: "UserFilter") { inputFields { name defaultValue } } }"#,
+        )
+        .await;
+        assert_eq!(
+            filter["__type"]["inputFields"],
+            json!([
+                { "name": "role", "defaultValue": "USER" },
+                { "name": "limit", "defaultValue": "10" }
+            ])
+        );
+
+        let query = introspect(
+            &schema,
+            r#"{ __schema { queryType { fields { name } } mutationType { name } } }"#,
+        )
+        .await;
+        assert_eq!(
+            query["__schema"]["queryType"]["fields"],
+            json!([{ "name": "users" }, { "name": "node" }, { "name": "me" }])
+        );
+        assert_eq!(query["__schema"]["mutationType"], Value::Null);
+    }
+
+    #[test]
+    fn invalid_sdl_schemas_are_reported() {
+        assert!(build_sdl_schema("type Query { users: [User!]! ").is_err());
+        assert!(build_sdl_schema("type Query { users: [Missing] }").is_err());
+        assert!(build_sdl_schema("type Query { id: ID } extend type Other { id: ID }").is_err());
+    }
+
+    #[test]
+    fn schema_files_are_only_loaded_when_present() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let folder = temp_dir.path().as_os_str().to_os_string();
+        assert!(load_schema_file(&folder).is_none());
+
+        std::fs::write(
+            temp_dir.path().join(SCHEMA_FILE),
+            "schema { query: Root mutation: Writes }\ntype Root { id: ID }\ntype Writes { ok: Boolean }",
+        )
+        .unwrap();
+        let schema = load_schema_file(&folder).unwrap().unwrap();
+        assert!(schema.sdl().contains("mutation: Writes"));
+    }
+}
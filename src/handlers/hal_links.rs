@@ -0,0 +1,237 @@
+//! HAL `_links` added to REST collection items, so hypermedia clients can
+//! navigate from an item to its collection and to the items it references.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use serde_json::{Map, Value, json};
+
+use crate::handlers::id_string;
+
+/// Field holding the links of an item or listing.
+pub const LINKS_FIELD: &str = "_links";
+
+/// Where a REST collection is served and which fields reference other collections.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionLinks {
+    /// Route listing the collection.
+    pub route: String,
+    /// Field used as the item identifier.
+    pub id_key: String,
+    /// Fields referencing other collections, mapped to the referenced collection name.
+    pub refs: HashMap<String, String>,
+    /// Whether responses of this collection carry links.
+    pub enabled: bool,
+}
+
+/// Registry of REST collection routes keyed by collection name.
+///
+/// Every REST collection is registered, so links to referenced items resolve
+/// even when only the referencing collection has links enabled.
+#[derive(Debug, Default)]
+pub struct HalLinks {
+    collections: RwLock<HashMap<String, CollectionLinks>>,
+}
+
+impl HalLinks {
+    /// Declares the route and references of a collection.
+    pub fn register(&self, collection: &str, links: CollectionLinks) {
+        self.collections
+            .write()
+            .unwrap()
+            .insert(collection.to_string(), links);
+    }
+
+    /// Removes every declared collection.
+    pub fn clear(&self) {
+        self.collections.write().unwrap().clear();
+    }
+
+    /// Returns the declared collections and their links, by collection name.
+    pub fn collections(&self) -> Vec<(String, CollectionLinks)> {
+        let mut collections: Vec<_> = self
+            .collections
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, links)| (name.clone(), links.clone()))
+            .collect();
+        collections.sort_by(|(a, _), (b, _)| a.cmp(b));
+        collections
+    }
+
+    /// Returns true when responses of a collection carry links.
+    pub fn is_enabled(&self, collection: &str) -> bool {
+        self.collections
+            .read()
+            .unwrap()
+            .get(collection)
+            .is_some_and(|links| links.enabled)
+    }
+
+    /// Adds `_links` to an item of a collection with links enabled.
+    pub fn decorate_item(&self, collection: &str, item: Value) -> Value {
+        let collections = self.collections.read().unwrap();
+        match collections.get(collection) {
+            Some(links) if links.enabled => with_item_links(&collections, links, item),
+            _ => item,
+        }
+    }
+
+    /// Builds the `{"data": [...]}` listing of a collection, with links on the
+    /// listing and on every item when they are enabled.
+    pub fn decorate_list(&self, collection: &str, items: Vec<Value>) -> Value {
+        let collections = self.collections.read().unwrap();
+        let Some(links) = collections.get(collection).filter(|links| links.enabled) else {
+            return json!({ "data": items });
+        };
+
+        let items: Vec<Value> = items
+            .into_iter()
+            .map(|item| with_item_links(&collections, links, item))
+            .collect();
+        json!({
+            "data": items,
+            LINKS_FIELD: { "self": { "href": links.route } },
+        })
+    }
+
+    /// Drops `_links` sent back by a client, so they are never stored.
+    pub fn strip(&self, collection: &str, payload: Value) -> Value {
+        match payload {
+            Value::Object(mut item) if self.is_enabled(collection) => {
+                item.remove(LINKS_FIELD);
+                Value::Object(item)
+            }
+            payload => payload,
+        }
+    }
+}
+
+fn with_item_links(
+    collections: &HashMap<String, CollectionLinks>,
+    links: &CollectionLinks,
+    item: Value,
+) -> Value {
+    let Value::Object(mut fields) = item else {
+        return item;
+    };
+
+    let mut item_links = Map::new();
+    if let Some(id) = fields.get(&links.id_key).and_then(id_string) {
+        item_links.insert(
+            "self".to_string(),
+            json!({ "href": format!("{}/{}", links.route, id) }),
+        );
+    }
+    item_links.insert("collection".to_string(), json!({ "href": links.route }));
+
+    let mut refs: Vec<_> = links.refs.iter().collect();
+    refs.sort();
+    for (field, ref_collection) in refs {
+        let Some(id) = fields.get(field).and_then(id_string) else {
+            continue;
+        };
+        let Some(related) = collections.get(ref_collection) else {
+            continue;
+        };
+        item_links.insert(
+            relation_name(field),
+            json!({ "href": format!("{}/{}", related.route, id) }),
+        );
+    }
+
+    fields.insert(LINKS_FIELD.to_string(), Value::Object(item_links));
+    Value::Object(fields)
+}
+
+/// Names the link of a reference field after the field without its id suffix,
+/// so `companyId` and `company_id` both link as `company`.
+fn relation_name(field: &str) -> String {
+    ["_id", "Id"]
+        .iter()
+        .find_map(|suffix| field.strip_suffix(suffix))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(field)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hal_links() -> HalLinks {
+        let links = HalLinks::default();
+        links.register(
+            "orders",
+            CollectionLinks {
+                route: "/api/orders".to_string(),
+                id_key: "id".to_string(),
+                refs: HashMap::from([
+                    ("customerId".to_string(), "customers".to_string()),
+                    ("warehouse_id".to_string(), "warehouses".to_string()),
+                ]),
+                enabled: true,
+            },
+        );
+        links.register(
+            "customers",
+            CollectionLinks {
+                route: "/api/customers".to_string(),
+                id_key: "id".to_string(),
+                refs: HashMap::new(),
+                enabled: false,
+            },
+        );
+        links
+    }
+
+    #[test]
+    fn items_link_to_themselves_their_collection_and_references() {
+        let item = hal_links().decorate_item(
+            "orders",
+            json!({ "id": 7, "customerId": "c1", "warehouse_id": 3 }),
+        );
+
+        assert_eq!(
+            item[LINKS_FIELD],
+            json!({
+                "self": { "href": "/api/orders/7" },
+                "collection": { "href": "/api/orders" },
+                "customer": { "href": "/api/customers/c1" },
+            })
+        );
+    }
+
+    #[test]
+    fn listings_link_to_the_collection() {
+        let listing = hal_links().decorate_list("orders", vec![json!({ "id": 1 })]);
+
+        assert_eq!(listing[LINKS_FIELD]["self"]["href"], "/api/orders");
+        assert_eq!(
+            listing["data"][0][LINKS_FIELD]["self"]["href"],
+            "/api/orders/1"
+        );
+    }
+
+    #[test]
+    fn collections_without_links_are_unchanged() {
+        let links = hal_links();
+
+        assert_eq!(
+            links.decorate_item("customers", json!({ "id": "c1" })),
+            json!({ "id": "c1" })
+        );
+        assert_eq!(
+            links.decorate_list("customers", vec![]),
+            json!({ "data": [] })
+        );
+        assert_eq!(
+            links.strip("customers", json!({ "_links": {} })),
+            json!({ "_links": {} })
+        );
+        assert_eq!(
+            links.strip("orders", json!({ "id": 1, "_links": {} })),
+            json!({ "id": 1 })
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Id generation strategies for REST collections.
+//!
+//! Fosk only generates UUID and integer ids, so a collection that declares an
+//! `id_strategy` is created with `IdType::None` and its ids are assigned here
+//! before an item is added: ULIDs, nanoids, snowflake-like numbers, or
+//! templates such as `usr_{{seq}}`.
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::Utc;
+use rand::Rng;
+use serde_json::Value;
+
+use crate::random;
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const NANOID_ALPHABET: &[u8; 64] =
+    b"useandom-26T198340PX75pxJACKVERYMINDBUSHWOLF_GQZbfghjklqvwyzrict";
+const NANOID_LENGTH: usize = 21;
+/// Snowflake timestamps count milliseconds from 2020-01-01T00:00:00Z.
+const SNOWFLAKE_EPOCH_MS: u64 = 1_577_836_800_000;
+const SNOWFLAKE_SEQUENCE_BITS: u64 = 12;
+const SNOWFLAKE_WORKER_BITS: u64 = 10;
+
+/// Last snowflake millisecond and the sequence used within it.
+static SNOWFLAKE_STATE: Mutex<(u64, u64)> = Mutex::new((0, 0));
+
+/// How ids are generated for items created without one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdStrategy {
+    /// 26-character, time-ordered Crockford base32 id.
+    Ulid,
+    /// 21-character URL-safe random id.
+    Nanoid,
+    /// Time-ordered 64-bit number, written as a string.
+    Snowflake,
+    /// Text where `{{seq}}`, `{{ulid}}`, `{{nanoid}}`, and `{{snowflake}}` are replaced.
+    Template(String),
+}
+
+impl IdStrategy {
+    /// Parses `ulid`, `nanoid`, `snowflake`, or a template containing a placeholder.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "ulid" => Some(Self::Ulid),
+            "nanoid" => Some(Self::Nanoid),
+            "snowflake" => Some(Self::Snowflake),
+            _ if value.contains("{{") => Some(Self::Template(value.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl Display for IdStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Ulid => write!(f, "ulid"),
+            Self::Nanoid => write!(f, "nanoid"),
+            Self::Snowflake => write!(f, "snowflake"),
+            Self::Template(template) => write!(f, "{}", template),
+        }
+    }
+}
+
+/// Assigns ids to a collection's new items using an [`IdStrategy`].
+#[derive(Debug, Clone)]
+pub struct IdGenerator {
+    /// Field receiving the generated id.
+    pub id_key: String,
+    /// Strategy used to generate ids.
+    pub strategy: IdStrategy,
+    sequence: Arc<AtomicU64>,
+}
+
+impl PartialEq for IdGenerator {
+    fn eq(&self, other: &Self) -> bool {
+        self.id_key == other.id_key && self.strategy == other.strategy
+    }
+}
+
+impl IdGenerator {
+    /// Creates a generator whose `{{seq}}` starts at 1.
+    pub fn new(id_key: impl Into<String>, strategy: IdStrategy) -> Self {
+        Self {
+            id_key: id_key.into(),
+            strategy,
+            sequence: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns the next id.
+    pub fn next_id(&self) -> String {
+        match &self.strategy {
+            IdStrategy::Ulid => ulid(),
+            IdStrategy::Nanoid => nanoid(),
+            IdStrategy::Snowflake => snowflake().to_string(),
+            IdStrategy::Template(template) => {
+                let mut id = template.clone();
+                if id.contains("{{seq}}") {
+                    let seq = self.sequence.fetch_add(1, Ordering::SeqCst) + 1;
+                    id = id.replace("{{seq}}", &seq.to_string());
+                }
+                if id.contains("{{ulid}}") {
+                    id = id.replace("{{ulid}}", &ulid());
+                }
+                if id.contains("{{nanoid}}") {
+                    id = id.replace("{{nanoid}}", &nanoid());
+                }
+                if id.contains("{{snowflake}}") {
+                    id = id.replace("{{snowflake}}", &snowflake().to_string());
+                }
+                id
+            }
+        }
+    }
+
+    /// Fills the id of an item that has none, leaving provided ids untouched.
+    pub fn assign(&self, payload: Value) -> Value {
+        let Value::Object(mut item) = payload else {
+            return payload;
+        };
+        match item.get(&self.id_key) {
+            None | Some(Value::Null) => {
+                item.insert(self.id_key.clone(), Value::String(self.next_id()));
+            }
+            Some(id) => self.observe(id),
+        }
+        Value::Object(item)
+    }
+
+    /// Fills missing ids in a list of seed items, or in a single seed item.
+    pub fn assign_all(&self, items: Value) -> Value {
+        match items {
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|item| self.assign(item)).collect())
+            }
+            item => self.assign(item),
+        }
+    }
+
+    /// Moves `{{seq}}` past a provided id that matches the template, so seeded
+    /// items such as `usr_3` are not generated again.
+    fn observe(&self, id: &Value) {
+        let IdStrategy::Template(template) = &self.strategy else {
+            return;
+        };
+        let (Some(id), Some((prefix, suffix))) = (id.as_str(), template.split_once("{{seq}}"))
+        else {
+            return;
+        };
+        if let Some(seq) = id
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .and_then(|seq| seq.parse::<u64>().ok())
+        {
+            self.sequence.fetch_max(seq, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Returns a ULID: a 48-bit millisecond timestamp followed by 80 random bits.
+fn ulid() -> String {
+    let millis = Utc::now().timestamp_millis().max(0) as u128 & ((1 << 48) - 1);
+    let random = random::current().with_rng(|rng| rng.random::<u128>()) & ((1 << 80) - 1);
+    let mut value = (millis << 80) | random;
+    let mut encoded = [0u8; 26];
+    for slot in encoded.iter_mut().rev() {
+        *slot = CROCKFORD[(value & 0x1f) as usize];
+        value >>= 5;
+    }
+    String::from_utf8_lossy(&encoded).into_owned()
+}
+
+/// Returns a 21-character nanoid over the URL-safe alphabet.
+fn nanoid() -> String {
+    random::current().with_rng(|rng| {
+        (0..NANOID_LENGTH)
+            .map(|_| NANOID_ALPHABET[(rng.random::<u8>() & 63) as usize] as char)
+            .collect()
+    })
+}
+
+/// Returns a snowflake-like id: milliseconds since 2020, a zero worker id, and
+/// a per-millisecond sequence.
+fn snowflake() -> u64 {
+    let now = (Utc::now().timestamp_millis().max(0) as u64).saturating_sub(SNOWFLAKE_EPOCH_MS);
+    let mut state = SNOWFLAKE_STATE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let (last, sequence) = *state;
+    *state = if now > last {
+        (now, 0)
+    } else if sequence + 1 < 1 << SNOWFLAKE_SEQUENCE_BITS {
+        (last, sequence + 1)
+    } else {
+        // The sequence is exhausted; borrow the next millisecond.
+        (last + 1, 0)
+    };
+    (state.0 << (SNOWFLAKE_WORKER_BITS + SNOWFLAKE_SEQUENCE_BITS)) | state.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_named_strategies_and_templates() {
+        assert_eq!(IdStrategy::parse("ULID"), Some(IdStrategy::Ulid));
+        assert_eq!(IdStrategy::parse("nanoid"), Some(IdStrategy::Nanoid));
+        assert_eq!(IdStrategy::parse("snowflake"), Some(IdStrategy::Snowflake));
+        assert_eq!(
+            IdStrategy::parse("usr_{{seq}}"),
+            Some(IdStrategy::Template("usr_{{seq}}".to_string()))
+        );
+        assert_eq!(IdStrategy::parse("uuid"), None);
+    }
+
+    #[test]
+    fn generates_ids_in_each_format() {
+        let ulid = IdGenerator::new("id", IdStrategy::Ulid).next_id();
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.bytes().all(|byte| CROCKFORD.contains(&byte)));
+
+        let nanoid = IdGenerator::new("id", IdStrategy::Nanoid).next_id();
+        assert_eq!(nanoid.len(), NANOID_LENGTH);
+        assert!(nanoid.bytes().all(|byte| NANOID_ALPHABET.contains(&byte)));
+
+        let snowflake = IdGenerator::new("id", IdStrategy::Snowflake);
+        let first: u64 = snowflake.next_id().parse().unwrap();
+        let second: u64 = snowflake.next_id().parse().unwrap();
+        assert!(second > first);
+    }
+
+    #[test]
+    fn template_ids_count_up_and_skip_seeded_ids() {
+        let generator = IdGenerator::new("id", IdStrategy::Template("usr_{{seq}}".to_string()));
+        let seeded = generator.assign_all(json!([{ "id": "usr_7" }, { "name": "Ada" }]));
+
+        assert_eq!(seeded[0]["id"], "usr_7");
+        assert_eq!(seeded[1]["id"], "usr_8");
+        assert_eq!(generator.assign(json!({ "id": null }))["id"], "usr_9");
+        assert_eq!(generator.assign(json!({ "id": "custom" }))["id"], "custom");
+    }
+}
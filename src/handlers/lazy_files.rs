@@ -0,0 +1,101 @@
+//! Basic route files read at request time, so editing one is served on the
+//! next request without restarting the server.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::SystemTime,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct CachedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    content: String,
+}
+
+/// Files served by basic routes, with their content cached by modification time.
+///
+/// The watcher leaves changes to these files to the handlers instead of
+/// restarting the server.
+#[derive(Debug, Default)]
+pub struct LazyFiles {
+    files: RwLock<HashMap<PathBuf, Option<CachedFile>>>,
+}
+
+impl LazyFiles {
+    /// Declares a file read by its handler on every request.
+    pub fn register(&self, path: &Path) {
+        self.files
+            .write()
+            .unwrap()
+            .entry(canonical(path))
+            .or_default();
+    }
+
+    /// Returns true when changes to `path` are picked up by its handler.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.read().unwrap().contains_key(&canonical(path))
+    }
+
+    /// Removes every declared file.
+    pub fn clear(&self) {
+        self.files.write().unwrap().clear();
+    }
+
+    /// Reads a file, reusing the last content while its modification time
+    /// and size are unchanged.
+    pub fn read(&self, path: &Path) -> io::Result<String> {
+        let key = canonical(path);
+        let metadata = fs::metadata(&key)?;
+        let modified = metadata.modified().ok();
+        let len = metadata.len();
+        if let Some(Some(cached)) = self.files.read().unwrap().get(&key)
+            && cached.modified == modified
+            && cached.len == len
+        {
+            return Ok(cached.content.clone());
+        }
+
+        let content = fs::read_to_string(&key)?;
+        self.files.write().unwrap().insert(
+            key,
+            Some(CachedFile {
+                modified,
+                len,
+                content: content.clone(),
+            }),
+        );
+        Ok(content)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_are_cached_until_the_file_changes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("get.json");
+        fs::write(&path, r#"{"v":1}"#).unwrap();
+
+        let files = LazyFiles::default();
+        files.register(&path);
+        assert!(files.contains(&temp_dir.path().join(".").join("get.json")));
+        assert!(!files.contains(&temp_dir.path().join("post.json")));
+
+        assert_eq!(files.read(&path).unwrap(), r#"{"v":1}"#);
+        fs::write(&path, r#"{"v":22}"#).unwrap();
+        assert_eq!(files.read(&path).unwrap(), r#"{"v":22}"#);
+
+        fs::remove_file(&path).unwrap();
+        assert!(files.read(&path).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    pin::Pin,
+    sync::Arc,
+};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderValue, Method, StatusCode, Uri, header::ALLOW};
+
+use crate::{app::RegisteredRoute, route_builder::config::ServerConfig};
+
+/// HTTP methods registered on each route path.
+#[derive(Debug, Default, Clone)]
+pub struct AllowedMethods {
+    methods: HashMap<String, BTreeSet<String>>,
+}
+
+impl AllowedMethods {
+    /// Groups registered routes by path.
+    pub fn from_routes(routes: &[RegisteredRoute]) -> Self {
+        let mut methods = HashMap::<String, BTreeSet<String>>::new();
+        for route in routes {
+            methods
+                .entry(route.path.clone())
+                .or_default()
+                .insert(route.method.to_uppercase());
+        }
+        Self { methods }
+    }
+
+    /// Returns true when `method` has a route registered on `path`.
+    pub fn contains(&self, path: &str, method: &str) -> bool {
+        self.methods
+            .get(path)
+            .is_some_and(|methods| methods.contains(method))
+    }
+
+    /// Returns the `Allow` header value of `path`, including the `HEAD` answered
+    /// by `GET` routes and the automatic `OPTIONS`.
+    pub fn allow(&self, path: &str) -> Option<String> {
+        Some(allow_header(with_implicit_methods(
+            self.methods.get(path)?.clone(),
+        )))
+    }
+
+    /// Returns the `405 Method Not Allowed` response for a request that no
+    /// route answered, when its path matches a route registered for other
+    /// methods. A trailing slash is ignored.
+    pub fn method_not_allowed(&self, method: &Method, uri_path: &str) -> Option<Response> {
+        let methods = self
+            .methods
+            .iter()
+            .filter(|(path, _)| path_matches(path, uri_path))
+            .flat_map(|(_, methods)| methods.iter().cloned())
+            .collect::<BTreeSet<_>>();
+        if methods.is_empty() {
+            return None;
+        }
+
+        let methods = with_implicit_methods(methods);
+        if methods.contains(method.as_str()) {
+            return None;
+        }
+        Some(method_not_allowed_response(&allow_header(methods)))
+    }
+
+    /// Returns the registered route path a request reaches once its trailing
+    /// slash or the case of its fixed segments is ignored, as `matching` allows.
+    ///
+    /// Returns `None` when the request path needs no change or reaches no route.
+    /// Static segments win over parameters when several routes match.
+    pub fn normalized_path(&self, uri_path: &str, matching: PathMatching) -> Option<String> {
+        if uri_path.len() > 1 && uri_path.ends_with('/') && !matching.trailing_slash {
+            return None;
+        }
+
+        self.methods
+            .keys()
+            .filter_map(|route| match_route(route, uri_path, matching.case_insensitive))
+            .min_by(|left, right| left.cmp(right))
+            .map(|(_, path)| path)
+            .filter(|path| path != uri_path)
+    }
+}
+
+/// Request path leniency applied to requests no route matches.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PathMatching {
+    /// Ignores a trailing slash, so `/users/` reaches `/users`.
+    pub trailing_slash: bool,
+    /// Ignores the case of fixed segments, so `/Users` reaches `/users`.
+    pub case_insensitive: bool,
+}
+
+impl PathMatching {
+    /// Reads `normalize_trailing_slash` and `case_insensitive_paths` from the server config.
+    pub fn from_config(server: &ServerConfig) -> Self {
+        Self {
+            trailing_slash: server.normalize_trailing_slash.unwrap_or(false),
+            case_insensitive: server.case_insensitive_paths.unwrap_or(false),
+        }
+    }
+
+    /// Returns true when any leniency is enabled.
+    pub fn is_active(&self) -> bool {
+        self.trailing_slash || self.case_insensitive
+    }
+}
+
+fn with_implicit_methods(mut methods: BTreeSet<String>) -> BTreeSet<String> {
+    if methods.contains("GET") {
+        methods.insert("HEAD".to_string());
+    }
+    methods.insert("OPTIONS".to_string());
+    methods
+}
+
+fn allow_header(methods: BTreeSet<String>) -> String {
+    methods.into_iter().collect::<Vec<_>>().join(", ")
+}
+
+fn method_not_allowed_response(allow: &str) -> Response {
+    match HeaderValue::from_str(allow) {
+        Ok(allow) => (StatusCode::METHOD_NOT_ALLOWED, [(ALLOW, allow)]).into_response(),
+        Err(_) => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
+
+/// Matches a request path against a route path with `{param}` and `{*rest}` segments.
+fn path_matches(route: &str, uri_path: &str) -> bool {
+    match_route(route, uri_path, false).is_some()
+}
+
+/// Matches a request path against a route path, returning the number of
+/// parameters and the request path with fixed segments spelled as in the route.
+fn match_route(route: &str, uri_path: &str, case_insensitive: bool) -> Option<(usize, String)> {
+    let route = route
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let uri_path = uri_path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut params = 0;
+    let mut matched = vec![];
+    for (index, segment) in route.iter().enumerate() {
+        if segment.starts_with("{*") {
+            if index >= uri_path.len() {
+                return None;
+            }
+            matched.extend_from_slice(&uri_path[index..]);
+            return Some((params + 1, format!("/{}", matched.join("/"))));
+        }
+
+        let value = uri_path.get(index)?;
+        if segment.starts_with('{') && segment.ends_with('}') {
+            params += 1;
+            matched.push(*value);
+        } else if segment == value || (case_insensitive && segment.eq_ignore_ascii_case(value)) {
+            matched.push(*segment);
+        } else {
+            return None;
+        }
+    }
+
+    (route.len() == uri_path.len()).then(|| (params, format!("/{}", matched.join("/"))))
+}
+
+/// Replaces the path of `uri`, keeping its query string.
+pub fn with_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+type MethodMiddlewareReturn = Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware that answers `OPTIONS` requests with the `Allow` list of
+/// the matched path, unless the path has its own `OPTIONS` route, and adds the
+/// same list to `405 Method Not Allowed` responses that lack it.
+///
+/// CORS preflight requests are left to the CORS layer; their response only
+/// receives the `Allow` header. `HEAD` requests are answered by the `GET`
+/// route of the path, without a body.
+pub fn make_method_middleware(
+    allowed: &Arc<AllowedMethods>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> MethodMiddlewareReturn {
+    let allowed = Arc::clone(allowed);
+    move |req: Request, next: Next| {
+        let allowed = Arc::clone(&allowed);
+        Box::pin(async move {
+            let Some(path) = req
+                .extensions()
+                .get::<MatchedPath>()
+                .map(|path| path.as_str().to_string())
+            else {
+                return next.run(req).await;
+            };
+            let Some(allow) = allowed
+                .allow(&path)
+                .and_then(|allow| HeaderValue::from_str(&allow).ok())
+            else {
+                return next.run(req).await;
+            };
+            if req.method() == Method::OPTIONS
+                && !allowed.contains(&path, "OPTIONS")
+                && !is_cors_preflight(&req)
+            {
+                return (StatusCode::NO_CONTENT, [(ALLOW, allow)]).into_response();
+            }
+
+            let is_options = req.method() == Method::OPTIONS;
+            let mut response = next.run(req).await;
+            if is_options || response.status() == StatusCode::METHOD_NOT_ALLOWED {
+                response.headers_mut().entry(ALLOW).or_insert(allow);
+            }
+            response
+        })
+    }
+}
+
+fn is_cors_preflight(req: &Request) -> bool {
+    req.headers().contains_key("access-control-request-method")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(method: &str, path: &str) -> RegisteredRoute {
+        RegisteredRoute {
+            method: method.to_string(),
+            path: path.to_string(),
+            is_protected: false,
+        }
+    }
+
+    #[test]
+    fn allow_lists_registered_methods_with_head_and_options() {
+        let allowed = AllowedMethods::from_routes(&[
+            registered("GET", "/users"),
+            registered("post", "/users"),
+            registered("PUT", "/users/{id}"),
+        ]);
+
+        assert_eq!(
+            allowed.allow("/users").as_deref(),
+            Some("GET, HEAD, OPTIONS, POST")
+        );
+        assert_eq!(
+            allowed.allow("/users/{id}").as_deref(),
+            Some("OPTIONS, PUT")
+        );
+        assert_eq!(allowed.allow("/missing"), None);
+        assert!(allowed.contains("/users", "POST"));
+        assert!(!allowed.contains("/users", "HEAD"));
+    }
+
+    #[test]
+    fn normalized_path_ignores_trailing_slash_and_case_when_enabled() {
+        let allowed = AllowedMethods::from_routes(&[
+            registered("GET", "/users"),
+            registered("GET", "/users/{id}"),
+            registered("GET", "/users/me"),
+            registered("GET", "/files/{*path}"),
+        ]);
+        let lenient = PathMatching {
+            trailing_slash: true,
+            case_insensitive: true,
+        };
+
+        assert_eq!(
+            allowed.normalized_path("/users/", PathMatching::default()),
+            None
+        );
+        assert_eq!(
+            allowed.normalized_path(
+                "/users/",
+                PathMatching {
+                    trailing_slash: true,
+                    ..Default::default()
+                }
+            ),
+            Some("/users".to_string())
+        );
+        assert_eq!(
+            allowed.normalized_path(
+                "/Users/",
+                PathMatching {
+                    trailing_slash: true,
+                    ..Default::default()
+                }
+            ),
+            None
+        );
+        assert_eq!(
+            allowed.normalized_path("/Users/AbC/", lenient),
+            Some("/users/AbC".to_string())
+        );
+        assert_eq!(
+            allowed.normalized_path("/USERS/ME", lenient),
+            Some("/users/me".to_string())
+        );
+        assert_eq!(
+            allowed.normalized_path("/Files/A/B.txt", lenient),
+            Some("/files/A/B.txt".to_string())
+        );
+        assert_eq!(allowed.normalized_path("/users", lenient), None);
+        assert_eq!(allowed.normalized_path("/orders/", lenient), None);
+    }
+
+    #[test]
+    fn with_path_keeps_the_query_string() {
+        let uri = "http://localhost/Users/?page=2".parse::<Uri>().unwrap();
+        assert_eq!(
+            with_path(&uri, "/users").unwrap().to_string(),
+            "http://localhost/users?page=2"
+        );
+    }
+
+    #[test]
+    fn method_not_allowed_matches_route_parameters() {
+        let allowed = AllowedMethods::from_routes(&[
+            registered("GET", "/users/{id}"),
+            registered("PUT", "/users/{id}"),
+            registered("GET", "/files/{*path}"),
+        ]);
+
+        let response = allowed
+            .method_not_allowed(&Method::DELETE, "/users/1/")
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "GET, HEAD, OPTIONS, PUT");
+        assert!(
+            allowed
+                .method_not_allowed(&Method::POST, "/files/a/b.txt")
+                .is_some()
+        );
+
+        assert!(
+            allowed
+                .method_not_allowed(&Method::GET, "/users/1/")
+                .is_none()
+        );
+        assert!(
+            allowed
+                .method_not_allowed(&Method::DELETE, "/users")
+                .is_none()
+        );
+        assert!(
+            allowed
+                .method_not_allowed(&Method::DELETE, "/users/1/posts")
+                .is_none()
+        );
+    }
+}
@@ -12,10 +12,18 @@ pub use rest_handlers::*;
 pub mod upload_handlers;
 pub use upload_handlers::*;
 
+/// S3-compatible object storage handlers.
+pub mod s3_handlers;
+pub use s3_handlers::*;
+
 /// Authentication handlers and middleware.
 pub mod auth_handlers;
 pub use auth_handlers::*;
 
+/// Second login step with static or TOTP codes.
+pub mod auth_mfa;
+pub use auth_mfa::*;
+
 /// Internal collection inspection handlers.
 pub mod collections_handlers;
 pub use collections_handlers::*;
@@ -28,6 +36,147 @@ pub use schema_handlers::*;
 pub mod graphql_handlers;
 pub use graphql_handlers::*;
 
+/// GraphQL schemas read from `schema.graphql` files.
+pub mod graphql_sdl;
+pub use graphql_sdl::*;
+
+/// Referential integrity checks between REST collections.
+pub mod reference_constraints;
+pub use reference_constraints::*;
+
+/// Default and computed fields for REST collections.
+pub mod field_defaults;
+pub use field_defaults::*;
+
+/// ULID, nanoid, snowflake, and templated ids for REST collections.
+pub mod id_strategies;
+pub use id_strategies::*;
+
+/// REST collections that clients can read but not write.
+pub mod read_only;
+pub use read_only::*;
+
+/// HAL `_links` on REST collection items.
+pub mod hal_links;
+pub use hal_links::*;
+
+/// Field renames, enum mappings, and masking on REST and GraphQL responses.
+pub mod response_transforms;
+pub use response_transforms::*;
+
+/// Deterministic anonymization of personal data in proxy recordings.
+pub mod anonymize;
+pub use anonymize::*;
+
+/// Stable insertion order for REST collection listings.
+pub mod collection_order;
+pub use collection_order::*;
+
+/// Count, sum, and group-by aggregations over REST collection items.
+pub mod aggregation;
+pub use aggregation::*;
+
+/// JSON and CSV imports into collections.
+pub mod collection_import;
+pub use collection_import::*;
+
+/// Response sequences served on successive calls.
+pub mod sequence_handlers;
+pub use sequence_handlers::*;
+
+/// Cached responses of basic routes with a `cache_ttl`.
+pub mod cache_handlers;
+pub use cache_handlers::*;
+
+/// Basic route files read at request time instead of restarting on change.
+pub mod lazy_files;
+pub use lazy_files::*;
+
+/// Echo routes answering with the received request.
+pub mod echo_handlers;
+pub use echo_handlers::*;
+
+/// Routes answered by Rhai scripts.
+pub mod script_handlers;
+pub use script_handlers::*;
+
+/// Internal calls to other routes made after a route answers.
+pub mod after_call_handlers;
+pub use after_call_handlers::*;
+
+/// Cookies read from requests and set on responses.
+pub mod cookie_handlers;
+pub use cookie_handlers::*;
+
+/// Hanging routes and broken responses for client robustness tests.
+pub mod fault_handlers;
+pub use fault_handlers::*;
+
+/// Per-tenant and per-session copies of the in-memory database.
+pub mod partitions;
+pub use partitions::*;
+
+/// Named snapshots of the in-memory state.
+pub mod state_handlers;
+pub use state_handlers::*;
+
+/// Transactions over the collections of a partition, and the batch endpoint.
+pub mod transactions;
+pub use transactions::*;
+
+/// Events published for every write to a collection item.
+pub mod change_events;
+pub use change_events::*;
+
+/// Journal of the answered requests and its live tail at `/__requests/live`.
+pub mod request_journal;
+pub use request_journal::*;
+
+/// Log of the change events, listed by `/__audit`.
+pub mod audit_log;
+pub use audit_log::*;
+
+/// Content type sniffing and the per-route content type and charset.
+pub mod content_types;
+pub use content_types::*;
+
+/// Static files of `public` folders.
+pub mod public_handlers;
+pub use public_handlers::*;
+
+/// Request quotas counted per client token or IP address, listed by `/__quota`.
+pub mod quota;
+pub use quota::*;
+
+/// CSRF token issuance and validation for folders with `csrf = true`.
+pub mod csrf_handlers;
+pub use csrf_handlers::*;
+
+/// Mock SAML identity provider of the `[saml]` table.
+pub mod saml_handlers;
+pub use saml_handlers::*;
+
+/// OpenAPI document of the mocked routes and the Swagger UI at `/__docs`.
+pub mod api_docs;
+pub use api_docs::*;
+
+/// Unmatched requests forwarded to real backends by `[proxy]` rules.
+pub mod proxy_handlers;
+pub use proxy_handlers::*;
+
+/// Simulated network partitions between the server and single clients.
+pub mod network_partitions;
+pub use network_partitions::*;
+
+/// Incoming requests compared with golden requests recorded by the proxy.
+pub mod golden_diff;
+pub use golden_diff::*;
+
+/// Automatic `OPTIONS` and `405 Method Not Allowed` answers built from the
+/// methods registered per path.
+pub mod method_handlers;
+pub use method_handlers::*;
+
 /// Shared handler utilities.
 pub mod utils;
 pub use utils::*;
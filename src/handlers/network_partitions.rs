@@ -0,0 +1,411 @@
+//! Simulated network partitions between the server and single clients, so a
+//! multi-client test can cut one consumer off while the others continue.
+//!
+//! `POST /mock-server/network` adds a rule matching the requests of a client
+//! IP address, of a header, or of a header value, and `DELETE` heals it. The
+//! matching requests are held open (`blackhole`), answered with an error
+//! status (`error`), or have their connection dropped (`reset`).
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{ConnectInfo, FromRequestParts, Path as AxumPath, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::watch;
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE},
+    handlers::{drop_connection, error_response},
+};
+
+/// What happens to the requests of a partitioned client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionAction {
+    /// Hold the request open until the rule is removed, then drop the
+    /// connection without answering.
+    #[default]
+    Blackhole,
+    /// Answer with `status`, `503 Service Unavailable` by default.
+    Error,
+    /// Drop the connection at once.
+    Reset,
+}
+
+/// A client cut off from the server.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PartitionRule {
+    /// Assigned when the rule is added.
+    #[serde(default)]
+    pub id: u64,
+    /// Client IP address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpAddr>,
+    /// Header the client sends, such as `x-client-id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    /// Value of `header`; any value by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub action: PartitionAction,
+    /// Status of the `error` action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl PartitionRule {
+    fn validate(&self) -> Result<(), String> {
+        if self.ip.is_none() && self.header.is_none() {
+            return Err("A partition rule needs an ip or a header".to_string());
+        }
+        if let Some(header) = &self.header
+            && http::HeaderName::from_bytes(header.as_bytes()).is_err()
+        {
+            return Err(format!("'{}' is not a header name", header));
+        }
+        if let Some(status) = self.status
+            && StatusCode::from_u16(status).is_err()
+        {
+            return Err(format!("{} is not a status code", status));
+        }
+        Ok(())
+    }
+
+    fn matches(&self, client: Option<IpAddr>, headers: &HeaderMap) -> bool {
+        let ip_matches = self
+            .ip
+            .is_none_or(|ip| client.is_some_and(|client| client == ip.to_canonical()));
+        let header_matches = self.header.as_deref().is_none_or(|name| {
+            headers.get_all(name).iter().any(|value| {
+                self.value
+                    .as_deref()
+                    .is_none_or(|expected| value.to_str().is_ok_and(|value| value == expected))
+            })
+        });
+        ip_matches && header_matches
+    }
+}
+
+/// The partition rules in place.
+pub struct NetworkPartitions {
+    rules: Mutex<Vec<PartitionRule>>,
+    next_id: AtomicU64,
+    /// Bumped whenever rules are removed, waking blackholed requests.
+    healed: watch::Sender<u64>,
+}
+
+impl Default for NetworkPartitions {
+    fn default() -> Self {
+        Self {
+            rules: Mutex::default(),
+            next_id: AtomicU64::new(1),
+            healed: watch::Sender::new(0),
+        }
+    }
+}
+
+impl NetworkPartitions {
+    /// Adds a rule and returns it with its id.
+    pub fn add(&self, mut rule: PartitionRule) -> Result<PartitionRule, String> {
+        rule.validate()?;
+        rule.id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        rule.ip = rule.ip.map(|ip| ip.to_canonical());
+        self.rules.lock().unwrap().push(rule.clone());
+        Ok(rule)
+    }
+
+    /// Returns the rules, oldest first.
+    pub fn rules(&self) -> Vec<PartitionRule> {
+        self.rules.lock().unwrap().clone()
+    }
+
+    /// Removes a rule, returning whether it existed.
+    pub fn remove(&self, id: u64) -> bool {
+        let removed = {
+            let mut rules = self.rules.lock().unwrap();
+            let count = rules.len();
+            rules.retain(|rule| rule.id != id);
+            rules.len() != count
+        };
+        if removed {
+            self.healed.send_modify(|generation| *generation += 1);
+        }
+        removed
+    }
+
+    /// Removes every rule and returns how many there were.
+    pub fn clear(&self) -> usize {
+        let cleared = std::mem::take(&mut *self.rules.lock().unwrap()).len();
+        self.healed.send_modify(|generation| *generation += 1);
+        cleared
+    }
+
+    fn find(&self, client: Option<IpAddr>, headers: &HeaderMap) -> Option<PartitionRule> {
+        self.rules
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|rule| rule.matches(client, headers))
+            .cloned()
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.rules.lock().unwrap().iter().any(|rule| rule.id == id)
+    }
+
+    /// Waits until the rule `id` is removed.
+    async fn healing(&self, id: u64) {
+        let mut healed = self.healed.subscribe();
+        while self.contains(id) {
+            if healed.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+type NetworkPartitionMiddlewareReturn =
+    Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware applying the partition rules to every request, except
+/// those of the `/mock-server` and internal `/__` routes, so a partitioned
+/// client can still heal itself.
+pub fn make_network_partition_middleware(
+    partitions: &Arc<NetworkPartitions>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> NetworkPartitionMiddlewareReturn {
+    let partitions = Arc::clone(partitions);
+    move |req: Request, next: Next| {
+        let partitions = Arc::clone(&partitions);
+        Box::pin(async move {
+            let path = req.uri().path();
+            if path.starts_with("/__")
+                || path == MOCK_SERVER_ROUTE
+                || path.starts_with(&format!("{}/", MOCK_SERVER_ROUTE))
+            {
+                return next.run(req).await;
+            }
+
+            let (mut parts, body) = req.into_parts();
+            let client = ConnectInfo::<SocketAddr>::from_request_parts(&mut parts, &())
+                .await
+                .ok()
+                .map(|ConnectInfo(address)| address.ip().to_canonical());
+            let Some(rule) = partitions.find(client, &parts.headers) else {
+                return next.run(Request::from_parts(parts, body)).await;
+            };
+            match rule.action {
+                PartitionAction::Blackhole => {
+                    partitions.healing(rule.id).await;
+                    drop_connection()
+                }
+                PartitionAction::Error => error_response(
+                    rule.status
+                        .and_then(|status| StatusCode::from_u16(status).ok())
+                        .unwrap_or(StatusCode::SERVICE_UNAVAILABLE),
+                    "network_partition",
+                    format!("Partition rule {} cuts this client off", rule.id),
+                ),
+                PartitionAction::Reset => drop_connection(),
+            }
+        })
+    }
+}
+
+/// Registers `GET`, `POST`, and `DELETE /mock-server/network`, which list,
+/// add, and remove every partition rule, and
+/// `DELETE /mock-server/network/{id}`, which heals one.
+pub fn create_network_partition_routes(app: &mut App) {
+    let route = format!("{}/network", MOCK_SERVER_ROUTE);
+
+    let partitions = Arc::clone(&app.network_partitions);
+    let list_router = get(async move || Json(partitions.rules()).into_response());
+    app.route(&route, list_router, Some("GET"), None);
+
+    let partitions = Arc::clone(&app.network_partitions);
+    let add_router = axum::routing::post(async move |body: Bytes| {
+        let rule = serde_json::from_slice::<PartitionRule>(&body)
+            .map_err(|err| err.to_string())
+            .and_then(|rule| partitions.add(rule));
+        match rule {
+            Ok(rule) => (StatusCode::CREATED, Json(rule)).into_response(),
+            Err(err) => error_response(StatusCode::BAD_REQUEST, "invalid_partition_rule", err),
+        }
+    });
+    app.route(&route, add_router, Some("POST"), None);
+
+    let partitions = Arc::clone(&app.network_partitions);
+    let clear_router =
+        delete(async move || Json(json!({ "cleared": partitions.clear() })).into_response());
+    app.route(&route, clear_router, Some("DELETE"), None);
+
+    let partitions = Arc::clone(&app.network_partitions);
+    let remove_router = delete(async move |AxumPath(id): AxumPath<u64>| {
+        if partitions.remove(id) {
+            StatusCode::NO_CONTENT.into_response()
+        } else {
+            error_response(
+                StatusCode::NOT_FOUND,
+                "partition_rule_not_found",
+                format!("No partition rule {}", id),
+            )
+        }
+    });
+    app.route(
+        &format!("{}/{{id}}", route),
+        remove_router,
+        Some("DELETE"),
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, to_bytes},
+        extract::connect_info::MockConnectInfo,
+        middleware,
+    };
+    use serde_json::Value;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn router(app: &mut App, client: &str) -> axum::Router {
+        app.route("/users", get(|| async { "users" }), Some("GET"), None);
+        create_network_partition_routes(app);
+        let partitions = Arc::clone(&app.network_partitions);
+        app.take_router_for_test()
+            .layer(middleware::from_fn(make_network_partition_middleware(
+                &partitions,
+            )))
+            .layer(MockConnectInfo(client.parse::<SocketAddr>().unwrap()))
+    }
+
+    async fn call(router: &axum::Router, request: Request) -> (StatusCode, Value) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn add(rule: Value) -> Request {
+        Request::post("/mock-server/network")
+            .body(Body::from(rule.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn partitioned_clients_get_errors_until_healed() {
+        let mut app = App::default();
+        let router = router(&mut app, "10.0.0.7:5000");
+
+        let (status, rule) = call(
+            &router,
+            add(json!({ "header": "x-client", "value": "mobile", "action": "error", "status": 504 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            rule,
+            json!({ "id": 1, "header": "x-client", "value": "mobile", "action": "error", "status": 504 })
+        );
+
+        let users = |client: &str| {
+            Request::get("/users")
+                .header("x-client", client)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let (status, body) = call(&router, users("mobile")).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["error"], "network_partition");
+        assert_eq!(call(&router, users("web")).await.0, StatusCode::OK);
+
+        let (status, _) = call(&router, add(json!({ "ip": "10.0.0.7", "action": "error" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            call(&router, users("web")).await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let heal = Request::delete("/mock-server/network/2")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(call(&router, heal).await.0, StatusCode::NO_CONTENT);
+        assert_eq!(call(&router, users("web")).await.0, StatusCode::OK);
+
+        let clear = Request::delete("/mock-server/network")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(call(&router, clear).await.1, json!({ "cleared": 1 }));
+        assert_eq!(call(&router, users("mobile")).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn invalid_rules_are_rejected() {
+        let mut app = App::default();
+        let router = router(&mut app, "127.0.0.1:5000");
+
+        for rule in [
+            json!({ "action": "error" }),
+            json!({ "ip": "not an ip" }),
+            json!({ "header": "x-client", "action": "error", "status": 42 }),
+        ] {
+            let (status, body) = call(&router, add(rule)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["error"], "invalid_partition_rule");
+        }
+        assert!(app.network_partitions.rules().is_empty());
+    }
+
+    #[tokio::test]
+    async fn blackholed_requests_wait_for_the_rule_to_be_removed() {
+        let partitions = Arc::new(NetworkPartitions::default());
+        let rule = partitions
+            .add(PartitionRule {
+                ip: Some("127.0.0.1".parse().unwrap()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rule.action, PartitionAction::Blackhole);
+        assert!(
+            partitions
+                .find(Some("127.0.0.1".parse().unwrap()), &HeaderMap::new())
+                .is_some()
+        );
+        assert!(partitions.find(None, &HeaderMap::new()).is_none());
+
+        let waiting = {
+            let partitions = Arc::clone(&partitions);
+            tokio::spawn(async move { partitions.healing(rule.id).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        assert!(partitions.remove(rule.id));
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!partitions.remove(rule.id));
+    }
+}
@@ -0,0 +1,382 @@
+//! Per-tenant and per-session copies of the in-memory database.
+//!
+//! With `tenant_header` set or `sessions` enabled, REST collection routes and
+//! SQL routes read and write a copy of every collection owned by the tenant
+//! and session id of the request, so tenants and parallel test users do not
+//! see each other's items. A tenant copy is made the first time the tenant is
+//! seen, from the shared collections that requests without a tenant use; a
+//! session copy is made from the collections of its tenant.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use fosk::{Db, DbCollection};
+use http::{HeaderMap, HeaderName};
+use serde_json::Value;
+
+use crate::{
+    app::App,
+    handlers::{CollectionOrder, id_string, request_cookies},
+    route_builder::config::ServerConfig,
+};
+
+/// Header carrying the session id when `session_header` is not configured.
+pub const DEFAULT_SESSION_HEADER: &str = "x-session-id";
+/// Cookie carrying the session id when `session_cookie` is not configured.
+pub const DEFAULT_SESSION_COOKIE: &str = "session_id";
+
+/// Database and item order a request reads and writes.
+#[derive(Clone)]
+pub struct Partition {
+    /// Collections of the partition.
+    pub db: Arc<Db>,
+    /// Insertion order of the partition's collection items.
+    pub collection_order: Arc<CollectionOrder>,
+}
+
+impl Partition {
+    /// Returns the partition's collection named like `collection`, or
+    /// `collection` itself when the partition has none.
+    pub fn collection(&self, collection: &Arc<DbCollection>) -> Arc<DbCollection> {
+        collection
+            .get_name()
+            .ok()
+            .and_then(|name| self.db.get(&name))
+            .unwrap_or_else(|| Arc::clone(collection))
+    }
+
+    /// Copies every collection with its items and their order.
+    pub fn copy(&self) -> Self {
+        let copy = Self {
+            db: Db::new_arc(),
+            collection_order: Arc::default(),
+        };
+        copy.restore(self);
+        copy
+    }
+
+    /// Replaces the items of every collection, and their order, with those of
+    /// `source`.
+    ///
+    /// Collections keep their identity, so handlers holding them see the
+    /// restored items. Collections missing from `source` are emptied.
+    pub fn restore(&self, source: &Partition) {
+        let source_names = source.db.list_collections();
+        for name in self.db.list_collections() {
+            if !source_names.contains(&name) {
+                self.restore_collection(source, &name);
+            }
+        }
+
+        for name in source_names {
+            self.restore_collection(source, &name);
+        }
+    }
+
+    /// Replaces the items of one collection, and their order, with those of
+    /// the same collection in `source`, emptying it when `source` has none.
+    pub fn restore_collection(&self, source: &Partition, name: &str) {
+        let Some(collection) = source.db.get(name) else {
+            if let Some(target) = self.db.get(name) {
+                let _ = target.clear();
+                self.collection_order.reset(name, []);
+            }
+            return;
+        };
+        let (Ok(config), Ok(mut items)) = (collection.get_config(), collection.get_all()) else {
+            return;
+        };
+        source
+            .collection_order
+            .sort(name, &config.id_key, &mut items);
+        let ids = items
+            .iter()
+            .filter_map(|item| item.get(&config.id_key).and_then(id_string))
+            .collect::<Vec<_>>();
+
+        let target = match self.db.get(name) {
+            Some(target) => target,
+            None => self.db.create_with_config(name, config),
+        };
+        if let Err(err) = target.load_from_json(Value::Array(items), false) {
+            eprintln!("❌ Could not copy collection {}: {}", name, err);
+        }
+        self.collection_order.reset(name, ids);
+    }
+}
+
+/// Where requests carry their session id.
+#[derive(Debug, Clone, PartialEq)]
+struct SessionSource {
+    header: HeaderName,
+    cookie: String,
+}
+
+/// Tenant and session id a partition belongs to.
+pub type PartitionKey = (Option<String>, Option<String>);
+
+/// Partitions created for the tenants and session ids seen so far.
+#[derive(Default)]
+pub struct Partitions {
+    tenant_header: Option<HeaderName>,
+    sessions: Option<SessionSource>,
+    partitions: Mutex<HashMap<PartitionKey, Partition>>,
+}
+
+impl Partitions {
+    /// Reads `tenant_header`, `sessions`, `session_header`, and `session_cookie`
+    /// from the server config.
+    pub fn from_config(server: &ServerConfig) -> Self {
+        let tenant_header = server.tenant_header.as_deref().and_then(|header| {
+            HeaderName::try_from(header)
+                .inspect_err(|_| {
+                    eprintln!("❌ tenant_header {} is not a valid header name", header)
+                })
+                .ok()
+        });
+        let sessions = server.sessions.unwrap_or(false).then(|| {
+            let header = server
+                .session_header
+                .as_deref()
+                .and_then(|header| HeaderName::try_from(header).ok())
+                .unwrap_or(HeaderName::from_static(DEFAULT_SESSION_HEADER));
+            let cookie = server
+                .session_cookie
+                .clone()
+                .unwrap_or(DEFAULT_SESSION_COOKIE.to_string());
+            SessionSource { header, cookie }
+        });
+        Self {
+            tenant_header,
+            sessions,
+            partitions: Mutex::default(),
+        }
+    }
+
+    /// Returns the tenant of a request, from the tenant header, when `tenant_header` is set.
+    pub fn tenant_id(&self, headers: &HeaderMap) -> Option<String> {
+        headers
+            .get(self.tenant_header.as_ref()?)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|tenant_id| !tenant_id.is_empty())
+    }
+
+    /// Returns the session id of a request, from the session header or else
+    /// the session cookie, when sessions are enabled.
+    pub fn session_id(&self, headers: &HeaderMap) -> Option<String> {
+        let sessions = self.sessions.as_ref()?;
+        headers
+            .get(&sessions.header)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .or_else(|| {
+                request_cookies(headers)
+                    .into_iter()
+                    .find(|(name, _)| *name == sessions.cookie)
+                    .map(|(_, value)| value)
+            })
+            .filter(|session_id| !session_id.is_empty())
+    }
+
+    /// Returns the partition of a request's tenant and session, or `shared`
+    /// when the request has neither.
+    ///
+    /// A tenant partition starts as a copy of `shared`, and a session
+    /// partition as a copy of its tenant partition.
+    pub fn resolve(&self, shared: &Partition, headers: &HeaderMap) -> Partition {
+        let tenant_id = self.tenant_id(headers);
+        let session_id = self.session_id(headers);
+        if tenant_id.is_none() && session_id.is_none() {
+            return shared.clone();
+        }
+
+        let mut partitions = self.partitions.lock().unwrap();
+        let tenant = match &tenant_id {
+            Some(tenant_id) => partitions
+                .entry((Some(tenant_id.clone()), None))
+                .or_insert_with(|| shared.copy())
+                .clone(),
+            None => shared.clone(),
+        };
+        match session_id {
+            Some(session_id) => partitions
+                .entry((tenant_id, Some(session_id)))
+                .or_insert_with(|| tenant.copy())
+                .clone(),
+            None => tenant,
+        }
+    }
+
+    /// Returns a copy of every tenant and session partition.
+    pub fn snapshot(&self) -> HashMap<PartitionKey, Partition> {
+        self.partitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, partition)| (key.clone(), partition.copy()))
+            .collect()
+    }
+
+    /// Replaces every tenant and session partition with a copy of `snapshot`.
+    pub fn restore(&self, snapshot: &HashMap<PartitionKey, Partition>) {
+        *self.partitions.lock().unwrap() = snapshot
+            .iter()
+            .map(|(key, partition)| (key.clone(), partition.copy()))
+            .collect();
+    }
+
+    /// Forgets every tenant and session partition.
+    pub fn clear(&self) {
+        self.partitions.lock().unwrap().clear();
+    }
+}
+
+/// A REST collection resolved to the copy of the request's tenant and session.
+#[derive(Clone)]
+pub struct PartitionedCollection {
+    collection: Arc<DbCollection>,
+    shared: Partition,
+    partitions: Arc<Partitions>,
+}
+
+impl PartitionedCollection {
+    /// Wraps a shared collection of the application database.
+    pub fn new(app: &App, collection: &Arc<DbCollection>) -> Self {
+        Self {
+            collection: Arc::clone(collection),
+            shared: app.shared_partition(),
+            partitions: Arc::clone(&app.partitions),
+        }
+    }
+
+    /// Returns the partition of a request and its copy of the collection.
+    pub fn resolve(&self, headers: &HeaderMap) -> (Partition, Arc<DbCollection>) {
+        let partition = self.partitions.resolve(&self.shared, headers);
+        let collection = partition.collection(&self.collection);
+        (partition, collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fosk::DbConfig;
+    use http::HeaderValue;
+    use serde_json::json;
+
+    fn shared() -> Partition {
+        let db = Db::new_arc();
+        let users = db.create_with_config("users", DbConfig::int("id"));
+        users
+            .load_from_json(
+                json!([{ "id": 2, "name": "Grace" }, { "id": 1, "name": "Ada" }]),
+                false,
+            )
+            .unwrap();
+        let collection_order = Arc::new(CollectionOrder::default());
+        collection_order.reset("users", ["2".to_string(), "1".to_string()]);
+        Partition {
+            db,
+            collection_order,
+        }
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn session_ids_come_from_the_header_or_the_cookie() {
+        let partitions = Partitions::from_config(&ServerConfig {
+            sessions: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(
+            partitions.session_id(&headers("x-session-id", "a")),
+            Some("a".to_string())
+        );
+        assert_eq!(
+            partitions.session_id(&headers("cookie", "theme=dark; session_id=b")),
+            Some("b".to_string())
+        );
+        assert_eq!(partitions.session_id(&headers("x-session-id", " ")), None);
+
+        let disabled = Partitions::from_config(&ServerConfig::default());
+        assert_eq!(disabled.session_id(&headers("x-session-id", "a")), None);
+    }
+
+    #[test]
+    fn sessions_write_to_their_own_copy_of_the_collections() {
+        let shared = shared();
+        let partitions = Partitions::from_config(&ServerConfig {
+            sessions: Some(true),
+            session_header: Some("X-Test-Session".to_string()),
+            ..Default::default()
+        });
+        let shared_users = shared.db.get("users").unwrap();
+
+        let first = partitions.resolve(&shared, &headers("x-test-session", "a"));
+        let users = first.collection(&shared_users);
+        let added = users.add(json!({ "name": "Hopper" })).unwrap();
+        assert_eq!(added["id"], 3);
+        assert_eq!(shared_users.count().unwrap(), 2);
+
+        let again = partitions.resolve(&shared, &headers("x-test-session", "a"));
+        assert_eq!(again.collection(&shared_users).count().unwrap(), 3);
+        let mut items = again.collection(&shared_users).get_all().unwrap();
+        again.collection_order.sort("users", "id", &mut items);
+        assert_eq!(items[0]["name"], "Grace");
+
+        let other = partitions.resolve(&shared, &headers("x-test-session", "b"));
+        assert_eq!(other.collection(&shared_users).count().unwrap(), 2);
+
+        let without_session = partitions.resolve(&shared, &HeaderMap::new());
+        assert!(Arc::ptr_eq(&without_session.db, &shared.db));
+
+        partitions.clear();
+        let restarted = partitions.resolve(&shared, &headers("x-test-session", "a"));
+        assert_eq!(restarted.collection(&shared_users).count().unwrap(), 2);
+    }
+
+    #[test]
+    fn tenants_get_their_own_copy_shared_by_their_sessions() {
+        let shared = shared();
+        let partitions = Partitions::from_config(&ServerConfig {
+            tenant_header: Some("X-Tenant-Id".to_string()),
+            sessions: Some(true),
+            ..Default::default()
+        });
+        let shared_users = shared.db.get("users").unwrap();
+        let request = |tenant: Option<&'static str>, session: Option<&'static str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(tenant) = tenant {
+                headers.insert("x-tenant-id", HeaderValue::from_static(tenant));
+            }
+            if let Some(session) = session {
+                headers.insert("x-session-id", HeaderValue::from_static(session));
+            }
+            partitions
+                .resolve(&shared, &headers)
+                .collection(&shared_users)
+        };
+
+        request(Some("acme"), None)
+            .add(json!({ "name": "Hopper" }))
+            .unwrap();
+        assert_eq!(request(Some("acme"), None).count().unwrap(), 3);
+        assert_eq!(request(Some("globex"), None).count().unwrap(), 2);
+        assert_eq!(request(None, None).count().unwrap(), 2);
+
+        request(Some("acme"), Some("a"))
+            .add(json!({ "name": "Lovelace" }))
+            .unwrap();
+        assert_eq!(request(Some("acme"), Some("a")).count().unwrap(), 4);
+        assert_eq!(request(Some("acme"), None).count().unwrap(), 3);
+        assert_eq!(request(Some("globex"), Some("a")).count().unwrap(), 2);
+    }
+}
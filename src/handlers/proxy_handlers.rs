@@ -0,0 +1,562 @@
+//! Forwarding of unmatched requests under configured path prefixes to a real
+//! backend, so a mock folder can cover part of an API while the rest stays
+//! real.
+//!
+//! Requests matching a mock route are always answered by the mock. Requests a
+//! mock route does not answer and whose path starts with a `[[proxy.rules]]`
+//! prefix are sent to the rule's target, with the configured header changes,
+//! and successful responses can be recorded as mock files, with the shape of
+//! their request as a golden request. With `anonymize`, personal data in the
+//! recorded bodies is replaced before they reach the disk.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use http::{
+    HeaderMap, HeaderName, HeaderValue, StatusCode, Uri,
+    header::{CONTENT_TYPE, HOST},
+    uri::{Authority, Scheme},
+};
+use hyper::client::conn::http1;
+use hyper_util::rt::TokioIo;
+use rustls::pki_types::ServerName;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::TlsConnector;
+
+use crate::{
+    error_bodies::VerbatimErrorBody,
+    handlers::{Anonymizer, GoldenRequest, error_response},
+    route_builder::config::{Config, ProxyRule},
+    route_import::mock_file_extension,
+    tls::client_tls_config,
+};
+
+/// Folder recorded responses are written to when `record_folder` is not set.
+pub const DEFAULT_RECORD_FOLDER: &str = "recordings";
+
+/// Headers that describe a single connection and are never forwarded.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The `[proxy]` rules of the root configuration.
+#[derive(Default)]
+pub struct ProxyRules {
+    routes: Vec<ProxyRoute>,
+    record_folder: PathBuf,
+    anonymizer: Option<Anonymizer>,
+}
+
+/// A resolved proxy rule.
+struct ProxyRoute {
+    prefix: String,
+    target: Uri,
+    authority: Authority,
+    strip_prefix: bool,
+    set_headers: Vec<(HeaderName, HeaderValue)>,
+    remove_headers: Vec<HeaderName>,
+    record: bool,
+    tls: Option<TlsConnector>,
+}
+
+impl ProxyRules {
+    /// Resolves the `[proxy]` rules, longest prefix first.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let Some(proxy) = &config.proxy else {
+            return Ok(Self::default());
+        };
+        let mut routes = proxy
+            .rules
+            .iter()
+            .flatten()
+            .map(ProxyRoute::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        routes.sort_by_key(|route| std::cmp::Reverse(route.prefix.len()));
+
+        Ok(Self {
+            routes,
+            record_folder: PathBuf::from(
+                proxy
+                    .record_folder
+                    .as_deref()
+                    .unwrap_or(DEFAULT_RECORD_FOLDER),
+            ),
+            anonymizer: proxy.anonymize.unwrap_or(false).then(|| {
+                Anonymizer::new(
+                    proxy.anonymize_fields.clone().unwrap_or_default(),
+                    proxy.anonymize_secret.as_deref(),
+                )
+            }),
+        })
+    }
+
+    /// Returns true when no prefix is forwarded.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Forwards a request whose path starts with a rule prefix, answering
+    /// `502 Bad Gateway` when the backend cannot be reached. Returns the
+    /// request back when no rule matches.
+    pub async fn forward(&self, uri: &Uri, request: Request) -> Result<Response, Request> {
+        let Some(route) = self
+            .routes
+            .iter()
+            .find(|route| matches_prefix(&route.prefix, uri.path()))
+        else {
+            return Err(request);
+        };
+
+        let method = request.method().clone();
+        let (request, golden) = if route.record {
+            let (parts, body) = request.into_parts();
+            let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+                return Ok(error_response(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_body",
+                    "The request body could not be read",
+                ));
+            };
+            let golden = GoldenRequest::from_parts(&parts.method, uri, &parts.headers, &bytes);
+            (Request::from_parts(parts, Body::from(bytes)), Some(golden))
+        } else {
+            (request, None)
+        };
+        let mut response = match route.send(uri, request).await {
+            Ok(response) => response,
+            Err(err) => {
+                return Ok(error_response(
+                    StatusCode::BAD_GATEWAY,
+                    "proxy_error",
+                    format!("Unable to reach {}: {}", route.target, err),
+                ));
+            }
+        };
+        // Backend errors reach the client unchanged, not as mock error bodies.
+        response.extensions_mut().insert(VerbatimErrorBody);
+        if !route.record || !response.status().is_success() {
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+            return Ok(error_response(
+                StatusCode::BAD_GATEWAY,
+                "proxy_error",
+                format!("The response of {} could not be read", route.target),
+            ));
+        };
+        if let Some(path) = recording_path(&self.record_folder, &method, uri, &parts.headers) {
+            let recorded = match &self.anonymizer {
+                Some(anonymizer) => anonymizer.anonymize_body(&bytes),
+                None => bytes.to_vec(),
+            };
+            let _ = path
+                .parent()
+                .map(fs::create_dir_all)
+                .unwrap_or(Ok(()))
+                .and_then(|_| fs::write(&path, recorded));
+        }
+        if let Some(golden) = golden {
+            let _ = golden.append_to(&self.record_folder);
+        }
+        Ok(Response::from_parts(parts, Body::from(bytes)))
+    }
+}
+
+impl TryFrom<&ProxyRule> for ProxyRoute {
+    type Error = String;
+
+    fn try_from(rule: &ProxyRule) -> Result<Self, Self::Error> {
+        let invalid = |message: &dyn std::fmt::Display| {
+            format!("invalid proxy rule for `{}`: {}", rule.prefix, message)
+        };
+        let target = rule.target.parse::<Uri>().map_err(|err| invalid(&err))?;
+        let authority = target
+            .authority()
+            .cloned()
+            .ok_or_else(|| invalid(&"the target has no host"))?;
+        let tls = match target.scheme() {
+            Some(scheme) if *scheme == Scheme::HTTP => None,
+            Some(scheme) if *scheme == Scheme::HTTPS => {
+                let config = client_tls_config(rule.ca.as_deref().map(Path::new))
+                    .map_err(|err| invalid(&err))?;
+                Some(TlsConnector::from(Arc::new(config)))
+            }
+            _ => return Err(invalid(&"the target must be an http or https URL")),
+        };
+        let set_headers = rule
+            .set_headers
+            .iter()
+            .flatten()
+            .map(|(name, value)| {
+                Ok((
+                    HeaderName::try_from(name.as_str()).map_err(|err| invalid(&err))?,
+                    HeaderValue::try_from(value.as_str()).map_err(|err| invalid(&err))?,
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let remove_headers = rule
+            .remove_headers
+            .iter()
+            .flatten()
+            .map(|name| HeaderName::try_from(name.as_str()).map_err(|err| invalid(&err)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            prefix: format!("/{}", rule.prefix.trim_matches('/')),
+            target,
+            authority,
+            strip_prefix: rule.strip_prefix.unwrap_or(false),
+            set_headers,
+            remove_headers,
+            record: rule.record.unwrap_or(false),
+            tls,
+        })
+    }
+}
+
+impl ProxyRoute {
+    /// Target URI of a request: the target path followed by the request path,
+    /// without the prefix when it is stripped, and the request query.
+    fn upstream_uri(&self, uri: &Uri) -> Uri {
+        let path = if self.strip_prefix && self.prefix != "/" {
+            &uri.path()[self.prefix.len().min(uri.path().len())..]
+        } else {
+            uri.path()
+        };
+        let path = format!("{}{}", self.target.path().trim_end_matches('/'), path);
+        let path = if path.is_empty() { "/" } else { &path };
+        let path_and_query = match uri.query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string(),
+        };
+        Uri::builder()
+            .path_and_query(path_and_query)
+            .build()
+            .unwrap_or_default()
+    }
+
+    async fn send(&self, uri: &Uri, request: Request) -> Result<Response, String> {
+        let (mut parts, body) = request.into_parts();
+        parts.uri = self.upstream_uri(uri);
+        strip_hop_by_hop(&mut parts.headers);
+        for name in &self.remove_headers {
+            parts.headers.remove(name);
+        }
+        for (name, value) in &self.set_headers {
+            parts.headers.insert(name.clone(), value.clone());
+        }
+        if let Ok(host) = HeaderValue::try_from(self.authority.as_str()) {
+            parts.headers.insert(HOST, host);
+        }
+        send_to(
+            &self.authority,
+            self.tls.as_ref(),
+            Request::from_parts(parts, body),
+        )
+        .await
+    }
+}
+
+/// Connects to `authority`, over TLS when a connector is given, and sends one
+/// request, streaming its response.
+pub(crate) async fn send_to(
+    authority: &Authority,
+    tls: Option<&TlsConnector>,
+    request: Request,
+) -> Result<Response, String> {
+    let port = authority
+        .port_u16()
+        .unwrap_or(if tls.is_some() { 443 } else { 80 });
+    let host = authority.host();
+    let stream = TcpStream::connect((host, port))
+        .await
+        .map_err(|err| err.to_string())?;
+    match tls {
+        None => send_over(stream, request).await,
+        Some(connector) => {
+            let name = ServerName::try_from(host.trim_matches(['[', ']']).to_string())
+                .map_err(|err| err.to_string())?;
+            let stream = connector
+                .connect(name, stream)
+                .await
+                .map_err(|err| err.to_string())?;
+            send_over(stream, request).await
+        }
+    }
+}
+
+/// Sends one request over an HTTP/1.1 connection and streams its response.
+async fn send_over<S>(stream: S, request: Request) -> Result<Response, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|err| err.to_string())?;
+    tokio::spawn(connection);
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let (mut parts, body) = response.into_parts();
+    strip_hop_by_hop(&mut parts.headers);
+    Ok(Response::from_parts(parts, Body::new(body)).into_response())
+}
+
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+}
+
+/// Returns true when `path` is `prefix` or lies below it.
+fn matches_prefix(prefix: &str, path: &str) -> bool {
+    prefix == "/"
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Characters a recorded file name cannot hold: path separators, characters
+/// Windows refuses, and the dot that starts the extension.
+const UNSAFE_FILE_NAME_CHARACTERS: [char; 10] = ['/', '\\', '.', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Mock file a response is recorded to: the request path as folders, and the
+/// method, with the query as a `~` query variant, as the file name. Responses
+/// other than JSON, XML, HTML, and plain text, and requests whose path or query
+/// cannot be kept inside the record folder as a file name, are not recorded.
+fn recording_path(
+    folder: &Path,
+    method: &http::Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Option<PathBuf> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    let extension = mock_file_extension(content_type)?;
+
+    let mut path = folder.to_path_buf();
+    for segment in uri.path().split('/').filter(|segment| !segment.is_empty()) {
+        if segment == ".." || segment == "." || segment.contains(['\\', ':']) {
+            return None;
+        }
+        path.push(segment);
+    }
+    let query = match uri.query().filter(|query| !query.is_empty()) {
+        Some(query) if query.contains(UNSAFE_FILE_NAME_CHARACTERS) => return None,
+        Some(query) => format!("~{}", query),
+        None => String::new(),
+    };
+    path.push(format!(
+        "{}{}.{}",
+        method.as_str().to_ascii_lowercase(),
+        query,
+        extension
+    ));
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::App, handlers::GOLDEN_FILE, route_builder::config::ProxyConfig};
+    use axum::{Router, routing::get};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    #[test]
+    fn upstream_uris_join_the_target_path_and_the_request_path() {
+        let route = |strip_prefix: bool| {
+            ProxyRoute::try_from(&ProxyRule {
+                prefix: "payments/".to_string(),
+                target: "http://backend:9000/v2/".to_string(),
+                strip_prefix: Some(strip_prefix),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let uri = "/payments/charges?limit=2".parse::<Uri>().unwrap();
+
+        assert_eq!(route(true).upstream_uri(&uri), "/v2/charges?limit=2");
+        assert_eq!(
+            route(false).upstream_uri(&uri),
+            "/v2/payments/charges?limit=2"
+        );
+        assert!(matches_prefix("/payments", "/payments"));
+        assert!(!matches_prefix("/payments", "/payments-old"));
+        assert!(
+            ProxyRoute::try_from(&ProxyRule {
+                prefix: "/x".to_string(),
+                target: "ftp://backend".to_string(),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn recordings_never_leave_the_record_folder() {
+        let folder = Path::new("recordings");
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let path = |uri: &str| {
+            recording_path(
+                folder,
+                &http::Method::GET,
+                &uri.parse::<Uri>().unwrap(),
+                &headers,
+            )
+        };
+
+        assert_eq!(
+            path("/payments/charges?limit=2&sort=desc"),
+            Some(folder.join("payments/charges/get~limit=2&sort=desc.json"))
+        );
+        assert_eq!(path("/payments"), Some(folder.join("payments/get.json")));
+        assert_eq!(path("/x?a=/../../../../tmp/pwn"), None);
+        assert_eq!(path("/x?a=..%5C..%5Cpwn"), None);
+        assert_eq!(path("/x?a=b?c"), None);
+        assert_eq!(path("/x/C:"), None);
+    }
+
+    #[test]
+    fn anonymize_turns_on_the_recording_anonymizer() {
+        let rules = |anonymize: Option<bool>| {
+            ProxyRules::from_config(&Config {
+                proxy: Some(ProxyConfig {
+                    anonymize,
+                    anonymize_fields: Some(vec!["owner_name".to_string()]),
+                    anonymize_secret: Some("fixtures".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        assert_eq!(
+            rules(Some(true)).anonymizer,
+            Some(Anonymizer::new(
+                vec!["ownerName".to_string()],
+                Some("fixtures")
+            ))
+        );
+        assert_eq!(rules(None).anonymizer, None);
+    }
+
+    #[tokio::test]
+    async fn unmatched_requests_under_a_prefix_reach_the_backend_and_are_recorded() {
+        let backend = Router::new().route(
+            "/v2/{*path}",
+            get(|request: Request| async move {
+                let header = |name: &str| {
+                    request
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(String::from)
+                };
+                axum::Json(json!({
+                    "path": request.uri().to_string(),
+                    "key": header("x-api-key"),
+                    "cookie": header("cookie"),
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, backend).await });
+
+        let record_folder = tempfile::tempdir().unwrap();
+        let mut app = App::default();
+        app.server_config = Config {
+            proxy: Some(ProxyConfig {
+                record_folder: Some(record_folder.path().to_string_lossy().to_string()),
+                rules: Some(vec![ProxyRule {
+                    prefix: "/payments".to_string(),
+                    target: format!("http://{}/v2", address),
+                    strip_prefix: Some(true),
+                    set_headers: Some(HashMap::from([(
+                        "x-api-key".to_string(),
+                        "secret".to_string(),
+                    )])),
+                    remove_headers: Some(vec!["cookie".to_string()]),
+                    record: Some(true),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        app.route(
+            "/payments/refunds",
+            get(|| async { "mocked" }),
+            Some("GET"),
+            None,
+        );
+        app.build_fallback();
+        let router = app.take_router_for_test();
+        let call = |uri: &str| {
+            let request = Request::get(uri)
+                .header("cookie", "session=1")
+                .body(Body::empty())
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, bytes)
+            }
+        };
+
+        let (status, bytes) = call("/payments/charges?limit=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::from_slice::<Value>(&bytes).unwrap(),
+            json!({ "path": "/v2/charges?limit=2", "key": "secret", "cookie": null })
+        );
+        let recorded = record_folder
+            .path()
+            .join("payments/charges/get~limit=2.json");
+        assert_eq!(fs::read(recorded).unwrap(), bytes.to_vec());
+        let golden: GoldenRequest = serde_json::from_str(
+            &fs::read_to_string(record_folder.path().join(GOLDEN_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(golden.path, "/payments/charges");
+        assert_eq!(golden.headers.get("cookie"), Some(&None));
+
+        assert_eq!(call("/payments/charges?a=/../pwn").await.0, StatusCode::OK);
+        assert_eq!(
+            fs::read_dir(record_folder.path().join("payments/charges"))
+                .unwrap()
+                .count(),
+            1
+        );
+
+        assert_eq!(call("/payments/refunds").await.1, "mocked");
+        assert_eq!(call("/orders").await.0, StatusCode::NOT_FOUND);
+    }
+}
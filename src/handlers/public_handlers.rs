@@ -0,0 +1,218 @@
+//! Static files of `public` folders, with directory indexes, an optional
+//! single-page application fallback, and configurable cache headers.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    response::IntoResponse,
+    routing::{MethodRouter, get},
+};
+use http::{HeaderValue, StatusCode, header::CACHE_CONTROL};
+use percent_encoding::percent_decode_str;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+use crate::route_builder::config::PublicConfig;
+
+/// Document served for directories and by the SPA fallback, by default.
+pub const DEFAULT_INDEX: &str = "index.html";
+
+/// How a `public` folder answers, from its `[public]` table.
+#[derive(Debug, Clone, Default)]
+pub struct PublicOptions {
+    /// Serve the root index for unknown extensionless paths.
+    pub spa: bool,
+    /// File name of directory indexes.
+    pub index: String,
+    /// `Cache-Control` of the files.
+    pub cache_control: Option<HeaderValue>,
+    /// `Cache-Control` of index documents, including the SPA fallback.
+    pub index_cache_control: Option<HeaderValue>,
+}
+
+impl PublicOptions {
+    /// Reads the options of a `[public]` table; invalid header values are ignored.
+    pub fn from_config(config: &PublicConfig) -> Self {
+        let header = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|value| HeaderValue::from_str(value).ok())
+        };
+        Self {
+            spa: config.spa.unwrap_or(false),
+            index: config
+                .index
+                .clone()
+                .unwrap_or_else(|| DEFAULT_INDEX.to_string()),
+            cache_control: header(&config.cache_control),
+            index_cache_control: header(&config.index_cache_control),
+        }
+    }
+}
+
+/// What a request path resolves to inside a public folder.
+#[derive(Debug, PartialEq)]
+enum PublicFile {
+    File(PathBuf),
+    Index(PathBuf),
+    NotFound,
+}
+
+/// Resolves `request_path`, relative to the folder mount, to a file of `folder`.
+fn resolve(folder: &Path, request_path: &str, options: &PublicOptions) -> PublicFile {
+    let Ok(decoded) = percent_decode_str(request_path).decode_utf8() else {
+        return PublicFile::NotFound;
+    };
+    let relative = Path::new(decoded.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+        || is_folder_config(relative)
+    {
+        return PublicFile::NotFound;
+    }
+
+    let target = folder.join(relative);
+    if target.is_file() {
+        return PublicFile::File(target);
+    }
+    if target.is_dir() {
+        let index = target.join(&options.index);
+        return match index.is_file() {
+            true => PublicFile::Index(index),
+            false => PublicFile::NotFound,
+        };
+    }
+
+    let is_deep_link = relative
+        .file_name()
+        .is_some_and(|name| !name.to_string_lossy().contains('.'));
+    let index = folder.join(&options.index);
+    match options.spa && is_deep_link && index.is_file() {
+        true => PublicFile::Index(index),
+        false => PublicFile::NotFound,
+    }
+}
+
+/// `config.toml` files at the root of the folder configure it and are not served.
+fn is_folder_config(relative: &Path) -> bool {
+    let mut components = relative.components();
+    let (Some(Component::Normal(name)), None) = (components.next(), components.next()) else {
+        return false;
+    };
+    let name = name.to_string_lossy();
+    name.starts_with("config.") && name.ends_with(".toml")
+}
+
+/// Builds the router serving the files of `folder`, to be nested under the
+/// public route prefix.
+pub fn public_router(folder: PathBuf, options: PublicOptions) -> MethodRouter {
+    let options = Arc::new(options);
+    get(move |req: Request| {
+        let options = Arc::clone(&options);
+        let resolved = resolve(&folder, req.uri().path(), &options);
+        async move {
+            let (path, cache_control) = match resolved {
+                PublicFile::File(path) => (path, options.cache_control.clone()),
+                PublicFile::Index(path) => (
+                    path,
+                    options
+                        .index_cache_control
+                        .clone()
+                        .or(options.cache_control.clone()),
+                ),
+                PublicFile::NotFound => return StatusCode::NOT_FOUND.into_response(),
+            };
+
+            let mut response = match ServeFile::new(path).oneshot(req).await {
+                Ok(response) => response.map(Body::new),
+                Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            };
+            if let Some(cache_control) = cache_control
+                && response.status().is_success()
+            {
+                response.headers_mut().insert(CACHE_CONTROL, cache_control);
+            }
+            response
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, response::Response};
+
+    fn folder() -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("index.html"), "root").unwrap();
+        std::fs::write(temp_dir.path().join("app.js"), "js").unwrap();
+        std::fs::write(temp_dir.path().join("config.toml"), "[public]").unwrap();
+        std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        std::fs::write(temp_dir.path().join("docs/index.html"), "docs").unwrap();
+        temp_dir
+    }
+
+    async fn call(router: MethodRouter, uri: &str) -> Response {
+        axum::Router::new()
+            .nest_service("/app", router)
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn body(response: Response) -> String {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8_lossy(&bytes).to_string()
+    }
+
+    #[tokio::test]
+    async fn directories_serve_their_index_and_deep_links_fall_back_in_spa_mode() {
+        let temp_dir = folder();
+        let options = PublicOptions::from_config(&PublicConfig {
+            spa: Some(true),
+            cache_control: Some("public, max-age=3600".to_string()),
+            index_cache_control: Some("no-cache".to_string()),
+            ..Default::default()
+        });
+        let router = public_router(temp_dir.path().to_path_buf(), options);
+
+        let response = call(router.clone(), "/app").await;
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+        assert_eq!(body(response).await, "root");
+        assert_eq!(body(call(router.clone(), "/app/docs").await).await, "docs");
+        assert_eq!(body(call(router.clone(), "/app/docs/").await).await, "docs");
+
+        let response = call(router.clone(), "/app/app.js").await;
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=3600");
+        assert_eq!(body(response).await, "js");
+
+        let response = call(router.clone(), "/app/orders/42").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, "root");
+
+        let response = call(router.clone(), "/app/missing.js").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = call(router, "/app/config.toml").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn unknown_paths_are_not_found_without_spa_mode() {
+        let temp_dir = folder();
+        let router = public_router(
+            temp_dir.path().to_path_buf(),
+            PublicOptions::from_config(&PublicConfig::default()),
+        );
+
+        let response = call(router.clone(), "/app/orders/42").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = call(router, "/app/..%2Fsecret").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
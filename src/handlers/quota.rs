@@ -0,0 +1,473 @@
+//! Request quotas counted per client token or IP address, so tests can drive an
+//! account into its "quota exceeded" state with prior traffic.
+//!
+//! With a `[quota]` table, every request outside the internal routes consumes
+//! one unit of its client's quota: the bearer token (or the `header` token)
+//! when the request carries one, its IP address otherwise. Once `limit`
+//! requests are used within the `window`, the client is answered with
+//! `429 Too Many Requests`. `GET /__quota` lists the consumption per client and
+//! `DELETE /__quota` resets it.
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json,
+    extract::{ConnectInfo, FromRequestParts, Path as AxumPath, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use http::{HeaderMap, HeaderValue, StatusCode, header};
+use serde_json::{Value, json};
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE},
+    criteria::wildcard_matches,
+    handlers::error_response,
+    route_builder::config::Config,
+};
+
+/// Route listing the quota consumption per client.
+pub const QUOTA_ROUTE: &str = "/__quota";
+/// Header with the requests left in the client's quota.
+pub const QUOTA_REMAINING_HEADER: &str = "x-quota-remaining";
+
+/// Settings of the `[quota]` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct QuotaSettings {
+    limit: Option<u64>,
+    window: Option<TimeDelta>,
+    by_ip: bool,
+    header: Option<String>,
+    path: Option<String>,
+}
+
+/// Consumption of one client's quota.
+#[derive(Debug, Clone, PartialEq)]
+struct QuotaUsage {
+    used: u64,
+    rejected: u64,
+    since: DateTime<Utc>,
+    last_request_at: DateTime<Utc>,
+}
+
+/// Outcome of a counted request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaVerdict {
+    /// The request is within the quota, with the requests left when limited.
+    Allowed(Option<u64>),
+    /// The quota is used up, with the seconds until it starts over when it does.
+    Exceeded(Option<i64>),
+}
+
+/// Quota consumption per client, active when the `[quota]` table is set.
+#[derive(Debug, Default)]
+pub struct QuotaTracker {
+    settings: Option<QuotaSettings>,
+    usage: Mutex<BTreeMap<String, QuotaUsage>>,
+}
+
+impl QuotaTracker {
+    /// Creates a tracker from the `[quota]` table, inactive without one.
+    pub fn from_config(config: &Config) -> Self {
+        let Some(quota) = config.quota.as_ref() else {
+            return Self::default();
+        };
+        Self {
+            settings: Some(QuotaSettings {
+                limit: quota.limit,
+                window: quota
+                    .window
+                    .map(|seconds| TimeDelta::seconds(seconds.min(i64::MAX as u64) as i64)),
+                by_ip: quota
+                    .key
+                    .as_deref()
+                    .is_some_and(|key| key.eq_ignore_ascii_case("ip")),
+                header: quota.header.as_ref().map(|header| header.to_lowercase()),
+                path: quota.path.clone(),
+            }),
+            usage: Mutex::default(),
+        }
+    }
+
+    /// Whether requests are counted.
+    pub fn is_active(&self) -> bool {
+        self.settings.is_some()
+    }
+
+    /// Whether requests to a path consume quota.
+    pub fn counts(&self, path: &str) -> bool {
+        self.settings.as_ref().is_some_and(|settings| {
+            settings
+                .path
+                .as_deref()
+                .is_none_or(|pattern| wildcard_matches(path, pattern, '*', '?'))
+        })
+    }
+
+    /// Returns the key of the client sending a request: `token:<token>` when
+    /// it carries one and quotas are counted per token, `ip:<address>`
+    /// otherwise.
+    pub fn client_key(&self, headers: &HeaderMap, client: Option<SocketAddr>) -> Option<String> {
+        let settings = self.settings.as_ref()?;
+        let token = match &settings.header {
+            _ if settings.by_ip => None,
+            Some(name) => headers.get(name).and_then(|value| value.to_str().ok()),
+            None => headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer ")),
+        };
+        match token.map(str::trim).filter(|token| !token.is_empty()) {
+            Some(token) => Some(format!("token:{}", token)),
+            None => client.map(|address| format!("ip:{}", address.ip().to_canonical())),
+        }
+    }
+
+    /// Counts a request of a client at `now`.
+    pub fn consume(&self, key: &str, now: DateTime<Utc>) -> QuotaVerdict {
+        let Some(settings) = &self.settings else {
+            return QuotaVerdict::Allowed(None);
+        };
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage.entry(key.to_string()).or_insert(QuotaUsage {
+            used: 0,
+            rejected: 0,
+            since: now,
+            last_request_at: now,
+        });
+        if let Some(window) = settings.window
+            && now >= entry.since + window
+        {
+            entry.used = 0;
+            entry.rejected = 0;
+            entry.since = now;
+        }
+        entry.last_request_at = now;
+
+        match settings.limit {
+            Some(limit) if entry.used >= limit => {
+                entry.rejected += 1;
+                QuotaVerdict::Exceeded(
+                    settings
+                        .window
+                        .map(|window| (entry.since + window - now).num_seconds().max(1)),
+                )
+            }
+            limit => {
+                entry.used += 1;
+                QuotaVerdict::Allowed(limit.map(|limit| limit - entry.used))
+            }
+        }
+    }
+
+    /// Returns the settings and the consumption of every client, by key.
+    pub fn report(&self) -> Value {
+        let Some(settings) = &self.settings else {
+            return json!({ "clients": [] });
+        };
+        let clients = self
+            .usage
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, usage)| {
+                json!({
+                    "key": key,
+                    "used": usage.used,
+                    "remaining": settings.limit.map(|limit| limit.saturating_sub(usage.used)),
+                    "rejected": usage.rejected,
+                    "exceeded": settings.limit.is_some_and(|limit| usage.used >= limit),
+                    "since": timestamp(usage.since),
+                    "last_request_at": timestamp(usage.last_request_at),
+                    "resets_at": settings.window.map(|window| timestamp(usage.since + window)),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "limit": settings.limit,
+            "window": settings.window.map(|window| window.num_seconds()),
+            "key": if settings.by_ip { "ip" } else { "token" },
+            "clients": clients,
+        })
+    }
+
+    /// Resets the quota of one client, returning false when it made no request.
+    pub fn reset(&self, key: &str) -> bool {
+        self.usage.lock().unwrap().remove(key).is_some()
+    }
+
+    /// Resets the quota of every client, returning how many there were.
+    pub fn reset_all(&self) -> usize {
+        let mut usage = self.usage.lock().unwrap();
+        let count = usage.len();
+        usage.clear();
+        count
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+type QuotaMiddlewareReturn = Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware counting every request against its client's quota,
+/// except those of the `/mock-server` and internal `/__` routes, and answering
+/// `429 Too Many Requests` once the quota is used up.
+pub fn make_quota_middleware(
+    quota: &Arc<QuotaTracker>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> QuotaMiddlewareReturn {
+    let quota = Arc::clone(quota);
+    move |req: Request, next: Next| {
+        let quota = Arc::clone(&quota);
+        Box::pin(async move {
+            let path = req.uri().path();
+            if path.starts_with("/__")
+                || path == MOCK_SERVER_ROUTE
+                || path.starts_with(&format!("{}/", MOCK_SERVER_ROUTE))
+                || !quota.counts(path)
+            {
+                return next.run(req).await;
+            }
+
+            let (mut parts, body) = req.into_parts();
+            let client = ConnectInfo::<SocketAddr>::from_request_parts(&mut parts, &())
+                .await
+                .ok()
+                .map(|ConnectInfo(address)| address);
+            let Some(key) = quota.client_key(&parts.headers, client) else {
+                return next.run(Request::from_parts(parts, body)).await;
+            };
+            match quota.consume(&key, Utc::now()) {
+                QuotaVerdict::Allowed(remaining) => {
+                    let mut response = next.run(Request::from_parts(parts, body)).await;
+                    if let Some(remaining) = remaining {
+                        response
+                            .headers_mut()
+                            .insert(QUOTA_REMAINING_HEADER, HeaderValue::from(remaining));
+                    }
+                    response
+                }
+                QuotaVerdict::Exceeded(retry_after) => {
+                    let mut response = error_response(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        "quota_exceeded",
+                        format!("The quota of {} is used up", key),
+                    );
+                    let headers = response.headers_mut();
+                    headers.insert(QUOTA_REMAINING_HEADER, HeaderValue::from(0));
+                    if let Some(seconds) = retry_after {
+                        headers.insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+                    }
+                    response
+                }
+            }
+        })
+    }
+}
+
+/// Registers `GET /__quota`, which lists the consumption per client,
+/// `DELETE /__quota`, which resets every quota, and `DELETE /__quota/{key}`,
+/// which resets one, when quotas are counted.
+pub fn create_quota_routes(app: &mut App) {
+    if !app.quota.is_active() {
+        return;
+    }
+    let quota = Arc::clone(&app.quota);
+    let report_router = get(async move || Json(quota.report()).into_response());
+    app.route(QUOTA_ROUTE, report_router, Some("GET"), None);
+
+    let quota = Arc::clone(&app.quota);
+    let reset_router =
+        delete(async move || Json(json!({ "reset": quota.reset_all() })).into_response());
+    app.route(QUOTA_ROUTE, reset_router, Some("DELETE"), None);
+
+    let quota = Arc::clone(&app.quota);
+    let reset_one_router = delete(async move |AxumPath(key): AxumPath<String>| {
+        if quota.reset(&key) {
+            StatusCode::NO_CONTENT.into_response()
+        } else {
+            error_response(
+                StatusCode::NOT_FOUND,
+                "quota_not_found",
+                format!("No requests counted for {}", key),
+            )
+        }
+    });
+    app.route(
+        &format!("{}/{{key}}", QUOTA_ROUTE),
+        reset_one_router,
+        Some("DELETE"),
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::QuotaConfig;
+    use axum::{
+        body::{Body, to_bytes},
+        extract::connect_info::MockConnectInfo,
+        middleware,
+    };
+    use tower::ServiceExt;
+
+    fn tracker(quota: QuotaConfig) -> QuotaTracker {
+        QuotaTracker::from_config(&Config {
+            quota: Some(quota),
+            ..Default::default()
+        })
+    }
+
+    async fn call(router: &axum::Router, request: Request) -> (StatusCode, HeaderMap, Value) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            headers,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn get_users(token: Option<&str>) -> Request {
+        let mut request = Request::get("/users");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn clients_are_keyed_by_token_then_ip() {
+        let client = "10.0.0.7:5000".parse::<SocketAddr>().ok();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer abc"),
+        );
+        headers.insert("x-api-key", HeaderValue::from_static("key-1"));
+
+        let by_token = tracker(QuotaConfig::default());
+        assert_eq!(
+            by_token.client_key(&headers, client).as_deref(),
+            Some("token:abc")
+        );
+        assert_eq!(
+            by_token.client_key(&HeaderMap::new(), client).as_deref(),
+            Some("ip:10.0.0.7")
+        );
+
+        let by_header = tracker(QuotaConfig {
+            header: Some("X-Api-Key".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            by_header.client_key(&headers, client).as_deref(),
+            Some("token:key-1")
+        );
+
+        let by_ip = tracker(QuotaConfig {
+            key: Some("ip".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            by_ip.client_key(&headers, client).as_deref(),
+            Some("ip:10.0.0.7")
+        );
+    }
+
+    #[test]
+    fn quotas_start_over_after_the_window() {
+        let quota = tracker(QuotaConfig {
+            limit: Some(2),
+            window: Some(60),
+            ..Default::default()
+        });
+        let start = Utc::now();
+
+        assert_eq!(quota.consume("ip:a", start), QuotaVerdict::Allowed(Some(1)));
+        assert_eq!(quota.consume("ip:a", start), QuotaVerdict::Allowed(Some(0)));
+        assert_eq!(
+            quota.consume("ip:a", start + TimeDelta::seconds(20)),
+            QuotaVerdict::Exceeded(Some(40))
+        );
+        assert_eq!(quota.consume("ip:b", start), QuotaVerdict::Allowed(Some(1)));
+        assert_eq!(
+            quota.consume("ip:a", start + TimeDelta::seconds(60)),
+            QuotaVerdict::Allowed(Some(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn exceeded_quotas_are_listed_and_reset() {
+        let mut app = App::default();
+        app.quota = Arc::new(tracker(QuotaConfig {
+            limit: Some(2),
+            path: Some("/users*".to_string()),
+            ..Default::default()
+        }));
+        app.route("/users", get(|| async { "users" }), Some("GET"), None);
+        app.route("/health", get(|| async { "ok" }), Some("GET"), None);
+        create_quota_routes(&mut app);
+        let quota = Arc::clone(&app.quota);
+        let router = app
+            .take_router_for_test()
+            .layer(middleware::from_fn(make_quota_middleware(&quota)))
+            .layer(MockConnectInfo(
+                "10.0.0.7:5000".parse::<SocketAddr>().unwrap(),
+            ));
+
+        for _ in 0..2 {
+            let (status, headers, _) = call(&router, get_users(Some("abc"))).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(headers.contains_key(QUOTA_REMAINING_HEADER));
+        }
+        let (status, headers, body) = call(&router, get_users(Some("abc"))).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["error"], "quota_exceeded");
+        assert_eq!(headers[QUOTA_REMAINING_HEADER], "0");
+        assert!(!headers.contains_key(header::RETRY_AFTER));
+
+        let (status, _, _) = call(&router, get_users(None)).await;
+        assert_eq!(status, StatusCode::OK);
+        let health = Request::get("/health").body(Body::empty()).unwrap();
+        let (_, headers, _) = call(&router, health).await;
+        assert!(!headers.contains_key(QUOTA_REMAINING_HEADER));
+
+        let report = Request::get(QUOTA_ROUTE).body(Body::empty()).unwrap();
+        let (_, _, report) = call(&router, report).await;
+        assert_eq!(report["limit"], 2);
+        assert_eq!(report["clients"][0]["key"], "ip:10.0.0.7");
+        assert_eq!(report["clients"][0]["remaining"], 1);
+        assert_eq!(report["clients"][1]["key"], "token:abc");
+        assert_eq!(report["clients"][1]["used"], 2);
+        assert_eq!(report["clients"][1]["rejected"], 1);
+        assert_eq!(report["clients"][1]["exceeded"], true);
+
+        let reset = Request::delete(format!("{}/token:abc", QUOTA_ROUTE))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(call(&router, reset).await.0, StatusCode::NO_CONTENT);
+        assert_eq!(
+            call(&router, get_users(Some("abc"))).await.0,
+            StatusCode::OK
+        );
+
+        let reset_all = Request::delete(QUOTA_ROUTE).body(Body::empty()).unwrap();
+        assert_eq!(call(&router, reset_all).await.2["reset"], 2);
+        let missing = Request::delete(format!("{}/token:abc", QUOTA_ROUTE))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(call(&router, missing).await.0, StatusCode::NOT_FOUND);
+    }
+}
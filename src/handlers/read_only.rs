@@ -0,0 +1,67 @@
+//! Read-only REST collections, such as countries or currencies, which clients
+//! can list and read but never write.
+//!
+//! Their REST routes are registered for `GET` only, so writes are answered with
+//! `405 Method Not Allowed`; batch writes naming them are rejected with `403`.
+
+use std::{collections::HashSet, sync::RwLock};
+
+use axum::response::Response;
+use http::StatusCode;
+
+use crate::handlers::error_response;
+
+/// Registry of the collections declared with `read_only = true`.
+#[derive(Debug, Default)]
+pub struct ReadOnlyCollections {
+    collections: RwLock<HashSet<String>>,
+}
+
+impl ReadOnlyCollections {
+    /// Declares whether a collection is read-only.
+    pub fn register(&self, collection: &str, read_only: bool) {
+        let mut collections = self.collections.write().unwrap();
+        if read_only {
+            collections.insert(collection.to_string());
+        } else {
+            collections.remove(collection);
+        }
+    }
+
+    /// Returns true when the collection was declared read-only.
+    pub fn contains(&self, collection: &str) -> bool {
+        self.collections.read().unwrap().contains(collection)
+    }
+
+    /// Removes every declared collection.
+    pub fn clear(&self) {
+        self.collections.write().unwrap().clear();
+    }
+}
+
+/// Returns the `403 Forbidden` response for a write to a read-only collection.
+pub fn read_only_response(collection: &str) -> Response {
+    error_response(
+        StatusCode::FORBIDDEN,
+        "read_only_collection",
+        format!("The {} collection is read-only", collection),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_tracks_declared_collections() {
+        let registry = ReadOnlyCollections::default();
+        registry.register("countries", true);
+        registry.register("users", false);
+
+        assert!(registry.contains("countries"));
+        assert!(!registry.contains("users"));
+
+        registry.register("countries", false);
+        assert!(!registry.contains("countries"));
+    }
+}
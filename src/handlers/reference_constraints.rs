@@ -0,0 +1,387 @@
+//! Optional referential integrity between REST collections.
+//!
+//! A collection declares `refs = { userId = "users" }` in its TOML config. Writes
+//! to that collection are rejected when a referenced id does not exist, and
+//! deleting a referenced item either fails or cascades depending on `on_delete`.
+
+use std::{collections::HashSet, sync::RwLock};
+
+use axum::{http::StatusCode, response::Response};
+use fosk::{CollectionReadError, CollectionWriteError, Db};
+use serde_json::Value;
+
+use crate::{
+    handlers::{error_response, read_error_response, write_error_response},
+    route_builder::config::OnDelete,
+};
+
+/// A declared reference from `collection.field` to the ids of `ref_collection`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceConstraint {
+    /// Collection holding the referencing field.
+    pub collection: String,
+    /// Field whose value must match an id in `ref_collection`.
+    pub field: String,
+    /// Referenced collection.
+    pub ref_collection: String,
+    /// Behavior applied when a referenced item is deleted.
+    pub on_delete: OnDelete,
+}
+
+/// Reasons a write or delete was refused by a reference constraint.
+#[derive(Debug)]
+pub enum ReferenceError {
+    /// A referenced id does not exist in the referenced collection.
+    MissingReference {
+        /// Referencing field.
+        field: String,
+        /// Referenced collection.
+        ref_collection: String,
+        /// Missing referenced id.
+        id: String,
+    },
+    /// The item is still referenced by a restricting constraint.
+    Restricted {
+        /// Collection of the item being deleted.
+        collection: String,
+        /// Id of the item being deleted.
+        id: String,
+        /// Referencing collection.
+        ref_by: String,
+        /// Referencing field.
+        field: String,
+        /// Number of referencing items.
+        count: usize,
+    },
+    /// A referenced or referencing collection could not be read.
+    Read(CollectionReadError),
+    /// A cascading delete could not be written.
+    Write(CollectionWriteError),
+}
+
+/// Maps a [`ReferenceError`] to an HTTP error response.
+pub fn reference_error_response(err: ReferenceError) -> Response {
+    match err {
+        ReferenceError::MissingReference {
+            field,
+            ref_collection,
+            id,
+        } => error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "invalid_reference",
+            format!("Field '{field}' references missing {ref_collection} item '{id}'"),
+        ),
+        ReferenceError::Restricted {
+            collection,
+            id,
+            ref_by,
+            field,
+            count,
+        } => error_response(
+            StatusCode::CONFLICT,
+            "reference_conflict",
+            format!(
+                "Item '{id}' in {collection} is still referenced by {count} item(s) through {ref_by}.{field}"
+            ),
+        ),
+        ReferenceError::Read(err) => read_error_response(err),
+        ReferenceError::Write(err) => write_error_response(err),
+    }
+}
+
+/// Registry of reference constraints shared by all REST handlers.
+#[derive(Debug, Default)]
+pub struct ReferenceConstraints {
+    constraints: RwLock<Vec<ReferenceConstraint>>,
+}
+
+/// Returns an id value as a string, accepting string and numeric ids.
+pub(crate) fn id_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the ids referenced by a field value, accepting a single id or an array of ids.
+fn referenced_ids(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(id_string).collect(),
+        value => id_string(value).into_iter().collect(),
+    }
+}
+
+impl ReferenceConstraints {
+    /// Registers a reference constraint.
+    pub fn register(&self, constraint: ReferenceConstraint) {
+        self.constraints.write().unwrap().push(constraint);
+    }
+
+    /// Returns every registered constraint.
+    pub fn list(&self) -> Vec<ReferenceConstraint> {
+        self.constraints.read().unwrap().clone()
+    }
+
+    /// Removes every registered constraint.
+    pub fn clear(&self) {
+        self.constraints.write().unwrap().clear();
+    }
+
+    fn declared_by(&self, collection: &str) -> Vec<ReferenceConstraint> {
+        self.list()
+            .into_iter()
+            .filter(|constraint| constraint.collection == collection)
+            .collect()
+    }
+
+    fn targeting(&self, ref_collection: &str) -> Vec<ReferenceConstraint> {
+        self.list()
+            .into_iter()
+            .filter(|constraint| constraint.ref_collection == ref_collection)
+            .collect()
+    }
+
+    /// Validates that every referenced id present in `payload` exists.
+    ///
+    /// Fields missing from the payload or set to `null` are not checked, so
+    /// partial updates only validate the references they change.
+    pub fn check_references(
+        &self,
+        db: &Db,
+        collection: &str,
+        payload: &Value,
+    ) -> Result<(), ReferenceError> {
+        let Value::Object(payload) = payload else {
+            return Ok(());
+        };
+
+        for constraint in self.declared_by(collection) {
+            let Some(value) = payload.get(&constraint.field) else {
+                continue;
+            };
+
+            for id in referenced_ids(value) {
+                let exists = match db.get(&constraint.ref_collection) {
+                    Some(ref_collection) => {
+                        ref_collection.exists(&id).map_err(ReferenceError::Read)?
+                    }
+                    None => false,
+                };
+
+                if !exists {
+                    return Err(ReferenceError::MissingReference {
+                        field: constraint.field,
+                        ref_collection: constraint.ref_collection,
+                        id,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collects the items that must be deleted with `collection/id`, failing
+    /// when a restricting reference is found anywhere in the cascade.
+    fn plan_delete(
+        &self,
+        db: &Db,
+        collection: &str,
+        id: &str,
+        planned: &mut Vec<(String, String)>,
+        visited: &mut HashSet<(String, String)>,
+    ) -> Result<(), ReferenceError> {
+        if !visited.insert((collection.to_string(), id.to_string())) {
+            return Ok(());
+        }
+        planned.push((collection.to_string(), id.to_string()));
+
+        for constraint in self.targeting(collection) {
+            let Some(referencing) = db.get(&constraint.collection) else {
+                continue;
+            };
+            let id_key = referencing
+                .get_config()
+                .map_err(ReferenceError::Read)?
+                .id_key;
+            let dependents = referencing
+                .get_all()
+                .map_err(ReferenceError::Read)?
+                .into_iter()
+                .filter(|item| {
+                    item.get(&constraint.field).is_some_and(|value| {
+                        referenced_ids(value).iter().any(|ref_id| ref_id == id)
+                    })
+                })
+                .filter_map(|item| item.get(&id_key).and_then(id_string))
+                .collect::<Vec<_>>();
+
+            if dependents.is_empty() {
+                continue;
+            }
+
+            match constraint.on_delete {
+                OnDelete::Restrict => {
+                    return Err(ReferenceError::Restricted {
+                        collection: collection.to_string(),
+                        id: id.to_string(),
+                        ref_by: constraint.collection,
+                        field: constraint.field,
+                        count: dependents.len(),
+                    });
+                }
+                OnDelete::Cascade => {
+                    for dependent in dependents {
+                        self.plan_delete(db, &constraint.collection, &dependent, planned, visited)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Enforces `on_delete` behavior before `collection/id` is deleted.
+    ///
+    /// Returns a conflict response when a restricting reference exists;
+    /// otherwise removes every cascading dependent and leaves the item itself
+    /// for the caller to delete. Returns the collection, id, and item of each
+    /// removed dependent.
+    pub fn apply_delete(
+        &self,
+        db: &Db,
+        collection: &str,
+        id: &str,
+    ) -> Result<Vec<(String, String, Value)>, ReferenceError> {
+        let mut planned = Vec::new();
+        self.plan_delete(db, collection, id, &mut planned, &mut HashSet::new())?;
+
+        let mut deleted = Vec::new();
+        for (dependent_collection, dependent_id) in planned.into_iter().skip(1) {
+            if let Some(dependents) = db.get(&dependent_collection)
+                && let Some(item) = dependents
+                    .delete(&dependent_id)
+                    .map_err(ReferenceError::Write)?
+            {
+                deleted.push((dependent_collection, dependent_id, item));
+            }
+        }
+
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use fosk::DbConfig;
+    use serde_json::json;
+
+    fn constraint(
+        collection: &str,
+        field: &str,
+        ref_collection: &str,
+        on_delete: OnDelete,
+    ) -> ReferenceConstraint {
+        ReferenceConstraint {
+            collection: collection.to_string(),
+            field: field.to_string(),
+            ref_collection: ref_collection.to_string(),
+            on_delete,
+        }
+    }
+
+    fn seeded_db() -> Db {
+        let db = Db::new();
+        let users = db.create_with_config("users", DbConfig::none("id"));
+        users.add(json!({ "id": "1" })).unwrap();
+        let posts = db.create_with_config("posts", DbConfig::none("id"));
+        posts.add(json!({ "id": "10", "userId": "1" })).unwrap();
+        let comments = db.create_with_config("comments", DbConfig::none("id"));
+        comments
+            .add(json!({ "id": "100", "postId": "10" }))
+            .unwrap();
+        db
+    }
+
+    async fn error_code(response: Response) -> String {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        body["error"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn check_references_rejects_missing_ids() {
+        let db = seeded_db();
+        let constraints = ReferenceConstraints::default();
+        constraints.register(constraint("posts", "userId", "users", OnDelete::Restrict));
+
+        assert!(
+            constraints
+                .check_references(&db, "posts", &json!({ "userId": "1" }))
+                .is_ok()
+        );
+        assert!(
+            constraints
+                .check_references(&db, "posts", &json!({ "userId": null }))
+                .is_ok()
+        );
+        assert!(
+            constraints
+                .check_references(&db, "posts", &json!({ "title": "x" }))
+                .is_ok()
+        );
+
+        let response = reference_error_response(
+            constraints
+                .check_references(&db, "posts", &json!({ "userId": ["1", "2"] }))
+                .unwrap_err(),
+        );
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error_code(response).await, "invalid_reference");
+    }
+
+    #[tokio::test]
+    async fn apply_delete_restricts_referenced_items() {
+        let db = seeded_db();
+        let constraints = ReferenceConstraints::default();
+        constraints.register(constraint("posts", "userId", "users", OnDelete::Restrict));
+
+        let response =
+            reference_error_response(constraints.apply_delete(&db, "users", "1").unwrap_err());
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(error_code(response).await, "reference_conflict");
+        assert!(db.get("posts").unwrap().exists("10").unwrap());
+    }
+
+    #[tokio::test]
+    async fn apply_delete_cascades_through_dependents() {
+        let db = seeded_db();
+        let constraints = ReferenceConstraints::default();
+        constraints.register(constraint("posts", "userId", "users", OnDelete::Cascade));
+        constraints.register(constraint("comments", "postId", "posts", OnDelete::Cascade));
+
+        constraints.apply_delete(&db, "users", "1").unwrap();
+        assert!(!db.get("posts").unwrap().exists("10").unwrap());
+        assert!(!db.get("comments").unwrap().exists("100").unwrap());
+        assert!(db.get("users").unwrap().exists("1").unwrap());
+
+        // A restricting reference deeper in the cascade blocks the whole delete.
+        let db = seeded_db();
+        let constraints = ReferenceConstraints::default();
+        constraints.register(constraint("posts", "userId", "users", OnDelete::Cascade));
+        constraints.register(constraint(
+            "comments",
+            "postId",
+            "posts",
+            OnDelete::Restrict,
+        ));
+
+        let response =
+            reference_error_response(constraints.apply_delete(&db, "users", "1").unwrap_err());
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(db.get("posts").unwrap().exists("10").unwrap());
+    }
+}
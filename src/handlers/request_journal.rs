@@ -0,0 +1,244 @@
+//! Journal of the requests the server answers, with their matched route,
+//! status, and latency. `GET /__requests` lists the latest ones,
+//! `GET /__requests/stream` streams them as server-sent events, and
+//! `GET /__requests/live` is a page following the stream.
+
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+use axum::{
+    Json,
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use chrono::{SecondsFormat, Utc};
+use http::{HeaderMap, HeaderValue, header::CONTENT_TYPE};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio_stream::{StreamExt, wrappers::BroadcastStream};
+
+use crate::app::App;
+
+/// Route listing the latest journal entries.
+pub const REQUESTS_ROUTE: &str = "/__requests";
+/// Route streaming journal entries as server-sent events.
+pub const REQUESTS_STREAM_ROUTE: &str = "/__requests/stream";
+/// Route of the live tail page.
+pub const REQUESTS_LIVE_ROUTE: &str = "/__requests/live";
+/// Entries kept for `GET /__requests` and for subscribers that fall behind.
+pub const REQUEST_JOURNAL_CAPACITY: usize = 500;
+
+/// An answered request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequestEntry {
+    /// Position of the request since the server started, from 1.
+    pub sequence: u64,
+    /// When the request was answered, as RFC 3339.
+    pub at: String,
+    pub method: String,
+    /// Requested path and query.
+    pub uri: String,
+    /// Route pattern that answered, such as `/users/{id}`; `None` when no
+    /// route matched.
+    pub route: Option<String>,
+    pub status: u16,
+    /// Milliseconds from receiving the request to its response headers.
+    pub latency_ms: f64,
+}
+
+/// The latest answered requests, and a broadcast channel of new ones.
+pub struct RequestJournal {
+    sender: broadcast::Sender<RequestEntry>,
+    sequence: AtomicU64,
+    latest: Mutex<VecDeque<RequestEntry>>,
+}
+
+impl Default for RequestJournal {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::Sender::new(REQUEST_JOURNAL_CAPACITY),
+            sequence: AtomicU64::new(0),
+            latest: Mutex::new(VecDeque::with_capacity(REQUEST_JOURNAL_CAPACITY)),
+        }
+    }
+}
+
+impl RequestJournal {
+    /// Receives every entry recorded from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<RequestEntry> {
+        self.sender.subscribe()
+    }
+
+    /// Returns the latest entries, oldest first.
+    pub fn latest(&self) -> Vec<RequestEntry> {
+        self.latest.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Numbers, keeps, and publishes an answered request.
+    fn record(
+        &self,
+        method: String,
+        uri: String,
+        route: Option<String>,
+        status: u16,
+        latency_ms: f64,
+    ) {
+        let entry = RequestEntry {
+            sequence: self.sequence.fetch_add(1, Ordering::SeqCst) + 1,
+            at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            method,
+            uri,
+            route,
+            status,
+            latency_ms,
+        };
+        {
+            let mut latest = self.latest.lock().unwrap();
+            if latest.len() == REQUEST_JOURNAL_CAPACITY {
+                latest.pop_front();
+            }
+            latest.push_back(entry.clone());
+        }
+        let _ = self.sender.send(entry);
+    }
+}
+
+type RequestJournalMiddlewareReturn =
+    Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware recording every request, except those of the journal
+/// routes themselves.
+pub fn make_request_journal_middleware(
+    journal: &Arc<RequestJournal>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> RequestJournalMiddlewareReturn {
+    let journal = Arc::clone(journal);
+    move |req: Request, next: Next| {
+        let journal = Arc::clone(&journal);
+        Box::pin(async move {
+            let path = req.uri().path();
+            if path == REQUESTS_ROUTE || path.starts_with(&format!("{}/", REQUESTS_ROUTE)) {
+                return next.run(req).await;
+            }
+
+            let started = Instant::now();
+            let method = req.method().to_string();
+            let uri = req.uri().to_string();
+            let route = req
+                .extensions()
+                .get::<MatchedPath>()
+                .map(|matched| matched.as_str().to_string());
+            let response = next.run(req).await;
+            journal.record(
+                method,
+                uri,
+                route,
+                response.status().as_u16(),
+                started.elapsed().as_secs_f64() * 1000.0,
+            );
+            response
+        })
+    }
+}
+
+/// Registers the journal listing, its event stream, and the live tail page.
+pub fn create_request_journal_routes(app: &mut App) {
+    let journal = Arc::clone(&app.request_journal);
+    let list_router = get(async move || Json(journal.latest()).into_response());
+    app.route(REQUESTS_ROUTE, list_router, Some("GET"), None);
+
+    let journal = Arc::clone(&app.request_journal);
+    let stream_router = get(async move || {
+        let stream = BroadcastStream::new(journal.subscribe()).filter_map(|entry| {
+            let entry = entry.ok()?;
+            let sse = Event::default()
+                .event("request")
+                .id(entry.sequence.to_string())
+                .json_data(&entry)
+                .ok()?;
+            Some(Ok::<_, Infallible>(sse))
+        });
+        Sse::new(stream)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    });
+    app.route(REQUESTS_STREAM_ROUTE, stream_router, Some("GET"), None);
+
+    let pages = Arc::clone(&app.pages);
+    let page_router = get(async move || {
+        let body = pages.lock().unwrap().render_live_requests();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        (headers, body).into_response()
+    });
+    app.route(REQUESTS_LIVE_ROUTE, page_router, None, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, to_bytes},
+        middleware,
+    };
+    use http::StatusCode;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn requests_are_recorded_with_their_route_status_and_latency() {
+        let mut app = App::default();
+        app.route("/users/{id}", get(|| async { "user" }), Some("GET"), None);
+        create_request_journal_routes(&mut app);
+        let journal = Arc::clone(&app.request_journal);
+        let mut receiver = journal.subscribe();
+        let router =
+            app.take_router_for_test()
+                .layer(middleware::from_fn(make_request_journal_middleware(
+                    &journal,
+                )));
+
+        for uri in ["/users/7?full=true", "/missing", REQUESTS_ROUTE] {
+            router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
+
+        let found = receiver.try_recv().unwrap();
+        assert_eq!(found.sequence, 1);
+        assert_eq!(found.method, "GET");
+        assert_eq!(found.uri, "/users/7?full=true");
+        assert_eq!(found.route.as_deref(), Some("/users/{id}"));
+        assert_eq!(found.status, 200);
+        assert!(found.latency_ms >= 0.0);
+        let missing = receiver.try_recv().unwrap();
+        assert_eq!(missing.route, None);
+        assert_eq!(missing.status, 404);
+        assert!(receiver.try_recv().is_err());
+
+        let listing = router
+            .oneshot(Request::get(REQUESTS_ROUTE).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(listing.status(), StatusCode::OK);
+        let listing: Value =
+            serde_json::from_slice(&to_bytes(listing.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(listing.as_array().unwrap().len(), 2);
+        assert_eq!(listing[1]["uri"], "/missing");
+    }
+}
@@ -0,0 +1,237 @@
+//! Field transformations applied to collection items on the way out of REST and
+//! GraphQL handlers, so the same seed data can serve APIs with slightly different shapes.
+//!
+//! A collection declares `[collection.transform]` to map enum codes to other
+//! values, mask personal data such as emails or card numbers, and rename
+//! fields. Stored items are never changed; only responses are.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use serde_json::Value;
+
+/// Character replacing the hidden part of a masked value.
+const MASK: char = '*';
+/// Trailing characters kept visible by masking, such as the last digits of a card.
+const MASK_VISIBLE: usize = 4;
+
+/// Transformations declared for one collection, applied as map, mask, rename.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResponseTransform {
+    /// Values replacing stored codes, by field and code.
+    pub map: HashMap<String, HashMap<String, Value>>,
+    /// Fields whose values are masked.
+    pub mask: Vec<String>,
+    /// New names of fields, by stored name.
+    pub rename: HashMap<String, String>,
+}
+
+impl ResponseTransform {
+    /// Returns true when no transformation is declared.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty() && self.mask.is_empty() && self.rename.is_empty()
+    }
+
+    /// Transforms one item. Fields are named as stored in every step.
+    pub fn apply(&self, item: Value) -> Value {
+        let Value::Object(item) = item else {
+            return item;
+        };
+
+        let mut transformed = serde_json::Map::with_capacity(item.len());
+        for (field, mut value) in item {
+            if let Some(codes) = self.map.get(&field)
+                && let Some(mapped) = code_of(&value).and_then(|code| codes.get(&code))
+            {
+                value = mapped.clone();
+            }
+            if self.mask.contains(&field) {
+                value = mask_value(&value);
+            }
+            let field = self.rename.get(&field).cloned().unwrap_or(field);
+            transformed.insert(field, value);
+        }
+        Value::Object(transformed)
+    }
+}
+
+/// Returns the text a stored value is looked up by in a `map` table.
+fn code_of(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+/// Masks personal data: emails keep their first character and domain, other
+/// text and numbers keep their last four characters, and arrays and objects
+/// are masked item by item.
+pub fn mask_value(value: &Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(mask_text(text)),
+        Value::Number(number) => Value::String(mask_text(&number.to_string())),
+        Value::Array(items) => Value::Array(items.iter().map(mask_value).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(field, value)| (field.clone(), mask_value(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn mask_text(text: &str) -> String {
+    if let Some((local, domain)) = text.split_once('@')
+        && let Some(first) = local.chars().next()
+    {
+        return format!("{}{}@{}", first, MASK.to_string().repeat(3), domain);
+    }
+
+    let length = text.chars().count();
+    let visible = if length > MASK_VISIBLE {
+        MASK_VISIBLE
+    } else {
+        0
+    };
+    text.chars()
+        .enumerate()
+        .map(|(index, c)| if index < length - visible { MASK } else { c })
+        .collect()
+}
+
+/// Registry of response transformations keyed by collection name, ignoring
+/// case as Fosk does.
+#[derive(Debug, Default)]
+pub struct ResponseTransforms {
+    collections: RwLock<HashMap<String, ResponseTransform>>,
+}
+
+impl ResponseTransforms {
+    /// Declares the transformations of a collection.
+    pub fn register(&self, collection: &str, transform: ResponseTransform) {
+        let mut collections = self.collections.write().unwrap();
+        if transform.is_empty() {
+            collections.remove(&collection.to_lowercase());
+        } else {
+            collections.insert(collection.to_lowercase(), transform);
+        }
+    }
+
+    /// Removes every declared collection.
+    pub fn clear(&self) {
+        self.collections.write().unwrap().clear();
+    }
+
+    /// Transforms one item of a collection.
+    pub fn apply_item(&self, collection: &str, item: Value) -> Value {
+        match self
+            .collections
+            .read()
+            .unwrap()
+            .get(&collection.to_lowercase())
+        {
+            Some(transform) => transform.apply(item),
+            None => item,
+        }
+    }
+
+    /// Transforms the items of a collection.
+    pub fn apply_items(&self, collection: &str, items: Vec<Value>) -> Vec<Value> {
+        match self
+            .collections
+            .read()
+            .unwrap()
+            .get(&collection.to_lowercase())
+        {
+            Some(transform) => items
+                .into_iter()
+                .map(|item| transform.apply(item))
+                .collect(),
+            None => items,
+        }
+    }
+
+    /// Transforms the items of a `{"data": [...]}` listing.
+    pub fn apply_list(&self, collection: &str, mut listing: Value) -> Value {
+        if let Some(Value::Array(items)) = listing.get_mut("data") {
+            *items = self.apply_items(collection, std::mem::take(items));
+        }
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transform() -> ResponseTransform {
+        ResponseTransform {
+            map: HashMap::from([(
+                "status".to_string(),
+                HashMap::from([
+                    ("A".to_string(), json!("active")),
+                    ("1".to_string(), json!(true)),
+                ]),
+            )]),
+            mask: vec!["email".to_string(), "card".to_string()],
+            rename: HashMap::from([
+                ("firstName".to_string(), "first_name".to_string()),
+                ("email".to_string(), "contact".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn items_are_mapped_masked_and_renamed() {
+        let item = transform().apply(json!({
+            "id": 1,
+            "firstName": "Ada",
+            "status": "A",
+            "email": "ada@example.com",
+            "card": "4111111111111111",
+        }));
+
+        assert_eq!(
+            item,
+            json!({
+                "id": 1,
+                "first_name": "Ada",
+                "status": "active",
+                "contact": "a***@example.com",
+                "card": "************1111",
+            })
+        );
+        assert_eq!(transform().apply(json!({ "status": 1 }))["status"], true);
+        assert_eq!(transform().apply(json!({ "status": "X" }))["status"], "X");
+    }
+
+    #[test]
+    fn short_values_and_numbers_are_masked() {
+        assert_eq!(mask_value(&json!("1234")), "****");
+        assert_eq!(mask_value(&json!(5551234567u64)), "******4567");
+        assert_eq!(mask_value(&json!(null)), json!(null));
+    }
+
+    #[test]
+    fn registry_transforms_listings_of_declared_collections() {
+        let registry = ResponseTransforms::default();
+        registry.register("users", transform());
+        registry.register("orders", ResponseTransform::default());
+
+        let listing = registry.apply_list("users", json!({ "data": [{ "firstName": "Ada" }] }));
+        assert_eq!(listing["data"][0]["first_name"], "Ada");
+        assert_eq!(
+            registry.apply_item("orders", json!({ "firstName": "Ada" })),
+            json!({ "firstName": "Ada" })
+        );
+
+        registry.clear();
+        assert_eq!(
+            registry.apply_item("users", json!({ "firstName": "Ada" })),
+            json!({ "firstName": "Ada" })
+        );
+    }
+}
@@ -1,91 +1,356 @@
 //! Handlers for generated REST collection routes.
 
-use std::{path::PathBuf, str::FromStr, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 use axum::{
-    extract::{Json, Path as AxumPath},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Json, Path as AxumPath, Query},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, patch, post, put},
 };
-use fosk::{DbCollection, DbConfig};
-use jgd_rs::generate_jgd_from_file;
-use serde_json::{Map, Value};
+use chrono::{SecondsFormat, Utc};
+use fosk::{CollectionReadError, DbCollection, DbConfig};
+use serde_json::{Value, json};
 
 use crate::{
     app::App,
-    handlers::{SleepThread, add_error_response, is_jgd, read_error_response, write_error_response},
-    route_builder::{RouteRegistrator, RouteRest},
+    criteria::{CriteriaBuilder, CriteriaError},
+    handlers::{
+        AggregateQuery, Change, CollectionLinks, PartitionedCollection, ReferenceConstraint,
+        SleepDelay, add_error_response, error_response, id_string, is_jgd, read_error_response,
+        reference_error_response, write_error_response,
+    },
+    odata::{ODataError, ODataQuery},
+    random,
+    route_builder::{RouteRegistrator, RouteRest, config::Delay},
+    seed_stream::{load_seed_file, load_seed_value},
+    templates::render_value,
 };
 
+/// Field set on items removed from a soft-delete collection.
+pub const SOFT_DELETE_FIELD: &str = "deletedAt";
+
+/// Returns true when an item has been soft-deleted.
+pub fn is_soft_deleted(item: &Value) -> bool {
+    item.get(SOFT_DELETE_FIELD)
+        .is_some_and(|deleted_at| !deleted_at.is_null())
+}
+
+/// Returns the `?case_insensitive=` override, falling back to the collection setting.
+fn case_insensitive(params: &HashMap<String, String>, default: bool) -> bool {
+    params
+        .get("case_insensitive")
+        .map_or(default, |value| value == "true" || value == "1")
+}
+
+fn include_deleted(params: &HashMap<String, String>) -> bool {
+    params
+        .get("include_deleted")
+        .is_some_and(|value| value == "true" || value == "1")
+}
+
+/// Shared state of the routes that list collection items.
+#[derive(Clone)]
+struct ItemListing {
+    collection: PartitionedCollection,
+    collection_name: String,
+    id_key: String,
+    soft_delete: bool,
+    ignore_case: bool,
+}
+
+enum ListingError {
+    InvalidWhere(CriteriaError),
+    InvalidODataQuery(ODataError),
+    Read(CollectionReadError),
+}
+
+fn listing_error_response(err: ListingError) -> Response {
+    match err {
+        ListingError::InvalidWhere(err) => {
+            error_response(StatusCode::BAD_REQUEST, "invalid_where", err.to_string())
+        }
+        ListingError::InvalidODataQuery(err) => error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_odata_query",
+            err.to_string(),
+        ),
+        ListingError::Read(err) => read_error_response(err),
+    }
+}
+
+impl ItemListing {
+    fn new(
+        app: &App,
+        collection: &Arc<DbCollection>,
+        soft_delete: bool,
+        ignore_case: bool,
+    ) -> Self {
+        Self {
+            collection: PartitionedCollection::new(app, collection),
+            collection_name: collection.get_name().unwrap_or_default(),
+            id_key: collection
+                .get_config()
+                .map(|config| config.id_key)
+                .unwrap_or_default(),
+            soft_delete,
+            ignore_case,
+        }
+    }
+
+    /// Lists the items of the request's session in insertion order after the
+    /// soft-delete, `?where=`, and `?$filter=` filters.
+    fn items(
+        &self,
+        params: &HashMap<String, String>,
+        headers: &HeaderMap,
+    ) -> Result<Vec<Value>, ListingError> {
+        let criteria = params
+            .get("where")
+            .map(|text| CriteriaBuilder::parse(text))
+            .transpose()
+            .map_err(ListingError::InvalidWhere)?;
+        let odata = ODataQuery::from_params(params).map_err(ListingError::InvalidODataQuery)?;
+
+        let (partition, collection) = self.collection.resolve(headers);
+        let mut items = collection.get_all().map_err(ListingError::Read)?;
+        partition
+            .collection_order
+            .sort(&self.collection_name, &self.id_key, &mut items);
+        if self.soft_delete && !include_deleted(params) {
+            items.retain(|item| !is_soft_deleted(item));
+        }
+        let ignore_case = case_insensitive(params, self.ignore_case);
+        for criteria in criteria.iter().chain(&odata.filter) {
+            items.retain(|item| criteria.matches_with(item, ignore_case));
+        }
+
+        Ok(items)
+    }
+
+    /// Counts the items [`ItemListing::items`] would list. Without filters the
+    /// collection's own count is used, so no item is cloned.
+    fn count(
+        &self,
+        params: &HashMap<String, String>,
+        headers: &HeaderMap,
+    ) -> Result<usize, ListingError> {
+        let odata = ODataQuery::from_params(params).map_err(ListingError::InvalidODataQuery)?;
+        if params.contains_key("where")
+            || odata.filter.is_some()
+            || (self.soft_delete && !include_deleted(params))
+        {
+            return self.items(params, headers).map(|items| items.len());
+        }
+
+        let (_, collection) = self.collection.resolve(headers);
+        collection.count().map_err(ListingError::Read)
+    }
+}
+
 /// Registers `GET /resource` to list all items in a collection.
+///
+/// Items are returned in insertion order. Soft-deleted items are hidden unless `?include_deleted=true` is sent.
+/// A `?where=` criteria expression filters the listed items; string comparisons ignore case
+/// when the collection sets `case_insensitive` or `?case_insensitive=true` is sent.
+/// The OData options `$filter`, `$orderby`, `$skip`, `$top`, `$select`, and `$count`
+/// are also accepted.
 pub fn create_get_all(
     app: &mut App,
     route: &str,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
     collection: &Arc<DbCollection>,
+    soft_delete: bool,
+    ignore_case: bool,
 ) {
     // GET /resource - list all
-    let list_collection = Arc::clone(collection);
-    let list_router = get(move || async move {
-        delay.sleep_thread();
-
-        match list_collection.get_all() {
-            Ok(items) => {
-                let mut data: Map<String, Value> = Map::new();
-                data.insert("data".to_string(), Value::Array(items));
+    let listing = ItemListing::new(app, collection, soft_delete, ignore_case);
+    let hal_links = Arc::clone(&app.hal_links);
+    let transforms = Arc::clone(&app.response_transforms);
+    let list_router = get(
+        move |headers: HeaderMap, Query(params): Query<HashMap<String, String>>| async move {
+            delay.sleep().await;
 
-                Json(data).into_response()
+            let odata = match ODataQuery::from_params(&params) {
+                Ok(odata) => odata,
+                Err(err) => return listing_error_response(ListingError::InvalidODataQuery(err)),
+            };
+            match listing.items(&params, &headers) {
+                Ok(items) => {
+                    let count = items.len();
+                    let collection_name = &listing.collection_name;
+                    let mut listing = transforms.apply_list(
+                        collection_name,
+                        hal_links.decorate_list(collection_name, odata.page(items)),
+                    );
+                    if odata.count {
+                        listing["@odata.count"] = json!(count);
+                    }
+                    Json(listing).into_response()
+                }
+                Err(err) => listing_error_response(err),
             }
-            Err(err) => read_error_response(err),
-        }
-    });
+        },
+    );
 
     app.push_route(route, list_router, Some("GET"), is_protected, None);
 }
 
+/// Registers `GET /resource/_count` to count the items that `GET /resource` would list.
+pub fn create_count(
+    app: &mut App,
+    route: &str,
+    is_protected: bool,
+    delay: Option<Delay>,
+    collection: &Arc<DbCollection>,
+    soft_delete: bool,
+    ignore_case: bool,
+) {
+    // GET /resource/_count - count listed items
+    let listing = ItemListing::new(app, collection, soft_delete, ignore_case);
+    let count_router = get(
+        move |headers: HeaderMap, Query(params): Query<HashMap<String, String>>| async move {
+            delay.sleep().await;
+
+            match listing.count(&params, &headers) {
+                Ok(count) => Json(json!({ "count": count })).into_response(),
+                Err(err) => listing_error_response(err),
+            }
+        },
+    );
+
+    let count_route = format!("{}/_count", route);
+    app.push_route(&count_route, count_router, Some("GET"), is_protected, None);
+}
+
+/// Registers `GET /resource/_aggregate` to summarize the items that `GET /resource` would list.
+///
+/// `?group_by=` groups items by one or more fields, and `?sum=`, `?avg=`, `?min=`,
+/// and `?max=` aggregate numeric fields per group.
+pub fn create_aggregate(
+    app: &mut App,
+    route: &str,
+    is_protected: bool,
+    delay: Option<Delay>,
+    collection: &Arc<DbCollection>,
+    soft_delete: bool,
+    ignore_case: bool,
+) {
+    // GET /resource/_aggregate - summarize listed items
+    let listing = ItemListing::new(app, collection, soft_delete, ignore_case);
+    let aggregate_router = get(
+        move |headers: HeaderMap, Query(params): Query<HashMap<String, String>>| async move {
+            delay.sleep().await;
+
+            match listing.items(&params, &headers) {
+                Ok(items) => {
+                    let rows = AggregateQuery::from_params(&params).aggregate(&items);
+                    Json(json!({ "data": rows })).into_response()
+                }
+                Err(err) => listing_error_response(err),
+            }
+        },
+    );
+
+    let aggregate_route = format!("{}/_aggregate", route);
+    app.push_route(
+        &aggregate_route,
+        aggregate_router,
+        Some("GET"),
+        is_protected,
+        None,
+    );
+}
+
 /// Registers `POST /resource` to insert an item into a collection.
 pub fn create_insert(
     app: &mut App,
     route: &str,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
     collection: &Arc<DbCollection>,
 ) {
     // POST /resource - create new
-    let create_collection = Arc::clone(collection);
-    let create_router = post(move |Json(payload): Json<Value>| async move {
-        delay.sleep_thread();
+    let create_collection = PartitionedCollection::new(app, collection);
+    let collection_name = collection.get_name().unwrap_or_default();
+    let id_key = collection
+        .get_config()
+        .map(|config| config.id_key)
+        .unwrap_or_default();
+    let references = Arc::clone(&app.references);
+    let collection_defaults = Arc::clone(&app.collection_defaults);
+    let hal_links = Arc::clone(&app.hal_links);
+    let transforms = Arc::clone(&app.response_transforms);
+    let change_events = Arc::clone(&app.change_events);
+    let create_router = post(
+        move |headers: HeaderMap, Json(payload): Json<Value>| async move {
+            delay.sleep().await;
 
-        match create_collection.add(payload) {
-            Ok(item) => (StatusCode::CREATED, Json(item)).into_response(),
-            Err(err) => add_error_response(err),
-        }
-    });
+            let (partition, create_collection) = create_collection.resolve(&headers);
+            let payload = hal_links.strip(&collection_name, payload);
+            let payload = collection_defaults.apply_create(&collection_name, payload);
+            if let Err(err) = references.check_references(&partition.db, &collection_name, &payload)
+            {
+                return reference_error_response(err);
+            }
+
+            match random::current().add_item(&create_collection, payload) {
+                Ok(item) => {
+                    if let Some(id) = item.get(&id_key).and_then(id_string) {
+                        change_events.publish(Change::created(&collection_name, &id, &item));
+                        partition.collection_order.push(&collection_name, id);
+                    }
+                    let item = hal_links.decorate_item(&collection_name, item);
+                    let item = transforms.apply_item(&collection_name, item);
+                    (StatusCode::CREATED, Json(item)).into_response()
+                }
+                Err(err) => add_error_response(err),
+            }
+        },
+    );
 
     app.push_route(route, create_router, Some("POST"), is_protected, None);
 }
 
 /// Registers `GET /resource/{id}` to retrieve one collection item.
+///
+/// Soft-deleted items are not found unless `?include_deleted=true` is sent.
 pub fn create_get_item(
     app: &mut App,
     id_route: &str,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
     collection: &Arc<DbCollection>,
+    soft_delete: bool,
 ) {
     // GET /resource/:id - get by id
-    let get_collection = Arc::clone(collection);
-    let get_router = get(move |AxumPath(id): AxumPath<String>| async move {
-        delay.sleep_thread();
-
-        match get_collection.get(&id) {
-            Ok(Some(item)) => Json(item).into_response(),
-            Ok(None) => StatusCode::NOT_FOUND.into_response(),
-            Err(err) => read_error_response(err),
-        }
-    });
+    let get_collection = PartitionedCollection::new(app, collection);
+    let collection_name = collection.get_name().unwrap_or_default();
+    let hal_links = Arc::clone(&app.hal_links);
+    let transforms = Arc::clone(&app.response_transforms);
+    let get_router = get(
+        move |AxumPath(id): AxumPath<String>,
+              headers: HeaderMap,
+              Query(params): Query<HashMap<String, String>>| async move {
+            delay.sleep().await;
+
+            let (_, get_collection) = get_collection.resolve(&headers);
+            match get_collection.get(&id) {
+                Ok(Some(item))
+                    if soft_delete && is_soft_deleted(&item) && !include_deleted(&params) =>
+                {
+                    StatusCode::NOT_FOUND.into_response()
+                }
+                Ok(Some(item)) => {
+                    let item = hal_links.decorate_item(&collection_name, item);
+                    Json(transforms.apply_item(&collection_name, item)).into_response()
+                }
+                Ok(None) => StatusCode::NOT_FOUND.into_response(),
+                Err(err) => read_error_response(err),
+            }
+        },
+    );
 
     app.push_route(id_route, get_router, Some("GET"), is_protected, None);
 }
@@ -95,17 +360,36 @@ pub fn create_full_update(
     app: &mut App,
     id_route: &str,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
     collection: &Arc<DbCollection>,
 ) {
     // PUT /resource/:id - update by id
-    let update_collection = Arc::clone(collection);
+    let update_collection = PartitionedCollection::new(app, collection);
+    let collection_name = collection.get_name().unwrap_or_default();
+    let references = Arc::clone(&app.references);
+    let collection_defaults = Arc::clone(&app.collection_defaults);
+    let hal_links = Arc::clone(&app.hal_links);
+    let transforms = Arc::clone(&app.response_transforms);
+    let change_events = Arc::clone(&app.change_events);
     let put_router = put(
-        move |AxumPath(id): AxumPath<String>, Json(payload): Json<Value>| async move {
-            delay.sleep_thread();
+        move |AxumPath(id): AxumPath<String>, headers: HeaderMap, Json(payload): Json<Value>| async move {
+            delay.sleep().await;
+
+            let (partition, update_collection) = update_collection.resolve(&headers);
+            let payload = hal_links.strip(&collection_name, payload);
+            let payload = collection_defaults.apply_insert(&collection_name, payload);
+            if let Err(err) = references.check_references(&partition.db, &collection_name, &payload)
+            {
+                return reference_error_response(err);
+            }
 
+            let before = update_collection.get(&id).ok().flatten();
             match update_collection.update(&id, payload) {
-                Ok(Some(item)) => Json(item).into_response(),
+                Ok(Some(item)) => {
+                    change_events.publish(Change::updated(&collection_name, &id, before, &item));
+                    let item = hal_links.decorate_item(&collection_name, item);
+                    Json(transforms.apply_item(&collection_name, item)).into_response()
+                }
                 Ok(None) => StatusCode::NOT_FOUND.into_response(),
                 Err(err) => write_error_response(err),
             }
@@ -120,17 +404,36 @@ pub fn create_partial_update(
     app: &mut App,
     id_route: &str,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
     collection: &Arc<DbCollection>,
 ) {
     // PATCH /resource/:id - partial update by id
-    let patch_collection = Arc::clone(collection);
+    let patch_collection = PartitionedCollection::new(app, collection);
+    let collection_name = collection.get_name().unwrap_or_default();
+    let references = Arc::clone(&app.references);
+    let collection_defaults = Arc::clone(&app.collection_defaults);
+    let hal_links = Arc::clone(&app.hal_links);
+    let transforms = Arc::clone(&app.response_transforms);
+    let change_events = Arc::clone(&app.change_events);
     let patch_router = patch(
-        move |AxumPath(id): AxumPath<String>, Json(payload): Json<Value>| async move {
-            delay.sleep_thread();
+        move |AxumPath(id): AxumPath<String>, headers: HeaderMap, Json(payload): Json<Value>| async move {
+            delay.sleep().await;
 
+            let (partition, patch_collection) = patch_collection.resolve(&headers);
+            let payload = hal_links.strip(&collection_name, payload);
+            let payload = collection_defaults.apply_update(&collection_name, payload);
+            if let Err(err) = references.check_references(&partition.db, &collection_name, &payload)
+            {
+                return reference_error_response(err);
+            }
+
+            let before = patch_collection.get(&id).ok().flatten();
             match patch_collection.update_partial(&id, payload) {
-                Ok(Some(item)) => Json(item).into_response(),
+                Ok(Some(item)) => {
+                    change_events.publish(Change::updated(&collection_name, &id, before, &item));
+                    let item = hal_links.decorate_item(&collection_name, item);
+                    Json(transforms.apply_item(&collection_name, item)).into_response()
+                }
                 Ok(None) => StatusCode::NOT_FOUND.into_response(),
                 Err(err) => write_error_response(err),
             }
@@ -141,24 +444,73 @@ pub fn create_partial_update(
 }
 
 /// Registers `DELETE /resource/{id}` to remove one collection item.
+///
+/// With `soft_delete`, the item is kept and marked with [`SOFT_DELETE_FIELD`]
+/// instead; reference constraints are enforced when it is purged.
 pub fn create_delete(
     app: &mut App,
     id_route: &str,
     is_protected: bool,
-    delay: Option<u16>,
+    delay: Option<Delay>,
     collection: &Arc<DbCollection>,
+    soft_delete: bool,
 ) {
     // DELETE /resource/:id - delete by id
-    let delete_collection = Arc::clone(collection);
-    let delete_router = delete(move |AxumPath(id): AxumPath<String>| async move {
-        delay.sleep_thread();
-
-        match delete_collection.delete(&id) {
-            Ok(Some(item)) => Json(item).into_response(),
-            Ok(None) => StatusCode::NOT_FOUND.into_response(),
-            Err(err) => write_error_response(err),
-        }
-    });
+    let delete_collection = PartitionedCollection::new(app, collection);
+    let collection_name = collection.get_name().unwrap_or_default();
+    let references = Arc::clone(&app.references);
+    let transforms = Arc::clone(&app.response_transforms);
+    let change_events = Arc::clone(&app.change_events);
+    let delete_router = delete(
+        move |AxumPath(id): AxumPath<String>, headers: HeaderMap| async move {
+            delay.sleep().await;
+
+            let (partition, delete_collection) = delete_collection.resolve(&headers);
+            if soft_delete {
+                return match delete_collection.get(&id) {
+                    Ok(Some(before)) if !is_soft_deleted(&before) => {
+                        let deleted_at = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+                        match delete_collection
+                            .update_partial(&id, json!({ SOFT_DELETE_FIELD: deleted_at }))
+                        {
+                            Ok(Some(item)) => {
+                                change_events.publish(Change::updated(
+                                    &collection_name,
+                                    &id,
+                                    Some(before),
+                                    &item,
+                                ));
+                                Json(transforms.apply_item(&collection_name, item)).into_response()
+                            }
+                            Ok(None) => StatusCode::NOT_FOUND.into_response(),
+                            Err(err) => write_error_response(err),
+                        }
+                    }
+                    Ok(_) => StatusCode::NOT_FOUND.into_response(),
+                    Err(err) => read_error_response(err),
+                };
+            }
+
+            match delete_collection.exists(&id) {
+                Ok(true) => match references.apply_delete(&partition.db, &collection_name, &id) {
+                    Ok(cascaded) => change_events.publish_all(Change::cascaded(cascaded)),
+                    Err(err) => return reference_error_response(err),
+                },
+                Ok(false) => return StatusCode::NOT_FOUND.into_response(),
+                Err(err) => return read_error_response(err),
+            }
+
+            match delete_collection.delete(&id) {
+                Ok(Some(item)) => {
+                    change_events.publish(Change::deleted(&collection_name, &id, &item));
+                    partition.collection_order.remove(&collection_name, &id);
+                    Json(transforms.apply_item(&collection_name, item)).into_response()
+                }
+                Ok(None) => StatusCode::NOT_FOUND.into_response(),
+                Err(err) => write_error_response(err),
+            }
+        },
+    );
 
     app.push_route(id_route, delete_router, Some("DELETE"), is_protected, None);
 }
@@ -171,12 +523,28 @@ pub fn build_rest_routes(app: &mut App, config: &RouteRest) -> Arc<DbCollection>
         DbConfig::from(config.id_type, &config.id_key),
     );
 
+    let collection_order = Arc::clone(&app.collection_order);
+    let record_order = |items: &[Value]| {
+        collection_order.reset(
+            &collection_name,
+            items
+                .iter()
+                .filter_map(|item| item.get(&config.id_key).and_then(id_string)),
+        );
+    };
+
     let result: Result<String, String> = if is_jgd(&config.path) {
-        match generate_jgd_from_file(&PathBuf::from_str(config.path.to_str().unwrap()).unwrap()) {
+        match random::current().generate_jgd(config.path.as_ref()) {
             Ok(jgd_json) => {
+                let jgd_json = render_value(&jgd_json, Utc::now());
+                let jgd_json = match &config.field_defaults.id {
+                    Some(ids) => ids.assign_all(jgd_json),
+                    None => random::current().fill_ids(&collection, jgd_json),
+                };
                 let value = collection.load_from_json(jgd_json, false);
                 value
                     .map(|items| {
+                        record_order(&items);
                         format!(
                             "✔️ Generated {} initial items from {}",
                             items.len(),
@@ -192,34 +560,119 @@ pub fn build_rest_routes(app: &mut App, config: &RouteRest) -> Arc<DbCollection>
             )),
         }
     } else {
-        collection
-            .load_from_file(&config.path)
+        // Streamed in batches, recording the seed order as items are added.
+        let path = config.path.to_string_lossy().to_string();
+        let mut ids = vec![];
+        let mut on_batch = |items: &[Value]| {
+            ids.extend(
+                items
+                    .iter()
+                    .filter_map(|item| item.get(&config.id_key).and_then(id_string)),
+            );
+        };
+        let progress = |loaded: usize| app.log(format!("… {} items loaded from {}", loaded, path));
+        let id_generator = config.field_defaults.id.as_ref();
+        let loaded = match &config.seed {
+            Some(seed) => {
+                load_seed_value(&collection, id_generator, seed, &mut on_batch, &progress)
+            }
+            None => load_seed_file(
+                &collection,
+                id_generator,
+                config.path.as_ref(),
+                &mut on_batch,
+                &progress,
+            ),
+        };
+        collection_order.reset(&collection_name, ids);
+        loaded
+            .map(|loaded| format!("✔️ Loaded {} initial items from {}", loaded, path))
             .map_err(|error| error.to_string())
     };
 
     // load_initial_data(file_path, &collection);
     match result {
-        Ok(msg) => println!("{}", msg),
+        Ok(msg) => app.log(msg),
         Err(msg) => eprintln!("{}", msg),
     }
 
+    for (field, ref_collection) in &config.refs {
+        app.references.register(ReferenceConstraint {
+            collection: collection_name.clone(),
+            field: field.clone(),
+            ref_collection: ref_collection.clone(),
+            on_delete: config.on_delete,
+        });
+    }
+
+    app.collection_defaults
+        .register(&collection_name, config.field_defaults.clone());
+    app.hal_links.register(
+        &collection_name,
+        CollectionLinks {
+            route: config.route.clone(),
+            id_key: config.id_key.clone(),
+            refs: config.refs.clone(),
+            enabled: config.links,
+        },
+    );
+    app.read_only_collections
+        .register(&collection_name, config.read_only);
+    app.response_transforms
+        .register(&collection_name, config.transform.clone());
+
     let route = &config.route;
     let id_route = &format!("{}/{{{}}}", route, config.id_key);
     let is_protected = config.is_protected;
     let delay = config.delay;
 
     // Build REST routes for CRUD operations
-    create_get_all(app, route, is_protected, delay, &collection);
+    let soft_delete = config.soft_delete;
+    let ignore_case = config.case_insensitive;
+    create_get_all(
+        app,
+        route,
+        is_protected,
+        delay,
+        &collection,
+        soft_delete,
+        ignore_case,
+    );
 
-    create_insert(app, route, is_protected, delay, &collection);
+    create_count(
+        app,
+        route,
+        is_protected,
+        delay,
+        &collection,
+        soft_delete,
+        ignore_case,
+    );
+
+    create_aggregate(
+        app,
+        route,
+        is_protected,
+        delay,
+        &collection,
+        soft_delete,
+        ignore_case,
+    );
+
+    // Writes to a read-only collection reach no route and answer 405.
+    if !config.read_only {
+        create_insert(app, route, is_protected, delay, &collection);
+    }
 
-    create_get_item(app, id_route, is_protected, delay, &collection);
+    create_get_item(app, id_route, is_protected, delay, &collection, soft_delete);
 
-    create_full_update(app, id_route, is_protected, delay, &collection);
+    if !config.read_only {
+        create_full_update(app, id_route, is_protected, delay, &collection);
 
-    create_partial_update(app, id_route, is_protected, delay, &collection);
+        create_partial_update(app, id_route, is_protected, delay, &collection);
 
-    create_delete(app, id_route, is_protected, delay, &collection);
+        create_delete(app, id_route, is_protected, delay, &collection, soft_delete);
+    }
 
     collection
 }
@@ -227,6 +680,7 @@ pub fn build_rest_routes(app: &mut App, config: &RouteRest) -> Arc<DbCollection>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::{IdGenerator, IdStrategy, ResponseTransform};
     use axum::{
         body::{Body, to_bytes},
         http::{Method, Request, header::CONTENT_TYPE},
@@ -354,6 +808,878 @@ mod tests {
         assert_eq!(missing.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn rest_refs_reject_missing_ids_and_restrict_deletes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let users_path = temp_dir.path().join("users.json");
+        let posts_path = temp_dir.path().join("posts.json");
+        std::fs::write(&users_path, r#"[{"id":"1"}]"#).unwrap();
+        std::fs::write(&posts_path, r#"[]"#).unwrap();
+
+        let mut app = App::default();
+        build_rest_routes(
+            &mut app,
+            &RouteRest::new(
+                "/users".to_string(),
+                users_path.into_os_string(),
+                "id".to_string(),
+                IdType::None,
+                false,
+                "users".to_string(),
+                None,
+            ),
+        );
+        let mut posts = RouteRest::new(
+            "/posts".to_string(),
+            posts_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "posts".to_string(),
+            None,
+        );
+        posts.refs.insert("userId".to_string(), "users".to_string());
+        build_rest_routes(&mut app, &posts);
+
+        let router = app.take_router_for_test();
+        let invalid = router
+            .clone()
+            .oneshot(json_request(
+                Method::POST,
+                "/posts",
+                json!({"id":"10","userId":"2"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body_json(invalid).await["error"], "invalid_reference");
+
+        let created = router
+            .clone()
+            .oneshot(json_request(
+                Method::POST,
+                "/posts",
+                json!({"id":"10","userId":"1"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+
+        let invalid_patch = router
+            .clone()
+            .oneshot(json_request(
+                Method::PATCH,
+                "/posts/10",
+                json!({"userId":"3"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(invalid_patch.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let restricted = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri("/users/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(restricted.status(), StatusCode::CONFLICT);
+        assert_eq!(body_json(restricted).await["error"], "reference_conflict");
+    }
+
+    #[tokio::test]
+    async fn rest_links_point_at_items_collections_and_references() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let users_path = temp_dir.path().join("users.json");
+        let posts_path = temp_dir.path().join("posts.json");
+        std::fs::write(&users_path, r#"[{"id":"1"}]"#).unwrap();
+        std::fs::write(&posts_path, r#"[{"id":"10","userId":"1"}]"#).unwrap();
+
+        let mut app = App::default();
+        build_rest_routes(
+            &mut app,
+            &RouteRest::new(
+                "/api/users".to_string(),
+                users_path.into_os_string(),
+                "id".to_string(),
+                IdType::None,
+                false,
+                "users".to_string(),
+                None,
+            ),
+        );
+        let mut posts = RouteRest::new(
+            "/api/posts".to_string(),
+            posts_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "posts".to_string(),
+            None,
+        );
+        posts.refs.insert("userId".to_string(), "users".to_string());
+        posts.links = true;
+        build_rest_routes(&mut app, &posts);
+
+        let router = app.take_router_for_test();
+        let list = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/posts")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let list = body_json(list).await;
+        assert_eq!(list["_links"], json!({ "self": { "href": "/api/posts" } }));
+        assert_eq!(
+            list["data"][0]["_links"],
+            json!({
+                "self": { "href": "/api/posts/10" },
+                "collection": { "href": "/api/posts" },
+                "user": { "href": "/api/users/1" },
+            })
+        );
+
+        let put = router
+            .clone()
+            .oneshot(json_request(
+                Method::PUT,
+                "/api/posts/10",
+                list["data"][0].clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            body_json(put).await["_links"]["self"]["href"],
+            "/api/posts/10"
+        );
+
+        let user = router
+            .oneshot(
+                Request::builder()
+                    .uri("/api/users/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(body_json(user).await, json!({ "id": "1" }));
+        assert!(
+            app.db
+                .get("posts")
+                .unwrap()
+                .get("10")
+                .unwrap()
+                .unwrap()
+                .get("_links")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn rest_defaults_and_computed_fields_are_injected_on_writes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(&file_path, r#"[]"#).unwrap();
+
+        let mut app = App::default();
+        let mut orders = RouteRest::new(
+            "/orders".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "orders".to_string(),
+            None,
+        );
+        orders
+            .field_defaults
+            .defaults
+            .insert("status".to_string(), json!("pending"));
+        orders
+            .field_defaults
+            .computed
+            .insert("revision".to_string(), json!("rev-{{date}}"));
+        build_rest_routes(&mut app, &orders);
+
+        let router = app.take_router_for_test();
+        let created = router
+            .clone()
+            .oneshot(json_request(Method::POST, "/orders", json!({"id":"1"})))
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let created = body_json(created).await;
+        assert_eq!(created["status"], "pending");
+        assert!(created["revision"].as_str().unwrap().starts_with("rev-"));
+
+        let patched = router
+            .oneshot(json_request(
+                Method::PATCH,
+                "/orders/1",
+                json!({"status":"shipped","revision":"manual"}),
+            ))
+            .await
+            .unwrap();
+        let patched = body_json(patched).await;
+        assert_eq!(patched["status"], "shipped");
+        assert_eq!(patched["revision"], created["revision"]);
+    }
+
+    #[tokio::test]
+    async fn rest_id_strategies_fill_seeded_and_created_ids() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(
+            &file_path,
+            r#"[{"name":"Ada"},{"id":"usr_5","name":"Grace"}]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let mut users = RouteRest::new(
+            "/users".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "users".to_string(),
+            None,
+        );
+        users.field_defaults.id = Some(IdGenerator::new(
+            "id",
+            IdStrategy::Template("usr_{{seq}}".to_string()),
+        ));
+        let collection = build_rest_routes(&mut app, &users);
+        assert!(collection.get("usr_1").unwrap().is_some());
+
+        let router = app.take_router_for_test();
+        let created = router
+            .clone()
+            .oneshot(json_request(
+                Method::POST,
+                "/users",
+                json!({"name":"Hopper"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        assert_eq!(body_json(created).await["id"], "usr_6");
+
+        let replaced = router
+            .oneshot(json_request(
+                Method::PUT,
+                "/users/usr_1",
+                json!({"name":"Lovelace"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(body_json(replaced).await["id"], "usr_1");
+    }
+
+    #[tokio::test]
+    async fn rest_read_only_collections_reject_writes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(&file_path, r#"[{"id":"pt","name":"Portugal"}]"#).unwrap();
+
+        let mut app = App::default();
+        let mut countries = RouteRest::new(
+            "/countries".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "countries".to_string(),
+            None,
+        );
+        countries.read_only = true;
+        build_rest_routes(&mut app, &countries);
+        app.build_batch_route();
+        app.build_fallback();
+
+        let router = app.take_router_for_test();
+        let item = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/countries/pt")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(item.status(), StatusCode::OK);
+
+        for (method, uri) in [
+            (Method::POST, "/countries"),
+            (Method::PUT, "/countries/pt"),
+            (Method::PATCH, "/countries/pt"),
+            (Method::DELETE, "/countries/pt"),
+        ] {
+            let response = router
+                .clone()
+                .oneshot(json_request(method, uri, json!({"name":"Spain"})))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+            let allow = response.headers()["allow"].to_str().unwrap();
+            assert!(allow.starts_with("GET"), "{}", allow);
+        }
+
+        let batch = router
+            .oneshot(json_request(
+                Method::POST,
+                "/mock-server/batch",
+                json!({"operations":[{"op":"remove","collection":"countries","id":"pt"}]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(batch.status(), StatusCode::FORBIDDEN);
+        assert_eq!(body_json(batch).await["error"], "read_only_collection");
+        assert_eq!(app.db.get("countries").unwrap().count().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn rest_responses_are_transformed_but_items_are_stored_unchanged() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(
+            &file_path,
+            r#"[{"id":"u1","firstName":"Ada","status":"A","email":"ada@example.com"}]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let mut users = RouteRest::new(
+            "/users".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "users".to_string(),
+            None,
+        );
+        users.transform = ResponseTransform {
+            map: HashMap::from([(
+                "status".to_string(),
+                HashMap::from([("A".to_string(), json!("active"))]),
+            )]),
+            mask: vec!["email".to_string()],
+            rename: HashMap::from([("firstName".to_string(), "first_name".to_string())]),
+        };
+        build_rest_routes(&mut app, &users);
+
+        let router = app.take_router_for_test();
+        let list = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let list = body_json(list).await;
+        assert_eq!(
+            list,
+            json!({"data":[{"id":"u1","first_name":"Ada","status":"active","email":"a***@example.com"}]})
+        );
+
+        let patched = router
+            .oneshot(json_request(
+                Method::PATCH,
+                "/users/u1",
+                json!({"firstName":"Grace"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(body_json(patched).await["first_name"], "Grace");
+
+        let stored = app.db.get("users").unwrap().get("u1").unwrap().unwrap();
+        assert_eq!(stored["firstName"], "Grace");
+        assert_eq!(stored["email"], "ada@example.com");
+    }
+
+    #[tokio::test]
+    async fn rest_soft_delete_hides_restores_and_purges_items() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(&file_path, r#"[{"id":"1"},{"id":"2"}]"#).unwrap();
+
+        let mut app = App::default();
+        let mut notes = RouteRest::new(
+            "/notes".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "notes".to_string(),
+            None,
+        );
+        notes.soft_delete = true;
+        build_rest_routes(&mut app, &notes);
+        crate::handlers::create_collections_routes(&mut app);
+
+        let router = app.take_router_for_test();
+        let request = |method: Method, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let deleted = router
+            .clone()
+            .oneshot(request(Method::DELETE, "/notes/1"))
+            .await
+            .unwrap();
+        assert_eq!(deleted.status(), StatusCode::OK);
+        assert!(body_json(deleted).await[SOFT_DELETE_FIELD].is_string());
+
+        let deleted_again = router
+            .clone()
+            .oneshot(request(Method::DELETE, "/notes/1"))
+            .await
+            .unwrap();
+        assert_eq!(deleted_again.status(), StatusCode::NOT_FOUND);
+
+        let listed = router
+            .clone()
+            .oneshot(request(Method::GET, "/notes"))
+            .await
+            .unwrap();
+        assert_eq!(body_json(listed).await["data"], json!([{"id":"2"}]));
+
+        let hidden = router
+            .clone()
+            .oneshot(request(Method::GET, "/notes/1"))
+            .await
+            .unwrap();
+        assert_eq!(hidden.status(), StatusCode::NOT_FOUND);
+
+        let included = router
+            .clone()
+            .oneshot(request(Method::GET, "/notes?include_deleted=true"))
+            .await
+            .unwrap();
+        assert_eq!(
+            body_json(included).await["data"].as_array().unwrap().len(),
+            2
+        );
+
+        let restored = router
+            .clone()
+            .oneshot(json_request(
+                Method::PATCH,
+                "/notes/1",
+                json!({ SOFT_DELETE_FIELD: null }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(restored.status(), StatusCode::OK);
+        let visible = router
+            .clone()
+            .oneshot(request(Method::GET, "/notes/1"))
+            .await
+            .unwrap();
+        assert_eq!(visible.status(), StatusCode::OK);
+
+        router
+            .clone()
+            .oneshot(request(Method::DELETE, "/notes/2"))
+            .await
+            .unwrap();
+        let purged = router
+            .clone()
+            .oneshot(request(
+                Method::POST,
+                "/mock-server/collections/notes/purge",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(body_json(purged).await, json!({ "purged": 1 }));
+
+        let remaining = router
+            .oneshot(request(Method::GET, "/notes?include_deleted=true"))
+            .await
+            .unwrap();
+        assert_eq!(
+            body_json(remaining).await["data"],
+            json!([{"id":"1","deletedAt":null}])
+        );
+    }
+
+    #[tokio::test]
+    async fn rest_get_all_returns_items_in_insertion_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        let seed = (0..20)
+            .rev()
+            .map(|i| json!({ "id": format!("item-{}", i) }))
+            .collect::<Vec<_>>();
+        std::fs::write(&file_path, Value::Array(seed.clone()).to_string()).unwrap();
+
+        let mut app = App::default();
+        let config = RouteRest::new(
+            "/items".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "items".to_string(),
+            None,
+        );
+        build_rest_routes(&mut app, &config);
+
+        let router = app.take_router_for_test();
+        router
+            .clone()
+            .oneshot(json_request(Method::POST, "/items", json!({"id":"new"})))
+            .await
+            .unwrap();
+        router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri("/items/item-10")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let mut expected = seed;
+        expected.retain(|item| item["id"] != "item-10");
+        expected.push(json!({"id":"new"}));
+        for _ in 0..2 {
+            let listed = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/items")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                body_json(listed).await["data"],
+                Value::Array(expected.clone())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rest_sessions_see_only_their_own_items() {
+        use crate::route_builder::config::{Config, ServerConfig};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(&file_path, r#"[{"id":1,"name":"Ada"}]"#).unwrap();
+
+        let mut app = App::new(Config {
+            server: Some(ServerConfig {
+                sessions: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let config = RouteRest::new(
+            "/cart".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::Int,
+            false,
+            "cart".to_string(),
+            None,
+        );
+        build_rest_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+
+        let mut created = json_request(Method::POST, "/cart", json!({"name":"Grace"}));
+        created
+            .headers_mut()
+            .insert("x-session-id", "a".parse().unwrap());
+        let created = router.clone().oneshot(created).await.unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        assert_eq!(body_json(created).await["id"], 2);
+
+        for (session, names) in [
+            (Some("x-session-id"), json!(["Ada", "Grace"])),
+            (Some("cookie"), json!(["Ada"])),
+            (None, json!(["Ada"])),
+        ] {
+            let mut request = Request::builder().uri("/cart");
+            if let Some(header) = session {
+                let value = if header == "cookie" {
+                    "session_id=b"
+                } else {
+                    "a"
+                };
+                request = request.header(header, value);
+            }
+            let listed = router
+                .clone()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let listed = body_json(listed).await["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["name"].clone())
+                .collect::<Vec<_>>();
+            assert_eq!(Value::Array(listed), names, "{:?}", session);
+        }
+    }
+
+    #[tokio::test]
+    async fn rest_get_all_filters_items_with_where_criteria() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        let seed = json!([
+            {"id":"1","status":"active","age":17},
+            {"id":"2","status":"pending","age":25},
+            {"id":"3","status":"banned","age":30},
+            {"id":"4","status":"active","age":45}
+        ]);
+        std::fs::write(&file_path, seed.to_string()).unwrap();
+
+        let mut app = App::default();
+        let config = RouteRest::new(
+            "/users".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "users".to_string(),
+            None,
+        );
+        build_rest_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+        let list = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let ids = |body: Value| {
+            body["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let cases = [
+            (
+                "status+IN+(%22active%22,%22pending%22)",
+                vec!["1", "2", "4"],
+            ),
+            ("status+NOT+IN+(%22active%22)", vec!["2", "3"]),
+            ("age+BETWEEN+18+AND+30", vec!["2", "3"]),
+            (
+                "age+BETWEEN+18+AND+30+OR+status+%3D+%22active%22",
+                vec!["1", "2", "3", "4"],
+            ),
+        ];
+        for (criteria, expected) in cases {
+            let response = router
+                .clone()
+                .oneshot(list(&format!("/users?where={}", criteria)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(ids(body_json(response).await), expected, "{}", criteria);
+        }
+
+        let response = router
+            .oneshot(list("/users?where=age+BETWEEN+18"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["error"], "invalid_where");
+    }
+
+    #[tokio::test]
+    async fn rest_get_all_applies_odata_query_options() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        let seed = json!([
+            {"id":"1","status":"active","age":17},
+            {"id":"2","status":"pending","age":25},
+            {"id":"3","status":"banned","age":30},
+            {"id":"4","status":"active","age":45}
+        ]);
+        std::fs::write(&file_path, seed.to_string()).unwrap();
+
+        let mut app = App::default();
+        let config = RouteRest::new(
+            "/users".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "users".to_string(),
+            None,
+        );
+        build_rest_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+        let list = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = router
+            .clone()
+            .oneshot(list(
+                "/users?$filter=age+gt+18+and+status+ne+%27pending%27\
+                 &$orderby=age+desc&$top=1&$select=id,age&$count=true",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body_json(response).await,
+            json!({ "data": [{ "id": "4", "age": 45 }], "@odata.count": 2 })
+        );
+
+        let count = router
+            .clone()
+            .oneshot(list("/users/_count?$filter=status+eq+%27active%27"))
+            .await
+            .unwrap();
+        assert_eq!(body_json(count).await, json!({ "count": 2 }));
+
+        for uri in ["/users?$filter=age+%3E+18", "/users?$top=many"] {
+            let response = router.clone().oneshot(list(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(body_json(response).await["error"], "invalid_odata_query");
+        }
+    }
+
+    #[tokio::test]
+    async fn rest_get_all_where_case_follows_collection_and_query() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        std::fs::write(
+            &file_path,
+            r#"[{"id":"1","name":"Ada"},{"id":"2","name":"Bob"}]"#,
+        )
+        .unwrap();
+
+        let mut app = App::default();
+        let mut config = RouteRest::new(
+            "/users".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "users".to_string(),
+            None,
+        );
+        config.case_insensitive = true;
+        build_rest_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+        let list = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let cases = [
+            ("/users?where=name+%3D+%22ADA%22", 1),
+            ("/users?where=name+%3D+%22ADA%22&case_insensitive=false", 0),
+            (
+                "/users?where=name+ILIKE+%22b%25%22&case_insensitive=false",
+                1,
+            ),
+        ];
+        for (uri, expected) in cases {
+            let response = router.clone().oneshot(list(uri)).await.unwrap();
+            let body = body_json(response).await;
+            assert_eq!(body["data"].as_array().unwrap().len(), expected, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn rest_count_and_aggregate_summarize_listed_items() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("rest.json");
+        let seed = json!([
+            {"id":"1","status":"open","amount":10},
+            {"id":"2","status":"paid","amount":25},
+            {"id":"3","status":"open","amount":5},
+            {"id":"4","status":"open","amount":1,"deletedAt":"2024-01-01T00:00:00.000Z"}
+        ]);
+        std::fs::write(&file_path, seed.to_string()).unwrap();
+
+        let mut app = App::default();
+        let mut config = RouteRest::new(
+            "/orders".to_string(),
+            file_path.into_os_string(),
+            "id".to_string(),
+            IdType::None,
+            false,
+            "orders".to_string(),
+            None,
+        );
+        config.soft_delete = true;
+        build_rest_routes(&mut app, &config);
+        let router = app.take_router_for_test();
+        let list = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let count = router
+            .clone()
+            .oneshot(list("/orders/_count"))
+            .await
+            .unwrap();
+        assert_eq!(body_json(count).await, json!({"count": 3}));
+
+        let count = router
+            .clone()
+            .oneshot(list("/orders/_count?include_deleted=true"))
+            .await
+            .unwrap();
+        assert_eq!(body_json(count).await, json!({"count": 4}));
+
+        let count = router
+            .clone()
+            .oneshot(list("/orders/_count?include_deleted=true&$top=x"))
+            .await
+            .unwrap();
+        assert_eq!(count.status(), StatusCode::BAD_REQUEST);
+
+        let count = router
+            .clone()
+            .oneshot(list("/orders/_count?where=amount+%3E+5"))
+            .await
+            .unwrap();
+        assert_eq!(body_json(count).await, json!({"count": 2}));
+
+        let aggregate = router
+            .clone()
+            .oneshot(list("/orders/_aggregate?group_by=status&sum=amount"))
+            .await
+            .unwrap();
+        assert_eq!(
+            body_json(aggregate).await,
+            json!({"data": [
+                {"status":"open","count":2,"sum":{"amount":15}},
+                {"status":"paid","count":1,"sum":{"amount":25}}
+            ]})
+        );
+
+        let invalid = router
+            .clone()
+            .oneshot(list("/orders/_aggregate?where=amount+%3E"))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let item = router.oneshot(list("/orders/2")).await.unwrap();
+        assert_eq!(body_json(item).await["amount"], 25);
+    }
+
     #[tokio::test]
     async fn rest_post_duplicate_id_returns_conflict() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -0,0 +1,854 @@
+//! Minimal S3-compatible object storage handlers backed by a local directory.
+//!
+//! Buckets are subdirectories of the storage folder and object keys are file
+//! paths relative to their bucket. Requests are never authenticated: signed
+//! headers are ignored and presigned URLs are accepted as long as they have not
+//! expired. Errors use the S3 XML error shape so SDK clients can parse them.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path as AxumPath, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use http::{
+    HeaderMap, HeaderValue,
+    header::{
+        ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+        LAST_MODIFIED, RANGE,
+    },
+};
+use mime_guess::from_path;
+
+use crate::{
+    app::App,
+    error_bodies::VerbatimErrorBody,
+    handlers::{
+        SleepDelay,
+        upload_handlers::{is_safe_file_name, parse_byte_range},
+    },
+    route_builder::RouteS3,
+};
+
+const XML_CONTENT_TYPE: &str = "application/xml";
+const DEFAULT_MAX_KEYS: usize = 1000;
+const CONTENT_SHA256_HEADER: &str = "x-amz-content-sha256";
+
+type S3Query = Query<HashMap<String, String>>;
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn xml_response(status: StatusCode, body: String) -> Response {
+    (
+        status,
+        [(CONTENT_TYPE, XML_CONTENT_TYPE)],
+        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}", body),
+    )
+        .into_response()
+}
+
+/// Builds an S3 XML error response with the given status, error code, and
+/// message, which S3 clients parse, so error body templates leave it alone.
+fn s3_error(status: StatusCode, code: &str, message: &str) -> Response {
+    let mut response = xml_response(
+        status,
+        format!(
+            "<Error><Code>{}</Code><Message>{}</Message></Error>",
+            code,
+            escape_xml(message)
+        ),
+    );
+    response.extensions_mut().insert(VerbatimErrorBody);
+    response
+}
+
+fn no_such_bucket() -> Response {
+    s3_error(
+        StatusCode::NOT_FOUND,
+        "NoSuchBucket",
+        "The specified bucket does not exist",
+    )
+}
+
+fn no_such_key() -> Response {
+    s3_error(
+        StatusCode::NOT_FOUND,
+        "NoSuchKey",
+        "The specified key does not exist.",
+    )
+}
+
+fn invalid_name() -> Response {
+    s3_error(
+        StatusCode::BAD_REQUEST,
+        "InvalidArgument",
+        "Bucket names and object keys must not contain empty, '.' or '..' segments",
+    )
+}
+
+/// Rejects presigned URLs whose `X-Amz-Date` plus `X-Amz-Expires` is in the
+/// past, and those whose expiry lies beyond the dates chrono can represent.
+///
+/// Signatures are not verified; any other request is accepted as is.
+fn expired_presigned_url(query: &HashMap<String, String>) -> Option<Response> {
+    let signed_at = query.get("X-Amz-Date")?;
+    let expires = query.get("X-Amz-Expires")?.parse::<i64>().ok()?;
+    let signed_at = NaiveDateTime::parse_from_str(signed_at, "%Y%m%dT%H%M%SZ")
+        .ok()?
+        .and_utc();
+
+    let Some(expires_at) =
+        TimeDelta::try_seconds(expires).and_then(|expires| signed_at.checked_add_signed(expires))
+    else {
+        return Some(s3_error(
+            StatusCode::BAD_REQUEST,
+            "AuthorizationQueryParametersError",
+            "X-Amz-Expires is out of range",
+        ));
+    };
+    if expires_at < Utc::now() {
+        return Some(s3_error(
+            StatusCode::FORBIDDEN,
+            "AccessDenied",
+            "Request has expired",
+        ));
+    }
+
+    None
+}
+
+fn bucket_path(root: &Path, bucket: &str) -> Option<PathBuf> {
+    is_safe_file_name(bucket).then(|| root.join(bucket))
+}
+
+fn object_path(root: &Path, bucket: &str, key: &str) -> Option<PathBuf> {
+    let bucket_path = bucket_path(root, bucket)?;
+    let segments = key.split('/').collect::<Vec<_>>();
+    if !segments.iter().all(|segment| is_safe_file_name(segment)) {
+        return None;
+    }
+
+    Some(
+        segments
+            .iter()
+            .fold(bucket_path, |path, segment| path.join(segment)),
+    )
+}
+
+fn modified_at(metadata: &fs::Metadata) -> DateTime<Utc> {
+    metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH).into()
+}
+
+/// Derives a stable entity tag from an object's size and modification time.
+fn etag(metadata: &fs::Metadata) -> String {
+    format!(
+        "\"{:x}-{:x}\"",
+        metadata.len(),
+        modified_at(metadata).timestamp_millis()
+    )
+}
+
+fn object_headers(path: &Path, metadata: &fs::Metadata) -> HeaderMap {
+    let mime_type = from_path(path).first_or_octet_stream().to_string();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_str(&mime_type).unwrap());
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(ETAG, HeaderValue::from_str(&etag(metadata)).unwrap());
+    headers.insert(
+        LAST_MODIFIED,
+        HeaderValue::from_str(
+            &modified_at(metadata)
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        )
+        .unwrap(),
+    );
+    headers
+}
+
+/// Decodes an `aws-chunked` request body, as sent by SDKs that stream
+/// uploads with per-chunk signatures or trailing checksums.
+fn decode_aws_chunked(body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut rest = body;
+
+    loop {
+        let line_end = rest.windows(2).position(|window| window == b"\r\n")?;
+        let header = std::str::from_utf8(&rest[..line_end]).ok()?;
+        let size = header.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        rest = &rest[line_end + 2..];
+
+        if size == 0 {
+            return Some(decoded);
+        }
+
+        decoded.extend_from_slice(rest.get(..size)?);
+        rest = rest.get(size..)?.strip_prefix(b"\r\n")?;
+    }
+}
+
+fn is_aws_chunked(headers: &HeaderMap) -> bool {
+    let encoding = headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let content_sha256 = headers
+        .get(CONTENT_SHA256_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    encoding.contains("aws-chunked") || content_sha256.starts_with("STREAMING-")
+}
+
+/// Recursively collects object keys and metadata below a bucket directory.
+fn collect_objects(dir: &Path, prefix: &str, objects: &mut Vec<(String, fs::Metadata)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let key = format!("{}{}", prefix, name);
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            collect_objects(&entry.path(), &format!("{}/", key), objects);
+        } else {
+            objects.push((key, metadata));
+        }
+    }
+}
+
+enum ListEntry {
+    Object(String, fs::Metadata),
+    CommonPrefix(String),
+}
+
+impl ListEntry {
+    fn name(&self) -> &str {
+        match self {
+            ListEntry::Object(key, _) => key,
+            ListEntry::CommonPrefix(prefix) => prefix,
+        }
+    }
+}
+
+/// Renders a `ListObjectsV2` result for a bucket directory.
+fn list_objects(bucket: &str, bucket_path: &Path, query: &HashMap<String, String>) -> Response {
+    let prefix = query.get("prefix").cloned().unwrap_or_default();
+    let delimiter = query
+        .get("delimiter")
+        .filter(|delimiter| !delimiter.is_empty())
+        .cloned();
+    let max_keys = query
+        .get("max-keys")
+        .and_then(|max_keys| max_keys.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_KEYS);
+    let start_after = query
+        .get("continuation-token")
+        .or_else(|| query.get("start-after"))
+        .cloned()
+        .unwrap_or_default();
+
+    let mut objects = Vec::new();
+    collect_objects(bucket_path, "", &mut objects);
+    objects.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut entries: Vec<ListEntry> = Vec::new();
+    for (key, metadata) in objects {
+        let Some(remainder) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+
+        let common_prefix = delimiter.as_ref().and_then(|delimiter| {
+            remainder
+                .find(delimiter.as_str())
+                .map(|index| format!("{}{}", prefix, &remainder[..index + delimiter.len()]))
+        });
+
+        match common_prefix {
+            Some(common_prefix) => {
+                if entries.last().map(ListEntry::name) != Some(common_prefix.as_str()) {
+                    entries.push(ListEntry::CommonPrefix(common_prefix));
+                }
+            }
+            None => entries.push(ListEntry::Object(key, metadata)),
+        }
+    }
+
+    let mut entries = entries
+        .into_iter()
+        .filter(|entry| entry.name() > start_after.as_str())
+        .peekable();
+    let page = entries.by_ref().take(max_keys).collect::<Vec<_>>();
+    let is_truncated = entries.peek().is_some();
+
+    let mut body = String::new();
+    body.push_str("<ListBucketResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">");
+    body.push_str(&format!("<Name>{}</Name>", escape_xml(bucket)));
+    body.push_str(&format!("<Prefix>{}</Prefix>", escape_xml(&prefix)));
+    if let Some(delimiter) = &delimiter {
+        body.push_str(&format!("<Delimiter>{}</Delimiter>", escape_xml(delimiter)));
+    }
+    body.push_str(&format!("<MaxKeys>{}</MaxKeys>", max_keys));
+    body.push_str(&format!("<KeyCount>{}</KeyCount>", page.len()));
+    body.push_str(&format!("<IsTruncated>{}</IsTruncated>", is_truncated));
+    if let Some(token) = query.get("continuation-token") {
+        body.push_str(&format!(
+            "<ContinuationToken>{}</ContinuationToken>",
+            escape_xml(token)
+        ));
+    }
+    if is_truncated && let Some(last) = page.last() {
+        body.push_str(&format!(
+            "<NextContinuationToken>{}</NextContinuationToken>",
+            escape_xml(last.name())
+        ));
+    }
+
+    for entry in &page {
+        match entry {
+            ListEntry::Object(key, metadata) => body.push_str(&format!(
+                "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                escape_xml(key),
+                modified_at(metadata).format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                escape_xml(&etag(metadata)),
+                metadata.len()
+            )),
+            ListEntry::CommonPrefix(prefix) => body.push_str(&format!(
+                "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                escape_xml(prefix)
+            )),
+        }
+    }
+    body.push_str("</ListBucketResult>");
+
+    xml_response(StatusCode::OK, body)
+}
+
+fn create_list_buckets_route(app: &mut App, s3_def: &RouteS3) {
+    let root = PathBuf::from(&s3_def.path);
+    let delay = s3_def.delay;
+
+    // GET /s3 - list buckets
+    let router = get(move |Query(query): S3Query| async move {
+        delay.sleep().await;
+        if let Some(response) = expired_presigned_url(&query) {
+            return response;
+        }
+
+        let mut buckets = fs::read_dir(&root)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| {
+                        let metadata = entry.metadata().ok()?;
+                        metadata.is_dir().then(|| {
+                            (
+                                entry.file_name().to_string_lossy().to_string(),
+                                modified_at(&metadata),
+                            )
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        buckets.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let buckets = buckets
+            .iter()
+            .map(|(name, created_at)| {
+                format!(
+                    "<Bucket><Name>{}</Name><CreationDate>{}</CreationDate></Bucket>",
+                    escape_xml(name),
+                    created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                )
+            })
+            .collect::<String>();
+
+        xml_response(
+            StatusCode::OK,
+            format!(
+                "<ListAllMyBucketsResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"><Owner><ID>rs-mock-server</ID><DisplayName>rs-mock-server</DisplayName></Owner><Buckets>{}</Buckets></ListAllMyBucketsResult>",
+                buckets
+            ),
+        )
+    });
+
+    app.route(&s3_def.route, router, Some("GET"), None);
+}
+
+fn create_bucket_routes(app: &mut App, s3_def: &RouteS3) {
+    let route = s3_def.get_bucket_route();
+    let root = PathBuf::from(&s3_def.path);
+    let delay = s3_def.delay;
+
+    let list_root = root.clone();
+    let put_root = root.clone();
+    let head_root = root.clone();
+    let delete_root = root;
+
+    // GET /s3/{bucket} - ListObjectsV2
+    let router = get(
+        move |AxumPath(bucket): AxumPath<String>, Query(query): S3Query| async move {
+            delay.sleep().await;
+            if let Some(response) = expired_presigned_url(&query) {
+                return response;
+            }
+            let Some(bucket_path) = bucket_path(&list_root, &bucket) else {
+                return invalid_name();
+            };
+            if !bucket_path.is_dir() {
+                return no_such_bucket();
+            }
+
+            list_objects(&bucket, &bucket_path, &query)
+        },
+    )
+    // PUT /s3/{bucket} - CreateBucket
+    .put(move |AxumPath(bucket): AxumPath<String>| async move {
+        delay.sleep().await;
+        let Some(bucket_path) = bucket_path(&put_root, &bucket) else {
+            return invalid_name();
+        };
+
+        match tokio::fs::create_dir_all(&bucket_path).await {
+            Ok(()) => StatusCode::OK.into_response(),
+            Err(err) => s3_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "InternalError",
+                &err.to_string(),
+            ),
+        }
+    })
+    // HEAD /s3/{bucket} - HeadBucket
+    .head(move |AxumPath(bucket): AxumPath<String>| async move {
+        delay.sleep().await;
+        match bucket_path(&head_root, &bucket) {
+            Some(bucket_path) if bucket_path.is_dir() => StatusCode::OK.into_response(),
+            Some(_) => StatusCode::NOT_FOUND.into_response(),
+            None => StatusCode::BAD_REQUEST.into_response(),
+        }
+    })
+    // DELETE /s3/{bucket} - DeleteBucket
+    .delete(move |AxumPath(bucket): AxumPath<String>| async move {
+        delay.sleep().await;
+        let Some(bucket_path) = bucket_path(&delete_root, &bucket) else {
+            return invalid_name();
+        };
+        if !bucket_path.is_dir() {
+            return no_such_bucket();
+        }
+
+        match tokio::fs::remove_dir(&bucket_path).await {
+            Ok(()) => StatusCode::NO_CONTENT.into_response(),
+            Err(_) => s3_error(
+                StatusCode::CONFLICT,
+                "BucketNotEmpty",
+                "The bucket you tried to delete is not empty",
+            ),
+        }
+    });
+
+    app.route(&route, router, Some("GET"), None);
+}
+
+fn create_object_routes(app: &mut App, s3_def: &RouteS3) {
+    let route = s3_def.get_object_route();
+    let root = PathBuf::from(&s3_def.path);
+    let delay = s3_def.delay;
+
+    let get_root = root.clone();
+    let put_root = root.clone();
+    let head_root = root.clone();
+    let delete_root = root;
+
+    // GET /s3/{bucket}/{*key} - GetObject
+    let router = get(
+        move |AxumPath((bucket, key)): AxumPath<(String, String)>,
+              Query(query): S3Query,
+              request_headers: HeaderMap| async move {
+            delay.sleep().await;
+            if let Some(response) = expired_presigned_url(&query) {
+                return response;
+            }
+            let Some(object_path) = object_path(&get_root, &bucket, &key) else {
+                return invalid_name();
+            };
+            if !object_path.is_file() {
+                return no_such_key();
+            }
+
+            let (Ok(metadata), Ok(contents)) = (
+                fs::metadata(&object_path),
+                tokio::fs::read(&object_path).await,
+            ) else {
+                return no_such_key();
+            };
+
+            let mut headers = object_headers(&object_path, &metadata);
+            let range = request_headers
+                .get(RANGE)
+                .and_then(|value| value.to_str().ok());
+            let Some(range) = range else {
+                return (StatusCode::OK, headers, contents).into_response();
+            };
+
+            let len = contents.len() as u64;
+            match parse_byte_range(range, len) {
+                Some((start, end)) => {
+                    headers.insert(
+                        CONTENT_RANGE,
+                        HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len))
+                            .unwrap(),
+                    );
+                    (
+                        StatusCode::PARTIAL_CONTENT,
+                        headers,
+                        contents[start as usize..=end as usize].to_vec(),
+                    )
+                        .into_response()
+                }
+                None => s3_error(
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    "InvalidRange",
+                    "The requested range is not satisfiable",
+                ),
+            }
+        },
+    )
+    // PUT /s3/{bucket}/{*key} - PutObject
+    .put(
+        move |AxumPath((bucket, key)): AxumPath<(String, String)>,
+              Query(query): S3Query,
+              request_headers: HeaderMap,
+              body: Bytes| async move {
+            delay.sleep().await;
+            if let Some(response) = expired_presigned_url(&query) {
+                return response;
+            }
+            let Some(object_path) = object_path(&put_root, &bucket, &key) else {
+                return invalid_name();
+            };
+            if !put_root.join(&bucket).is_dir() {
+                return no_such_bucket();
+            }
+
+            let contents = if is_aws_chunked(&request_headers) {
+                match decode_aws_chunked(&body) {
+                    Some(contents) => Bytes::from(contents),
+                    None => {
+                        return s3_error(
+                            StatusCode::BAD_REQUEST,
+                            "IncompleteBody",
+                            "The aws-chunked request body could not be decoded",
+                        );
+                    }
+                }
+            } else {
+                body
+            };
+
+            if let Some(parent) = object_path.parent()
+                && let Err(err) = tokio::fs::create_dir_all(parent).await
+            {
+                return s3_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "InternalError",
+                    &err.to_string(),
+                );
+            }
+
+            match tokio::fs::write(&object_path, &contents).await {
+                Ok(()) => {
+                    let mut headers = HeaderMap::new();
+                    if let Ok(metadata) = fs::metadata(&object_path) {
+                        headers.insert(ETAG, HeaderValue::from_str(&etag(&metadata)).unwrap());
+                    }
+                    (StatusCode::OK, headers).into_response()
+                }
+                Err(err) => s3_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "InternalError",
+                    &err.to_string(),
+                ),
+            }
+        },
+    )
+    // HEAD /s3/{bucket}/{*key} - HeadObject
+    .head(
+        move |AxumPath((bucket, key)): AxumPath<(String, String)>| async move {
+            delay.sleep().await;
+            let Some(object_path) = object_path(&head_root, &bucket, &key) else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+
+            match fs::metadata(&object_path) {
+                Ok(metadata) if metadata.is_file() => {
+                    let mut headers = object_headers(&object_path, &metadata);
+                    headers.insert(CONTENT_LENGTH, HeaderValue::from(metadata.len()));
+                    (StatusCode::OK, headers).into_response()
+                }
+                _ => StatusCode::NOT_FOUND.into_response(),
+            }
+        },
+    )
+    // DELETE /s3/{bucket}/{*key} - DeleteObject
+    .delete(
+        move |AxumPath((bucket, key)): AxumPath<(String, String)>,
+              Query(query): S3Query| async move {
+            delay.sleep().await;
+            if let Some(response) = expired_presigned_url(&query) {
+                return response;
+            }
+            let Some(object_path) = object_path(&delete_root, &bucket, &key) else {
+                return invalid_name();
+            };
+
+            // S3 reports success whether or not the key existed
+            let _ = tokio::fs::remove_file(&object_path).await;
+            StatusCode::NO_CONTENT.into_response()
+        },
+    )
+    .layer(DefaultBodyLimit::disable());
+
+    app.route(&route, router, Some("GET"), None);
+}
+
+/// Registers S3-compatible bucket and object routes for a storage folder.
+pub fn build_s3_routes(app: &mut App, s3_def: &RouteS3) {
+    create_list_buckets_route(app, s3_def);
+    create_bucket_routes(app, s3_def);
+    create_object_routes(app, s3_def);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use http::Request;
+    use tower::ServiceExt;
+
+    fn s3_def(path: &Path) -> RouteS3 {
+        RouteS3 {
+            path: path.as_os_str().to_os_string(),
+            route: "/s3".to_string(),
+            delay: None,
+        }
+    }
+
+    fn request(method: &str, uri: &str, body: impl Into<Body>) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(body.into())
+            .unwrap()
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn objects_can_be_put_listed_fetched_and_deleted() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = App::default();
+        build_s3_routes(&mut app, &s3_def(temp_dir.path()));
+        let router = app.take_router_for_test();
+
+        let response = router
+            .clone()
+            .oneshot(request("PUT", "/s3/photos", Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for key in ["2024/cat.txt", "2024/dog.txt", "readme.txt"] {
+            let response = router
+                .clone()
+                .oneshot(request("PUT", &format!("/s3/photos/{}", key), key))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.headers().contains_key(ETAG));
+        }
+
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/s3/photos/2024/cat.txt", Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, "2024/cat.txt");
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/s3/photos?list-type=2&delimiter=/",
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        let body = body_text(response).await;
+        assert!(body.contains("<KeyCount>2</KeyCount>"));
+        assert!(body.contains("<CommonPrefixes><Prefix>2024/</Prefix></CommonPrefixes>"));
+        assert!(body.contains("<Key>readme.txt</Key>"));
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/s3/photos?list-type=2&prefix=2024/&max-keys=1",
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        let body = body_text(response).await;
+        assert!(body.contains("<Key>2024/cat.txt</Key>"));
+        assert!(body.contains("<IsTruncated>true</IsTruncated>"));
+        assert!(body.contains("<NextContinuationToken>2024/cat.txt</NextContinuationToken>"));
+
+        let response = router
+            .clone()
+            .oneshot(request("DELETE", "/s3/photos/2024/cat.txt", Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/s3/photos/2024/cat.txt", Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        // Error body templates leave the S3 XML error alone.
+        assert!(response.extensions().get::<VerbatimErrorBody>().is_some());
+        assert!(body_text(response).await.contains("<Code>NoSuchKey</Code>"));
+
+        let response = router
+            .oneshot(request("GET", "/s3", Body::empty()))
+            .await
+            .unwrap();
+        assert!(body_text(response).await.contains("<Name>photos</Name>"));
+    }
+
+    #[tokio::test]
+    async fn presigned_urls_are_accepted_until_they_expire() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        std::fs::write(temp_dir.path().join("docs/a.txt"), "hello").unwrap();
+        let mut app = App::default();
+        build_s3_routes(&mut app, &s3_def(temp_dir.path()));
+        let router = app.take_router_for_test();
+
+        let signed_at = Utc::now().format("%Y%m%dT%H%M%SZ");
+        let response = router
+            .clone()
+            .oneshot(request(
+                "GET",
+                &format!(
+                    "/s3/docs/a.txt?X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Date={}&X-Amz-Expires=300&X-Amz-Signature=abc",
+                    signed_at
+                ),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/s3/docs/a.txt?X-Amz-Date=20200101T000000Z&X-Amz-Expires=60&X-Amz-Signature=abc",
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(body_text(response).await.contains("Request has expired"));
+
+        for expires in ["99999999999999999", &i64::MAX.to_string()] {
+            let response = router
+                .clone()
+                .oneshot(request(
+                    "GET",
+                    &format!(
+                        "/s3/docs/a.txt?X-Amz-Date=20200101T000000Z&X-Amz-Expires={}",
+                        expires
+                    ),
+                    Body::empty(),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert!(
+                body_text(response)
+                    .await
+                    .contains("<Code>AuthorizationQueryParametersError</Code>")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn put_object_decodes_aws_chunked_bodies_and_requires_bucket() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        let mut app = App::default();
+        build_s3_routes(&mut app, &s3_def(temp_dir.path()));
+        let router = app.take_router_for_test();
+
+        let chunked = Request::builder()
+            .method("PUT")
+            .uri("/s3/docs/b.txt")
+            .header(CONTENT_SHA256_HEADER, "STREAMING-UNSIGNED-PAYLOAD-TRAILER")
+            .body(Body::from(
+                "5\r\nhello\r\n6;chunk-signature=x\r\n world\r\n0\r\nx-amz-checksum-crc32:abc\r\n\r\n",
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(chunked).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("docs/b.txt")).unwrap(),
+            "hello world"
+        );
+
+        let response = router
+            .oneshot(request("PUT", "/s3/missing/b.txt", "x"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(body_text(response).await.contains("NoSuchBucket"));
+    }
+
+    #[test]
+    fn object_keys_reject_path_traversal() {
+        let root = Path::new("storage");
+        assert!(object_path(root, "docs", "a/b.txt").is_some());
+        assert!(object_path(root, "docs", "../b.txt").is_none());
+        assert!(object_path(root, "..", "b.txt").is_none());
+        assert!(object_path(root, "docs", "a//b.txt").is_none());
+    }
+}
@@ -0,0 +1,604 @@
+//! Routes answered by Rhai scripts, for logic the filename grammar cannot
+//! express.
+//!
+//! A `.rhai` mock file runs on every request with two variables in scope:
+//! `request`, a map with the method, path, path `params`, `query`, `headers`,
+//! `body`, and the mutual TLS `client_cert`, and `db`, a handle onto the collections of the request's
+//! tenant or session. The script's value is the response: `response(status,
+//! body, headers)` sets every part, `()` answers `204 No Content`, a string is
+//! sent as text, and any other value as JSON.
+//!
+//! `db.begin()` opens a transaction over the collections, `db.commit()` keeps
+//! its writes and `db.rollback()` undoes them. A transaction still open when
+//! the script ends, or fails, is rolled back.
+
+use std::{
+    ffi::OsString,
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json,
+    body::to_bytes,
+    extract::{FromRequestParts, Query, RawPathParams, Request},
+    response::{IntoResponse, Response},
+    routing::{MethodRouter, delete, get, options, patch, post, put},
+};
+use fosk::DbCollection;
+use http::{HeaderName, HeaderValue, StatusCode};
+use once_cell::sync::Lazy;
+use rhai::{
+    AST, Array, Dynamic, Engine, EvalAltResult, Map as RhaiMap, Scope,
+    serde::{from_dynamic, to_dynamic},
+};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    app::App,
+    handlers::{
+        Change, ChangeEvents, CollectionDefaults, Partition, Transaction, Transactions,
+        client_cert_json, error_response, grouped, headers_json, id_string, is_json_content_type,
+        query,
+    },
+    random,
+};
+
+/// Operations a script may run per request, so an endless loop fails instead
+/// of holding the request forever.
+pub const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+static ENGINE: Lazy<Engine> = Lazy::new(script_engine);
+
+/// Response built by `response(status, body, headers)` in a script.
+#[derive(Debug, Clone)]
+struct ScriptResponse {
+    status: i64,
+    body: Dynamic,
+    headers: RhaiMap,
+}
+
+/// Collections of the partition a request resolved to, exposed as `db`.
+#[derive(Clone)]
+struct ScriptDb {
+    partition: Partition,
+    defaults: Arc<CollectionDefaults>,
+    transactions: Arc<Transactions>,
+    transaction: Arc<Mutex<Option<Transaction>>>,
+    events: Arc<ChangeEvents>,
+}
+
+/// A collection handle returned by `db.collection(name)`.
+#[derive(Clone)]
+struct ScriptCollection {
+    name: String,
+    collection: Arc<DbCollection>,
+    db: ScriptDb,
+}
+
+impl ScriptDb {
+    fn collection(&mut self, name: &str) -> ScriptCollection {
+        let collection = self
+            .partition
+            .db
+            .get(name)
+            .unwrap_or_else(|| self.partition.db.create(name));
+        ScriptCollection {
+            name: name.to_string(),
+            collection,
+            db: self.clone(),
+        }
+    }
+
+    fn begin(&mut self) -> ScriptResult<()> {
+        let mut transaction = self.transaction.lock().unwrap();
+        if transaction.is_some() {
+            return Err("a transaction is already open".into());
+        }
+        *transaction = Some(self.transactions.begin(&self.partition));
+        Ok(())
+    }
+
+    fn commit(&mut self) -> ScriptResult<()> {
+        let transaction = self.transaction.lock().unwrap().take();
+        let changes = transaction.ok_or("no transaction is open")?.commit();
+        self.events.publish_all(changes);
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> ScriptResult<()> {
+        let transaction = self.transaction.lock().unwrap().take();
+        transaction.ok_or("no transaction is open")?.rollback();
+        Ok(())
+    }
+
+    /// Lets the open transaction, if any, restore a collection written to.
+    fn track(&self, collection: &str) {
+        if let Some(transaction) = self.transaction.lock().unwrap().as_mut() {
+            transaction.track(collection);
+        }
+    }
+
+    /// Publishes a write, or defers it until the open transaction commits.
+    fn publish(&self, change: Change) {
+        match self.transaction.lock().unwrap().as_mut() {
+            Some(transaction) => transaction.defer(change),
+            None => self.events.publish(change),
+        }
+    }
+
+    fn query(&mut self, sql: &str) -> ScriptResult<Dynamic> {
+        let rows = self
+            .partition
+            .db
+            .query(sql)
+            .map_err(|err| format!("{:?}", err))?;
+        to_dynamic(rows)
+    }
+}
+
+impl ScriptCollection {
+    fn id_key(&self) -> ScriptResult<String> {
+        Ok(self
+            .collection
+            .get_config()
+            .map_err(|err| err.to_string())?
+            .id_key)
+    }
+
+    fn all(&mut self) -> ScriptResult<Array> {
+        let mut items = self.collection.get_all().map_err(|err| err.to_string())?;
+        let id_key = self.id_key()?;
+        self.db
+            .partition
+            .collection_order
+            .sort(&self.name, &id_key, &mut items);
+        items.into_iter().map(to_dynamic).collect()
+    }
+
+    fn get(&mut self, id: Dynamic) -> ScriptResult<Dynamic> {
+        let item = self
+            .collection
+            .get(&id.to_string())
+            .map_err(|err| err.to_string())?;
+        to_dynamic(item)
+    }
+
+    fn exists(&mut self, id: Dynamic) -> ScriptResult<bool> {
+        Ok(self
+            .collection
+            .exists(&id.to_string())
+            .map_err(|err| err.to_string())?)
+    }
+
+    fn count(&mut self) -> ScriptResult<i64> {
+        let count = self.collection.count().map_err(|err| err.to_string())?;
+        Ok(count as i64)
+    }
+
+    fn add(&mut self, item: Dynamic) -> ScriptResult<Dynamic> {
+        self.db.track(&self.name);
+        let item = self
+            .db
+            .defaults
+            .apply_create(&self.name, from_dynamic(&item)?);
+        let item = random::current()
+            .add_item(&self.collection, item)
+            .map_err(|err| err.to_string())?;
+        if let Some(id) = item.get(self.id_key()?).and_then(id_string) {
+            self.db.publish(Change::created(&self.name, &id, &item));
+            self.db.partition.collection_order.push(&self.name, id);
+        }
+        to_dynamic(item)
+    }
+
+    fn update(&mut self, id: Dynamic, item: Dynamic) -> ScriptResult<Dynamic> {
+        self.db.track(&self.name);
+        let item = self
+            .db
+            .defaults
+            .apply_update(&self.name, from_dynamic(&item)?);
+        let id = id.to_string();
+        let before = self.collection.get(&id).map_err(|err| err.to_string())?;
+        let item = self
+            .collection
+            .update(&id, item)
+            .map_err(|err| err.to_string())?;
+        if let Some(item) = &item {
+            self.db
+                .publish(Change::updated(&self.name, &id, before, item));
+        }
+        to_dynamic(item)
+    }
+
+    fn patch(&mut self, id: Dynamic, fields: Dynamic) -> ScriptResult<Dynamic> {
+        self.db.track(&self.name);
+        let fields = self
+            .db
+            .defaults
+            .apply_update(&self.name, from_dynamic(&fields)?);
+        let id = id.to_string();
+        let before = self.collection.get(&id).map_err(|err| err.to_string())?;
+        let item = self
+            .collection
+            .update_partial(&id, fields)
+            .map_err(|err| err.to_string())?;
+        if let Some(item) = &item {
+            self.db
+                .publish(Change::updated(&self.name, &id, before, item));
+        }
+        to_dynamic(item)
+    }
+
+    fn remove(&mut self, id: Dynamic) -> ScriptResult<Dynamic> {
+        self.db.track(&self.name);
+        let id = id.to_string();
+        let item = self.collection.delete(&id).map_err(|err| err.to_string())?;
+        if let Some(item) = &item {
+            self.db.publish(Change::deleted(&self.name, &id, item));
+            self.db.partition.collection_order.remove(&self.name, &id);
+        }
+        to_dynamic(item)
+    }
+}
+
+fn script_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+
+    engine
+        .register_type_with_name::<ScriptResponse>("Response")
+        .register_fn("response", |status: i64| ScriptResponse {
+            status,
+            body: Dynamic::UNIT,
+            headers: RhaiMap::new(),
+        })
+        .register_fn("response", |status: i64, body: Dynamic| ScriptResponse {
+            status,
+            body,
+            headers: RhaiMap::new(),
+        })
+        .register_fn(
+            "response",
+            |status: i64, body: Dynamic, headers: RhaiMap| ScriptResponse {
+                status,
+                body,
+                headers,
+            },
+        );
+
+    engine
+        .register_type_with_name::<ScriptDb>("Db")
+        .register_fn("collection", ScriptDb::collection)
+        .register_fn("begin", ScriptDb::begin)
+        .register_fn("commit", ScriptDb::commit)
+        .register_fn("rollback", ScriptDb::rollback)
+        .register_fn("query", ScriptDb::query);
+
+    engine
+        .register_type_with_name::<ScriptCollection>("Collection")
+        .register_fn("all", ScriptCollection::all)
+        .register_fn("get", ScriptCollection::get)
+        .register_fn("exists", ScriptCollection::exists)
+        .register_fn("count", ScriptCollection::count)
+        .register_fn("add", ScriptCollection::add)
+        .register_fn("update", ScriptCollection::update)
+        .register_fn("patch", ScriptCollection::patch)
+        .register_fn("remove", ScriptCollection::remove);
+
+    engine
+}
+
+/// Compiles a script file, returning the error message when it cannot be
+/// read or parsed.
+pub fn compile_script(file_path: &OsString) -> Result<AST, String> {
+    let script = fs::read_to_string(file_path).map_err(|err| err.to_string())?;
+    ENGINE.compile(script).map_err(|err| err.to_string())
+}
+
+/// Builds a router that runs a `.rhai` script for every request.
+///
+/// A script that does not compile is reported once and answers
+/// `500 Internal Server Error` with the compile error.
+pub fn build_script_router(app: &mut App, file_path: OsString, method: &str) -> MethodRouter {
+    let script = compile_script(&file_path).map(Arc::new);
+    if let Err(err) = &script {
+        app.log(format!(
+            "⚠️ Unable to compile the script {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ));
+    }
+    let shared = app.shared_partition();
+    let partitions = Arc::clone(&app.partitions);
+    let defaults = Arc::clone(&app.collection_defaults);
+    let transactions = Arc::clone(&app.transactions);
+    let events = Arc::clone(&app.change_events);
+
+    let handler = move |req: Request| {
+        let script = script.clone();
+        let db = ScriptDb {
+            partition: partitions.resolve(&shared, req.headers()),
+            defaults: Arc::clone(&defaults),
+            transactions: Arc::clone(&transactions),
+            transaction: Arc::default(),
+            events: Arc::clone(&events),
+        };
+        async move {
+            match script {
+                Ok(ast) => run_script(&ast, db, req).await,
+                Err(err) => script_error(err),
+            }
+        }
+    };
+
+    match method.to_uppercase().as_str() {
+        "GET" => get(handler),
+        "POST" => post(handler),
+        "PUT" => put(handler),
+        "PATCH" => patch(handler),
+        "DELETE" => delete(handler),
+        "OPTIONS" => options(handler),
+        "QUERY" => query(handler),
+        // Fallback for an unknown method string
+        _ => get(|| async { "Unknown method in filename" }),
+    }
+}
+
+async fn run_script(ast: &AST, db: ScriptDb, req: Request) -> Response {
+    let (mut parts, body) = req.into_parts();
+    let params = RawPathParams::from_request_parts(&mut parts, &())
+        .await
+        .map(|params| {
+            params
+                .iter()
+                .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+                .collect::<Map<_, _>>()
+        })
+        .unwrap_or_default();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_body",
+            "The request body could not be read",
+        );
+    };
+    let query = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+        .map(|Query(query)| query)
+        .unwrap_or_default();
+    let body = if bytes.is_empty() {
+        Value::Null
+    } else if is_json_content_type(&parts.headers) {
+        serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).to_string()))
+    } else {
+        Value::String(String::from_utf8_lossy(&bytes).to_string())
+    };
+    let request = json!({
+        "method": parts.method.as_str(),
+        "path": parts.uri.path(),
+        "params": params,
+        "query": grouped(query),
+        "headers": headers_json(&parts.headers),
+        "body": body,
+        "client_cert": client_cert_json(&parts.extensions),
+    });
+
+    let transaction = Arc::clone(&db.transaction);
+    let result = to_dynamic(request).and_then(|request| {
+        let mut scope = Scope::new();
+        scope.push("request", request);
+        scope.push("db", db);
+        ENGINE.eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+    });
+    if let Some(transaction) = transaction.lock().unwrap().take() {
+        transaction.rollback();
+    }
+    match result {
+        Ok(value) => script_response(value),
+        Err(err) => script_error(err.to_string()),
+    }
+}
+
+/// Turns the value of a script into a response.
+fn script_response(value: Dynamic) -> Response {
+    let response = if value.is::<ScriptResponse>() {
+        value.cast::<ScriptResponse>()
+    } else {
+        let status = if value.is_unit() { 204 } else { 200 };
+        ScriptResponse {
+            status,
+            body: value,
+            headers: RhaiMap::new(),
+        }
+    };
+
+    let Some(status) = u16::try_from(response.status)
+        .ok()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+    else {
+        return script_error(format!("{} is not an HTTP status", response.status));
+    };
+    let mut http_response = if response.body.is_unit() {
+        status.into_response()
+    } else if response.body.is_string() {
+        (status, response.body.to_string()).into_response()
+    } else {
+        match from_dynamic::<Value>(&response.body) {
+            Ok(body) => (status, Json(body)).into_response(),
+            Err(err) => return script_error(err.to_string()),
+        }
+    };
+
+    for (name, value) in response.headers {
+        let (Ok(name), Ok(value)) = (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::try_from(value.to_string()),
+        ) else {
+            return script_error(format!("`{}` is not a valid response header", name));
+        };
+        http_response.headers_mut().insert(name, value);
+    }
+    http_response
+}
+
+fn script_error(message: impl Into<String>) -> Response {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "script_error", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::ChangeKind;
+    use axum::{Router, body::Body};
+    use fosk::DbConfig;
+    use tower::ServiceExt;
+
+    fn script_router(app: &mut App, script: &str) -> Router {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("post{id}.rhai");
+        std::fs::write(&file_path, script).unwrap();
+
+        let router = build_script_router(app, file_path.into_os_string(), "POST");
+        app.route("/orders/{id}", router, Some("POST"), None);
+        app.take_router_for_test()
+    }
+
+    async fn send(router: &Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    async fn run(script: &str) -> (StatusCode, String) {
+        let router = script_router(&mut App::default(), script);
+        send(&router, post("/orders/1", "")).await
+    }
+
+    fn post(uri: &str, body: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn scripts_read_the_request_and_write_collections() {
+        let mut app = App::default();
+        let orders = app.db.create_with_config("orders", DbConfig::int("id"));
+        let script = r#"
+            let order = request.body;
+            if order.items.len() == 0 {
+                return response(422, #{ error: "empty", ref: request.params.id });
+            }
+            order.customer = request.query.customer;
+            let saved = db.collection("orders").add(order);
+            response(201, saved, #{ "Location": `/orders/${saved.id}` })
+        "#;
+
+        let router = script_router(&mut app, script);
+
+        let (status, body) =
+            send(&router, post("/orders/7?customer=ada", r#"{"items":[1]}"#)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!({ "id": 1, "items": [1], "customer": "ada" })
+        );
+        assert_eq!(orders.count().unwrap(), 1);
+
+        let (status, body) = send(&router, post("/orders/7", r#"{"items":[]}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!({ "error": "empty", "ref": "7" })
+        );
+    }
+
+    #[tokio::test]
+    async fn transactions_roll_back_every_write_on_failure() {
+        let mut app = App::default();
+        let products = app.db.create_with_config("products", DbConfig::none("id"));
+        products.add(json!({ "id": "p1", "stock": 1 })).unwrap();
+        let script = r#"
+            db.begin();
+            let products = db.collection("products");
+            let product = products.get("p1");
+            products.patch("p1", #{ stock: product.stock - request.body.quantity });
+            db.collection("orders").add(#{ product: "p1", quantity: request.body.quantity });
+            if products.get("p1").stock < 0 {
+                db.rollback();
+                return response(409, #{ error: "out_of_stock" });
+            }
+            db.commit();
+            response(201)
+        "#;
+
+        let router = script_router(&mut app, script);
+        let mut events = app.change_events.subscribe();
+
+        let (status, _) = send(&router, post("/orders/1", r#"{"quantity":2}"#)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(products.get("p1").unwrap().unwrap()["stock"], 1);
+        assert_eq!(app.db.get("orders").unwrap().count().unwrap(), 0);
+        assert!(events.try_recv().is_err());
+
+        let (status, _) = send(&router, post("/orders/1", r#"{"quantity":1}"#)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(products.get("p1").unwrap().unwrap()["stock"], 0);
+        assert_eq!(app.db.get("orders").unwrap().count().unwrap(), 1);
+        let updated = events.try_recv().unwrap();
+        assert_eq!(updated.kind, ChangeKind::Updated);
+        assert_eq!(updated.before.unwrap()["stock"], 1);
+        assert_eq!(events.try_recv().unwrap().kind, ChangeKind::Created);
+    }
+
+    #[tokio::test]
+    async fn unfinished_transactions_are_rolled_back() {
+        let mut app = App::default();
+        let script = r#"
+            db.begin();
+            db.collection("orders").add(#{ product: "p1" });
+            throw "validation failed";
+        "#;
+
+        let router = script_router(&mut app, script);
+
+        let (status, _) = send(&router, post("/orders/1", "{}")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(app.db.get("orders").unwrap().count().unwrap(), 0);
+        let (status, _) = send(&router, post("/orders/1", "{}")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn script_values_become_responses() {
+        let (status, body) = run(r#""pong""#).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "pong"));
+
+        let (status, body) = run("()").await;
+        assert_eq!((status, body.as_str()), (StatusCode::NO_CONTENT, ""));
+
+        let (status, body) = run("#{ ok: true }").await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, r#"{"ok":true}"#));
+    }
+
+    #[tokio::test]
+    async fn script_errors_answer_500() {
+        let (status, body) = run("let x = ;").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body.contains("script_error"));
+
+        let (status, body) = run("loop {}").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body.contains("script_error"));
+
+        let (status, _) = run("response(1000)").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,256 @@
+//! Response sequences that serve `get.1.json`, `get.2.json`, ... on successive
+//! calls and `get.rest.json` once the numbered files are exhausted, or pick
+//! among them in turn or at random with a route `strategy`.
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{
+    Json,
+    extract::Request,
+    response::IntoResponse,
+    routing::{MethodRouter, delete, get, options, patch, post, put},
+};
+use rand::{Rng, RngCore};
+use serde_json::json;
+use tower::ServiceExt;
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE},
+    handlers::query,
+    random,
+    route_builder::{SequenceStep, config::ResponseStrategy},
+};
+
+/// Call counters of every registered response sequence.
+#[derive(Debug, Default)]
+pub struct Sequences {
+    counters: Mutex<Vec<Arc<AtomicUsize>>>,
+}
+
+impl Sequences {
+    /// Creates the call counter of a new sequence.
+    pub fn register(&self) -> Arc<AtomicUsize> {
+        let counter = Arc::new(AtomicUsize::new(0));
+        self.counters.lock().unwrap().push(Arc::clone(&counter));
+        counter
+    }
+
+    /// Restarts every sequence at its first call and returns how many there are.
+    pub fn reset(&self) -> usize {
+        let counters = self.counters.lock().unwrap();
+        for counter in counters.iter() {
+            counter.store(0, Ordering::SeqCst);
+        }
+        counters.len()
+    }
+
+    /// Returns the number of calls each sequence has answered.
+    pub fn calls(&self) -> Vec<usize> {
+        self.counters
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|counter| counter.load(Ordering::SeqCst))
+            .collect()
+    }
+
+    /// Sets the number of calls each sequence has answered, as returned by [`Sequences::calls`].
+    pub fn restore_calls(&self, calls: &[usize]) {
+        let counters = self.counters.lock().unwrap();
+        for (counter, calls) in counters.iter().zip(calls) {
+            counter.store(*calls, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns true when no sequence is registered.
+    pub fn is_empty(&self) -> bool {
+        self.counters.lock().unwrap().is_empty()
+    }
+}
+
+/// Returns the index of the step served on `call`, counting from `1`.
+///
+/// A numbered step is served from its call until the next numbered step.
+/// After the last numbered step, the `rest` step takes over when there is one.
+fn select_step(steps: &[SequenceStep], call: usize) -> usize {
+    let rest = steps.iter().position(|step| *step == SequenceStep::Rest);
+    let last_call = steps
+        .iter()
+        .filter_map(|step| match step {
+            SequenceStep::Call(call) => Some(*call as usize),
+            SequenceStep::Rest => None,
+        })
+        .max();
+    if let Some(rest) = rest
+        && last_call.is_none_or(|last_call| call > last_call)
+    {
+        return rest;
+    }
+
+    steps
+        .iter()
+        .rposition(|step| matches!(step, SequenceStep::Call(step) if *step as usize <= call))
+        .or(rest)
+        .unwrap_or(0)
+}
+
+/// Returns the index of the step served on `call`, counting from `1`, as
+/// `strategy` picks it.
+fn select_with_strategy(
+    strategy: &ResponseStrategy,
+    steps: &[SequenceStep],
+    call: usize,
+    rng: &mut dyn RngCore,
+) -> usize {
+    match strategy {
+        ResponseStrategy::Sequence => select_step(steps, call),
+        ResponseStrategy::RoundRobin => (call - 1) % steps.len(),
+        ResponseStrategy::Random => rng.random_range(0..steps.len()),
+        ResponseStrategy::Weighted(weights) => {
+            // Files without a weight are never picked.
+            let weights = &weights[..weights.len().min(steps.len())];
+            let total = weights.iter().map(|weight| u64::from(*weight)).sum::<u64>();
+            if total == 0 {
+                return 0;
+            }
+            let mut draw = rng.random_range(0..total);
+            weights
+                .iter()
+                .position(|weight| {
+                    let weight = u64::from(*weight);
+                    if draw < weight {
+                        return true;
+                    }
+                    draw -= weight;
+                    false
+                })
+                .unwrap_or(0)
+        }
+    }
+}
+
+/// Builds a router that serves the router of the step `strategy` picks on
+/// every call, the next step by default.
+///
+/// `steps` must be ordered by step and not empty.
+pub fn build_sequence_router(
+    app: &App,
+    steps: Vec<(SequenceStep, MethodRouter)>,
+    method: &str,
+    strategy: ResponseStrategy,
+) -> MethodRouter {
+    let calls = app.sequences.register();
+    let steps = Arc::new(steps);
+    let strategy = Arc::new(strategy);
+    let handler = move |req: Request| {
+        let calls = Arc::clone(&calls);
+        let steps = Arc::clone(&steps);
+        let strategy = Arc::clone(&strategy);
+        async move {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let step_list = steps.iter().map(|(step, _)| *step).collect::<Vec<_>>();
+            let index = random::current()
+                .with_rng(|rng| select_with_strategy(&strategy, &step_list, call, rng));
+            let (_, router) = &steps[index];
+            router
+                .clone()
+                .oneshot(req)
+                .await
+                .unwrap_or_else(|err| match err {})
+        }
+    };
+
+    match method.to_uppercase().as_str() {
+        "GET" => get(handler),
+        "POST" => post(handler),
+        "PUT" => put(handler),
+        "PATCH" => patch(handler),
+        "DELETE" => delete(handler),
+        "OPTIONS" => options(handler),
+        "QUERY" => query(handler),
+        // Fallback for an unknown method string
+        _ => get(|| async { "Unknown method in filename" }),
+    }
+}
+
+/// Registers `POST /mock-server/sequences/reset`, which restarts every
+/// response sequence at its first call.
+pub fn create_sequence_routes(app: &mut App) {
+    let sequence_route = format!("{}/sequences/reset", MOCK_SERVER_ROUTE);
+    let sequences = Arc::clone(&app.sequences);
+
+    let reset_router = post(async move || {
+        let reset = sequences.reset();
+        Json(json!({ "reset": reset })).into_response()
+    });
+    app.route(&sequence_route, reset_router, Some("POST"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SequenceStep::{Call, Rest};
+    use rand::SeedableRng;
+
+    #[test]
+    fn select_step_walks_numbered_steps_then_rest() {
+        let steps = [Call(1), Call(2), Rest];
+        let served = (1..=5)
+            .map(|call| select_step(&steps, call))
+            .collect::<Vec<_>>();
+        assert_eq!(served, vec![0, 1, 2, 2, 2]);
+
+        let without_rest = [Call(1), Call(3)];
+        let served = (1..=4)
+            .map(|call| select_step(&without_rest, call))
+            .collect::<Vec<_>>();
+        assert_eq!(served, vec![0, 0, 1, 1]);
+
+        let late_start = [Call(2), Rest];
+        assert_eq!(select_step(&late_start, 1), 1);
+        assert_eq!(select_step(&late_start, 2), 0);
+        assert_eq!(select_step(&late_start, 3), 1);
+    }
+
+    #[test]
+    fn strategies_cycle_or_draw_among_the_files() {
+        let steps = [Call(1), Call(2), Rest];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut pick = |strategy: &ResponseStrategy, call| {
+            select_with_strategy(strategy, &steps, call, &mut rng)
+        };
+
+        let served = (1..=5)
+            .map(|call| pick(&ResponseStrategy::RoundRobin, call))
+            .collect::<Vec<_>>();
+        assert_eq!(served, vec![0, 1, 2, 0, 1]);
+
+        let weighted = ResponseStrategy::Weighted(vec![90, 10]);
+        let mut counts = [0; 3];
+        for call in 1..=1000 {
+            counts[pick(&weighted, call)] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        assert!((850..=950).contains(&counts[0]), "{:?}", counts);
+
+        assert!((1..=20).all(|call| pick(&ResponseStrategy::Random, call) < 3));
+    }
+
+    #[test]
+    fn reset_restarts_every_sequence() {
+        let sequences = Sequences::default();
+        assert!(sequences.is_empty());
+
+        let first = sequences.register();
+        let second = sequences.register();
+        first.store(3, Ordering::SeqCst);
+        second.store(1, Ordering::SeqCst);
+
+        assert_eq!(sequences.reset(), 2);
+        assert_eq!(first.load(Ordering::SeqCst), 0);
+        assert_eq!(second.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,187 @@
+//! Named snapshots of the in-memory state, so a test suite can prepare a
+//! state once and return to it quickly instead of re-seeding.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json,
+    extract::Path as AxumPath,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use http::StatusCode;
+use serde_json::json;
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE},
+    handlers::{Partition, PartitionKey, Partitions, Sequences, error_response},
+};
+
+/// State captured by a snapshot.
+struct StateSnapshot {
+    shared: Partition,
+    partitions: HashMap<PartitionKey, Partition>,
+    sequence_calls: Vec<usize>,
+}
+
+/// Snapshots taken so far, by name.
+#[derive(Default)]
+pub struct StateSnapshots {
+    snapshots: Mutex<HashMap<String, StateSnapshot>>,
+}
+
+/// State a snapshot captures and a restore replaces.
+#[derive(Clone)]
+pub struct MockState {
+    /// Collections shared by requests without a tenant or session.
+    pub shared: Partition,
+    /// Tenant and session copies of the collections.
+    pub partitions: Arc<Partitions>,
+    /// Call counters of the response sequences.
+    pub sequences: Arc<Sequences>,
+}
+
+impl MockState {
+    /// Captures the state of an application.
+    pub fn new(app: &App) -> Self {
+        Self {
+            shared: app.shared_partition(),
+            partitions: Arc::clone(&app.partitions),
+            sequences: Arc::clone(&app.sequences),
+        }
+    }
+}
+
+impl StateSnapshots {
+    /// Stores a copy of the current state under `name`, replacing any snapshot
+    /// of the same name, and returns the number of shared collections copied.
+    pub fn take(&self, name: &str, state: &MockState) -> usize {
+        let snapshot = StateSnapshot {
+            shared: state.shared.copy(),
+            partitions: state.partitions.snapshot(),
+            sequence_calls: state.sequences.calls(),
+        };
+        let collections = snapshot.shared.db.list_collections().len();
+        self.snapshots
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), snapshot);
+        collections
+    }
+
+    /// Replaces the current state with the snapshot stored under `name`, and
+    /// returns the number of shared collections restored, or `None` when there
+    /// is no such snapshot.
+    ///
+    /// The snapshot is kept, so it can be restored again.
+    pub fn restore(&self, name: &str, state: &MockState) -> Option<usize> {
+        let snapshots = self.snapshots.lock().unwrap();
+        let snapshot = snapshots.get(name)?;
+        state.shared.restore(&snapshot.shared);
+        state.partitions.restore(&snapshot.partitions);
+        state.sequences.restore_calls(&snapshot.sequence_calls);
+        Some(snapshot.shared.db.list_collections().len())
+    }
+
+    /// Removes every snapshot.
+    pub fn clear(&self) {
+        self.snapshots.lock().unwrap().clear();
+    }
+}
+
+/// Registers `POST /mock-server/state/snapshot/{name}` and
+/// `POST /mock-server/state/restore/{name}`.
+pub fn create_state_routes(app: &mut App) {
+    let snapshot_route = format!("{}/state/snapshot/{{name}}", MOCK_SERVER_ROUTE);
+    let snapshots = Arc::clone(&app.snapshots);
+    let state = MockState::new(app);
+    let snapshot_router = post(async move |AxumPath(name): AxumPath<String>| {
+        let collections = snapshots.take(&name, &state);
+        Json(json!({ "name": name, "collections": collections })).into_response()
+    });
+    app.route(&snapshot_route, snapshot_router, Some("POST"), None);
+
+    let restore_route = format!("{}/state/restore/{{name}}", MOCK_SERVER_ROUTE);
+    let snapshots = Arc::clone(&app.snapshots);
+    let state = MockState::new(app);
+    let restore_router = post(async move |AxumPath(name): AxumPath<String>| {
+        match snapshots.restore(&name, &state) {
+            Some(collections) => {
+                Json(json!({ "name": name, "collections": collections })).into_response()
+            }
+            None => snapshot_not_found(&name),
+        }
+    });
+    app.route(&restore_route, restore_router, Some("POST"), None);
+}
+
+fn snapshot_not_found(name: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "snapshot_not_found",
+        format!("No snapshot named '{}' was taken", name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use fosk::DbConfig;
+    use http::Request;
+    use serde_json::Value;
+    use std::sync::atomic::Ordering;
+    use tower::ServiceExt;
+
+    fn post_request(uri: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn restore_returns_collections_and_sequences_to_the_snapshot() {
+        let mut app = App::default();
+        let users = app.db.create_with_config("users", DbConfig::int("id"));
+        users.add(serde_json::json!({ "name": "Ada" })).unwrap();
+        let calls = app.sequences.register();
+        calls.store(2, Ordering::SeqCst);
+        create_state_routes(&mut app);
+        let router = app.take_router_for_test();
+
+        let response = router
+            .clone()
+            .oneshot(post_request("/mock-server/state/snapshot/prepared"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        users.add(serde_json::json!({ "name": "Grace" })).unwrap();
+        calls.store(5, Ordering::SeqCst);
+
+        for _ in 0..2 {
+            let response = router
+                .clone()
+                .oneshot(post_request("/mock-server/state/restore/prepared"))
+                .await
+                .unwrap();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body = serde_json::from_slice::<Value>(&bytes).unwrap();
+            assert_eq!(body["collections"], 1);
+            assert_eq!(users.count().unwrap(), 1);
+            assert_eq!(calls.load(Ordering::SeqCst), 2);
+            users.add(serde_json::json!({ "name": "Hopper" })).unwrap();
+        }
+
+        let missing = router
+            .oneshot(post_request("/mock-server/state/restore/unknown"))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+}
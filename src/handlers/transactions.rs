@@ -0,0 +1,398 @@
+//! Transactions over the collections of a partition, so a flow writing several
+//! collections, such as placing an order and decrementing stock, applies every
+//! write or none.
+//!
+//! A transaction copies each collection the first time it writes to it, and a
+//! rollback puts those copies back. Only one transaction is open at a time;
+//! `begin` waits for the open one to finish. Scripts open transactions with
+//! `db.begin()`, and `POST /mock-server/batch` runs a list of writes in one.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use axum::{
+    Json,
+    body::Bytes,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use fosk::Db;
+use http::{HeaderMap, StatusCode};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    app::{App, MOCK_SERVER_ROUTE},
+    criteria::Criteria,
+    handlers::{
+        Change, CollectionDefaults, Partition, error_response, id_string, read_only_response,
+    },
+    random,
+};
+
+/// Lets one transaction at a time write to the collections.
+#[derive(Debug, Default)]
+pub struct Transactions {
+    open: Mutex<bool>,
+    closed: Condvar,
+}
+
+impl Transactions {
+    /// Opens a transaction over the collections of `partition`, waiting for
+    /// the open transaction, if any, to commit or roll back.
+    pub fn begin(self: &Arc<Self>, partition: &Partition) -> Transaction {
+        let mut open = self.open.lock().unwrap();
+        while *open {
+            open = self.closed.wait(open).unwrap();
+        }
+        *open = true;
+
+        Transaction {
+            transactions: Arc::clone(self),
+            partition: partition.clone(),
+            saved: Partition {
+                db: Db::new_arc(),
+                collection_order: Arc::default(),
+            },
+            tracked: HashSet::new(),
+            changes: vec![],
+        }
+    }
+
+    fn close(&self) {
+        *self.open.lock().unwrap() = false;
+        self.closed.notify_one();
+    }
+}
+
+/// Writes to a partition that are undone unless committed.
+///
+/// Dropping a transaction without committing it rolls it back, and drops the
+/// changes deferred until commit.
+pub struct Transaction {
+    transactions: Arc<Transactions>,
+    partition: Partition,
+    saved: Partition,
+    tracked: HashSet<String>,
+    changes: Vec<Change>,
+}
+
+impl Transaction {
+    /// Copies a collection before the transaction first writes to it, so a
+    /// rollback can restore it. A collection created by the transaction is
+    /// emptied on rollback.
+    pub fn track(&mut self, collection: &str) {
+        if self.tracked.insert(collection.to_string()) {
+            self.saved.restore_collection(&self.partition, collection);
+        }
+    }
+
+    /// Holds a change until the transaction commits, so writes rolled back
+    /// are never published.
+    pub fn defer(&mut self, change: Change) {
+        self.changes.push(change);
+    }
+
+    /// Keeps every write of the transaction, and returns the deferred changes.
+    pub fn commit(mut self) -> Vec<Change> {
+        self.tracked.clear();
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Undoes every write of the transaction.
+    pub fn rollback(self) {}
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        for collection in &self.tracked {
+            self.partition.restore_collection(&self.saved, collection);
+        }
+        self.transactions.close();
+    }
+}
+
+/// Writes sent to `POST /mock-server/batch`, applied in order.
+#[derive(Debug, Deserialize)]
+struct Batch {
+    operations: Vec<BatchOperation>,
+}
+
+/// One write of a batch, or a check that an item matches a criteria.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum BatchOperation {
+    Add {
+        collection: String,
+        item: Value,
+    },
+    Update {
+        collection: String,
+        id: Value,
+        item: Value,
+    },
+    Patch {
+        collection: String,
+        id: Value,
+        fields: Value,
+    },
+    Remove {
+        collection: String,
+        id: Value,
+    },
+    Check {
+        collection: String,
+        id: Value,
+        #[serde(rename = "where")]
+        criteria: String,
+    },
+}
+
+impl BatchOperation {
+    fn collection(&self) -> &str {
+        match self {
+            BatchOperation::Add { collection, .. }
+            | BatchOperation::Update { collection, .. }
+            | BatchOperation::Patch { collection, .. }
+            | BatchOperation::Remove { collection, .. }
+            | BatchOperation::Check { collection, .. } => collection,
+        }
+    }
+
+    /// Returns true for operations that write their collection.
+    fn is_write(&self) -> bool {
+        !matches!(self, BatchOperation::Check { .. })
+    }
+
+    /// Applies the operation, records its change in `transaction`, and
+    /// returns the written or checked item.
+    fn apply(
+        &self,
+        partition: &Partition,
+        defaults: &CollectionDefaults,
+        transaction: &mut Transaction,
+    ) -> Result<Value, String> {
+        let name = self.collection();
+        if let BatchOperation::Add { item, .. } = self {
+            let collection = partition
+                .db
+                .get(name)
+                .unwrap_or_else(|| partition.db.create(name));
+            let item = random::current()
+                .add_item(&collection, defaults.apply_create(name, item.clone()))
+                .map_err(|err| err.to_string())?;
+            if let Ok(config) = collection.get_config()
+                && let Some(id) = item.get(&config.id_key).and_then(id_string)
+            {
+                transaction.defer(Change::created(name, &id, &item));
+                partition.collection_order.push(name, id);
+            }
+            return Ok(item);
+        }
+
+        let collection = partition
+            .db
+            .get(name)
+            .ok_or_else(|| format!("collection `{}` does not exist", name))?;
+        let (BatchOperation::Update { id, .. }
+        | BatchOperation::Patch { id, .. }
+        | BatchOperation::Remove { id, .. }
+        | BatchOperation::Check { id, .. }) = self
+        else {
+            unreachable!("adds are applied above");
+        };
+        let id = id_string(id).ok_or_else(|| format!("invalid id {}", id))?;
+        let before = collection.get(&id).map_err(|err| err.to_string())?;
+        let item = match self {
+            BatchOperation::Update { item, .. } => collection
+                .update(&id, defaults.apply_update(name, item.clone()))
+                .map_err(|err| err.to_string())?,
+            BatchOperation::Patch { fields, .. } => collection
+                .update_partial(&id, defaults.apply_update(name, fields.clone()))
+                .map_err(|err| err.to_string())?,
+            BatchOperation::Remove { .. } => {
+                let item = collection.delete(&id).map_err(|err| err.to_string())?;
+                if let Some(item) = &item {
+                    transaction.defer(Change::deleted(name, &id, item));
+                    partition.collection_order.remove(name, &id);
+                }
+                item
+            }
+            BatchOperation::Check { criteria, .. } => {
+                let parsed = Criteria::try_from(criteria.as_str())
+                    .map_err(|err| format!("invalid criteria `{}`: {}", criteria, err))?;
+                let item = collection.get(&id).map_err(|err| err.to_string())?;
+                if let Some(item) = &item
+                    && !parsed.matches(item)
+                {
+                    return Err(format!("{}/{} does not match `{}`", name, id, criteria));
+                }
+                item
+            }
+            BatchOperation::Add { .. } => unreachable!("adds are applied above"),
+        };
+        if let (BatchOperation::Update { .. } | BatchOperation::Patch { .. }, Some(item)) =
+            (self, &item)
+        {
+            transaction.defer(Change::updated(name, &id, before, item));
+        }
+        item.ok_or_else(|| format!("{}/{} does not exist", name, id))
+    }
+}
+
+/// Registers `POST /mock-server/batch`, which applies a list of writes in one
+/// transaction and rolls every write back when one of them fails.
+pub fn create_batch_routes(app: &mut App) {
+    let batch_route = format!("{}/batch", MOCK_SERVER_ROUTE);
+    let transactions = Arc::clone(&app.transactions);
+    let shared = app.shared_partition();
+    let partitions = Arc::clone(&app.partitions);
+    let defaults = Arc::clone(&app.collection_defaults);
+    let read_only = Arc::clone(&app.read_only_collections);
+    let events = Arc::clone(&app.change_events);
+
+    let batch_router = post(async move |headers: HeaderMap, body: Bytes| -> Response {
+        let batch = match serde_json::from_slice::<Batch>(&body) {
+            Ok(batch) => batch,
+            Err(err) => {
+                return error_response(StatusCode::BAD_REQUEST, "invalid_batch", err.to_string());
+            }
+        };
+
+        if let Some(operation) = batch
+            .operations
+            .iter()
+            .find(|operation| operation.is_write() && read_only.contains(operation.collection()))
+        {
+            return read_only_response(operation.collection());
+        }
+
+        let partition = partitions.resolve(&shared, &headers);
+        let mut transaction = transactions.begin(&partition);
+        let mut results = Vec::with_capacity(batch.operations.len());
+        for (index, operation) in batch.operations.iter().enumerate() {
+            transaction.track(operation.collection());
+            match operation.apply(&partition, &defaults, &mut transaction) {
+                Ok(item) => results.push(item),
+                Err(message) => {
+                    transaction.rollback();
+                    return error_response(
+                        StatusCode::CONFLICT,
+                        "batch_failed",
+                        format!(
+                            "Operation {} failed, no write was applied: {}",
+                            index, message
+                        ),
+                    );
+                }
+            }
+        }
+        events.publish_all(transaction.commit());
+        Json(json!({ "results": results })).into_response()
+    });
+    app.route(&batch_route, batch_router, Some("POST"), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use fosk::DbConfig;
+    use http::Request;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn partition() -> Partition {
+        let partition = Partition {
+            db: Db::new_arc(),
+            collection_order: Arc::default(),
+        };
+        let products = partition
+            .db
+            .create_with_config("products", DbConfig::none("id"));
+        products.add(json!({ "id": "p1", "stock": 1 })).unwrap();
+        partition
+    }
+
+    #[test]
+    fn rollbacks_restore_tracked_collections_and_empty_created_ones() {
+        let partition = partition();
+        let transactions = Arc::new(Transactions::default());
+
+        let mut transaction = transactions.begin(&partition);
+        transaction.track("products");
+        transaction.track("orders");
+        let products = partition.db.get("products").unwrap();
+        products
+            .update_partial("p1", json!({ "stock": 0 }))
+            .unwrap();
+        partition
+            .db
+            .create("orders")
+            .add(json!({ "product": "p1" }))
+            .unwrap();
+        transaction.rollback();
+
+        assert_eq!(products.get("p1").unwrap().unwrap()["stock"], 1);
+        assert_eq!(partition.db.get("orders").unwrap().count().unwrap(), 0);
+    }
+
+    #[test]
+    fn commits_keep_writes_and_release_the_next_transaction() {
+        let partition = partition();
+        let transactions = Arc::new(Transactions::default());
+
+        let mut transaction = transactions.begin(&partition);
+        transaction.track("products");
+        let products = partition.db.get("products").unwrap();
+        products
+            .update_partial("p1", json!({ "stock": 0 }))
+            .unwrap();
+        transaction.commit();
+
+        assert_eq!(products.get("p1").unwrap().unwrap()["stock"], 0);
+        drop(transactions.begin(&partition));
+    }
+
+    #[tokio::test]
+    async fn failing_batches_apply_no_write() {
+        let mut app = App::default();
+        let products = app.db.create_with_config("products", DbConfig::none("id"));
+        products.add(json!({ "id": "p1", "stock": 1 })).unwrap();
+        app.build_batch_route();
+        let router = app.take_router_for_test();
+
+        let send = |quantity: i64| {
+            let body = json!({ "operations": [
+                { "op": "patch", "collection": "products", "id": "p1", "fields": { "stock": 1 - quantity } },
+                { "op": "add", "collection": "orders", "item": { "product": "p1", "quantity": quantity } },
+                { "op": "check", "collection": "products", "id": "p1", "where": "stock >= 0" },
+            ] });
+            let request = Request::post("/mock-server/batch")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, body) = send(2).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "batch_failed");
+        assert_eq!(products.get("p1").unwrap().unwrap()["stock"], 1);
+        assert_eq!(app.db.get("orders").unwrap().count().unwrap(), 0);
+
+        let (status, body) = send(1).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"][1]["quantity"], 1);
+        assert_eq!(products.get("p1").unwrap().unwrap()["stock"], 0);
+        assert_eq!(app.db.get("orders").unwrap().count().unwrap(), 1);
+    }
+}
@@ -1,31 +1,305 @@
-use std::{ffi::OsStr, fs, path::Path};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
 use axum::{
-    extract::{Json, Multipart, Path as AxumPath},
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Json, Multipart, Path as AxumPath},
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, head, post},
 };
+use chrono::{DateTime, Utc};
 use http::{
-    HeaderMap, HeaderValue,
-    header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    HeaderMap, HeaderValue, Uri,
+    header::{ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, HOST, RANGE},
 };
 use mime_guess::from_path;
-use serde_json::Value;
+use rand::Rng;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio_rustls::TlsConnector;
+use tokio_util::io::ReaderStream;
 
 use crate::{
-    app::App,
-    route_builder::{FILE_NAME_PARAM, RouteUpload},
+    app::{App, MOCK_SERVER_ROUTE},
+    criteria::wildcard_matches,
+    handlers::{error_response, send_to},
+    random,
+    route_builder::{FILE_NAME_PARAM, RouteUpload, UploadScan, config::Retention},
+    tls::client_tls_config,
+    upload_configuration::UploadConfiguration,
 };
 
-fn create_upload_route(app: &mut App, upload_def: &RouteUpload) {
+/// Header carrying the current byte offset of a chunked upload.
+pub const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+/// Header advertising the resumable upload protocol version.
+pub const TUS_RESUMABLE_HEADER: &str = "tus-resumable";
+const TUS_VERSION: &str = "1.0.0";
+
+/// Metadata of the files uploaded to a folder since the server started, by
+/// generated file id.
+type UploadMetadata = Arc<Mutex<HashMap<String, Value>>>;
+
+/// Upload metadata of every upload folder, by folder path.
+///
+/// A hot reload hands the registry to the rebuilt app, so `/meta/{id}` and
+/// the scan status of earlier uploads survive it.
+#[derive(Debug, Default)]
+pub struct UploadRegistry {
+    folders: Mutex<HashMap<PathBuf, UploadMetadata>>,
+}
+
+impl UploadRegistry {
+    fn folder(&self, path: &Path) -> UploadMetadata {
+        Arc::clone(
+            self.folders
+                .lock()
+                .unwrap()
+                .entry(path.to_path_buf())
+                .or_default(),
+        )
+    }
+}
+
+pub(crate) fn is_safe_file_name(file_name: &str) -> bool {
+    !file_name.is_empty()
+        && file_name != "."
+        && file_name != ".."
+        && !file_name.contains(['/', '\\'])
+}
+
+fn offset_headers(offset: u64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(UPLOAD_OFFSET_HEADER, HeaderValue::from(offset));
+    headers.insert(TUS_RESUMABLE_HEADER, HeaderValue::from_static(TUS_VERSION));
+    headers
+}
+
+/// Parses a single `bytes=` range against a file length, returning the
+/// inclusive start and end offsets, or `None` when it can't be satisfied.
+pub(crate) fn parse_byte_range(range: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<u64>().ok().filter(|suffix| *suffix > 0)?;
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => (start.parse::<u64>().ok()?, len - 1),
+        (start, end) => (
+            start.parse::<u64>().ok()?,
+            end.parse::<u64>().ok()?.min(len - 1),
+        ),
+    };
+
+    (start <= end && start < len).then_some((start, end))
+}
+
+/// Wraps file contents in a response body, streaming it at roughly
+/// `rate` kilobytes per second when a bandwidth limit is configured.
+fn throttled_body(contents: Vec<u8>, rate: Option<u32>) -> Body {
+    let Some(rate) = rate else {
+        return Body::from(contents);
+    };
+
+    const TICKS_PER_SECOND: usize = 10;
+    let chunk_size = (rate as usize * 1024 / TICKS_PER_SECOND).max(1);
+    let (mut writer, reader) = tokio::io::duplex(chunk_size);
+
+    tokio::spawn(async move {
+        for chunk in contents.chunks(chunk_size) {
+            if writer.write_all(chunk).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(
+                1000 / TICKS_PER_SECOND as u64,
+            ))
+            .await;
+        }
+    });
+
+    Body::from_stream(ReaderStream::new(reader))
+}
+
+fn is_config_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .eq_ignore_ascii_case("toml")
+}
+
+/// Returns when an uploaded file expires, counting `retention` from its last
+/// write, or `None` when it is kept until shutdown.
+fn expires_at(file_path: &Path, retention: Option<Retention>) -> Option<SystemTime> {
+    let Some(Retention::For(duration)) = retention else {
+        return None;
+    };
+    let modified = fs::metadata(file_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    Some(modified + duration)
+}
+
+fn is_expired(file_path: &Path, retention: Option<Retention>) -> bool {
+    expires_at(file_path, retention).is_some_and(|expires_at| expires_at <= SystemTime::now())
+}
+
+/// Deletes the files of `folder` written longer than `retention` ago, keeping
+/// TOML config files, and returns how many were deleted.
+pub fn sweep_expired_uploads(folder: &Path, retention: Duration) -> usize {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && !is_config_file(path))
+        .filter(|path| is_expired(path, Some(Retention::For(retention))))
+        .filter(|path| match fs::remove_file(path) {
+            Ok(()) => {
+                println!("🗑️ Deleted expired upload: {}", path.display());
+                true
+            }
+            Err(err) => {
+                eprintln!("⚠️ Failed to delete file {}: {}", path.display(), err);
+                false
+            }
+        })
+        .count()
+}
+
+/// Deletes the expired files of an upload folder in the background, checking
+/// every tenth of the retention, between one second and one minute.
+fn spawn_retention_sweep(app: &mut App, upload_def: &RouteUpload) {
+    let Some(Retention::For(retention)) = upload_def.retention else {
+        return;
+    };
+    let folder = PathBuf::from(&upload_def.path);
+    let period = (retention / 10).clamp(Duration::from_secs(1), Duration::from_secs(60));
+    app.spawn_background(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let folder = folder.clone();
+            let _ = tokio::task::spawn_blocking(move || sweep_expired_uploads(&folder, retention))
+                .await;
+        }
+    });
+}
+
+/// Describes a file saved by the upload route.
+fn file_metadata(id: &str, file_name: &str, mime_type: &str, data: &[u8], url: String) -> Value {
+    json!({
+        "id": id,
+        "original_name": file_name,
+        "size": data.len(),
+        "mime_type": mime_type,
+        "sha256": format!("{:x}", Sha256::digest(data)),
+        "url": url,
+    })
+}
+
+/// Verdict of a simulated scan: `infected` for file names matching a pattern,
+/// and for the configured share of the others, `clean` otherwise.
+fn scan_verdict(scan: &UploadScan, file_name: &str) -> &'static str {
+    let file_name = file_name.to_lowercase();
+    let infected = scan
+        .infected
+        .iter()
+        .any(|pattern| wildcard_matches(&file_name, &pattern.to_lowercase(), '*', '?'))
+        || (scan.infected_percent > 0
+            && random::current().with_rng(|rng| rng.random_range(0..100) < scan.infected_percent));
+    if infected { "infected" } else { "clean" }
+}
+
+/// Time a scan webhook has to accept the verdict.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends `body` as a JSON `POST` to an `http://` or `https://` URL, ignoring
+/// the response, and gives up after `timeout`.
+async fn send_webhook(url: &str, body: &Value, timeout: Duration) -> Result<(), String> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|err: http::uri::InvalidUri| err.to_string())?;
+    let tls = match uri.scheme_str() {
+        Some("http") => None,
+        Some("https") => Some(TlsConnector::from(Arc::new(
+            client_tls_config(None).map_err(|err| err.to_string())?,
+        ))),
+        _ => return Err("the webhook must be an http:// or https:// URL".to_string()),
+    };
+    let authority = uri.authority().ok_or("the URL has no host")?;
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    let request = http::Request::post(path)
+        .header(HOST, authority.as_str())
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .map_err(|err| err.to_string())?;
+
+    match tokio::time::timeout(timeout, send_to(authority, tls.as_ref(), request)).await {
+        Ok(sent) => sent.map(|_| ()),
+        Err(_) => Err(format!("no answer within {:?}", timeout)),
+    }
+}
+
+/// Scans an uploaded file in the background: after the scan delay, records
+/// the verdict in its metadata and posts it to the webhook.
+fn start_scan(
+    scan: &UploadScan,
+    metadata: UploadMetadata,
+    id: String,
+    file_name: String,
+    quiet: bool,
+) {
+    let scan = scan.clone();
+    // Spawned tasks leave the request scope, so the server's source is carried over.
+    tokio::spawn(random::scope(random::current(), async move {
+        tokio::time::sleep(Duration::from_millis(scan.delay)).await;
+        let status = scan_verdict(&scan, &file_name);
+        let file = {
+            let mut metadata = metadata.lock().unwrap();
+            let Some(file) = metadata.get_mut(&id) else {
+                return;
+            };
+            file["scan_status"] = json!(status);
+            file["scanned_at"] = json!(Utc::now().to_rfc3339());
+            file.clone()
+        };
+        if !quiet {
+            println!("🦠 Scanned upload '{}': {}", file_name, status);
+        }
+
+        let event = json!({ "event": "upload.scanned", "file": file });
+        if let Some(webhook) = &scan.webhook
+            && let Err(err) = send_webhook(webhook, &event, WEBHOOK_TIMEOUT).await
+        {
+            eprintln!("⚠️ Failed to send the scan webhook to {}: {}", webhook, err);
+        }
+    }));
+}
+
+fn create_upload_route(app: &mut App, upload_def: &RouteUpload, metadata: UploadMetadata) {
     let route = upload_def.get_upload_route();
     let download_route = upload_def.get_download_route();
-    let upload_path = upload_def.path.to_string_lossy().to_string();
+    let upload_path = PathBuf::from(&upload_def.path);
+    let scan = upload_def.scan.clone();
+    let quiet = app.quiet;
 
     // POST /uploads - create new
     let uploads_router = post(async move |mut multipart: Multipart| {
         let mut file_name = "".to_string();
+        let mut files = vec![];
 
         while let Some(field) = multipart.next_field().await.unwrap() {
             let field_name = field.name().unwrap_or("file").to_string();
@@ -33,6 +307,10 @@ fn create_upload_route(app: &mut App, upload_def: &RouteUpload) {
                 .file_name()
                 .map(|name| name.to_string())
                 .unwrap_or_else(|| "uploaded_file.bin".to_string());
+            let mime_type = field
+                .content_type()
+                .map(|mime_type| mime_type.to_string())
+                .unwrap_or_else(|| from_path(&file_name).first_or_octet_stream().to_string());
 
             let data = field.bytes().await.unwrap();
 
@@ -44,23 +322,32 @@ fn create_upload_route(app: &mut App, upload_def: &RouteUpload) {
             );
 
             // Save the file with its original name
-            let file_path = format!("{}/{}", upload_path, file_name);
+            let file_path = upload_path.join(&file_name);
             tokio::fs::write(&file_path, &data).await.unwrap();
+
+            let id = uuid::Uuid::new_v4().to_string();
+            let url = download_route.replace(FILE_NAME_PARAM, &file_name);
+            let mut file = file_metadata(&id, &file_name, &mime_type, &data, url);
+            if scan.is_some() {
+                file["scan_status"] = json!("pending");
+            }
+            metadata.lock().unwrap().insert(id.clone(), file.clone());
+            files.push(file);
+            if let Some(scan) = &scan {
+                start_scan(scan, Arc::clone(&metadata), id, file_name.clone(), quiet);
+            }
         }
-        let response = Value::Object({
-            let mut map = serde_json::Map::new();
-            map.insert("status".to_string(), Value::String("success".to_string()));
-            map.insert(
-                "message".to_string(),
-                Value::String("File uploaded successfully".to_string()),
-            );
-            map.insert("filename".to_string(), Value::String(file_name.clone()));
-            map.insert(
-                "filepath".to_string(),
-                Value::String(download_route.replace(FILE_NAME_PARAM, &file_name)),
-            );
-            map
+
+        let mut response = json!({
+            "status": "success",
+            "message": "File uploaded successfully",
+            "filename": file_name,
+            "filepath": download_route.replace(FILE_NAME_PARAM, &file_name),
         });
+        if let (Value::Object(map), Some(Value::Object(last))) = (&mut response, files.last()) {
+            map.extend(last.clone());
+        }
+        response["files"] = Value::Array(files);
 
         Json(response).into_response()
     });
@@ -73,42 +360,122 @@ fn create_upload_route(app: &mut App, upload_def: &RouteUpload) {
     );
 }
 
+fn create_meta_route(app: &mut App, upload_def: &RouteUpload, metadata: UploadMetadata) {
+    let route = upload_def.get_meta_route();
+    let upload_path = PathBuf::from(&upload_def.path);
+
+    // GET /uploads/meta/{id} - metadata of an uploaded file
+    let meta_router = get(async move |AxumPath(id): AxumPath<String>| {
+        let file = metadata.lock().unwrap().get(&id).cloned();
+        match file {
+            Some(file)
+                if upload_path
+                    .join(file["original_name"].as_str().unwrap_or_default())
+                    .is_file() =>
+            {
+                Json(file).into_response()
+            }
+            _ => error_response(
+                StatusCode::NOT_FOUND,
+                "upload_not_found",
+                format!("No uploaded file has the id '{}'", id),
+            ),
+        }
+    });
+
+    app.route(&route, meta_router, Some("GET"), None);
+}
+
 fn create_download_route(app: &mut App, upload_def: &RouteUpload) {
     let download_route = upload_def.get_download_route();
-    let download_path = upload_def.path.to_string_lossy().to_string();
+    let download_path = PathBuf::from(&upload_def.path);
+    let download_rate = upload_def.download_rate;
+    let retention = upload_def.retention;
+    let disposition = if upload_def.is_inline_download {
+        "inline"
+    } else {
+        "attachment"
+    };
 
     // GET /uploads/{filename} - download file
-    let download_router = get(move |AxumPath(file_name): AxumPath<String>| {
-        async move {
-            let file_path = Path::new(&download_path).join(&file_name);
+    let download_router = get(
+        move |AxumPath(file_name): AxumPath<String>, request_headers: HeaderMap| {
+            async move {
+                let file_path = download_path.join(&file_name);
 
-            // Check if file exists
-            if !file_path.exists() {
-                return StatusCode::NOT_FOUND.into_response();
-            }
+                // Check if file exists
+                if !file_path.exists() {
+                    return StatusCode::NOT_FOUND.into_response();
+                }
+                if is_expired(&file_path, retention) {
+                    return error_response(
+                        StatusCode::GONE,
+                        "upload_expired",
+                        format!("The file '{}' has expired", file_name),
+                    );
+                }
 
-            // Read file content
-            match tokio::fs::read(&file_path).await {
-                Ok(contents) => {
-                    // Guess MIME type
-                    let mime_type = from_path(&file_path).first_or_octet_stream().to_string();
+                // Read file content
+                match tokio::fs::read(&file_path).await {
+                    Ok(contents) => {
+                        let len = contents.len() as u64;
 
-                    // Set headers
-                    let mut headers = HeaderMap::new();
-                    headers.insert(CONTENT_TYPE, HeaderValue::from_str(&mime_type).unwrap());
+                        // Guess MIME type
+                        let mime_type = from_path(&file_path).first_or_octet_stream().to_string();
 
-                    headers.insert(
-                        CONTENT_DISPOSITION,
-                        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name))
+                        // Set headers
+                        let mut headers = HeaderMap::new();
+                        headers.insert(CONTENT_TYPE, HeaderValue::from_str(&mime_type).unwrap());
+                        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+                        headers.insert(
+                            CONTENT_DISPOSITION,
+                            HeaderValue::from_str(&format!(
+                                "{}; filename=\"{}\"",
+                                disposition, file_name
+                            ))
                             .unwrap(),
-                    );
+                        );
 
-                    (headers, contents).into_response()
+                        // Resume support through a single byte range
+                        let range = request_headers
+                            .get(RANGE)
+                            .and_then(|value| value.to_str().ok());
+                        let (status, contents) = match range {
+                            None => (StatusCode::OK, contents),
+                            Some(range) => match parse_byte_range(range, len) {
+                                Some((start, end)) => {
+                                    headers.insert(
+                                        CONTENT_RANGE,
+                                        HeaderValue::from_str(&format!(
+                                            "bytes {}-{}/{}",
+                                            start, end, len
+                                        ))
+                                        .unwrap(),
+                                    );
+                                    (
+                                        StatusCode::PARTIAL_CONTENT,
+                                        contents[start as usize..=end as usize].to_vec(),
+                                    )
+                                }
+                                None => {
+                                    headers.insert(
+                                        CONTENT_RANGE,
+                                        HeaderValue::from_str(&format!("bytes */{}", len)).unwrap(),
+                                    );
+                                    return (StatusCode::RANGE_NOT_SATISFIABLE, headers)
+                                        .into_response();
+                                }
+                            },
+                        };
+
+                        (status, headers, throttled_body(contents, download_rate)).into_response()
+                    }
+                    Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
                 }
-                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
             }
-        }
-    });
+        },
+    );
 
     app.route(
         &download_route,
@@ -121,34 +488,37 @@ fn create_download_route(app: &mut App, upload_def: &RouteUpload) {
 fn create_uploaded_list_route(app: &mut App, upload_def: &RouteUpload) {
     let route = upload_def.get_list_files_route();
     let download_route = upload_def.get_download_route();
-    let upload_path = upload_def.path.to_string_lossy().to_string();
+    let upload_path = PathBuf::from(&upload_def.path);
+    let retention = upload_def.retention;
 
-    // GET /uploads - download file
+    // GET /uploads - list files, with their expiry when the folder has a retention
     let upload_list_router = get(move || {
         async move {
-            let upload_path = Path::new(&upload_path);
-
             // Check if file exists
             if !upload_path.exists() {
                 return StatusCode::NOT_FOUND.into_response();
             }
 
-            let entries = fs::read_dir(upload_path).unwrap();
+            let entries = fs::read_dir(&upload_path).unwrap();
             let array = entries
                 .filter_map(Result::ok)
-                .filter(|entry| {
-                    !entry
-                        .path()
-                        .extension()
-                        .and_then(OsStr::to_str)
-                        .unwrap_or_default()
-                        .eq_ignore_ascii_case("toml")
-                })
+                .filter(|entry| !is_config_file(&entry.path()))
                 .map(|entry| {
-                    let value = download_route
-                        .replace(FILE_NAME_PARAM, entry.file_name().to_str().unwrap());
+                    let file_name = entry.file_name().to_string_lossy().into_owned();
+                    let url = download_route.replace(FILE_NAME_PARAM, &file_name);
+                    if retention.is_none() {
+                        return Value::String(url);
+                    }
 
-                    Value::String(value)
+                    let expires_at = expires_at(&entry.path(), retention);
+                    json!({
+                        "name": file_name,
+                        "url": url,
+                        "expires_at": expires_at
+                            .map(|expires_at| DateTime::<Utc>::from(expires_at).to_rfc3339()),
+                        "pending_deletion": expires_at
+                            .is_some_and(|expires_at| expires_at <= SystemTime::now()),
+                    })
                 })
                 .collect();
 
@@ -161,13 +531,144 @@ fn create_uploaded_list_route(app: &mut App, upload_def: &RouteUpload) {
     app.route(&route, upload_list_router, Some("GET"), None);
 }
 
+fn create_chunk_routes(app: &mut App, upload_def: &RouteUpload) {
+    let route = upload_def.get_chunk_route();
+    let head_path = PathBuf::from(&upload_def.path);
+    let patch_path = head_path.clone();
+    let max_chunk_size = upload_def.max_chunk_size;
+
+    // HEAD /uploads/{file_name} - current offset of a chunked upload
+    let chunk_router = head(move |AxumPath(file_name): AxumPath<String>| async move {
+        if !is_safe_file_name(&file_name) {
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+
+        let offset = tokio::fs::metadata(head_path.join(&file_name))
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+
+        (StatusCode::OK, offset_headers(offset)).into_response()
+    });
+
+    // PATCH /uploads/{file_name} - append a chunk at the expected offset
+    let chunk_router = chunk_router
+        .patch(
+            move |AxumPath(file_name): AxumPath<String>, headers: HeaderMap, body: Bytes| async move {
+                if !is_safe_file_name(&file_name) {
+                    return error_response(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_file_name",
+                        format!("The file name '{file_name}' is not allowed"),
+                    );
+                }
+
+                let Some(expected_offset) = headers
+                    .get(UPLOAD_OFFSET_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok())
+                else {
+                    return error_response(
+                        StatusCode::BAD_REQUEST,
+                        "missing_offset",
+                        "The Upload-Offset header is required for chunked uploads",
+                    );
+                };
+
+                if let Some(max_chunk_size) = max_chunk_size
+                    && body.len() as u64 > max_chunk_size
+                {
+                    return error_response(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "chunk_too_large",
+                        format!(
+                            "The chunk has {} bytes but the maximum allowed is {}",
+                            body.len(),
+                            max_chunk_size
+                        ),
+                    );
+                }
+
+                let file_path = patch_path.join(&file_name);
+                let current_offset = tokio::fs::metadata(&file_path)
+                    .await
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                if current_offset != expected_offset {
+                    return (
+                        offset_headers(current_offset),
+                        error_response(
+                            StatusCode::CONFLICT,
+                            "offset_mismatch",
+                            format!(
+                                "The upload is at offset {current_offset} but the chunk starts at {expected_offset}"
+                            ),
+                        ),
+                    )
+                        .into_response();
+                }
+
+                let file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&file_path)
+                    .await;
+                let written = match file {
+                    Ok(mut file) => file.write_all(&body).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = written {
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "write_error",
+                        format!("Could not write chunk to '{file_name}': {err}"),
+                    );
+                }
+
+                let new_offset = current_offset + body.len() as u64;
+                (StatusCode::NO_CONTENT, offset_headers(new_offset)).into_response()
+            },
+        )
+        .layer(DefaultBodyLimit::disable());
+
+    app.route(
+        &route,
+        chunk_router,
+        Some("PATCH"),
+        Some(&["chunked".to_string()]),
+    );
+}
+
 /// Registers upload, download, and list-file routes for an upload directory.
 pub fn build_upload_routes(app: &mut App, upload_def: &RouteUpload) {
-    create_upload_route(app, upload_def);
+    let metadata = app.uploads.folder(Path::new(&upload_def.path));
+    create_upload_route(app, upload_def, Arc::clone(&metadata));
+    create_meta_route(app, upload_def, metadata);
 
     create_download_route(app, upload_def);
 
     create_uploaded_list_route(app, upload_def);
+
+    if upload_def.is_chunked {
+        create_chunk_routes(app, upload_def);
+    }
+
+    spawn_retention_sweep(app, upload_def);
+}
+
+/// Registers `DELETE /mock-server/uploads`, which deletes the files of every
+/// temporary upload folder and answers how many were deleted.
+pub fn create_upload_cleanup_route(app: &mut App, folders: Vec<UploadConfiguration>) {
+    let route = format!("{}/uploads", MOCK_SERVER_ROUTE);
+
+    let cleanup_router = delete(async move || {
+        let deleted = folders
+            .iter()
+            .map(UploadConfiguration::clean_upload_folder)
+            .sum::<usize>();
+        Json(json!({ "deleted": deleted })).into_response()
+    });
+    app.route(&route, cleanup_router, Some("DELETE"), None);
 }
 
 #[cfg(test)]
@@ -189,9 +690,333 @@ mod tests {
             upload_endpoint: None,
             download_endpoint: None,
             list_files_endpoint: None,
+            is_chunked: false,
+            max_chunk_size: None,
+            download_rate: None,
+            is_inline_download: false,
+            retention: None,
+            scan: None,
         }
     }
 
+    fn range_request(uri: &str, range: &str) -> Request<Body> {
+        Request::builder()
+            .uri(uri)
+            .header(RANGE, range)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn byte_ranges_are_parsed_against_file_length() {
+        assert_eq!(parse_byte_range("bytes=0-4", 10), Some((0, 4)));
+        assert_eq!(parse_byte_range("bytes=6-", 10), Some((6, 9)));
+        assert_eq!(parse_byte_range("bytes=-3", 10), Some((7, 9)));
+        assert_eq!(parse_byte_range("bytes=8-20", 10), Some((8, 9)));
+        assert_eq!(parse_byte_range("bytes=10-", 10), None);
+        assert_eq!(parse_byte_range("bytes=5-2", 10), None);
+        assert_eq!(parse_byte_range("bytes=0-1,3-4", 10), None);
+        assert_eq!(parse_byte_range("items=0-1", 10), None);
+    }
+
+    #[tokio::test]
+    async fn download_supports_ranges_inline_disposition_and_throttling() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("movie.txt"), "0123456789").unwrap();
+
+        let mut app = App::default();
+        let mut upload = upload_def(temp_dir.path());
+        upload.is_inline_download = true;
+        upload.download_rate = Some(1);
+        build_upload_routes(&mut app, &upload);
+        let router = app.take_router_for_test();
+
+        let partial = router
+            .clone()
+            .oneshot(range_request("/uploads/movie.txt", "bytes=4-"))
+            .await
+            .unwrap();
+        assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            partial.headers().get(CONTENT_RANGE).unwrap(),
+            "bytes 4-9/10"
+        );
+        assert_eq!(partial.headers().get(ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(
+            partial.headers().get(CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"movie.txt\""
+        );
+        assert_eq!(
+            to_bytes(partial.into_body(), usize::MAX).await.unwrap(),
+            "456789"
+        );
+
+        let unsatisfiable = router
+            .oneshot(range_request("/uploads/movie.txt", "bytes=50-"))
+            .await
+            .unwrap();
+        assert_eq!(unsatisfiable.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            unsatisfiable.headers().get(CONTENT_RANGE).unwrap(),
+            "bytes */10"
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_uploads_are_flagged_gone_and_swept() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("fresh.txt"), "fresh").unwrap();
+        std::fs::write(temp_dir.path().join("config.toml"), "[upload]").unwrap();
+        let old = std::fs::File::create(temp_dir.path().join("old.txt")).unwrap();
+        old.set_modified(SystemTime::now() - Duration::from_secs(120))
+            .unwrap();
+
+        let mut app = App::default();
+        let mut upload = upload_def(temp_dir.path());
+        upload.retention = Some(Retention::For(Duration::from_secs(60)));
+        build_upload_routes(&mut app, &upload);
+        let router = app.take_router_for_test();
+
+        let list = router
+            .clone()
+            .oneshot(Request::get("/uploads").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(list.into_body(), usize::MAX).await.unwrap();
+        let mut files: Vec<Value> = serde_json::from_slice(&body).unwrap();
+        files.sort_by_key(|file| file["name"].as_str().unwrap().to_string());
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["url"], "/uploads/fresh.txt");
+        assert_eq!(files[0]["pending_deletion"], false);
+        assert!(files[0]["expires_at"].is_string());
+        assert_eq!(files[1]["name"], "old.txt");
+        assert_eq!(files[1]["pending_deletion"], true);
+
+        let gone = router
+            .oneshot(
+                Request::get("/uploads/old.txt")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(gone.status(), StatusCode::GONE);
+
+        assert_eq!(
+            sweep_expired_uploads(temp_dir.path(), Duration::from_secs(60)),
+            1
+        );
+        assert!(!temp_dir.path().join("old.txt").exists());
+        assert!(temp_dir.path().join("fresh.txt").exists());
+        assert!(temp_dir.path().join("config.toml").exists());
+    }
+
+    #[test]
+    fn scan_verdicts_follow_patterns_and_percentages() {
+        let mut scan = UploadScan {
+            infected: vec!["*.EXE".to_string(), "eicar*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(scan_verdict(&scan, "setup.exe"), "infected");
+        assert_eq!(scan_verdict(&scan, "EICAR.txt"), "infected");
+        assert_eq!(scan_verdict(&scan, "report.pdf"), "clean");
+        scan.infected_percent = 100;
+        assert_eq!(scan_verdict(&scan, "report.pdf"), "infected");
+    }
+
+    #[tokio::test]
+    async fn webhooks_give_up_on_silent_receivers_and_refuse_other_schemes() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        // Accepts connections and never answers.
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+
+        let err = send_webhook(
+            &format!("http://{}/scans", address),
+            &json!({}),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.starts_with("no answer within"), "{}", err);
+
+        let err = send_webhook(
+            "ftp://localhost/scans",
+            &json!({}),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err, "the webhook must be an http:// or https:// URL");
+    }
+
+    #[tokio::test]
+    async fn scans_update_the_metadata_and_post_the_verdict() {
+        let (verdicts, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let receiver = axum::Router::new().route(
+            "/scans",
+            post(move |Json(body): Json<Value>| async move {
+                verdicts.send(body).unwrap();
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = App::default();
+        let mut upload = upload_def(temp_dir.path());
+        upload.scan = Some(UploadScan {
+            delay: 0,
+            infected: vec!["*.exe".to_string()],
+            infected_percent: 0,
+            webhook: Some(format!("http://{}/scans", address)),
+        });
+        build_upload_routes(&mut app, &upload);
+        let router = app.take_router_for_test();
+
+        let multipart = concat!(
+            "--BOUNDARY\r\n",
+            "Content-Disposition: form-data; name=\"file\"; filename=\"setup.exe\"\r\n\r\n",
+            "MZ\r\n",
+            "--BOUNDARY--\r\n"
+        );
+        let uploaded = router
+            .clone()
+            .oneshot(
+                Request::post("/uploads")
+                    .header(CONTENT_TYPE, "multipart/form-data; boundary=BOUNDARY")
+                    .body(Body::from(multipart))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body: Value =
+            serde_json::from_slice(&to_bytes(uploaded.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body["scan_status"], "pending");
+
+        let verdict = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(verdict["event"], "upload.scanned");
+        assert_eq!(verdict["file"]["id"], body["id"]);
+        assert_eq!(verdict["file"]["scan_status"], "infected");
+
+        let meta_uri = format!("/uploads/meta/{}", body["id"].as_str().unwrap());
+        let meta = router
+            .oneshot(Request::get(&meta_uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let meta: Value =
+            serde_json::from_slice(&to_bytes(meta.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(meta["scan_status"], "infected");
+        assert!(meta["scanned_at"].is_string());
+    }
+
+    fn chunk_request(uri: &str, offset: Option<u64>, body: &'static str) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method(Method::PATCH)
+            .uri(uri)
+            .header(CONTENT_TYPE, "application/offset+octet-stream");
+        if let Some(offset) = offset {
+            builder = builder.header(UPLOAD_OFFSET_HEADER, offset);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn chunked_upload_appends_chunks_and_reports_offset() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = App::default();
+        let mut upload = upload_def(temp_dir.path());
+        upload.is_chunked = true;
+        upload.max_chunk_size = Some(8);
+        build_upload_routes(&mut app, &upload);
+        let router = app.take_router_for_test();
+
+        let first = router
+            .clone()
+            .oneshot(chunk_request("/uploads/big.bin", Some(0), "hello "))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::NO_CONTENT);
+        assert_eq!(first.headers().get(UPLOAD_OFFSET_HEADER).unwrap(), "6");
+
+        let mismatch = router
+            .clone()
+            .oneshot(chunk_request("/uploads/big.bin", Some(2), "world"))
+            .await
+            .unwrap();
+        assert_eq!(mismatch.status(), StatusCode::CONFLICT);
+        assert_eq!(mismatch.headers().get(UPLOAD_OFFSET_HEADER).unwrap(), "6");
+
+        let too_large = router
+            .clone()
+            .oneshot(chunk_request("/uploads/big.bin", Some(6), "world!!!!"))
+            .await
+            .unwrap();
+        assert_eq!(too_large.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let missing_offset = router
+            .clone()
+            .oneshot(chunk_request("/uploads/big.bin", None, "world"))
+            .await
+            .unwrap();
+        assert_eq!(missing_offset.status(), StatusCode::BAD_REQUEST);
+
+        let second = router
+            .clone()
+            .oneshot(chunk_request("/uploads/big.bin", Some(6), "world"))
+            .await
+            .unwrap();
+        assert_eq!(second.status(), StatusCode::NO_CONTENT);
+
+        let offset = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::HEAD)
+                    .uri("/uploads/big.bin")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(offset.status(), StatusCode::OK);
+        assert_eq!(offset.headers().get(UPLOAD_OFFSET_HEADER).unwrap(), "11");
+        assert_eq!(offset.headers().get(TUS_RESUMABLE_HEADER).unwrap(), "1.0.0");
+
+        let download = router
+            .oneshot(
+                Request::builder()
+                    .uri("/uploads/big.bin")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(download.status(), StatusCode::OK);
+        assert_eq!(
+            to_bytes(download.into_body(), usize::MAX).await.unwrap(),
+            "hello world"
+        );
+    }
+
+    #[test]
+    fn chunk_file_names_reject_path_traversal() {
+        assert!(is_safe_file_name("report.pdf"));
+        assert!(!is_safe_file_name(".."));
+        assert!(!is_safe_file_name("../secret"));
+        assert!(!is_safe_file_name(""));
+    }
+
     #[tokio::test]
     async fn upload_list_and_download_routes_work() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -256,6 +1081,7 @@ mod tests {
             "--BOUNDARY--\r\n"
         );
         let uploaded = router
+            .clone()
             .oneshot(
                 Request::builder()
                     .method(Method::POST)
@@ -274,6 +1100,46 @@ mod tests {
             std::fs::read_to_string(temp_dir.path().join("new.txt")).unwrap(),
             "uploaded"
         );
+        let body: Value =
+            serde_json::from_slice(&to_bytes(uploaded.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body["original_name"], "new.txt");
+        assert_eq!(body["size"], 8);
+        assert_eq!(body["mime_type"], "text/plain");
+        assert_eq!(
+            body["sha256"],
+            "3cef1b245d4b2f37dadddcbadb76017d440ba283601228724c0cb2c07d35bed4"
+        );
+        assert_eq!(body["url"], "/uploads/new.txt");
+        assert_eq!(body["files"].as_array().unwrap().len(), 1);
+
+        let meta_uri = format!("/uploads/meta/{}", body["id"].as_str().unwrap());
+        let meta = router
+            .clone()
+            .oneshot(Request::get(&meta_uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let meta: Value =
+            serde_json::from_slice(&to_bytes(meta.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(meta, body["files"][0]);
+
+        // The app rebuilt by a hot reload receives the registry.
+        let mut reloaded = App::new(app.server_config.clone());
+        reloaded.uploads = Arc::clone(&app.uploads);
+        build_upload_routes(&mut reloaded, &upload_def(temp_dir.path()));
+        let meta = reloaded
+            .take_router_for_test()
+            .oneshot(Request::get(&meta_uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(meta.status(), StatusCode::OK);
+
+        std::fs::remove_file(temp_dir.path().join("new.txt")).unwrap();
+        let meta = router
+            .oneshot(Request::get(&meta_uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(meta.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
@@ -1,6 +1,6 @@
 //! Utility helpers shared by route handlers.
 
-use std::{ffi::OsString, path::Path};
+use std::{f64::consts::PI, ffi::OsString, path::Path, time::Duration};
 
 use axum::{
     extract::Request,
@@ -10,6 +10,9 @@ use axum::{
     response::{IntoResponse, Response},
     routing::{MethodRouter, any},
 };
+use rand::{Rng, RngCore};
+
+use crate::{random, route_builder::config::Delay};
 
 /// Returns the lowercase-sensitive file extension for a path, or an empty string.
 pub fn get_file_extension(file_path: &OsString) -> String {
@@ -48,6 +51,12 @@ pub fn is_sql(file_path: &OsString) -> bool {
     extension == "sql"
 }
 
+/// Returns true when the path has a Rhai script extension.
+pub fn is_rhai(file_path: &OsString) -> bool {
+    let extension = get_file_extension(file_path);
+    extension == "rhai"
+}
+
 /// Returns true when the path has a TOML extension.
 pub fn is_toml(file_path: &OsString) -> bool {
     let extension = get_file_extension(file_path);
@@ -55,16 +64,57 @@ pub fn is_toml(file_path: &OsString) -> bool {
 }
 
 /// Extension trait for applying optional route response delays.
-pub trait SleepThread {
-    /// Sleeps the current thread when the option contains a delay in milliseconds.
-    fn sleep_thread(self);
+pub trait SleepDelay {
+    /// Sleeps without blocking the runtime when the option contains a delay,
+    /// drawing its length from the delay's distribution, so timeouts around
+    /// the handler can still fire.
+    fn sleep(self) -> impl Future<Output = ()> + Send;
 }
 
-impl SleepThread for Option<u16> {
-    fn sleep_thread(self) {
+impl SleepDelay for Option<Delay> {
+    async fn sleep(self) {
         if let Some(delay) = self {
-            let millis = std::time::Duration::from_millis(delay.into());
-            std::thread::sleep(millis);
+            tokio::time::sleep(delay_duration(delay)).await;
+        }
+    }
+}
+
+/// Draws the length of one delay from the server's random source, so seeded
+/// runs repeat it.
+pub fn delay_duration(delay: Delay) -> Duration {
+    let millis = random::current().with_rng(|rng| delay_millis(delay, rng));
+    Duration::from_micros((millis.max(0.0) * 1000.0).round() as u64)
+}
+
+/// Draws the length of one delay, in milliseconds, from `rng`.
+fn delay_millis(delay: Delay, rng: &mut dyn RngCore) -> f64 {
+    match delay {
+        Delay::Fixed(millis) => f64::from(millis),
+        Delay::Uniform { min, max } => f64::from(rng.random_range(min..=max)),
+        Delay::Normal { mean, std_dev } => {
+            // Box-Muller transform of two uniform draws.
+            let (u1, u2) = (1.0 - rng.random::<f64>(), rng.random::<f64>());
+            mean + std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+        }
+        Delay::Percentiles { p50, p95, p99 } => {
+            // Piecewise-linear quantile function through the given percentiles,
+            // from half the p50 up to twice the p99.
+            let points = [
+                (0.0, f64::from(p50) / 2.0),
+                (0.5, f64::from(p50)),
+                (0.95, f64::from(p95)),
+                (0.99, f64::from(p99)),
+                (1.0, f64::from(p99) * 2.0),
+            ];
+            let quantile = rng.random::<f64>();
+            points
+                .windows(2)
+                .find(|window| quantile <= window[1].0)
+                .map(|window| {
+                    let ((q0, v0), (q1, v1)) = (window[0], window[1]);
+                    v0 + (v1 - v0) * (quantile - q0) / (q1 - q0)
+                })
+                .unwrap_or(points[4].1)
         }
     }
 }
@@ -119,6 +169,7 @@ mod tests {
         body::{Body, to_bytes},
         http::Request,
     };
+    use rand::SeedableRng;
     use std::ffi::OsString;
     use std::time::Instant;
     use tower::ServiceExt;
@@ -227,11 +278,41 @@ mod tests {
         assert!(!is_text_file(&OsString::from("image.png")));
     }
 
-    #[test]
-    fn sleep_thread_handles_none_and_some() {
+    #[tokio::test]
+    async fn sleep_delay_handles_none_and_some() {
         let start = Instant::now();
-        None::<u16>.sleep_thread();
-        Some(1).sleep_thread();
+        None::<Delay>.sleep().await;
+        Some(Delay::Fixed(1)).sleep().await;
         assert!(start.elapsed().as_millis() >= 1);
     }
+
+    #[test]
+    fn delay_distributions_are_sampled_within_their_shape() {
+        const SAMPLES: usize = 20_000;
+        // A local generator, since other tests reseed the shared one.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut samples = |delay: &str| {
+            let delay = Delay::parse(delay).unwrap();
+            let mut millis: Vec<u128> = (0..SAMPLES)
+                .map(|_| delay_millis(delay, &mut rng).max(0.0) as u128)
+                .collect();
+            millis.sort();
+            millis
+        };
+        let at = |millis: &[u128], quantile: f64| millis[(quantile * SAMPLES as f64) as usize];
+
+        let uniform = samples("uniform(50, 500)");
+        assert!(uniform[0] >= 50 && uniform[SAMPLES - 1] <= 500);
+
+        let normal = samples("normal(200,50)");
+        assert!((190..=210).contains(&at(&normal, 0.5)));
+
+        let percentiles = samples("p50=100,p95=400,p99=1200");
+        assert!((90..=110).contains(&at(&percentiles, 0.5)));
+        // The share of samples below the p95 is more stable than the sample
+        // at 95%, which sits where the distribution gets steeper.
+        let below_p95 = percentiles.iter().filter(|millis| **millis <= 400).count();
+        assert!((0.94..=0.96).contains(&(below_p95 as f64 / SAMPLES as f64)));
+        assert!(percentiles[0] >= 50 && percentiles[SAMPLES - 1] <= 2400);
+    }
 }
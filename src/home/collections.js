@@ -0,0 +1,268 @@
+// Dashboard of the collections: lists them with their inferred schema, and
+// adds, edits, and deletes items through the REST routes of each collection.
+
+let selected = null;
+let editing = null;
+
+window.addEventListener("DOMContentLoaded", () => {
+    const authorization = document.getElementById("authorization");
+    authorization.value = localStorage.getItem("mock_authorization") || "";
+    authorization.addEventListener("change", () => {
+        localStorage.setItem("mock_authorization", authorization.value);
+    });
+
+    buildCollectionList();
+    if (mock_collections.length > 0) {
+        selectCollection(mock_collections[0]);
+    } else {
+        document.getElementById("schema").textContent =
+            "No collections are loaded.";
+    }
+});
+
+function buildCollectionList() {
+    const nav = document.getElementById("collections");
+    const list = document.createElement("ul");
+    mock_collections.forEach((collection) => {
+        const item = document.createElement("li");
+        item.dataset.name = collection.name;
+        const link = document.createElement("a");
+        link.textContent = collection.name;
+        link.addEventListener("click", () => selectCollection(collection));
+        item.appendChild(link);
+        list.appendChild(item);
+    });
+    nav.appendChild(list);
+}
+
+function selectCollection(collection) {
+    selected = collection;
+    document.querySelectorAll("#collections li").forEach((item) => {
+        item.classList.toggle("selected", item.dataset.name === collection.name);
+    });
+    renderSchema(collection);
+    resetForm();
+    loadItems();
+}
+
+function renderSchema(collection) {
+    const section = document.getElementById("schema");
+    section.replaceChildren();
+
+    const title = document.createElement("h2");
+    title.textContent = collection.route
+        ? `${collection.name} (${collection.route})`
+        : `${collection.name} (no REST route)`;
+    section.appendChild(title);
+
+    const table = document.createElement("table");
+    table.appendChild(row("th", ["Field", "Type", "Nullable"]));
+    Object.entries(collection.fields).forEach(([name, field]) => {
+        table.appendChild(row("td", [name, field.type, String(field.nullable)]));
+    });
+    section.appendChild(table);
+}
+
+function row(cellTag, values) {
+    const tr = document.createElement("tr");
+    values.forEach((value) => {
+        const cell = document.createElement(cellTag);
+        if (value instanceof Node) {
+            cell.appendChild(value);
+        } else {
+            cell.textContent = value;
+        }
+        tr.appendChild(cell);
+    });
+    return tr;
+}
+
+function fieldNames() {
+    return Object.keys(selected.fields).filter(
+        (name) => name !== selected.id_key || editing === null
+    );
+}
+
+function resetForm(item = null) {
+    editing = item;
+    const form = document.getElementById("item-form");
+    form.replaceChildren();
+    document.getElementById("form-title").textContent = item
+        ? `Edit ${item[selected.id_key]}`
+        : "Add item";
+    if (!selected.route) {
+        return;
+    }
+
+    fieldNames().forEach((name) => {
+        const field = selected.fields[name];
+        const label = document.createElement("label");
+        const caption = document.createElement("span");
+        caption.textContent = `${name} (${field.type})`;
+        label.appendChild(caption);
+
+        const value = item ? item[name] : undefined;
+        let input;
+        if (field.type === "Object" || field.type === "Array") {
+            input = document.createElement("textarea");
+            input.value = value === undefined ? "" : JSON.stringify(value, null, 2);
+        } else {
+            input = document.createElement("input");
+            if (field.type === "Bool") {
+                input.type = "checkbox";
+                input.checked = value === true;
+            } else {
+                input.type = field.type === "Int" || field.type === "Float" ? "number" : "text";
+                input.step = field.type === "Float" ? "any" : "1";
+                input.value = value === undefined || value === null ? "" : value;
+            }
+        }
+        input.name = name;
+        label.appendChild(input);
+        form.appendChild(label);
+    });
+
+    const save = document.createElement("button");
+    save.type = "submit";
+    save.textContent = item ? "Save" : "Add";
+    form.appendChild(save);
+    if (item) {
+        const cancel = document.createElement("button");
+        cancel.type = "button";
+        cancel.textContent = "Cancel";
+        cancel.addEventListener("click", () => resetForm());
+        form.appendChild(cancel);
+    }
+    form.onsubmit = (event) => {
+        event.preventDefault();
+        saveItem();
+    };
+}
+
+function readForm() {
+    const payload = editing ? { ...editing } : {};
+    delete payload._links;
+    fieldNames().forEach((name) => {
+        const field = selected.fields[name];
+        const input = document.querySelector(`#item-form [name="${CSS.escape(name)}"]`);
+        if (field.type === "Bool") {
+            payload[name] = input.checked;
+        } else if (input.value === "") {
+            if (field.nullable || editing) {
+                payload[name] = null;
+            }
+        } else if (field.type === "Int") {
+            payload[name] = parseInt(input.value, 10);
+        } else if (field.type === "Float") {
+            payload[name] = parseFloat(input.value);
+        } else if (field.type === "Object" || field.type === "Array") {
+            payload[name] = JSON.parse(input.value);
+        } else {
+            payload[name] = input.value;
+        }
+    });
+    return payload;
+}
+
+async function request(method, url, body) {
+    const headers = { "Content-Type": "application/json" };
+    const authorization = document.getElementById("authorization").value;
+    if (authorization) {
+        headers["Authorization"] = authorization;
+    }
+    const response = await fetch(url, {
+        method,
+        headers,
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const text = await response.text();
+    if (!response.ok) {
+        throw new Error(`${method} ${url}: ${response.status} ${text}`);
+    }
+    return text ? JSON.parse(text) : null;
+}
+
+function showMessage(text, isError = false) {
+    const message = document.getElementById("message");
+    message.textContent = text;
+    message.classList.toggle("error", isError);
+}
+
+async function saveItem() {
+    try {
+        const payload = readForm();
+        if (editing) {
+            const id = encodeURIComponent(editing[selected.id_key]);
+            await request("PUT", `${selected.route}/${id}`, payload);
+            showMessage(`Saved ${editing[selected.id_key]}`);
+        } else {
+            const created = await request("POST", selected.route, payload);
+            showMessage(`Added ${created ? created[selected.id_key] : "item"}`);
+        }
+        resetForm();
+        loadItems();
+    } catch (error) {
+        showMessage(error.message, true);
+    }
+}
+
+async function deleteItem(item) {
+    const id = item[selected.id_key];
+    if (!confirm(`Delete ${id}?`)) {
+        return;
+    }
+    try {
+        await request("DELETE", `${selected.route}/${encodeURIComponent(id)}`);
+        showMessage(`Deleted ${id}`);
+        if (editing && editing[selected.id_key] === id) {
+            resetForm();
+        }
+        loadItems();
+    } catch (error) {
+        showMessage(error.message, true);
+    }
+}
+
+async function loadItems() {
+    const section = document.getElementById("items");
+    section.replaceChildren();
+    if (!selected.route) {
+        return;
+    }
+
+    let items;
+    try {
+        const listing = await request("GET", selected.route);
+        items = Array.isArray(listing) ? listing : listing.data || [];
+    } catch (error) {
+        showMessage(error.message, true);
+        return;
+    }
+
+    const title = document.createElement("h2");
+    title.textContent = `${items.length} items`;
+    section.appendChild(title);
+
+    const columns = Object.keys(selected.fields);
+    const table = document.createElement("table");
+    table.appendChild(row("th", [...columns, ""]));
+    items.forEach((item) => {
+        const actions = document.createElement("span");
+        const edit = document.createElement("button");
+        edit.textContent = "Edit";
+        edit.addEventListener("click", () => resetForm(item));
+        const remove = document.createElement("button");
+        remove.textContent = "Delete";
+        remove.addEventListener("click", () => deleteItem(item));
+        actions.append(edit, remove);
+
+        const values = columns.map((name) => {
+            const value = item[name];
+            return typeof value === "object" && value !== null
+                ? JSON.stringify(value)
+                : String(value ?? "");
+        });
+        table.appendChild(row("td", [...values, actions]));
+    });
+    section.appendChild(table);
+}
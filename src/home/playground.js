@@ -0,0 +1,232 @@
+// Playground of one route: pre-fills the method, path parameters, headers,
+// and a body from the registered route and its collection schema, then sends
+// the request and shows the response.
+
+const BODY_METHODS = ["POST", "PUT", "PATCH"];
+const PARAM = /{([^}]+)}/g;
+
+let current = null;
+
+window.addEventListener("DOMContentLoaded", () => {
+    const select = document.getElementById("route-select");
+    mock_routes.forEach((route, index) => {
+        const option = document.createElement("option");
+        option.value = index;
+        option.textContent = `${route.method.toUpperCase()} ${route.route}`;
+        select.appendChild(option);
+    });
+    select.addEventListener("change", () => {
+        const route = mock_routes[select.value];
+        history.replaceState(null, "", `?method=${route.method}&path=${encodeURIComponent(route.route)}`);
+        load(route);
+    });
+
+    document.getElementById("method").addEventListener("change", updatePreview);
+    document.getElementById("body").addEventListener("input", updatePreview);
+    document.getElementById("add-query").addEventListener("click", () => {
+        addPair("query-params");
+    });
+    document.getElementById("add-header").addEventListener("click", () => {
+        addPair("headers");
+    });
+    document.getElementById("send").addEventListener("click", send);
+
+    const query = new URLSearchParams(location.search);
+    const method = (query.get("method") || "GET").toUpperCase();
+    const path = query.get("path");
+    let index = mock_routes.findIndex(
+        (route) => route.route === path && route.method.toUpperCase() === method
+    );
+    if (index < 0) {
+        index = mock_routes.findIndex((route) => route.route === path);
+    }
+    if (index >= 0) {
+        select.value = index;
+        load(mock_routes[index]);
+    } else if (mock_routes.length > 0) {
+        load(mock_routes[0]);
+    }
+});
+
+// The collection a route belongs to, and whether it is its item route.
+function collectionOf(path) {
+    for (const collection of mock_collections) {
+        if (!collection.route) {
+            continue;
+        }
+        if (path === collection.route) {
+            return { collection, isItem: false };
+        }
+        if (path === `${collection.route}/{${collection.id_key}}`) {
+            return { collection, isItem: true };
+        }
+    }
+    return null;
+}
+
+function sampleValue(field) {
+    switch (field.type) {
+        case "Bool":
+            return false;
+        case "Int":
+            return 0;
+        case "Float":
+            return 0.0;
+        case "Object":
+            return {};
+        case "Array":
+            return [];
+        case "Null":
+            return null;
+        default:
+            return "";
+    }
+}
+
+function sampleBody(route) {
+    const found = collectionOf(route.route);
+    if (!found) {
+        return "{}";
+    }
+    const body = {};
+    Object.entries(found.collection.fields).forEach(([name, field]) => {
+        if (name !== found.collection.id_key) {
+            body[name] = sampleValue(field);
+        }
+    });
+    return JSON.stringify(body, null, 2);
+}
+
+function load(route) {
+    current = route;
+    const method = route.method.toUpperCase();
+    document.getElementById("method").value = method;
+    document.getElementById("protected").hidden = !route.protected;
+
+    const pathParams = document.getElementById("path-params");
+    pathParams.replaceChildren();
+    [...route.route.matchAll(PARAM)].forEach((match) => {
+        const tr = document.createElement("tr");
+        const name = document.createElement("td");
+        name.textContent = match[1].replace(/^\*/, "");
+        const value = document.createElement("td");
+        const input = document.createElement("input");
+        input.dataset.param = match[0];
+        input.placeholder = match[1];
+        input.addEventListener("input", updatePreview);
+        value.appendChild(input);
+        tr.append(name, value);
+        pathParams.appendChild(tr);
+    });
+
+    document.getElementById("query-params").replaceChildren();
+    addPair("query-params");
+
+    document.getElementById("headers").replaceChildren();
+    if (BODY_METHODS.includes(method)) {
+        addPair("headers", "Content-Type", "application/json");
+    }
+    if (route.protected) {
+        addPair("headers", "Authorization", localStorage.getItem("mock_authorization") || "Bearer ");
+    }
+    addPair("headers");
+
+    document.getElementById("body").value = BODY_METHODS.includes(method)
+        ? sampleBody(route)
+        : "";
+    updatePreview();
+}
+
+function addPair(tableId, key = "", value = "") {
+    const tr = document.createElement("tr");
+    [key, value].forEach((text, index) => {
+        const td = document.createElement("td");
+        const input = document.createElement("input");
+        input.placeholder = index === 0 ? "key" : "value";
+        input.value = text;
+        input.addEventListener("input", updatePreview);
+        td.appendChild(input);
+        tr.appendChild(td);
+    });
+    document.getElementById(tableId).appendChild(tr);
+}
+
+function pairs(tableId) {
+    return [...document.querySelectorAll(`#${tableId} tr`)]
+        .map((tr) => [...tr.querySelectorAll("input")].map((input) => input.value))
+        .filter(([key]) => key);
+}
+
+function buildUrl() {
+    let path = current.route;
+    document.querySelectorAll("#path-params input").forEach((input) => {
+        path = path.replace(input.dataset.param, encodeURIComponent(input.value) || input.dataset.param);
+    });
+    const query = new URLSearchParams(pairs("query-params")).toString();
+    return query ? `${path}?${query}` : path;
+}
+
+function buildRequest() {
+    const method = document.getElementById("method").value;
+    const headers = Object.fromEntries(pairs("headers"));
+    const body = document.getElementById("body").value;
+    return {
+        method,
+        headers,
+        body: BODY_METHODS.includes(method) && body ? body : undefined,
+    };
+}
+
+function updatePreview() {
+    if (!current) {
+        return;
+    }
+    const url = buildUrl();
+    document.getElementById("url").value = url;
+
+    const request = buildRequest();
+    const parts = [`curl -X ${request.method} '${location.origin}${url}'`];
+    Object.entries(request.headers).forEach(([key, value]) => {
+        parts.push(`-H '${key}: ${value}'`);
+    });
+    if (request.body !== undefined) {
+        parts.push(`-d '${request.body.replace(/'/g, "'\\''")}'`);
+    }
+    document.getElementById("curl").textContent = parts.join(" \\\n  ");
+}
+
+async function send() {
+    const status = document.getElementById("status");
+    const responseHeaders = document.getElementById("response-headers");
+    const responseBody = document.getElementById("response-body");
+    status.textContent = "sending...";
+    status.className = "";
+
+    const request = buildRequest();
+    if (request.headers.Authorization) {
+        localStorage.setItem("mock_authorization", request.headers.Authorization);
+    }
+
+    const started = performance.now();
+    try {
+        const response = await fetch(buildUrl(), request);
+        const text = await response.text();
+        const elapsed = (performance.now() - started).toFixed(1);
+
+        status.textContent = `${response.status} ${response.statusText} in ${elapsed} ms`;
+        status.className = response.ok ? "ok" : "error";
+        responseHeaders.textContent = [...response.headers]
+            .map(([key, value]) => `${key}: ${value}`)
+            .join("\n");
+        try {
+            responseBody.textContent = JSON.stringify(JSON.parse(text), null, 2);
+        } catch {
+            responseBody.textContent = text;
+        }
+    } catch (error) {
+        status.textContent = error.message;
+        status.className = "error";
+        responseHeaders.textContent = "";
+        responseBody.textContent = "";
+    }
+}
@@ -0,0 +1,92 @@
+// Live tail of the request journal: loads the latest requests, then follows
+// the server-sent events of new ones, newest first.
+
+const MAX_ROWS = 500;
+let paused = false;
+let buffered = [];
+
+window.addEventListener("DOMContentLoaded", async () => {
+    const filter = document.getElementById("filter");
+    filter.addEventListener("input", applyFilter);
+
+    const pause = document.getElementById("pause");
+    pause.addEventListener("click", () => {
+        paused = !paused;
+        pause.textContent = paused ? "Resume" : "Pause";
+        if (!paused) {
+            buffered.forEach(addRow);
+            buffered = [];
+        }
+    });
+
+    document.getElementById("clear").addEventListener("click", () => {
+        document.getElementById("requests").replaceChildren();
+    });
+
+    try {
+        const response = await fetch("/__requests");
+        const entries = await response.json();
+        entries.forEach(addRow);
+    } catch (error) {
+        setState(`(${error.message})`);
+    }
+    follow();
+});
+
+function follow() {
+    const source = new EventSource("/__requests/stream");
+    source.addEventListener("open", () => setState("● live"));
+    source.addEventListener("error", () => setState("○ reconnecting"));
+    source.addEventListener("request", (event) => {
+        const entry = JSON.parse(event.data);
+        if (paused) {
+            buffered.push(entry);
+        } else {
+            addRow(entry);
+        }
+    });
+}
+
+function setState(text) {
+    document.getElementById("state").textContent = text;
+}
+
+function addRow(entry) {
+    const body = document.getElementById("requests");
+    const tr = document.createElement("tr");
+    const time = new Date(entry.at).toLocaleTimeString();
+    const cells = [
+        [time, ""],
+        [entry.method, ""],
+        [entry.uri, "uri"],
+        [entry.route || "no route", entry.route ? "" : "unmatched"],
+        [String(entry.status), `status-${String(entry.status)[0]}`],
+        [`${entry.latency_ms.toFixed(1)} ms`, ""],
+    ];
+    cells.forEach(([text, className]) => {
+        const td = document.createElement("td");
+        td.textContent = text;
+        if (className) {
+            td.className = className;
+        }
+        tr.appendChild(td);
+    });
+    tr.dataset.search = `${entry.method} ${entry.uri} ${entry.route || ""} ${entry.status}`.toLowerCase();
+    tr.hidden = !matchesFilter(tr);
+
+    body.insertBefore(tr, body.firstChild);
+    while (body.children.length > MAX_ROWS) {
+        body.removeChild(body.lastChild);
+    }
+}
+
+function matchesFilter(tr) {
+    const filter = document.getElementById("filter").value.trim().toLowerCase();
+    return filter === "" || tr.dataset.search.includes(filter);
+}
+
+function applyFilter() {
+    document.querySelectorAll("#requests tr").forEach((tr) => {
+        tr.hidden = !matchesFilter(tr);
+    });
+}
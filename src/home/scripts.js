@@ -13,107 +13,120 @@ let logo = `                                  ___     ___
 
 window.addEventListener("DOMContentLoaded", () => {
     document.getElementById("logo").appendChild(document.createTextNode(logo));
+    // `?embed` hides the logo, for the page shown inside another tool.
+    if (new URLSearchParams(location.search).has("embed")) {
+        document.body.classList.add("embed");
+    }
 
     const navElement = document.getElementById("routes");
-    createRouteNavBar(navElement, mock_routes);
-});
+    createGroupedNavBar(navElement, mock_routes, mock_entries);
 
-const REGEX_PARAM = /^{(.+)}$/;
+    const search = document.getElementById("route-search");
+    search.addEventListener("input", () => filterRoutes(search.value));
+});
 
-function isArg(key) {
-    return REGEX_PARAM.test(key);
+// Folder of a mock file or folder, relative to where the server runs.
+function folderOf(source) {
+    return source.replace(/[\\/][^\\/]*$/, "") || ".";
 }
 
-function createRouteTree(routes) {
-    const root = {};
-
+// Routes grouped by the folder of the file defining them; routes of the
+// server itself, such as /mock-server, form a "server" group.
+function groupRoutes(routes, entries) {
+    const entriesByRoute = new Map(
+        entries.map((entry) => [`${entry.method.toUpperCase()} ${entry.path}`, entry])
+    );
+    const groups = new Map();
     routes.forEach((route) => {
-        const parts = route.route.split("/").filter(Boolean);
-        let current = root;
-
-        let params = [];
-
-        parts.forEach((part, index) => {
-            if (!current[part]) {
-                current[part] = {};
-            }
-
-            current = current[part];
-            if (isArg(part) && index < parts.length - 1) {
-                params.push(part);
-            }
-        });
-
-        current.routeConfigs = current.routeConfigs || { methods: [] };
-        current.routeConfigs.methods.push({
-            method: route.method,
-            options: route.options || [],
-            params,
-        });
+        const entry = entriesByRoute.get(`${route.method.toUpperCase()} ${route.route}`);
+        const group = entry ? folderOf(entry.source) : "server";
+        if (!groups.has(group)) {
+            groups.set(group, []);
+        }
+        groups.get(group).push({ ...route, entry });
     });
+    return [...groups.entries()].sort(([left], [right]) => {
+        if (left === "server") return 1;
+        if (right === "server") return -1;
+        return left.localeCompare(right);
+    });
+}
 
-    return root;
+function delayLabel(delay) {
+    return typeof delay === "number" ? `${delay}ms` : delay;
 }
 
-function createRouteNavBar(navElement, routes) {
-    const routeTree = createRouteTree(routes);
-    buildNavList(navElement, routeTree, "");
+function createGroupedNavBar(navElement, routes, entries) {
+    const groupList = document.createElement("ul");
+    groupRoutes(routes, entries).forEach(([group, members]) => {
+        const groupItem = document.createElement("li", { is: "route-item" });
+        groupItem.route = group;
+        groupItem.path = group;
+        groupItem.classList.add("expanded");
+        groupList.appendChild(groupItem);
+
+        const list = document.createElement("ul");
+        members
+            .sort((left, right) => left.route.localeCompare(right.route))
+            .forEach((route) => {
+                const item = document.createElement("li", { is: "route-item" });
+                item.route = `${route.method.toUpperCase()} ${route.route}`;
+                item.path = route.route;
+                item.method = route.method;
+                item.params = route.route.split("/").filter(isArg);
+                item.options = route.options;
+                item.dataset.search = [
+                    route.method,
+                    route.route,
+                    route.entry?.source || "",
+                    route.entry?.kind || "",
+                ]
+                    .join(" ")
+                    .toLowerCase();
+                list.appendChild(item);
+
+                if (route.protected || route.entry?.protected) {
+                    item.appendChild(badge("🔒 protected"));
+                }
+                if (route.entry?.delay !== undefined && route.entry?.delay !== null) {
+                    item.appendChild(badge(`⏱ ${delayLabel(route.entry.delay)}`));
+                }
+            });
+        groupItem.appendChild(list);
+    });
+    navElement.appendChild(groupList);
 }
 
-function isEndArgKey(leaf, key) {
-    return (
-        isArg(key) && leaf && Object.keys(leaf).length == 1 && leaf.routeConfigs
-    );
+function badge(text) {
+    const span = document.createElement("span");
+    span.className = "badge";
+    span.textContent = text;
+    return span;
 }
 
-function buildNavList(navList, leaf, path, param, ulParent) {
-    const ul = ulParent ?? document.createElement("ul");
-    Object.keys(leaf)
-        .sort((a, b) => {
-            if (isArg(a)) return 1;
-            if (isArg(b)) return -1;
-            return 0;
-        })
-        .forEach((key) => {
-            if (key === "routeConfigs") {
-                leaf.routeConfigs.methods.forEach((methodInfo) => {
-                    const item = document.createElement("li", {
-                        is: "route-item",
-                    });
-                    item.route = methodInfo.method;
-                    item.path = path;
-                    item.method = methodInfo.method;
-                    item.param = param;
-                    item.params = methodInfo.params;
-                    item.options = methodInfo.options;
-                    ul.appendChild(item);
-                });
-                return;
+// Shows the routes whose method, path, source, or kind contain `text`, and
+// the groups holding one of them.
+function filterRoutes(text) {
+    const filter = text.trim().toLowerCase();
+    document.querySelectorAll("#routes > ul > li").forEach((group) => {
+        let visible = 0;
+        group.querySelectorAll(":scope > ul > li").forEach((item) => {
+            item.hidden = filter !== "" && !item.dataset.search.includes(filter);
+            if (!item.hidden) {
+                visible += 1;
             }
+        });
+        group.hidden = visible === 0;
+        if (filter !== "" && visible > 0) {
+            group.classList.add("expanded");
+        }
+    });
+}
 
-            const current = leaf[key];
-
-            let el = navList;
-            let newPath = path;
-            if (isEndArgKey(current, key)) {
-                buildNavList(navList, current, path, key, ul);
-                return;
-            } else {
-                newPath = `${path}/${key}`;
-
-                el = document.createElement("li", { is: "route-item" });
-                el.route = key;
-                el.path = newPath;
-
-                ul.appendChild(el);
-            }
+const REGEX_PARAM = /^{(.+)}$/;
 
-            if (Object.keys(current).length > 0) {
-                let param = isArg(key) ? key : undefined;
-                buildNavList(el, current, newPath, param);
-            }
-        });
-    navList.appendChild(ul);
+function isArg(key) {
+    return REGEX_PARAM.test(key);
 }
 
 class RouteItem extends HTMLLIElement {
@@ -253,6 +266,13 @@ class RouteItem extends HTMLLIElement {
 
 customElements.define("route-item", RouteItem, { extends: "li" });
 
+// Link to the playground page of a route, with its full path.
+function playgroundUrl(method, route, param) {
+    const path =
+        param && !route.endsWith(`/${param}`) ? `${route}/${param}` : route;
+    return `/__playground?method=${method}&path=${encodeURIComponent(path)}`;
+}
+
 class ApiRequestSender extends HTMLElement {
     constructor() {
         super();
@@ -302,6 +322,7 @@ class ApiRequestSender extends HTMLElement {
                 return `<input type="text" id="param-input-${index}" placeholder="${param}" />`;
             })}
             <button id="send-btn">Send</button>
+            <a href="${playgroundUrl(method, route, param)}" target="_blank">playground ↗</a>
         `;
 
         // === Render Conditional Content (Second Row) ===
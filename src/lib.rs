@@ -15,27 +15,66 @@ pub const DEFAULT_COLLECTIONS_FOLDER: &str = "{collections}";
 pub const DEFAULT_SCHEMAS_FOLDER: &str = "{schemas}";
 /// Default file name for a complete compact database schema.
 pub const DEFAULT_SCHEMAS_DB_FILE: &str = "db.schema";
+/// Default file, relative to the mock root, with the body of unmatched routes.
+pub const DEFAULT_NOT_FOUND_BODY_FILE: &str = "__404.json";
+/// Default file, relative to the mock root, with the body of error responses.
+pub const DEFAULT_ERROR_BODY_FILE: &str = "__error.json";
 
 /// Application bootstrap, router assembly, and shared server state.
 pub mod app;
+/// Packing mock folders into `.zip` or `.tar.gz` archives and extracting them to serve.
+pub mod archive;
+/// Load generator reporting latency and throughput per route kind.
+pub mod bench;
+/// Mock folder validation used by the `check` command.
+pub mod check;
 /// Startup collection seed file loading.
 pub mod collection_files;
+/// Criteria language for filtering REST collection listings.
+pub mod criteria;
+/// Custom bodies for unmatched routes and error responses.
+pub mod error_bodies;
 /// Interactive mock route and configuration generator.
 pub mod generator;
 /// HTTP handlers for generated mock routes.
 pub mod handlers;
 /// Link model used by the generated home page.
 pub mod link;
+/// MQTT broker-lite publishing mock topics and collecting inbound messages.
+pub mod mqtt;
+/// OData query options for filtering, sorting, and paging REST listings.
+pub mod odata;
 /// Embedded home page renderer.
 pub mod pages;
+/// Per-server random sources, optionally seeded for deterministic runs.
+pub mod random;
+/// Validation of mock responses against OpenAPI and JSON Schema contracts.
+pub mod response_validation;
 /// File and directory route discovery.
 pub mod route_builder;
+/// Mock route files generated from captured traffic such as `.har` files.
+pub mod route_import;
+/// Dry-run listing of the routes a mock folder defines.
+pub mod route_inventory;
+/// Grouped startup summary of the registered routes.
+pub mod route_summary;
+/// Behavior changes scheduled over wall-clock time.
+pub mod schedule;
 /// Compact Fosk schema file loading and serialization.
 pub mod schema_files;
+/// Streaming loader for JSON array and NDJSON seed files.
+pub mod seed_stream;
+/// Embeddable mock server for integration tests.
+pub mod server;
+/// Placeholder rendering for JSON templates, including fake data.
+pub mod templates;
 /// Local HTTPS configuration and certificate handling.
 pub mod tls;
 /// Upload cleanup configuration.
 pub mod upload_configuration;
+/// File watching settings of the hot reload.
+pub mod watch;
 
 pub use app::App;
-pub use route_builder::config::{Config, ServerConfig};
+pub use route_builder::config::{Config, FolderMount, ServerConfig, TlsConfig};
+pub use server::{MockCollection, MockServer, MockServerHandle};
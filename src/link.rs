@@ -11,6 +11,9 @@ pub struct Link {
     pub route: String,
     /// Route capabilities used by the home page UI.
     pub options: Vec<String>,
+    /// Whether the route requires authentication.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
 }
 
 impl Link {
@@ -20,6 +23,7 @@ impl Link {
             method,
             route,
             options: options.to_vec(),
+            protected: false,
         }
     }
 }
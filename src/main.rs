@@ -1,10 +1,31 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use notify::{RecursiveMode, Watcher};
 use rs_mock_server::{
-    App, Config, DEFAULT_FOLDER, DEFAULT_PORT, ServerConfig, generator::run_generator,
+    App, Config, DEFAULT_FOLDER, DEFAULT_PORT, FolderMount, ServerConfig,
+    archive::{UnpackedArchive, pack, unpack_config_archives},
+    bench::{
+        BenchOptions, BenchTransport, classify_targets, default_targets, format_report,
+        parse_targets, run_bench,
+    },
+    check::check_folder,
+    collection_files::{SeedGeneration, generate_collection_file},
+    generator::run_generator,
+    handlers::UploadRegistry,
+    route_builder::{RouteS3, RouteUpload},
+    route_import::{
+        ImportFilter, ImportReport, read_har, read_insomnia, read_postman, write_route_files,
+    },
+    route_inventory::{format_table, list_routes},
+    watch::{WatchSettings, next_change},
+};
+use std::time::Duration;
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
-use std::time::{Duration, Instant};
-use std::{path::Path, sync::Arc};
 use tokio::sync::Mutex;
 use tokio::{signal, sync::mpsc};
 use tokio_util::sync::CancellationToken;
@@ -15,13 +36,20 @@ use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberI
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Port to run the server on
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Port to run the server on, 0 binds any free port
     #[arg(short, long, default_value_t = DEFAULT_PORT)]
     port: u16,
 
-    /// Directory to load mock files from
-    #[arg(short, long, default_value = DEFAULT_FOLDER)]
-    folder: String,
+    /// Write the bound port to this file once the server listens
+    #[arg(long = "port-file")]
+    port_file: Option<String>,
+
+    /// Directory or `.zip`/`.tar.gz` archive to load mock files from, as `[prefix:]path`; repeat to mount several trees
+    #[arg(short, long, global = true, default_value = DEFAULT_FOLDER)]
+    folder: Vec<String>,
 
     /// Disable CORS, by default CORS is enabled
     #[arg(short, long)]
@@ -46,6 +74,110 @@ struct Args {
     /// PEM private key path for HTTPS
     #[arg(long = "ssl-key")]
     ssl_key: Option<String>,
+
+    /// Seed generated UUIDs, fake values, and JGD data so runs return identical data
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Do not restart the server when mock files change
+    #[arg(long = "no-watch")]
+    no_watch: bool,
+
+    /// Overlay `<name>.<profile>.toml` files, such as `config.staging.toml`, over the base configs
+    #[arg(long, global = true)]
+    profile: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a collection seed file generated from a JGD template
+    Generate {
+        /// Collection name, also used as the seed file name
+        #[arg(long)]
+        collection: String,
+
+        /// JGD template file
+        #[arg(long)]
+        template: PathBuf,
+
+        /// Number of items to generate, overriding the template count
+        #[arg(long)]
+        count: Option<u64>,
+    },
+    /// Check the mock folder for problems without starting the server
+    Check,
+    /// Print every route the mock folder defines without starting the server
+    Routes {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = RoutesFormat::Table)]
+        format: RoutesFormat,
+    },
+    /// Send load to the mock routes and report latency and throughput per route kind
+    Bench {
+        /// File with one `[METHOD] PATH [JSON BODY]` target per line; every GET route without parameters by default
+        #[arg(long)]
+        routes: Option<PathBuf>,
+
+        /// Requests in flight at once
+        #[arg(long, default_value_t = 64)]
+        concurrency: usize,
+
+        /// Requests sent in total, spread over the targets in turn
+        #[arg(long, default_value_t = 10_000)]
+        requests: usize,
+
+        /// Running server to load, such as `http://localhost:4520`; routes are served in process by default
+        #[arg(long)]
+        url: Option<String>,
+    },
+    /// Pack the mock folder into a `.zip` or `.tar.gz` archive
+    Pack {
+        /// Archive to write; its extension selects the format
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Write route files into the mock folder from captured traffic
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportSource {
+    /// Import the successful responses of a browser-exported `.har` file
+    Har {
+        /// HAR file to import
+        file: PathBuf,
+
+        /// Only import requests sent to this host, such as `api.example.com`
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Only import requests whose path starts with this prefix, such as `/api`
+        #[arg(long)]
+        prefix: Option<String>,
+    },
+
+    /// Import the example responses of a Postman collection v2.0 or v2.1
+    Postman {
+        /// Exported collection file to import
+        file: PathBuf,
+    },
+
+    /// Import the requests of an Insomnia export as empty JSON routes
+    Insomnia {
+        /// Exported workspace file to import
+        file: PathBuf,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum RoutesFormat {
+    /// Aligned text table
+    Table,
+    /// JSON array of route objects
+    Json,
 }
 
 enum SessionResult {
@@ -53,13 +185,18 @@ enum SessionResult {
     Shutdown,
 }
 
-fn is_upload_folder(folder: &str) -> bool {
-    folder.contains("{upload}")
+/// Returns whether the path is an upload or S3 storage folder, or lies inside one.
+fn is_upload_folder(path: &Path) -> bool {
+    path.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        RouteUpload::is_upload_dir(&name) || RouteS3::is_s3_dir(&name)
+    })
 }
 
-async fn run_app_session(config: Config) -> SessionResult {
+async fn run_app_session(config: &mut Config, uploads: &Arc<UploadRegistry>) -> SessionResult {
     let token = CancellationToken::new();
-    let app = App::new(config);
+    let mut app = App::new(config.clone());
+    app.uploads = Arc::clone(uploads);
     let app_arc = Arc::new(Mutex::new(app));
 
     let main_logic = {
@@ -70,24 +207,36 @@ async fn run_app_session(config: Config) -> SessionResult {
         }
     };
 
+    // Set before cancelling when the session ends for a hot reload.
+    let restarting = Arc::new(AtomicBool::new(false));
     let app_finisher_task = tokio::spawn({
         let token_clone = token.clone();
         let app_ref = Arc::clone(&app_arc);
+        let restarting = Arc::clone(&restarting);
         async move {
             token_clone.cancelled().await;
             let mut app = app_ref.lock().await;
-            app.finish();
+            if restarting.load(Ordering::SeqCst) {
+                app.finish_for_reload();
+            } else {
+                app.finish();
+            }
         }
     });
 
-    tracing::info!(
-        "RS-MOCK-SERVER started. Watching for file changes in '{}'...",
-        app_arc.lock().await.get_folder()
-    );
+    let settings = WatchSettings::from_config(config.server.as_ref());
+    if settings.enabled {
+        tracing::info!(
+            "RS-MOCK-SERVER started. Watching for file changes in '{}'...",
+            app_arc.lock().await.get_folders().join("', '")
+        );
+    } else {
+        tracing::info!("RS-MOCK-SERVER started. File watching is disabled.");
+    }
 
     let (tx, mut rx) = mpsc::channel(1);
-    let last_send_time = Arc::new(Mutex::new(Instant::now() - Duration::from_millis(1000)));
-    let debounce_duration = Duration::from_millis(300);
+    let ignore = settings.clone();
+    let lazy_files = Arc::clone(&app_arc.lock().await.lazy_files);
 
     let mut watcher =
         notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
@@ -100,64 +249,96 @@ async fn run_app_session(config: Config) -> SessionResult {
                 }
 
                 for path in &event.paths {
-                    if is_upload_folder(path.to_str().unwrap()) {
+                    if is_upload_folder(path) {
                         // For upload folders, only allow modify events for folders, skip all file events
                         if !path.is_dir() {
                             return;
                         }
                     }
                 }
+                if event.paths.iter().all(|path| ignore.is_ignored(path)) {
+                    return;
+                }
+                // Handlers of lazy files read the new content on the next request.
+                if matches!(event.kind, notify::EventKind::Modify(_))
+                    && event.paths.iter().all(|path| lazy_files.contains(path))
+                {
+                    println!("Serving the changed file on the next request");
+                    return;
+                }
                 println!(
                     "event {:?}",
                     event
                         .paths
                         .iter()
-                        .map(|f| f.to_str().unwrap_or(""))
-                        .collect::<Vec<&str>>()
+                        .map(|f| f.to_string_lossy())
+                        .collect::<Vec<_>>()
                         .join("|")
                 );
 
-                // Simple debouncing - only send if enough time has passed since last send
-                let now = std::time::Instant::now();
-                let mut last_time = last_send_time.blocking_lock();
-
-                if now.duration_since(*last_time) >= debounce_duration {
-                    *last_time = now;
-                    let _ = tx.blocking_send(());
-                }
+                // A full channel already holds a pending change
+                let _ = tx.try_send(());
             }
         })
         .unwrap();
 
-    watcher
-        .watch(
-            Path::new(&app_arc.lock().await.get_folder()),
-            RecursiveMode::Recursive,
-        )
-        .unwrap();
+    if settings.enabled {
+        for folder in app_arc.lock().await.get_folders() {
+            watcher
+                .watch(Path::new(&folder), RecursiveMode::Recursive)
+                .unwrap();
+        }
+    }
 
     let result = tokio::select! {
         _ = main_logic => {
             tracing::warn!("Main logic completed unexpectedly. Shutting down.");
             SessionResult::Shutdown
         },
-        _ = rx.recv() => {
+        _ = next_change(&mut rx, settings.debounce) => {
             tracing::info!("File change detected. Restarting application...");
             SessionResult::Restart
         },
-        _ = signal::ctrl_c() => {
-            tracing::info!("Ctrl+C received. Shutting down.");
+        _ = shutdown_signal() => {
+            tracing::info!("Shutdown signal received. Shutting down.");
             SessionResult::Shutdown
         }
     };
 
+    restarting.store(matches!(result, SessionResult::Restart), Ordering::SeqCst);
     token.cancel();
     let _ = app_finisher_task.await;
+
+    // Reuse the port picked for `--port 0` after a hot reload.
+    if let Some(port) = app_arc.lock().await.bound_port() {
+        config.server.get_or_insert_default().port = Some(port);
+    }
     tracing::info!("Application instance shut down gracefully.");
 
     result
 }
 
+/// Waits for Ctrl+C, or SIGTERM on Unix, so a stopped container also shuts
+/// down gracefully and removes extracted mock archives.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = signal::ctrl_c() => {},
+        _ = terminate => {},
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::registry()
@@ -167,42 +348,311 @@ async fn main() {
 
     let args = Args::parse();
 
-    if args.generate {
-        if let Err(err) = run_generator(&args.folder) {
-            eprintln!("Generator failed: {}", err);
+    if let Some(Command::Generate {
+        collection,
+        template,
+        count,
+    }) = &args.command
+    {
+        let generation = SeedGeneration {
+            collection: collection.clone(),
+            template: template.clone(),
+            count: *count,
+            seed: args.seed,
+        };
+        let result =
+            load_config(&args).and_then(|config| generate_collection_file(&config, &generation));
+        match result {
+            Ok((path, count)) => {
+                println!("✔️ Wrote {} items to {}", count, path.to_string_lossy())
+            }
+            Err(err) => eprintln!("Error: {}", err),
         }
         return;
     }
 
-    let config = if let Ok(file) = std::fs::read_to_string("./rs-mock-server.toml") {
-        match Config::try_from(file.as_str()) {
-            Ok(config) => apply_cli_ssl_config(config, &args),
+    if let Some(Command::Pack { output }) = &args.command {
+        let folder = FolderMount::parse(&args.folder[0]).folder;
+        match pack(Path::new(&folder), output) {
+            Ok(count) => println!(
+                "✔️ Packed {} file(s) from {} into {}",
+                count,
+                folder,
+                output.to_string_lossy()
+            ),
             Err(err) => {
-                println!("Error: {}", err);
-                return;
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
             }
         }
-    } else {
-        Config {
-            server: Some(ServerConfig {
-                port: Some(args.port),
-                folder: Some(args.folder),
-                allowed_origin: args.allowed_origin,
-                enable_cors: Some(!args.disable_cors),
-                ssl: Some(args.ssl).filter(|enabled| *enabled),
-                ssl_cert: args.ssl_cert,
-                ssl_key: args.ssl_key,
-            }),
-            ..Default::default()
+        return;
+    }
+
+    if let Some(Command::Import { source }) = &args.command {
+        let folder = FolderMount::parse(&args.folder[0]).folder;
+        match import(Path::new(&folder), source) {
+            Ok(report) => println!(
+                "✔️ Wrote {} route file(s) into {}, skipped {} request(s)",
+                report.written.len(),
+                folder,
+                report.skipped
+            ),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Check) = &args.command {
+        let mut config = match load_config(&args) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("invalid TOML: ./rs-mock-server.toml: {}", err);
+                std::process::exit(1);
+            }
+        };
+        let folder = App::new(config.clone()).get_folder();
+        let unpacked = match unpack_config_archives(&mut config) {
+            Ok(unpacked) => unpacked,
+            Err(err) => {
+                eprintln!("✖ {}", err);
+                std::process::exit(1);
+            }
+        };
+        let problems = check_folder(&config);
+        // `exit` skips destructors, so the extracted archives are removed first.
+        drop(unpacked);
+        if problems.is_empty() {
+            println!("✔️ No problems found in {}", folder);
+            return;
+        }
+
+        for problem in &problems {
+            eprintln!("✖ {}", problem);
+        }
+        eprintln!("Found {} problem(s) in {}", problems.len(), folder);
+        std::process::exit(1);
+    }
+
+    if let Some(Command::Routes { format }) = &args.command {
+        let routes =
+            load_unpacked_config(&args).and_then(|(config, _unpacked)| list_routes(&config));
+        match routes {
+            Ok(routes) if *format == RoutesFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&routes).unwrap())
+            }
+            Ok(routes) => println!("{}", format_table(&routes)),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Bench {
+        routes,
+        concurrency,
+        requests,
+        url,
+    }) = &args.command
+    {
+        let options = BenchOptions {
+            concurrency: *concurrency,
+            requests: *requests,
+        };
+        match bench(&args, routes.as_deref(), url.as_deref(), options).await {
+            Ok(report) => println!("{}", report),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if args.generate {
+        let folder = FolderMount::parse(&args.folder[0]).folder;
+        if let Err(err) = run_generator(&folder) {
+            eprintln!("Generator failed: {}", err);
+        }
+        return;
+    }
+
+    let (mut config, _unpacked) = match load_unpacked_config(&args) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            println!("Error: {}", err);
+            return;
         }
     };
 
-    while let SessionResult::Restart = run_app_session(config.clone()).await {
+    // Shared by every session, so upload metadata survives hot reloads.
+    let uploads = Arc::default();
+    while let SessionResult::Restart = run_app_session(&mut config, &uploads).await {
         // Small delay before restarting
         tokio::time::sleep(Duration::from_millis(100)).await;
     }
 }
 
+/// Runs the `bench` command against a running server at `url`, or against the
+/// mock folder served in process, and returns the report.
+async fn bench(
+    args: &Args,
+    routes_file: Option<&Path>,
+    url: Option<&str>,
+    options: BenchOptions,
+) -> Result<String, String> {
+    let (config, _unpacked) = load_unpacked_config(args)?;
+    // Without a mock folder, targets of a running server keep the `other` kind.
+    let routes = list_routes(&config).or_else(|err| url.map(|_| vec![]).ok_or(err))?;
+    let targets = match routes_file {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|err| format!("Could not read {}: {}", path.to_string_lossy(), err))?;
+            let mut targets = parse_targets(&text)?;
+            classify_targets(&mut targets, &routes);
+            targets
+        }
+        None => default_targets(&routes),
+    };
+    if targets.is_empty() {
+        return Err("no GET route without parameters to benchmark, pass --routes".to_string());
+    }
+
+    let transport = match url {
+        Some(url) => BenchTransport::from_url(url)?,
+        None => {
+            let mut app = App::new(config);
+            app.quiet = true;
+            BenchTransport::Router(app.into_router())
+        }
+    };
+    println!(
+        "Sending {} requests to {} target(s), {} at a time",
+        options.requests,
+        targets.len(),
+        options.concurrency
+    );
+    let stats = run_bench(transport, targets, options).await;
+    Ok(format_report(&stats))
+}
+
+/// Reads `./rs-mock-server.toml` when present, or builds the configuration from CLI arguments.
+fn load_config(args: &Args) -> Result<Config, String> {
+    if let Ok(file) = std::fs::read_to_string("./rs-mock-server.toml") {
+        return Config::try_from(file.as_str())
+            .map_err(|err| err.to_string())
+            .and_then(|config| apply_profile(config, args))
+            .map(|config| {
+                apply_cli_watch(
+                    apply_cli_port_file(
+                        apply_cli_seed(apply_cli_ssl_config(config, args), args),
+                        args,
+                    ),
+                    args,
+                )
+            });
+    }
+
+    let (folder, mounts) = folder_mounts(&args.folder);
+    let config = Config {
+        server: Some(ServerConfig {
+            port: Some(args.port),
+            port_file: args.port_file.clone(),
+            folder,
+            mounts,
+            allowed_origin: args.allowed_origin.clone(),
+            enable_cors: Some(!args.disable_cors),
+            ssl: Some(args.ssl).filter(|enabled| *enabled),
+            ssl_cert: args.ssl_cert.clone(),
+            ssl_key: args.ssl_key.clone(),
+            seed: args.seed,
+            watch: Some(false).filter(|_| args.no_watch),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    apply_profile(config, args)
+}
+
+/// Selects the `--profile` flag, or the `server.profile` setting, and overlays
+/// `./rs-mock-server.<profile>.toml` when present.
+fn apply_profile(config: Config, args: &Args) -> Result<Config, String> {
+    let Some(profile) = args.profile.clone().or_else(|| {
+        config
+            .server
+            .as_ref()
+            .and_then(|server| server.profile.clone())
+    }) else {
+        return Ok(config);
+    };
+    if profile.is_empty() || profile.contains(['.', '/', '\\']) {
+        return Err(format!(
+            "Invalid profile '{}': use a name without dots or slashes",
+            profile
+        ));
+    }
+
+    let profile_file = format!("./rs-mock-server.{}.toml", profile);
+    let mut config = match std::fs::read_to_string(&profile_file) {
+        Ok(file) => Config::try_from(file.as_str())
+            .map_err(|err| format!("{}: {}", profile_file, err))?
+            .overlay(config),
+        Err(_) => config,
+    };
+    let mut server = config.server.unwrap_or_default();
+    server.profile = Some(profile);
+    config.server = Some(server);
+    Ok(config)
+}
+
+/// Loads the configuration like [`load_config`] and extracts archived mock
+/// folders, which are removed once the returned archives are dropped.
+fn load_unpacked_config(args: &Args) -> Result<(Config, Vec<UnpackedArchive>), String> {
+    let mut config = load_config(args)?;
+    let unpacked = unpack_config_archives(&mut config)?;
+    Ok((config, unpacked))
+}
+
+/// Keeps a single unprefixed `--folder` as the main folder and mounts every
+/// folder otherwise.
+fn folder_mounts(folders: &[String]) -> (Option<String>, Option<Vec<FolderMount>>) {
+    let mounts = folders
+        .iter()
+        .map(|folder| FolderMount::parse(folder))
+        .collect::<Vec<_>>();
+    match mounts.as_slice() {
+        [
+            FolderMount {
+                prefix: None,
+                folder,
+            },
+        ] => (Some(folder.clone()), None),
+        _ => (None, Some(mounts)),
+    }
+}
+
+/// Writes the route files captured by an import source into `folder`.
+fn import(folder: &Path, source: &ImportSource) -> Result<ImportReport, String> {
+    let (responses, skipped) = match source {
+        ImportSource::Har { file, host, prefix } => {
+            let filter = ImportFilter {
+                host: host.clone(),
+                prefix: prefix.clone(),
+            };
+            read_har(file, &filter)?
+        }
+        ImportSource::Postman { file } => read_postman(file)?,
+        ImportSource::Insomnia { file } => read_insomnia(file)?,
+    };
+    let mut report = write_route_files(folder, responses)?;
+    report.skipped += skipped;
+    Ok(report)
+}
+
 fn apply_cli_ssl_config(mut config: Config, args: &Args) -> Config {
     if !args.ssl && args.ssl_cert.is_none() && args.ssl_key.is_none() {
         return config;
@@ -217,15 +667,103 @@ fn apply_cli_ssl_config(mut config: Config, args: &Args) -> Config {
     config
 }
 
+fn apply_cli_seed(mut config: Config, args: &Args) -> Config {
+    if args.seed.is_some() {
+        let mut server = config.server.unwrap_or_default();
+        server.seed = args.seed;
+        config.server = Some(server);
+    }
+
+    config
+}
+
+fn apply_cli_port_file(mut config: Config, args: &Args) -> Config {
+    if args.port_file.is_some() {
+        let mut server = config.server.unwrap_or_default();
+        server.port_file = args.port_file.clone();
+        config.server = Some(server);
+    }
+
+    config
+}
+
+fn apply_cli_watch(mut config: Config, args: &Args) -> Config {
+    if args.no_watch {
+        let mut server = config.server.unwrap_or_default();
+        server.watch = Some(false);
+        config.server = Some(server);
+    }
+
+    config
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn upload_folder_detection_matches_upload_marker() {
-        assert!(is_upload_folder("mocks/{upload}"));
-        assert!(is_upload_folder("mocks/${upload}{temp}"));
-        assert!(!is_upload_folder("mocks/uploads"));
+        assert!(is_upload_folder(Path::new("mocks/{upload}")));
+        assert!(is_upload_folder(Path::new("mocks/${upload}{temp}")));
+        assert!(is_upload_folder(Path::new(
+            "mocks/{s3}-storage/photos/cat.png"
+        )));
+        assert!(!is_upload_folder(Path::new("mocks/uploads")));
+        assert!(!is_upload_folder(Path::new("mocks/notes{upload}.json")));
+        assert!(!is_upload_folder(Path::new("mocks/my{s3}backup/cat.png")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn upload_folder_detection_accepts_non_utf8_paths() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let folder = Path::new("mocks/{upload}").join(OsStr::from_bytes(b"caf\xe9.png"));
+        assert!(is_upload_folder(&folder));
+        assert!(!is_upload_folder(Path::new(OsStr::from_bytes(
+            b"mocks/caf\xe9"
+        ))));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn upload_folder_detection_splits_windows_paths() {
+        assert!(is_upload_folder(Path::new(r"C:\mocks\{upload}\cat.png")));
+        assert!(is_upload_folder(Path::new(r"\\?\C:\mocks\{s3}-storage")));
+        assert!(!is_upload_folder(Path::new(r"C:\mocks\uploads\cat.png")));
+    }
+
+    #[test]
+    fn generate_subcommand_parses_seed_options() {
+        let args = Args::parse_from([
+            "rs-mock-server",
+            "generate",
+            "--collection",
+            "users",
+            "--count",
+            "1000",
+            "--template",
+            "users.jgd",
+            "--seed",
+            "42",
+            "--folder",
+            "fixtures",
+        ]);
+
+        assert_eq!(args.folder, ["fixtures"]);
+        assert_eq!(args.seed, Some(42));
+        match args.command {
+            Some(Command::Generate {
+                collection,
+                template,
+                count,
+            }) => {
+                assert_eq!(collection, "users");
+                assert_eq!(template, PathBuf::from("users.jgd"));
+                assert_eq!(count, Some(1000));
+            }
+            _ => panic!("expected generate subcommand"),
+        }
     }
 
     #[test]
@@ -252,4 +790,109 @@ mod tests {
         assert_eq!(server.ssl_cert, Some("localhost.pem".into()));
         assert_eq!(server.ssl_key, Some("localhost-key.pem".into()));
     }
+
+    #[test]
+    fn cli_seed_overrides_file_config() {
+        let config = Config {
+            server: Some(ServerConfig {
+                seed: Some(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let unchanged = apply_cli_seed(config.clone(), &Args::parse_from(["rs-mock-server"]));
+        assert_eq!(unchanged.server.unwrap().seed, Some(1));
+
+        let args = Args::parse_from(["rs-mock-server", "--seed", "7"]);
+        assert_eq!(apply_cli_seed(config, &args).server.unwrap().seed, Some(7));
+    }
+
+    #[test]
+    fn cli_port_file_overlays_file_config() {
+        let config = Config {
+            server: Some(ServerConfig {
+                port: Some(8080),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let args = Args::parse_from(["rs-mock-server", "--port", "0", "--port-file", "port.txt"]);
+        assert_eq!(args.port, 0);
+
+        let server = apply_cli_port_file(config, &args).server.unwrap();
+        assert_eq!(server.port, Some(8080));
+        assert_eq!(server.port_file, Some("port.txt".into()));
+    }
+
+    #[test]
+    fn cli_no_watch_disables_file_watching() {
+        let config = Config {
+            server: Some(ServerConfig {
+                watch_debounce: Some(500),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let unchanged = apply_cli_watch(config.clone(), &Args::parse_from(["rs-mock-server"]));
+        assert_eq!(unchanged.server.unwrap().watch, None);
+
+        let args = Args::parse_from(["rs-mock-server", "--no-watch"]);
+        let server = apply_cli_watch(config, &args).server.unwrap();
+        assert_eq!(server.watch, Some(false));
+        assert_eq!(server.watch_debounce, Some(500));
+    }
+
+    #[test]
+    fn cli_profile_selects_the_profile() {
+        let config = Config {
+            server: Some(ServerConfig {
+                profile: Some("ci".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let unchanged = apply_profile(config.clone(), &Args::parse_from(["rs-mock-server"]));
+        assert_eq!(
+            unchanged.unwrap().server.unwrap().profile.as_deref(),
+            Some("ci")
+        );
+
+        let args = Args::parse_from(["rs-mock-server", "--profile", "rs-mock-server-test"]);
+        let server = apply_profile(config.clone(), &args)
+            .unwrap()
+            .server
+            .unwrap();
+        assert_eq!(server.profile.as_deref(), Some("rs-mock-server-test"));
+
+        let args = Args::parse_from(["rs-mock-server", "--profile", "../staging"]);
+        assert!(apply_profile(config, &args).is_err());
+    }
+
+    #[test]
+    fn repeated_folders_are_mounted_under_their_prefixes() {
+        let args = Args::parse_from([
+            "rs-mock-server",
+            "--folder",
+            "api1:./mocks/service-a",
+            "--folder",
+            "/billing:./mocks/service-b",
+        ]);
+
+        let (folder, mounts) = folder_mounts(&args.folder);
+        assert_eq!(folder, None);
+        assert_eq!(
+            mounts.unwrap(),
+            [
+                FolderMount::parse("/api1:./mocks/service-a"),
+                FolderMount::parse("/billing:./mocks/service-b")
+            ]
+        );
+
+        let args = Args::parse_from(["rs-mock-server", "--folder", "fixtures"]);
+        assert_eq!(folder_mounts(&args.folder), (Some("fixtures".into()), None));
+    }
 }
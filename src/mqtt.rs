@@ -0,0 +1,690 @@
+//! MQTT broker-lite for IoT dashboards mocked alongside REST.
+//!
+//! The `[mqtt]` table starts a small MQTT 3.1.1 broker. `[[mqtt.publish]]`
+//! entries publish a topic from a JSON or JGD mock file on a schedule, and
+//! `[[mqtt.collect]]` entries store the messages clients publish on matching
+//! topics in collections, where REST and GraphQL routes can read them.
+//!
+//! Messages are delivered to subscribers at QoS 0. Inbound QoS 1 and 2
+//! publications are acknowledged, but there are no sessions, wills, or
+//! authentication.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinSet,
+};
+
+use crate::{
+    handlers::{Change, ChangeEvents, Partition, id_string, is_jgd},
+    random,
+    response_validation::resolve_contract_path,
+    route_builder::config::{Config, MqttCollect, MqttConfig, MqttPublish},
+    templates::render_value,
+};
+
+/// Port the broker listens on when `port` is not set.
+pub const DEFAULT_MQTT_PORT: u16 = 1883;
+/// Seconds between scheduled messages when `interval` is not set.
+pub const DEFAULT_PUBLISH_INTERVAL: u64 = 5;
+/// Largest packet accepted from a client.
+const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+const CONNECT: u8 = 1;
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+const PUBACK: u8 = 4;
+const PUBREC: u8 = 5;
+const PUBREL: u8 = 6;
+const PUBCOMP: u8 = 7;
+const SUBSCRIBE: u8 = 8;
+const SUBACK: u8 = 9;
+const UNSUBSCRIBE: u8 = 10;
+const UNSUBACK: u8 = 11;
+const PINGREQ: u8 = 12;
+const PINGRESP: u8 = 13;
+const DISCONNECT: u8 = 14;
+
+/// A connected client: where its packets are queued and what it subscribed to.
+struct Client {
+    sender: mpsc::UnboundedSender<Vec<u8>>,
+    filters: Vec<String>,
+}
+
+/// Routes messages between clients, retains them, and stores inbound ones.
+pub struct MqttBroker {
+    port: u16,
+    publish: Vec<MqttPublish>,
+    collect: Vec<MqttCollect>,
+    config: Config,
+    partition: Partition,
+    events: Arc<ChangeEvents>,
+    clients: Mutex<HashMap<u64, Client>>,
+    retained: Mutex<HashMap<String, Vec<u8>>>,
+    next_client: AtomicU64,
+}
+
+impl MqttBroker {
+    /// Creates the broker of the `[mqtt]` table, storing inbound messages in
+    /// `partition` and announcing them on `events`.
+    pub fn new(
+        config: &Config,
+        mqtt: MqttConfig,
+        partition: Partition,
+        events: Arc<ChangeEvents>,
+    ) -> Self {
+        Self {
+            port: mqtt.port.unwrap_or(DEFAULT_MQTT_PORT),
+            publish: mqtt.publish.unwrap_or_default(),
+            collect: mqtt.collect.unwrap_or_default(),
+            config: config.clone(),
+            partition,
+            events,
+            clients: Mutex::default(),
+            retained: Mutex::default(),
+            next_client: AtomicU64::new(0),
+        }
+    }
+
+    /// Port the broker listens on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Topics published from mock files.
+    pub fn publications(&self) -> &[MqttPublish] {
+        &self.publish
+    }
+
+    /// Delivers a message to the subscribers of its topic, keeping it for
+    /// later subscribers when `retain` is set; an empty retained message
+    /// clears the topic.
+    pub fn publish(&self, topic: &str, payload: &[u8], retain: bool) {
+        if retain {
+            let mut retained = self.retained.lock().unwrap();
+            if payload.is_empty() {
+                retained.remove(topic);
+            } else {
+                retained.insert(topic.to_string(), payload.to_vec());
+            }
+        }
+
+        let packet = publish_packet(topic, payload, false);
+        for client in self.clients.lock().unwrap().values() {
+            if client
+                .filters
+                .iter()
+                .any(|filter| topic_matches(filter, topic))
+            {
+                let _ = client.sender.send(packet.clone());
+            }
+        }
+    }
+
+    /// Publishes the rendered file of a `[[mqtt.publish]]` entry once.
+    pub fn publish_file(&self, publication: &MqttPublish) -> Result<(), String> {
+        let path = resolve_contract_path(&self.config, &publication.file);
+        let payload = render_payload(&path)?;
+        self.publish(
+            &publication.topic,
+            &payload,
+            publication.retain.unwrap_or(false),
+        );
+        Ok(())
+    }
+
+    /// Stores a message in the collections whose filter matches its topic.
+    fn collect(&self, topic: &str, payload: &[u8]) {
+        for rule in self
+            .collect
+            .iter()
+            .filter(|rule| topic_matches(&rule.topic, topic))
+        {
+            let db = &self.partition.db;
+            let collection = db
+                .get(&rule.collection)
+                .unwrap_or_else(|| db.create(&rule.collection));
+            match random::current().add_item(&collection, inbound_item(topic, payload)) {
+                Ok(item) => {
+                    let id_key = collection
+                        .get_config()
+                        .map(|config| config.id_key)
+                        .unwrap_or_default();
+                    if let Some(id) = item.get(&id_key).and_then(id_string) {
+                        self.events
+                            .publish(Change::created(&rule.collection, &id, &item));
+                        self.partition.collection_order.push(&rule.collection, id);
+                    }
+                }
+                Err(err) => eprintln!(
+                    "⚠️ Unable to store the MQTT message of {} in {}: {}",
+                    topic, rule.collection, err
+                ),
+            }
+        }
+    }
+
+    /// Accepts clients until the task is aborted, which disconnects them.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    if let Ok((stream, _)) = accepted {
+                        connections.spawn(random::scope(
+                            random::current(),
+                            Arc::clone(&self).handle_connection(stream),
+                        ));
+                    }
+                }
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            }
+        }
+    }
+
+    async fn handle_connection(self: Arc<Self>, stream: TcpStream) {
+        let (mut reader, mut writer) = stream.into_split();
+        let Ok(Some((header, body))) = read_packet(&mut reader).await else {
+            return;
+        };
+        if header >> 4 != CONNECT {
+            return;
+        }
+        // Protocol levels 3 and 4 are MQTT 3.1 and 3.1.1.
+        if !matches!(connect_level(&body), Some(3 | 4)) {
+            let _ = writer.write_all(&[CONNACK << 4, 2, 0, 1]).await;
+            return;
+        }
+        if writer.write_all(&[CONNACK << 4, 2, 0, 0]).await.is_err() {
+            return;
+        }
+
+        let (sender, mut outgoing) = mpsc::unbounded_channel::<Vec<u8>>();
+        let id = self.next_client.fetch_add(1, Ordering::SeqCst);
+        self.clients.lock().unwrap().insert(
+            id,
+            Client {
+                sender: sender.clone(),
+                filters: vec![],
+            },
+        );
+
+        let writing = async move {
+            while let Some(packet) = outgoing.recv().await {
+                if writer.write_all(&packet).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let reading = async {
+            while let Ok(Some((header, body))) = read_packet(&mut reader).await {
+                if !self.handle_packet(id, header, &body, &sender) {
+                    break;
+                }
+            }
+        };
+        tokio::select! {
+            _ = writing => {}
+            _ = reading => {}
+        }
+        self.clients.lock().unwrap().remove(&id);
+    }
+
+    /// Answers one packet of a connected client. Returns false when the
+    /// connection must be closed.
+    fn handle_packet(
+        &self,
+        id: u64,
+        header: u8,
+        body: &[u8],
+        sender: &mpsc::UnboundedSender<Vec<u8>>,
+    ) -> bool {
+        let mut reader = PacketReader::new(body);
+        match header >> 4 {
+            PUBLISH => {
+                let qos = (header >> 1) & 3;
+                let Some(topic) = reader.string() else {
+                    return false;
+                };
+                let packet_id = if qos > 0 { reader.u16() } else { Some(0) };
+                let Some(packet_id) = packet_id.filter(|_| is_valid_topic(&topic)) else {
+                    return false;
+                };
+                let payload = reader.rest();
+                self.publish(&topic, payload, header & 1 == 1);
+                self.collect(&topic, payload);
+                match qos {
+                    1 => send_ack(sender, PUBACK << 4, packet_id),
+                    2 => send_ack(sender, PUBREC << 4, packet_id),
+                    _ => {}
+                }
+                true
+            }
+            PUBREL => match reader.u16() {
+                Some(packet_id) => {
+                    send_ack(sender, PUBCOMP << 4, packet_id);
+                    true
+                }
+                None => false,
+            },
+            SUBSCRIBE => {
+                let Some(packet_id) = reader.u16() else {
+                    return false;
+                };
+                let mut granted = vec![];
+                let mut filters = vec![];
+                while let Some(filter) = reader.string() {
+                    let Some(_qos) = reader.u8() else {
+                        return false;
+                    };
+                    if is_valid_filter(&filter) {
+                        granted.push(0);
+                        filters.push(filter);
+                    } else {
+                        granted.push(0x80);
+                    }
+                }
+
+                let mut suback = packet_id.to_be_bytes().to_vec();
+                suback.extend(granted);
+                let _ = sender.send(packet(SUBACK << 4, &suback));
+                for (topic, payload) in self.retained.lock().unwrap().iter() {
+                    if filters.iter().any(|filter| topic_matches(filter, topic)) {
+                        let _ = sender.send(publish_packet(topic, payload, true));
+                    }
+                }
+                if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+                    client.filters.extend(filters);
+                }
+                true
+            }
+            UNSUBSCRIBE => {
+                let Some(packet_id) = reader.u16() else {
+                    return false;
+                };
+                let mut filters = vec![];
+                while let Some(filter) = reader.string() {
+                    filters.push(filter);
+                }
+                if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+                    client.filters.retain(|filter| !filters.contains(filter));
+                }
+                send_ack(sender, UNSUBACK << 4, packet_id);
+                true
+            }
+            PINGREQ => {
+                let _ = sender.send(vec![PINGRESP << 4, 0]);
+                true
+            }
+            DISCONNECT | CONNECT => false,
+            _ => true,
+        }
+    }
+}
+
+/// Starts the broker of the `[mqtt]` table and its scheduled publications
+/// as background tasks of the app.
+pub fn start_mqtt_broker(app: &mut crate::App) {
+    let Some(mqtt) = app.server_config.mqtt.clone() else {
+        return;
+    };
+    let broker = Arc::new(MqttBroker::new(
+        &app.server_config,
+        mqtt,
+        app.shared_partition(),
+        Arc::clone(&app.change_events),
+    ));
+
+    let listening = Arc::clone(&broker);
+    let quiet = app.quiet;
+    app.spawn_background(async move {
+        match TcpListener::bind(("0.0.0.0", listening.port())).await {
+            Ok(listener) => {
+                if !quiet && let Ok(address) = listener.local_addr() {
+                    println!(
+                        "🛰️ MQTT broker listening on mqtt://localhost:{}",
+                        address.port()
+                    );
+                }
+                listening.serve(listener).await;
+            }
+            Err(err) => eprintln!(
+                "⚠️ Unable to start the MQTT broker on port {}: {}",
+                listening.port(),
+                err
+            ),
+        }
+    });
+
+    for publication in broker.publications().to_vec() {
+        let broker = Arc::clone(&broker);
+        let period = Duration::from_secs(
+            publication
+                .interval
+                .unwrap_or(DEFAULT_PUBLISH_INTERVAL)
+                .max(1),
+        );
+        app.spawn_background(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(err) = broker.publish_file(&publication) {
+                    eprintln!("⚠️ Unable to publish {}: {}", publication.topic, err);
+                }
+            }
+        });
+    }
+}
+
+/// Returns whether a topic filter matches a topic: `+` matches one level and
+/// a trailing `#` matches the rest, but wildcards never match the first
+/// level of `$` topics.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(filter), Some(topic)) if filter == topic => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+fn is_valid_topic(topic: &str) -> bool {
+    !topic.is_empty() && !topic.contains(['+', '#'])
+}
+
+fn is_valid_filter(filter: &str) -> bool {
+    let levels = filter.split('/').collect::<Vec<_>>();
+    !filter.is_empty()
+        && levels
+            .iter()
+            .enumerate()
+            .all(|(index, level)| match *level {
+                "#" => index == levels.len() - 1,
+                "+" => true,
+                level => !level.contains(['+', '#']),
+            })
+}
+
+/// Renders a mock file as a message: JGD is generated, JSON has its
+/// placeholders rendered, and other files are sent as they are.
+fn render_payload(path: &Path) -> Result<Vec<u8>, String> {
+    let value = if is_jgd(&path.as_os_str().to_os_string()) {
+        random::current()
+            .generate_jgd(path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?
+    } else {
+        let bytes = fs::read(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) => value,
+            Err(_) => return Ok(bytes),
+        }
+    };
+    serde_json::to_vec(&render_value(&value, Utc::now())).map_err(|err| err.to_string())
+}
+
+/// Item stored for an inbound message: a JSON object payload with its topic
+/// and arrival time, or the payload as `value`.
+fn inbound_item(topic: &str, payload: &[u8]) -> Value {
+    let value = serde_json::from_slice::<Value>(payload)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(payload).into_owned()));
+    let mut item = match value {
+        Value::Object(fields) => fields,
+        value => serde_json::Map::from_iter([("value".to_string(), value)]),
+    };
+    item.entry("topic").or_insert_with(|| json!(topic));
+    item.entry("receivedAt")
+        .or_insert_with(|| json!(Utc::now().to_rfc3339()));
+    Value::Object(item)
+}
+
+/// Returns the protocol level of a CONNECT packet.
+fn connect_level(body: &[u8]) -> Option<u8> {
+    let mut reader = PacketReader::new(body);
+    reader.string()?;
+    reader.u8()
+}
+
+fn send_ack(sender: &mpsc::UnboundedSender<Vec<u8>>, header: u8, packet_id: u16) {
+    let _ = sender.send(packet(header, &packet_id.to_be_bytes()));
+}
+
+/// Encodes a QoS 0 PUBLISH packet.
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = (topic.len() as u16).to_be_bytes().to_vec();
+    body.extend(topic.as_bytes());
+    body.extend(payload);
+    packet(PUBLISH << 4 | u8::from(retain), &body)
+}
+
+/// Encodes a packet: its header byte, remaining length, and body.
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            packet.push(byte | 0x80);
+        } else {
+            packet.push(byte);
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+/// Reads one packet, returning its header byte and body, or `None` when the
+/// client closed the connection.
+async fn read_packet<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0u8; 1];
+    if reader.read(&mut header).await? == 0 {
+        return Ok(None);
+    }
+
+    let mut length = 0usize;
+    for shift in (0..28).step_by(7) {
+        let byte = reader.read_u8().await?;
+        length |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            if length > MAX_PACKET_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "packet too large",
+                ));
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await?;
+            return Ok(Some((header[0], body)));
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "invalid remaining length",
+    ))
+}
+
+/// Reads the fields of a packet body.
+struct PacketReader<'a> {
+    body: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PacketReader<'a> {
+    fn new(body: &'a [u8]) -> Self {
+        Self { body, position: 0 }
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let byte = *self.body.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from(self.u8()?) << 8 | u16::from(self.u8()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.u16()? as usize;
+        let bytes = self.body.get(self.position..self.position + length)?;
+        self.position += length;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.body[self.position.min(self.body.len())..];
+        self.position = self.body.len();
+        rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{App, route_builder::config::ServerConfig};
+
+    #[test]
+    fn filters_match_topics_level_by_level() {
+        assert!(topic_matches(
+            "sensors/+/temperature",
+            "sensors/kitchen/temperature"
+        ));
+        assert!(!topic_matches(
+            "sensors/+/temperature",
+            "sensors/kitchen/humidity"
+        ));
+        assert!(topic_matches("sensors/#", "sensors"));
+        assert!(topic_matches("sensors/#", "sensors/kitchen/humidity"));
+        assert!(topic_matches("#", "devices/1"));
+        assert!(!topic_matches("#", "$SYS/uptime"));
+        assert!(!topic_matches("sensors", "sensors/kitchen"));
+
+        assert!(is_valid_filter("sensors/+/temperature"));
+        assert!(!is_valid_filter("sensors/#/temperature"));
+        assert!(!is_valid_filter("sensors/kit+chen"));
+        assert!(!is_valid_topic("sensors/+"));
+    }
+
+    fn connect() -> Vec<u8> {
+        let mut body = 4u16.to_be_bytes().to_vec();
+        body.extend(b"MQTT");
+        body.extend([4, 2, 0, 60]);
+        body.extend(4u16.to_be_bytes());
+        body.extend(b"test");
+        packet(CONNECT << 4, &body)
+    }
+
+    fn subscribe(filter: &str) -> Vec<u8> {
+        let mut body = 1u16.to_be_bytes().to_vec();
+        body.extend((filter.len() as u16).to_be_bytes());
+        body.extend(filter.as_bytes());
+        body.push(0);
+        packet(SUBSCRIBE << 4 | 2, &body)
+    }
+
+    async fn read(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        tokio::time::timeout(Duration::from_secs(5), read_packet(stream))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn clients_receive_published_files_and_inbound_messages_are_collected() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(
+            folder.path().join("temperature.json"),
+            r#"{"celsius": 21.5, "at": "{{timestamp}}"}"#,
+        )
+        .unwrap();
+
+        let app = App::default();
+        let config = Config {
+            server: Some(ServerConfig {
+                folder: Some(folder.path().to_string_lossy().to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mqtt = MqttConfig {
+            port: Some(0),
+            publish: Some(vec![MqttPublish {
+                topic: "sensors/kitchen/temperature".to_string(),
+                file: "temperature.json".to_string(),
+                retain: Some(true),
+                ..Default::default()
+            }]),
+            collect: Some(vec![MqttCollect {
+                topic: "devices/+/status".to_string(),
+                collection: "device_status".to_string(),
+            }]),
+        };
+        let broker = Arc::new(MqttBroker::new(
+            &config,
+            mqtt,
+            app.shared_partition(),
+            Arc::clone(&app.change_events),
+        ));
+        broker.publish_file(&broker.publications()[0]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Arc::clone(&broker).serve(listener));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client.write_all(&connect()).await.unwrap();
+        assert_eq!(read(&mut client).await, (CONNACK << 4, vec![0, 0]));
+
+        client.write_all(&subscribe("sensors/#")).await.unwrap();
+        assert_eq!(read(&mut client).await, (SUBACK << 4, vec![0, 1, 0]));
+        let (header, body) = read(&mut client).await;
+        assert_eq!(header, PUBLISH << 4 | 1);
+        let mut reader = PacketReader::new(&body);
+        assert_eq!(reader.string().unwrap(), "sensors/kitchen/temperature");
+        let message: Value = serde_json::from_slice(reader.rest()).unwrap();
+        assert_eq!(message["celsius"], 21.5);
+        assert!(message["at"].is_number());
+
+        let mut body = 16u16.to_be_bytes().to_vec();
+        body.extend(b"devices/7/status");
+        body.extend(9u16.to_be_bytes());
+        body.extend(br#"{"online":true}"#);
+        client
+            .write_all(&packet(PUBLISH << 4 | 2, &body))
+            .await
+            .unwrap();
+        assert_eq!(read(&mut client).await, (PUBACK << 4, vec![0, 9]));
+
+        let stored = app.db.get("device_status").unwrap().get_all().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0]["online"], true);
+        assert_eq!(stored[0]["topic"], "devices/7/status");
+
+        client.write_all(&[PINGREQ << 4, 0]).await.unwrap();
+        assert_eq!(read(&mut client).await, (PINGRESP << 4, vec![]));
+    }
+}
@@ -0,0 +1,601 @@
+//! OData query options on REST collection listings.
+//!
+//! `$filter` is translated into a [`Criteria`] expression, and `$orderby`,
+//! `$skip`, `$top`, `$select`, and `$count` shape the listed page, for
+//! example:
+//!
+//! ```text
+//! ?$filter=price gt 20 and contains(name, 'Wireless')&$orderby=price desc&$top=10
+//! ```
+
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
+
+use serde_json::{Map, Number, Value};
+
+use crate::criteria::{Comparer, Constraint, Criteria, CriteriaError, field_value, order};
+
+/// Error returned when an OData query option cannot be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ODataError {
+    /// `$filter` is not a supported expression.
+    InvalidFilter(CriteriaError),
+    /// An option holds a value it does not accept.
+    InvalidOption {
+        /// Option name, such as `$top`.
+        option: String,
+        /// The rejected value.
+        value: String,
+    },
+}
+
+impl Display for ODataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ODataError::InvalidFilter(err) => write!(f, "invalid $filter: {}", err),
+            ODataError::InvalidOption { option, value } => {
+                write!(f, "invalid {} value '{}'", option, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ODataError {}
+
+/// One `$orderby` key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    /// Sorted field name or dot path.
+    pub field: String,
+    /// Sort from the highest value down.
+    pub descending: bool,
+}
+
+/// OData query options of a listing request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ODataQuery {
+    /// Criteria translated from `$filter`.
+    pub filter: Option<Criteria>,
+    /// Sort keys from `$orderby`, most significant first.
+    pub order_by: Vec<OrderBy>,
+    /// Items skipped by `$skip`.
+    pub skip: usize,
+    /// Most items returned, from `$top`.
+    pub top: Option<usize>,
+    /// Fields kept by `$select`.
+    pub select: Option<Vec<String>>,
+    /// Whether `$count=true` asks for the number of matching items.
+    pub count: bool,
+}
+
+impl ODataQuery {
+    /// Reads the `$`-prefixed options from listing query parameters.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, ODataError> {
+        let invalid = |option: &str, value: &str| ODataError::InvalidOption {
+            option: option.to_string(),
+            value: value.to_string(),
+        };
+        let number = |option: &str| {
+            params
+                .get(option)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| invalid(option, value))
+                })
+                .transpose()
+        };
+
+        let filter = params
+            .get("$filter")
+            .map(|text| parse_filter(text))
+            .transpose()
+            .map_err(ODataError::InvalidFilter)?;
+
+        let mut order_by = vec![];
+        for key in list_option(params, "$orderby") {
+            let mut parts = key.split_whitespace();
+            let field = field_path(parts.next().unwrap_or_default());
+            let descending = match parts.next().map(str::to_ascii_lowercase).as_deref() {
+                None | Some("asc") => false,
+                Some("desc") => true,
+                Some(_) => return Err(invalid("$orderby", &key)),
+            };
+            if parts.next().is_some() {
+                return Err(invalid("$orderby", &key));
+            }
+            order_by.push(OrderBy { field, descending });
+        }
+
+        let count = match params.get("$count").map(|value| value.trim()) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(value) => return Err(invalid("$count", value)),
+        };
+
+        Ok(Self {
+            filter,
+            order_by,
+            skip: number("$skip")?.unwrap_or(0),
+            top: number("$top")?,
+            select: params
+                .contains_key("$select")
+                .then(|| list_option(params, "$select")),
+            count,
+        })
+    }
+
+    /// Sorts, pages, and projects filtered items.
+    pub fn page(&self, mut items: Vec<Value>) -> Vec<Value> {
+        if !self.order_by.is_empty() {
+            items.sort_by(|left, right| {
+                self.order_by
+                    .iter()
+                    .map(|key| {
+                        let ordering = compare_field(left, right, &key.field);
+                        if key.descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let items = items
+            .into_iter()
+            .skip(self.skip)
+            .take(self.top.unwrap_or(usize::MAX));
+        match &self.select {
+            Some(fields) => items.map(|item| select_fields(item, fields)).collect(),
+            None => items.collect(),
+        }
+    }
+}
+
+/// Splits a comma-separated option into trimmed, non-empty entries.
+fn list_option(params: &HashMap<String, String>, option: &str) -> Vec<String> {
+    params
+        .get(option)
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Converts an OData property path such as `address/city` into a dot path.
+fn field_path(path: &str) -> String {
+    path.replace('/', ".")
+}
+
+/// Orders two items by a field, with missing and `null` values first.
+fn compare_field(left: &Value, right: &Value, field: &str) -> Ordering {
+    let value = |item| field_value(item, field).filter(|value| !value.is_null());
+    match (value(left), value(right)) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(left), Some(right)) => order(left, right).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Keeps the selected top-level fields of an item.
+fn select_fields(item: Value, fields: &[String]) -> Value {
+    let Value::Object(mut item) = item else {
+        return item;
+    };
+    let selected: Map<String, Value> = fields
+        .iter()
+        .filter_map(|field| item.remove(field).map(|value| (field.clone(), value)))
+        .collect();
+    Value::Object(selected)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Literal(Value),
+    Symbol(char),
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Text(text) => write!(f, "'{}'", text.replace('\'', "''")),
+            Token::Literal(value) => write!(f, "{}", value),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, CriteriaError> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        let character = chars[index];
+        if character.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        // Strings are single-quoted, with `''` for a quote inside.
+        if character == '\'' {
+            let mut value = String::new();
+            index += 1;
+            loop {
+                match chars.get(index) {
+                    None => return Err(CriteriaError::UnterminatedString),
+                    Some('\'') if chars.get(index + 1) == Some(&'\'') => {
+                        value.push('\'');
+                        index += 2;
+                    }
+                    Some('\'') => {
+                        index += 1;
+                        break;
+                    }
+                    Some(&other) => {
+                        value.push(other);
+                        index += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Text(value));
+            continue;
+        }
+
+        // Numbers, and unquoted date and time literals such as `2024-01-31T10:00:00Z`.
+        if character.is_ascii_digit()
+            || (character == '-' && chars.get(index + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = index;
+            index += 1;
+            while index < chars.len()
+                && (chars[index].is_ascii_alphanumeric()
+                    || matches!(chars[index], '.' | ':' | '-' | '+'))
+            {
+                index += 1;
+            }
+            let literal = chars[start..index].iter().collect::<String>();
+            let value = literal
+                .parse::<i64>()
+                .map(Number::from)
+                .ok()
+                .or_else(|| literal.parse::<f64>().ok().and_then(Number::from_f64))
+                .map_or(Value::String(literal), Value::Number);
+            tokens.push(Token::Literal(value));
+            continue;
+        }
+
+        if character.is_alphabetic() || character == '_' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_alphanumeric() || matches!(chars[index], '_' | '/'))
+            {
+                index += 1;
+            }
+            tokens.push(Token::Word(chars[start..index].iter().collect()));
+            continue;
+        }
+
+        if matches!(character, '(' | ')' | ',') {
+            tokens.push(Token::Symbol(character));
+            index += 1;
+            continue;
+        }
+
+        return Err(CriteriaError::InvalidCharacter { character });
+    }
+
+    Ok(tokens)
+}
+
+/// Translates an OData `$filter` expression into [`Criteria`].
+///
+/// Supports `eq`, `ne`, `gt`, `ge`, `lt`, `le`, `in`, `and`, `or`, `not`,
+/// parentheses, and the `contains`, `startswith`, and `endswith` functions.
+pub fn parse_filter(text: &str) -> Result<Criteria, CriteriaError> {
+    let mut parser = FilterParser {
+        tokens: tokenize(text)?,
+        position: 0,
+    };
+    let criteria = parser.or_expression()?;
+    match parser.tokens.get(parser.position) {
+        Some(token) => Err(CriteriaError::UnexpectedToken {
+            token: token.to_string(),
+            expected: "the end of the filter".to_string(),
+        }),
+        None => Ok(criteria),
+    }
+}
+
+struct FilterParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, CriteriaError> {
+        let token = self.tokens.get(self.position).cloned().ok_or_else(|| {
+            CriteriaError::UnexpectedEnd {
+                expected: expected.to_string(),
+            }
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().is_some_and(|token| token.is_keyword(keyword)) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), CriteriaError> {
+        let expected = format!("'{}'", symbol);
+        match self.next(&expected)? {
+            Token::Symbol(found) if found == symbol => Ok(()),
+            token => Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected,
+            }),
+        }
+    }
+
+    fn or_expression(&mut self) -> Result<Criteria, CriteriaError> {
+        let mut criteria = vec![self.and_expression()?];
+        while self.eat_keyword("or") {
+            criteria.push(self.and_expression()?);
+        }
+        Ok(match criteria.len() {
+            1 => criteria.remove(0),
+            _ => Criteria::Or(criteria),
+        })
+    }
+
+    fn and_expression(&mut self) -> Result<Criteria, CriteriaError> {
+        let mut criteria = vec![self.primary()?];
+        while self.eat_keyword("and") {
+            criteria.push(self.primary()?);
+        }
+        Ok(match criteria.len() {
+            1 => criteria.remove(0),
+            _ => Criteria::And(criteria),
+        })
+    }
+
+    fn primary(&mut self) -> Result<Criteria, CriteriaError> {
+        if self.eat_keyword("not") {
+            return Ok(Criteria::Not(Box::new(self.primary()?)));
+        }
+        if self.peek() == Some(&Token::Symbol('(')) {
+            self.position += 1;
+            let criteria = self.or_expression()?;
+            self.expect(')')?;
+            return Ok(criteria);
+        }
+
+        let field = self.field()?;
+        if self.peek() == Some(&Token::Symbol('(')) {
+            return self.function(&field);
+        }
+        self.comparison(field_path(&field))
+    }
+
+    fn field(&mut self) -> Result<String, CriteriaError> {
+        match self.next("a property name")? {
+            Token::Word(word) => Ok(word),
+            token => Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected: "a property name".to_string(),
+            }),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, CriteriaError> {
+        match self.next("a value")? {
+            Token::Text(text) => Ok(Value::String(text)),
+            Token::Literal(value) => Ok(value),
+            token if token.is_keyword("true") => Ok(Value::Bool(true)),
+            token if token.is_keyword("false") => Ok(Value::Bool(false)),
+            token if token.is_keyword("null") => Ok(Value::Null),
+            token => Err(CriteriaError::UnexpectedToken {
+                token: token.to_string(),
+                expected: "a value".to_string(),
+            }),
+        }
+    }
+
+    fn comparison(&mut self, field: String) -> Result<Criteria, CriteriaError> {
+        let expected = "a comparison operator";
+        let token = self.next(expected)?;
+        let comparer = match &token {
+            Token::Word(word) => match word.to_ascii_lowercase().as_str() {
+                "eq" => Some(Comparer::Equal),
+                "ne" => Some(Comparer::NotEqual),
+                "gt" => Some(Comparer::Greater),
+                "ge" => Some(Comparer::GreaterOrEqual),
+                "lt" => Some(Comparer::Less),
+                "le" => Some(Comparer::LessOrEqual),
+                "in" => Some(Comparer::In),
+                _ => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| CriteriaError::UnexpectedToken {
+            token: token.to_string(),
+            expected: expected.to_string(),
+        })?;
+
+        let values = if comparer == Comparer::In {
+            self.expect('(')?;
+            let mut values = vec![self.value()?];
+            while self.peek() == Some(&Token::Symbol(',')) {
+                self.position += 1;
+                values.push(self.value()?);
+            }
+            self.expect(')')?;
+            values
+        } else {
+            vec![self.value()?]
+        };
+
+        Ok(Criteria::Constraint(Constraint {
+            field,
+            comparer,
+            values,
+        }))
+    }
+
+    /// Translates `contains`, `startswith`, and `endswith` into `LIKE` patterns.
+    fn function(&mut self, name: &str) -> Result<Criteria, CriteriaError> {
+        let pattern: fn(&str) -> String = match name.to_ascii_lowercase().as_str() {
+            "contains" => |text| format!("%{}%", text),
+            "startswith" => |text| format!("{}%", text),
+            "endswith" => |text| format!("%{}", text),
+            _ => {
+                return Err(CriteriaError::UnexpectedToken {
+                    token: name.to_string(),
+                    expected: "contains, startswith, or endswith".to_string(),
+                });
+            }
+        };
+
+        self.expect('(')?;
+        let field = field_path(&self.field()?);
+        self.expect(',')?;
+        let text = match self.value()? {
+            Value::String(text) => text,
+            value => {
+                return Err(CriteriaError::UnexpectedToken {
+                    token: value.to_string(),
+                    expected: "a string".to_string(),
+                });
+            }
+        };
+        self.expect(')')?;
+
+        Ok(Criteria::Constraint(Constraint {
+            field,
+            comparer: Comparer::Like,
+            values: vec![Value::String(pattern(&text))],
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn matches(filter: &str, item: Value) -> bool {
+        parse_filter(filter).unwrap().matches(&item)
+    }
+
+    #[test]
+    fn filters_translate_comparisons_and_logic() {
+        let item = json!({ "name": "O'Brien", "price": 25, "address": { "city": "Paris" } });
+
+        assert!(matches("price gt 20 and price le 25", item.clone()));
+        assert!(matches("name eq 'O''Brien'", item.clone()));
+        assert!(matches("address/city in ('Lyon', 'Paris')", item.clone()));
+        assert!(matches("not (price lt 10) or name eq 'x'", item.clone()));
+        assert!(matches("discount eq null", item.clone()));
+        assert!(!matches("price ne 25", item));
+    }
+
+    #[test]
+    fn filters_translate_string_functions_and_dates() {
+        let item = json!({ "name": "Wireless Mouse", "createdAt": "2024-03-01T10:00:00Z" });
+
+        assert!(matches("contains(name, 'less')", item.clone()));
+        assert!(matches(
+            "startswith(name,'Wire') and endswith(name,'Mouse')",
+            item.clone()
+        ));
+        assert!(matches("createdAt gt 2024-01-01T00:00:00Z", item.clone()));
+        assert!(!matches("contains(name, 'Keyboard')", item));
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        assert!(parse_filter("price >= 20").is_err());
+        assert!(parse_filter("price gt").is_err());
+        assert!(parse_filter("substringof('a', name)").is_err());
+        assert!(parse_filter("name eq 'open").is_err());
+    }
+
+    #[test]
+    fn options_sort_page_and_select_items() {
+        let query = ODataQuery::from_params(&params(&[
+            ("$orderby", "group desc, price"),
+            ("$skip", "1"),
+            ("$top", "2"),
+            ("$select", "id,price"),
+            ("$count", "true"),
+        ]))
+        .unwrap();
+        assert!(query.count);
+
+        let items = vec![
+            json!({ "id": 1, "group": "a", "price": 3 }),
+            json!({ "id": 2, "group": "b", "price": 2 }),
+            json!({ "id": 3, "group": "b", "price": 1 }),
+            json!({ "id": 4, "group": null, "price": 0 }),
+        ];
+        assert_eq!(
+            query.page(items),
+            [
+                json!({ "id": 2, "price": 2 }),
+                json!({ "id": 1, "price": 3 })
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        for (option, value) in [
+            ("$top", "-1"),
+            ("$skip", "many"),
+            ("$orderby", "price upward"),
+            ("$count", "yes"),
+        ] {
+            assert_eq!(
+                ODataQuery::from_params(&params(&[(option, value)])),
+                Err(ODataError::InvalidOption {
+                    option: option.to_string(),
+                    value: value.to_string(),
+                })
+            );
+        }
+    }
+}
@@ -1,11 +1,20 @@
-use crate::link::Link;
+use serde_json::Value;
+
+use crate::{link::Link, route_inventory::RouteEntry};
 
 /// Renderer for the embedded mock-server home page.
 pub struct Pages {
     links: Vec<Link>,
+    entries: Vec<RouteEntry>,
     index_template: &'static str,
     scripts_template: &'static str,
     styles_template: &'static str,
+    collections_template: &'static str,
+    collections_scripts_template: &'static str,
+    requests_template: &'static str,
+    requests_scripts_template: &'static str,
+    playground_template: &'static str,
+    playground_scripts_template: &'static str,
 }
 
 impl Default for Pages {
@@ -14,11 +23,24 @@ impl Default for Pages {
         let index_template = include_str!("home/index.html");
         let scripts_template = include_str!("home/scripts.js");
         let styles_template = include_str!("home/styles.css");
+        let collections_template = include_str!("home/collections.html");
+        let collections_scripts_template = include_str!("home/collections.js");
+        let requests_template = include_str!("home/requests.html");
+        let requests_scripts_template = include_str!("home/requests.js");
+        let playground_template = include_str!("home/playground.html");
+        let playground_scripts_template = include_str!("home/playground.js");
         Pages {
             links,
+            entries: vec![],
             index_template,
             scripts_template,
             styles_template,
+            collections_template,
+            collections_scripts_template,
+            requests_template,
+            requests_scripts_template,
+            playground_template,
+            playground_scripts_template,
         }
     }
 }
@@ -30,14 +52,32 @@ impl Pages {
     }
 
     /// Adds a route entry to the home page.
-    pub fn push_link(&mut self, method: String, route: String, options: &[String]) {
-        self.links.push(Link::new(method, route, options));
+    pub fn push_link(
+        &mut self,
+        method: String,
+        route: String,
+        options: &[String],
+        is_protected: bool,
+    ) {
+        let mut link = Link::new(method, route, options);
+        link.protected = is_protected;
+        self.links.push(link);
+    }
+
+    /// Adds the routes of mock files, which group the home page by folder and
+    /// show their protection and delay.
+    pub fn push_route_entries(&mut self, entries: &[RouteEntry]) {
+        self.entries.extend_from_slice(entries);
     }
 
     /// Renders the full home page HTML with route data and assets inlined.
     pub fn render_index(&self) -> String {
-        let json = serde_json::to_string(&self.links);
-        let mock_routes = format!("let mock_routes = {};", json.unwrap());
+        let mock_routes = format!(
+            "let mock_routes = {};\n    let mock_entries = {};",
+            serde_json::to_string(&self.links).unwrap(),
+            serde_json::to_string(&self.entries).unwrap()
+        )
+        .replace("</", "<\\/");
 
         let scripts = format!(
             r#"<script type="text/javascript">
@@ -47,17 +87,87 @@ impl Pages {
             mock_routes, self.scripts_template
         );
 
-        let styles = format!(
+        self.index_template
+            .replace(r#"<script src="/mock-routes.js"></script>"#, "")
+            .replace(r#"<script src="/scripts.js"></script>"#, &scripts)
+            .replace(
+                r#"<link rel="stylesheet" href="/styles.css" />"#,
+                &self.styles(),
+            )
+    }
+
+    /// Renders the collections dashboard HTML with the collections, their
+    /// routes, and their schemas inlined.
+    pub fn render_collections(&self, collections: &Value) -> String {
+        // A `</script>` inside a value would end the inline script early.
+        let mock_collections = format!(
+            "let mock_collections = {};",
+            collections.to_string().replace("</", "<\\/")
+        );
+
+        let scripts = format!(
+            r#"<script type="text/javascript">
+    {}
+    {}
+        </script>"#,
+            mock_collections, self.collections_scripts_template
+        );
+
+        self.collections_template
+            .replace(r#"<script src="/collections.js"></script>"#, &scripts)
+            .replace(
+                r#"<link rel="stylesheet" href="/styles.css" />"#,
+                &self.styles(),
+            )
+    }
+
+    /// Renders the route playground HTML with the routes and the collections,
+    /// whose schemas pre-fill request bodies, inlined.
+    pub fn render_playground(&self, collections: &Value) -> String {
+        let json = serde_json::to_string(&self.links);
+        let scripts = format!(
+            r#"<script type="text/javascript">
+    let mock_routes = {};
+    let mock_collections = {};
+    {}
+        </script>"#,
+            json.unwrap().replace("</", "<\\/"),
+            collections.to_string().replace("</", "<\\/"),
+            self.playground_scripts_template
+        );
+
+        self.playground_template
+            .replace(r#"<script src="/playground.js"></script>"#, &scripts)
+            .replace(
+                r#"<link rel="stylesheet" href="/styles.css" />"#,
+                &self.styles(),
+            )
+    }
+
+    /// Renders the live tail page of the request journal with assets inlined.
+    pub fn render_live_requests(&self) -> String {
+        let scripts = format!(
+            r#"<script type="text/javascript">
+    {}
+        </script>"#,
+            self.requests_scripts_template
+        );
+
+        self.requests_template
+            .replace(r#"<script src="/requests.js"></script>"#, &scripts)
+            .replace(
+                r#"<link rel="stylesheet" href="/styles.css" />"#,
+                &self.styles(),
+            )
+    }
+
+    fn styles(&self) -> String {
+        format!(
             r#"<style>
             {}
         </style>"#,
             self.styles_template
-        );
-
-        self.index_template
-            .replace(r#"<script src="/mock-routes.js"></script>"#, "")
-            .replace(r#"<script src="/scripts.js"></script>"#, &scripts)
-            .replace(r#"<link rel="stylesheet" href="/styles.css" />"#, &styles)
+        )
     }
 }
 
@@ -72,15 +182,48 @@ mod tests {
             "POST".to_string(),
             "/api/users".to_string(),
             &["upload".to_string()],
+            false,
         );
 
         let html = pages.render_index();
 
         assert!(html.contains("let mock_routes ="));
+        assert!(html.contains("let mock_entries = [];"));
         assert!(html.contains("/api/users"));
         assert!(html.contains("POST"));
         assert!(html.contains("<script type=\"text/javascript\">"));
         assert!(html.contains("<style>"));
         assert!(!html.contains(r#"<script src="/mock-routes.js"></script>"#));
     }
+
+    #[test]
+    fn render_collections_inlines_assets_and_collections() {
+        let pages = Pages::new();
+
+        let html = pages.render_collections(&serde_json::json!([{
+            "name": "users",
+            "route": "/api/users",
+        }]));
+
+        assert!(
+            html.contains(r#"let mock_collections = [{"name":"users","route":"/api/users"}];"#)
+        );
+        assert!(html.contains("<style>"));
+        assert!(!html.contains(r#"<script src="/collections.js"></script>"#));
+    }
+
+    #[test]
+    fn render_playground_inlines_routes_with_their_protection() {
+        let mut pages = Pages::new();
+        pages.push_link("GET".to_string(), "/api/me".to_string(), &[], true);
+        pages.push_link("GET".to_string(), "/api/open".to_string(), &[], false);
+
+        let html = pages.render_playground(&serde_json::json!([]));
+
+        assert!(html.contains(
+            r#"let mock_routes = [{"method":"GET","route":"/api/me","options":[],"protected":true},{"method":"GET","route":"/api/open","options":[]}];"#
+        ));
+        assert!(html.contains("let mock_collections = [];"));
+        assert!(!html.contains(r#"<script src="/playground.js"></script>"#));
+    }
 }
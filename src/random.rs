@@ -0,0 +1,269 @@
+//! Random sources for generated data.
+//!
+//! UUIDs, `{{faker.*}}` placeholders, and JGD templates draw from the
+//! [`RandomSource`] of the server handling them. Each `App` owns one, which
+//! uses the thread RNG until a seed is set with `--seed` or `[server].seed`,
+//! after which every run over the same mocks produces the same data. The seed
+//! is re-applied whenever the router is rebuilt, so hot reloads start from the
+//! same sequence.
+//!
+//! Requests, route building, and background tasks run inside a scope that
+//! makes the source of their server the [`current`] one, so servers running
+//! side by side in one process never share a sequence.
+
+use std::{
+    fs,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use fosk::{AddBatchError, AddError, DbCollection, IdType};
+use jgd_rs::{JgdGeneratorError, generate_jgd_from_file, generate_jgd_from_str};
+use once_cell::sync::Lazy;
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+use serde_json::Value;
+use uuid::{Builder, Uuid};
+
+tokio::task_local! {
+    static CURRENT: Arc<RandomSource>;
+}
+
+/// Source used outside of any server scope. It is never seeded.
+static UNSEEDED: Lazy<Arc<RandomSource>> = Lazy::new(Arc::default);
+
+/// Returns the random source of the server running the current task, or an
+/// unseeded source outside of a server.
+pub fn current() -> Arc<RandomSource> {
+    CURRENT
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| Arc::clone(&UNSEEDED))
+}
+
+/// Runs `future` with `source` as the [`current`] random source.
+pub async fn scope<F: Future>(source: Arc<RandomSource>, future: F) -> F::Output {
+    CURRENT.scope(source, future).await
+}
+
+/// Runs `f` with `source` as the [`current`] random source.
+pub fn sync_scope<T>(source: Arc<RandomSource>, f: impl FnOnce() -> T) -> T {
+    CURRENT.sync_scope(source, f)
+}
+
+type RandomMiddlewareReturn = Pin<Box<dyn Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware running each request with `source` as the [`current`]
+/// random source.
+pub fn make_random_middleware(
+    source: &Arc<RandomSource>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> RandomMiddlewareReturn {
+    let source = Arc::clone(source);
+    move |req: Request, next: Next| Box::pin(scope(Arc::clone(&source), next.run(req)))
+}
+
+/// Random generator that is either seeded or backed by the thread RNG.
+#[derive(Debug)]
+pub struct RandomSource {
+    seeded: Mutex<Option<StdRng>>,
+}
+
+impl Default for RandomSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RandomSource {
+    /// Creates an unseeded source.
+    pub const fn new() -> Self {
+        Self {
+            seeded: Mutex::new(None),
+        }
+    }
+
+    /// Seeds the source, or returns it to the thread RNG with `None`.
+    pub fn set_seed(&self, seed: Option<u64>) {
+        *self.seeded.lock().unwrap_or_else(|err| err.into_inner()) =
+            seed.map(StdRng::seed_from_u64);
+    }
+
+    /// Returns true when values come from a seeded generator.
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .is_some()
+    }
+
+    /// Runs `f` with the seeded generator, or with the thread RNG when unseeded.
+    pub fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        let mut seeded = self.seeded.lock().unwrap_or_else(|err| err.into_inner());
+        match seeded.as_mut() {
+            Some(rng) => f(rng),
+            None => f(&mut rand::rng()),
+        }
+    }
+
+    /// Returns a version 4 UUID.
+    pub fn uuid_v4(&self) -> Uuid {
+        Builder::from_random_bytes(self.with_rng(|rng| rng.random())).into_uuid()
+    }
+
+    /// Generates data from a JGD file.
+    ///
+    /// While seeded, templates without their own `seed` receive one drawn from
+    /// this source. JGD `${uuid.v4}` values are not covered by the seed.
+    pub fn generate_jgd(&self, path: &Path) -> Result<Value, JgdGeneratorError> {
+        if self.is_seeded()
+            && let Some(mut template) = fs::read_to_string(path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            && let Some(fields) = template.as_object_mut()
+        {
+            if !fields.contains_key("seed") {
+                fields.insert(
+                    "seed".to_string(),
+                    self.with_rng(|rng| rng.next_u64()).into(),
+                );
+            }
+            return generate_jgd_from_str(&template.to_string());
+        }
+
+        generate_jgd_from_file(&path.to_path_buf())
+    }
+
+    /// Fills missing UUID ids in a list of items before it is loaded into a collection.
+    ///
+    /// Fosk draws UUID ids from its own generator, so seeded runs assign them
+    /// here. Other values are returned unchanged.
+    pub fn fill_ids(&self, collection: &DbCollection, items: Value) -> Value {
+        let Some(id_key) = self.seeded_uuid_key(collection) else {
+            return items;
+        };
+
+        match items {
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|mut item| {
+                        if let Some(fields) = item.as_object_mut()
+                            && !matches!(fields.get(&id_key), Some(Value::String(_)))
+                        {
+                            fields.insert(id_key.clone(), self.uuid_v4().to_string().into());
+                        }
+                        item
+                    })
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Adds an item to a collection, drawing its UUID id from this source while seeded.
+    pub fn add_item(&self, collection: &DbCollection, item: Value) -> Result<Value, AddError> {
+        let Some(id_key) = self.seeded_uuid_key(collection) else {
+            return collection.add(item);
+        };
+        let Value::Object(mut fields) = item else {
+            return Err(AddError::NonObjectItem);
+        };
+
+        fields.insert(id_key, self.uuid_v4().to_string().into());
+        // A batch keeps the provided UUID; a single object with a string id
+        // can only fail on the collection lock.
+        collection
+            .add_batch(Value::Array(vec![Value::Object(fields)]))
+            .map(|mut items| items.remove(0))
+            .map_err(|err| match err {
+                AddBatchError::NonObjectItem { .. } => AddError::NonObjectItem,
+                _ => AddError::LockPoisoned,
+            })
+    }
+
+    fn seeded_uuid_key(&self, collection: &DbCollection) -> Option<String> {
+        if !self.is_seeded() {
+            return None;
+        }
+        collection
+            .get_config()
+            .ok()
+            .filter(|config| config.id_type == IdType::Uuid)
+            .map(|config| config.id_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fosk::{Db, DbConfig};
+    use serde_json::json;
+
+    fn uuids(source: &RandomSource) -> Vec<Uuid> {
+        (0..3).map(|_| source.uuid_v4()).collect()
+    }
+
+    #[test]
+    fn seeded_source_repeats_its_sequence() {
+        let source = RandomSource::new();
+        assert!(!source.is_seeded());
+        assert_ne!(uuids(&source), uuids(&source));
+
+        source.set_seed(Some(42));
+        let first = uuids(&source);
+        source.set_seed(Some(42));
+        assert_eq!(uuids(&source), first);
+        assert!(first.iter().all(|uuid| uuid.get_version_num() == 4));
+
+        source.set_seed(Some(43));
+        assert_ne!(uuids(&source), first);
+    }
+
+    #[test]
+    fn seeded_jgd_generation_is_reproducible() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("users.jgd");
+        std::fs::write(
+            &path,
+            json!({
+                "$format": "jgd/v1",
+                "version": "1.0",
+                "root": {
+                    "count": 5,
+                    "fields": { "name": "${name.firstName}", "age": { "number": { "min": 1, "max": 99, "integer": true } } }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let source = RandomSource::new();
+        source.set_seed(Some(7));
+        let first = source.generate_jgd(&path).unwrap();
+        source.set_seed(Some(7));
+        assert_eq!(source.generate_jgd(&path).unwrap(), first);
+        assert_eq!(first.as_array().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn seeded_ids_are_assigned_before_fosk() {
+        let db = Db::new();
+        let users = db.create_with_config("users", DbConfig::from(IdType::Uuid, "id"));
+        let source = RandomSource::new();
+
+        let unseeded = source.fill_ids(&users, json!([{ "name": "Ada" }]));
+        assert_eq!(unseeded, json!([{ "name": "Ada" }]));
+
+        source.set_seed(Some(1));
+        let filled = source.fill_ids(&users, json!([{ "name": "Ada" }, { "id": "kept" }]));
+        let added = source.add_item(&users, json!({ "name": "Grace" })).unwrap();
+
+        source.set_seed(Some(1));
+        let expected = source.uuid_v4().to_string();
+        assert_eq!(filled[0]["id"], expected);
+        assert_eq!(filled[1]["id"], "kept");
+        assert_eq!(added["id"], source.uuid_v4().to_string());
+        assert!(users.get(added["id"].as_str().unwrap()).unwrap().is_some());
+    }
+}
@@ -0,0 +1,765 @@
+//! Validation of outgoing mock responses against JSON Schema contracts.
+//!
+//! Contracts come from an OpenAPI document configured with `[server] openapi`
+//! and from per-route JSON Schema files configured with `[route] response_schema`.
+//! Only the JSON Schema keywords commonly used in API contracts are checked;
+//! unknown keywords are ignored.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{Method, StatusCode, header::CONTENT_TYPE},
+    middleware::Next,
+    response::Response,
+};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    handlers::{error_response, is_dropped_connection},
+    route_builder::config::Config,
+};
+
+const MAX_REF_DEPTH: usize = 64;
+const HTTP_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How response schema violations are reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Responses are not validated.
+    Off,
+    /// Violations are logged and the response is sent unchanged.
+    #[default]
+    Warn,
+    /// Violations replace the response with a `500` error.
+    Fail,
+}
+
+impl ValidationMode {
+    /// Parses a `response_validation` config value, defaulting to [`ValidationMode::Warn`].
+    pub fn from_config(value: Option<&str>) -> Self {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            Some("off") => ValidationMode::Off,
+            Some("fail") => ValidationMode::Fail,
+            _ => ValidationMode::Warn,
+        }
+    }
+}
+
+/// Expected response shape for one method and route template.
+#[derive(Debug, Clone)]
+struct ResponseContract {
+    method: Method,
+    route: String,
+    /// Status key as written in OpenAPI (`200`, `2XX`, `default`), or `None`
+    /// for per-route schemas that apply to every successful response.
+    status: Option<String>,
+    schema: Value,
+    /// Document used to resolve `$ref` pointers.
+    root: Arc<Value>,
+}
+
+impl ResponseContract {
+    fn matches_route(&self, method: &Method, path: &str) -> bool {
+        self.method == method && route_matches(&self.route, path)
+    }
+
+    fn matches_status(&self, status: StatusCode) -> bool {
+        match &self.status {
+            None => status.is_success(),
+            Some(key) if key == "default" => true,
+            Some(key) if key.len() == 3 && key.ends_with("XX") => {
+                key.starts_with(&status.as_u16().to_string()[..1])
+            }
+            Some(key) => key == status.as_str(),
+        }
+    }
+
+    /// Ranks how specifically this contract's status key matches a response.
+    fn status_rank(&self) -> u8 {
+        match self.status.as_deref() {
+            Some("default") => 0,
+            Some(key) if key.ends_with("XX") => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// Matches a request path against a route template where `{param}` matches
+/// one segment and `{*param}` matches the remaining segments.
+pub(crate) fn route_matches(template: &str, path: &str) -> bool {
+    let mut path_segments = path.trim_end_matches('/').split('/');
+
+    for template_segment in template.trim_end_matches('/').split('/') {
+        if template_segment.starts_with("{*") {
+            return path_segments
+                .next()
+                .is_some_and(|segment| !segment.is_empty());
+        }
+
+        match path_segments.next() {
+            Some(segment)
+                if template_segment.starts_with('{') && template_segment.ends_with('}') =>
+            {
+                if segment.is_empty() {
+                    return false;
+                }
+            }
+            Some(segment) if segment == template_segment => {}
+            _ => return false,
+        }
+    }
+
+    path_segments.next().is_none()
+}
+
+/// Registry of response contracts and the configured violation mode.
+#[derive(Debug, Default)]
+pub struct ResponseValidator {
+    mode: ValidationMode,
+    contracts: RwLock<Vec<ResponseContract>>,
+}
+
+impl ResponseValidator {
+    /// Creates an empty validator using the given violation mode.
+    pub fn new(mode: ValidationMode) -> Self {
+        Self {
+            mode,
+            contracts: RwLock::new(vec![]),
+        }
+    }
+
+    /// Returns the configured violation mode.
+    pub fn mode(&self) -> ValidationMode {
+        self.mode
+    }
+
+    /// Returns true when validation is enabled and at least one contract is registered.
+    pub fn is_active(&self) -> bool {
+        self.mode != ValidationMode::Off && !self.contracts.read().unwrap().is_empty()
+    }
+
+    /// Removes every registered contract.
+    pub fn clear(&self) {
+        self.contracts.write().unwrap().clear();
+    }
+
+    /// Registers a JSON Schema that every successful response of `method route` must match.
+    pub fn add_route_schema(&self, method: &str, route: &str, schema: Value) {
+        let root = Arc::new(schema.clone());
+        self.contracts.write().unwrap().push(ResponseContract {
+            method: Method::from_bytes(method.as_bytes()).unwrap_or(Method::GET),
+            route: route.to_string(),
+            status: None,
+            schema,
+            root,
+        });
+    }
+
+    /// Registers every JSON response schema declared in an OpenAPI document.
+    ///
+    /// Returns the number of registered contracts.
+    pub fn add_openapi(&self, document: Value) -> usize {
+        let root = Arc::new(document);
+        let base_path = openapi_base_path(&root);
+        let mut contracts = vec![];
+
+        let Some(paths) = root.get("paths").and_then(Value::as_object) else {
+            return 0;
+        };
+
+        for (path, item) in paths {
+            let route = format!("{}{}", base_path, path);
+            for method in HTTP_METHODS {
+                let Some(responses) = item
+                    .get(method)
+                    .and_then(|operation| operation.get("responses"))
+                    .and_then(Value::as_object)
+                else {
+                    continue;
+                };
+
+                for (status, response) in responses {
+                    let response = resolve_ref(response, &root).unwrap_or(response);
+                    if let Some(schema) = json_media_schema(response) {
+                        contracts.push(ResponseContract {
+                            method: Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                                .unwrap(),
+                            route: route.clone(),
+                            status: Some(status.to_ascii_uppercase().replace("DEFAULT", "default")),
+                            schema: schema.clone(),
+                            root: Arc::clone(&root),
+                        });
+                    }
+                }
+            }
+        }
+
+        let count = contracts.len();
+        self.contracts.write().unwrap().extend(contracts);
+        count
+    }
+
+    /// Finds the most specific contract for a response.
+    ///
+    /// Per-route schemas win over OpenAPI operations, and exact status codes
+    /// win over ranges and `default`.
+    fn find(&self, method: &Method, path: &str, status: StatusCode) -> Option<ResponseContract> {
+        let contracts = self.contracts.read().unwrap();
+        let mut candidates = contracts
+            .iter()
+            .filter(|contract| {
+                contract.matches_route(method, path) && contract.matches_status(status)
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(per_route) = candidates.iter().find(|contract| contract.status.is_none()) {
+            return Some((*per_route).clone());
+        }
+
+        candidates.sort_by_key(|contract| std::cmp::Reverse(contract.status_rank()));
+        candidates.first().map(|contract| (*contract).clone())
+    }
+}
+
+/// Returns the path portion of the first OpenAPI `servers` URL, if any.
+fn openapi_base_path(document: &Value) -> String {
+    let url = document
+        .pointer("/servers/0/url")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest
+            .find('/')
+            .map(|index| &rest[index..])
+            .unwrap_or_default(),
+        None => url,
+    };
+
+    path.trim_end_matches('/').to_string()
+}
+
+fn json_media_schema(response: &Value) -> Option<&Value> {
+    let content = response.get("content")?.as_object()?;
+    content
+        .get("application/json")
+        .or_else(|| {
+            content
+                .iter()
+                .find(|(media_type, _)| media_type.contains("json"))
+                .map(|(_, media)| media)
+        })?
+        .get("schema")
+}
+
+fn resolve_ref<'a>(schema: &'a Value, root: &'a Value) -> Option<&'a Value> {
+    let reference = schema.get("$ref")?.as_str()?;
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match (expected, value) {
+        ("number", Value::Number(_)) => true,
+        ("integer", Value::Number(number)) => {
+            number.is_i64() || number.is_u64() || number.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        (expected, value) => expected == type_name(value),
+    }
+}
+
+fn validate_at(
+    schema: &Value,
+    value: &Value,
+    root: &Value,
+    path: &str,
+    depth: usize,
+    errors: &mut Vec<String>,
+) {
+    if depth > MAX_REF_DEPTH {
+        errors.push(format!("{}: schema nesting is too deep", path));
+        return;
+    }
+
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{}: no value is allowed here", path));
+            return;
+        }
+        Value::Object(_) => schema,
+        _ => return,
+    };
+
+    if schema.get("$ref").is_some() {
+        match resolve_ref(schema, root) {
+            Some(resolved) => validate_at(resolved, value, root, path, depth + 1, errors),
+            None => errors.push(format!("{}: unresolvable $ref {}", path, schema["$ref"])),
+        }
+        return;
+    }
+
+    if value.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+        return;
+    }
+
+    if let Some(all_of) = schema.get("allOf").and_then(Value::as_array) {
+        for sub_schema in all_of {
+            validate_at(sub_schema, value, root, path, depth + 1, errors);
+        }
+    }
+
+    for (keyword, exactly_one) in [("anyOf", false), ("oneOf", true)] {
+        if let Some(options) = schema.get(keyword).and_then(Value::as_array) {
+            let matching = options
+                .iter()
+                .filter(|option| {
+                    let mut option_errors = vec![];
+                    validate_at(option, value, root, path, depth + 1, &mut option_errors);
+                    option_errors.is_empty()
+                })
+                .count();
+            if matching == 0 || (exactly_one && matching > 1) {
+                errors.push(format!(
+                    "{}: expected exactly {} of the {} schemas to match, {} matched",
+                    path,
+                    if exactly_one { "one" } else { "at least one" },
+                    keyword,
+                    matching
+                ));
+            }
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let types = match expected {
+            Value::String(expected) => vec![expected.as_str()],
+            Value::Array(expected) => expected.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|expected| matches_type(expected, value)) {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                path,
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        errors.push(format!(
+            "{}: {} is not one of {}",
+            path,
+            value,
+            Value::Array(allowed.clone())
+        ));
+    }
+
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        errors.push(format!("{}: expected {}, found {}", path, expected, value));
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for field in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(field) {
+                        errors.push(format!("{}: missing required property '{}'", path, field));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, property) in object {
+                let property_path = format!("{}.{}", path, key);
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property_schema) => validate_at(
+                        property_schema,
+                        property,
+                        root,
+                        &property_path,
+                        depth + 1,
+                        errors,
+                    ),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{}: property is not allowed", property_path))
+                        }
+                        Some(additional) => validate_at(
+                            additional,
+                            property,
+                            root,
+                            &property_path,
+                            depth + 1,
+                            errors,
+                        ),
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+                && (items.len() as u64) < min
+            {
+                errors.push(format!(
+                    "{}: expected at least {} items, found {}",
+                    path,
+                    min,
+                    items.len()
+                ));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+                && (items.len() as u64) > max
+            {
+                errors.push(format!(
+                    "{}: expected at most {} items, found {}",
+                    path,
+                    max,
+                    items.len()
+                ));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    let item_path = format!("{}[{}]", path, index);
+                    validate_at(item_schema, item, root, &item_path, depth + 1, errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                && length < min
+            {
+                errors.push(format!("{}: expected at least {} characters", path, min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                && length > max
+            {
+                errors.push(format!("{}: expected at most {} characters", path, max));
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str)
+                && let Ok(regex) = Regex::new(pattern)
+                && !regex.is_match(text)
+            {
+                errors.push(format!("{}: does not match pattern {}", path, pattern));
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && number < min
+            {
+                errors.push(format!("{}: {} is less than {}", path, number, min));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && number > max
+            {
+                errors.push(format!("{}: {} is greater than {}", path, number, max));
+            }
+            if let Some(min) = schema.get("exclusiveMinimum").and_then(Value::as_f64)
+                && number <= min
+            {
+                errors.push(format!("{}: {} must be greater than {}", path, number, min));
+            }
+            if let Some(max) = schema.get("exclusiveMaximum").and_then(Value::as_f64)
+                && number >= max
+            {
+                errors.push(format!("{}: {} must be less than {}", path, number, max));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validates a JSON value against a schema, returning one message per violation.
+///
+/// `root` is the document used to resolve local `$ref` pointers.
+pub fn validate_schema(schema: &Value, value: &Value, root: &Value) -> Vec<String> {
+    let mut errors = vec![];
+    validate_at(schema, value, root, "$", 0, &mut errors);
+    errors
+}
+
+/// Resolves a contract file path against the configured mock root.
+pub fn resolve_contract_path(config: &Config, path: &str) -> PathBuf {
+    let path_buf = PathBuf::from(path);
+    if path_buf.is_absolute() {
+        return path_buf;
+    }
+
+    let mock_root = config
+        .server
+        .clone()
+        .unwrap_or_default()
+        .folder
+        .unwrap_or_else(|| crate::DEFAULT_FOLDER.to_string());
+    Path::new(&mock_root).join(path_buf)
+}
+
+/// Reads and parses a JSON contract file.
+pub fn read_contract(path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.to_string_lossy(), err))?;
+    serde_json::from_str(&content)
+        .map_err(|err| format!("{} is not valid JSON: {}", path.to_string_lossy(), err))
+}
+
+/// Loads the OpenAPI document configured with `[server] openapi`, if any.
+pub fn load_openapi_contract(
+    validator: &ResponseValidator,
+    config: &Config,
+) -> Result<Option<String>, String> {
+    let Some(openapi) = config.server.clone().unwrap_or_default().openapi else {
+        return Ok(None);
+    };
+
+    let path = resolve_contract_path(config, &openapi);
+    let count = validator.add_openapi(read_contract(&path)?);
+
+    Ok(Some(format!(
+        "✔️ Loaded {} response contracts from {}",
+        count,
+        path.to_string_lossy()
+    )))
+}
+
+/// Returns true for JSON responses and for text responses, since handcrafted
+/// `.json` mocks are served as plain text.
+fn may_contain_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|content_type| {
+            content_type.contains("json") || content_type.starts_with("text/plain")
+        })
+}
+
+type ResponseValidationReturn =
+    Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware that validates JSON responses against registered contracts.
+pub fn make_response_validation_middleware(
+    validator: &Arc<ResponseValidator>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> ResponseValidationReturn {
+    let validator = Arc::clone(validator);
+    move |req: Request, next: Next| {
+        let validator = Arc::clone(&validator);
+        Box::pin(async move {
+            let method = req.method().clone();
+            let path = req.uri().path().to_string();
+            let response = next.run(req).await;
+
+            if !may_contain_json(&response) || is_dropped_connection(&response) {
+                return response;
+            }
+            let Some(contract) = validator.find(&method, &path, response.status()) else {
+                return response;
+            };
+
+            let (parts, body) = response.into_parts();
+            let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+                return Response::from_parts(parts, Body::empty());
+            };
+
+            let errors = match serde_json::from_slice::<Value>(&bytes) {
+                Ok(value) => validate_schema(&contract.schema, &value, &contract.root),
+                Err(err) => vec![format!("$: response is not valid JSON ({})", err)],
+            };
+            if errors.is_empty() {
+                return Response::from_parts(parts, Body::from(bytes));
+            }
+
+            let message = format!(
+                "Response for {} {} does not match its schema: {}",
+                method,
+                path,
+                errors.join("; ")
+            );
+            match validator.mode() {
+                ValidationMode::Fail => error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "response_validation_failed",
+                    message,
+                ),
+                _ => {
+                    eprintln!("⚠️ {}", message);
+                    Response::from_parts(parts, Body::from(bytes))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware, routing::get};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn openapi() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "servers": [{ "url": "http://localhost:4520/api" }],
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "$ref": "#/components/schemas/User" }
+                                    }
+                                }
+                            },
+                            "default": {
+                                "content": {
+                                    "application/json": { "schema": { "type": "object", "required": ["error"] } }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "required": ["id", "name"],
+                        "properties": {
+                            "id": { "type": "integer" },
+                            "name": { "type": "string", "minLength": 1 },
+                            "email": { "type": "string", "nullable": true },
+                            "roles": { "type": "array", "items": { "enum": ["admin", "user"] } }
+                        },
+                        "additionalProperties": false
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn validation_mode_parses_config_values() {
+        assert_eq!(
+            ValidationMode::from_config(Some("FAIL")),
+            ValidationMode::Fail
+        );
+        assert_eq!(
+            ValidationMode::from_config(Some("off")),
+            ValidationMode::Off
+        );
+        assert_eq!(ValidationMode::from_config(None), ValidationMode::Warn);
+    }
+
+    #[test]
+    fn route_templates_match_request_paths() {
+        assert!(route_matches("/api/users/{id}", "/api/users/1"));
+        assert!(route_matches("/api/users", "/api/users/"));
+        assert!(route_matches("/files/{*key}", "/files/a/b.txt"));
+        assert!(!route_matches("/api/users/{id}", "/api/users"));
+        assert!(!route_matches("/api/users/{id}", "/api/users/1/posts"));
+    }
+
+    #[test]
+    fn validate_schema_reports_violations_with_paths() {
+        let document = openapi();
+        let schema = json!({ "$ref": "#/components/schemas/User" });
+
+        let valid = json!({ "id": 1, "name": "Ada", "email": null, "roles": ["admin"] });
+        assert!(validate_schema(&schema, &valid, &document).is_empty());
+
+        let invalid = json!({ "id": "1", "roles": ["root"], "extra": true });
+        let errors = validate_schema(&schema, &invalid, &document);
+        assert!(errors.contains(&"$: missing required property 'name'".to_string()));
+        assert!(errors.contains(&"$.id: expected integer, found string".to_string()));
+        assert!(errors.iter().any(|error| error.starts_with("$.roles[0]:")));
+        assert!(errors.contains(&"$.extra: property is not allowed".to_string()));
+    }
+
+    #[test]
+    fn openapi_contracts_prefer_exact_status_codes() {
+        let validator = ResponseValidator::new(ValidationMode::Warn);
+        assert_eq!(validator.add_openapi(openapi()), 2);
+        assert!(validator.is_active());
+
+        let ok = validator
+            .find(&Method::GET, "/api/users/1", StatusCode::OK)
+            .unwrap();
+        assert_eq!(ok.status.as_deref(), Some("200"));
+
+        let not_found = validator
+            .find(&Method::GET, "/api/users/1", StatusCode::NOT_FOUND)
+            .unwrap();
+        assert_eq!(not_found.status.as_deref(), Some("default"));
+
+        assert!(
+            validator
+                .find(&Method::POST, "/api/users/1", StatusCode::OK)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn middleware_fails_or_passes_responses_by_mode() {
+        for (mode, expected) in [
+            (ValidationMode::Fail, StatusCode::INTERNAL_SERVER_ERROR),
+            (ValidationMode::Warn, StatusCode::OK),
+        ] {
+            let validator = Arc::new(ResponseValidator::new(mode));
+            validator.add_route_schema(
+                "GET",
+                "/users/{id}",
+                json!({ "type": "object", "required": ["name"] }),
+            );
+
+            let router = Router::new()
+                .route(
+                    "/users/{id}",
+                    get(|| async { axum::Json(json!({ "id": 1 })) }),
+                )
+                .layer(middleware::from_fn(make_response_validation_middleware(
+                    &validator,
+                )));
+
+            let response = router
+                .oneshot(
+                    Request::builder()
+                        .uri("/users/1")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+    }
+}
@@ -2,14 +2,17 @@
 
 use std::{
     collections::HashMap,
+    fmt,
     fs::{self, DirEntry},
+    path::Path,
 };
 
 use fosk::IdType;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use toml::de::Error as DeserializeError;
 
-use crate::handlers::is_toml;
+use crate::{handlers::is_toml, templates::interpolate_env};
 
 /// Represents the combined configuration for the mock server.
 ///
@@ -32,6 +35,22 @@ pub struct Config {
     pub collections: Option<CollectionsConfig>,
     /// Schema file loading configuration options.
     pub schemas: Option<SchemasConfig>,
+    /// API versioning options for `vN` folders.
+    pub versioning: Option<VersioningConfig>,
+    /// GraphQL endpoint options.
+    pub graphql: Option<GraphQLConfig>,
+    /// Path prefixes forwarded to a real backend.
+    pub proxy: Option<ProxyConfig>,
+    /// Static `public` folder options.
+    pub public: Option<PublicConfig>,
+    /// Request quotas counted per client token or IP address.
+    pub quota: Option<QuotaConfig>,
+    /// Mock SAML identity provider options.
+    pub saml: Option<SamlConfig>,
+    /// Behavior changes scheduled over wall-clock time.
+    pub schedule: Option<ScheduleConfig>,
+    /// MQTT broker publishing mock topics and collecting inbound messages.
+    pub mqtt: Option<MqttConfig>,
 }
 
 /// Server configuration settings such as port, static folder, and CORS.
@@ -39,8 +58,10 @@ pub struct Config {
 /// These settings apply globally to the mock server.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ServerConfig {
-    /// Port number the server listens on.
+    /// Port number the server listens on; `0` binds any free port.
     pub port: Option<u16>,
+    /// File the bound port is written to once the server listens.
+    pub port_file: Option<String>,
     /// Filesystem path to serve static files from.
     pub folder: Option<String>,
     /// Enable or disable Cross-Origin Resource Sharing.
@@ -53,6 +74,300 @@ pub struct ServerConfig {
     pub ssl_cert: Option<String>,
     /// Path to a PEM-encoded TLS private key.
     pub ssl_key: Option<String>,
+    /// TLS options beyond the certificate pair, such as client certificates.
+    pub tls: Option<TlsConfig>,
+    /// OpenAPI JSON document used to validate responses, relative to the mock root unless absolute.
+    pub openapi: Option<String>,
+    /// Response validation mode: `warn` (default), `fail`, or `off`.
+    pub response_validation: Option<String>,
+    /// Seed for generated UUIDs, fake values, and JGD data, making runs reproducible.
+    pub seed: Option<u64>,
+    /// Additional mock folders, each mounted under its own URL prefix.
+    pub mounts: Option<Vec<FolderMount>>,
+    /// JSON template returned for unmatched routes, relative to the mock root unless absolute.
+    pub not_found_body: Option<String>,
+    /// JSON template replacing the body of error responses, relative to the mock root unless absolute.
+    pub error_body: Option<String>,
+    /// Send error responses as RFC 7807 `application/problem+json` documents.
+    pub problem_details: Option<bool>,
+    /// Prefix of problem `type` URIs, completed with the error code.
+    pub problem_type_base: Option<String>,
+    /// Problem `type` URIs by error code or status, such as `not_found` or `404`.
+    pub problem_types: Option<HashMap<String, String>>,
+    /// Serve `/users/` like `/users` instead of answering `404`.
+    pub normalize_trailing_slash: Option<bool>,
+    /// Match fixed path segments ignoring case, so `/Users` reaches `/users`.
+    pub case_insensitive_paths: Option<bool>,
+    /// Header whose value gives every tenant its own copy of the REST collections.
+    pub tenant_header: Option<String>,
+    /// Give every session id its own copy of the REST collections.
+    pub sessions: Option<bool>,
+    /// Header carrying the session id, `X-Session-Id` by default.
+    pub session_header: Option<String>,
+    /// Cookie carrying the session id when the header is missing, `session_id` by default.
+    pub session_cookie: Option<String>,
+    /// Restart the server when mock files change, enabled by default.
+    pub watch: Option<bool>,
+    /// Quiet period in milliseconds after the last file change before a restart, 300 by default.
+    pub watch_debounce: Option<u64>,
+    /// Glob patterns of files whose changes never restart the server, such as `*.log`.
+    pub watch_ignore: Option<Vec<String>>,
+    /// Serve edits to basic route files on the next request instead of restarting the server.
+    pub lazy_files: Option<bool>,
+    /// Keep every change event in the `/__audit` log, enabled by default.
+    pub audit_log: Option<bool>,
+    /// Serve the OpenAPI document at `/__openapi.json` and Swagger UI at `/__docs`, enabled by default.
+    pub docs: Option<bool>,
+    /// Active profile, whose `<name>.<profile>.toml` files overlay the matching TOML files.
+    pub profile: Option<String>,
+    /// Which file serves a method and path that several files produce.
+    pub on_conflict: Option<OnConflict>,
+    /// Keep the files of temporary upload folders across hot reloads, cleaning
+    /// them on exit or through `DELETE /mock-server/uploads` instead.
+    pub preserve_uploads: Option<bool>,
+    /// Serve the home page listing the routes, enabled by default.
+    pub home_page: Option<bool>,
+}
+
+/// How duplicate routes, several files producing the same method and path,
+/// are resolved.
+///
+/// Written as `on_conflict = "error"`, `"first"`, or `"last"` in TOML.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Refuses to start, naming both files.
+    Error,
+    /// Keeps the route of the file registered first and skips the other.
+    #[default]
+    First,
+    /// Keeps the route of the file registered last and skips the other.
+    Last,
+}
+
+/// What deleting an item does to the items of other collections referencing it.
+///
+/// Written as `on_delete = "restrict"` or `"cascade"` in TOML.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDelete {
+    /// Refuse to delete an item that is still referenced.
+    #[default]
+    Restrict,
+    /// Delete every item that references the deleted item.
+    Cascade,
+}
+
+/// TLS options of the `[server.tls]` table.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TlsConfig {
+    /// PEM file of the certificate authorities client certificates must chain
+    /// to; setting it serves HTTPS and verifies client certificates.
+    pub client_ca: Option<String>,
+    /// Client certificate policy: `required` (default) rejects handshakes
+    /// without a certificate, `optional` accepts them.
+    pub client_auth: Option<String>,
+}
+
+/// Options of a `public` folder, from the `[public]` table of its `config.toml`.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicConfig {
+    /// Serve the root index for unknown extensionless paths, so deep links
+    /// into a single-page application load it.
+    pub spa: Option<bool>,
+    /// File served for directories and by the SPA fallback, `index.html` by default.
+    pub index: Option<String>,
+    /// `Cache-Control` header of the served files.
+    pub cache_control: Option<String>,
+    /// `Cache-Control` header of index documents, `cache_control` by default.
+    pub index_cache_control: Option<String>,
+}
+
+/// Forwarding of unmatched requests to real backends, from the `[proxy]` table.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProxyConfig {
+    /// Folder recorded responses are written to, `recordings` by default.
+    pub record_folder: Option<String>,
+    /// Forwarded path prefixes, checked longest first.
+    pub rules: Option<Vec<ProxyRule>>,
+    /// Compare incoming requests with the golden requests recorded in the
+    /// record folder, and report the differences at `/__diff`.
+    pub diff: Option<bool>,
+    /// Replace names, emails, phone numbers, and card numbers in recorded
+    /// responses with deterministic fake values.
+    pub anonymize: Option<bool>,
+    /// Extra fields anonymized as full names, such as `name` or `owner`.
+    pub anonymize_fields: Option<Vec<String>>,
+    /// Key of the hash anonymized values are derived from; a random key is
+    /// used for each run when unset.
+    pub anonymize_secret: Option<String>,
+}
+
+/// Behavior changes scheduled over wall-clock time, from the `[schedule]` table.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleConfig {
+    /// Time windows and what routes do during them, applied in order.
+    pub windows: Option<Vec<ScheduleWindow>>,
+}
+
+/// MQTT broker-lite options, from the `[mqtt]` table.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttConfig {
+    /// Port the broker listens on, `1883` by default; `0` binds any free port.
+    pub port: Option<u16>,
+    /// Topics published from mock files on a schedule.
+    pub publish: Option<Vec<MqttPublish>>,
+    /// Inbound topics whose messages are stored in collections.
+    pub collect: Option<Vec<MqttCollect>>,
+}
+
+/// A topic published from a mock file, from a `[[mqtt.publish]]` entry.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttPublish {
+    /// Topic of the messages, such as `sensors/livingroom/temperature`.
+    pub topic: String,
+    /// JSON or JGD file rendered as each message, relative to the mock root unless absolute.
+    pub file: String,
+    /// Seconds between messages, `5` by default.
+    pub interval: Option<u64>,
+    /// Keep the last message for clients subscribing later.
+    pub retain: Option<bool>,
+}
+
+/// Inbound messages stored in a collection, from a `[[mqtt.collect]]` entry.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttCollect {
+    /// Topic filter of the stored messages, where `+` matches one level and `#` the rest.
+    pub topic: String,
+    /// Collection receiving the messages, created when missing.
+    pub collection: String,
+}
+
+/// A time window during which matching requests are delayed or fail.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleWindow {
+    /// Name shown in the `X-Mock-Schedule` header of affected responses.
+    pub name: Option<String>,
+    /// Glob pattern of the affected paths, such as `/payments*`; every path by default.
+    pub path: Option<String>,
+    /// Affected methods; every method by default.
+    pub methods: Option<Vec<String>>,
+    /// Start of the window: `HH:MM[:SS]` local time every day, or an RFC 3339
+    /// timestamp; server start by default.
+    pub start: Option<String>,
+    /// End of the window, in the same form as `start`; open-ended by default.
+    pub end: Option<String>,
+    /// Length of the window in seconds, instead of `end`.
+    pub duration: Option<u64>,
+    /// Status answered instead of the route's response, such as `503`.
+    pub status: Option<u16>,
+    /// Message of the error body answered with `status`.
+    pub message: Option<String>,
+    /// Delay added before the response.
+    pub delay: Option<Delay>,
+    /// Delay growing linearly from the first to the second number of
+    /// milliseconds over the window, which needs an end.
+    pub delay_ramp: Option<[u64; 2]>,
+}
+
+/// Request quotas counted per client, from the `[quota]` table.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuotaConfig {
+    /// Requests a client may make per window; unlimited by default, which
+    /// only counts them.
+    pub limit: Option<u64>,
+    /// Seconds after a client's first request when its count starts over;
+    /// never by default, until the quota is reset through `/__quota`.
+    pub window: Option<u64>,
+    /// What identifies a client: `token` (default) counts per bearer token and
+    /// falls back to the IP address, `ip` counts per IP address only.
+    pub key: Option<String>,
+    /// Header carrying the token, such as `x-api-key`, instead of the
+    /// `Authorization: Bearer` header.
+    pub header: Option<String>,
+    /// Glob pattern of the counted paths, such as `/api/*`; every path by default.
+    pub path: Option<String>,
+}
+
+/// A mock SAML identity provider, from the `[saml]` table.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SamlConfig {
+    /// Route prefix of the metadata and SSO endpoints, `/saml` by default.
+    pub route: Option<String>,
+    /// Entity ID of the identity provider, the metadata URL by default.
+    pub entity_id: Option<String>,
+    /// Assertion consumer service URL used when the request names none.
+    pub acs_url: Option<String>,
+    /// Audience of the assertions used when the request names no issuer.
+    pub audience: Option<String>,
+    /// PEM file of the PKCS#8 RSA signing key, relative to the mock root unless
+    /// absolute; a key is generated on first use when unset.
+    pub key: Option<String>,
+    /// PEM file of the certificate of `key`; self-signed from the key when unset.
+    pub certificate: Option<String>,
+    /// Minutes an assertion stays valid, 5 by default.
+    pub assertion_minutes: Option<u64>,
+    /// User the assertions are issued for: `name_id` is the subject, and every
+    /// other field becomes an attribute.
+    pub user: Option<Value>,
+}
+
+/// A path prefix forwarded to a backend.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProxyRule {
+    /// Path prefix of the forwarded requests, such as `/payments`.
+    pub prefix: String,
+    /// Backend URL, such as `https://sandbox.example.com/v2`.
+    pub target: String,
+    /// Drop the prefix from the forwarded path.
+    pub strip_prefix: Option<bool>,
+    /// Headers set on forwarded requests, replacing those sent by the client.
+    pub set_headers: Option<HashMap<String, String>>,
+    /// Headers removed from forwarded requests.
+    pub remove_headers: Option<Vec<String>>,
+    /// Write successful responses to the record folder as mock files.
+    pub record: Option<bool>,
+    /// PEM file of the authorities trusted for an `https` target, instead of
+    /// the system trust store.
+    pub ca: Option<String>,
+}
+
+/// A mock folder mounted under a URL prefix.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FolderMount {
+    /// URL prefix the folder routes are mounted under, such as `/billing`.
+    pub prefix: Option<String>,
+    /// Mock folder to load.
+    pub folder: String,
+}
+
+impl FolderMount {
+    /// Parses the `[prefix:]folder` command-line form, e.g. `/billing:./mocks/billing`.
+    ///
+    /// A missing leading slash is added to the prefix, and a single drive letter
+    /// such as `C:\mocks` is read as part of the folder.
+    pub fn parse(value: &str) -> Self {
+        match value.split_once(':') {
+            Some((prefix, folder)) if !prefix.is_empty() && !is_drive_letter(prefix, folder) => {
+                Self {
+                    prefix: Some(normalize_prefix(prefix)),
+                    folder: folder.to_string(),
+                }
+            }
+            _ => Self {
+                prefix: None,
+                folder: value.to_string(),
+            },
+        }
+    }
+}
+
+fn is_drive_letter(prefix: &str, folder: &str) -> bool {
+    prefix.len() == 1 && folder.starts_with(['/', '\\'])
+}
+
+fn normalize_prefix(prefix: &str) -> String {
+    format!("/{}", prefix.trim_matches('/'))
 }
 
 /// Route-specific configuration settings.
@@ -61,12 +376,471 @@ pub struct ServerConfig {
 /// and protection for individual routes.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RouteConfig {
-    /// Artificial delay (in milliseconds) before responding.
-    pub delay: Option<u16>,
+    /// Artificial delay before responding, fixed in milliseconds or drawn
+    /// from a distribution on every request.
+    pub delay: Option<Delay>,
     /// Remapped path for the route.
     pub remap: Option<String>,
+    /// Pattern-based rewrites such as `/api/* -> /v2/*`, applied to every discovered route.
+    pub rewrites: Option<Vec<String>>,
     /// Protect the route (e.g., require authentication).
     pub protect: Option<bool>,
+    /// JSON Schema file that responses must match, relative to the mock root unless absolute.
+    pub response_schema: Option<String>,
+    /// Holds requests open without answering, to exercise client timeouts.
+    pub hang: Option<Hang>,
+    /// Breaks the connection or the response body, to exercise client error handling.
+    pub fault: Option<Fault>,
+    /// Cookies set on every response, as `[[route.cookies]]` tables.
+    pub cookies: Option<Vec<CookieConfig>>,
+    /// Route called after every successful response, to simulate side effects.
+    pub after: Option<AfterCall>,
+    /// Seconds a rendered response is reused before the file is rendered again.
+    pub cache_ttl: Option<u64>,
+    /// Media type sent instead of the one inferred from the file, such as `application/xml`.
+    pub content_type: Option<String>,
+    /// Charset declared on the content type and used to encode text bodies,
+    /// `utf-8` or `iso-8859-1`.
+    pub charset: Option<String>,
+    /// Reject mutating requests that do not echo the token of `GET /csrf`.
+    pub csrf: Option<bool>,
+    /// How the numbered files of a route are picked on each call.
+    pub strategy: Option<ResponseStrategy>,
+    /// Largest response body in bytes; larger bodies are handled as `oversize` says.
+    pub max_response_bytes: Option<u64>,
+    /// What happens to bodies over `max_response_bytes`: truncated by default.
+    pub oversize: Option<Oversize>,
+    /// Seconds a request may take before it times out, as `timeout_mode` says.
+    pub request_timeout: Option<u64>,
+    /// Which side times out: the `client` sending the body (408, default) or
+    /// the `gateway` waiting for the answer (504).
+    pub timeout_mode: Option<TimeoutMode>,
+}
+
+/// Internal call to another route made after a route answers successfully.
+///
+/// Written as `after = { call = "POST /internal/audit", body = "{{response}}" }`.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AfterCall {
+    /// Method and path of the called route, such as `POST /internal/audit`.
+    pub call: String,
+    /// JSON body sent, with `{{response}}` and `{{request}}` placeholders;
+    /// the response body by default.
+    pub body: Option<Value>,
+}
+
+/// Cookie a route sets on its responses with a `Set-Cookie` header.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CookieConfig {
+    /// Cookie name.
+    pub name: String,
+    /// Cookie value.
+    pub value: String,
+    /// `Path` attribute.
+    pub path: Option<String>,
+    /// `Domain` attribute.
+    pub domain: Option<String>,
+    /// `Max-Age` attribute in seconds.
+    pub max_age: Option<i64>,
+    /// Adds the `HttpOnly` flag.
+    pub http_only: Option<bool>,
+    /// Adds the `Secure` flag.
+    pub secure: Option<bool>,
+    /// `SameSite` attribute, such as `Strict`, `Lax`, or `None`.
+    pub same_site: Option<String>,
+}
+
+/// Transport-level failure a route answers with instead of a valid response.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// Closes the connection without answering.
+    Reset,
+    /// Answers with the first half of the body, which is not valid JSON.
+    InvalidJson,
+    /// Announces the full `Content-Length`, then closes the connection halfway through the body.
+    Truncated,
+    /// Sends a chunked body that stops without its terminating chunk.
+    BrokenChunked,
+}
+
+/// Artificial response delay, fixed or drawn from a distribution on every request.
+///
+/// Written as `delay = <milliseconds>`, `delay = "uniform(50,500)"`,
+/// `delay = "normal(200,50)"`, or `delay = "p50=100,p95=400,p99=1200"` in TOML.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "DelayValue", into = "DelayValue")]
+pub enum Delay {
+    /// Always the given milliseconds.
+    Fixed(u32),
+    /// Uniformly between `min` and `max` milliseconds.
+    Uniform { min: u32, max: u32 },
+    /// Normally distributed around `mean` milliseconds, never below zero.
+    Normal { mean: f64, std_dev: f64 },
+    /// Half the requests up to `p50`, 95% up to `p95`, and 99% up to `p99`
+    /// milliseconds.
+    Percentiles { p50: u32, p95: u32, p99: u32 },
+}
+
+impl Delay {
+    /// Parses the text form of a delay, such as `normal(200,50)`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let invalid = || {
+            format!(
+                "delay must be milliseconds, \"uniform(min,max)\", \"normal(mean,std_dev)\", or \"p50=..,p95=..,p99=..\", not \"{}\"",
+                text
+            )
+        };
+        let arguments = |name: &str| {
+            text.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('('))
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|rest| rest.split_once(','))
+        };
+
+        if let Ok(millis) = text.parse::<u32>() {
+            return Ok(Self::Fixed(millis));
+        }
+        if let Some((min, max)) = arguments("uniform") {
+            let (Ok(min), Ok(max)) = (min.parse::<u32>(), max.parse::<u32>()) else {
+                return Err(invalid());
+            };
+            if min > max {
+                return Err(format!(
+                    "uniform delay minimum {} exceeds its maximum {}",
+                    min, max
+                ));
+            }
+            return Ok(Self::Uniform { min, max });
+        }
+        if let Some((mean, std_dev)) = arguments("normal") {
+            let (Ok(mean), Ok(std_dev)) = (mean.parse::<f64>(), std_dev.parse::<f64>()) else {
+                return Err(invalid());
+            };
+            if !(mean.is_finite() && std_dev.is_finite() && mean >= 0.0 && std_dev >= 0.0) {
+                return Err(invalid());
+            }
+            return Ok(Self::Normal { mean, std_dev });
+        }
+
+        let mut percentiles = HashMap::new();
+        for part in text.split(',') {
+            let Some((name, millis)) = part.split_once('=') else {
+                return Err(invalid());
+            };
+            let Ok(millis) = millis.trim_end_matches("ms").parse::<u32>() else {
+                return Err(invalid());
+            };
+            percentiles.insert(name.to_ascii_lowercase(), millis);
+        }
+        let (Some(&p50), Some(&p95), Some(&p99), 3) = (
+            percentiles.get("p50"),
+            percentiles.get("p95"),
+            percentiles.get("p99"),
+            percentiles.len(),
+        ) else {
+            return Err(invalid());
+        };
+        if p50 > p95 || p95 > p99 {
+            return Err(format!(
+                "delay percentiles must not decrease: p50={}, p95={}, p99={}",
+                p50, p95, p99
+            ));
+        }
+        Ok(Self::Percentiles { p50, p95, p99 })
+    }
+}
+
+impl fmt::Display for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Delay::Fixed(millis) => write!(f, "{}ms", millis),
+            Delay::Uniform { min, max } => write!(f, "uniform({},{})", min, max),
+            Delay::Normal { mean, std_dev } => write!(f, "normal({},{})", mean, std_dev),
+            Delay::Percentiles { p50, p95, p99 } => {
+                write!(f, "p50={},p95={},p99={}", p50, p95, p99)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum DelayValue {
+    Millis(u32),
+    Text(String),
+}
+
+impl TryFrom<DelayValue> for Delay {
+    type Error = String;
+
+    fn try_from(value: DelayValue) -> Result<Self, Self::Error> {
+        match value {
+            DelayValue::Millis(millis) => Ok(Self::Fixed(millis)),
+            DelayValue::Text(text) => Self::parse(&text),
+        }
+    }
+}
+
+impl From<Delay> for DelayValue {
+    fn from(delay: Delay) -> Self {
+        match delay {
+            Delay::Fixed(millis) => Self::Millis(millis),
+            delay => Self::Text(delay.to_string()),
+        }
+    }
+}
+
+/// How a route holds requests open without answering.
+///
+/// Written as `hang = "infinite"` or `hang = <seconds>` in TOML.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "HangValue", into = "HangValue")]
+pub enum Hang {
+    /// Never answers.
+    Infinite,
+    /// Closes the connection without answering after the given seconds.
+    Seconds(u64),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HangValue {
+    Seconds(u64),
+    Text(String),
+}
+
+impl TryFrom<HangValue> for Hang {
+    type Error = String;
+
+    fn try_from(value: HangValue) -> Result<Self, Self::Error> {
+        match value {
+            HangValue::Seconds(seconds) => Ok(Self::Seconds(seconds)),
+            HangValue::Text(text) if text == "infinite" => Ok(Self::Infinite),
+            HangValue::Text(text) => Err(format!(
+                "hang must be \"infinite\" or a number of seconds, not \"{}\"",
+                text
+            )),
+        }
+    }
+}
+
+impl From<Hang> for HangValue {
+    fn from(hang: Hang) -> Self {
+        match hang {
+            Hang::Infinite => Self::Text("infinite".to_string()),
+            Hang::Seconds(seconds) => Self::Seconds(seconds),
+        }
+    }
+}
+
+/// Which timeout a route's `request_timeout` emulates.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutMode {
+    /// Answers `408 Request Timeout` when the request body is not received in time.
+    #[default]
+    Client,
+    /// Answers `504 Gateway Timeout` when the route does not answer in time.
+    Gateway,
+}
+
+/// What a route does with a response body over its `max_response_bytes`.
+///
+/// Written as `oversize = "truncate"`, `oversize = "reject"`, or
+/// `oversize = <status>` in TOML.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "OversizeValue", into = "OversizeValue")]
+pub enum Oversize {
+    /// Cuts the body at the limit.
+    #[default]
+    Truncate,
+    /// Answers with the given error status instead, `413` for `reject`.
+    Reject(u16),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum OversizeValue {
+    Status(u16),
+    Text(String),
+}
+
+impl TryFrom<OversizeValue> for Oversize {
+    type Error = String;
+
+    fn try_from(value: OversizeValue) -> Result<Self, Self::Error> {
+        match value {
+            OversizeValue::Status(status @ 400..=599) => Ok(Self::Reject(status)),
+            OversizeValue::Status(status) => Err(format!(
+                "oversize status must be a 4xx or 5xx status, not {}",
+                status
+            )),
+            OversizeValue::Text(text) if text == "truncate" => Ok(Self::Truncate),
+            OversizeValue::Text(text) if text == "reject" => Ok(Self::Reject(413)),
+            OversizeValue::Text(text) => Err(format!(
+                "oversize must be \"truncate\", \"reject\", or an error status, not \"{}\"",
+                text
+            )),
+        }
+    }
+}
+
+impl From<Oversize> for OversizeValue {
+    fn from(oversize: Oversize) -> Self {
+        match oversize {
+            Oversize::Truncate => Self::Text("truncate".to_string()),
+            Oversize::Reject(status) => Self::Status(status),
+        }
+    }
+}
+
+/// How a route with several numbered files, such as `get.1.json` and
+/// `get.2.json`, picks the file served on each call.
+///
+/// Written as `strategy = "sequence"`, `"round-robin"`, `"random"`, or
+/// `"weighted(90,10)"` in TOML.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum ResponseStrategy {
+    /// Each file on its numbered call, then the `rest` file.
+    Sequence,
+    /// The files in turn, starting over after the last one.
+    RoundRobin,
+    /// Any file, with the same chance.
+    Random,
+    /// Any file, with chances proportional to the weights, given in file order.
+    Weighted(Vec<u32>),
+}
+
+impl ResponseStrategy {
+    /// Parses the text form of a strategy, such as `weighted(90,10)`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        match text.to_ascii_lowercase().as_str() {
+            "sequence" => return Ok(Self::Sequence),
+            "round-robin" | "round_robin" => return Ok(Self::RoundRobin),
+            "random" => return Ok(Self::Random),
+            _ => {}
+        }
+
+        let invalid = || {
+            format!(
+                "strategy must be \"sequence\", \"round-robin\", \"random\", or \"weighted(90,10)\", not \"{}\"",
+                text
+            )
+        };
+        let weights = text
+            .strip_prefix("weighted(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(invalid)?
+            .split(',')
+            .map(|weight| weight.trim_end_matches('%').parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        if weights.iter().all(|weight| *weight == 0) {
+            return Err("strategy weights must not all be zero".to_string());
+        }
+        Ok(Self::Weighted(weights))
+    }
+}
+
+impl fmt::Display for ResponseStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sequence => write!(f, "sequence"),
+            Self::RoundRobin => write!(f, "round-robin"),
+            Self::Random => write!(f, "random"),
+            Self::Weighted(weights) => {
+                let weights = weights.iter().map(u32::to_string).collect::<Vec<_>>();
+                write!(f, "weighted({})", weights.join(","))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for ResponseStrategy {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+impl From<ResponseStrategy> for String {
+    fn from(strategy: ResponseStrategy) -> Self {
+        strategy.to_string()
+    }
+}
+
+/// How long the files of an upload folder are kept.
+///
+/// Written as `retention = "15m"`, with an `s`, `m`, `h`, or `d` unit, or
+/// `retention = "until-shutdown"` in TOML.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Retention {
+    /// Deleted when the server stops.
+    UntilShutdown,
+    /// Deleted once older than the duration, counted from the last write.
+    For(std::time::Duration),
+}
+
+impl Retention {
+    /// Parses the text form of a retention, such as `15m` or `until-shutdown`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("until-shutdown") {
+            return Ok(Self::UntilShutdown);
+        }
+
+        let invalid = || {
+            format!(
+                "retention must be a duration such as \"90s\", \"15m\", \"2h\", or \"1d\", or \"until-shutdown\", not \"{}\"",
+                text
+            )
+        };
+        let unit_at = text
+            .find(|character: char| !character.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let (amount, unit) = text.split_at(unit_at);
+        let amount = amount.parse::<u64>().map_err(|_| invalid())?;
+        let seconds = match unit {
+            "s" => amount,
+            "m" => amount * 60,
+            "h" => amount * 60 * 60,
+            "d" => amount * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        if seconds == 0 {
+            return Err("retention must be longer than zero".to_string());
+        }
+        Ok(Self::For(std::time::Duration::from_secs(seconds)))
+    }
+}
+
+impl fmt::Display for Retention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UntilShutdown => write!(f, "until-shutdown"),
+            Self::For(duration) => write!(f, "{}s", duration.as_secs()),
+        }
+    }
+}
+
+impl TryFrom<String> for Retention {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+impl From<Retention> for String {
+    fn from(retention: Retention) -> Self {
+        retention.to_string()
+    }
 }
 
 /// Configuration for Fosk collections.
@@ -80,6 +854,40 @@ pub struct CollectionConfig {
     pub id_key: Option<String>,
     /// Strategy for generating or interpreting Fosk collection identifiers.
     pub id_type: Option<IdType>,
+    /// Generated id format: `ulid`, `nanoid`, `snowflake`, or a template such as `usr_{{seq}}`.
+    pub id_strategy: Option<String>,
+    /// Fields referencing other collections, mapped to the referenced collection name.
+    pub refs: Option<HashMap<String, String>>,
+    /// Behavior when a referenced item is deleted: `restrict` (default) or `cascade`.
+    pub on_delete: Option<OnDelete>,
+    /// Values filled into inserted items when the field is missing.
+    pub defaults: Option<HashMap<String, Value>>,
+    /// Values written into items on every insert and update.
+    pub computed: Option<HashMap<String, Value>>,
+    /// Mark deleted items with `deletedAt` instead of removing them.
+    pub soft_delete: Option<bool>,
+    /// Ignore string case in `?where=` equality, `IN`, and `LIKE` comparisons.
+    pub case_insensitive: Option<bool>,
+    /// Add HAL `_links` to items, pointing at the item, its collection, and referenced items.
+    pub links: Option<bool>,
+    /// Serve the collection for reads only, rejecting POST, PUT, PATCH, and DELETE.
+    pub read_only: Option<bool>,
+    /// Field renames, enum mappings, and masking applied to REST and GraphQL responses.
+    pub transform: Option<TransformConfig>,
+}
+
+/// Response transformation configuration for a collection.
+///
+/// Applied to items on the way out of REST and GraphQL handlers: codes are
+/// mapped first, then fields are masked, then renamed.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransformConfig {
+    /// New field names, keyed by stored field name.
+    pub rename: Option<HashMap<String, String>>,
+    /// Values replacing stored codes, keyed by field and then by code.
+    pub map: Option<HashMap<String, HashMap<String, Value>>>,
+    /// Fields masked as personal data, such as emails or card numbers.
+    pub mask: Option<Vec<String>>,
 }
 
 /// Collection file loading configuration.
@@ -91,6 +899,27 @@ pub struct CollectionsConfig {
     pub folder: Option<String>,
 }
 
+/// API versioning configuration for `vN` folders.
+///
+/// Version folders such as `users/v1` are served under a version prefix
+/// (`/v1/users`); these settings add a `latest` alias and deprecation headers.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersioningConfig {
+    /// Also serve the highest version under the `latest` prefix.
+    pub latest: Option<bool>,
+    /// Deprecation date of each deprecated version, sent in the `Deprecation` header.
+    pub deprecated: Option<HashMap<String, String>>,
+    /// Sunset date of each version, sent in the `Sunset` header.
+    pub sunset: Option<HashMap<String, String>>,
+}
+
+/// GraphQL endpoint configuration.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GraphQLConfig {
+    /// Expose collections as Relay connections instead of plain lists.
+    pub relay: Option<bool>,
+}
+
 /// Authentication-related configuration.
 ///
 /// Includes user credentials, cookie settings, JWT secret,
@@ -119,6 +948,24 @@ pub struct AuthConfig {
     pub logout_endpoint: Option<String>,
     /// Route path for user management.
     pub users_route: Option<String>,
+    /// Endpoint changing a user's password, `/password` by default.
+    pub password_endpoint: Option<String>,
+    /// Endpoint signing up a new user, `/register` by default.
+    pub register_endpoint: Option<String>,
+    /// Endpoint reading and updating the logged-in user, `/me` by default.
+    pub me_endpoint: Option<String>,
+    /// Consecutive failed logins that lock a username; unset never locks.
+    pub max_failed_attempts: Option<u32>,
+    /// Minutes a locked username answers `423 Locked`, 15 by default.
+    pub lockout_minutes: Option<u64>,
+    /// Static code of the MFA login step; setting it enables the step.
+    pub mfa_code: Option<String>,
+    /// Base32 TOTP secret of the MFA login step; setting it enables the step.
+    pub mfa_secret: Option<String>,
+    /// Endpoint verifying MFA codes, `/mfa/verify` by default.
+    pub mfa_endpoint: Option<String>,
+    /// Minutes an MFA challenge stays valid, 5 by default.
+    pub mfa_challenge_minutes: Option<u64>,
 }
 
 /// File upload configuration settings.
@@ -135,6 +982,27 @@ pub struct UploadConfig {
     pub list_files_endpoint: Option<String>,
     /// Use temporary storage for uploads.
     pub temporary: Option<bool>,
+    /// Enable resumable chunked uploads (HEAD for offset, PATCH to append).
+    pub chunked: Option<bool>,
+    /// Maximum accepted chunk size in bytes for chunked uploads.
+    pub max_chunk_size: Option<u64>,
+    /// Download bandwidth limit in kilobytes per second.
+    pub download_rate: Option<u32>,
+    /// `Content-Disposition` type for downloads: `attachment` (default) or `inline`.
+    pub download_disposition: Option<String>,
+    /// How long uploaded files are kept: a duration such as `15m`, or
+    /// `until-shutdown`; either makes the folder temporary.
+    pub retention: Option<Retention>,
+    /// Simulate a virus scan of every uploaded file.
+    pub scan: Option<bool>,
+    /// Milliseconds a simulated scan takes before its verdict.
+    pub scan_delay: Option<u64>,
+    /// `*` and `?` patterns of the file names a scan reports as infected.
+    pub scan_infected: Option<Vec<String>>,
+    /// Percentage of the other files a scan reports as infected.
+    pub scan_infected_percent: Option<u32>,
+    /// `http://` URL receiving scan verdicts as JSON `POST` requests.
+    pub scan_webhook: Option<String>,
 }
 
 /// Schema file loading configuration.
@@ -151,8 +1019,10 @@ pub struct SchemasConfig {
 impl TryFrom<&str> for Config {
     type Error = DeserializeError;
 
+    /// Parses TOML text after replacing `${NAME}` and `${NAME:-default}`
+    /// with environment variables.
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        toml::from_str(value)
+        toml::from_str(&interpolate_env(value))
     }
 }
 
@@ -170,12 +1040,23 @@ impl TryFrom<&DirEntry> for Config {
 #[derive(Debug, Default)]
 pub struct ConfigStore {
     map_configs: HashMap<String, Config>,
+    profile: Option<String>,
 }
 
 impl ConfigStore {
     /// Loads all TOML files in a directory, keyed by lowercase file stem.
-    pub fn try_from_dir(dir_path: &str) -> Result<Self, std::io::Error> {
-        let mut store = Self::default();
+    ///
+    /// With a `profile`, a `<name>.<profile>.toml` file overlays `<name>.toml`
+    /// through [`Config::overlay`], and is also used alone when there is no
+    /// `<name>.toml`.
+    pub fn try_from_dir(
+        dir_path: impl AsRef<Path>,
+        profile: Option<&str>,
+    ) -> Result<Self, std::io::Error> {
+        let mut store = Self {
+            profile: profile.map(str::to_string),
+            ..Default::default()
+        };
         fs::read_dir(dir_path)?
             .filter_map(Result::ok)
             .filter(|file| is_toml(&file.file_name()))
@@ -199,9 +1080,33 @@ impl ConfigStore {
                 }
             });
 
+        if let Some(profile) = profile {
+            let suffix = format!(".{}", profile.to_ascii_lowercase());
+            let overlays = store
+                .map_configs
+                .keys()
+                .filter(|key| key.ends_with(&suffix))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in overlays {
+                let overlay = store.map_configs.remove(&key).unwrap();
+                let base = key.strip_suffix(&suffix).unwrap().to_string();
+                let config = match store.map_configs.remove(&base) {
+                    Some(base_config) => overlay.overlay(base_config),
+                    None => overlay,
+                };
+                store.map_configs.insert(base, config);
+            }
+        }
+
         Ok(store)
     }
 
+    /// Returns the profile whose overlays this store applied.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
     /// Returns a cloned configuration by case-insensitive key.
     pub fn get(&self, key: &str) -> Option<Config> {
         self.map_configs
@@ -228,11 +1133,44 @@ impl Config {
                 upload: self.upload,         //.merge(parent.upload), don't merge upload
                 collections: self.collections.merge(parent.collections),
                 schemas: self.schemas.merge(parent.schemas),
+                versioning: self.versioning.merge(parent.versioning),
+                graphql: self.graphql, //.merge(parent.graphql), don't merge graphql
+                proxy: self.proxy,     //.merge(parent.proxy), don't merge proxy
+                public: self.public,   //.merge(parent.public), don't merge public
+                quota: self.quota,     //.merge(parent.quota), don't merge quota
+                saml: self.saml,       //.merge(parent.saml), don't merge saml
+                schedule: self.schedule, //.merge(parent.schedule), don't merge schedule
+                mqtt: self.mqtt,       //.merge(parent.mqtt), don't merge mqtt
             },
             None => self,
         }
     }
 
+    /// Overlays this profile configuration on a `base` configuration.
+    ///
+    /// Unlike [`Config::merge`], the `collection`, `auth`, and `upload` tables
+    /// are merged too, so a profile can change a single value such as the JWT
+    /// secret.
+    pub fn overlay(self, base: Self) -> Self {
+        Self {
+            server: self.server.merge(base.server),
+            route: self.route.merge(base.route),
+            collection: self.collection.merge(base.collection),
+            auth: self.auth.merge(base.auth),
+            upload: self.upload.merge(base.upload),
+            collections: self.collections.merge(base.collections),
+            schemas: self.schemas.merge(base.schemas),
+            versioning: self.versioning.merge(base.versioning),
+            graphql: self.graphql.merge(base.graphql),
+            proxy: self.proxy.merge(base.proxy),
+            public: self.public.merge(base.public),
+            quota: self.quota.merge(base.quota),
+            saml: self.saml.merge(base.saml),
+            schedule: self.schedule.merge(base.schedule),
+            mqtt: self.mqtt.merge(base.mqtt),
+        }
+    }
+
     /// Merges this configuration with a borrowed parent configuration.
     pub fn merge_with_ref(self, parent: &Self) -> Self {
         let parent = parent.clone();
@@ -244,9 +1182,59 @@ impl Config {
             upload: self.upload,         //.merge(parent.upload), don't merge upload
             collections: self.collections.merge(parent.collections),
             schemas: self.schemas.merge(parent.schemas),
+            versioning: self.versioning.merge(parent.versioning),
+            graphql: self.graphql, //.merge(parent.graphql), don't merge graphql
+            proxy: self.proxy,     //.merge(parent.proxy), don't merge proxy
+            public: self.public,   //.merge(parent.public), don't merge public
+            quota: self.quota,     //.merge(parent.quota), don't merge quota
+            saml: self.saml,       //.merge(parent.saml), don't merge saml
+            schedule: self.schedule, //.merge(parent.schedule), don't merge schedule
+            mqtt: self.mqtt,       //.merge(parent.mqtt), don't merge mqtt
         }
     }
 
+    /// Returns one configuration per mounted mock folder.
+    ///
+    /// The `[server].folder` tree comes first, unless it is unset and mounts are
+    /// configured. Each mount gets its folder and its prefix prepended to the
+    /// route remap.
+    pub fn mount_configs(&self) -> Vec<Config> {
+        let server = self.server.clone().unwrap_or_default();
+        let mounts = server.mounts.clone().unwrap_or_default();
+
+        let primary = (server.folder.is_some() || mounts.is_empty()).then(|| FolderMount {
+            prefix: None,
+            folder: server
+                .folder
+                .clone()
+                .unwrap_or(crate::DEFAULT_FOLDER.to_string()),
+        });
+
+        primary
+            .into_iter()
+            .chain(mounts)
+            .map(|mount| {
+                let mut config = self.clone();
+                let mut server = server.clone();
+                server.folder = Some(mount.folder);
+                server.mounts = None;
+                config.server = Some(server);
+
+                if let Some(prefix) = mount.prefix {
+                    let mut route = config.route.unwrap_or_default();
+                    let remap = route.remap.unwrap_or_default();
+                    route.remap = Some(format!(
+                        "{}{}",
+                        normalize_prefix(&prefix).trim_end_matches('/'),
+                        remap
+                    ));
+                    config.route = Some(route);
+                }
+                config
+            })
+            .collect()
+    }
+
     /// Sets the route protection flag.
     pub fn with_protect(mut self, protect: bool) -> Self {
         let mut route = self.route.unwrap_or_default();
@@ -294,6 +1282,14 @@ impl Mergeable for Config {
             upload: self.upload,         //.merge(parent.upload), don't merge upload
             collections: self.collections.merge(parent.collections),
             schemas: self.schemas.merge(parent.schemas),
+            versioning: self.versioning.merge(parent.versioning),
+            graphql: self.graphql, //.merge(parent.graphql), don't merge graphql
+            proxy: self.proxy,     //.merge(parent.proxy), don't merge proxy
+            public: self.public,   //.merge(parent.public), don't merge public
+            quota: self.quota,     //.merge(parent.quota), don't merge quota
+            saml: self.saml,       //.merge(parent.saml), don't merge saml
+            schedule: self.schedule, //.merge(parent.schedule), don't merge schedule
+            mqtt: self.mqtt,       //.merge(parent.mqtt), don't merge mqtt
         }
     }
 }
@@ -306,6 +1302,7 @@ impl Mergeable for Option<Config> {
                 route: None.merge(p.route),
                 collections: None.merge(p.collections),
                 schemas: None.merge(p.schemas),
+                versioning: None.merge(p.versioning),
                 ..Default::default()
             }),
             (Some(child), None) => Some(child),
@@ -317,6 +1314,14 @@ impl Mergeable for Option<Config> {
                 upload: child.upload,         //.merge(parent.upload), don't merge upload
                 collections: child.collections.merge(parent.collections),
                 schemas: child.schemas.merge(parent.schemas),
+                versioning: child.versioning.merge(parent.versioning),
+                graphql: child.graphql, //.merge(parent.graphql), don't merge graphql
+                proxy: child.proxy,     //.merge(parent.proxy), don't merge proxy
+                public: child.public,   //.merge(parent.public), don't merge public
+                quota: child.quota,     //.merge(parent.quota), don't merge quota
+                saml: child.saml,       //.merge(parent.saml), don't merge saml
+                schedule: child.schedule, //.merge(parent.schedule), don't merge schedule
+                mqtt: child.mqtt,       //.merge(parent.mqtt), don't merge mqtt
             }),
         }
     }
@@ -330,12 +1335,43 @@ impl Mergeable for Option<ServerConfig> {
             (Some(child), None) => Some(child),
             (Some(child), Some(parent)) => Some(ServerConfig {
                 port: child.port.merge(parent.port),
+                port_file: child.port_file.merge(parent.port_file),
                 folder: child.folder.merge(parent.folder),
                 enable_cors: child.enable_cors.merge(parent.enable_cors),
                 allowed_origin: child.allowed_origin.merge(parent.allowed_origin),
                 ssl: child.ssl.merge(parent.ssl),
                 ssl_cert: child.ssl_cert.merge(parent.ssl_cert),
                 ssl_key: child.ssl_key.merge(parent.ssl_key),
+                tls: child.tls.merge(parent.tls),
+                openapi: child.openapi.merge(parent.openapi),
+                response_validation: child.response_validation.merge(parent.response_validation),
+                seed: child.seed.merge(parent.seed),
+                mounts: child.mounts.merge(parent.mounts),
+                not_found_body: child.not_found_body.merge(parent.not_found_body),
+                error_body: child.error_body.merge(parent.error_body),
+                problem_details: child.problem_details.merge(parent.problem_details),
+                problem_type_base: child.problem_type_base.merge(parent.problem_type_base),
+                problem_types: child.problem_types.merge(parent.problem_types),
+                normalize_trailing_slash: child
+                    .normalize_trailing_slash
+                    .merge(parent.normalize_trailing_slash),
+                case_insensitive_paths: child
+                    .case_insensitive_paths
+                    .merge(parent.case_insensitive_paths),
+                tenant_header: child.tenant_header.merge(parent.tenant_header),
+                sessions: child.sessions.merge(parent.sessions),
+                session_header: child.session_header.merge(parent.session_header),
+                session_cookie: child.session_cookie.merge(parent.session_cookie),
+                watch: child.watch.merge(parent.watch),
+                watch_debounce: child.watch_debounce.merge(parent.watch_debounce),
+                watch_ignore: child.watch_ignore.merge(parent.watch_ignore),
+                lazy_files: child.lazy_files.merge(parent.lazy_files),
+                audit_log: child.audit_log.merge(parent.audit_log),
+                docs: child.docs.merge(parent.docs),
+                profile: child.profile.merge(parent.profile),
+                on_conflict: child.on_conflict.merge(parent.on_conflict),
+                preserve_uploads: child.preserve_uploads.merge(parent.preserve_uploads),
+                home_page: child.home_page.merge(parent.home_page),
             }),
         }
     }
@@ -348,13 +1384,39 @@ impl Mergeable for Option<RouteConfig> {
             (None, Some(p)) => Some(RouteConfig {
                 delay: p.delay,
                 protect: p.protect,
+                hang: p.hang,
+                fault: p.fault,
+                cookies: p.cookies,
+                cache_ttl: p.cache_ttl,
+                charset: p.charset,
+                csrf: p.csrf,
+                strategy: p.strategy,
+                max_response_bytes: p.max_response_bytes,
+                oversize: p.oversize,
+                request_timeout: p.request_timeout,
+                timeout_mode: p.timeout_mode,
                 ..Default::default()
             }),
             (Some(child), None) => Some(child),
             (Some(child), Some(parent)) => Some(RouteConfig {
                 delay: child.delay.merge(parent.delay),
                 remap: child.remap, //.merge(parent.remap),
+                rewrites: child.rewrites,
                 protect: child.protect.merge(parent.protect),
+                response_schema: child.response_schema, //.merge(parent.response_schema),
+                hang: child.hang.merge(parent.hang),
+                fault: child.fault.merge(parent.fault),
+                cookies: child.cookies.merge(parent.cookies),
+                after: child.after, //.merge(parent.after), don't inherit side effects
+                cache_ttl: child.cache_ttl.merge(parent.cache_ttl),
+                content_type: child.content_type, //.merge(parent.content_type), files differ
+                charset: child.charset.merge(parent.charset),
+                csrf: child.csrf.merge(parent.csrf),
+                strategy: child.strategy.merge(parent.strategy),
+                max_response_bytes: child.max_response_bytes.merge(parent.max_response_bytes),
+                oversize: child.oversize.merge(parent.oversize),
+                request_timeout: child.request_timeout.merge(parent.request_timeout),
+                timeout_mode: child.timeout_mode.merge(parent.timeout_mode),
             }),
         }
     }
@@ -370,6 +1432,31 @@ impl Mergeable for Option<CollectionConfig> {
                 name: child.name.merge(parent.name),
                 id_key: child.id_key.merge(parent.id_key),
                 id_type: child.id_type.merge(parent.id_type),
+                id_strategy: child.id_strategy.merge(parent.id_strategy),
+                refs: child.refs.merge(parent.refs),
+                on_delete: child.on_delete.merge(parent.on_delete),
+                defaults: child.defaults.merge(parent.defaults),
+                computed: child.computed.merge(parent.computed),
+                soft_delete: child.soft_delete.merge(parent.soft_delete),
+                case_insensitive: child.case_insensitive.merge(parent.case_insensitive),
+                links: child.links.merge(parent.links),
+                read_only: child.read_only.merge(parent.read_only),
+                transform: child.transform.merge(parent.transform),
+            }),
+        }
+    }
+}
+
+impl Mergeable for Option<TransformConfig> {
+    fn merge(self, parent: Self) -> Self {
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(TransformConfig {
+                rename: child.rename.merge(parent.rename),
+                map: child.map.merge(parent.map),
+                mask: child.mask.merge(parent.mask),
             }),
         }
     }
@@ -393,6 +1480,17 @@ impl Mergeable for Option<AuthConfig> {
                 login_endpoint: child.login_endpoint.merge(parent.login_endpoint),
                 logout_endpoint: child.logout_endpoint.merge(parent.logout_endpoint),
                 users_route: child.users_route.merge(parent.users_route),
+                password_endpoint: child.password_endpoint.merge(parent.password_endpoint),
+                register_endpoint: child.register_endpoint.merge(parent.register_endpoint),
+                me_endpoint: child.me_endpoint.merge(parent.me_endpoint),
+                max_failed_attempts: child.max_failed_attempts.merge(parent.max_failed_attempts),
+                lockout_minutes: child.lockout_minutes.merge(parent.lockout_minutes),
+                mfa_code: child.mfa_code.merge(parent.mfa_code),
+                mfa_secret: child.mfa_secret.merge(parent.mfa_secret),
+                mfa_endpoint: child.mfa_endpoint.merge(parent.mfa_endpoint),
+                mfa_challenge_minutes: child
+                    .mfa_challenge_minutes
+                    .merge(parent.mfa_challenge_minutes),
             }),
         }
     }
@@ -409,6 +1507,33 @@ impl Mergeable for Option<UploadConfig> {
                 download_endpoint: child.download_endpoint.merge(parent.download_endpoint),
                 list_files_endpoint: child.list_files_endpoint.merge(parent.list_files_endpoint),
                 temporary: child.temporary.merge(parent.temporary),
+                chunked: child.chunked.merge(parent.chunked),
+                max_chunk_size: child.max_chunk_size.merge(parent.max_chunk_size),
+                download_rate: child.download_rate.merge(parent.download_rate),
+                download_disposition: child
+                    .download_disposition
+                    .merge(parent.download_disposition),
+                retention: child.retention.merge(parent.retention),
+                scan: child.scan.merge(parent.scan),
+                scan_delay: child.scan_delay.merge(parent.scan_delay),
+                scan_infected: child.scan_infected.merge(parent.scan_infected),
+                scan_infected_percent: child
+                    .scan_infected_percent
+                    .merge(parent.scan_infected_percent),
+                scan_webhook: child.scan_webhook.merge(parent.scan_webhook),
+            }),
+        }
+    }
+}
+
+impl Mergeable for Option<GraphQLConfig> {
+    fn merge(self, parent: Self) -> Self {
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(parent)) => Some(parent),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(GraphQLConfig {
+                relay: child.relay.merge(parent.relay),
             }),
         }
     }
@@ -441,32 +1566,266 @@ impl Mergeable for Option<CollectionsConfig> {
     }
 }
 
-impl Mergeable for Option<String> {
+impl Mergeable for Option<VersioningConfig> {
     fn merge(self, parent: Self) -> Self {
-        if self.is_some() { self } else { parent }
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(VersioningConfig {
+                latest: child.latest.merge(parent.latest),
+                deprecated: child.deprecated.merge(parent.deprecated),
+                sunset: child.sunset.merge(parent.sunset),
+            }),
+        }
     }
 }
 
-impl Mergeable for Option<bool> {
+impl Mergeable for Option<TlsConfig> {
     fn merge(self, parent: Self) -> Self {
-        if self.is_some() { self } else { parent }
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(TlsConfig {
+                client_ca: child.client_ca.merge(parent.client_ca),
+                client_auth: child.client_auth.merge(parent.client_auth),
+            }),
+        }
     }
 }
 
-impl Mergeable for Option<u16> {
+impl Mergeable for Option<ProxyConfig> {
     fn merge(self, parent: Self) -> Self {
-        if self.is_some() { self } else { parent }
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(ProxyConfig {
+                record_folder: child.record_folder.merge(parent.record_folder),
+                rules: if child.rules.is_some() {
+                    child.rules
+                } else {
+                    parent.rules
+                },
+                diff: child.diff.merge(parent.diff),
+                anonymize: child.anonymize.merge(parent.anonymize),
+                anonymize_fields: child.anonymize_fields.merge(parent.anonymize_fields),
+                anonymize_secret: child.anonymize_secret.merge(parent.anonymize_secret),
+            }),
+        }
     }
 }
 
-impl Mergeable for Option<IdType> {
+impl Mergeable for Option<MqttConfig> {
     fn merge(self, parent: Self) -> Self {
-        if self.is_some() { self } else { parent }
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(MqttConfig {
+                port: child.port.merge(parent.port),
+                publish: if child.publish.is_some() {
+                    child.publish
+                } else {
+                    parent.publish
+                },
+                collect: if child.collect.is_some() {
+                    child.collect
+                } else {
+                    parent.collect
+                },
+            }),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
+impl Mergeable for Option<ScheduleConfig> {
+    fn merge(self, parent: Self) -> Self {
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(ScheduleConfig {
+                windows: if child.windows.is_some() {
+                    child.windows
+                } else {
+                    parent.windows
+                },
+            }),
+        }
+    }
+}
+
+impl Mergeable for Option<QuotaConfig> {
+    fn merge(self, parent: Self) -> Self {
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(QuotaConfig {
+                limit: child.limit.merge(parent.limit),
+                window: child.window.merge(parent.window),
+                key: child.key.merge(parent.key),
+                header: child.header.merge(parent.header),
+                path: child.path.merge(parent.path),
+            }),
+        }
+    }
+}
+
+impl Mergeable for Option<SamlConfig> {
+    fn merge(self, parent: Self) -> Self {
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(SamlConfig {
+                route: child.route.merge(parent.route),
+                entity_id: child.entity_id.merge(parent.entity_id),
+                acs_url: child.acs_url.merge(parent.acs_url),
+                audience: child.audience.merge(parent.audience),
+                key: child.key.merge(parent.key),
+                certificate: child.certificate.merge(parent.certificate),
+                assertion_minutes: child.assertion_minutes.merge(parent.assertion_minutes),
+                user: child.user.or(parent.user),
+            }),
+        }
+    }
+}
+
+impl Mergeable for Option<PublicConfig> {
+    fn merge(self, parent: Self) -> Self {
+        match (self, parent) {
+            (None, None) => None,
+            (None, Some(p)) => Some(p),
+            (Some(child), None) => Some(child),
+            (Some(child), Some(parent)) => Some(PublicConfig {
+                spa: child.spa.merge(parent.spa),
+                index: child.index.merge(parent.index),
+                cache_control: child.cache_control.merge(parent.cache_control),
+                index_cache_control: child.index_cache_control.merge(parent.index_cache_control),
+            }),
+        }
+    }
+}
+
+impl Mergeable for Option<String> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Vec<FolderMount>> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Vec<String>> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Vec<CookieConfig>> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl<V> Mergeable for Option<HashMap<String, V>> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<bool> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<u16> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Delay> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Hang> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<TimeoutMode> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Oversize> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Fault> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<Retention> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<OnDelete> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<OnConflict> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<ResponseStrategy> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<u32> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<u64> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+impl Mergeable for Option<IdType> {
+    fn merge(self, parent: Self) -> Self {
+        if self.is_some() { self } else { parent }
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
 
     #[test]
@@ -492,20 +1851,87 @@ mod tests {
         assert_eq!(merged.allowed_origin, Some("example.com".to_string()));
     }
 
+    #[test]
+    fn folder_mount_parses_prefix_and_folder() {
+        let mount = |prefix: Option<&str>, folder: &str| FolderMount {
+            prefix: prefix.map(str::to_string),
+            folder: folder.to_string(),
+        };
+
+        assert_eq!(
+            FolderMount::parse("api1:./mocks/service-a"),
+            mount(Some("/api1"), "./mocks/service-a")
+        );
+        assert_eq!(
+            FolderMount::parse("/billing/:mocks/b"),
+            mount(Some("/billing"), "mocks/b")
+        );
+        assert_eq!(FolderMount::parse("./mocks"), mount(None, "./mocks"));
+        assert_eq!(FolderMount::parse("C:\\mocks"), mount(None, "C:\\mocks"));
+    }
+
+    #[test]
+    fn mount_configs_prefix_each_folder_remap() {
+        let config = Config {
+            server: Some(ServerConfig {
+                folder: Some("mocks".into()),
+                mounts: Some(vec![FolderMount {
+                    prefix: Some("billing".into()),
+                    folder: "billing-mocks".into(),
+                }]),
+                ..Default::default()
+            }),
+            route: Some(RouteConfig {
+                remap: Some("/v1".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mounts = config
+            .mount_configs()
+            .into_iter()
+            .map(|config| {
+                let server = config.server.unwrap();
+                assert_eq!(server.mounts, None);
+                (server.folder.unwrap(), config.route.unwrap().remap.unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mounts,
+            [
+                ("mocks".to_string(), "/v1".to_string()),
+                ("billing-mocks".to_string(), "/billing/v1".to_string())
+            ]
+        );
+
+        let only_mounts = Config {
+            server: Some(ServerConfig {
+                mounts: config.server.unwrap().mounts,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(only_mounts.mount_configs().len(), 1);
+        assert_eq!(Config::default().mount_configs().len(), 1);
+    }
+
     #[test]
     fn test_route_config_merge() {
         let child = RouteConfig {
             delay: None,
             remap: Some("/api".into()),
             protect: None,
+            ..Default::default()
         };
         let parent = RouteConfig {
-            delay: Some(10),
+            delay: Some(Delay::Fixed(10)),
             remap: None,
             protect: Some(true),
+            ..Default::default()
         };
         let merged = Some(child.clone()).merge(Some(parent.clone())).unwrap();
-        assert_eq!(merged.delay, Some(10));
+        assert_eq!(merged.delay, Some(Delay::Fixed(10)));
         assert_eq!(merged.remap, Some("/api".to_string()));
         assert_eq!(merged.protect, Some(true));
     }
@@ -516,16 +1942,96 @@ mod tests {
             name: Some("child".into()),
             id_key: None,
             id_type: Some(IdType::Uuid),
+            id_strategy: None,
+            refs: Some(HashMap::from([("userId".into(), "users".into())])),
+            on_delete: None,
+            defaults: None,
+            computed: Some(HashMap::from([("updatedAt".into(), "{{now}}".into())])),
+            soft_delete: None,
+            case_insensitive: Some(true),
+            links: None,
+            read_only: None,
+            transform: Some(TransformConfig {
+                rename: Some(HashMap::from([("firstName".into(), "first_name".into())])),
+                map: None,
+                mask: None,
+            }),
         };
         let parent = CollectionConfig {
             name: None,
             id_key: Some("id".into()),
             id_type: Some(IdType::Int),
+            id_strategy: Some("usr_{{seq}}".into()),
+            refs: None,
+            on_delete: Some(OnDelete::Cascade),
+            defaults: Some(HashMap::from([("status".into(), "pending".into())])),
+            computed: None,
+            soft_delete: Some(true),
+            case_insensitive: Some(false),
+            links: Some(true),
+            read_only: Some(true),
+            transform: Some(TransformConfig {
+                rename: None,
+                map: None,
+                mask: Some(vec!["email".into()]),
+            }),
         };
         let merged = Some(child.clone()).merge(Some(parent.clone())).unwrap();
         assert_eq!(merged.name, Some("child".to_string()));
         assert_eq!(merged.id_key, Some("id".to_string()));
         assert_eq!(merged.id_type, Some(IdType::Uuid));
+        assert_eq!(merged.id_strategy, Some("usr_{{seq}}".to_string()));
+        assert_eq!(merged.refs, child.refs);
+        assert_eq!(merged.on_delete, Some(OnDelete::Cascade));
+        assert_eq!(merged.defaults, parent.defaults);
+        assert_eq!(merged.computed, child.computed);
+        assert_eq!(merged.soft_delete, Some(true));
+        assert_eq!(merged.case_insensitive, Some(true));
+        assert_eq!(merged.links, Some(true));
+        let transform = merged.transform.unwrap();
+        assert_eq!(transform.rename, child.transform.unwrap().rename);
+        assert_eq!(transform.mask, Some(vec!["email".to_string()]));
+    }
+
+    #[test]
+    fn test_collection_defaults_parse_from_toml() {
+        let config = Config::try_from(
+            r#"
+[collection.defaults]
+status = "pending"
+priority = 3
+tags = ["new"]
+
+[collection.computed]
+updatedAt = "{{now}}"
+"#,
+        )
+        .unwrap();
+        let collection = config.collection.unwrap();
+        let defaults = collection.defaults.unwrap();
+        assert_eq!(defaults["status"], Value::from("pending"));
+        assert_eq!(defaults["priority"], Value::from(3));
+        assert_eq!(defaults["tags"], serde_json::json!(["new"]));
+        assert_eq!(
+            collection.computed.unwrap()["updatedAt"],
+            Value::from("{{now}}")
+        );
+    }
+
+    #[test]
+    fn test_env_variables_are_replaced_before_parsing() {
+        let path = std::env::var("PATH").unwrap();
+        let config = Config::try_from(
+            r#"
+[server]
+port = ${RS_MOCK_SERVER_UNSET_PORT:-4520}
+folder = "${PATH}"
+"#,
+        )
+        .unwrap();
+        let server = config.server.unwrap();
+        assert_eq!(server.port, Some(4520));
+        assert_eq!(server.folder, Some(path));
     }
 
     #[test]
@@ -536,6 +2042,7 @@ mod tests {
                 name: Some("tok".into()),
                 id_key: Some("t".into()),
                 id_type: Some(IdType::Uuid),
+                ..Default::default()
             }),
             ..Default::default()
         };
@@ -546,6 +2053,7 @@ mod tests {
                 name: Some("parent_tok".into()),
                 id_key: None,
                 id_type: Some(IdType::Int),
+                ..Default::default()
             }),
             ..Default::default()
         };
@@ -565,18 +2073,47 @@ mod tests {
             download_endpoint: Some("/dl".into()),
             list_files_endpoint: None,
             temporary: Some(true),
+            chunked: None,
+            max_chunk_size: Some(1024),
+            download_rate: Some(64),
+            download_disposition: None,
+            retention: None,
+            scan: Some(true),
+            scan_delay: None,
+            scan_infected: Some(vec!["*.exe".into()]),
+            scan_infected_percent: None,
+            scan_webhook: None,
         };
         let parent = UploadConfig {
             upload_endpoint: Some("/up".into()),
             download_endpoint: None,
             list_files_endpoint: Some("/list".into()),
             temporary: Some(false),
+            chunked: Some(true),
+            max_chunk_size: None,
+            download_rate: Some(128),
+            download_disposition: Some("inline".into()),
+            retention: Some(Retention::UntilShutdown),
+            scan: None,
+            scan_delay: Some(500),
+            scan_infected: Some(vec!["eicar*".into()]),
+            scan_infected_percent: Some(10),
+            scan_webhook: None,
         };
         let merged = Some(child.clone()).merge(Some(parent.clone())).unwrap();
         assert_eq!(merged.upload_endpoint, Some("/up".into()));
         assert_eq!(merged.download_endpoint, Some("/dl".into()));
         assert_eq!(merged.list_files_endpoint, Some("/list".into()));
         assert_eq!(merged.temporary, Some(true));
+        assert_eq!(merged.chunked, Some(true));
+        assert_eq!(merged.max_chunk_size, Some(1024));
+        assert_eq!(merged.download_rate, Some(64));
+        assert_eq!(merged.download_disposition, Some("inline".into()));
+        assert_eq!(merged.retention, Some(Retention::UntilShutdown));
+        assert_eq!(merged.scan, Some(true));
+        assert_eq!(merged.scan_delay, Some(500));
+        assert_eq!(merged.scan_infected, Some(vec!["*.exe".into()]));
+        assert_eq!(merged.scan_infected_percent, Some(10));
     }
 
     #[test]
@@ -595,6 +2132,14 @@ mod tests {
             upload: None,
             collections: None,
             schemas: None,
+            versioning: None,
+            graphql: None,
+            proxy: None,
+            public: None,
+            quota: None,
+            saml: None,
+            schedule: None,
+            mqtt: None,
         };
         let parent = Config {
             server: Some(ServerConfig {
@@ -605,15 +2150,24 @@ mod tests {
                 ..Default::default()
             }),
             route: Some(RouteConfig {
-                delay: Some(5),
+                delay: Some(Delay::Fixed(5)),
                 remap: None,
                 protect: Some(false),
+                ..Default::default()
             }),
             collection: None,
             auth: None,
             upload: None,
             collections: None,
             schemas: None,
+            versioning: None,
+            graphql: None,
+            proxy: None,
+            public: None,
+            quota: None,
+            saml: None,
+            schedule: None,
+            mqtt: None,
         };
         let merged_opt = Some(child.clone()).merge(Some(parent.clone()));
         let merged = merged_opt.unwrap();
@@ -624,9 +2178,10 @@ mod tests {
         assert_eq!(
             merged.route,
             Some(RouteConfig {
-                delay: Some(5),
+                delay: Some(Delay::Fixed(5)),
                 remap: None,
-                protect: Some(false)
+                protect: Some(false),
+                ..Default::default()
             })
         );
     }
@@ -636,15 +2191,24 @@ mod tests {
         let child = Config {
             server: None,
             route: Some(RouteConfig {
-                delay: Some(2),
+                delay: Some(Delay::Fixed(2)),
                 remap: None,
                 protect: None,
+                ..Default::default()
             }),
             collection: None,
             auth: None,
             upload: None,
             collections: None,
             schemas: None,
+            versioning: None,
+            graphql: None,
+            proxy: None,
+            public: None,
+            quota: None,
+            saml: None,
+            schedule: None,
+            mqtt: None,
         };
         let parent = Config {
             server: None,
@@ -652,16 +2216,25 @@ mod tests {
                 delay: None,
                 remap: Some("/p".into()),
                 protect: Some(true),
+                ..Default::default()
             }),
             collection: None,
             auth: None,
             upload: None,
             collections: None,
             schemas: None,
+            versioning: None,
+            graphql: None,
+            proxy: None,
+            public: None,
+            quota: None,
+            saml: None,
+            schedule: None,
+            mqtt: None,
         };
         let merged = child.merge(Some(parent));
         let route = merged.route.unwrap();
-        assert_eq!(route.delay, Some(2));
+        assert_eq!(route.delay, Some(Delay::Fixed(2)));
         assert!(route.remap.is_none());
         assert_eq!(route.protect, Some(true));
     }
@@ -698,6 +2271,47 @@ mod tests {
         assert_eq!(config.route.unwrap().protect, Some(true));
     }
 
+    #[test]
+    fn test_config_store_overlays_profile_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let write = |name: &str, content: &str| {
+            std::fs::write(temp_dir.path().join(name), content).unwrap();
+        };
+        write(
+            "config.toml",
+            "[route]\ndelay = 10\nprotect = true\n[auth]\nusername_field = \"email\"\n",
+        );
+        write(
+            "config.staging.toml",
+            "[route]\ndelay = 500\n[auth]\njwt_secret = \"staging\"\n",
+        );
+        write("get.staging.toml", "[route]\ndelay = 50\n");
+
+        let base = ConfigStore::try_from_dir(temp_dir.path(), None).unwrap();
+        assert_eq!(
+            base.get("config").unwrap().route.unwrap().delay,
+            Some(Delay::Fixed(10))
+        );
+        assert!(base.get("get").is_none());
+
+        let staging = ConfigStore::try_from_dir(temp_dir.path(), Some("staging")).unwrap();
+        let config = staging.get("config").unwrap();
+        let route = config.route.unwrap();
+        assert_eq!(
+            (route.delay, route.protect),
+            (Some(Delay::Fixed(500)), Some(true))
+        );
+        let auth = config.auth.unwrap();
+        assert_eq!(auth.username_field.as_deref(), Some("email"));
+        assert_eq!(auth.jwt_secret.as_deref(), Some("staging"));
+        assert_eq!(
+            staging.get("get").unwrap().route.unwrap().delay,
+            Some(Delay::Fixed(50))
+        );
+        assert!(staging.get("config.staging").is_none());
+        assert_eq!(staging.profile(), Some("staging"));
+    }
+
     #[test]
     fn test_schemas_config_deserializes_and_merges() {
         let config = Config::try_from(
@@ -752,4 +2366,201 @@ mod tests {
         let merged = child.merge(parent).unwrap();
         assert_eq!(merged.folder.as_deref(), Some("{collections}"));
     }
+
+    #[test]
+    fn test_route_oversize_deserializes_and_merges() {
+        let oversize = |value: &str| {
+            Config::try_from(format!("[route]\noversize = {}", value).as_str())
+                .map(|config| config.route.unwrap().oversize)
+        };
+        assert_eq!(oversize(r#""truncate""#).unwrap(), Some(Oversize::Truncate));
+        assert_eq!(
+            oversize(r#""reject""#).unwrap(),
+            Some(Oversize::Reject(413))
+        );
+        assert_eq!(oversize("500").unwrap(), Some(Oversize::Reject(500)));
+        assert!(oversize("200").is_err());
+        assert!(oversize(r#""drop""#).is_err());
+
+        let parent = Some(RouteConfig {
+            max_response_bytes: Some(1024),
+            oversize: Some(Oversize::Reject(500)),
+            ..Default::default()
+        });
+        let merged = None.merge(parent.clone()).unwrap();
+        assert_eq!(merged.max_response_bytes, Some(1024));
+        assert_eq!(merged.oversize, Some(Oversize::Reject(500)));
+        let child = Some(RouteConfig {
+            max_response_bytes: Some(16),
+            ..Default::default()
+        });
+        let merged = child.merge(parent).unwrap();
+        assert_eq!(merged.max_response_bytes, Some(16));
+        assert_eq!(merged.oversize, Some(Oversize::Reject(500)));
+    }
+
+    #[test]
+    fn test_collection_on_delete_rejects_unknown_values() {
+        let config = Config::try_from("[collection]\non_delete = \"cascade\"").unwrap();
+        assert_eq!(
+            config.collection.unwrap().on_delete,
+            Some(OnDelete::Cascade)
+        );
+        assert!(Config::try_from("[collection]\non_delete = \"cascde\"").is_err());
+    }
+
+    #[test]
+    fn test_route_request_timeout_deserializes_and_merges() {
+        let config = Config::try_from("[route]\nrequest_timeout = 5\ntimeout_mode = \"gateway\"")
+            .unwrap()
+            .route
+            .unwrap();
+        assert_eq!(config.request_timeout, Some(5));
+        assert_eq!(config.timeout_mode, Some(TimeoutMode::Gateway));
+        assert!(Config::try_from("[route]\ntimeout_mode = \"server\"").is_err());
+
+        let child = Some(RouteConfig {
+            request_timeout: Some(2),
+            ..Default::default()
+        });
+        let merged = child.merge(Some(config)).unwrap();
+        assert_eq!(merged.request_timeout, Some(2));
+        assert_eq!(merged.timeout_mode, Some(TimeoutMode::Gateway));
+    }
+
+    #[test]
+    fn test_route_hang_deserializes_and_merges() {
+        let hang = |value: &str| {
+            Config::try_from(format!("[route]\nhang = {}", value).as_str())
+                .map(|config| config.route.unwrap().hang)
+        };
+        assert_eq!(hang(r#""infinite""#).unwrap(), Some(Hang::Infinite));
+        assert_eq!(hang("30").unwrap(), Some(Hang::Seconds(30)));
+        assert!(hang(r#""forever""#).is_err());
+
+        let parent = Some(RouteConfig {
+            hang: Some(Hang::Seconds(5)),
+            ..Default::default()
+        });
+        assert_eq!(
+            None.merge(parent.clone()).unwrap().hang,
+            Some(Hang::Seconds(5))
+        );
+        let child = Some(RouteConfig {
+            hang: Some(Hang::Infinite),
+            ..Default::default()
+        });
+        assert_eq!(child.merge(parent).unwrap().hang, Some(Hang::Infinite));
+    }
+
+    #[test]
+    fn test_upload_retention_parses_durations() {
+        let retention = |value: &str| {
+            Config::try_from(format!("[upload]\nretention = \"{}\"", value).as_str())
+                .map(|config| config.upload.unwrap().retention.unwrap())
+        };
+        assert_eq!(
+            retention("15m").unwrap(),
+            Retention::For(std::time::Duration::from_secs(900))
+        );
+        assert_eq!(retention("90s").unwrap().to_string(), "90s");
+        assert_eq!(
+            retention("until-shutdown").unwrap(),
+            Retention::UntilShutdown
+        );
+        assert!(retention("0m").is_err());
+        assert!(retention("15").is_err());
+        assert!(retention("soon").is_err());
+    }
+
+    #[test]
+    fn test_route_strategy_parses_weights() {
+        let strategy = |value: &str| {
+            Config::try_from(format!("[route]\nstrategy = \"{}\"", value).as_str())
+                .map(|config| config.route.unwrap().strategy.unwrap())
+        };
+        assert_eq!(
+            strategy("round-robin").unwrap(),
+            ResponseStrategy::RoundRobin
+        );
+        assert_eq!(strategy("random").unwrap(), ResponseStrategy::Random);
+        let weighted = strategy("weighted(90%, 10%)").unwrap();
+        assert_eq!(weighted, ResponseStrategy::Weighted(vec![90, 10]));
+        assert_eq!(weighted.to_string(), "weighted(90,10)");
+        assert!(strategy("weighted(0,0)").is_err());
+        assert!(strategy("weighted(a,b)").is_err());
+        assert!(strategy("fastest").is_err());
+    }
+
+    #[test]
+    fn test_route_delay_accepts_distributions() {
+        let delay = |value: &str| {
+            Config::try_from(format!("[route]\ndelay = {}", value).as_str())
+                .map(|config| config.route.unwrap().delay.unwrap())
+        };
+        assert_eq!(delay("50").unwrap(), Delay::Fixed(50));
+        assert_eq!(delay("70000").unwrap(), Delay::Fixed(70_000));
+        assert_eq!(delay(r#""70000""#).unwrap(), Delay::Fixed(70_000));
+        assert_eq!(
+            delay(r#""uniform(50, 500)""#).unwrap(),
+            Delay::Uniform { min: 50, max: 500 }
+        );
+        assert_eq!(
+            delay(r#""normal(200,50)""#).unwrap(),
+            Delay::Normal {
+                mean: 200.0,
+                std_dev: 50.0
+            }
+        );
+        let percentiles = delay(r#""p50=100, p95=400, p99=1200""#).unwrap();
+        assert_eq!(
+            percentiles,
+            Delay::Percentiles {
+                p50: 100,
+                p95: 400,
+                p99: 1200
+            }
+        );
+        assert_eq!(percentiles.to_string(), "p50=100,p95=400,p99=1200");
+        assert!(delay(r#""uniform(500,50)""#).is_err());
+        assert!(delay(r#""p50=400,p95=100,p99=1200""#).is_err());
+        assert!(delay(r#""poisson(3)""#).is_err());
+
+        let config = Config::try_from(
+            r#"[route]
+delay = "normal(200,50)""#,
+        )
+        .unwrap();
+        let toml = toml::to_string(&config).unwrap();
+        assert!(toml.contains(r#"delay = "normal(200,50)""#), "{}", toml);
+    }
+
+    #[test]
+    fn test_route_cookies_deserialize_and_merge() {
+        let config = Config::try_from(
+            "[[route.cookies]]\nname = \"ab\"\nvalue = \"b\"\nmax_age = 60\nsame_site = \"Lax\"",
+        )
+        .unwrap();
+        let cookies = config.route.clone().unwrap().cookies.unwrap();
+        assert_eq!(
+            cookies,
+            vec![CookieConfig {
+                name: "ab".to_string(),
+                value: "b".to_string(),
+                max_age: Some(60),
+                same_site: Some("Lax".to_string()),
+                ..Default::default()
+            }]
+        );
+
+        assert_eq!(
+            None.merge(config.route.clone()).unwrap().cookies,
+            Some(cookies)
+        );
+        let child = Some(RouteConfig {
+            cookies: Some(vec![]),
+            ..Default::default()
+        });
+        assert_eq!(child.merge(config.route).unwrap().cookies, Some(vec![]));
+    }
 }
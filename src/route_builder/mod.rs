@@ -8,6 +8,8 @@ pub mod route;
 pub mod route_auth;
 /// Static file route discovery.
 pub mod route_basic;
+/// Echo route discovery.
+pub mod route_echo;
 /// GraphQL route discovery.
 pub mod route_graphql;
 /// Directory traversal and route ordering.
@@ -18,27 +20,31 @@ pub mod route_params;
 pub mod route_public;
 /// REST collection route discovery.
 pub mod route_rest;
+/// Pattern-based route rewrites.
+pub mod route_rewrite;
+/// S3-compatible storage directory route discovery.
+pub mod route_s3;
 /// Upload directory route discovery.
 pub mod route_upload;
+/// API version prefixes discovered from `vN` folders.
+pub mod route_version;
 
 use axum::routing::MethodRouter;
 use http::Method;
 pub use route::*;
 pub use route_auth::*;
 pub use route_basic::*;
+pub use route_echo::*;
 pub use route_params::*;
 pub use route_public::*;
 pub use route_rest::*;
+pub use route_rewrite::*;
+pub use route_s3::*;
 pub use route_upload::*;
+pub use route_version::*;
 
 use crate::app::App;
 
-/// Prints a human-readable route registration message.
-pub trait PrintRoute {
-    /// Prints this route to standard output.
-    fn println(&self);
-}
-
 /// Registers generated routes on an application router.
 pub trait RouteRegistrator {
     /// Adds a route with optional auth protection and home-page options.
@@ -1,12 +1,12 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, ffi::OsString};
 
 use fosk::IdType;
 
 use crate::{
     app::App,
     route_builder::{
-        PrintRoute, RouteAuth, RouteBasic, RouteGenerator, RouteParams, RoutePublic, RouteRest,
-        RouteUpload, route_graphql::RouteGraphQL,
+        RouteAuth, RouteBasic, RouteEcho, RouteGenerator, RouteParams, RoutePublic, RouteRest,
+        RouteS3, RouteUpload, config::Delay, route_graphql::RouteGraphQL,
     },
 };
 
@@ -39,6 +39,10 @@ pub enum Route {
     Public(RoutePublic),
     /// File upload route set.
     Upload(RouteUpload),
+    /// S3-compatible object storage route set.
+    S3(RouteS3),
+    /// Route answering with the received request.
+    Echo(RouteEcho),
 }
 
 impl Route {
@@ -74,6 +78,16 @@ impl Route {
                 return route;
             }
 
+            let route = RouteS3::try_parse(route_params.clone());
+            if route.is_some() {
+                return route;
+            }
+
+            let route = RouteEcho::try_parse(route_params.clone());
+            if route.is_some() {
+                return route;
+            }
+
             return Route::None;
         }
 
@@ -87,6 +101,11 @@ impl Route {
             return route;
         }
 
+        let route = RouteEcho::try_parse(route_params.clone());
+        if route.is_some() {
+            return route;
+        }
+
         let route = RouteBasic::try_parse(route_params.clone());
         if route.is_some() {
             return route;
@@ -95,11 +114,61 @@ impl Route {
         Route::None
     }
 
-    /// Registers this route and prints its mapping when it is present.
-    pub fn make_routes_and_print(&self, app: &mut App) {
-        if self.is_some() {
-            self.make_routes(app);
-            self.println();
+    /// Returns the file or directory this route was parsed from.
+    pub fn source_path(&self) -> Option<&OsString> {
+        match self {
+            Route::None => None,
+            Route::Auth(route_auth) => Some(&route_auth.path),
+            Route::Basic(route_basic) => Some(&route_basic.path),
+            Route::Rest(route_rest) => Some(&route_rest.path),
+            Route::GraphQL(route_graphql) => Some(&route_graphql.path),
+            Route::Public(route_public) => Some(&route_public.path),
+            Route::Upload(route_upload) => Some(&route_upload.path),
+            Route::S3(route_s3) => Some(&route_s3.path),
+            Route::Echo(route_echo) => Some(&route_echo.path),
+        }
+    }
+
+    /// Returns the base route path for route kinds that have one.
+    pub fn route_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Route::None => None,
+            Route::Auth(route_auth) => Some(&mut route_auth.route),
+            Route::Basic(route_basic) => Some(&mut route_basic.route),
+            Route::Rest(route_rest) => Some(&mut route_rest.route),
+            Route::GraphQL(route_graphql) => Some(&mut route_graphql.route),
+            Route::Public(route_public) => Some(&mut route_public.route),
+            Route::Upload(route_upload) => Some(&mut route_upload.route),
+            Route::S3(route_s3) => Some(&mut route_s3.route),
+            Route::Echo(route_echo) => Some(&mut route_echo.route),
+        }
+    }
+
+    /// Returns the name of the route kind, such as `basic` or `rest`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Route::None => "none",
+            Route::Auth(_) => "auth",
+            Route::Basic(_) => "basic",
+            Route::Rest(_) => "rest",
+            Route::GraphQL(_) => "graphql",
+            Route::Public(_) => "public",
+            Route::Upload(_) => "upload",
+            Route::S3(_) => "s3",
+            Route::Echo(_) => "echo",
+        }
+    }
+
+    /// Returns the response delay in milliseconds for route kinds that support one.
+    pub fn delay(&self) -> Option<Delay> {
+        match self {
+            Route::Auth(route_auth) => route_auth.delay,
+            Route::Rest(route_rest) => route_rest.delay,
+            Route::GraphQL(route_graphql) => route_graphql.delay,
+            Route::Upload(route_upload) => route_upload.delay,
+            Route::S3(route_s3) => route_s3.delay,
+            Route::Echo(route_echo) => route_echo.delay,
+            Route::None | Route::Basic(_) | Route::Public(_) => None,
         }
     }
 }
@@ -114,20 +183,8 @@ impl RouteGenerator for Route {
             Route::Rest(route_rest) => route_rest.make_routes(app),
             Route::GraphQL(route_graphql) => route_graphql.make_routes(app),
             Route::Upload(route_upload) => route_upload.make_routes(app),
-        }
-    }
-}
-
-impl PrintRoute for Route {
-    fn println(&self) {
-        match self {
-            Route::None => (),
-            Route::Auth(route_auth) => route_auth.println(),
-            Route::Basic(route_basic) => route_basic.println(),
-            Route::Public(route_public) => route_public.println(),
-            Route::Rest(route_rest) => route_rest.println(),
-            Route::GraphQL(route_graphql) => route_graphql.println(),
-            Route::Upload(route_upload) => route_upload.println(),
+            Route::S3(route_s3) => route_s3.make_routes(app),
+            Route::Echo(route_echo) => route_echo.make_routes(app),
         }
     }
 }
@@ -143,6 +200,8 @@ impl PartialOrd for Route {
             Route::GraphQL(_) => 4,
             Route::Public(_) => 5,
             Route::Upload(_) => 6,
+            Route::S3(_) => 7,
+            Route::Echo(_) => 8,
         };
         let other_order = match other {
             Route::None => 0,
@@ -152,6 +211,8 @@ impl PartialOrd for Route {
             Route::GraphQL(_) => 4,
             Route::Public(_) => 5,
             Route::Upload(_) => 6,
+            Route::S3(_) => 7,
+            Route::Echo(_) => 8,
         };
 
         match self_order.cmp(&other_order) {
@@ -167,6 +228,8 @@ impl PartialOrd for Route {
                     (Route::Rest(a), Route::Rest(b)) => a.path.partial_cmp(&b.path),
                     (Route::Public(a), Route::Public(b)) => a.path.partial_cmp(&b.path),
                     (Route::Upload(a), Route::Upload(b)) => a.path.partial_cmp(&b.path),
+                    (Route::S3(a), Route::S3(b)) => a.path.partial_cmp(&b.path),
+                    (Route::Echo(a), Route::Echo(b)) => a.path.partial_cmp(&b.path),
                     _ => unreachable!(),
                 }
             }
@@ -261,6 +324,11 @@ mod tests {
         let route = Route::try_parse(&route_params);
         assert!(matches!(route, Route::Upload(_)));
 
+        // Test S3 storage directory - should use {s3} pattern
+        let route_params = create_test_route_params("{s3}-storage", true, false);
+        let route = Route::try_parse(&route_params);
+        assert!(matches!(route, Route::S3(_)));
+
         // Regular "upload" directory without braces should return None
         let route_params = create_test_route_params("upload", true, false);
         let route = Route::try_parse(&route_params);
@@ -8,7 +8,7 @@ use crate::{
     app::App,
     handlers::build_auth_routes,
     route_builder::{
-        CollectionConfig, PrintRoute, Route, RouteGenerator, route_params::RouteParams,
+        CollectionConfig, Route, RouteGenerator, config::Delay, route_params::RouteParams,
     },
 };
 
@@ -33,6 +33,18 @@ pub static LOGIN_ENDPOINT: &str = "/login";
 pub static LOGOUT_ENDPOINT: &str = "/logout";
 /// Default route for user management.
 pub static USERS_ENDPOINT: &str = "/users";
+/// Default password change endpoint suffix.
+pub static PASSWORD_ENDPOINT: &str = "/password";
+/// Default sign-up endpoint suffix.
+pub static REGISTER_ENDPOINT: &str = "/register";
+/// Default endpoint suffix of the logged-in user's profile.
+pub static ME_ENDPOINT: &str = "/me";
+/// Default MFA verification endpoint suffix.
+pub static MFA_ENDPOINT: &str = "/mfa/verify";
+/// Default minutes an MFA challenge stays valid.
+pub static MFA_CHALLENGE_MINUTES: u64 = 5;
+/// Default minutes a username stays locked after too many failed logins.
+pub static LOCKOUT_MINUTES: u64 = 15;
 
 /// Authentication route set generated from a `{auth}` mock file.
 #[derive(Debug, Clone, PartialEq)]
@@ -42,11 +54,17 @@ pub struct RouteAuth {
     /// Base auth route.
     pub route: String,
     /// Optional response delay in milliseconds.
-    pub delay: Option<u16>,
+    pub delay: Option<Delay>,
     /// Login endpoint suffix.
     pub login_endpoint: String,
     /// Logout endpoint suffix.
     pub logout_endpoint: String,
+    /// Password change endpoint suffix.
+    pub password_endpoint: String,
+    /// Sign-up endpoint suffix.
+    pub register_endpoint: String,
+    /// Profile endpoint suffix of the logged-in user.
+    pub me_endpoint: String,
     /// Route that exposes the users collection.
     pub users_route: String,
     /// Token storage collection configuration.
@@ -65,6 +83,18 @@ pub struct RouteAuth {
     pub cookie_name: String,
     /// Whether user passwords are stored encrypted.
     pub encrypt_password: bool,
+    /// Consecutive failed logins that lock a username.
+    pub max_failed_attempts: Option<u32>,
+    /// Minutes a locked username stays locked.
+    pub lockout_minutes: u64,
+    /// Static code of the MFA login step.
+    pub mfa_code: Option<String>,
+    /// Base32 TOTP secret of the MFA login step.
+    pub mfa_secret: Option<String>,
+    /// MFA verification endpoint suffix.
+    pub mfa_endpoint: String,
+    /// Minutes an MFA challenge stays valid.
+    pub mfa_challenge_minutes: u64,
 }
 
 impl RouteAuth {
@@ -87,6 +117,13 @@ impl RouteAuth {
                 logout_endpoint: auth_config
                     .logout_endpoint
                     .unwrap_or(LOGOUT_ENDPOINT.into()),
+                password_endpoint: auth_config
+                    .password_endpoint
+                    .unwrap_or(PASSWORD_ENDPOINT.into()),
+                register_endpoint: auth_config
+                    .register_endpoint
+                    .unwrap_or(REGISTER_ENDPOINT.into()),
+                me_endpoint: auth_config.me_endpoint.unwrap_or(ME_ENDPOINT.into()),
                 users_route: auth_config
                     .users_route
                     .unwrap_or(format!("{}{}", route, USERS_ENDPOINT)),
@@ -106,6 +143,14 @@ impl RouteAuth {
                 cookie_name: auth_config.cookie_name.unwrap_or(COOKIE_NAME.into()),
                 jwt_secret: auth_config.jwt_secret.unwrap_or(JWT_SECRET.into()),
                 encrypt_password: auth_config.encrypt_password.unwrap_or(false),
+                max_failed_attempts: auth_config.max_failed_attempts.filter(|max| *max > 0),
+                lockout_minutes: auth_config.lockout_minutes.unwrap_or(LOCKOUT_MINUTES),
+                mfa_code: auth_config.mfa_code,
+                mfa_secret: auth_config.mfa_secret,
+                mfa_endpoint: auth_config.mfa_endpoint.unwrap_or(MFA_ENDPOINT.into()),
+                mfa_challenge_minutes: auth_config
+                    .mfa_challenge_minutes
+                    .unwrap_or(MFA_CHALLENGE_MINUTES),
             };
 
             return Route::Auth(Box::new(route_auth));
@@ -121,19 +166,6 @@ impl RouteGenerator for RouteAuth {
     }
 }
 
-impl PrintRoute for RouteAuth {
-    fn println(&self) {
-        println!(
-            "✔️ Built AUTH route for {}{}",
-            self.route, self.login_endpoint
-        );
-        println!(
-            "✔️ Built logout routes for {}{}",
-            self.route, self.logout_endpoint
-        );
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,6 +225,9 @@ mod tests {
             delay: None,
             login_endpoint: "/login".to_string(),
             logout_endpoint: "/logout".to_string(),
+            password_endpoint: "/password".to_string(),
+            register_endpoint: "/register".to_string(),
+            me_endpoint: "/me".to_string(),
             users_route: "/auth-test/users".to_string(),
             token_collection: CollectionConfig {
                 name: "auth_test_tokens".to_string(),
@@ -210,10 +245,15 @@ mod tests {
             jwt_secret: "secret".to_string(),
             cookie_name: "auth_token".to_string(),
             encrypt_password: false,
+            max_failed_attempts: None,
+            lockout_minutes: 15,
+            mfa_code: None,
+            mfa_secret: None,
+            mfa_endpoint: "/mfa/verify".to_string(),
+            mfa_challenge_minutes: 5,
         };
         let mut app = App::default();
         route_auth.make_routes(&mut app);
-        route_auth.println();
         assert!(
             app.pages
                 .lock()
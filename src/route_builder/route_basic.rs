@@ -1,13 +1,20 @@
-use std::{ffi::OsString, fmt::Display};
+use std::{ffi::OsString, fmt::Display, path::Path, sync::Arc, time::Duration};
 
+use axum::routing::MethodRouter;
 use http::Method;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
 use crate::{
-    handlers::build_method_router,
+    handlers::{
+        Charset, after_call_router, build_method_router, build_query_variant_router,
+        build_sequence_router, cache_router, constrain_param, content_type_router, fault_router,
+        hang_router, set_cookies_router, size_limit_router, timeout_router,
+    },
     route_builder::{
-        PrintRoute, Route, RouteGenerator, RouteRegistrator, method_from_str,
+        Route, RouteGenerator, RouteRegistrator,
+        config::{AfterCall, CookieConfig, Fault, Hang, Oversize, ResponseStrategy, TimeoutMode},
+        method_from_str,
         route_params::RouteParams,
     },
 };
@@ -37,6 +44,11 @@ pub enum SubRoute {
     Range(u32, u32),
     /// Static path segment.
     Static(String),
+    /// Catch-all `{*name}` segment matching the rest of the path, from `{**}` or `{*name}`.
+    Wildcard(String),
+    /// Dynamic `{name}` segment that only matches values satisfying a regex,
+    /// from `{name:regex}`.
+    Constrained(String, String),
 }
 
 impl SubRoute {
@@ -50,6 +62,15 @@ impl SubRoute {
         if pattern == "id" {
             return Self::Id;
         }
+        if let Some(name) = wildcard_name(pattern) {
+            return Self::Wildcard(name);
+        }
+        if let Some((name, regex)) = pattern.split_once(':')
+            && is_param_name(name)
+            && !regex.is_empty()
+        {
+            return Self::Constrained(name.to_string(), regex.to_string());
+        }
 
         if pattern.contains('-')
             && let Some((start_str, end_str)) = pattern.split_once('-')
@@ -69,10 +90,133 @@ impl Display for SubRoute {
             SubRoute::Id => write!(f, "/{{id}}"),
             SubRoute::Static(value) => write!(f, "/{{{}}}", value),
             SubRoute::Range(start, end) => write!(f, "/{{{}-{}}}", start, end),
+            SubRoute::Wildcard(name) => write!(f, "/{{*{}}}", name),
+            SubRoute::Constrained(name, regex) => write!(f, "/{{{}:{}}}", name, regex),
         }
     }
 }
 
+/// Returns the parameter name of a catch-all descriptor: `path` for `**`,
+/// `name` for `*name`.
+fn wildcard_name(pattern: &str) -> Option<String> {
+    match pattern.strip_prefix('*')? {
+        "*" => Some("path".to_string()),
+        name if is_param_name(name) => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+fn is_param_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+/// Compiles the regex of a `{name:regex}` descriptor, anchored to the whole segment.
+fn constraint_regex(regex: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", regex))
+}
+
+/// Prefix of a condition on a request cookie, as in `get?cookie:ab=b.json`.
+const COOKIE_CONDITION: &str = "cookie:";
+
+/// Mock file served when the request carries the listed query parameters and
+/// cookies, parsed from a `?name=value&other&cookie:name=value` filename suffix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryVariant {
+    /// Required query parameters, and cookies when prefixed with `cookie:`;
+    /// a `None` value accepts any value.
+    pub query: Vec<(String, Option<String>)>,
+    /// Source mock file path.
+    pub path: OsString,
+    /// Files served on successive calls, when the variant is a sequence.
+    pub sequence: Vec<SequenceFile>,
+}
+
+impl QueryVariant {
+    /// Returns true when `query` and `cookies` carry every required parameter.
+    pub fn matches(&self, query: &[(String, String)], cookies: &[(String, String)]) -> bool {
+        self.query.iter().all(|(name, expected)| {
+            let (name, pairs) = match name.strip_prefix(COOKIE_CONDITION) {
+                Some(name) => (name, cookies),
+                None => (name.as_str(), query),
+            };
+            pairs.iter().any(|(key, value)| {
+                key == name && expected.as_ref().is_none_or(|expected| expected == value)
+            })
+        })
+    }
+}
+
+/// Position of a file in a response sequence, parsed from the part between
+/// the file stem and the extension, such as `get.2.json` or `get.rest.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SequenceStep {
+    /// Served on the numbered call, starting at `1`.
+    Call(u32),
+    /// Served once the numbered calls are exhausted.
+    Rest,
+}
+
+impl SequenceStep {
+    /// Parses the step of a `get.1.json` or `get.rest.json` file name.
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let mut parts = file_name.split('.').skip(1);
+        let step = parts.next()?;
+        parts.next()?;
+        match step {
+            "rest" => Some(Self::Rest),
+            _ if !step.is_empty() && step.bytes().all(|byte| byte.is_ascii_digit()) => {
+                step.parse().ok().filter(|call| *call > 0).map(Self::Call)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for SequenceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceStep::Call(call) => write!(f, "call {}", call),
+            SequenceStep::Rest => write!(f, "later calls"),
+        }
+    }
+}
+
+/// Returns true for a `get.timeout.json` file, which never answers.
+fn is_timeout_file(file_name: &str) -> bool {
+    let mut parts = file_name.split('.').skip(1);
+    parts.next() == Some("timeout") && parts.next().is_some()
+}
+
+/// Mock file served at one step of a response sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceFile {
+    /// Calls the file is served on.
+    pub step: SequenceStep,
+    /// Source mock file path.
+    pub path: OsString,
+}
+
+/// Splits a `get?status=active` file stem into its route part and required
+/// query parameters. `~` may stand for the `?`, which Windows refuses in
+/// file names.
+fn split_query(file_stem: &str) -> (&str, Vec<(String, Option<String>)>) {
+    let Some((file_stem, query)) = file_stem.split_once(['?', '~']) else {
+        return (file_stem, vec![]);
+    };
+    let query = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| match param.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (param.to_string(), None),
+        })
+        .collect();
+    (file_stem, query)
+}
+
 /// Static file-backed route generated from a mock file.
 #[derive(Debug, Clone, PartialEq)]
 pub struct RouteBasic {
@@ -86,6 +230,40 @@ pub struct RouteBasic {
     pub sub_route: SubRoute,
     /// Whether this route requires auth middleware.
     pub is_protected: bool,
+    /// JSON Schema file the served responses must match.
+    pub response_schema: Option<String>,
+    /// Query parameters a request must carry to receive this file.
+    pub query: Vec<(String, Option<String>)>,
+    /// Files of the same route selected by other query parameters.
+    pub variants: Vec<QueryVariant>,
+    /// Sequence step of this file, from a `get.1.json` or `get.rest.json` name.
+    pub step: Option<SequenceStep>,
+    /// Other files of the sequence this file belongs to.
+    pub sequence: Vec<SequenceFile>,
+    /// Holds requests open without answering, from `hang` or a `get.timeout.json` name.
+    pub hang: Option<Hang>,
+    /// Breaks the connection or the response body of every request.
+    pub fault: Option<Fault>,
+    /// Cookies set on every response.
+    pub cookies: Vec<CookieConfig>,
+    /// Route called after every successful response.
+    pub after: Option<AfterCall>,
+    /// Seconds a rendered response is reused.
+    pub cache_ttl: Option<u64>,
+    /// Media type sent instead of the inferred one.
+    pub content_type: Option<String>,
+    /// Charset declared on the content type and used to encode text bodies.
+    pub charset: Option<String>,
+    /// How the files of a sequence are picked on each call.
+    pub strategy: Option<ResponseStrategy>,
+    /// Largest response body in bytes.
+    pub max_response_bytes: Option<u64>,
+    /// What happens to bodies over `max_response_bytes`.
+    pub oversize: Option<Oversize>,
+    /// Seconds a request may take before it times out.
+    pub request_timeout: Option<u64>,
+    /// Whether a timeout answers 408 for the client or 504 for a gateway.
+    pub timeout_mode: Option<TimeoutMode>,
 }
 
 impl RouteBasic {
@@ -100,7 +278,15 @@ impl RouteBasic {
             .unwrap_or_default()
             .protect
             .unwrap_or(false);
-        if let Some(captures) = RE_FILE_METHODS.captures(&route_params.file_stem) {
+        let (file_stem, query) = split_query(&route_params.file_stem);
+        let step = SequenceStep::from_file_name(&route_params.file_name);
+        let hang = if is_timeout_file(&route_params.file_name) {
+            Some(Hang::Infinite)
+        } else {
+            route_config.hang
+        };
+        let cookies = route_config.cookies.clone().unwrap_or_default();
+        if let Some(captures) = RE_FILE_METHODS.captures(file_stem) {
             let is_protected = is_protected || captures.get(ELEMENT_IS_PROTECTED).is_some();
             let method = captures.get(ELEMENT_METHOD).unwrap().as_str();
             let pattern = captures.get(ELEMENT_DESCRIPTOR);
@@ -111,12 +297,29 @@ impl RouteBasic {
                 route: route_config.remap.unwrap_or(route_params.full_route),
                 sub_route: SubRoute::from(pattern),
                 is_protected,
+                response_schema: route_config.response_schema,
+                query,
+                variants: vec![],
+                step,
+                sequence: vec![],
+                hang,
+                fault: route_config.fault,
+                cookies: cookies.clone(),
+                after: route_config.after.clone(),
+                cache_ttl: route_config.cache_ttl,
+                content_type: route_config.content_type.clone(),
+                charset: route_config.charset.clone(),
+                strategy: route_config.strategy.clone(),
+                max_response_bytes: route_config.max_response_bytes,
+                oversize: route_config.oversize,
+                request_timeout: route_config.request_timeout,
+                timeout_mode: route_config.timeout_mode,
             };
 
             return Route::Basic(route_basic);
         }
 
-        if let Some(captures) = RE_FILE_PARAM.captures(&route_params.file_stem) {
+        if let Some(captures) = RE_FILE_PARAM.captures(file_stem) {
             let is_protected = is_protected || captures.get(ELEMENT_IS_PROTECTED).is_some();
             let route = captures.get(ELEMENT_ROUTE_NAME).unwrap().as_str();
             let param = captures.get(ELEMENT_PARAM);
@@ -129,6 +332,23 @@ impl RouteBasic {
                     .unwrap_or(format!("{}/{}", route_params.full_route, route)),
                 sub_route: SubRoute::from(param),
                 is_protected,
+                response_schema: route_config.response_schema,
+                query,
+                variants: vec![],
+                step,
+                sequence: vec![],
+                hang,
+                fault: route_config.fault,
+                cookies: cookies.clone(),
+                after: route_config.after.clone(),
+                cache_ttl: route_config.cache_ttl,
+                content_type: route_config.content_type.clone(),
+                charset: route_config.charset.clone(),
+                strategy: route_config.strategy.clone(),
+                max_response_bytes: route_config.max_response_bytes,
+                oversize: route_config.oversize,
+                request_timeout: route_config.request_timeout,
+                timeout_mode: route_config.timeout_mode,
             };
 
             return Route::Basic(route_basic);
@@ -137,57 +357,267 @@ impl RouteBasic {
         let route_basic = Self {
             path: route_params.file_path,
             method: Method::GET,
-            route: route_config.remap.unwrap_or(format!(
-                "{}/{}",
-                route_params.full_route, route_params.file_stem
-            )),
+            route: route_config
+                .remap
+                .unwrap_or(format!("{}/{}", route_params.full_route, file_stem)),
             sub_route: SubRoute::None,
             is_protected,
+            response_schema: route_config.response_schema,
+            query,
+            variants: vec![],
+            step,
+            sequence: vec![],
+            hang,
+            fault: route_config.fault,
+            cookies,
+            after: route_config.after,
+            cache_ttl: route_config.cache_ttl,
+            content_type: route_config.content_type,
+            charset: route_config.charset,
+            strategy: route_config.strategy,
+            max_response_bytes: route_config.max_response_bytes,
+            oversize: route_config.oversize,
+            request_timeout: route_config.request_timeout,
+            timeout_mode: route_config.timeout_mode,
         };
 
         Route::Basic(route_basic)
     }
+
+    /// Describes a filename pattern that was not understood, or returns `None`
+    /// when the file maps as intended.
+    pub fn pattern_problem(&self) -> Option<String> {
+        let file_name = Path::new(&self.path).file_name()?.to_string_lossy();
+        let (file_stem, _) = split_query(file_name.split('.').next()?);
+        match &self.sub_route {
+            SubRoute::Range(start, end) if start > end => Some(format!(
+                "range {{{}-{}}} is empty, so no route is registered",
+                start, end
+            )),
+            SubRoute::Constrained(name, regex) => constraint_regex(regex).err().map(|err| {
+                format!(
+                    "regex of {{{}:{}}} is invalid, so no route is registered: {}",
+                    name, regex, err
+                )
+            }),
+            SubRoute::None
+                if file_stem.contains(['{', '}', '$']) && !RE_FILE_METHODS.is_match(file_stem) =>
+            {
+                Some(format!(
+                    "`{}` does not match a filename pattern and is served as a static file",
+                    file_name
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true when `other` serves the same route and either file selects
+    /// it by query parameters.
+    pub fn is_query_variant_of(&self, other: &RouteBasic) -> bool {
+        (!self.query.is_empty() || !other.query.is_empty())
+            && self.method == other.method
+            && self.route == other.route
+            && self.sub_route == other.sub_route
+    }
+
+    /// Serves `other` as a query variant of this route. A file without query
+    /// parameters becomes the default served when no variant matches.
+    pub fn push_query_variant(&mut self, mut other: RouteBasic) {
+        if !self.query.is_empty() && other.query.is_empty() {
+            std::mem::swap(self, &mut other);
+            self.variants = std::mem::take(&mut other.variants);
+        }
+        self.variants.push(QueryVariant {
+            sequence: other.sequence_files(),
+            query: other.query,
+            path: other.path,
+        });
+        self.variants
+            .sort_by(|left, right| left.path.cmp(&right.path));
+    }
+
+    /// Returns true when `other` is another step of the sequence this file
+    /// belongs to: both serve the same route for the same query parameters.
+    pub fn is_sequence_step_of(&self, other: &RouteBasic) -> bool {
+        (self.step.is_some() || other.step.is_some())
+            && self.method == other.method
+            && self.route == other.route
+            && self.sub_route == other.sub_route
+            && self.query == other.query
+    }
+
+    /// Adds `other` to the sequence of this file. A file without a step is
+    /// served like a `rest` step.
+    pub fn push_sequence_step(&mut self, other: RouteBasic) {
+        self.sequence.push(SequenceFile {
+            step: other.step.unwrap_or(SequenceStep::Rest),
+            path: other.path,
+        });
+    }
+
+    /// Returns the files of this sequence ordered by step, or nothing when the
+    /// file is not part of a sequence.
+    fn sequence_files(&self) -> Vec<SequenceFile> {
+        if self.step.is_none() && self.sequence.is_empty() {
+            return vec![];
+        }
+        let mut files = std::iter::once(SequenceFile {
+            step: self.step.unwrap_or(SequenceStep::Rest),
+            path: self.path.clone(),
+        })
+        .chain(self.sequence.iter().cloned())
+        .collect::<Vec<_>>();
+        files.sort_by(|left, right| (left.step, &left.path).cmp(&(right.step, &right.path)));
+        files
+    }
+
+    /// Returns this file and its query variants, in matching order.
+    fn query_variants(&self) -> Vec<QueryVariant> {
+        std::iter::once(QueryVariant {
+            query: self.query.clone(),
+            path: self.path.clone(),
+            sequence: self.sequence_files(),
+        })
+        .chain(self.variants.iter().cloned())
+        .collect()
+    }
+
+    /// Returns every concrete route path served by this file.
+    fn route_paths(&self) -> Vec<String> {
+        match &self.sub_route {
+            SubRoute::None => vec![self.route.clone()],
+            SubRoute::Id => vec![format!("{}/{}", self.route, "{id}")],
+            SubRoute::Range(start, end) => (*start..=*end)
+                .map(|i| format!("{}/{}", self.route, i))
+                .collect(),
+            SubRoute::Static(end_point) => vec![format!("{}/{}", self.route, end_point)],
+            SubRoute::Wildcard(name) => vec![format!("{}/{{*{}}}", self.route, name)],
+            SubRoute::Constrained(name, _) => vec![format!("{}/{{{}}}", self.route, name)],
+        }
+    }
 }
 
 impl RouteGenerator for RouteBasic {
     fn make_routes(&self, app: &mut crate::app::App) {
         let method = self.method.as_str();
+        let constraint = match &self.sub_route {
+            SubRoute::Constrained(name, regex) => match constraint_regex(regex) {
+                Ok(regex) => Some((name, regex)),
+                Err(_) => return,
+            },
+            _ => None,
+        };
+        let charset = match self.charset.as_deref().map(Charset::parse).transpose() {
+            Ok(charset) => charset,
+            Err(err) => {
+                app.log(format!(
+                    "⚠️ Ignoring the charset of {}: {}",
+                    Path::new(&self.path).display(),
+                    err
+                ));
+                None
+            }
+        };
 
-        match &self.sub_route {
-            SubRoute::None => {
-                let router = build_method_router(app, &self.path, method);
-                app.push_route(&self.route, router, Some(method), self.is_protected, None);
-            }
-            SubRoute::Id => {
-                let route_path = format!("{}/{}", self.route, "{id}");
-                let router = build_method_router(app, &self.path, method);
-                app.push_route(&route_path, router, Some(method), self.is_protected, None);
-            }
-            SubRoute::Range(start, end) => {
-                for i in *start..=*end {
-                    let route_path = format!("{}/{}", self.route, i);
-                    let router = build_method_router(app, &self.path, method);
-                    app.push_route(&route_path, router, Some(method), self.is_protected, None);
+        if let Some(ResponseStrategy::Weighted(weights)) = &self.strategy {
+            let files = self.sequence_files().len();
+            if files > 0 && weights.len() != files {
+                app.log(format!(
+                    "⚠️ {} has {} strategy weights for {} files; files without a weight are never served",
+                    Path::new(&self.path).display(),
+                    weights.len(),
+                    files
+                ));
+            }
+        }
+
+        for route_path in self.route_paths() {
+            let mut variants = self
+                .query_variants()
+                .into_iter()
+                .map(|variant| {
+                    let mut router = variant_router(app, &variant, method, &self.strategy);
+                    // Sequences answer differently on every call, so they are never cached.
+                    if let Some(ttl) = self.cache_ttl.filter(|_| variant.sequence.is_empty()) {
+                        router = cache_router(
+                            router,
+                            &variant.path.to_string_lossy(),
+                            Duration::from_secs(ttl),
+                            &app.response_cache,
+                        );
+                    }
+                    (variant, router)
+                })
+                .collect::<Vec<_>>();
+            let mut router = if self.query.is_empty() && self.variants.is_empty() {
+                variants.remove(0).1
+            } else {
+                build_query_variant_router(variants, method)
+            };
+            if self.content_type.is_some() || charset.is_some() {
+                router = content_type_router(router, self.content_type.clone(), charset);
+            }
+            if !self.cookies.is_empty() {
+                router = set_cookies_router(router, &self.cookies);
+            }
+            if let Some(max_bytes) = self.max_response_bytes {
+                router = size_limit_router(router, max_bytes, self.oversize.unwrap_or_default());
+            }
+            if let Some(fault) = self.fault {
+                router = fault_router(router, fault);
+            }
+            if let Some(hang) = self.hang {
+                router = hang_router(router, hang);
+            }
+            // Outside the hang, so a gateway timeout answers a hanging route.
+            if let Some(seconds) = self.request_timeout {
+                router = timeout_router(
+                    router,
+                    Duration::from_secs(seconds),
+                    self.timeout_mode.unwrap_or_default(),
+                );
+            }
+            if let Some((name, regex)) = &constraint {
+                router = constrain_param(router, name, regex);
+            }
+            if let Some(after) = &self.after {
+                match after_call_router(router.clone(), after, Arc::clone(&app.routes)) {
+                    Ok(with_after) => router = with_after,
+                    Err(err) => app.log(format!(
+                        "⚠️ Ignoring the after call of {}: {}",
+                        Path::new(&self.path).display(),
+                        err
+                    )),
                 }
             }
-            SubRoute::Static(end_point) => {
-                let route_path = format!("{}/{}", self.route, end_point);
-                let router = build_method_router(app, &self.path, method);
-                app.push_route(&route_path, router, Some(method), self.is_protected, None);
+            app.push_route(&route_path, router, Some(method), self.is_protected, None);
+
+            if let Some(response_schema) = &self.response_schema {
+                app.push_response_schema(method, &route_path, response_schema);
             }
         }
     }
 }
 
-impl PrintRoute for RouteBasic {
-    fn println(&self) {
-        let path = &self.path.to_string_lossy();
-        let method = self.method.as_str();
-        let route = &self.route;
-        let subroute = self.sub_route.to_string();
-
-        println!("✔️ Mapped {} to {} {}{}", path, method, route, subroute);
+/// Builds the router of one query variant, walking its sequence with
+/// `strategy` when it has one.
+fn variant_router(
+    app: &mut crate::app::App,
+    variant: &QueryVariant,
+    method: &str,
+    strategy: &Option<ResponseStrategy>,
+) -> MethodRouter {
+    if variant.sequence.is_empty() {
+        return build_method_router(app, &variant.path, method);
     }
+    let steps = variant
+        .sequence
+        .iter()
+        .map(|file| (file.step, build_method_router(app, &file.path, method)))
+        .collect();
+    let strategy = strategy.clone().unwrap_or(ResponseStrategy::Sequence);
+    build_sequence_router(app, steps, method, strategy)
 }
 
 #[cfg(test)]
@@ -425,6 +855,236 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_parse_method_with_wildcard_descriptor() {
+        let temp_dir = TempDir::new().unwrap();
+        let cases = [
+            (
+                "get{**}.json",
+                SubRoute::Wildcard("path".to_string()),
+                "/api/files/{*path}",
+            ),
+            (
+                "put{*key}.json",
+                SubRoute::Wildcard("key".to_string()),
+                "/api/files/{*key}",
+            ),
+            (
+                "get{*}.json",
+                SubRoute::Static("*".to_string()),
+                "/api/files/*",
+            ),
+        ];
+
+        for (file_name, sub_route, route_path) in cases {
+            let entry = create_test_file(temp_dir.path(), file_name);
+            let route_params = RouteParams::new(
+                "/api/files",
+                &entry,
+                Config::default().with_protect(false),
+                &ConfigStore::default(),
+            );
+
+            match RouteBasic::try_parse(route_params) {
+                Route::Basic(route_basic) => {
+                    assert_eq!(route_basic.sub_route, sub_route, "{}", file_name);
+                    assert_eq!(route_basic.route_paths(), [route_path]);
+                }
+                _ => panic!("Expected Route::Basic"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn wildcard_routes_match_any_path_suffix() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("get{**}.json"), r#"{"ok":true}"#).unwrap();
+        let entry = temp_dir.path().read_dir().unwrap().next().unwrap().unwrap();
+        let route = RouteBasic::try_parse(RouteParams::new(
+            "/files",
+            &entry,
+            Config::default(),
+            &ConfigStore::default(),
+        ));
+
+        let mut app = crate::app::App::default();
+        route.make_routes(&mut app);
+        let router = app.take_router_for_test();
+        for uri in ["/files/a.txt", "/files/nested/dir/b.txt"] {
+            let response = router
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn constrained_routes_only_match_values_satisfying_the_regex() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("get{id:[0-9]+}.json"), r#"{"id":1}"#).unwrap();
+        std::fs::write(temp_dir.path().join("get{export}.json"), r#"[]"#).unwrap();
+
+        let mut app = crate::app::App::default();
+        for entry in temp_dir.path().read_dir().unwrap() {
+            let route = RouteBasic::try_parse(RouteParams::new(
+                "/items",
+                &entry.unwrap(),
+                Config::default(),
+                &ConfigStore::default(),
+            ));
+            if let Route::Basic(route_basic) = &route
+                && route_basic.path.to_string_lossy().contains(':')
+            {
+                assert_eq!(
+                    route_basic.sub_route,
+                    SubRoute::Constrained("id".to_string(), "[0-9]+".to_string())
+                );
+                assert_eq!(route_basic.pattern_problem(), None);
+            }
+            route.make_routes(&mut app);
+        }
+
+        let router = app.take_router_for_test();
+        for (uri, status) in [
+            ("/items/123", http::StatusCode::OK),
+            ("/items/export", http::StatusCode::OK),
+            ("/items/abc", http::StatusCode::NOT_FOUND),
+            ("/items/12a", http::StatusCode::NOT_FOUND),
+        ] {
+            let response = router
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn invalid_constraint_regex_is_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_file(temp_dir.path(), "get{id:[0-9}.json");
+        let route = RouteBasic::try_parse(RouteParams::new(
+            "/items",
+            &entry,
+            Config::default(),
+            &ConfigStore::default(),
+        ));
+
+        let Route::Basic(route_basic) = route else {
+            panic!("Expected Route::Basic");
+        };
+        assert!(
+            route_basic
+                .pattern_problem()
+                .is_some_and(|problem| problem.contains("{id:[0-9}"))
+        );
+    }
+
+    #[test]
+    fn test_try_parse_query_variant() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_file(temp_dir.path(), "$get{id}?view=full&expand.json");
+        let route = RouteBasic::try_parse(RouteParams::new(
+            "/api/items",
+            &entry,
+            Config::default().with_protect(false),
+            &ConfigStore::default(),
+        ));
+
+        let Route::Basic(route_basic) = route else {
+            panic!("Expected Route::Basic");
+        };
+        assert_eq!(route_basic.sub_route, SubRoute::Id);
+        assert!(route_basic.is_protected);
+        assert_eq!(
+            route_basic.query,
+            vec![
+                ("view".to_string(), Some("full".to_string())),
+                ("expand".to_string(), None)
+            ]
+        );
+        assert_eq!(route_basic.pattern_problem(), None);
+
+        let entry = create_test_file(temp_dir.path(), "get~view=full.json");
+        let route = RouteBasic::try_parse(RouteParams::new(
+            "/api/items",
+            &entry,
+            Config::default(),
+            &ConfigStore::default(),
+        ));
+        let Route::Basic(route_basic) = route else {
+            panic!("Expected Route::Basic");
+        };
+        assert_eq!(
+            route_basic.query,
+            vec![("view".to_string(), Some("full".to_string()))]
+        );
+    }
+
+    #[test]
+    fn sequence_steps_are_parsed_from_file_names() {
+        assert_eq!(
+            SequenceStep::from_file_name("get.1.json"),
+            Some(SequenceStep::Call(1))
+        );
+        assert_eq!(
+            SequenceStep::from_file_name("get{id}.12.txt"),
+            Some(SequenceStep::Call(12))
+        );
+        assert_eq!(
+            SequenceStep::from_file_name("get.rest.json"),
+            Some(SequenceStep::Rest)
+        );
+        assert_eq!(SequenceStep::from_file_name("get.json"), None);
+        assert_eq!(SequenceStep::from_file_name("get.0.json"), None);
+        assert_eq!(SequenceStep::from_file_name("archive.tar.gz"), None);
+    }
+
+    #[tokio::test]
+    async fn hanging_routes_never_answer() {
+        use axum::{body::Body, http::Request};
+        use std::time::Duration;
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_file(temp_dir.path(), "get.timeout.json");
+        let route = RouteBasic::try_parse(RouteParams::new(
+            "/slow",
+            &entry,
+            Config::default(),
+            &ConfigStore::default(),
+        ));
+        let Route::Basic(mut route_basic) = route else {
+            panic!("Expected Route::Basic");
+        };
+        assert_eq!(route_basic.route, "/slow");
+        assert_eq!(route_basic.hang, Some(Hang::Infinite));
+
+        let request = || Request::builder().uri("/slow").body(Body::empty()).unwrap();
+        let mut app = crate::app::App::default();
+        route_basic.make_routes(&mut app);
+        let router = app.take_router_for_test();
+        let response =
+            tokio::time::timeout(Duration::from_millis(50), router.oneshot(request())).await;
+        assert!(response.is_err());
+
+        route_basic.hang = Some(Hang::Seconds(0));
+        let mut app = crate::app::App::default();
+        route_basic.make_routes(&mut app);
+        let router = app.take_router_for_test();
+        let response = router.oneshot(request()).await.unwrap();
+        assert!(crate::handlers::is_dropped_connection(&response));
+    }
+
     #[test]
     fn test_try_parse_protected_with_descriptor() {
         let temp_dir = TempDir::new().unwrap();
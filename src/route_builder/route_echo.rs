@@ -0,0 +1,109 @@
+use std::ffi::OsString;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    handlers::build_echo_routes,
+    route_builder::{Route, RouteGenerator, config::Delay, route_params::RouteParams},
+};
+
+static RE_FILE_ECHO: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\$)?echo$").unwrap());
+
+static RE_DIR_ECHO: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\$)?\{echo\}(-(.+))?$").unwrap());
+
+const ELEMENT_IS_PROTECTED: usize = 1;
+const ELEMENT_ROUTE: usize = 3;
+
+/// Route that answers with the request it received, from an `echo.json` file
+/// or an `{echo}` directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteEcho {
+    /// Source file or directory path.
+    pub path: OsString,
+    /// Route answering every method.
+    pub route: String,
+    /// Whether this route requires auth middleware.
+    pub is_protected: bool,
+    /// Optional response delay in milliseconds.
+    pub delay: Option<Delay>,
+}
+
+impl RouteEcho {
+    /// Parses route parameters as an echo route definition.
+    pub fn try_parse(route_params: RouteParams) -> Route {
+        let captures = if route_params.is_dir {
+            RE_DIR_ECHO.captures(&route_params.file_name)
+        } else {
+            RE_FILE_ECHO.captures(&route_params.file_stem)
+        };
+        let Some(captures) = captures else {
+            return Route::None;
+        };
+
+        let route_config = route_params.config.route.clone().unwrap_or_default();
+        let is_protected =
+            route_config.protect.unwrap_or(false) || captures.get(ELEMENT_IS_PROTECTED).is_some();
+        let echo_route = captures
+            .get(ELEMENT_ROUTE)
+            .map(|route| route.as_str())
+            .unwrap_or("echo");
+
+        Route::Echo(Self {
+            path: route_params.file_path,
+            route: route_config
+                .remap
+                .unwrap_or(format!("{}/{}", route_params.parent_route, echo_route)),
+            is_protected,
+            delay: route_config.delay,
+        })
+    }
+}
+
+impl RouteGenerator for RouteEcho {
+    fn make_routes(&self, app: &mut crate::app::App) {
+        build_echo_routes(app, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::{Config, ConfigStore};
+    use tempfile::TempDir;
+
+    fn parse(name: &str, is_dir: bool) -> Route {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(name);
+        if is_dir {
+            std::fs::create_dir(&path).unwrap();
+        } else {
+            std::fs::write(&path, "{}").unwrap();
+        }
+        let entry = temp_dir.path().read_dir().unwrap().next().unwrap().unwrap();
+        RouteEcho::try_parse(RouteParams::new(
+            "/debug",
+            &entry,
+            Config::default(),
+            &ConfigStore::default(),
+        ))
+    }
+
+    #[test]
+    fn echo_files_and_directories_are_parsed() {
+        let Route::Echo(file) = parse("echo.json", false) else {
+            panic!("Expected Route::Echo");
+        };
+        assert_eq!(file.route, "/debug/echo");
+        assert!(!file.is_protected);
+
+        let Route::Echo(dir) = parse("${echo}-inspect", true) else {
+            panic!("Expected Route::Echo");
+        };
+        assert_eq!(dir.route, "/debug/inspect");
+        assert!(dir.is_protected);
+
+        assert!(parse("echoes.json", false).is_none());
+        assert!(parse("echo", true).is_none());
+    }
+}
@@ -6,7 +6,7 @@ use regex::Regex;
 use crate::{
     app::App,
     handlers::build_graphql_routes,
-    route_builder::{PrintRoute, Route, RouteGenerator, route_params::RouteParams},
+    route_builder::{Route, RouteGenerator, config::Delay, route_params::RouteParams},
 };
 
 static RE_FOLDER_GRAPHQL: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\$)?graphql$").unwrap());
@@ -21,19 +21,22 @@ pub struct RouteGraphQL {
     /// Base route for GraphQL and GraphiQL endpoints.
     pub route: String,
     /// Optional response delay in milliseconds.
-    pub delay: Option<u16>,
+    pub delay: Option<Delay>,
     /// Whether this route requires auth middleware.
     pub is_protected: bool,
+    /// Whether collections are exposed as Relay connections.
+    pub relay: bool,
 }
 
 impl RouteGraphQL {
     /// Creates a GraphQL route definition.
-    pub fn new(path: OsString, route: String, is_protected: bool, delay: Option<u16>) -> Self {
+    pub fn new(path: OsString, route: String, is_protected: bool, delay: Option<Delay>) -> Self {
         Self {
             path,
             route,
             is_protected,
             delay,
+            relay: false,
         }
     }
 
@@ -48,12 +51,17 @@ impl RouteGraphQL {
             let is_protected = is_protected || captures.get(ELEMENT_IS_PROTECTED).is_some();
 
             let route = route_config.remap.unwrap_or(route_params.full_route);
+            let relay = config
+                .graphql
+                .and_then(|graphql| graphql.relay)
+                .unwrap_or(false);
 
             let route_graphql = Self {
                 path: route_params.file_path,
                 route,
                 delay,
                 is_protected,
+                relay,
             };
 
             return Route::GraphQL(route_graphql);
@@ -69,12 +77,6 @@ impl RouteGenerator for RouteGraphQL {
     }
 }
 
-impl PrintRoute for RouteGraphQL {
-    fn println(&self) {
-        println!("✔️ Built GraphQL routes");
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,12 +94,16 @@ mod tests {
 
     #[test]
     fn new_stores_graphql_route_configuration() {
-        let route = RouteGraphQL::new("graphql".into(), "/graphql".to_string(), true, Some(5));
+        let route = RouteGraphQL::new(
+            "graphql".into(),
+            "/graphql".to_string(),
+            true,
+            Some(Delay::Fixed(5)),
+        );
         assert_eq!(route.path, OsString::from("graphql"));
         assert_eq!(route.route, "/graphql");
         assert!(route.is_protected);
-        assert_eq!(route.delay, Some(5));
-        route.println();
+        assert_eq!(route.delay, Some(Delay::Fixed(5)));
     }
 
     #[test]
@@ -132,6 +138,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn try_parse_reads_the_relay_option() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = dir_entry(temp_dir.path(), "graphql");
+        let config = Config::try_from("[graphql]\nrelay = true\n").unwrap();
+
+        match RouteGraphQL::try_parse(RouteParams::new(
+            "/",
+            &entry,
+            config,
+            &ConfigStore::default(),
+        )) {
+            Route::GraphQL(graphql) => assert!(graphql.relay),
+            _ => panic!("Expected GraphQL route"),
+        }
+    }
+
     #[test]
     fn try_parse_rejects_non_graphql_folder() {
         let temp_dir = TempDir::new().unwrap();
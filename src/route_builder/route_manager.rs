@@ -3,13 +3,20 @@ use std::{
     path::Path,
 };
 
+use rayon::prelude::*;
+
 use crate::{
-    DEFAULT_COLLECTIONS_FOLDER, DEFAULT_SCHEMAS_FOLDER,
-    app::App,
+    DEFAULT_COLLECTIONS_FOLDER, DEFAULT_ERROR_BODY_FILE, DEFAULT_NOT_FOUND_BODY_FILE,
+    DEFAULT_SCHEMAS_FOLDER,
+    app::{App, RouteOverlap, describe_overlaps},
+    handlers::CsrfScope,
     route_builder::{
-        Route, RouteGenerator, RouteParams,
-        config::{Config, ConfigStore, Mergeable},
+        LATEST_VERSION, Route, RouteBasic, RouteGenerator, RouteParams, RouteRewrite, RouteVersion,
+        config::{Config, ConfigStore, Mergeable, OnConflict},
+        version_number,
     },
+    route_inventory::{RouteEntry, entries_since},
+    route_summary::{format_summary, route_conflicts, use_color},
 };
 
 /// Discovers, orders, and registers routes from a mock directory tree.
@@ -19,6 +26,13 @@ pub struct RouteManager {
     pub auth_route: Route,
     /// Parsed non-auth routes.
     pub routes: Vec<Route>,
+    /// Version prefixes discovered from `vN` folders.
+    pub versions: Vec<RouteVersion>,
+    /// Folders whose `[route]` table sets `csrf`.
+    pub csrf_scopes: Vec<CsrfScope>,
+    root_route: String,
+    profile: Option<String>,
+    on_conflict: OnConflict,
 }
 
 impl RouteManager {
@@ -27,6 +41,11 @@ impl RouteManager {
         Self {
             auth_route: Route::None,
             routes: vec![],
+            versions: vec![],
+            csrf_scopes: vec![],
+            root_route: String::new(),
+            profile: None,
+            on_conflict: OnConflict::default(),
         }
     }
 
@@ -35,17 +54,7 @@ impl RouteManager {
         let start_time = std::time::Instant::now();
         println!("Start - Loading routes");
 
-        let parent_route = config
-            .clone()
-            .unwrap_or_default()
-            .route
-            .unwrap_or_default()
-            .remap
-            .unwrap_or("".into());
-
-        let mut manager = Self::new();
-        manager.load_dir(&parent_route, root_path, config);
-        manager.sort();
+        let manager = Self::discover(root_path, config);
 
         println!(
             "Finish - Loading routes. Routes loaded in {:?}",
@@ -55,32 +64,178 @@ impl RouteManager {
         manager
     }
 
-    fn load_dir(&mut self, parent_route: &str, entries_path: &str, config: Option<Config>) {
-        let config_store = ConfigStore::try_from_dir(entries_path).unwrap_or_else(|err| {
-            panic!(
-                "Unable to load configs from {}. Error: {:?}",
-                entries_path, err
-            )
+    /// Loads route definitions like [`RouteManager::from_dir`] without printing progress.
+    pub fn discover(root_path: &str, config: Option<Config>) -> Self {
+        let route_config = config.clone().unwrap_or_default().route.unwrap_or_default();
+        let parent_route = route_config.remap.unwrap_or("".into());
+
+        let mut manager = Self::new();
+        manager.root_route = parent_route.clone();
+        manager.profile = config
+            .as_ref()
+            .and_then(|config| config.server.as_ref())
+            .and_then(|server| server.profile.clone());
+        manager.on_conflict = config
+            .as_ref()
+            .and_then(|config| config.server.as_ref())
+            .and_then(|server| server.on_conflict)
+            .unwrap_or_default();
+        let loaded = manager.load_dir(&parent_route, Path::new(root_path), config);
+        manager.auth_route = loaded.auth_route;
+        manager.routes = loaded.routes;
+        manager.versions = loaded.versions;
+        manager.csrf_scopes = loaded.csrf_scopes;
+        manager.group_sequences();
+        manager.group_query_variants();
+        manager.rewrite_routes(&parent_route, &route_config.rewrites.unwrap_or_default());
+        manager.sort();
+
+        manager
+    }
+
+    /// Parses the JSON seeds of REST collections in parallel, ahead of
+    /// registering their routes.
+    pub fn load_seeds(&mut self) {
+        self.routes.par_iter_mut().for_each(|route| {
+            if let Route::Rest(route_rest) = route {
+                route_rest.load_seed();
+            }
         });
+    }
+
+    /// Merges the steps of response sequences, such as `get.1.json` and
+    /// `get.rest.json`, into the first file of each sequence.
+    fn group_sequences(&mut self) {
+        self.merge_basic_routes(
+            RouteBasic::is_sequence_step_of,
+            RouteBasic::push_sequence_step,
+        );
+    }
+
+    /// Merges basic routes selected by query parameters, such as
+    /// `get?status=active.json`, into the route sharing their method and path.
+    fn group_query_variants(&mut self) {
+        self.merge_basic_routes(
+            RouteBasic::is_query_variant_of,
+            RouteBasic::push_query_variant,
+        );
+    }
+
+    /// Merges each basic route into the first earlier one it `belongs` to.
+    fn merge_basic_routes(
+        &mut self,
+        belongs: impl Fn(&RouteBasic, &RouteBasic) -> bool,
+        merge: impl Fn(&mut RouteBasic, RouteBasic),
+    ) {
+        let mut routes = Vec::<Route>::with_capacity(self.routes.len());
+        for route in std::mem::take(&mut self.routes) {
+            let Route::Basic(route_basic) = route else {
+                routes.push(route);
+                continue;
+            };
+            let host = routes.iter_mut().find_map(|route| match route {
+                Route::Basic(host) if belongs(host, &route_basic) => Some(host),
+                _ => None,
+            });
+            match host {
+                Some(host) => merge(host, route_basic),
+                None => routes.push(Route::Basic(route_basic)),
+            }
+        }
+        self.routes = routes;
+    }
+
+    /// Applies the first matching rewrite to each route path.
+    fn rewrite_routes(&mut self, parent_route: &str, rules: &[String]) {
+        let rewrites = rules
+            .iter()
+            .filter_map(|rule| match RouteRewrite::parse(rule, parent_route) {
+                Ok(rewrite) => Some(rewrite),
+                Err(err) => {
+                    println!("Ignoring route rewrite. Details: {}", err);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        if rewrites.is_empty() {
+            return;
+        }
+
+        let route_paths = std::iter::once(&mut self.auth_route)
+            .chain(&mut self.routes)
+            .filter_map(Route::route_mut);
+        let version_paths = self
+            .versions
+            .iter_mut()
+            .flat_map(|version| std::iter::once(&mut version.route).chain(version.alias.as_mut()));
+        let csrf_paths = self.csrf_scopes.iter_mut().map(|scope| &mut scope.route);
+        for path in route_paths.chain(version_paths).chain(csrf_paths) {
+            if let Some(rewritten) = rewrites.iter().find_map(|rewrite| rewrite.apply(path)) {
+                *path = rewritten;
+            }
+        }
+    }
+
+    /// Walks a folder, loading its entries in parallel and merging them in
+    /// folder order so the result matches a sequential walk.
+    fn load_dir(
+        &self,
+        parent_route: &str,
+        entries_path: &Path,
+        config: Option<Config>,
+    ) -> LoadedDir {
+        let config_store = ConfigStore::try_from_dir(entries_path, self.profile.as_deref())
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Unable to load configs from {}. Error: {:?}",
+                    entries_path.display(),
+                    err
+                )
+            });
 
         let config = config_store.get("config").merge(config);
+        let csrf = config
+            .as_ref()
+            .and_then(|config| config.route.as_ref())
+            .and_then(|route| route.csrf);
 
-        let entries = fs::read_dir(entries_path).unwrap();
-        for entry in entries {
-            let entry = entry.unwrap();
-            self.load_entry(parent_route, &entry, &config, &config_store);
+        let entries = fs::read_dir(entries_path)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .collect::<Vec<_>>();
+        let latest_version = entries
+            .iter()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| version_number(&entry.file_name().to_string_lossy()))
+            .max();
+        let mut loaded = LoadedDir::default();
+        if let Some(enabled) = csrf {
+            loaded.csrf_scopes.push(CsrfScope {
+                route: parent_route.to_string(),
+                enabled,
+            });
         }
+        entries
+            .par_iter()
+            .map(|entry| {
+                self.load_entry(parent_route, entry, &config, &config_store, latest_version)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .fold(loaded, LoadedDir::merge)
     }
 
     fn load_entry(
-        &mut self,
+        &self,
         parent_route: &str,
         entry: &DirEntry,
         config: &Option<Config>,
         config_store: &ConfigStore,
-    ) {
-        if is_reserved_data_folder_entry(entry, config) {
-            return;
+        latest_version: Option<u32>,
+    ) -> LoadedDir {
+        let mut loaded = LoadedDir::default();
+        if is_reserved_data_folder_entry(entry, config) || is_error_body_entry(entry) {
+            return loaded;
         }
 
         let route_params = RouteParams::new(
@@ -90,30 +245,66 @@ impl RouteManager {
             config_store,
         );
         if route_params.file_extension == "toml" {
-            return;
+            return loaded;
         }
 
         let route = Route::try_parse(&route_params);
 
         if route.is_none() {
-            if route_params.is_dir {
-                self.load_dir(
+            if route_params.is_dir
+                && let Some(version) = version_number(&route_params.file_name)
+            {
+                return self.load_version_dir(
+                    parent_route,
+                    &route_params,
+                    latest_version == Some(version),
+                );
+            } else if route_params.is_dir {
+                return self.load_dir(
                     &route_params.full_route,
-                    &route_params.file_path.to_string_lossy(),
+                    Path::new(&route_params.file_path),
                     Some(route_params.config.clone()),
                 );
             }
-            return;
+            return loaded;
         }
 
         if let Route::Auth(_) = route {
-            if self.auth_route.is_some() {
-                panic!("Only one auth route is allowed");
-            }
-            self.auth_route = route;
+            loaded.auth_route = route;
         } else {
-            self.routes.push(route);
+            loaded.routes.push(route);
         }
+        loaded
+    }
+
+    /// Loads a `vN` folder under `/vN` followed by the route of its parent folder.
+    fn load_version_dir(
+        &self,
+        parent_route: &str,
+        route_params: &RouteParams,
+        is_latest: bool,
+    ) -> LoadedDir {
+        let resource = parent_route
+            .strip_prefix(self.root_route.as_str())
+            .unwrap_or(parent_route);
+        let version = &route_params.file_name;
+        let route = format!("{}/{}{}", self.root_route, version, resource);
+        let versioning = route_params.config.versioning.clone().unwrap_or_default();
+        let alias = (is_latest && versioning.latest.unwrap_or(false))
+            .then(|| format!("{}/{}{}", self.root_route, LATEST_VERSION, resource));
+
+        let mut loaded = LoadedDir::default();
+        loaded.versions.push(RouteVersion::new(
+            version,
+            route.clone(),
+            alias,
+            &versioning,
+        ));
+        loaded.merge(self.load_dir(
+            &route,
+            Path::new(&route_params.file_path),
+            Some(route_params.config.clone()),
+        ))
     }
 
     fn sort(&mut self) {
@@ -122,6 +313,36 @@ impl RouteManager {
     }
 }
 
+/// Routes found while walking a folder.
+#[derive(Debug, Default)]
+struct LoadedDir {
+    auth_route: Route,
+    routes: Vec<Route>,
+    versions: Vec<RouteVersion>,
+    csrf_scopes: Vec<CsrfScope>,
+}
+
+impl LoadedDir {
+    /// Appends the routes of a later entry of the same walk.
+    fn merge(mut self, other: LoadedDir) -> LoadedDir {
+        if other.auth_route.is_some() {
+            if self.auth_route.is_some() {
+                panic!("Only one auth route is allowed");
+            }
+            self.auth_route = other.auth_route;
+        }
+        self.routes.extend(other.routes);
+        self.versions.extend(other.versions);
+        self.csrf_scopes.extend(other.csrf_scopes);
+        self
+    }
+}
+
+fn is_error_body_entry(entry: &DirEntry) -> bool {
+    let file_name = entry.file_name();
+    file_name == DEFAULT_NOT_FOUND_BODY_FILE || file_name == DEFAULT_ERROR_BODY_FILE
+}
+
 fn is_reserved_data_folder_entry(entry: &DirEntry, config: &Option<Config>) -> bool {
     is_configured_folder_entry(
         entry,
@@ -185,12 +406,103 @@ fn same_existing_path(left: &Path, right: &Path) -> bool {
     }
 }
 
+/// Describes the overlapping routes of `source` that a file registered
+/// earlier already serves, naming both files.
+fn describe_conflicts(
+    overlaps: &[RouteOverlap],
+    source: &str,
+    entries: &[RouteEntry],
+) -> Vec<String> {
+    overlaps
+        .iter()
+        .filter_map(|overlap| {
+            let earlier = entries.iter().find(|entry| {
+                entry.method == overlap.earlier.method && entry.path == overlap.earlier.path
+            })?;
+            Some(format!(
+                "{} {} from {} duplicates {} {} from {}",
+                overlap.route.method,
+                overlap.route.path,
+                source,
+                earlier.method,
+                earlier.path,
+                earlier.source
+            ))
+        })
+        .collect()
+}
+
+/// Replaces the parameter names of a route path, which the router ignores
+/// when it looks for overlapping routes.
+pub(crate) fn route_shape(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix('{') {
+            Some(rest) if rest.starts_with('*') => "{*}",
+            Some(_) => "{}",
+            None => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 impl RouteGenerator for RouteManager {
+    /// Registers every route and prints a grouped summary of them, resolving
+    /// the routes of a file that overlap another file by `on_conflict` and
+    /// warning about routes that shadow part of another.
+    ///
+    /// With `on_conflict = "last"`, files are registered in reverse order, so
+    /// the earlier file is the one skipped.
     fn make_routes(&self, app: &mut App) {
-        self.auth_route.make_routes_and_print(app);
+        let routes: Box<dyn Iterator<Item = &Route>> = match self.on_conflict {
+            OnConflict::Last => Box::new(self.routes.iter().rev()),
+            OnConflict::Error | OnConflict::First => Box::new(self.routes.iter()),
+        };
+
+        let mut entries = vec![];
+        for route in std::iter::once(&self.auth_route).chain(routes) {
+            let Some(source) = route.source_path() else {
+                continue;
+            };
+            let source = source.to_string_lossy().into_owned();
 
-        for route in self.routes.iter() {
-            route.make_routes_and_print(app);
+            let registered = app.registered_routes().len();
+            let Err(overlaps) = app.try_make_routes(route) else {
+                entries.extend(entries_since(app, registered, route, &source));
+                continue;
+            };
+
+            let conflicts = describe_conflicts(&overlaps, &source, &entries);
+            if conflicts.is_empty() {
+                app.log(format!(
+                    "⚠️ Skipped the routes of {}: {}",
+                    source,
+                    describe_overlaps(&overlaps)
+                ));
+                continue;
+            }
+            let on_conflict = match self.on_conflict {
+                OnConflict::Error => panic!(
+                    "Duplicate routes: {}. Set on_conflict = \"first\" or \"last\" in [server] to keep one of the files",
+                    conflicts.join("; ")
+                ),
+                OnConflict::First => "first",
+                OnConflict::Last => "last",
+            };
+            for conflict in conflicts {
+                app.log(format!("⚠️ {}", conflict));
+            }
+            app.log(format!(
+                "⚠️ Skipped the routes of {} (on_conflict = \"{}\")",
+                source, on_conflict
+            ));
+        }
+
+        app.pages.lock().unwrap().push_route_entries(&entries);
+        if !entries.is_empty() {
+            app.log(format_summary(&entries, use_color()));
+        }
+        for conflict in route_conflicts(&entries) {
+            app.log(format!("⚠️ {}", conflict));
         }
     }
 }
@@ -198,7 +510,7 @@ impl RouteGenerator for RouteManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::route_builder::config::{CollectionsConfig, Config, RouteConfig};
+    use crate::route_builder::config::{CollectionsConfig, Config, RouteConfig, VersioningConfig};
     use tempfile::TempDir;
 
     #[test]
@@ -267,6 +579,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn discover_applies_the_first_matching_rewrite() {
+        let temp_dir = TempDir::new().unwrap();
+        for folder in ["api/users", "api/orders", "health"] {
+            std::fs::create_dir_all(temp_dir.path().join(folder)).unwrap();
+            std::fs::write(temp_dir.path().join(folder).join("get.json"), "{}").unwrap();
+        }
+
+        let manager = RouteManager::discover(
+            temp_dir.path().to_str().unwrap(),
+            Some(Config {
+                route: Some(RouteConfig {
+                    remap: Some("/gateway".to_string()),
+                    rewrites: Some(vec![
+                        "/api/orders -> /legacy/orders".to_string(),
+                        "/api/* -> /v2/*".to_string(),
+                        "not a rule".to_string(),
+                    ]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        );
+
+        let mut routes = manager
+            .routes
+            .iter()
+            .map(|route| match route {
+                Route::Basic(route_basic) => route_basic.route.clone(),
+                _ => panic!("expected basic routes"),
+            })
+            .collect::<Vec<_>>();
+        routes.sort();
+        assert_eq!(
+            routes,
+            [
+                "/gateway/health",
+                "/gateway/legacy/orders",
+                "/gateway/v2/users"
+            ]
+        );
+    }
+
+    #[test]
+    fn discover_moves_version_folders_in_front_of_their_resource() {
+        let temp_dir = TempDir::new().unwrap();
+        for folder in ["users/v1", "users/v2", "users/v10/{id}", "orders"] {
+            std::fs::create_dir_all(temp_dir.path().join(folder)).unwrap();
+            std::fs::write(temp_dir.path().join(folder).join("get.json"), "{}").unwrap();
+        }
+
+        let manager = RouteManager::discover(
+            temp_dir.path().to_str().unwrap(),
+            Some(Config {
+                route: Some(RouteConfig {
+                    remap: Some("/api".to_string()),
+                    ..Default::default()
+                }),
+                versioning: Some(VersioningConfig {
+                    latest: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        );
+
+        let mut routes = manager
+            .routes
+            .iter()
+            .map(|route| match route {
+                Route::Basic(route_basic) => route_basic.route.clone(),
+                _ => panic!("expected basic routes"),
+            })
+            .collect::<Vec<_>>();
+        routes.sort();
+        assert_eq!(
+            routes,
+            [
+                "/api/orders",
+                "/api/v1/users",
+                "/api/v10/users/{id}",
+                "/api/v2/users"
+            ]
+        );
+
+        let mut versions = manager
+            .versions
+            .iter()
+            .map(|version| (version.route.as_str(), version.alias.as_deref()))
+            .collect::<Vec<_>>();
+        versions.sort();
+        assert_eq!(
+            versions,
+            [
+                ("/api/v1/users", None),
+                ("/api/v10/users", Some("/api/latest/users")),
+                ("/api/v2/users", None)
+            ]
+        );
+    }
+
     #[test]
     fn from_dir_skips_configured_schema_folder() {
         let temp_dir = TempDir::new().unwrap();
@@ -339,6 +752,152 @@ mod tests {
         RouteManager::from_dir(temp_dir.path().to_str().unwrap(), None);
     }
 
+    #[test]
+    fn load_seeds_parses_json_seeds_of_rest_routes() {
+        let temp_dir = TempDir::new().unwrap();
+        for folder in ["orders", "broken", "generated"] {
+            std::fs::create_dir(temp_dir.path().join(folder)).unwrap();
+        }
+        std::fs::write(
+            temp_dir.path().join("orders").join("rest.json"),
+            r#"[{"id":"o1"}]"#,
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("broken").join("rest.json"), "[").unwrap();
+        std::fs::write(
+            temp_dir.path().join("generated").join("rest.jgd"),
+            r#"{"root":{"fields":{"id":"${ulid}"},"count":1}}"#,
+        )
+        .unwrap();
+
+        let mut manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), None);
+        manager.load_seeds();
+
+        let seeds = manager
+            .routes
+            .iter()
+            .filter_map(|route| match route {
+                Route::Rest(route_rest) => Some((
+                    route_rest.collection_name.as_str(),
+                    route_rest.seed.as_deref().cloned(),
+                )),
+                _ => None,
+            })
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(seeds["orders"], Some(serde_json::json!([{ "id": "o1" }])));
+        assert_eq!(seeds["broken"], None);
+        assert_eq!(seeds["generated"], None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn discover_loads_non_utf8_directories() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let temp_dir = TempDir::new().unwrap();
+        let cafe = temp_dir.path().join(OsStr::from_bytes(b"caf\xe9"));
+        std::fs::create_dir(&cafe).unwrap();
+        std::fs::write(cafe.join("get.json"), "{}").unwrap();
+        std::fs::write(cafe.join("config.toml"), "[route]\nprotect = true\n").unwrap();
+
+        let manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), None);
+
+        assert_eq!(manager.routes.len(), 1);
+        let Route::Basic(route) = &manager.routes[0] else {
+            panic!("Expected Route::Basic");
+        };
+        assert_eq!(route.route, "/caf\u{fffd}");
+        assert!(route.is_protected);
+    }
+
+    #[test]
+    fn discover_overlays_the_profile_configs_in_nested_folders() {
+        let temp_dir = TempDir::new().unwrap();
+        let admin = temp_dir.path().join("users/admin");
+        std::fs::create_dir_all(&admin).unwrap();
+        std::fs::write(admin.join("get.json"), "{}").unwrap();
+        std::fs::write(
+            admin.join("config.staging.toml"),
+            "[route]\nprotect = true\n",
+        )
+        .unwrap();
+
+        let discover = |profile: Option<&str>| {
+            let manager = RouteManager::discover(
+                temp_dir.path().to_str().unwrap(),
+                Some(Config {
+                    server: Some(crate::ServerConfig {
+                        profile: profile.map(str::to_string),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+            );
+            let Route::Basic(route) = &manager.routes[0] else {
+                panic!("Expected Route::Basic");
+            };
+            route.is_protected
+        };
+
+        assert!(discover(Some("staging")));
+        assert!(!discover(Some("local")));
+        assert!(!discover(None));
+    }
+
+    #[test]
+    fn conflicts_name_the_file_serving_each_overlapped_route() {
+        let route = |method: &str, path: &str| crate::app::RegisteredRoute {
+            method: method.to_string(),
+            path: path.to_string(),
+            is_protected: false,
+        };
+        let entry = |method: &str, path: &str| RouteEntry {
+            method: method.to_string(),
+            path: path.to_string(),
+            source: "users/get.json".to_string(),
+            kind: "basic".to_string(),
+            protected: false,
+            delay: None,
+        };
+        let overlaps = [
+            RouteOverlap {
+                route: route("GET", "/users/{name}"),
+                earlier: route("GET", "/users/{id}"),
+            },
+            RouteOverlap {
+                route: route("GET", "/health"),
+                earlier: route("GET", "/health"),
+            },
+        ];
+
+        assert_eq!(
+            describe_conflicts(&overlaps, "users.json", &[entry("GET", "/users/{id}")]),
+            ["GET /users/{name} from users.json duplicates GET /users/{id} from users/get.json"]
+        );
+    }
+
+    #[test]
+    fn make_routes_skips_routes_overlapping_an_earlier_file() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("users")).unwrap();
+        std::fs::write(temp_dir.path().join("users").join("get.json"), "{}").unwrap();
+        std::fs::write(temp_dir.path().join("users").join("post.json"), "{}").unwrap();
+        std::fs::write(temp_dir.path().join("users.json"), "{}").unwrap();
+        std::fs::write(temp_dir.path().join("health.json"), "{}").unwrap();
+
+        let manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), None);
+        let mut app = App::default();
+        manager.make_routes(&mut app);
+
+        let mut registered = app
+            .registered_routes()
+            .iter()
+            .map(|route| format!("{} {}", route.method, route.path))
+            .collect::<Vec<_>>();
+        registered.sort();
+        assert_eq!(registered, ["GET /health", "GET /users", "POST /users"]);
+    }
+
     #[test]
     fn make_routes_registers_loaded_routes() {
         let temp_dir = TempDir::new().unwrap();
@@ -359,4 +918,204 @@ mod tests {
 
         assert!(app.pages.lock().unwrap().render_index().contains("GET"));
     }
+
+    #[tokio::test]
+    async fn query_variants_are_served_by_a_single_route() {
+        use axum::{
+            body::{Body, to_bytes},
+            http::Request,
+        };
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let items = temp_dir.path().join("items");
+        std::fs::create_dir(&items).unwrap();
+        std::fs::write(items.join("get?status=active.json"), r#""active""#).unwrap();
+        std::fs::write(items.join("get.json"), r#""default""#).unwrap();
+        std::fs::write(items.join("get?status=archived&page.json"), r#""page""#).unwrap();
+        let drafts = temp_dir.path().join("drafts");
+        std::fs::create_dir(&drafts).unwrap();
+        std::fs::write(drafts.join("get?mine=true.json"), r#""mine""#).unwrap();
+
+        let manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), None);
+        assert_eq!(manager.routes.len(), 2);
+        let Some(Route::Basic(route_basic)) = manager
+            .routes
+            .iter()
+            .find(|route| matches!(route, Route::Basic(route) if route.route == "/items"))
+        else {
+            panic!("Expected the /items route");
+        };
+        assert!(route_basic.query.is_empty());
+        assert_eq!(route_basic.variants.len(), 2);
+
+        let mut app = App::default();
+        manager.make_routes(&mut app);
+        let router = app.take_router_for_test();
+        for (uri, expected) in [
+            ("/items", Some("default")),
+            ("/items?status=active", Some("active")),
+            ("/items?status=archived", Some("default")),
+            ("/items?status=archived&page=2", Some("page")),
+            ("/drafts?mine=true", Some("mine")),
+            ("/drafts", None),
+        ] {
+            let response = router
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            match expected {
+                Some(expected) => {
+                    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                    assert_eq!(body, format!(r#""{}""#, expected), "{}", uri);
+                }
+                None => assert_eq!(response.status(), http::StatusCode::NOT_FOUND, "{}", uri),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn cookie_variants_are_selected_and_cookies_are_set() {
+        use axum::{
+            body::{Body, to_bytes},
+            http::Request,
+        };
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let flags = temp_dir.path().join("flags");
+        std::fs::create_dir(&flags).unwrap();
+        std::fs::write(flags.join("get.json"), r#""a""#).unwrap();
+        std::fs::write(flags.join("get?cookie:ab=b.json"), r#""b""#).unwrap();
+        std::fs::write(
+            flags.join("config.toml"),
+            "[[route.cookies]]\nname = \"node\"\nvalue = \"2\"\nhttp_only = true\n",
+        )
+        .unwrap();
+
+        let manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), None);
+        let mut app = App::default();
+        manager.make_routes(&mut app);
+        let router = app.take_router_for_test();
+        for (cookie, expected) in [("ab=a", "a"), ("theme=dark; ab=b", "b"), ("", "a")] {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/flags")
+                        .header(http::header::COOKIE, cookie)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                response.headers()[http::header::SET_COOKIE],
+                "node=2; HttpOnly"
+            );
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(body, format!(r#""{}""#, expected), "{}", cookie);
+        }
+    }
+
+    #[tokio::test]
+    async fn sequences_walk_their_steps_until_reset() {
+        use axum::{
+            body::{Body, to_bytes},
+            http::Request,
+        };
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let jobs = temp_dir.path().join("jobs");
+        std::fs::create_dir(&jobs).unwrap();
+        std::fs::write(jobs.join("get.rest.json"), r#""done""#).unwrap();
+        std::fs::write(jobs.join("get.2.json"), r#""processing""#).unwrap();
+        std::fs::write(jobs.join("get.1.json"), r#""pending""#).unwrap();
+
+        let manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), None);
+        assert_eq!(manager.routes.len(), 1);
+
+        let mut app = App::default();
+        manager.make_routes(&mut app);
+        app.build_sequences_route();
+        let router = app.take_router_for_test();
+        let call = |method: &str, uri: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let mut bodies = vec![];
+        for _ in 0..4 {
+            bodies.push(call("GET", "/jobs").await);
+        }
+        assert_eq!(
+            bodies,
+            vec![r#""pending""#, r#""processing""#, r#""done""#, r#""done""#]
+        );
+
+        assert_eq!(
+            call("POST", "/mock-server/sequences/reset").await,
+            r#"{"reset":1}"#
+        );
+        assert_eq!(call("GET", "/jobs").await, r#""pending""#);
+    }
+
+    #[tokio::test]
+    async fn on_conflict_keeps_the_first_or_last_file_or_refuses_to_start() {
+        use crate::{check::quietly, route_builder::config::ServerConfig};
+        use axum::{
+            body::{Body, to_bytes},
+            http::Request,
+        };
+        use tower::ServiceExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("users.json"), r#""users""#).unwrap();
+        std::fs::write(temp_dir.path().join("people.json"), r#""people""#).unwrap();
+        std::fs::write(
+            temp_dir.path().join("people.toml"),
+            "[route]\nremap = \"/users\"\n",
+        )
+        .unwrap();
+        let serve = |on_conflict: Option<OnConflict>| {
+            let config = Config {
+                server: Some(ServerConfig {
+                    on_conflict,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let manager = RouteManager::discover(temp_dir.path().to_str().unwrap(), Some(config));
+            let mut app = App::default();
+            app.quiet = true;
+            quietly(|| manager.make_routes(&mut app)).map(|_| app.take_router_for_test())
+        };
+        let body = |router: axum::Router| async move {
+            let request = Request::get("/users").body(Body::empty()).unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+
+        let first = body(serve(None).unwrap()).await;
+        let last = body(serve(Some(OnConflict::Last)).unwrap()).await;
+        assert_ne!(first, last);
+        assert_eq!(body(serve(Some(OnConflict::First)).unwrap()).await, first);
+
+        let err = serve(Some(OnConflict::Error)).err().unwrap();
+        assert!(err.contains("people.json"), "{}", err);
+        assert!(err.contains("users.json"), "{}", err);
+        assert_eq!(route_shape("/users/{id}/{*rest}"), "/users/{}/{*}");
+    }
 }
@@ -48,7 +48,7 @@ impl RouteParams {
         let is_dir = entry.file_type().unwrap().is_dir();
 
         let full_route = if is_dir {
-            let config_store = ConfigStore::try_from_dir(entry.path().to_str().unwrap())
+            let config_store = ConfigStore::try_from_dir(entry.path(), config_store.profile())
                 .unwrap_or_else(|_| {
                     println!("Unable to read configs from folder {:?}", entry.path());
                     ConfigStore::default()
@@ -61,6 +61,12 @@ impl RouteParams {
                 effective_config = config.merge_with_ref(&effective_config);
             }
             let end_point = file_name.replace("$", "");
+            // `{**}` is shorthand for a catch-all `{*path}` segment.
+            let end_point = if end_point == "{**}" {
+                "{*path}".to_string()
+            } else {
+                end_point
+            };
             format!("{}/{}", parent_route, end_point)
         } else {
             if let Some(config) = config_store.get(&file_stem) {
@@ -468,6 +474,30 @@ mod tests {
         assert!(!params.is_dir);
     }
 
+    #[test]
+    fn test_new_with_wildcard_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        for dir_name in ["{**}", "{*key}"] {
+            fs::create_dir(temp_dir.path().join(dir_name)).unwrap();
+        }
+
+        let mut routes = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| {
+                RouteParams::new(
+                    "/files",
+                    &entry.unwrap(),
+                    Config::default(),
+                    &ConfigStore::default(),
+                )
+                .full_route
+            })
+            .collect::<Vec<_>>();
+        routes.sort();
+
+        assert_eq!(routes, ["/files/{*key}", "/files/{*path}"]);
+    }
+
     #[test]
     fn test_new_with_nested_protected_structure() {
         let temp_dir = TempDir::new().unwrap();
@@ -2,7 +2,7 @@ use std::ffi::OsString;
 
 use crate::{
     app::App,
-    route_builder::{PrintRoute, Route, RouteGenerator, route_params::RouteParams},
+    route_builder::{Route, RouteGenerator, config::PublicConfig, route_params::RouteParams},
 };
 
 /// Public static directory route generated from a `public` folder.
@@ -14,6 +14,8 @@ pub struct RoutePublic {
     pub route: String,
     /// Whether this route requires auth middleware.
     pub is_protected: bool,
+    /// SPA fallback, index, and cache header options.
+    pub options: PublicConfig,
 }
 
 static PUBLIC_ROUTE_NAME: &str = "public";
@@ -43,6 +45,7 @@ impl RoutePublic {
                 path: route_params.file_path,
                 route,
                 is_protected: false,
+                options: config.public.unwrap_or_default(),
             };
 
             return Route::Public(route_public);
@@ -54,17 +57,7 @@ impl RoutePublic {
 
 impl RouteGenerator for RoutePublic {
     fn make_routes(&self, app: &mut App) {
-        app.build_public_router_v2(&self.path, &self.route);
-    }
-}
-
-impl PrintRoute for RoutePublic {
-    fn println(&self) {
-        println!(
-            "✔️ Built public routes from folder {} to {}",
-            self.path.to_string_lossy(),
-            self.route
-        );
+        app.build_public_router_v2(&self.path, &self.route, &self.options);
     }
 }
 
@@ -130,10 +123,10 @@ mod tests {
             path: public.into_os_string(),
             route: "/assets".to_string(),
             is_protected: false,
+            options: PublicConfig::default(),
         };
         let mut app = App::default();
         route_public.make_routes(&mut app);
-        route_public.println();
     }
 
     #[test]
@@ -1,13 +1,21 @@
-use std::ffi::OsString;
+use std::{collections::HashMap, ffi::OsString, fs, path::Path, sync::Arc};
 
 use fosk::IdType;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde_json::Value;
 
 use crate::{
     app::App,
-    handlers::build_rest_routes,
-    route_builder::{PrintRoute, Route, RouteGenerator, route_params::RouteParams},
+    handlers::{
+        FieldDefaults, IdGenerator, IdStrategy, ResponseTransform, build_rest_routes, is_jgd,
+    },
+    route_builder::{
+        Route, RouteGenerator,
+        config::{Delay, OnDelete},
+        route_params::RouteParams,
+    },
+    seed_stream::SEED_PRELOAD_LIMIT,
 };
 
 static RE_FILE_REST: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\$)?rest(\{(.+)\})?$").unwrap());
@@ -29,9 +37,27 @@ pub struct RouteRest {
     /// Fosk collection name backing this route.
     pub collection_name: String,
     /// Optional response delay in milliseconds.
-    pub delay: Option<u16>,
+    pub delay: Option<Delay>,
     /// Whether this route requires auth middleware.
     pub is_protected: bool,
+    /// Fields referencing other collections, mapped to the referenced collection name.
+    pub refs: HashMap<String, String>,
+    /// Behavior applied to this collection's items when a referenced item is deleted.
+    pub on_delete: OnDelete,
+    /// Default and computed fields injected into written items.
+    pub field_defaults: FieldDefaults,
+    /// Whether DELETE marks items as deleted instead of removing them.
+    pub soft_delete: bool,
+    /// Whether `?where=` string comparisons ignore case by default.
+    pub case_insensitive: bool,
+    /// Whether items carry HAL `_links`.
+    pub links: bool,
+    /// Whether only the read routes are registered.
+    pub read_only: bool,
+    /// Renames, mappings, and masking applied to response items.
+    pub transform: ResponseTransform,
+    /// JSON seed parsed ahead of registration by [`RouteRest::load_seed`].
+    pub seed: Option<Arc<Value>>,
 }
 
 impl RouteRest {
@@ -43,7 +69,7 @@ impl RouteRest {
         id_type: IdType,
         is_protected: bool,
         collection_name: String,
-        delay: Option<u16>,
+        delay: Option<Delay>,
     ) -> Self {
         Self {
             route,
@@ -53,6 +79,15 @@ impl RouteRest {
             is_protected,
             collection_name,
             delay,
+            refs: HashMap::new(),
+            on_delete: OnDelete::default(),
+            field_defaults: FieldDefaults::default(),
+            soft_delete: false,
+            case_insensitive: false,
+            links: false,
+            read_only: false,
+            transform: ResponseTransform::default(),
+            seed: None,
         }
     }
 
@@ -63,7 +98,7 @@ impl RouteRest {
             // Single value like "uuid", "int", "id", "_id"
             let part = parts[0];
             match part {
-                "none" => ("id", IdType::None),
+                "none" | "ulid" | "nanoid" | "snowflake" => ("id", IdType::None),
                 "uuid" => ("id", IdType::Uuid),
                 "int" => ("id", IdType::Int),
                 id_key => (id_key, IdType::Uuid), // Default fallback
@@ -73,7 +108,7 @@ impl RouteRest {
             let id_key = parts[0];
             let type_str = parts[1];
             let id_type = match type_str {
-                "none" => IdType::None,
+                "none" | "ulid" | "nanoid" | "snowflake" => IdType::None,
                 "uuid" => IdType::Uuid,
                 "int" => IdType::Int,
                 _ => IdType::Uuid, // Default to UUID
@@ -85,6 +120,17 @@ impl RouteRest {
         }
     }
 
+    /// Returns the generated id format named by the descriptor's type, such as
+    /// `ulid` in `rest{id-ulid}` or `rest{ulid}`.
+    fn get_id_strategy(descriptor: &str) -> Option<IdStrategy> {
+        match descriptor.rsplit('-').next()? {
+            "ulid" => Some(IdStrategy::Ulid),
+            "nanoid" => Some(IdStrategy::Nanoid),
+            "snowflake" => Some(IdStrategy::Snowflake),
+            _ => None,
+        }
+    }
+
     /// Parses route parameters as a REST collection route definition.
     pub fn try_parse(route_params: RouteParams) -> Route {
         if let Some(captures) = RE_FILE_REST.captures(&route_params.file_stem) {
@@ -104,13 +150,42 @@ impl RouteRest {
             let (id_key, id_type) = Self::get_rest_options(descriptor);
 
             let id_key = collection_config.id_key.unwrap_or(id_key.to_string());
-            let id_type = collection_config.id_type.unwrap_or(id_type);
+            // Generated ids are assigned before fosk sees the item.
+            let id_strategy = collection_config
+                .id_strategy
+                .as_deref()
+                .and_then(IdStrategy::parse)
+                .or_else(|| Self::get_id_strategy(descriptor));
+            let id_type = match id_strategy {
+                Some(_) => IdType::None,
+                None => collection_config.id_type.unwrap_or(id_type),
+            };
 
             let route = route_config.remap.unwrap_or(route_params.full_route);
             let collection_name = collection_config
                 .name
                 .unwrap_or_else(|| route.split('/').next_back().unwrap().to_string());
 
+            let refs = collection_config.refs.unwrap_or_default();
+            let on_delete = collection_config.on_delete.unwrap_or_default();
+            let field_defaults = FieldDefaults {
+                defaults: collection_config.defaults.unwrap_or_default(),
+                computed: collection_config.computed.unwrap_or_default(),
+                id: id_strategy.map(|strategy| IdGenerator::new(&id_key, strategy)),
+            };
+            let soft_delete = collection_config.soft_delete.unwrap_or(false);
+            let case_insensitive = collection_config.case_insensitive.unwrap_or(false);
+            let links = collection_config.links.unwrap_or(false);
+            let read_only = collection_config.read_only.unwrap_or(false);
+            let transform = collection_config
+                .transform
+                .map(|transform| ResponseTransform {
+                    map: transform.map.unwrap_or_default(),
+                    mask: transform.mask.unwrap_or_default(),
+                    rename: transform.rename.unwrap_or_default(),
+                })
+                .unwrap_or_default();
+
             let route_rest = Self {
                 path: route_params.file_path,
                 route,
@@ -119,6 +194,15 @@ impl RouteRest {
                 collection_name,
                 delay,
                 is_protected,
+                refs,
+                on_delete,
+                field_defaults,
+                soft_delete,
+                case_insensitive,
+                links,
+                read_only,
+                transform,
+                seed: None,
             };
 
             return Route::Rest(route_rest);
@@ -128,15 +212,52 @@ impl RouteRest {
     }
 }
 
-impl RouteGenerator for RouteRest {
-    fn make_routes(&self, app: &mut App) {
-        build_rest_routes(app, self);
+impl RouteRest {
+    /// Reads and parses the JSON seed file, so registration only loads the
+    /// parsed items. JGD templates are left to registration, where seeded runs
+    /// draw from the random source in order, and seeds larger than
+    /// [`SEED_PRELOAD_LIMIT`] are streamed there. A seed that cannot be read or
+    /// parsed is left to registration, which reports the error.
+    pub fn load_seed(&mut self) {
+        let too_large = fs::metadata(&self.path)
+            .map(|metadata| metadata.len() > SEED_PRELOAD_LIMIT)
+            .unwrap_or(true);
+        if is_jgd(&self.path) || too_large {
+            return;
+        }
+        self.seed = fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .map(Arc::new);
+    }
+
+    /// Describes filename descriptor parts that `try_parse` ignores, such as an
+    /// unknown id type, or returns `None` when the descriptor is valid.
+    pub fn descriptor_problem(&self) -> Option<String> {
+        let file_name = Path::new(&self.path).file_name()?.to_string_lossy();
+        let file_stem = file_name.split('.').next()?;
+        let descriptor = RE_FILE_REST
+            .captures(file_stem)?
+            .get(ELEMENT_DESCRIPTOR)?
+            .as_str();
+
+        match descriptor.split('-').collect::<Vec<_>>().as_slice() {
+            [_] | [_, "none" | "uuid" | "int" | "ulid" | "nanoid" | "snowflake"] => None,
+            [_, id_type] => Some(format!(
+                "unknown id type `{}` in `{}`, using uuid",
+                id_type, file_name
+            )),
+            _ => Some(format!(
+                "descriptor `{}` in `{}` should be `key` or `key-type`, using id-uuid",
+                descriptor, file_name
+            )),
+        }
     }
 }
 
-impl PrintRoute for RouteRest {
-    fn println(&self) {
-        println!("✔️ Built REST routes for {}", self.route);
+impl RouteGenerator for RouteRest {
+    fn make_routes(&self, app: &mut App) {
+        build_rest_routes(app, self);
     }
 }
 
@@ -201,7 +322,6 @@ mod tests {
         );
         let mut app = App::default();
         route_rest.make_routes(&mut app);
-        route_rest.println();
         assert!(app.pages.lock().unwrap().render_index().contains("/items"));
     }
 
@@ -349,6 +469,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_parse_rest_with_id_strategy_descriptor() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_file(temp_dir.path(), "rest{_id-ulid}.json");
+        let route_params = RouteParams::new(
+            "/api/items",
+            &entry,
+            Config::default().with_protect(false),
+            &ConfigStore::default(),
+        );
+
+        let Route::Rest(route_rest) = RouteRest::try_parse(route_params) else {
+            panic!("Expected Route::Rest");
+        };
+        assert_eq!(route_rest.id_key, "_id");
+        assert_eq!(route_rest.id_type, IdType::None);
+        assert_eq!(
+            route_rest.field_defaults.id,
+            Some(IdGenerator::new("_id", IdStrategy::Ulid))
+        );
+        assert_eq!(route_rest.descriptor_problem(), None);
+    }
+
+    #[test]
+    fn test_try_parse_rest_with_configured_id_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_file(temp_dir.path(), "rest{int}.json");
+        let config = Config {
+            collection: Some(crate::route_builder::config::CollectionConfig {
+                id_strategy: Some("usr_{{seq}}".to_string()),
+                ..Default::default()
+            }),
+            ..Config::default()
+        };
+        let route_params = RouteParams::new("/api/users", &entry, config, &ConfigStore::default());
+
+        let Route::Rest(route_rest) = RouteRest::try_parse(route_params) else {
+            panic!("Expected Route::Rest");
+        };
+        assert_eq!(route_rest.id_type, IdType::None);
+        assert_eq!(
+            route_rest.field_defaults.id,
+            Some(IdGenerator::new(
+                "id",
+                IdStrategy::Template("usr_{{seq}}".to_string())
+            ))
+        );
+    }
+
     #[test]
     fn test_try_parse_rest_with_custom_key_uuid_type() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,140 @@
+use std::fmt::Display;
+
+/// Pattern-based route rewrite declared as `from -> to`, such as `/api/* -> /v2/*`.
+///
+/// A `*` segment in `from` matches exactly one path segment, or every remaining
+/// segment when it is the last one. Each `*` segment in `to` is replaced by the
+/// matching capture, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteRewrite {
+    from: Vec<String>,
+    to: Vec<String>,
+}
+
+impl RouteRewrite {
+    /// Parses a `from -> to` rule, prefixing both sides with `parent_route`.
+    pub fn parse(rule: &str, parent_route: &str) -> Result<Self, String> {
+        let Some((from, to)) = rule.split_once("->") else {
+            return Err(format!(
+                "rewrite `{}` must look like `/from/* -> /to/*`",
+                rule
+            ));
+        };
+
+        let from = segments(&format!("{}{}", parent_route, from.trim()));
+        let to = segments(&format!("{}{}", parent_route, to.trim()));
+        if from.is_empty() {
+            return Err(format!("rewrite `{}` must match a route", rule));
+        }
+        let wildcards = |segments: &[String]| segments.iter().filter(|s| *s == "*").count();
+        if wildcards(&to) > wildcards(&from) {
+            return Err(format!(
+                "rewrite `{}` uses more `*` in its target than in its pattern",
+                rule
+            ));
+        }
+
+        Ok(Self { from, to })
+    }
+
+    /// Returns the rewritten route, or `None` when `route` does not match.
+    pub fn apply(&self, route: &str) -> Option<String> {
+        let route = segments(route);
+        let mut captures = vec![];
+
+        for (index, pattern) in self.from.iter().enumerate() {
+            let is_last = index == self.from.len() - 1;
+            match (pattern.as_str(), route.get(index)) {
+                ("*", _) if is_last => {
+                    captures.push(route[index..].join("/"));
+                    break;
+                }
+                ("*", Some(segment)) => captures.push(segment.clone()),
+                (pattern, Some(segment)) if pattern == segment => {}
+                _ => return None,
+            }
+            if is_last && route.len() > self.from.len() {
+                return None;
+            }
+        }
+
+        let mut captures = captures.into_iter();
+        let rewritten = self
+            .to
+            .iter()
+            .map(|segment| match segment.as_str() {
+                "*" => captures.next().unwrap_or_default(),
+                _ => segment.clone(),
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        Some(format!("/{}", rewritten.join("/")))
+    }
+}
+
+impl Display for RouteRewrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{} -> /{}", self.from.join("/"), self.to.join("/"))
+    }
+}
+
+fn segments(route: &str) -> Vec<String> {
+    route
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_wildcard_rewrites_a_whole_subtree() {
+        let rewrite = RouteRewrite::parse("/api/* -> /v2/*", "").unwrap();
+
+        assert_eq!(rewrite.apply("/api"), Some("/v2".to_string()));
+        assert_eq!(rewrite.apply("/api/users"), Some("/v2/users".to_string()));
+        assert_eq!(
+            rewrite.apply("/api/users/{id}"),
+            Some("/v2/users/{id}".to_string())
+        );
+        assert_eq!(rewrite.apply("/apis/users"), None);
+        assert_eq!(rewrite.apply("/other/api"), None);
+    }
+
+    #[test]
+    fn inner_wildcards_match_one_segment_and_are_reordered() {
+        let rewrite = RouteRewrite::parse("/*/items/* -> /items/*/*", "").unwrap();
+
+        assert_eq!(
+            rewrite.apply("/shop/items/{id}"),
+            Some("/items/shop/{id}".to_string())
+        );
+        assert_eq!(rewrite.apply("/shop/orders/1"), None);
+
+        let exact = RouteRewrite::parse("/*/health -> /status", "").unwrap();
+        assert_eq!(exact.apply("/a/health"), Some("/status".to_string()));
+        assert_eq!(exact.apply("/a/health/deep"), None);
+    }
+
+    #[test]
+    fn parent_route_prefixes_both_sides() {
+        let rewrite = RouteRewrite::parse("/api/* -> /v2/*", "/billing").unwrap();
+
+        assert_eq!(
+            rewrite.apply("/billing/api/invoices"),
+            Some("/billing/v2/invoices".to_string())
+        );
+        assert_eq!(rewrite.to_string(), "/billing/api/* -> /billing/v2/*");
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(RouteRewrite::parse("/api/*", "").is_err());
+        assert!(RouteRewrite::parse("/api -> /v2/*", "").is_err());
+        assert!(RouteRewrite::parse("/ -> /v2", "").is_err());
+    }
+}
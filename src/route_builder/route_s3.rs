@@ -0,0 +1,156 @@
+use std::ffi::OsString;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    app::App,
+    handlers::build_s3_routes,
+    route_builder::{Route, RouteGenerator, config::Delay, route_params::RouteParams},
+};
+
+static RE_DIR_S3: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\{s3\}(-(.+))?$").unwrap());
+
+const ELEMENT_ROUTE: usize = 2;
+
+/// Path parameter used by generated S3 bucket routes.
+pub const BUCKET_PARAM: &str = "{bucket}";
+/// Path parameter used by generated S3 object routes.
+pub const OBJECT_KEY_PARAM: &str = "{*key}";
+
+/// S3-compatible object storage route set generated from a `{s3}` directory.
+///
+/// Each subdirectory is exposed as a bucket and each file inside it as an
+/// object, using path-style addressing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteS3 {
+    /// Storage directory path.
+    pub path: OsString,
+    /// Base route acting as the S3 endpoint.
+    pub route: String,
+    /// Optional response delay in milliseconds.
+    pub delay: Option<Delay>,
+}
+
+impl RouteS3 {
+    /// Returns whether a directory name declares an S3 storage route, such as `{s3}-storage`.
+    pub fn is_s3_dir(name: &str) -> bool {
+        RE_DIR_S3.is_match(name)
+    }
+
+    /// Parses route parameters as an S3 storage directory route definition.
+    pub fn try_parse(route_params: RouteParams) -> Route {
+        if let Some(captures) = RE_DIR_S3.captures(&route_params.file_name) {
+            let config = route_params.config.clone();
+            let route_config = config.route.clone().unwrap_or_default();
+
+            let s3_route = if let Some(route) = captures.get(ELEMENT_ROUTE) {
+                route.as_str()
+            } else {
+                "s3"
+            };
+
+            let route = route_config
+                .remap
+                .unwrap_or(format!("{}/{}", route_params.parent_route, s3_route));
+
+            let route_s3 = Self {
+                path: route_params.file_path,
+                route,
+                delay: route_config.delay,
+            };
+
+            return Route::S3(route_s3);
+        }
+
+        Route::None
+    }
+
+    /// Returns the generated bucket route with a `{bucket}` path parameter.
+    pub fn get_bucket_route(&self) -> String {
+        format!("{}/{}", self.route, BUCKET_PARAM)
+    }
+
+    /// Returns the generated object route with `{bucket}` and `{*key}` path parameters.
+    pub fn get_object_route(&self) -> String {
+        format!("{}/{}", self.get_bucket_route(), OBJECT_KEY_PARAM)
+    }
+}
+
+impl RouteGenerator for RouteS3 {
+    fn make_routes(&self, app: &mut App) {
+        build_s3_routes(app, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::{Config, ConfigStore, RouteConfig};
+    use tempfile::TempDir;
+
+    fn dir_entry(dir: &std::path::Path, name: &str) -> std::fs::DirEntry {
+        std::fs::create_dir(dir.join(name)).unwrap();
+        std::fs::read_dir(dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .find(|entry| entry.file_name() == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn try_parse_accepts_s3_folders_with_optional_route_name() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let entry = dir_entry(temp_dir.path(), "{s3}");
+        let params = RouteParams::new("/api", &entry, Config::default(), &ConfigStore::default());
+        match RouteS3::try_parse(params) {
+            Route::S3(route) => {
+                assert_eq!(route.route, "/api/s3");
+                assert_eq!(route.get_bucket_route(), "/api/s3/{bucket}");
+                assert_eq!(route.get_object_route(), "/api/s3/{bucket}/{*key}");
+            }
+            _ => panic!("Expected Route::S3"),
+        }
+
+        let entry = dir_entry(temp_dir.path(), "{s3}-storage");
+        let params = RouteParams::new("", &entry, Config::default(), &ConfigStore::default());
+        match RouteS3::try_parse(params) {
+            Route::S3(route) => assert_eq!(route.route, "/storage"),
+            _ => panic!("Expected Route::S3"),
+        }
+    }
+
+    #[test]
+    fn try_parse_uses_route_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = dir_entry(temp_dir.path(), "{s3}");
+        let config = Config {
+            route: Some(RouteConfig {
+                remap: Some("/minio".to_string()),
+                delay: Some(Delay::Fixed(10)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let params = RouteParams::new("/api", &entry, config, &ConfigStore::default());
+
+        match RouteS3::try_parse(params) {
+            Route::S3(route) => {
+                assert_eq!(route.route, "/minio");
+                assert_eq!(route.delay, Some(Delay::Fixed(10)));
+            }
+            _ => panic!("Expected Route::S3"),
+        }
+    }
+
+    #[test]
+    fn try_parse_rejects_other_folders() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["s3", "{s3}x", "{upload}"] {
+            let entry = dir_entry(temp_dir.path(), name);
+            let params = RouteParams::new("", &entry, Config::default(), &ConfigStore::default());
+            assert_eq!(RouteS3::try_parse(params), Route::None);
+        }
+    }
+}
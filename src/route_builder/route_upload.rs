@@ -5,7 +5,11 @@ use regex::Regex;
 
 use crate::{
     handlers::build_upload_routes,
-    route_builder::{PrintRoute, Route, RouteGenerator, route_params::RouteParams},
+    route_builder::{
+        Route, RouteGenerator,
+        config::{Delay, Retention},
+        route_params::RouteParams,
+    },
 };
 
 static RE_DIR_UPLOAD: Lazy<Regex> =
@@ -30,16 +34,46 @@ pub struct RouteUpload {
     /// Whether this route requires auth middleware.
     pub is_protected: bool,
     /// Optional response delay in milliseconds.
-    pub delay: Option<u16>,
+    pub delay: Option<Delay>,
     /// Optional upload endpoint suffix.
     pub upload_endpoint: Option<String>,
     /// Optional download endpoint suffix.
     pub download_endpoint: Option<String>,
     /// Optional list-files endpoint suffix.
     pub list_files_endpoint: Option<String>,
+    /// Whether resumable chunked upload routes are registered.
+    pub is_chunked: bool,
+    /// Optional maximum chunk size in bytes for chunked uploads.
+    pub max_chunk_size: Option<u64>,
+    /// Optional download bandwidth limit in kilobytes per second.
+    pub download_rate: Option<u32>,
+    /// Whether downloads are served inline instead of as attachments.
+    pub is_inline_download: bool,
+    /// How long uploaded files are kept.
+    pub retention: Option<Retention>,
+    /// Simulated virus scan of uploaded files, from the `scan` keys.
+    pub scan: Option<UploadScan>,
+}
+
+/// Simulated virus scan run after each upload.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UploadScan {
+    /// Milliseconds before the verdict.
+    pub delay: u64,
+    /// `*` and `?` patterns of the file names reported as infected.
+    pub infected: Vec<String>,
+    /// Percentage of the other files reported as infected.
+    pub infected_percent: u32,
+    /// URL receiving the verdicts.
+    pub webhook: Option<String>,
 }
 
 impl RouteUpload {
+    /// Returns whether a directory name declares an upload route, such as `${upload}{temp}-files`.
+    pub fn is_upload_dir(name: &str) -> bool {
+        RE_DIR_UPLOAD.is_match(name)
+    }
+
     /// Parses route parameters as an upload directory route definition.
     pub fn try_parse(route_params: RouteParams) -> Route {
         if let Some(captures) = RE_DIR_UPLOAD.captures(&route_params.file_name) {
@@ -50,10 +84,23 @@ impl RouteUpload {
             // From config
             let delay = route_config.delay;
             let is_protected = route_config.protect.unwrap_or(false);
-            let is_temporary = upload_config.temporary.unwrap_or(false);
+            let retention = upload_config.retention;
+            let is_temporary = upload_config.temporary.unwrap_or(false) || retention.is_some();
             let upload_endpoint = upload_config.upload_endpoint;
             let download_endpoint = upload_config.download_endpoint;
             let list_files_endpoint = upload_config.list_files_endpoint;
+            let is_chunked = upload_config.chunked.unwrap_or(false);
+            let max_chunk_size = upload_config.max_chunk_size;
+            let download_rate = upload_config.download_rate.filter(|rate| *rate > 0);
+            let is_inline_download = upload_config
+                .download_disposition
+                .is_some_and(|disposition| disposition.eq_ignore_ascii_case("inline"));
+            let scan = upload_config.scan.unwrap_or(false).then(|| UploadScan {
+                delay: upload_config.scan_delay.unwrap_or(1000),
+                infected: upload_config.scan_infected.unwrap_or_default(),
+                infected_percent: upload_config.scan_infected_percent.unwrap_or(0).min(100),
+                webhook: upload_config.scan_webhook,
+            });
 
             // From file
             let is_protected = is_protected || captures.get(ELEMENT_IS_PROTECTED).is_some();
@@ -77,6 +124,12 @@ impl RouteUpload {
                 upload_endpoint,
                 download_endpoint,
                 list_files_endpoint,
+                is_chunked,
+                max_chunk_size,
+                download_rate,
+                is_inline_download,
+                retention,
+                scan,
             };
 
             return Route::Upload(route_upload);
@@ -111,6 +164,16 @@ impl RouteUpload {
     pub fn get_list_files_route(&self) -> String {
         self.get_route(&self.list_files_endpoint)
     }
+
+    /// Returns the generated metadata route with an `{id}` path parameter.
+    pub fn get_meta_route(&self) -> String {
+        format!("{}/meta/{{id}}", self.route)
+    }
+
+    /// Returns the generated chunked upload route with a `{file_name}` path parameter.
+    pub fn get_chunk_route(&self) -> String {
+        format!("{}/{}", self.get_upload_route(), FILE_NAME_PARAM)
+    }
 }
 
 impl RouteGenerator for RouteUpload {
@@ -122,28 +185,6 @@ impl RouteGenerator for RouteUpload {
     }
 }
 
-impl PrintRoute for RouteUpload {
-    fn println(&self) {
-        println!(
-            "✔️ Mapped uploads routes from folder {} to {}",
-            self.path.to_string_lossy(),
-            self.route
-        );
-        println!(
-            "   ├── upload route to     POST {}",
-            self.get_upload_route()
-        );
-        println!(
-            "   ├── download route to   GET {}",
-            self.get_download_route()
-        );
-        println!(
-            "   └── list files route to GET {}",
-            self.get_list_files_route()
-        );
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,10 +243,15 @@ mod tests {
             upload_endpoint: Some("/upload".to_string()),
             download_endpoint: Some("/download".to_string()),
             list_files_endpoint: Some("/list".to_string()),
+            is_chunked: true,
+            max_chunk_size: None,
+            download_rate: None,
+            is_inline_download: false,
+            retention: None,
+            scan: None,
         };
         let mut app = crate::app::App::default();
         route_upload.make_routes(&mut app);
-        route_upload.println();
         assert!(
             app.pages
                 .lock()
@@ -215,6 +261,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_parse_chunked_upload_from_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_dir(temp_dir.path(), "{upload}");
+        let config = Config::try_from("[upload]\nchunked = true\nmax_chunk_size = 512").unwrap();
+        let route_params = RouteParams::new("/api", &entry, config, &ConfigStore::default());
+
+        match RouteUpload::try_parse(route_params) {
+            Route::Upload(route_upload) => {
+                assert!(route_upload.is_chunked);
+                assert_eq!(route_upload.max_chunk_size, Some(512));
+                assert_eq!(route_upload.get_chunk_route(), "/api/upload/{file_name}");
+            }
+            _ => panic!("Expected Route::Upload"),
+        }
+    }
+
+    #[test]
+    fn test_try_parse_download_options_from_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = create_test_dir(temp_dir.path(), "{upload}");
+        let config =
+            Config::try_from("[upload]\ndownload_rate = 256\ndownload_disposition = \"inline\"")
+                .unwrap();
+        let route_params = RouteParams::new("/api", &entry, config, &ConfigStore::default());
+
+        match RouteUpload::try_parse(route_params) {
+            Route::Upload(route_upload) => {
+                assert_eq!(route_upload.download_rate, Some(256));
+                assert!(route_upload.is_inline_download);
+            }
+            _ => panic!("Expected Route::Upload"),
+        }
+    }
+
     #[test]
     fn test_try_parse_protected_upload_directory() {
         let temp_dir = TempDir::new().unwrap();
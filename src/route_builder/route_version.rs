@@ -0,0 +1,168 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use http::{HeaderName, HeaderValue};
+
+use crate::route_builder::config::VersioningConfig;
+
+/// Alias prefix segment serving the highest version of a resource.
+pub const LATEST_VERSION: &str = "latest";
+
+/// API version prefix discovered from a `vN` folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteVersion {
+    /// Version folder name, such as `v1`.
+    pub version: String,
+    /// Route prefix the version is served under, such as `/v1/users`.
+    pub route: String,
+    /// Prefix that serves the same routes, such as `/latest/users`.
+    pub alias: Option<String>,
+    /// `Deprecation` header value, an RFC 9745 date such as `@1735689600`.
+    pub deprecation: Option<String>,
+    /// `Sunset` header value, an HTTP date.
+    pub sunset: Option<String>,
+}
+
+impl RouteVersion {
+    /// Creates a version prefix and resolves its headers from the versioning config.
+    pub fn new(
+        version: &str,
+        route: String,
+        alias: Option<String>,
+        config: &VersioningConfig,
+    ) -> Self {
+        let date = |dates: &Option<std::collections::HashMap<String, String>>, header: &str| {
+            let value = dates.as_ref()?.get(version)?;
+            let date = parse_date(value);
+            if date.is_none() {
+                println!(
+                    "Ignoring {} date `{}` of version {}. Use YYYY-MM-DD or RFC 3339.",
+                    header, value, version
+                );
+            }
+            date
+        };
+
+        Self {
+            version: version.to_string(),
+            deprecation: date(&config.deprecated, "deprecation")
+                .map(|date| format!("@{}", date.timestamp())),
+            sunset: date(&config.sunset, "sunset")
+                .map(|date| date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+            route,
+            alias,
+        }
+    }
+
+    /// Returns true when `path` is the version prefix or a route below it.
+    pub fn contains(&self, path: &str) -> bool {
+        rebase(path, &self.route, "").is_some()
+    }
+
+    /// Returns `path` moved under the alias prefix, when the version has one.
+    pub fn alias_path(&self, path: &str) -> Option<String> {
+        rebase(path, &self.route, self.alias.as_deref()?)
+    }
+
+    /// Returns the deprecation headers sent by routes of this version.
+    pub fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        [("deprecation", &self.deprecation), ("sunset", &self.sunset)]
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_static(name),
+                    HeaderValue::from_str(value.as_deref()?).ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Returns the number of a version folder name such as `v2`.
+pub fn version_number(name: &str) -> Option<u32> {
+    let number = name.strip_prefix('v')?;
+    if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+fn rebase(path: &str, from: &str, to: &str) -> Option<String> {
+    let rest = path.strip_prefix(from)?;
+    (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", to, rest))
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn version_numbers_are_parsed_from_folder_names() {
+        assert_eq!(version_number("v1"), Some(1));
+        assert_eq!(version_number("v12"), Some(12));
+        assert_eq!(version_number("v"), None);
+        assert_eq!(version_number("v1beta"), None);
+        assert_eq!(version_number("users"), None);
+    }
+
+    #[test]
+    fn version_matches_its_routes_and_maps_them_to_the_alias() {
+        let version = RouteVersion::new(
+            "v2",
+            "/v2/users".to_string(),
+            Some("/latest/users".to_string()),
+            &VersioningConfig::default(),
+        );
+
+        assert!(version.contains("/v2/users"));
+        assert!(version.contains("/v2/users/{id}"));
+        assert!(!version.contains("/v2/users-archive"));
+        assert_eq!(
+            version.alias_path("/v2/users/{id}"),
+            Some("/latest/users/{id}".to_string())
+        );
+        assert!(version.headers().is_empty());
+    }
+
+    #[test]
+    fn deprecated_versions_send_deprecation_and_sunset_headers() {
+        let dates = |date: &str| Some(HashMap::from([("v1".to_string(), date.to_string())]));
+        let version = RouteVersion::new(
+            "v1",
+            "/v1/users".to_string(),
+            None,
+            &VersioningConfig {
+                deprecated: dates("2025-01-01"),
+                sunset: dates("2026-06-30T12:00:00Z"),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(version.deprecation.as_deref(), Some("@1735689600"));
+        assert_eq!(
+            version.sunset.as_deref(),
+            Some("Tue, 30 Jun 2026 12:00:00 GMT")
+        );
+        assert_eq!(version.headers().len(), 2);
+        assert_eq!(version.alias_path("/v1/users"), None);
+
+        let invalid = RouteVersion::new(
+            "v1",
+            "/v1/users".to_string(),
+            None,
+            &VersioningConfig {
+                sunset: dates("next year"),
+                ..Default::default()
+            },
+        );
+        assert_eq!(invalid.sunset, None);
+    }
+}
@@ -0,0 +1,652 @@
+//! Mock route files generated from captured traffic, used by the `import`
+//! command.
+//!
+//! A `.har` file exported from the browser developer tools lists every request
+//! of a session with its response. Each successful JSON, XML, HTML, or text
+//! response becomes a route file named after its method, path, and query, and
+//! a route answered differently over the session becomes a response sequence.
+//!
+//! Postman collections (v2.0 and v2.1) and Insomnia exports (v4) list saved
+//! requests instead. A Postman request becomes a route answering its first
+//! successful example response; path variables such as `:id` or `{{id}}`
+//! become `{id}` folders. Insomnia exports hold no responses, so their
+//! requests become routes answering an empty JSON object, ready to be filled.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use http::Uri;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Characters that cannot appear in route file names, or that the filename
+/// conventions read as parameters.
+const RESERVED_CHARACTERS: [char; 10] = ['<', '>', ':', '"', '\\', '|', '?', '*', '{', '}'];
+
+/// A response captured for a request, ready to become a route file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedResponse {
+    /// HTTP method, such as `GET`.
+    pub method: String,
+    /// Request path, such as `/api/users`.
+    pub path: String,
+    /// Request query string, without the leading `?`.
+    pub query: Option<String>,
+    /// Response `Content-Type`.
+    pub content_type: String,
+    /// Response body.
+    pub body: String,
+}
+
+/// Filters applied to captured requests.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportFilter {
+    /// Only import requests sent to this host.
+    pub host: Option<String>,
+    /// Only import requests whose path starts with this prefix.
+    pub prefix: Option<String>,
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    /// Route files written, relative to the mock folder.
+    pub written: Vec<PathBuf>,
+    /// Captured requests that were not imported: failed or binary responses,
+    /// paths that cannot be file names, and routes whose files already exist.
+    pub skipped: usize,
+}
+
+/// Returns the route file extension serving `content_type`, for the content
+/// types a mock file can hold as text.
+pub fn mock_file_extension(content_type: &str) -> Option<&'static str> {
+    let content_type = content_type.to_ascii_lowercase();
+    if content_type.contains("json") {
+        Some("json")
+    } else if content_type.contains("xml") {
+        Some("xml")
+    } else if content_type.starts_with("text/html") {
+        Some("html")
+    } else if content_type.starts_with("text/plain") {
+        Some("txt")
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+    response: HarResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarRequest {
+    method: String,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarResponse {
+    status: u16,
+    content: HarContent,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    #[serde(default)]
+    mime_type: String,
+    text: Option<String>,
+    encoding: Option<String>,
+}
+
+/// Reads the successful text responses of a `.har` file, in capture order.
+///
+/// Returns the responses and the number of entries left out by `filter` or
+/// because their response cannot be a route file.
+pub fn read_har(
+    path: &Path,
+    filter: &ImportFilter,
+) -> Result<(Vec<CapturedResponse>, usize), String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+    let har = serde_json::from_str::<Har>(&text)
+        .map_err(|err| format!("Invalid HAR file {}: {}", path.display(), err))?;
+
+    let total = har.log.entries.len();
+    let responses = har
+        .log
+        .entries
+        .into_iter()
+        .filter_map(|entry| captured_response(entry, filter))
+        .collect::<Vec<_>>();
+    let skipped = total - responses.len();
+    Ok((responses, skipped))
+}
+
+fn captured_response(entry: HarEntry, filter: &ImportFilter) -> Option<CapturedResponse> {
+    let uri = entry.request.url.parse::<Uri>().ok()?;
+    if let Some(host) = &filter.host
+        && uri.host() != Some(host.as_str())
+    {
+        return None;
+    }
+    if let Some(prefix) = &filter.prefix
+        && !uri.path().starts_with(prefix.as_str())
+    {
+        return None;
+    }
+    if !(200..300).contains(&entry.response.status) {
+        return None;
+    }
+
+    let content = entry.response.content;
+    mock_file_extension(&content.mime_type)?;
+    let body = match content.encoding.as_deref() {
+        Some("base64") => String::from_utf8(STANDARD.decode(content.text?).ok()?).ok()?,
+        _ => content.text?,
+    };
+    Some(CapturedResponse {
+        method: entry.request.method.to_ascii_uppercase(),
+        path: uri.path().to_string(),
+        query: uri.query().map(String::from),
+        content_type: content.mime_type,
+        body,
+    })
+}
+
+/// Writes captured responses into `folder` as route files.
+///
+/// A request to `/api/users?role=admin` answered with JSON becomes
+/// `api/users/get?role=admin.json`. Repeated identical answers are written
+/// once; a route answered differently over the capture becomes a sequence,
+/// `get.1.json`, `get.2.json`, and so on, with the last answer in
+/// `get.rest.json`. Routes with an existing file are left untouched.
+pub fn write_route_files(
+    folder: &Path,
+    responses: Vec<CapturedResponse>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+    let mut routes = BTreeMap::<(PathBuf, String, &'static str), Vec<String>>::new();
+    for response in responses {
+        let (Some(directory), Some(extension)) = (
+            route_directory(&response.path),
+            mock_file_extension(&response.content_type),
+        ) else {
+            report.skipped += 1;
+            continue;
+        };
+        let Some(stem) = file_stem(&response.method, response.query.as_deref()) else {
+            report.skipped += 1;
+            continue;
+        };
+        let body = pretty_body(extension, response.body);
+        let bodies = routes.entry((directory, stem, extension)).or_default();
+        if bodies.last() != Some(&body) {
+            bodies.push(body);
+        } else {
+            report.skipped += 1;
+        }
+    }
+
+    for ((directory, stem, extension), bodies) in routes {
+        let names = if bodies.len() == 1 {
+            vec![format!("{}.{}", stem, extension)]
+        } else {
+            (1..bodies.len())
+                .map(|call| format!("{}.{}.{}", stem, call, extension))
+                .chain([format!("{}.rest.{}", stem, extension)])
+                .collect()
+        };
+        if has_route_file(&folder.join(&directory), &stem) {
+            report.skipped += bodies.len();
+            continue;
+        }
+
+        fs::create_dir_all(folder.join(&directory)).map_err(|err| {
+            format!(
+                "Unable to create {}: {}",
+                folder.join(&directory).display(),
+                err
+            )
+        })?;
+        for (name, body) in names.iter().zip(bodies) {
+            let file = directory.join(name);
+            fs::write(folder.join(&file), body).map_err(|err| {
+                format!("Unable to write {}: {}", folder.join(&file).display(), err)
+            })?;
+            report.written.push(file);
+        }
+    }
+    Ok(report)
+}
+
+/// Folder of the route files of `path`, relative to the mock folder.
+///
+/// `{name}` segments are kept, as dynamic folders.
+fn route_directory(path: &str) -> Option<PathBuf> {
+    let mut directory = PathBuf::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        let is_parameter = segment
+            .strip_prefix('{')
+            .and_then(|name| name.strip_suffix('}'))
+            .is_some_and(is_parameter_name);
+        if segment == "."
+            || segment == ".."
+            || (!is_parameter && segment.contains(RESERVED_CHARACTERS))
+        {
+            return None;
+        }
+        directory.push(segment);
+    }
+    Some(directory)
+}
+
+fn is_parameter_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
+
+/// Route file name without extension: the method, with the query as a query
+/// variant.
+fn file_stem(method: &str, query: Option<&str>) -> Option<String> {
+    let method = method.to_ascii_lowercase();
+    match query.filter(|query| !query.is_empty()) {
+        None => Some(method),
+        Some(query) if query.contains(['/', '\\', '*', '"', '<', '>', '|', ':']) => None,
+        Some(query) => Some(format!("{}?{}", method, query)),
+    }
+}
+
+/// Returns true when the folder already serves the route, alone or as a
+/// sequence.
+fn has_route_file(directory: &Path, stem: &str) -> bool {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return false;
+    };
+    let prefix = format!("{}.", stem);
+    entries
+        .flatten()
+        .any(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+}
+
+fn pretty_body(extension: &str, body: String) -> String {
+    if extension != "json" {
+        return body;
+    }
+    serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or(body)
+}
+
+/// Reads the requests of a Postman collection, each with its first
+/// successful example response.
+///
+/// Returns the responses and the number of requests without a usable example.
+pub fn read_postman(path: &Path) -> Result<(Vec<CapturedResponse>, usize), String> {
+    let collection = read_json(path)?;
+    if collection.get("info").is_none() || !collection["item"].is_array() {
+        return Err(format!(
+            "{} is not a Postman collection v2.0 or v2.1",
+            path.display()
+        ));
+    }
+
+    let mut requests = vec![];
+    collect_postman_items(&collection["item"], &mut requests);
+    let total = requests.len();
+    let responses = first_per_route(requests.into_iter().filter_map(postman_response).collect());
+    let skipped = total - responses.len();
+    Ok((responses, skipped))
+}
+
+/// Collects the requests of a Postman item list, descending into folders.
+fn collect_postman_items<'a>(items: &'a Value, requests: &mut Vec<&'a Value>) {
+    for item in items.as_array().into_iter().flatten() {
+        if item.get("request").is_some() {
+            requests.push(item);
+        } else {
+            collect_postman_items(&item["item"], requests);
+        }
+    }
+}
+
+fn postman_response(item: &Value) -> Option<CapturedResponse> {
+    let request = &item["request"];
+    let example = item["response"].as_array()?.iter().find(|example| {
+        example["code"]
+            .as_u64()
+            .is_none_or(|code| (200..300).contains(&code))
+    })?;
+    let method = example["originalRequest"]["method"]
+        .as_str()
+        .or(request["method"].as_str())
+        .unwrap_or("GET");
+    let (path, query) = postman_url(&request["url"])?;
+    let body = example["body"].as_str().unwrap_or_default().to_string();
+    let content_type = example["header"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|header| {
+            header["key"]
+                .as_str()
+                .is_some_and(|key| key.eq_ignore_ascii_case("content-type"))
+        })
+        .and_then(|header| header["value"].as_str())
+        .map(String::from)
+        .unwrap_or_else(|| match example["_postman_previewlanguage"].as_str() {
+            Some("json") => "application/json".to_string(),
+            Some("xml") => "application/xml".to_string(),
+            Some("html") => "text/html".to_string(),
+            _ if serde_json::from_str::<Value>(&body).is_ok() => "application/json".to_string(),
+            _ => "text/plain".to_string(),
+        });
+
+    Some(CapturedResponse {
+        method: method.to_ascii_uppercase(),
+        path,
+        query,
+        content_type,
+        body,
+    })
+}
+
+/// Path, with `{name}` path variables, and query of a Postman URL, given as
+/// a string or as an object.
+fn postman_url(url: &Value) -> Option<(String, Option<String>)> {
+    if let Some(raw) = url.as_str() {
+        return Some(raw_url_route(raw));
+    }
+    let Some(segments) = url["path"].as_array() else {
+        return url["raw"].as_str().map(raw_url_route);
+    };
+    let path = segments
+        .iter()
+        .filter_map(|segment| segment.as_str().or(segment["value"].as_str()))
+        .map(route_segment)
+        .collect::<Vec<_>>()
+        .join("/");
+    let query = url["query"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|parameter| parameter["disabled"].as_bool() != Some(true))
+        .filter_map(|parameter| {
+            let key = parameter["key"].as_str()?;
+            let value = parameter["value"].as_str().unwrap_or_default();
+            (!key.contains("{{") && !value.contains("{{")).then(|| format!("{}={}", key, value))
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    Some((
+        format!("/{}", path),
+        Some(query).filter(|query| !query.is_empty()),
+    ))
+}
+
+/// Reads the requests of an Insomnia export, each answering `{}`.
+///
+/// Returns the responses and the number of requests sharing a route.
+pub fn read_insomnia(path: &Path) -> Result<(Vec<CapturedResponse>, usize), String> {
+    let export = read_json(path)?;
+    let Some(resources) = export["resources"].as_array() else {
+        return Err(format!("{} is not an Insomnia export", path.display()));
+    };
+
+    let requests = resources
+        .iter()
+        .filter(|resource| resource["_type"] == "request")
+        .filter_map(|request| {
+            let (path, query) = raw_url_route(request["url"].as_str()?);
+            Some(CapturedResponse {
+                method: request["method"]
+                    .as_str()
+                    .unwrap_or("GET")
+                    .to_ascii_uppercase(),
+                path,
+                query,
+                content_type: "application/json".to_string(),
+                body: "{}".to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let total = requests.len();
+    let responses = first_per_route(requests);
+    let skipped = total - responses.len();
+    Ok((responses, skipped))
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+    serde_json::from_str(&text)
+        .map_err(|err| format!("Invalid JSON in {}: {}", path.display(), err))
+}
+
+/// Path and query of a URL that may start with a variable, such as
+/// `{{baseUrl}}/users/:id?page=2`, with path variables as `{name}`.
+fn raw_url_route(raw: &str) -> (String, Option<String>) {
+    let (address, query) = match raw.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (raw, None),
+    };
+    let address = address.split_once("://").map_or(address, |(_, rest)| rest);
+    let path = match address.starts_with('/') {
+        true => address,
+        false => address.find('/').map_or("", |start| &address[start..]),
+    };
+    let path = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(route_segment)
+        .collect::<Vec<_>>()
+        .join("/");
+    let query = query
+        .map(|query| {
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty() && !pair.contains("{{"))
+                .collect::<Vec<_>>()
+                .join("&")
+        })
+        .filter(|query| !query.is_empty());
+    (format!("/{}", path), query)
+}
+
+/// A URL path segment, with `:name` and `{{name}}` variables as `{name}`.
+fn route_segment(segment: &str) -> String {
+    let variable = segment.strip_prefix(':').or_else(|| {
+        segment
+            .strip_prefix("{{")
+            .and_then(|name| name.strip_suffix("}}"))
+            .map(|name| name.trim().trim_start_matches("_."))
+    });
+    match variable {
+        Some(name) if is_parameter_name(name) => format!("{{{}}}", name),
+        _ => segment.to_string(),
+    }
+}
+
+/// Keeps the first response of every route, in order.
+fn first_per_route(responses: Vec<CapturedResponse>) -> Vec<CapturedResponse> {
+    let mut seen = std::collections::HashSet::new();
+    responses
+        .into_iter()
+        .filter(|response| {
+            seen.insert((
+                response.method.clone(),
+                response.path.clone(),
+                response.query.clone(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(method: &str, url: &str, status: u16, mime_type: &str, text: &str) -> Value {
+        json!({
+            "request": { "method": method, "url": url, "headers": [] },
+            "response": {
+                "status": status,
+                "content": { "mimeType": mime_type, "text": text },
+            },
+        })
+    }
+
+    #[test]
+    fn har_sessions_become_route_files_and_sequences() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let har_path = temp_dir.path().join("session.har");
+        let mut logo = entry(
+            "GET",
+            "https://app.example.com/logo.png",
+            200,
+            "image/png",
+            "",
+        );
+        logo["response"]["content"]["encoding"] = json!("base64");
+        let har = json!({ "log": { "entries": [
+            entry("GET", "https://app.example.com/api/users?role=admin", 200, "application/json", r#"[{"id":1}]"#),
+            entry("GET", "https://app.example.com/api/jobs/7", 200, "application/json", r#"{"status":"pending"}"#),
+            entry("GET", "https://app.example.com/api/jobs/7", 200, "application/json", r#"{"status":"pending"}"#),
+            entry("GET", "https://app.example.com/api/jobs/7", 200, "application/json", r#"{"status":"done"}"#),
+            entry("POST", "https://app.example.com/api/jobs", 404, "application/json", "{}"),
+            entry("GET", "https://metrics.example.com/api/ping", 200, "text/plain", "pong"),
+            logo,
+        ] } });
+        fs::write(&har_path, har.to_string()).unwrap();
+
+        let filter = ImportFilter {
+            host: Some("app.example.com".to_string()),
+            prefix: Some("/api".to_string()),
+        };
+        let (responses, skipped) = read_har(&har_path, &filter).unwrap();
+        assert_eq!((responses.len(), skipped), (4, 3));
+
+        let mocks = temp_dir.path().join("mocks");
+        let report = write_route_files(&mocks, responses.clone()).unwrap();
+        assert_eq!(
+            report.written,
+            vec![
+                PathBuf::from("api/jobs/7/get.1.json"),
+                PathBuf::from("api/jobs/7/get.rest.json"),
+                PathBuf::from("api/users/get?role=admin.json"),
+            ]
+        );
+        assert_eq!(report.skipped, 1);
+        let done = fs::read_to_string(mocks.join("api/jobs/7/get.rest.json")).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&done).unwrap()["status"],
+            "done"
+        );
+
+        let again = write_route_files(&mocks, responses).unwrap();
+        assert!(again.written.is_empty());
+        assert_eq!(again.skipped, 4);
+    }
+
+    #[test]
+    fn postman_examples_become_routes_with_dynamic_folders() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("collection.json");
+        let collection = json!({
+            "info": { "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+            "item": [{
+                "name": "Users",
+                "item": [
+                    {
+                        "name": "Get user",
+                        "request": {
+                            "method": "GET",
+                            "url": { "raw": "{{baseUrl}}/users/:id", "host": ["{{baseUrl}}"], "path": ["users", ":id"] },
+                        },
+                        "response": [
+                            { "code": 404, "body": "{}" },
+                            {
+                                "code": 200,
+                                "header": [{ "key": "Content-Type", "value": "application/json" }],
+                                "body": "{\"id\":1}",
+                            },
+                        ],
+                    },
+                    {
+                        "name": "Search",
+                        "request": { "method": "GET", "url": "{{baseUrl}}/users?role=admin&token={{token}}" },
+                        "response": [{ "code": 200, "_postman_previewlanguage": "json", "body": "[]" }],
+                    },
+                    { "name": "No examples", "request": { "method": "DELETE", "url": "{{baseUrl}}/users/:id" }, "response": [] },
+                ],
+            }],
+        });
+        fs::write(&path, collection.to_string()).unwrap();
+
+        let (responses, skipped) = read_postman(&path).unwrap();
+        assert_eq!(skipped, 1);
+        let report = write_route_files(temp_dir.path(), responses).unwrap();
+        assert_eq!(
+            report.written,
+            vec![
+                PathBuf::from("users/get?role=admin.json"),
+                PathBuf::from("users/{id}/get.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn insomnia_requests_become_stub_routes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("insomnia.json");
+        let export = json!({
+            "_type": "export",
+            "__export_format": 4,
+            "resources": [
+                { "_type": "request_group", "name": "Orders" },
+                { "_type": "request", "method": "POST", "url": "{{ _.base_url }}/orders" },
+                { "_type": "request", "method": "GET", "url": "https://api.example.com/orders/{{ _.order_id }}" },
+                { "_type": "request", "method": "POST", "url": "{{ _.base_url }}/orders" },
+            ],
+        });
+        fs::write(&path, export.to_string()).unwrap();
+
+        let (responses, skipped) = read_insomnia(&path).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(responses[1].path, "/orders/{order_id}");
+        assert_eq!(responses[0].body, "{}");
+    }
+
+    #[test]
+    fn unsafe_paths_are_not_imported() {
+        assert_eq!(route_directory("/api/../secret"), None);
+        assert_eq!(route_directory("/api/{id}x"), None);
+        assert_eq!(
+            route_directory("/api/{id}"),
+            Some(PathBuf::from("api/{id}"))
+        );
+        assert_eq!(route_directory("/"), Some(PathBuf::new()));
+        assert_eq!(file_stem("GET", Some("next=/home")), None);
+        assert_eq!(file_stem("GET", Some("")), Some("get".to_string()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Route inventory used by the `routes` command.
+//!
+//! Routes are built against a quiet [`App`] that never binds a port, and every
+//! registered method and path is attributed to the mock file or folder it came
+//! from.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{
+    app::{App, describe_overlaps},
+    check::quietly,
+    route_builder::{
+        Route,
+        config::{Config, Delay},
+        route_manager::RouteManager,
+    },
+};
+
+/// One HTTP route defined by the mock folder.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteEntry {
+    /// HTTP method.
+    pub method: String,
+    /// Route path.
+    pub path: String,
+    /// Mock file or folder that defines the route.
+    pub source: String,
+    /// Kind of route, such as `basic`, `rest`, or `graphql`.
+    pub kind: String,
+    /// Whether the route requires authentication.
+    pub protected: bool,
+    /// Response delay in milliseconds.
+    pub delay: Option<Delay>,
+}
+
+/// Lists every route the mock folder of `config` would register, in registration order.
+///
+/// Fails when a route cannot be registered, for example because it overlaps
+/// an earlier route.
+pub fn list_routes(config: &Config) -> Result<Vec<RouteEntry>, String> {
+    let mut app = App::new(config.clone());
+    app.quiet = true;
+
+    let mut managers = vec![];
+    for (folder, mount_config) in app.get_folders().into_iter().zip(config.mount_configs()) {
+        let manager = quietly(|| RouteManager::discover(&folder, Some(mount_config)))
+            .map_err(|err| format!("{}: {}", folder, err))?;
+        app.push_route_versions(&manager.versions);
+        managers.push(manager);
+    }
+
+    let mut entries = vec![];
+    let routes = managers
+        .iter()
+        .flat_map(|manager| std::iter::once(&manager.auth_route).chain(&manager.routes));
+    for route in routes {
+        let Some(source) = route.source_path() else {
+            continue;
+        };
+        let source = Path::new(source).to_string_lossy().into_owned();
+
+        let registered = app.registered_routes().len();
+        app.try_make_routes(route)
+            .map_err(|overlaps| format!("{}: {}", source, describe_overlaps(&overlaps)))?;
+
+        entries.extend(entries_since(&app, registered, route, &source));
+    }
+
+    Ok(entries)
+}
+
+/// Returns the routes `route` registered on `app` after the first `registered` routes.
+pub(crate) fn entries_since(
+    app: &App,
+    registered: usize,
+    route: &Route,
+    source: &str,
+) -> Vec<RouteEntry> {
+    app.registered_routes()[registered..]
+        .iter()
+        .map(|registered| RouteEntry {
+            method: registered.method.clone(),
+            path: registered.path.clone(),
+            source: source.to_string(),
+            kind: route.kind().to_string(),
+            protected: registered.is_protected,
+            delay: route.delay(),
+        })
+        .collect()
+}
+
+/// Formats route entries as an aligned text table with a header row.
+pub fn format_table(entries: &[RouteEntry]) -> String {
+    let headers = ["METHOD", "PATH", "PROTECTED", "DELAY", "SOURCE"];
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.method.clone(),
+                entry.path.clone(),
+                if entry.protected { "yes" } else { "no" }.to_string(),
+                entry
+                    .delay
+                    .map_or("-".to_string(), |delay| delay.to_string()),
+                entry.source.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(headers.map(str::to_string))
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::{FolderMount, ServerConfig};
+    use std::fs;
+
+    fn list(files: &[(&str, &str)]) -> Result<Vec<RouteEntry>, String> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (name, content) in files {
+            let path = temp_dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        list_routes(&Config {
+            server: Some(ServerConfig {
+                folder: Some(temp_dir.path().to_string_lossy().into_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .map(|entries| {
+            entries
+                .into_iter()
+                .map(|entry| RouteEntry {
+                    source: Path::new(&entry.source)
+                        .strip_prefix(temp_dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                    ..entry
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn lists_routes_with_their_source_protection_and_delay() {
+        let entries = list(&[
+            ("health/get.json", "{}"),
+            ("orders/$get{id}.json", "{}"),
+            ("users/rest.json", "[]"),
+            ("users/config.toml", "[route]\ndelay = 25\n"),
+        ])
+        .unwrap();
+
+        let route = |method: &str, path: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.method == method && entry.path == path)
+                .unwrap_or_else(|| panic!("missing {} {}", method, path))
+        };
+
+        assert_eq!(
+            route("GET", "/health"),
+            &RouteEntry {
+                method: "GET".to_string(),
+                path: "/health".to_string(),
+                source: "health/get.json".to_string(),
+                kind: "basic".to_string(),
+                protected: false,
+                delay: None,
+            }
+        );
+        assert!(route("GET", "/orders/{id}").protected);
+        let users = route("DELETE", "/users/{id}");
+        assert_eq!(users.source, "users/rest.json");
+        assert_eq!(users.delay, Some(Delay::Fixed(25)));
+        assert!(
+            entries
+                .iter()
+                .filter(|entry| entry.path.starts_with("/users"))
+                .count()
+                > 5
+        );
+    }
+
+    #[test]
+    fn lists_routes_of_every_mounted_folder_under_its_prefix() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let folder = |name: &str| {
+            let path = temp_dir.path().join(name);
+            fs::create_dir_all(path.join("users")).unwrap();
+            fs::write(path.join("users/get.json"), "{}").unwrap();
+            path.to_string_lossy().into_owned()
+        };
+
+        let entries = list_routes(&Config {
+            server: Some(ServerConfig {
+                mounts: Some(vec![
+                    FolderMount::parse(&format!("api1:{}", folder("a"))),
+                    FolderMount::parse(&format!("/billing:{}", folder("b"))),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect::<Vec<_>>(),
+            ["/api1/users", "/billing/users"]
+        );
+    }
+
+    #[test]
+    fn reports_routes_that_cannot_be_registered() {
+        let err = list(&[("users/get.json", "{}"), ("users.json", "{}")]).unwrap_err();
+
+        assert!(err.contains("GET /users overlaps GET /users"), "{}", err);
+    }
+
+    #[test]
+    fn format_table_aligns_columns() {
+        let table = format_table(&[
+            RouteEntry {
+                method: "GET".to_string(),
+                path: "/users".to_string(),
+                source: "mocks/users/rest.json".to_string(),
+                kind: "rest".to_string(),
+                protected: true,
+                delay: Some(Delay::Fixed(10)),
+            },
+            RouteEntry {
+                method: "DELETE".to_string(),
+                path: "/users/{id}".to_string(),
+                source: "mocks/users/rest.json".to_string(),
+                kind: "rest".to_string(),
+                protected: false,
+                delay: None,
+            },
+        ]);
+
+        assert_eq!(
+            table,
+            [
+                "METHOD  PATH         PROTECTED  DELAY  SOURCE",
+                "GET     /users       yes        10ms   mocks/users/rest.json",
+                "DELETE  /users/{id}  no         -      mocks/users/rest.json",
+            ]
+            .join("\n")
+        );
+    }
+}
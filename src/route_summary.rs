@@ -0,0 +1,257 @@
+//! Startup summary of the routes a mock folder registers.
+//!
+//! Routes are grouped by their first path segment and sorted, with a colored
+//! badge per method, and routes that partly hide a route from another mock
+//! folder are reported.
+
+use std::{collections::BTreeMap, io::IsTerminal, path::Path};
+
+use crossterm::style::{Color, Stylize};
+
+use crate::route_inventory::RouteEntry;
+
+const METHOD_ORDER: [&str; 7] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+/// Returns whether the summary should be colored: standard output is a
+/// terminal and `NO_COLOR` is not set.
+pub fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Formats routes grouped by their first path segment, each group sorted by
+/// path and method.
+pub fn format_summary(entries: &[RouteEntry], color: bool) -> String {
+    let mut groups = BTreeMap::<String, Vec<&RouteEntry>>::new();
+    for entry in entries {
+        groups
+            .entry(route_prefix(&entry.path))
+            .or_default()
+            .push(entry);
+    }
+    let width = entries
+        .iter()
+        .map(|entry| entry.path.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![];
+    for (prefix, mut group) in groups {
+        group.sort_by(|left, right| {
+            left.path
+                .cmp(&right.path)
+                .then(method_rank(&left.method).cmp(&method_rank(&right.method)))
+        });
+        lines.push(if color {
+            prefix.bold().to_string()
+        } else {
+            prefix
+        });
+        for entry in group {
+            let mut details = entry.source.clone();
+            if entry.protected {
+                details.push_str("  🔒 protected");
+            }
+            if let Some(delay) = entry.delay {
+                details.push_str(&format!("  ⏱ {}", delay));
+            }
+            let details = if color {
+                details.dark_grey().to_string()
+            } else {
+                details
+            };
+            lines.push(format!(
+                "  {} {:<width$}  {}",
+                method_badge(&entry.method, color),
+                entry.path,
+                details,
+                width = width
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Describes every route that hides part of a route from another mock folder.
+///
+/// A route hides another with the same method when it matches a subset of its
+/// paths, like `/users/me` for `/users/{id}`: the router prefers fixed segments,
+/// so requests for those paths never reach the broader route. Files of the
+/// same folder, like `get{admin}.json` next to `get{id}.json`, are expected to
+/// refine each other and are not reported.
+pub fn route_conflicts(entries: &[RouteEntry]) -> Vec<String> {
+    let mut conflicts = vec![];
+    for specific in entries {
+        for general in entries {
+            if specific.method == general.method
+                && Path::new(&specific.source).parent() != Path::new(&general.source).parent()
+                && shadows(&specific.path, &general.path)
+            {
+                conflicts.push(format!(
+                    "{} {} from {} shadows {} {} from {}",
+                    specific.method,
+                    specific.path,
+                    specific.source,
+                    general.method,
+                    general.path,
+                    general.source
+                ));
+            }
+        }
+    }
+    conflicts
+}
+
+fn route_prefix(path: &str) -> String {
+    match path.trim_start_matches('/').split('/').next() {
+        Some(segment) if !segment.is_empty() => format!("/{}", segment),
+        _ => "/".to_string(),
+    }
+}
+
+fn method_rank(method: &str) -> usize {
+    METHOD_ORDER
+        .iter()
+        .position(|known| *known == method)
+        .unwrap_or(METHOD_ORDER.len())
+}
+
+fn method_badge(method: &str, color: bool) -> String {
+    if !color {
+        return format!("{:<7}", method);
+    }
+    let background = match method {
+        "GET" | "HEAD" => Color::Green,
+        "POST" => Color::Yellow,
+        "PUT" | "PATCH" => Color::Blue,
+        "DELETE" => Color::Red,
+        _ => Color::Magenta,
+    };
+    format!(" {:<6}", method)
+        .black()
+        .on(background)
+        .bold()
+        .to_string()
+}
+
+fn is_param(segment: &str) -> bool {
+    segment.starts_with('{') && segment.ends_with('}')
+}
+
+fn is_catch_all(segment: &str) -> bool {
+    segment.starts_with("{*")
+}
+
+/// Returns whether every path matched by `specific` is also matched by
+/// `general`, which matches more paths.
+fn shadows(specific: &str, general: &str) -> bool {
+    let specific = specific
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    let general = general
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut is_narrower = false;
+    for (index, segment) in general.iter().enumerate() {
+        let Some(other) = specific.get(index) else {
+            return false;
+        };
+        if is_catch_all(segment) {
+            return !is_catch_all(other) || index < specific.len() - 1 || is_narrower;
+        }
+        match (is_param(segment), is_param(other)) {
+            (true, true) if is_catch_all(other) => return false,
+            (true, true) => {}
+            (true, false) => is_narrower = true,
+            (false, true) => return false,
+            (false, false) if segment != other => return false,
+            (false, false) => {}
+        }
+    }
+    is_narrower && specific.len() == general.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::Delay;
+
+    fn entry(method: &str, path: &str, source: &str) -> RouteEntry {
+        RouteEntry {
+            method: method.to_string(),
+            path: path.to_string(),
+            source: source.to_string(),
+            kind: "basic".to_string(),
+            protected: false,
+            delay: None,
+        }
+    }
+
+    #[test]
+    fn summary_groups_and_sorts_routes_by_prefix() {
+        let entries = [
+            entry("DELETE", "/users/{id}", "users/rest.json"),
+            RouteEntry {
+                protected: true,
+                delay: Some(Delay::Fixed(50)),
+                ..entry("POST", "/orders", "orders/$post.json")
+            },
+            entry("GET", "/users/{id}", "users/rest.json"),
+            entry("GET", "/", "get.json"),
+            entry("GET", "/users", "users/rest.json"),
+        ];
+
+        assert_eq!(
+            format_summary(&entries, false),
+            [
+                "/",
+                "  GET     /            get.json",
+                "/orders",
+                "  POST    /orders      orders/$post.json  🔒 protected  ⏱ 50ms",
+                "/users",
+                "  GET     /users       users/rest.json",
+                "  GET     /users/{id}  users/rest.json",
+                "  DELETE  /users/{id}  users/rest.json",
+            ]
+            .join("\n")
+        );
+        assert!(format_summary(&entries, true).contains("\u{1b}["));
+    }
+
+    #[test]
+    fn conflicts_report_routes_hiding_other_files() {
+        let entries = [
+            entry("GET", "/users/{id}", "users/rest.json"),
+            entry("GET", "/users/me", "users/me/get.json"),
+            entry("POST", "/users/me", "users/me/post.json"),
+            entry("GET", "/users/{id}/avatar", "users/rest.json"),
+            entry("GET", "/files/{*path}", "files/{**}/get.json"),
+            entry("GET", "/files/readme", "files/readme/get.json"),
+            entry("PUT", "/users/{id}", "users/rest.json"),
+            entry("PUT", "/users/{user_id}/role", "users/role.json"),
+            entry("GET", "/users/admin", "users/get{admin}.json"),
+        ];
+
+        assert_eq!(
+            route_conflicts(&entries),
+            [
+                "GET /users/me from users/me/get.json shadows GET /users/{id} from users/rest.json",
+                "GET /files/readme from files/readme/get.json shadows GET /files/{*path} from files/{**}/get.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn shadows_compares_fixed_and_parameter_segments() {
+        assert!(shadows("/users/me", "/users/{id}"));
+        assert!(shadows("/a/b/c", "/a/{*rest}"));
+        assert!(shadows("/a/{id}/c", "/a/{*rest}"));
+        assert!(!shadows("/users/{id}", "/users/me"));
+        assert!(!shadows("/users/{id}", "/users/{user_id}"));
+        assert!(!shadows("/users/me/posts", "/users/{id}"));
+        assert!(!shadows("/a/{*rest}", "/a/{*path}"));
+        assert!(!shadows("/a", "/a/{*rest}"));
+    }
+}
@@ -0,0 +1,473 @@
+//! Behavior changes scheduled over wall-clock time, for game days and outage
+//! rehearsals.
+//!
+//! While a `[[schedule.windows]]` entry is open, the requests matching its
+//! path and methods wait for its fixed or linearly growing delay, then fail
+//! with its status or reach their route. Windows either repeat every day
+//! between two local times or run between two timestamps.
+
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use chrono::{DateTime, Local, NaiveTime, TimeDelta};
+use http::{HeaderValue, StatusCode};
+
+use crate::{
+    criteria::wildcard_matches,
+    handlers::{delay_duration, error_response},
+    route_builder::config::{Config, Delay, ScheduleWindow},
+};
+
+/// Header naming the schedule windows applied to a response.
+pub const SCHEDULE_HEADER: &str = "x-mock-schedule";
+
+/// A `start` or `end` of a window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimePoint {
+    /// A local time of every day.
+    Daily(NaiveTime),
+    /// An instant.
+    At(DateTime<Local>),
+}
+
+fn parse_time(text: &str) -> Result<TimePoint, String> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Ok(TimePoint::At(at.with_timezone(&Local)));
+    }
+    NaiveTime::parse_from_str(text, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M"))
+        .map(TimePoint::Daily)
+        .map_err(|_| {
+            format!(
+                "Invalid schedule time '{}': expected HH:MM[:SS] or an RFC 3339 timestamp",
+                text
+            )
+        })
+}
+
+/// When a window is open.
+#[derive(Debug, Clone, PartialEq)]
+enum Period {
+    /// Every day from `start`, local time, for `length`.
+    Daily { start: NaiveTime, length: TimeDelta },
+    /// From `start` until `end`, or forever.
+    Between {
+        start: DateTime<Local>,
+        end: Option<DateTime<Local>>,
+    },
+}
+
+impl Period {
+    /// Resolves the `start`, `end`, and `duration` of a window, a missing
+    /// start being `started`.
+    fn resolve(window: &ScheduleWindow, started: DateTime<Local>) -> Result<Self, String> {
+        if window.end.is_some() && window.duration.is_some() {
+            return Err("set either end or duration, not both".to_string());
+        }
+        let start = window.start.as_deref().map(parse_time).transpose()?;
+        let end = window.end.as_deref().map(parse_time).transpose()?;
+        let duration = window
+            .duration
+            .map(|seconds| TimeDelta::seconds(seconds as i64));
+
+        match (start, end) {
+            (Some(TimePoint::Daily(start)), Some(TimePoint::Daily(end))) => {
+                let mut length = end - start;
+                if length <= TimeDelta::zero() {
+                    length += TimeDelta::days(1);
+                }
+                Ok(Self::Daily { start, length })
+            }
+            (Some(TimePoint::Daily(start)), None) => duration
+                .map(|length| Self::Daily { start, length })
+                .ok_or_else(|| "a daily start needs an end or a duration".to_string()),
+            (Some(TimePoint::Daily(_)), _) | (_, Some(TimePoint::Daily(_))) => {
+                Err("start and end must both be times of day or both timestamps".to_string())
+            }
+            (start, end) => {
+                let start = match start {
+                    Some(TimePoint::At(start)) => start,
+                    _ => started,
+                };
+                let end = match end {
+                    Some(TimePoint::At(end)) => Some(end),
+                    _ => duration.map(|duration| start + duration),
+                };
+                Ok(Self::Between { start, end })
+            }
+        }
+    }
+
+    /// Returns how far into the window `now` is, from 0 to 1, or `None` when
+    /// the window is closed. Open-ended windows stay at 0.
+    fn progress(&self, now: DateTime<Local>) -> Option<f64> {
+        match self {
+            Self::Daily { start, length } => {
+                let mut elapsed = now.time() - *start;
+                if elapsed < TimeDelta::zero() {
+                    elapsed += TimeDelta::days(1);
+                }
+                (elapsed < *length).then(|| ratio(elapsed, *length))
+            }
+            Self::Between { start, end } => {
+                if now < *start || end.is_some_and(|end| now >= end) {
+                    return None;
+                }
+                Some(end.map_or(0.0, |end| ratio(now - *start, end - *start)))
+            }
+        }
+    }
+
+    fn has_end(&self) -> bool {
+        !matches!(self, Self::Between { end: None, .. })
+    }
+}
+
+fn ratio(elapsed: TimeDelta, length: TimeDelta) -> f64 {
+    elapsed.num_milliseconds() as f64 / length.num_milliseconds().max(1) as f64
+}
+
+/// A resolved schedule window.
+struct Scheduled {
+    name: String,
+    path: Option<String>,
+    methods: Option<Vec<String>>,
+    period: Period,
+    status: Option<StatusCode>,
+    message: Option<String>,
+    delay: Option<Delay>,
+    delay_ramp: Option<[u64; 2]>,
+}
+
+impl Scheduled {
+    fn resolve(
+        index: usize,
+        window: &ScheduleWindow,
+        started: DateTime<Local>,
+    ) -> Result<Self, String> {
+        let name = window
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("window {}", index + 1));
+        let invalid = |err: String| format!("Invalid schedule window '{}': {}", name, err);
+
+        let period = Period::resolve(window, started).map_err(invalid)?;
+        if window.delay_ramp.is_some() && !period.has_end() {
+            return Err(invalid(
+                "a delay ramp needs an end or a duration".to_string(),
+            ));
+        }
+        let status = window
+            .status
+            .map(|status| {
+                StatusCode::from_u16(status).map_err(|_| invalid(format!("status {}", status)))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            name,
+            path: window.path.clone(),
+            methods: window.methods.as_ref().map(|methods| {
+                methods
+                    .iter()
+                    .map(|method| method.to_ascii_uppercase())
+                    .collect()
+            }),
+            period,
+            status,
+            message: window.message.clone(),
+            delay: window.delay,
+            delay_ramp: window.delay_ramp,
+        })
+    }
+
+    fn matches(&self, method: &str, path: &str) -> bool {
+        self.path
+            .as_deref()
+            .is_none_or(|pattern| wildcard_matches(path, pattern, '*', '?'))
+            && self
+                .methods
+                .as_ref()
+                .is_none_or(|methods| methods.iter().any(|allowed| allowed == method))
+    }
+}
+
+/// What the open windows matching a request do to it.
+#[derive(Debug, Default, PartialEq)]
+struct Effects {
+    /// Names of the windows applied.
+    names: Vec<String>,
+    delay: Duration,
+    /// Status and message answered instead of the route's response.
+    failure: Option<(StatusCode, String)>,
+}
+
+/// The `[schedule]` windows of the root configuration.
+#[derive(Default)]
+pub struct Schedule {
+    windows: Vec<Scheduled>,
+}
+
+impl Schedule {
+    /// Resolves the `[schedule]` windows, windows without a `start` opening now.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        Self::starting_at(config, Local::now())
+    }
+
+    fn starting_at(config: &Config, started: DateTime<Local>) -> Result<Self, String> {
+        let windows = config
+            .schedule
+            .iter()
+            .flat_map(|schedule| schedule.windows.iter().flatten())
+            .enumerate()
+            .map(|(index, window)| Scheduled::resolve(index, window, started))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { windows })
+    }
+
+    /// Returns the number of windows.
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// Returns true when nothing is scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Adds up the delays of the windows open at `now` that match a request;
+    /// the first of them with a status answers it.
+    fn effects(&self, method: &str, path: &str, now: DateTime<Local>) -> Effects {
+        let mut effects = Effects::default();
+        for window in &self.windows {
+            let Some(progress) = window.period.progress(now) else {
+                continue;
+            };
+            if !window.matches(method, path) {
+                continue;
+            }
+
+            effects.names.push(window.name.clone());
+            if let Some(delay) = window.delay {
+                effects.delay += delay_duration(delay);
+            }
+            if let Some([from, to]) = window.delay_ramp {
+                let millis = from as f64 + (to as f64 - from as f64) * progress;
+                effects.delay += Duration::from_secs_f64(millis.max(0.0) / 1000.0);
+            }
+            if let (None, Some(status)) = (&effects.failure, window.status) {
+                let message = window
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| format!("Scheduled outage: {}", window.name));
+                effects.failure = Some((status, message));
+            }
+        }
+        effects
+    }
+}
+
+type ScheduleMiddlewareReturn =
+    Pin<Box<dyn std::future::Future<Output = Response> + Send + 'static>>;
+
+/// Creates middleware applying the open schedule windows to every request,
+/// except those of the internal `/__` routes.
+pub fn make_schedule_middleware(
+    schedule: &Arc<Schedule>,
+) -> impl Clone + Send + Sync + 'static + Fn(Request, Next) -> ScheduleMiddlewareReturn {
+    let schedule = Arc::clone(schedule);
+    move |req: Request, next: Next| {
+        let schedule = Arc::clone(&schedule);
+        Box::pin(async move {
+            if req.uri().path().starts_with("/__") {
+                return next.run(req).await;
+            }
+            let effects = schedule.effects(req.method().as_str(), req.uri().path(), Local::now());
+            if effects.names.is_empty() {
+                return next.run(req).await;
+            }
+
+            if !effects.delay.is_zero() {
+                tokio::time::sleep(effects.delay).await;
+            }
+            let mut response = match effects.failure {
+                Some((status, message)) => error_response(status, "scheduled_outage", message),
+                None => next.run(req).await,
+            };
+            if let Ok(names) = HeaderValue::from_str(&effects.names.join(", ")) {
+                response.headers_mut().insert(SCHEDULE_HEADER, names);
+            }
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_builder::config::ScheduleConfig;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use chrono::TimeZone;
+    use tower::ServiceExt;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 3, 10, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn schedule(windows: Vec<ScheduleWindow>, started: DateTime<Local>) -> Schedule {
+        let config = Config {
+            schedule: Some(ScheduleConfig {
+                windows: Some(windows),
+            }),
+            ..Default::default()
+        };
+        Schedule::starting_at(&config, started).unwrap()
+    }
+
+    #[test]
+    fn daily_windows_repeat_and_wrap_past_midnight() {
+        let outage = schedule(
+            vec![
+                ScheduleWindow {
+                    path: Some("/payments*".to_string()),
+                    start: Some("12:00".to_string()),
+                    end: Some("12:05".to_string()),
+                    status: Some(503),
+                    ..Default::default()
+                },
+                ScheduleWindow {
+                    name: Some("nightly".to_string()),
+                    methods: Some(vec!["post".to_string()]),
+                    start: Some("23:30".to_string()),
+                    duration: Some(3600),
+                    status: Some(500),
+                    message: Some("Batch running".to_string()),
+                    ..Default::default()
+                },
+            ],
+            at(8, 0),
+        );
+
+        let failure = |method: &str, path: &str, now| outage.effects(method, path, now).failure;
+        assert_eq!(
+            failure("GET", "/payments/1", at(12, 3)),
+            Some((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Scheduled outage: window 1".to_string()
+            ))
+        );
+        assert_eq!(failure("GET", "/payments/1", at(12, 5)), None);
+        assert_eq!(failure("GET", "/orders", at(12, 3)), None);
+        assert_eq!(
+            failure("POST", "/orders", at(0, 15)),
+            Some((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Batch running".to_string()
+            ))
+        );
+        assert_eq!(failure("GET", "/orders", at(0, 15)), None);
+        assert_eq!(failure("POST", "/orders", at(0, 30)), None);
+    }
+
+    #[test]
+    fn delay_ramps_grow_linearly_over_the_window() {
+        let ramp = schedule(
+            vec![ScheduleWindow {
+                duration: Some(600),
+                delay_ramp: Some([0, 2000]),
+                delay: Some(Delay::Fixed(100)),
+                ..Default::default()
+            }],
+            at(10, 0),
+        );
+
+        assert_eq!(ramp.effects("GET", "/", at(9, 59)), Effects::default());
+        assert_eq!(
+            ramp.effects("GET", "/", at(10, 0)).delay,
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            ramp.effects("GET", "/", at(10, 5)).delay,
+            Duration::from_millis(1100)
+        );
+        assert_eq!(ramp.effects("GET", "/", at(10, 10)), Effects::default());
+    }
+
+    #[test]
+    fn invalid_windows_are_rejected() {
+        for (window, error) in [
+            (
+                ScheduleWindow {
+                    start: Some("noon".to_string()),
+                    ..Default::default()
+                },
+                "Invalid schedule window 'window 1': Invalid schedule time 'noon': expected HH:MM[:SS] or an RFC 3339 timestamp",
+            ),
+            (
+                ScheduleWindow {
+                    start: Some("12:00".to_string()),
+                    ..Default::default()
+                },
+                "Invalid schedule window 'window 1': a daily start needs an end or a duration",
+            ),
+            (
+                ScheduleWindow {
+                    start: Some("12:00".to_string()),
+                    end: Some("2026-03-10T12:05:00Z".to_string()),
+                    ..Default::default()
+                },
+                "Invalid schedule window 'window 1': start and end must both be times of day or both timestamps",
+            ),
+            (
+                ScheduleWindow {
+                    delay_ramp: Some([0, 100]),
+                    ..Default::default()
+                },
+                "Invalid schedule window 'window 1': a delay ramp needs an end or a duration",
+            ),
+        ] {
+            let config = Config {
+                schedule: Some(ScheduleConfig {
+                    windows: Some(vec![window]),
+                }),
+                ..Default::default()
+            };
+            assert_eq!(Schedule::from_config(&config).err().unwrap(), error);
+        }
+    }
+
+    #[tokio::test]
+    async fn open_windows_answer_with_their_status_and_name() {
+        let schedule = Arc::new(schedule(
+            vec![ScheduleWindow {
+                name: Some("payments outage".to_string()),
+                path: Some("/payments*".to_string()),
+                start: Some("2000-01-01T00:00:00Z".to_string()),
+                status: Some(503),
+                ..Default::default()
+            }],
+            Local::now(),
+        ));
+        let router = Router::new()
+            .route("/payments", get(|| async { "paid" }))
+            .route("/orders", get(|| async { "ordered" }))
+            .layer(middleware::from_fn(make_schedule_middleware(&schedule)));
+
+        let response = router
+            .clone()
+            .oneshot(Request::get("/payments").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[SCHEDULE_HEADER], "payments outage");
+
+        let response = router
+            .oneshot(Request::get("/orders").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(SCHEDULE_HEADER).is_none());
+    }
+}
@@ -0,0 +1,303 @@
+//! Item-by-item loading of JSON array and NDJSON seed files, so a seed is
+//! never held in memory as a whole.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    mem,
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use fosk::{DbCollection, LoadCollectionError};
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+use serde_json::{Deserializer, Value};
+
+use crate::{handlers::IdGenerator, random, templates::render_value};
+
+/// Items added to the collection at a time.
+pub const SEED_BATCH_SIZE: usize = 1_000;
+/// Loaded items between two progress reports.
+pub const SEED_PROGRESS_INTERVAL: usize = 100_000;
+/// Largest seed, in bytes, parsed ahead of route registration. Larger seeds
+/// are streamed while their routes are registered.
+pub const SEED_PRELOAD_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// Loads the items of a seed file into `collection`, replacing its items.
+///
+/// The file holds a JSON array, or one JSON item per line (NDJSON). Items are
+/// read one at a time, rendered, given an id by `ids` when they have none, and
+/// added in batches of [`SEED_BATCH_SIZE`].
+/// `on_batch` receives every batch of added items and `progress` the number of
+/// items loaded so far, every [`SEED_PROGRESS_INTERVAL`] items. Returns the
+/// number of loaded items.
+pub fn load_seed_file(
+    collection: &DbCollection,
+    ids: Option<&IdGenerator>,
+    path: &Path,
+    on_batch: &mut dyn FnMut(&[Value]),
+    progress: &dyn Fn(usize),
+) -> Result<usize, LoadCollectionError> {
+    let path_name = path.to_string_lossy().into_owned();
+    let file_read = || LoadCollectionError::FileRead {
+        path: path_name.clone(),
+    };
+    let invalid_json = || LoadCollectionError::InvalidJson {
+        path: path_name.clone(),
+    };
+
+    let mut reader = BufReader::new(File::open(path).map_err(|_| file_read())?);
+    let first = first_non_whitespace(&mut reader).map_err(|_| file_read())?;
+    let mut batches = SeedBatches::new(collection, ids, on_batch, progress);
+    match first {
+        None => return Err(invalid_json()),
+        Some(b'[') => {
+            let mut deserializer = Deserializer::from_reader(reader);
+            let visited = deserializer.deserialize_seq(ArrayItems {
+                batches: &mut batches,
+            });
+            if let Some(err) = batches.error.take() {
+                return Err(err);
+            }
+            visited
+                .and_then(|_| deserializer.end())
+                .map_err(|_| invalid_json())?;
+        }
+        Some(_) => {
+            for item in Deserializer::from_reader(reader).into_iter::<Value>() {
+                batches.push(item.map_err(|_| invalid_json())?)?;
+            }
+        }
+    }
+    batches.finish()
+}
+
+/// Loads the items of an already parsed seed into `collection`, replacing its
+/// items, like [`load_seed_file`].
+pub fn load_seed_value(
+    collection: &DbCollection,
+    ids: Option<&IdGenerator>,
+    seed: &Value,
+    on_batch: &mut dyn FnMut(&[Value]),
+    progress: &dyn Fn(usize),
+) -> Result<usize, LoadCollectionError> {
+    let mut batches = SeedBatches::new(collection, ids, on_batch, progress);
+    match seed {
+        Value::Array(items) => {
+            for item in items {
+                batches.push_ref(item)?;
+            }
+        }
+        item => batches.push_ref(item)?,
+    }
+    batches.finish()
+}
+
+/// Skips leading whitespace and returns the first byte of the content.
+fn first_non_whitespace(reader: &mut impl BufRead) -> std::io::Result<Option<u8>> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(None);
+        }
+        match buffer.iter().position(|byte| !byte.is_ascii_whitespace()) {
+            Some(position) => {
+                let first = buffer[position];
+                reader.consume(position);
+                return Ok(Some(first));
+            }
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+struct SeedBatches<'a> {
+    collection: &'a DbCollection,
+    ids: Option<&'a IdGenerator>,
+    on_batch: &'a mut dyn FnMut(&[Value]),
+    progress: &'a dyn Fn(usize),
+    now: DateTime<Utc>,
+    pending: Vec<Value>,
+    loaded: usize,
+    replaced: bool,
+    error: Option<LoadCollectionError>,
+}
+
+impl<'a> SeedBatches<'a> {
+    fn new(
+        collection: &'a DbCollection,
+        ids: Option<&'a IdGenerator>,
+        on_batch: &'a mut dyn FnMut(&[Value]),
+        progress: &'a dyn Fn(usize),
+    ) -> Self {
+        Self {
+            collection,
+            ids,
+            on_batch,
+            progress,
+            now: Utc::now(),
+            pending: Vec::with_capacity(SEED_BATCH_SIZE),
+            loaded: 0,
+            replaced: false,
+            error: None,
+        }
+    }
+
+    fn push(&mut self, item: Value) -> Result<(), LoadCollectionError> {
+        self.push_ref(&item)
+    }
+
+    fn push_ref(&mut self, item: &Value) -> Result<(), LoadCollectionError> {
+        let item = render_value(item, self.now);
+        self.pending.push(match self.ids {
+            Some(ids) => ids.assign(item),
+            None => item,
+        });
+        if self.pending.len() >= SEED_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Adds the pending items; the first batch replaces the existing items.
+    fn flush(&mut self) -> Result<(), LoadCollectionError> {
+        if self.pending.is_empty() && self.replaced {
+            return Ok(());
+        }
+        let pending = mem::replace(&mut self.pending, Vec::with_capacity(SEED_BATCH_SIZE));
+        let items = random::current().fill_ids(self.collection, Value::Array(pending));
+        let added = self.collection.load_from_json(items, self.replaced)?;
+        self.replaced = true;
+
+        let before = self.loaded;
+        self.loaded += added.len();
+        (self.on_batch)(&added);
+        if self.loaded / SEED_PROGRESS_INTERVAL > before / SEED_PROGRESS_INTERVAL {
+            (self.progress)(self.loaded);
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<usize, LoadCollectionError> {
+        self.flush()?;
+        Ok(self.loaded)
+    }
+}
+
+/// Hands the elements of a JSON array to the batches as they are parsed.
+struct ArrayItems<'a, 'b> {
+    batches: &'a mut SeedBatches<'b>,
+}
+
+impl<'de> Visitor<'de> for ArrayItems<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(item) = seq.next_element::<Value>()? {
+            if let Err(err) = self.batches.push(item) {
+                self.batches.error = Some(err);
+                return Err(de::Error::custom("the seed could not be loaded"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::IdStrategy;
+    use fosk::{Db, DbConfig, IdType};
+    use serde_json::json;
+    use std::{cell::RefCell, fs};
+
+    fn load(content: &str) -> (Result<usize, LoadCollectionError>, Vec<usize>, Vec<usize>) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("items.json");
+        fs::write(&path, content).unwrap();
+
+        let db = Db::new_arc();
+        let collection = db.create("items");
+        let mut batches = vec![];
+        let progress = RefCell::new(vec![]);
+        let result = load_seed_file(
+            &collection,
+            None,
+            &path,
+            &mut |items| batches.push(items.len()),
+            &|loaded| progress.borrow_mut().push(loaded),
+        );
+        (result, batches, progress.into_inner())
+    }
+
+    #[test]
+    fn arrays_are_loaded_in_batches_with_progress() {
+        let count = SEED_PROGRESS_INTERVAL + 1;
+        let items: Vec<Value> = (0..count).map(|n| json!({ "id": n })).collect();
+
+        let (result, batches, progress) = load(&Value::Array(items).to_string());
+
+        assert_eq!(result.unwrap(), count);
+        assert_eq!(batches.len(), count.div_ceil(SEED_BATCH_SIZE));
+        assert_eq!(batches[0], SEED_BATCH_SIZE);
+        assert_eq!(progress, vec![SEED_PROGRESS_INTERVAL]);
+    }
+
+    #[test]
+    fn ndjson_files_load_one_item_per_line() {
+        let (result, batches, _) = load("{\"name\":\"a\"}\n{\"name\":\"{{faker.name}}\"}\n");
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(batches, vec![2]);
+    }
+
+    #[test]
+    fn invalid_and_empty_files_are_rejected() {
+        assert!(matches!(
+            load("[{\"id\":1},").0,
+            Err(LoadCollectionError::InvalidJson { .. })
+        ));
+        assert!(matches!(
+            load("[] []").0,
+            Err(LoadCollectionError::InvalidJson { .. })
+        ));
+        assert!(matches!(
+            load("  \n").0,
+            Err(LoadCollectionError::InvalidJson { .. })
+        ));
+        assert!(load("[1]").0.is_err());
+    }
+
+    #[test]
+    fn empty_arrays_clear_the_collection() {
+        let db = Db::new_arc();
+        let collection = db.create("items");
+        collection.add(json!({ "id": 1 })).unwrap();
+
+        let loaded = load_seed_value(&collection, None, &json!([]), &mut |_| {}, &|_| {}).unwrap();
+
+        assert_eq!(loaded, 0);
+        assert_eq!(collection.count().unwrap(), 0);
+    }
+
+    #[test]
+    fn items_without_ids_get_generated_ones() {
+        let db = Db::new_arc();
+        let collection = db.create_with_config("users", DbConfig::from(IdType::None, "id"));
+        let ids = IdGenerator::new("id", IdStrategy::Template("usr_{{seq}}".to_string()));
+        let seed = json!([{ "id": "usr_2", "name": "Ada" }, { "name": "Grace" }]);
+
+        let loaded = load_seed_value(&collection, Some(&ids), &seed, &mut |_| {}, &|_| {});
+
+        assert_eq!(loaded.unwrap(), 2);
+        assert!(collection.get("usr_3").unwrap().is_some());
+    }
+}
@@ -0,0 +1,378 @@
+//! Embeddable mock server for integration tests of other Rust projects.
+//!
+//! [`MockServer`] builds the same routes as the CLI from a mock folder, binds
+//! them on the loopback interface and serves them on a background task. The
+//! returned [`MockServerHandle`] reports the bound address and stops the server.
+//! Routes and collection items defined in code are merged with the folder.
+//!
+//! ```no_run
+//! # async fn run() -> std::io::Result<()> {
+//! use rs_mock_server::MockServer;
+//!
+//! let server = MockServer::from_folder("mocks")
+//!     .route("GET", "/health", serde_json::json!({ "status": "ok" }))
+//!     .collection("users")
+//!     .seed(serde_json::json!([{ "id": 1, "name": "Ada" }]))
+//!     .port(0)
+//!     .spawn()
+//!     .await?;
+//! let users_url = format!("{}/users", server.url());
+//! // ... exercise the code under test against `users_url` ...
+//! server.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{io, net::SocketAddr};
+
+use serde_json::Value;
+use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+
+use crate::{
+    app::App,
+    route_builder::config::{Config, ServerConfig},
+    tls::{
+        ClientCertAcceptor, TlsMode, is_https, resolve_client_auth, resolve_tls_mode, rustls_config,
+    },
+};
+
+/// Builder for a mock server running inside the current Tokio runtime.
+#[derive(Debug, Clone)]
+pub struct MockServer {
+    config: Config,
+    quiet: bool,
+    routes: Vec<(String, String, Value)>,
+    seeds: Vec<(String, Value)>,
+}
+
+impl MockServer {
+    /// Creates a builder serving the mock definitions in `folder`.
+    pub fn from_folder(folder: impl Into<String>) -> Self {
+        Self::from_config(Config {
+            server: Some(ServerConfig {
+                folder: Some(folder.into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Creates a builder from a full configuration, as read from `rs-mock-server.toml`.
+    pub fn from_config(config: Config) -> Self {
+        Self {
+            config,
+            quiet: true,
+            routes: vec![],
+            seeds: vec![],
+        }
+    }
+
+    /// Adds a route answering `method` requests on `path` with a JSON body.
+    ///
+    /// It replaces a mock folder route with the same method and path.
+    pub fn route(mut self, method: &str, path: &str, body: Value) -> Self {
+        self.routes
+            .push((method.to_string(), path.to_string(), body));
+        self
+    }
+
+    /// Selects a collection to seed with items defined in code.
+    pub fn collection(self, name: impl Into<String>) -> MockCollection {
+        MockCollection {
+            server: self,
+            name: name.into(),
+        }
+    }
+
+    /// Sets the port to bind. Use `0` to let the operating system pick a free port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.server_config().port = Some(port);
+        self
+    }
+
+    /// Seeds generated data so every spawn serves the same values.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.server_config().seed = Some(seed);
+        self
+    }
+
+    /// Prints route loading messages like the CLI does. Quiet by default.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.quiet = !verbose;
+        self
+    }
+
+    fn server_config(&mut self) -> &mut ServerConfig {
+        self.config.server.get_or_insert_with(ServerConfig::default)
+    }
+
+    /// Builds the routes, binds `127.0.0.1` on the configured port and starts serving.
+    ///
+    /// Fails when the port cannot be bound or the TLS certificate cannot be loaded.
+    pub async fn spawn(self) -> io::Result<MockServerHandle> {
+        let mut app = App::new(self.config);
+        app.quiet = self.quiet;
+        for (method, path, body) in self.routes {
+            app.json_route(&method, &path, body)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        }
+        for (name, items) in self.seeds {
+            app.seed_collection(&name, items)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        }
+
+        let server_config = app.server_config.server.clone().unwrap_or_default();
+        let tls_mode = resolve_tls_mode(&server_config).map_err(io::Error::other)?;
+        let client_auth = resolve_client_auth(&server_config).map_err(io::Error::other)?;
+        let listener = TcpListener::bind(("127.0.0.1", app.get_port())).await?;
+        let address = listener.local_addr()?;
+        app.set_bound_port(address.port());
+        let router = app.build_server_router();
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+
+        let task = match &tls_mode {
+            TlsMode::Disabled => tokio::spawn(async move {
+                let _ = axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_signal.await;
+                })
+                .await;
+                app.finish();
+            }),
+            mode => {
+                let config = rustls_config(mode, client_auth.as_ref())
+                    .await
+                    .map_err(io::Error::other)?;
+                let server = axum_server::from_tcp(listener.into_std()?)?
+                    .acceptor(ClientCertAcceptor::new(config));
+                let handle = axum_server::Handle::new();
+                let shutdown_handle = handle.clone();
+                tokio::spawn(async move {
+                    let _ = shutdown_signal.await;
+                    shutdown_handle.graceful_shutdown(None);
+                });
+                tokio::spawn(async move {
+                    let _ = server
+                        .handle(handle)
+                        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+                        .await;
+                    app.finish();
+                })
+            }
+        };
+
+        Ok(MockServerHandle {
+            address,
+            https: is_https(&tls_mode),
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+}
+
+/// Collection selected by [`MockServer::collection`].
+#[derive(Debug, Clone)]
+pub struct MockCollection {
+    server: MockServer,
+    name: String,
+}
+
+impl MockCollection {
+    /// Replaces the collection items with `items`, a JSON array, once the mock
+    /// folder has been loaded. The collection is created when no mock file
+    /// defines it.
+    pub fn seed(mut self, items: Value) -> MockServer {
+        self.server.seeds.push((self.name, items));
+        self.server
+    }
+}
+
+/// Running mock server started by [`MockServer::spawn`].
+///
+/// Dropping the handle also stops the server, without waiting for it.
+#[derive(Debug)]
+pub struct MockServerHandle {
+    address: SocketAddr,
+    https: bool,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl MockServerHandle {
+    /// Returns the bound address, including the port picked for port `0`.
+    pub fn addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the base URL of the server, such as `http://127.0.0.1:4520`.
+    pub fn url(&self) -> String {
+        let scheme = if self.https { "https" } else { "http" };
+        format!("{}://{}", scheme, self.address)
+    }
+
+    /// Stops accepting connections and waits for in-flight requests to finish.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for MockServerHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    async fn get(address: SocketAddr, path: &str) -> io::Result<String> {
+        let mut stream = TcpStream::connect(address).await?;
+        stream
+            .write_all(
+                format!(
+                    "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                    path
+                )
+                .as_bytes(),
+            )
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn spawn_serves_folder_routes_on_a_free_port() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("users")).unwrap();
+        fs::write(
+            temp_dir.path().join("users/get.json"),
+            r#"[{"id":1,"name":"Ada"}]"#,
+        )
+        .unwrap();
+
+        let server = MockServer::from_folder(temp_dir.path().to_string_lossy())
+            .port(0)
+            .spawn()
+            .await
+            .unwrap();
+
+        assert_ne!(server.addr().port(), 0);
+        assert_eq!(server.url(), format!("http://{}", server.addr()));
+
+        let response = get(server.addr(), "/users").await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(
+            response.ends_with(r#"[{"id":1,"name":"Ada"}]"#),
+            "{}",
+            response
+        );
+
+        let response = get(server.addr(), "/missing").await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+
+        let address = server.addr();
+        server.shutdown().await;
+        assert!(get(address, "/users").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn code_routes_and_seeds_merge_with_folder_routes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("users")).unwrap();
+        fs::write(
+            temp_dir.path().join("users/rest{id-int}.json"),
+            r#"[{"id":1}]"#,
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("health.json"), r#"{"status":"file"}"#).unwrap();
+
+        let server = MockServer::from_folder(temp_dir.path().to_string_lossy())
+            .route("get", "/health", json!({ "status": "code" }))
+            .route("GET", "/version", json!("1.0"))
+            .collection("users")
+            .seed(json!([{ "id": 7, "name": "Ada" }]))
+            .collection("tags")
+            .seed(json!([{ "id": 1, "label": "new" }]))
+            .port(0)
+            .spawn()
+            .await
+            .unwrap();
+
+        let response = get(server.addr(), "/health").await.unwrap();
+        assert!(response.ends_with(r#"{"status":"code"}"#), "{}", response);
+        let response = get(server.addr(), "/version").await.unwrap();
+        assert!(response.ends_with(r#""1.0""#), "{}", response);
+        let response = get(server.addr(), "/users").await.unwrap();
+        assert!(
+            response.ends_with(r#"{"data":[{"id":7,"name":"Ada"}]}"#),
+            "{}",
+            response
+        );
+        let response = get(server.addr(), "/mock-server/collections/tags/download")
+            .await
+            .unwrap();
+        assert!(response.contains(r#""label":"new""#), "{}", response);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn spawn_rejects_invalid_code_routes_and_seeds() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let folder = temp_dir.path().to_string_lossy().into_owned();
+
+        let err = MockServer::from_folder(&folder)
+            .route("FETCH", "/users", json!([]))
+            .port(0)
+            .spawn()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("unknown method `FETCH`"));
+
+        let err = MockServer::from_folder(&folder)
+            .collection("users")
+            .seed(json!({ "id": 1 }))
+            .port(0)
+            .spawn()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must be a JSON array"));
+    }
+
+    #[tokio::test]
+    async fn spawn_fails_when_the_port_is_taken() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let first = MockServer::from_folder(temp_dir.path().to_string_lossy())
+            .port(0)
+            .spawn()
+            .await
+            .unwrap();
+
+        let second = MockServer::from_folder(temp_dir.path().to_string_lossy())
+            .port(first.addr().port())
+            .spawn()
+            .await;
+
+        assert_eq!(second.unwrap_err().kind(), io::ErrorKind::AddrInUse);
+        first.shutdown().await;
+    }
+}
@@ -0,0 +1,403 @@
+//! Placeholder rendering for JSON templates.
+//!
+//! Strings in collection defaults, seed files, and `.json` mock responses may
+//! contain `{{name}}` placeholders that are rendered when the value is used:
+//!
+//! - `{{now}}`, `{{date}}`, `{{timestamp}}`, and `{{uuid}}` for the current time
+//!   and identifiers. The time placeholders take an offset in seconds, minutes,
+//!   hours, days, or weeks, such as `{{now-2d}}` or `{{date+1w}}`, so seeded
+//!   dates stay within date-filtered views.
+//! - `{{faker.<kind>}}` for fake data such as `{{faker.name}}` or
+//!   `{{faker.email}}`, and `{{faker.int 1 100}}` or `{{faker.float 0 1}}` for
+//!   random numbers within an inclusive range.
+//!
+//! `${NAME}` and `${NAME:-default}` are replaced with environment variables,
+//! here and in TOML configuration files, so one mock folder works across
+//! environments. References to unset variables without a default are left
+//! untouched.
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use fake::{
+    Fake,
+    faker::{
+        address::en::{CityName, CountryName, StreetName, ZipCode},
+        company::en::CompanyName,
+        internet::en::{SafeEmail, Username},
+        lorem::en::{Paragraph, Sentence, Word},
+        name::en::{FirstName, LastName, Name},
+        phone_number::en::PhoneNumber,
+    },
+};
+use rand::{Rng, RngCore};
+use serde_json::Value;
+use uuid::Builder;
+
+use crate::random;
+
+/// Renders a `faker.*` placeholder from the shared random source, returning
+/// `None` for unknown kinds or invalid arguments.
+fn render_faker(kind: &str, args: &[&str]) -> Option<Value> {
+    random::current().with_rng(|rng| render_faker_with(kind, args, rng))
+}
+
+fn render_faker_with(kind: &str, args: &[&str], rng: &mut dyn RngCore) -> Option<Value> {
+    let text = |value: String| Some(Value::String(value));
+    match (kind, args) {
+        ("name", []) => text(Name().fake_with_rng(rng)),
+        ("first_name", []) => text(FirstName().fake_with_rng(rng)),
+        ("last_name", []) => text(LastName().fake_with_rng(rng)),
+        ("email", []) => text(SafeEmail().fake_with_rng(rng)),
+        ("username", []) => text(Username().fake_with_rng(rng)),
+        ("phone", []) => text(PhoneNumber().fake_with_rng(rng)),
+        ("company", []) => text(CompanyName().fake_with_rng(rng)),
+        ("street", []) => text(StreetName().fake_with_rng(rng)),
+        ("city", []) => text(CityName().fake_with_rng(rng)),
+        ("country", []) => text(CountryName().fake_with_rng(rng)),
+        ("zip", []) => text(ZipCode().fake_with_rng(rng)),
+        ("word", []) => text(Word().fake_with_rng(rng)),
+        ("sentence", []) => text(Sentence(4..10).fake_with_rng(rng)),
+        ("paragraph", []) => text(Paragraph(2..5).fake_with_rng(rng)),
+        ("uuid", []) => text(
+            Builder::from_random_bytes(rng.random())
+                .into_uuid()
+                .to_string(),
+        ),
+        ("bool", []) => Some(Value::Bool(fake::Faker.fake_with_rng(rng))),
+        ("int", [min, max]) => {
+            let (min, max) = (min.parse::<i64>().ok()?, max.parse::<i64>().ok()?);
+            (min <= max).then(|| Value::from((min..=max).fake_with_rng::<i64, _>(rng)))
+        }
+        ("float", [min, max]) => {
+            let (min, max) = (min.parse::<f64>().ok()?, max.parse::<f64>().ok()?);
+            (min <= max).then(|| Value::from((min..=max).fake_with_rng::<f64, _>(rng)))
+        }
+        _ => None,
+    }
+}
+
+/// Renders a single placeholder, returning `None` for unknown names.
+pub fn render_placeholder(placeholder: &str, now: DateTime<Utc>) -> Option<Value> {
+    let mut parts = placeholder.split_whitespace();
+    let name = parts.next()?;
+    let args = parts.collect::<Vec<_>>();
+
+    if let Some(kind) = name.strip_prefix("faker.") {
+        return render_faker(kind, &args);
+    }
+    if !args.is_empty() {
+        return None;
+    }
+    let (name, now) = match time_offset(name) {
+        Some((name, offset)) => (name, now.checked_add_signed(offset)?),
+        None => (name, now),
+    };
+
+    match name {
+        "now" => Some(Value::String(
+            now.to_rfc3339_opts(SecondsFormat::Millis, true),
+        )),
+        "date" => Some(Value::String(now.format("%Y-%m-%d").to_string())),
+        "timestamp" => Some(Value::from(now.timestamp_millis())),
+        "uuid" => Some(Value::String(random::current().uuid_v4().to_string())),
+        _ => None,
+    }
+}
+
+/// Splits a time placeholder with an offset, such as `now-2d`, into its name
+/// and the offset. Units are `s`, `m`, `h`, `d`, and `w`.
+fn time_offset(placeholder: &str) -> Option<(&str, TimeDelta)> {
+    let position = placeholder.find(['+', '-'])?;
+    let (name, offset) = placeholder.split_at(position);
+    if !matches!(name, "now" | "date" | "timestamp") {
+        return None;
+    }
+
+    let amount = offset[1..].trim();
+    let unit = amount.chars().last()?;
+    let count = amount[..amount.len() - unit.len_utf8()]
+        .parse::<i64>()
+        .ok()?;
+    let count = if offset.starts_with('-') {
+        -count
+    } else {
+        count
+    };
+    let offset = match unit {
+        's' => TimeDelta::try_seconds(count),
+        'm' => TimeDelta::try_minutes(count),
+        'h' => TimeDelta::try_hours(count),
+        'd' => TimeDelta::try_days(count),
+        'w' => TimeDelta::try_weeks(count),
+        _ => None,
+    }?;
+    Some((name, offset))
+}
+
+/// Returns true when a string may contain a placeholder or an environment
+/// variable reference.
+pub fn has_placeholders(text: &str) -> bool {
+    text.contains("{{") || text.contains("${")
+}
+
+/// Replaces `${NAME}` and `${NAME:-default}` with environment variables.
+///
+/// Unset variables take their default, and references to unset variables
+/// without a default are left untouched.
+pub fn interpolate_env(text: &str) -> String {
+    interpolate_env_with(text, &|name| std::env::var(name).ok())
+}
+
+/// Replaces environment variable references like [`interpolate_env`],
+/// resolving names with `lookup`.
+pub fn interpolate_env_with(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let reference = &rest[start + 2..start + end];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+        rendered.push_str(&rest[..start]);
+        match lookup(name).filter(|_| is_env_name(name)) {
+            Some(value) => rendered.push_str(&value),
+            None => match default.filter(|_| is_env_name(name)) {
+                Some(default) => rendered.push_str(default),
+                None => rendered.push_str(&rest[start..start + end + 1]),
+            },
+        }
+        rest = &rest[start + end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+/// Renders placeholders and environment variable references in a configured
+/// value.
+///
+/// A string that is exactly one placeholder takes the placeholder's type, so
+/// `"{{timestamp}}"` becomes a number. Placeholders embedded in longer strings
+/// are substituted as text, and unknown placeholders are left untouched.
+pub fn render_value(value: &Value, now: DateTime<Utc>) -> Value {
+    render_value_with(value, now, &|_| None)
+}
+
+/// Renders placeholders like [`render_value`], resolving names with `variables`
+/// before the built-in placeholders.
+pub fn render_value_with(
+    value: &Value,
+    now: DateTime<Utc>,
+    variables: &dyn Fn(&str) -> Option<Value>,
+) -> Value {
+    let render = |name: &str| variables(name).or_else(|| render_placeholder(name, now));
+    match value {
+        Value::String(text) => {
+            let text = &interpolate_env(text);
+            if let Some(name) = text
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .filter(|name| !name.contains("{{"))
+                && let Some(rendered) = render(name.trim())
+            {
+                return rendered;
+            }
+
+            let mut rendered = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+                let name = rest[start + 2..start + end].trim();
+                rendered.push_str(&rest[..start]);
+                match render(name) {
+                    Some(Value::String(text)) => rendered.push_str(&text),
+                    Some(other) => rendered.push_str(&other.to_string()),
+                    None => rendered.push_str(&rest[start..start + end + 2]),
+                }
+                rest = &rest[start + end + 2..];
+            }
+            rendered.push_str(rest);
+
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_value_with(item, now, variables))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), render_value_with(item, now, variables)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Renders placeholders in a JSON document, leaving text that is not JSON untouched.
+pub fn render_json_text(text: &str) -> String {
+    if !has_placeholders(text) {
+        return text.to_string();
+    }
+
+    match serde_json::from_str::<Value>(text) {
+        Ok(json) => serde_json::to_string_pretty(&render_value(&json, Utc::now()))
+            .unwrap_or_else(|_| text.to_string()),
+        Err(_) => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn placeholders_render_inside_strings_and_nested_values() {
+        let now = DateTime::parse_from_rfc3339("2024-05-06T07:08:09Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            render_value(&json!("report-{{date}}.csv"), now),
+            json!("report-2024-05-06.csv")
+        );
+        assert_eq!(
+            render_value(&json!({ "at": ["{{timestamp}}"] }), now),
+            json!({ "at": [1714979289000i64] })
+        );
+        assert_eq!(
+            render_value(&json!("{{unknown}}"), now),
+            json!("{{unknown}}")
+        );
+        assert_eq!(render_value(&json!(3), now), json!(3));
+    }
+
+    #[test]
+    fn time_placeholders_take_relative_offsets() {
+        let now = DateTime::parse_from_rfc3339("2024-05-06T07:08:09Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            render_value(&json!("{{now-2d}}"), now),
+            "2024-05-04T07:08:09.000Z"
+        );
+        assert_eq!(render_value(&json!("{{date+1w}}"), now), "2024-05-13");
+        assert_eq!(
+            render_value(&json!("{{ timestamp-90s }}"), now),
+            json!(now.timestamp_millis() - 90_000)
+        );
+        assert_eq!(
+            render_value(&json!("from {{date-30m}} to {{date+12h}}"), now),
+            "from 2024-05-06 to 2024-05-06"
+        );
+    }
+
+    #[test]
+    fn faker_placeholders_produce_typed_values() {
+        let now = Utc::now();
+        let rendered = render_value(
+            &json!({
+                "name": "{{faker.name}}",
+                "email": "{{ faker.email }}",
+                "age": "{{faker.int 18 18}}",
+                "score": "{{faker.float 0.5 1}}",
+                "active": "{{faker.bool}}",
+                "id": "{{faker.uuid}}",
+                "label": "user-{{faker.int 7 7}}"
+            }),
+            now,
+        );
+
+        assert!(!rendered["name"].as_str().unwrap().is_empty());
+        assert!(rendered["email"].as_str().unwrap().contains('@'));
+        assert_eq!(rendered["age"], 18);
+        let score = rendered["score"].as_f64().unwrap();
+        assert!((0.5..=1.0).contains(&score));
+        assert!(rendered["active"].is_boolean());
+        assert!(Uuid::parse_str(rendered["id"].as_str().unwrap()).is_ok());
+        assert_eq!(rendered["label"], "user-7");
+    }
+
+    #[test]
+    fn faker_placeholders_follow_the_given_rng() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let render = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            ["name", "email", "uuid", "sentence"]
+                .map(|kind| render_faker_with(kind, &[], &mut rng).unwrap())
+        };
+
+        assert_eq!(render(3), render(3));
+        assert_ne!(render(3), render(4));
+    }
+
+    #[test]
+    fn invalid_faker_placeholders_are_left_untouched() {
+        let now = Utc::now();
+
+        assert_eq!(
+            render_value(&json!("{{faker.unknown}}"), now),
+            "{{faker.unknown}}"
+        );
+        assert_eq!(
+            render_value(&json!("{{faker.int 9 1}}"), now),
+            "{{faker.int 9 1}}"
+        );
+        assert_eq!(
+            render_value(&json!("{{faker.int one}}"), now),
+            "{{faker.int one}}"
+        );
+        assert_eq!(render_value(&json!("{{now 1}}"), now), "{{now 1}}");
+        assert_eq!(render_value(&json!("{{now-2x}}"), now), "{{now-2x}}");
+        assert_eq!(render_value(&json!("{{uuid-1d}}"), now), "{{uuid-1d}}");
+    }
+
+    #[test]
+    fn env_references_take_variables_or_defaults() {
+        let lookup = |name: &str| (name == "TENANT").then(|| "acme".to_string());
+
+        assert_eq!(
+            interpolate_env_with("https://${TENANT}.test/${PATH:-hooks}", &lookup),
+            "https://acme.test/hooks"
+        );
+        assert_eq!(
+            interpolate_env_with("${MISSING} ${1X:-a} ${TENANT", &lookup),
+            "${MISSING} ${1X:-a} ${TENANT"
+        );
+        assert_eq!(interpolate_env_with("${MISSING:-}!", &lookup), "!");
+
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(
+            render_value(&json!({ "path": "${PATH}" }), Utc::now()),
+            json!({ "path": path })
+        );
+    }
+
+    #[test]
+    fn render_json_text_renders_documents_only() {
+        let rendered = render_json_text(r#"{"n":"{{faker.int 3 3}}"}"#);
+        assert_eq!(
+            serde_json::from_str::<Value>(&rendered).unwrap(),
+            json!({ "n": 3 })
+        );
+        assert_eq!(render_json_text("plain {{text"), "plain {{text");
+        assert_eq!(render_json_text(r#"{"a":1}"#), r#"{"a":1}"#);
+    }
+}
@@ -1,12 +1,32 @@
 //! Local HTTPS configuration and certificate handling.
+//!
+//! With `[server.tls] client_ca`, the server also verifies client certificates
+//! (mutual TLS) and attaches the subject of the presented certificate to every
+//! request of the connection as a [`ClientCertificate`] extension.
 
 use std::{
-    fmt, fs,
+    fmt, fs, io,
     path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
 };
 
-use axum_server::tls_rustls::RustlsConfig;
+use axum_server::{
+    accept::Accept,
+    tls_rustls::{RustlsAcceptor, RustlsConfig},
+};
+use http::Request;
 use rcgen::generate_simple_self_signed;
+use rustls::{
+    RootCertStore,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::WebPkiClientVerifier,
+};
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tower::Service;
+use x509_parser::prelude::{AttributeTypeAndValue, FromDer, X509Certificate};
 
 use crate::ServerConfig;
 
@@ -28,6 +48,15 @@ pub enum TlsMode {
     },
 }
 
+/// Client certificate verification, from `[server.tls]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientAuth {
+    /// PEM file of the certificate authorities client certificates chain to.
+    pub ca_path: PathBuf,
+    /// Reject handshakes without a client certificate.
+    pub required: bool,
+}
+
 /// Error returned when TLS configuration cannot be resolved or loaded.
 #[derive(Debug)]
 pub enum TlsError {
@@ -39,6 +68,12 @@ pub enum TlsError {
     CertificateStorage(std::io::Error),
     /// Rustls could not load the certificate pair.
     CertificateLoad(std::io::Error),
+    /// `client_auth` is neither `required` nor `optional`.
+    InvalidClientAuth(String),
+    /// The client certificate authorities could not be loaded.
+    ClientCaLoad(String),
+    /// No authority could be loaded to verify servers.
+    TrustedRootsLoad(String),
 }
 
 impl fmt::Display for TlsError {
@@ -56,6 +91,19 @@ impl fmt::Display for TlsError {
             TlsError::CertificateLoad(err) => {
                 write!(f, "failed to load SSL certificate: {}", err)
             }
+            TlsError::InvalidClientAuth(value) => {
+                write!(
+                    f,
+                    "invalid client_auth `{}`, expected `required` or `optional`",
+                    value
+                )
+            }
+            TlsError::ClientCaLoad(message) => {
+                write!(f, "failed to load client_ca: {}", message)
+            }
+            TlsError::TrustedRootsLoad(message) => {
+                write!(f, "failed to load trusted certificates: {}", message)
+            }
         }
     }
 }
@@ -66,22 +114,255 @@ impl std::error::Error for TlsError {}
 pub fn resolve_tls_mode(config: &ServerConfig) -> Result<TlsMode, TlsError> {
     match explicit_key_pair(config)? {
         Some(mode) => Ok(mode),
-        None if config.ssl.unwrap_or(false) => Ok(TlsMode::Generated),
+        None if config.ssl.unwrap_or(false) || client_ca(config).is_some() => {
+            Ok(TlsMode::Generated)
+        }
         None => Ok(TlsMode::Disabled),
     }
 }
 
+/// Resolves client certificate verification; `None` unless `client_ca` is set.
+pub fn resolve_client_auth(config: &ServerConfig) -> Result<Option<ClientAuth>, TlsError> {
+    let Some(ca_path) = client_ca(config) else {
+        return Ok(None);
+    };
+    let required = match config
+        .tls
+        .as_ref()
+        .and_then(|tls| tls.client_auth.as_deref())
+    {
+        None | Some("required") => true,
+        Some("optional") => false,
+        Some(other) => return Err(TlsError::InvalidClientAuth(other.to_string())),
+    };
+    Ok(Some(ClientAuth {
+        ca_path: PathBuf::from(ca_path),
+        required,
+    }))
+}
+
+fn client_ca(config: &ServerConfig) -> Option<&str> {
+    config.tls.as_ref()?.client_ca.as_deref()
+}
+
 /// Returns true when the resolved TLS mode serves HTTPS.
 pub fn is_https(mode: &TlsMode) -> bool {
     !matches!(mode, TlsMode::Disabled)
 }
 
-/// Builds the Rustls server configuration for an HTTPS mode.
-pub async fn rustls_config(mode: &TlsMode) -> Result<RustlsConfig, TlsError> {
+/// Builds the Rustls server configuration for an HTTPS mode, verifying client
+/// certificates when `client_auth` is given.
+pub async fn rustls_config(
+    mode: &TlsMode,
+    client_auth: Option<&ClientAuth>,
+) -> Result<RustlsConfig, TlsError> {
     let (cert_path, key_path) = certificate_paths(mode)?;
-    RustlsConfig::from_pem_file(cert_path, key_path)
-        .await
-        .map_err(TlsError::CertificateLoad)
+    match client_auth {
+        None => RustlsConfig::from_pem_file(cert_path, key_path)
+            .await
+            .map_err(TlsError::CertificateLoad),
+        Some(client_auth) => {
+            let config = mutual_tls_config(&cert_path, &key_path, client_auth)?;
+            Ok(RustlsConfig::from_config(Arc::new(config)))
+        }
+    }
+}
+
+fn mutual_tls_config(
+    cert_path: &Path,
+    key_path: &Path,
+    client_auth: &ClientAuth,
+) -> Result<rustls::ServerConfig, TlsError> {
+    let ca_error = |err: &dyn fmt::Display| {
+        TlsError::ClientCaLoad(format!("{}: {}", client_auth.ca_path.display(), err))
+    };
+    let mut roots = RootCertStore::empty();
+    for certificate in
+        CertificateDer::pem_file_iter(&client_auth.ca_path).map_err(|err| ca_error(&err))?
+    {
+        roots
+            .add(certificate.map_err(|err| ca_error(&err))?)
+            .map_err(|err| ca_error(&err))?;
+    }
+
+    let verifier = WebPkiClientVerifier::builder(Arc::new(roots));
+    let verifier = if client_auth.required {
+        verifier
+    } else {
+        verifier.allow_unauthenticated()
+    };
+    let verifier = verifier.build().map_err(|err| ca_error(&err))?;
+
+    let load_error = |err: rustls::pki_types::pem::Error| {
+        TlsError::CertificateLoad(io::Error::new(io::ErrorKind::InvalidData, err))
+    };
+    let chain = CertificateDer::pem_file_iter(cert_path)
+        .map_err(load_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(load_error)?;
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(load_error)?;
+
+    let mut config = rustls::ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(chain, key)
+        .map_err(|err| {
+            TlsError::CertificateLoad(io::Error::new(io::ErrorKind::InvalidData, err))
+        })?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Certificate bundles of the system trust store, checked in order after the
+/// `SSL_CERT_FILE` variable.
+const SYSTEM_CA_BUNDLES: [&str; 3] = [
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/cert.pem",
+];
+
+/// Builds the Rustls client configuration used to reach HTTPS backends,
+/// trusting the authorities of `ca`, or of the system trust store.
+pub fn client_tls_config(ca: Option<&Path>) -> Result<rustls::ClientConfig, TlsError> {
+    let bundle = match ca {
+        Some(ca) => ca.to_path_buf(),
+        None => std::env::var_os("SSL_CERT_FILE")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(SYSTEM_CA_BUNDLES.iter().map(PathBuf::from))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                TlsError::TrustedRootsLoad("no system certificate bundle found".to_string())
+            })?,
+    };
+
+    let mut roots = RootCertStore::empty();
+    let certificates = CertificateDer::pem_file_iter(&bundle)
+        .map_err(|err| TlsError::TrustedRootsLoad(format!("{}: {}", bundle.display(), err)))?;
+    roots.add_parsable_certificates(certificates.flatten());
+    if roots.is_empty() {
+        return Err(TlsError::TrustedRootsLoad(format!(
+            "{} holds no certificate",
+            bundle.display()
+        )));
+    }
+
+    Ok(rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
+
+/// The certificate a client presented during the TLS handshake.
+///
+/// Echo routes describe it as `client_cert`, and scripts read it from
+/// `request.client_cert`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientCertificate {
+    /// Distinguished name of the subject, such as `CN=billing, O=Acme`.
+    pub subject: String,
+    pub common_name: Option<String>,
+    pub organization: Option<String>,
+    pub organizational_unit: Option<String>,
+    /// Distinguished name of the issuing authority.
+    pub issuer: String,
+    /// Serial number, as colon-separated hexadecimal bytes.
+    pub serial: String,
+}
+
+impl ClientCertificate {
+    /// Reads the subject details of a DER-encoded certificate.
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, certificate) = X509Certificate::from_der(der).ok()?;
+        let subject = certificate.subject();
+        Some(Self {
+            subject: subject.to_string(),
+            common_name: first_attribute(subject.iter_common_name()),
+            organization: first_attribute(subject.iter_organization()),
+            organizational_unit: first_attribute(subject.iter_organizational_unit()),
+            issuer: certificate.issuer().to_string(),
+            serial: certificate.raw_serial_as_string(),
+        })
+    }
+}
+
+fn first_attribute<'a>(
+    mut attributes: impl Iterator<Item = &'a AttributeTypeAndValue<'a>>,
+) -> Option<String> {
+    attributes
+        .next()
+        .and_then(|attribute| attribute.as_str().ok())
+        .map(String::from)
+}
+
+/// Accepts TLS connections like [`RustlsAcceptor`], and attaches the client
+/// certificate, if any, to every request of the connection.
+#[derive(Clone)]
+pub struct ClientCertAcceptor {
+    inner: RustlsAcceptor,
+}
+
+impl ClientCertAcceptor {
+    pub fn new(config: RustlsConfig) -> Self {
+        Self {
+            inner: RustlsAcceptor::new(config),
+        }
+    }
+}
+
+impl<I, S> Accept<I, S> for ClientCertAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = <RustlsAcceptor as Accept<I, S>>::Stream;
+    type Service = WithClientCertificate<S>;
+    type Future = Pin<Box<dyn Future<Output = io::Result<(Self::Stream, Self::Service)>> + Send>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let accepting = self.inner.accept(stream, service);
+        Box::pin(async move {
+            let (stream, service) = accepting.await?;
+            let certificate = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|chain| chain.first())
+                .and_then(|certificate| ClientCertificate::from_der(certificate));
+            Ok((
+                stream,
+                WithClientCertificate {
+                    inner: service,
+                    certificate,
+                },
+            ))
+        })
+    }
+}
+
+/// Service inserting the client certificate of its connection into requests.
+#[derive(Clone)]
+pub struct WithClientCertificate<S> {
+    inner: S,
+    certificate: Option<ClientCertificate>,
+}
+
+impl<S, B> Service<Request<B>> for WithClientCertificate<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        if let Some(certificate) = &self.certificate {
+            request.extensions_mut().insert(certificate.clone());
+        }
+        self.inner.call(request)
+    }
 }
 
 fn explicit_key_pair(config: &ServerConfig) -> Result<Option<TlsMode>, TlsError> {
@@ -230,6 +511,148 @@ mod tests {
             key_path,
         };
 
-        assert!(rustls_config(&mode).await.is_ok());
+        assert!(rustls_config(&mode, None).await.is_ok());
+    }
+
+    fn tls_config(client_ca: Option<&str>, client_auth: Option<&str>) -> ServerConfig {
+        ServerConfig {
+            tls: Some(crate::TlsConfig {
+                client_ca: client_ca.map(String::from),
+                client_auth: client_auth.map(String::from),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn client_ca_serves_https_and_requires_certificates_by_default() {
+        let config = tls_config(Some("ca.pem"), None);
+        assert_eq!(resolve_tls_mode(&config).unwrap(), TlsMode::Generated);
+        assert_eq!(
+            resolve_client_auth(&config).unwrap(),
+            Some(ClientAuth {
+                ca_path: PathBuf::from("ca.pem"),
+                required: true
+            })
+        );
+
+        let optional = tls_config(Some("ca.pem"), Some("optional"));
+        assert!(!resolve_client_auth(&optional).unwrap().unwrap().required);
+        assert_eq!(resolve_client_auth(&tls_config(None, None)).unwrap(), None);
+        assert!(matches!(
+            resolve_client_auth(&tls_config(Some("ca.pem"), Some("always"))),
+            Err(TlsError::InvalidClientAuth(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_verifies_client_certificates_and_exposes_their_subject() {
+        use axum::{Extension, Router, routing::get};
+        use hyper_util::rt::TokioIo;
+        use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, Issuer, KeyPair};
+        use rustls::pki_types::{PrivatePkcs8KeyDer, ServerName};
+        use tokio_rustls::TlsConnector;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cert_path = dir.path().join("localhost.pem");
+        let key_path = dir.path().join("localhost-key.pem");
+        write_generated_certificate(&cert_path, &key_path).unwrap();
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "Test CA");
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+        let ca_path = dir.path().join("ca.pem");
+        fs::write(&ca_path, ca_cert.pem()).unwrap();
+        let issuer = Issuer::new(ca_params, ca_key);
+
+        let client_key = KeyPair::generate().unwrap();
+        let mut client_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        client_params
+            .distinguished_name
+            .push(DnType::CommonName, "billing");
+        client_params
+            .distinguished_name
+            .push(DnType::OrganizationName, "Acme");
+        let client_cert = client_params.signed_by(&client_key, &issuer).unwrap();
+
+        let mode = TlsMode::Provided {
+            cert_path: cert_path.clone(),
+            key_path,
+        };
+        let client_auth = ClientAuth {
+            ca_path,
+            required: true,
+        };
+        let config = rustls_config(&mode, Some(&client_auth)).await.unwrap();
+        let router = Router::new().route(
+            "/",
+            get(
+                |Extension(certificate): Extension<ClientCertificate>| async move {
+                    format!(
+                        "{} {}",
+                        certificate.common_name.unwrap(),
+                        certificate.organization.unwrap()
+                    )
+                },
+            ),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = axum_server::from_tcp(listener)
+            .unwrap()
+            .acceptor(ClientCertAcceptor::new(config));
+        tokio::spawn(server.serve(router.into_make_service()));
+
+        let mut roots = RootCertStore::empty();
+        for certificate in CertificateDer::pem_file_iter(&cert_path).unwrap() {
+            roots.add(certificate.unwrap()).unwrap();
+        }
+        let roots = Arc::new(roots);
+        let connect = |with_certificate: bool| {
+            let builder =
+                rustls::ClientConfig::builder().with_root_certificates(Arc::clone(&roots));
+            let config = if with_certificate {
+                builder
+                    .with_client_auth_cert(
+                        vec![client_cert.der().clone()],
+                        PrivatePkcs8KeyDer::from(client_key.serialize_der()).into(),
+                    )
+                    .unwrap()
+            } else {
+                builder.with_no_client_auth()
+            };
+            async move {
+                let stream = tokio::net::TcpStream::connect(address).await?;
+                let stream = TlsConnector::from(Arc::new(config))
+                    .connect(ServerName::try_from("localhost").unwrap(), stream)
+                    .await?;
+                let (mut sender, connection) =
+                    hyper::client::conn::http1::handshake(TokioIo::new(stream))
+                        .await
+                        .map_err(io::Error::other)?;
+                tokio::spawn(connection);
+                let request = Request::get("/")
+                    .header("host", "localhost")
+                    .body(axum::body::Body::empty())
+                    .unwrap();
+                let response = sender
+                    .send_request(request)
+                    .await
+                    .map_err(io::Error::other)?;
+                let bytes =
+                    axum::body::to_bytes(axum::body::Body::new(response.into_body()), usize::MAX)
+                        .await
+                        .map_err(io::Error::other)?;
+                Ok::<_, io::Error>(String::from_utf8(bytes.to_vec()).unwrap())
+            }
+        };
+
+        assert_eq!(connect(true).await.unwrap(), "billing Acme");
+        assert!(connect(false).await.is_err());
     }
 }
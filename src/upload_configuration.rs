@@ -1,6 +1,7 @@
 use std::ffi::OsStr;
 
 /// Runtime cleanup policy for one upload directory.
+#[derive(Debug, Clone)]
 pub struct UploadConfiguration {
     /// Directory where uploaded files are stored.
     pub uploads_path: String,
@@ -17,14 +18,16 @@ impl UploadConfiguration {
         }
     }
 
-    /// Removes uploaded files when cleanup is enabled, preserving TOML config files.
-    pub fn clean_upload_folder(&self) {
+    /// Removes uploaded files when cleanup is enabled, preserving TOML config
+    /// files, and returns how many files were deleted.
+    pub fn clean_upload_folder(&self) -> usize {
         use std::fs;
 
         if !self.clean_uploads {
-            return;
+            return 0;
         }
 
+        let mut deleted = 0;
         match fs::read_dir(&self.uploads_path) {
             Ok(entries) => {
                 for entry in entries.flatten() {
@@ -40,6 +43,7 @@ impl UploadConfiguration {
                         if let Err(e) = fs::remove_file(&entry_path) {
                             eprintln!("⚠️ Failed to delete file {}: {}", entry_path.display(), e);
                         } else {
+                            deleted += 1;
                             println!("🗑️ Deleted uploaded file: {}", entry_path.display());
                         }
                     }
@@ -53,6 +57,7 @@ impl UploadConfiguration {
                 );
             }
         }
+        deleted
     }
 }
 
@@ -80,8 +85,9 @@ mod tests {
             .clean_upload_folder();
         assert!(json_path.exists());
 
-        UploadConfiguration::new(temp_dir.path().to_string_lossy().to_string(), true)
+        let deleted = UploadConfiguration::new(temp_dir.path().to_string_lossy().to_string(), true)
             .clean_upload_folder();
+        assert_eq!(deleted, 1);
         assert!(!json_path.exists());
         assert!(toml_path.exists());
     }
@@ -0,0 +1,163 @@
+use std::{path::Path, time::Duration};
+
+use tokio::sync::mpsc::Receiver;
+
+use crate::{ServerConfig, criteria::wildcard_matches};
+
+/// Default quiet period, in milliseconds, after the last file change before a reload.
+pub const DEFAULT_WATCH_DEBOUNCE_MS: u64 = 300;
+
+/// Temporary and editor swap files that never trigger a reload.
+pub const DEFAULT_WATCH_IGNORE: &[&str] = &["*.tmp", "*.swp", "*.swx", "*~", ".#*", "4913"];
+
+/// File watching settings of the hot reload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchSettings {
+    /// Whether file changes restart the server.
+    pub enabled: bool,
+    /// Quiet period after the last change before the server restarts.
+    pub debounce: Duration,
+    /// Glob patterns of paths whose changes are ignored.
+    pub ignore: Vec<String>,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self::from_config(None)
+    }
+}
+
+impl WatchSettings {
+    /// Reads the `watch`, `watch_debounce`, and `watch_ignore` server settings.
+    ///
+    /// Configured patterns are added to [`DEFAULT_WATCH_IGNORE`].
+    pub fn from_config(server: Option<&ServerConfig>) -> Self {
+        let ignore = DEFAULT_WATCH_IGNORE
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .chain(
+                server
+                    .and_then(|server| server.watch_ignore.clone())
+                    .unwrap_or_default(),
+            )
+            .collect();
+        Self {
+            enabled: server.and_then(|server| server.watch).unwrap_or(true),
+            debounce: Duration::from_millis(
+                server
+                    .and_then(|server| server.watch_debounce)
+                    .unwrap_or(DEFAULT_WATCH_DEBOUNCE_MS),
+            ),
+            ignore,
+        }
+    }
+
+    /// Returns whether changes to `path` are ignored.
+    ///
+    /// Patterns without a `/` match the file name, and the others match the
+    /// whole path with `/` separators, also on Windows.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let full_path = path.to_string_lossy();
+        let full_path = if cfg!(windows) {
+            full_path.replace('\\', "/")
+        } else {
+            full_path.into_owned()
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.ignore.iter().any(|pattern| {
+            if pattern.contains('/') {
+                wildcard_matches(&full_path, pattern, '*', '?')
+            } else {
+                wildcard_matches(&file_name, pattern, '*', '?')
+            }
+        })
+    }
+}
+
+/// Waits for a file change, then for a quiet period of `debounce` without
+/// further changes, so a burst of saves restarts the server once.
+///
+/// Never completes once every sender is dropped.
+pub async fn next_change(changes: &mut Receiver<()>, debounce: Duration) {
+    if changes.recv().await.is_none() {
+        return std::future::pending().await;
+    }
+    while let Ok(Some(())) = tokio::time::timeout(debounce, changes.recv()).await {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn settings_read_the_server_config() {
+        assert_eq!(
+            WatchSettings::default(),
+            WatchSettings {
+                enabled: true,
+                debounce: Duration::from_millis(300),
+                ignore: DEFAULT_WATCH_IGNORE.iter().map(|p| p.to_string()).collect(),
+            }
+        );
+
+        let server = ServerConfig {
+            watch: Some(false),
+            watch_debounce: Some(1000),
+            watch_ignore: Some(vec!["*.log".to_string()]),
+            ..Default::default()
+        };
+        let settings = WatchSettings::from_config(Some(&server));
+        assert!(!settings.enabled);
+        assert_eq!(settings.debounce, Duration::from_secs(1));
+        assert_eq!(settings.ignore.last().unwrap(), "*.log");
+    }
+
+    #[test]
+    fn ignore_patterns_match_file_names_or_whole_paths() {
+        let settings = WatchSettings {
+            ignore: vec!["*.tmp".to_string(), "*/{upload}*/*".to_string()],
+            ..Default::default()
+        };
+
+        assert!(settings.is_ignored(Path::new("mocks/users/get.json.tmp")));
+        assert!(WatchSettings::default().is_ignored(Path::new("mocks/users/.get.json.swp")));
+        assert!(settings.is_ignored(Path::new("mocks/{upload}-files/cat.png")));
+        assert!(!settings.is_ignored(Path::new("mocks/{upload}-files")));
+        assert!(!settings.is_ignored(Path::new("mocks/users/get.json")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn ignore_patterns_match_windows_paths() {
+        let settings = WatchSettings {
+            ignore: vec!["*.tmp".to_string(), "*/{upload}*/*".to_string()],
+            ..Default::default()
+        };
+
+        assert!(settings.is_ignored(Path::new(r"C:\mocks\{upload}-files\cat.png")));
+        assert!(settings.is_ignored(Path::new(r"C:\mocks\users\get.json.tmp")));
+        assert!(!settings.is_ignored(Path::new(r"C:\mocks\users\get.json")));
+    }
+
+    #[tokio::test]
+    async fn a_burst_of_changes_settles_once() {
+        let (tx, mut rx) = mpsc::channel(8);
+        tx.send(()).await.unwrap();
+        tx.send(()).await.unwrap();
+
+        let debounce = Duration::from_millis(20);
+        let started = tokio::time::Instant::now();
+        next_change(&mut rx, debounce).await;
+        assert!(started.elapsed() >= debounce);
+        assert!(rx.try_recv().is_err());
+
+        drop(tx);
+        let closed =
+            tokio::time::timeout(Duration::from_millis(50), next_change(&mut rx, debounce));
+        assert!(closed.await.is_err());
+    }
+}